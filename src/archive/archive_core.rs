@@ -0,0 +1,221 @@
+//! # Archive Core Module
+//!
+//! This module archives data discs, such as photo backups or software, that `MakeMKV` has no
+//! video titles for. The whole disc is imaged to an ISO, which holds its contents exactly
+//! as they are on the disc, and can be mounted to get them back.
+//!
+//! ## Overview
+//!
+//! - **`archive_disc`**: Images a disc into `archives/{label}/` with a manifest and
+//!   checksums.
+//!
+//! - **`Manifest`**: What was archived, written alongside the image as `manifest.json`.
+//!
+//! - **`volume_label`**: Reads the label of a disc, used as the default archive name.
+//!
+//! ## Notes
+//!
+//! - Discs are imaged with `ddrescue` when it is installed, which retries sectors that fail
+//!   to read and keeps a map of any it couldn't. Otherwise the drive is copied directly,
+//!   which gives up on the first bad sector.
+//! - Checksums are written to `SHA256SUMS`, so an archive can be checked later with
+//!   `sha256sum -c SHA256SUMS`.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::process::Command;
+
+use crate::history::history_core::now;
+use crate::makemkv::naming::path_safe;
+use crate::{debug, info, trace, warn};
+
+use super::errors::{ArchiveError, Result};
+
+/// The directory archives are saved to, relative to the output directory
+pub const ARCHIVE_DIR: &str = "archives";
+
+/// Data discs use 2048 byte sectors
+const SECTOR_SIZE: &str = "2048";
+
+/// A single file in an archive.
+///
+/// * `path` - The path of the file, relative to the archive directory.
+/// * `size` - The size of the file in bytes.
+/// * `sha256` - The SHA-256 checksum of the file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// What was archived from a disc, saved alongside it as `manifest.json`.
+///
+/// # Fields
+///
+/// * `label` - The name of the archive.
+/// * `drive_number` - The drive the disc was archived from.
+/// * `created` - When the archive was made, in seconds since the unix epoch.
+/// * `method` - How the disc was read, `ddrescue` or `copy`.
+/// * `directory` - Where the archive was saved.
+/// * `files` - The files in the archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    pub label: String,
+    pub drive_number: u8,
+    pub created: u64,
+    pub method: String,
+    #[serde(skip)]
+    pub directory: PathBuf,
+    pub files: Vec<ManifestEntry>,
+}
+
+fn device(drive_number: u8) -> String {
+    crate::makemkv::drive_map::device_path(drive_number)
+}
+
+/// Reads the volume label of the disc in a drive with `blkid`, if it has one.
+pub async fn volume_label(drive_number: u8) -> Option<String> {
+    let output = Command::new("blkid")
+        .args(["-o", "value", "-s", "LABEL", &device(drive_number)])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+
+    let label = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !label.is_empty()).then_some(label)
+}
+
+/// Images the disc in a drive to `archives/{label}/{label}.iso` within the output
+/// directory, then writes its checksums and manifest alongside it. Returns the manifest.
+///
+/// Archiving again with the label of an archive that failed or was cancelled resumes it,
+/// `ddrescue` picks up from its map rather than reading the whole disc again.
+///
+/// # Errors
+///
+/// Returns an `ArchiveError` if an archive with the label already exists, or the disc
+/// can't be read, checksummed or have its manifest written.
+pub async fn archive_disc(drive_number: u8, label: &str, output_dir: &Path) -> Result<Manifest> {
+    let name = path_safe(label);
+    let directory = output_dir.join(ARCHIVE_DIR).join(&name);
+
+    // The manifest is written last, so only finished archives have one
+    if directory.join("manifest.json").exists() {
+        return Err(ArchiveError::AlreadyArchived(name));
+    }
+    tokio::fs::create_dir_all(&directory).await?;
+
+    let image = directory.join(format!("{name}.iso"));
+    let map = directory.join(format!("{name}.map"));
+
+    let method = image_disc(drive_number, &image, &map).await?;
+
+    let mut files = Vec::new();
+    for file in [&image, &map] {
+        if !file.exists() {
+            continue;
+        }
+
+        let file_name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        files.push(ManifestEntry {
+            size: tokio::fs::metadata(file).await?.len(),
+            sha256: sha256(file).await?,
+            path: file_name,
+        });
+    }
+
+    // The same format sha256sum writes, so it can check it
+    let checksums = files
+        .iter()
+        .map(|file| format!("{}  {}", file.sha256, file.path))
+        .collect::<Vec<String>>()
+        .join("\n")
+        + "\n";
+    tokio::fs::write(directory.join("SHA256SUMS"), checksums).await?;
+
+    let manifest = Manifest {
+        label: label.to_string(),
+        drive_number,
+        created: now(),
+        method: method.to_string(),
+        directory,
+        files,
+    };
+    tokio::fs::write(
+        manifest.directory.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .await?;
+
+    info!(
+        "Archived disc {} to {}",
+        drive_number,
+        manifest.directory.display()
+    );
+    Ok(manifest)
+}
+
+/// Images a disc with `ddrescue`, falling back to copying the drive directly if it isn't
+/// installed. Returns the method that was used.
+async fn image_disc(drive_number: u8, image: &Path, map: &Path) -> Result<&'static str> {
+    debug!("Imaging disc {} to {}", drive_number, image.display());
+
+    let result = Command::new("ddrescue")
+        .args(["-b", SECTOR_SIZE, "-r", "3", &device(drive_number)])
+        .arg(image)
+        .arg(map)
+        .kill_on_drop(true)
+        .output()
+        .await;
+
+    match result {
+        Ok(output) if output.status.success() => Ok("ddrescue"),
+        Ok(output) => Err(ArchiveError::ImageError(
+            drive_number,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            warn!(
+                "ddrescue isn't installed, copying disc {} directly",
+                drive_number
+            );
+            tokio::fs::copy(device(drive_number), image)
+                .await
+                .map_err(|e| ArchiveError::ImageError(drive_number, e.to_string()))?;
+            Ok("copy")
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Checksums a file with `sha256sum`
+async fn sha256(file: &Path) -> Result<String> {
+    trace!("Checksumming {}", file.display());
+    let checksum_error =
+        |reason: String| ArchiveError::ChecksumError(file.display().to_string(), reason);
+
+    let output = Command::new("sha256sum")
+        .arg(file)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(checksum_error(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .ok_or_else(|| checksum_error("no checksum in output".to_string()))
+}
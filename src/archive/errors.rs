@@ -0,0 +1,62 @@
+use std::io;
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, ArchiveError>;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Failed to execute command: {0}")]
+    CommandExecutionError(String),
+
+    #[error("An archive named {0} already exists")]
+    AlreadyArchived(String),
+
+    #[error("Failed to image disc {0}: {1}")]
+    ImageError(u8, String),
+
+    #[error("Failed to checksum {0}: {1}")]
+    ChecksumError(String, String),
+
+    #[error("Failed to write manifest: {0}")]
+    ManifestError(String),
+}
+
+impl ArchiveError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            ArchiveError::CommandExecutionError(_) => {
+                tr!("Failed to run a command needed to archive the disc.")
+            }
+            ArchiveError::AlreadyArchived(label) => {
+                tr!(
+                    "An archive named {label} already exists, please pick another label.",
+                    label = label
+                )
+            }
+            ArchiveError::ImageError(drive_number, _) => {
+                tr!(
+                    "Failed to read disc {drive_number}, it may be scratched.",
+                    drive_number = drive_number
+                )
+            }
+            ArchiveError::ChecksumError(_, _) => tr!("Failed to checksum the disc image."),
+            ArchiveError::ManifestError(_) => tr!("Failed to write the archive manifest."),
+        }
+    }
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(error: io::Error) -> Self {
+        ArchiveError::CommandExecutionError(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ArchiveError {
+    fn from(error: serde_json::Error) -> Self {
+        ArchiveError::ManifestError(error.to_string())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod archive_core;
+pub mod errors;
+
+pub use archive_core::{archive_disc, volume_label, Manifest, ManifestEntry};
@@ -0,0 +1,554 @@
+//! # Arr Core Module
+//!
+//! This module integrates with Sonarr and Radarr, when they are configured, so rips line
+//! up with the rest of an *arr stack.
+//!
+//! ## Overview
+//!
+//! - **`ArrClient`**: A connection to the API of a single Sonarr or Radarr instance.
+//!
+//! - **`Arr`**: The configured instances, and whether rips are handed to them to import.
+//!
+//! - **`ARR`**: A globally accessible, thread-safe instance of `Arr`.
+//!
+//! ## Features
+//!
+//! - Titles typed in Discord are resolved to the canonical name of the movie or series.
+//! - Show rips start from the first episode of the season Sonarr has no file for.
+//! - The episodes of a season are listed with their names, so disc titles can be mapped to
+//!   named episodes. Sonarr gets the names from `TheTVDB`, so no TVDB key is needed.
+//! - Once a rip finishes, the import is triggered so Sonarr or Radarr rename the files and
+//!   place them in the library, rather than relying on the naming templates.
+//!
+//! ## Notes
+//!
+//! - Instances are configured with the `SONARR_URL`, `SONARR_API_KEY`, `RADARR_URL` and
+//!   `RADARR_API_KEY` environment variables. Either can be left out.
+//! - The helpers in this module never fail, anything going wrong with an instance is
+//!   logged and the rip carries on as if it wasn't configured.
+//! - Importing moves the files, so the output directory has to be reachable by Sonarr and
+//!   Radarr at the same path.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::makemkv::{Rip, RipType};
+use crate::{debug, info, trace, warn};
+
+use super::errors::{ArrError, Result};
+
+/// How long a request to Sonarr or Radarr can take, the rip modals wait on some of them
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A globally accessible instance of `Arr` for talking to Sonarr and Radarr.
+pub static ARR: LazyLock<Arc<Mutex<Arr>>> = LazyLock::new(|| Arc::new(Mutex::new(Arr::default())));
+
+/// The type of *arr instance.
+///
+/// * `Sonarr` - Manages shows.
+/// * `Radarr` - Manages movies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrKind {
+    Sonarr,
+    Radarr,
+}
+
+impl ArrKind {
+    fn name(self) -> &'static str {
+        match self {
+            ArrKind::Sonarr => "Sonarr",
+            ArrKind::Radarr => "Radarr",
+        }
+    }
+
+    /// The command that imports a finished download from a directory
+    fn scan_command(self) -> &'static str {
+        match self {
+            ArrKind::Sonarr => "DownloadedEpisodesScan",
+            ArrKind::Radarr => "DownloadedMoviesScan",
+        }
+    }
+}
+
+/// A connection to the v3 API of a Sonarr or Radarr instance.
+///
+/// # Fields
+///
+/// * `kind` - Whether the instance is Sonarr or Radarr.
+/// * `url` - The base url of the instance, e.g. `http://localhost:8989`.
+/// * `api_key` - The API key found under Settings > General.
+#[derive(Debug, Clone)]
+pub struct ArrClient {
+    pub kind: ArrKind,
+    pub url: String,
+    api_key: String,
+    http: reqwest::Client,
+}
+
+/// A movie or series returned by a lookup, the runtime is in minutes
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LookupResult {
+    title: String,
+    year: Option<u16>,
+    #[serde(default)]
+    runtime: Option<u32>,
+    #[serde(default)]
+    overview: Option<String>,
+    #[serde(default)]
+    tmdb_id: Option<u64>,
+    #[serde(default)]
+    imdb_id: Option<String>,
+    #[serde(default)]
+    tvdb_id: Option<u64>,
+    #[serde(default)]
+    images: Vec<LookupImage>,
+}
+
+/// Artwork of a movie or series returned by a lookup, such as its poster
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LookupImage {
+    cover_type: String,
+    #[serde(default)]
+    remote_url: Option<String>,
+}
+
+/// What Sonarr or Radarr know about a movie or series, for the metadata written next to
+/// rips.
+///
+/// # Fields
+///
+/// * `title` - The canonical title.
+/// * `year` - The year it was released, or first aired.
+/// * `runtime` - How long it is in minutes, of an episode for series.
+/// * `overview` - A summary of the plot.
+/// * `tmdb_id` - The id of it on `TheMovieDB`.
+/// * `imdb_id` - The id of it on `IMDb`, e.g. `tt0113277`.
+/// * `tvdb_id` - The id of it on `TheTVDB`, for series.
+/// * `poster_url` - Where its poster can be downloaded from.
+/// * `fanart_url` - Where its background art can be downloaded from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArrMetadata {
+    pub title: String,
+    pub year: Option<u16>,
+    pub runtime: Option<u32>,
+    pub overview: Option<String>,
+    pub tmdb_id: Option<u64>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u64>,
+    pub poster_url: Option<String>,
+    pub fanart_url: Option<String>,
+}
+
+impl From<LookupResult> for ArrMetadata {
+    fn from(result: LookupResult) -> Self {
+        let image = |cover_type: &str| {
+            result
+                .images
+                .iter()
+                .find(|image| image.cover_type == cover_type)
+                .and_then(|image| image.remote_url.clone())
+                .filter(|url| url.starts_with("http"))
+        };
+        let (poster_url, fanart_url) = (image("poster"), image("fanart"));
+
+        // Missing values are reported as 0 or empty rather than left out
+        ArrMetadata {
+            title: result.title,
+            year: result.year.filter(|year| *year > 0),
+            runtime: result.runtime.filter(|runtime| *runtime > 0),
+            overview: result
+                .overview
+                .filter(|overview| !overview.trim().is_empty()),
+            tmdb_id: result.tmdb_id.filter(|id| *id > 0),
+            imdb_id: result.imdb_id.filter(|id| !id.is_empty()),
+            tvdb_id: result.tvdb_id.filter(|id| *id > 0),
+            poster_url,
+            fanart_url,
+        }
+    }
+}
+
+/// A series already in Sonarr's library
+#[derive(Debug, Deserialize)]
+struct Series {
+    id: u64,
+    title: String,
+}
+
+/// An episode of a series in Sonarr's library
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SeriesEpisode {
+    season_number: u8,
+    episode_number: u8,
+    has_file: bool,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+impl ArrClient {
+    #[must_use]
+    pub fn new(kind: ArrKind, url: &str, api_key: &str) -> Self {
+        ArrClient {
+            kind,
+            url: url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        trace!("GET {} {} {:?}", self.kind.name(), path, query);
+        let response = self
+            .http
+            .get(format!("{}/api/v3/{}", self.url, path))
+            .header("X-Api-Key", &self.api_key)
+            .query(query)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ArrError::ApiError(
+                self.kind.name().to_string(),
+                response.status().as_u16(),
+            ));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    async fn post(&self, path: &str, body: &serde_json::Value) -> Result<()> {
+        trace!("POST {} {} {}", self.kind.name(), path, body);
+        let response = self
+            .http
+            .post(format!("{}/api/v3/{}", self.url, path))
+            .header("X-Api-Key", &self.api_key)
+            .json(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ArrError::ApiError(
+                self.kind.name().to_string(),
+                response.status().as_u16(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Finds a movie or series by its title, preferring a result from the given year.
+    async fn lookup(&self, title: &str, year: Option<u16>) -> Result<Option<LookupResult>> {
+        let path = match self.kind {
+            ArrKind::Sonarr => "series/lookup",
+            ArrKind::Radarr => "movie/lookup",
+        };
+        let results: Vec<LookupResult> = self.get(path, &[("term", title.to_string())]).await?;
+
+        let result = results
+            .iter()
+            .find(|result| year.is_some() && result.year == year)
+            .or_else(|| results.first());
+
+        Ok(result.cloned())
+    }
+
+    /// Lists the episodes of a season, for a series in Sonarr's library. Series Sonarr
+    /// doesn't have are treated as having no episodes.
+    async fn season_episodes(&self, title: &str, season: u8) -> Result<Vec<SeriesEpisode>> {
+        let series: Vec<Series> = self.get("series", &[]).await?;
+        let Some(series) = series
+            .iter()
+            .find(|series| series.title.eq_ignore_ascii_case(title))
+        else {
+            return Ok(Vec::new());
+        };
+
+        let episodes: Vec<SeriesEpisode> = self
+            .get("episode", &[("seriesId", series.id.to_string())])
+            .await?;
+
+        let mut episodes: Vec<SeriesEpisode> = episodes
+            .into_iter()
+            .filter(|episode| episode.season_number == season)
+            .collect();
+        episodes.sort_by_key(|episode| episode.episode_number);
+        Ok(episodes)
+    }
+
+    /// Finds the first episode of a season without a file, for a series in Sonarr's library
+    async fn next_missing_episode(&self, title: &str, season: u8) -> Result<Option<u8>> {
+        Ok(self
+            .season_episodes(title, season)
+            .await?
+            .iter()
+            .find(|episode| !episode.has_file)
+            .map(|episode| episode.episode_number))
+    }
+
+    /// Asks the instance to import, and move, everything in a directory
+    async fn import(&self, dir: &Path) -> Result<()> {
+        self.post(
+            "command",
+            &json!({
+                "name": self.kind.scan_command(),
+                "path": dir,
+                "importMode": "Move",
+            }),
+        )
+        .await
+    }
+}
+
+/// The configured Sonarr and Radarr instances.
+///
+/// # Fields
+///
+/// * `sonarr` - The Sonarr instance shows are resolved and imported with, if configured.
+/// * `radarr` - The Radarr instance movies are resolved and imported with, if configured.
+/// * `import` - Whether finished rips are handed to Sonarr or Radarr to import.
+///
+/// # Methods
+///
+/// ## `init`
+/// Reads the instances from the environment variables.
+///
+/// ### Parameters
+/// - `import`: Whether finished rips should be imported.
+///
+/// ## `client`
+/// Returns the instance that handles a type of rip, if it is configured.
+#[derive(Debug, Clone, Default)]
+pub struct Arr {
+    pub sonarr: Option<ArrClient>,
+    pub radarr: Option<ArrClient>,
+    pub import: bool,
+}
+
+impl Arr {
+    pub fn init(&mut self, import: bool) {
+        self.sonarr = client_from_env(ArrKind::Sonarr, "SONARR_URL", "SONARR_API_KEY");
+        self.radarr = client_from_env(ArrKind::Radarr, "RADARR_URL", "RADARR_API_KEY");
+        self.import = import;
+
+        if import && self.sonarr.is_none() && self.radarr.is_none() {
+            warn!("Importing rips is enabled, but neither Sonarr nor Radarr is configured");
+        }
+    }
+
+    #[must_use]
+    pub fn client(&self, rip_type: RipType) -> Option<&ArrClient> {
+        match rip_type {
+            RipType::Movie => self.radarr.as_ref(),
+            RipType::Show { .. } => self.sonarr.as_ref(),
+        }
+    }
+}
+
+fn client_from_env(kind: ArrKind, url_var: &str, key_var: &str) -> Option<ArrClient> {
+    match (env::var(url_var), env::var(key_var)) {
+        (Ok(url), Ok(api_key)) => {
+            info!("{} integration enabled at {}", kind.name(), url);
+            Some(ArrClient::new(kind, &url, &api_key))
+        }
+        (Ok(_), Err(_)) => {
+            warn!(
+                "{} is set without {}, {} is disabled",
+                url_var,
+                key_var,
+                kind.name()
+            );
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Resolves the canonical title and year of a movie with Radarr, falling back to what was
+/// typed when Radarr isn't configured or doesn't know the movie.
+pub async fn canonical_movie(title: String, year: Option<u16>) -> (String, Option<u16>) {
+    // The lock isn't held while waiting on the request
+    let Some(radarr) = ARR.lock().await.radarr.clone() else {
+        return (title, year);
+    };
+
+    match radarr.lookup(&title, year).await {
+        Ok(Some(LookupResult {
+            title: canonical,
+            year: found_year,
+            ..
+        })) => {
+            debug!(
+                "Resolved movie {} to {} ({:?})",
+                title, canonical, found_year
+            );
+            (canonical, year.or(found_year))
+        }
+        Ok(None) => {
+            debug!("Radarr has no match for {}", title);
+            (title, year)
+        }
+        Err(e) => {
+            warn!("Failed to look up {} in Radarr: {}", title, e);
+            (title, year)
+        }
+    }
+}
+
+/// The runtime of a movie in minutes according to Radarr, used to pick out the main feature
+/// of a disc. `None` if Radarr isn't configured or doesn't know the movie's runtime.
+pub async fn movie_runtime(title: &str, year: Option<u16>) -> Option<u32> {
+    let radarr = ARR.lock().await.radarr.clone()?;
+
+    match radarr.lookup(title, year).await {
+        // Radarr reports 0 for movies it has no runtime for
+        Ok(result) => result
+            .and_then(|result| result.runtime)
+            .filter(|runtime| *runtime > 0),
+        Err(e) => {
+            warn!(
+                "Failed to look up the runtime of {} in Radarr: {}",
+                title, e
+            );
+            None
+        }
+    }
+}
+
+/// Looks a movie up in Radarr, or a series in Sonarr, for the metadata written next to
+/// rips. `None` if the instance isn't configured or doesn't know it.
+pub async fn lookup_metadata(
+    rip_type: RipType,
+    title: &str,
+    year: Option<u16>,
+) -> Option<ArrMetadata> {
+    let client = ARR.lock().await.client(rip_type).cloned()?;
+
+    match client.lookup(title, year).await {
+        Ok(result) => result.map(ArrMetadata::from),
+        Err(e) => {
+            warn!(
+                "Failed to look up {} in {}: {}",
+                title,
+                client.kind.name(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Resolves the canonical title of a series with Sonarr, falling back to what was typed
+/// when Sonarr isn't configured or doesn't know the series.
+pub async fn canonical_series(title: String) -> String {
+    let Some(sonarr) = ARR.lock().await.sonarr.clone() else {
+        return title;
+    };
+
+    match sonarr.lookup(&title, None).await {
+        Ok(Some(LookupResult {
+            title: canonical, ..
+        })) => {
+            debug!("Resolved series {} to {}", title, canonical);
+            canonical
+        }
+        Ok(None) => {
+            debug!("Sonarr has no match for {}", title);
+            title
+        }
+        Err(e) => {
+            warn!("Failed to look up {} in Sonarr: {}", title, e);
+            title
+        }
+    }
+}
+
+/// The first episode of a season Sonarr has no file for, or `None` if Sonarr isn't
+/// configured, doesn't have the series, or has every episode.
+pub async fn next_missing_episode(title: &str, season: u8) -> Option<u8> {
+    let sonarr = ARR.lock().await.sonarr.clone()?;
+
+    match sonarr.next_missing_episode(title, season).await {
+        Ok(episode) => episode,
+        Err(e) => {
+            warn!(
+                "Failed to read the episodes of {} from Sonarr: {}",
+                title, e
+            );
+            None
+        }
+    }
+}
+
+/// The episodes of a season with their names, in order, or an empty list if Sonarr isn't
+/// configured or doesn't have the series. Episodes Sonarr has no name for yet are left out.
+pub async fn episode_titles(title: &str, season: u8) -> Vec<(u8, String)> {
+    let Some(sonarr) = ARR.lock().await.sonarr.clone() else {
+        return Vec::new();
+    };
+
+    match sonarr.season_episodes(title, season).await {
+        Ok(episodes) => episodes
+            .into_iter()
+            .filter_map(|episode| {
+                let name = episode.title.filter(|name| !name.trim().is_empty())?;
+                Some((episode.episode_number, name))
+            })
+            .collect(),
+        Err(e) => {
+            warn!(
+                "Failed to read the episodes of {} from Sonarr: {}",
+                title, e
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Hands the files of a finished rip to Sonarr or Radarr to import, if importing is
+/// enabled. Each rip is saved to its own directory, which is what gets imported.
+pub async fn import_rip(rip: &Rip, files: &[PathBuf]) {
+    // Radarr would take an extra on its own for the movie, it is left in the movie's folder
+    if rip.extra.is_some() {
+        return;
+    }
+
+    let client = {
+        let arr = ARR.lock().await;
+        if !arr.import {
+            return;
+        }
+        arr.client(rip.rip_type).cloned()
+    };
+
+    let Some(client) = client else {
+        return;
+    };
+
+    let Some(dir) = files.first().and_then(|file| file.parent()) else {
+        return;
+    };
+
+    match client.import(dir).await {
+        Ok(()) => info!("Asked {} to import {}", client.kind.name(), dir.display()),
+        Err(e) => warn!(
+            "Failed to import {} with {}: {}",
+            dir.display(),
+            client.kind.name(),
+            e
+        ),
+    }
+}
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, ArrError>;
+
+#[derive(Debug, Error)]
+pub enum ArrError {
+    #[error("Request failed: {0}")]
+    RequestError(String),
+
+    #[error("{0} responded with status {1}")]
+    ApiError(String, u16),
+}
+
+impl From<reqwest::Error> for ArrError {
+    fn from(error: reqwest::Error) -> Self {
+        ArrError::RequestError(error.to_string())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod arr_core;
+pub mod errors;
+
+pub use arr_core::{
+    canonical_movie, canonical_series, episode_titles, import_rip, lookup_metadata, movie_runtime,
+    next_missing_episode, ArrMetadata, ARR,
+};
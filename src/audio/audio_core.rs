@@ -0,0 +1,353 @@
+//! # Audio Core Module
+//!
+//! This module rips audio CDs, which `MakeMKV` can't read, into a `music/` library of FLAC
+//! files tagged with the album's details.
+//!
+//! ## Overview
+//!
+//! - **`Toc`**: The table of contents of an audio CD, as read by `cdparanoia`.
+//!
+//! - **`Album`**: The artist, title and track names of a disc, from `MusicBrainz` or typed
+//!   in by hand.
+//!
+//! - **`rip_track`**: Reads a single track with `cdparanoia` and encodes it with `flac`.
+//!
+//! ## Notes
+//!
+//! - Tracks are saved to `music/{artist}/{album} ({year})/{track:02} - {title}.flac`,
+//!   relative to the output directory.
+//! - `abcde` isn't used, since it does its own lookups and naming and would have to be
+//!   configured to match.
+//! - Audio rips go through the queue like any other rip, but aren't recorded in the rip
+//!   history, which only tracks `MakeMKV` rips.
+
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+use crate::makemkv::makemkv_helpers::TEMP_DIR_PREFIX;
+use crate::makemkv::naming::path_safe;
+use crate::{debug, info, trace};
+
+use super::errors::{AudioError, Result};
+
+/// The directory music is saved to, relative to the output directory
+pub const MUSIC_DIR: &str = "music";
+
+/// Every disc has two seconds of lead-in before the first track, which `MusicBrainz`
+/// counts in its offsets but `cdparanoia` doesn't
+const LEAD_IN_SECTORS: u32 = 150;
+
+/// A single track in the table of contents of an audio CD.
+///
+/// * `number` - The number of the track, starting from 1.
+/// * `start` - The sector the track starts at.
+/// * `sectors` - The length of the track in sectors, 75 to a second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocTrack {
+    pub number: u8,
+    pub start: u32,
+    pub sectors: u32,
+}
+
+/// The table of contents of an audio CD.
+///
+/// # Fields
+///
+/// * `tracks` - The audio tracks on the disc, in order.
+///
+/// # Methods
+///
+/// ## `parse`
+/// Reads the table of contents from the output of `cdparanoia -Q`, `None` if it has no
+/// audio tracks.
+///
+/// ## `musicbrainz_toc`
+/// Formats the table of contents the way the `MusicBrainz` disc id lookup expects it,
+/// `{first} {last} {lead out} {offsets...}` joined with `+`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toc {
+    pub tracks: Vec<TocTrack>,
+}
+
+impl Toc {
+    #[must_use]
+    pub fn parse(output: &str) -> Option<Self> {
+        // Track lines look like ' 1.    16407 [03:38.57]        0 [00:00.00]    no   no  2'
+        let tracks: Vec<TocTrack> = output
+            .lines()
+            .filter_map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                Some(TocTrack {
+                    number: columns.first()?.strip_suffix('.')?.parse().ok()?,
+                    sectors: columns.get(1)?.parse().ok()?,
+                    start: columns.get(3)?.parse().ok()?,
+                })
+            })
+            .collect();
+
+        (!tracks.is_empty()).then_some(Toc { tracks })
+    }
+
+    #[must_use]
+    pub fn musicbrainz_toc(&self) -> String {
+        let (Some(first), Some(last)) = (self.tracks.first(), self.tracks.last()) else {
+            return String::new();
+        };
+
+        let lead_out = last.start + last.sectors + LEAD_IN_SECTORS;
+
+        [first.number.into(), last.number.into(), lead_out]
+            .into_iter()
+            .chain(
+                self.tracks
+                    .iter()
+                    .map(|track| track.start + LEAD_IN_SECTORS),
+            )
+            .map(|value: u32| value.to_string())
+            .collect::<Vec<String>>()
+            .join("+")
+    }
+}
+
+/// The details an audio CD is tagged and named with.
+///
+/// # Fields
+///
+/// * `artist` - The artist credited for the album.
+/// * `title` - The title of the album.
+/// * `year` - The year the album was released, if known.
+/// * `release_id` - The `MusicBrainz` id of the release, if it was found there.
+/// * `tracks` - The title of each track, in order.
+///
+/// # Methods
+///
+/// ## `unknown`
+/// Placeholder details for a disc that wasn't found on `MusicBrainz`, with tracks named
+/// `Track 1`, `Track 2` and so on.
+///
+/// ## `track_title`
+/// The title of a track, falling back to `Track {number}`.
+///
+/// ## `track_path`
+/// The path a track is saved to, relative to the output directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Album {
+    pub artist: String,
+    pub title: String,
+    pub year: Option<u16>,
+    pub release_id: Option<String>,
+    pub tracks: Vec<String>,
+}
+
+impl Album {
+    #[must_use]
+    pub fn unknown(toc: &Toc) -> Self {
+        Album {
+            artist: "Unknown Artist".to_string(),
+            title: "Unknown Album".to_string(),
+            year: None,
+            release_id: None,
+            tracks: toc
+                .tracks
+                .iter()
+                .map(|track| format!("Track {}", track.number))
+                .collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn track_title(&self, number: u8) -> String {
+        self.tracks
+            .get(usize::from(number).saturating_sub(1))
+            .cloned()
+            .unwrap_or_else(|| format!("Track {number}"))
+    }
+
+    #[must_use]
+    pub fn track_path(&self, number: u8) -> PathBuf {
+        let album = match self.year {
+            Some(year) => format!("{} ({})", self.title, year),
+            None => self.title.clone(),
+        };
+
+        PathBuf::from(MUSIC_DIR)
+            .join(path_safe(&self.artist))
+            .join(path_safe(&album))
+            .join(format!(
+                "{:02} - {}.flac",
+                number,
+                path_safe(&self.track_title(number))
+            ))
+    }
+}
+
+fn device(drive_number: u8) -> String {
+    crate::makemkv::drive_map::device_path(drive_number)
+}
+
+/// Reads the table of contents of the disc in a drive with `cdparanoia -Q`.
+///
+/// # Errors
+///
+/// Returns an `AudioError` if `cdparanoia` can't be run, or the disc has no audio tracks.
+pub async fn read_toc(drive_number: u8) -> Result<Toc> {
+    trace!("Reading table of contents of disc {}", drive_number);
+    let output = Command::new("cdparanoia")
+        .args(["-Q", "-d", &device(drive_number)])
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    // The table is printed to stderr, along with the version banner
+    let toc = String::from_utf8_lossy(&output.stderr);
+    trace!("cdparanoia output: {}", toc);
+
+    match Toc::parse(&toc) {
+        Some(toc) if output.status.success() => Ok(toc),
+        _ => Err(AudioError::NotAnAudioDisc(drive_number)),
+    }
+}
+
+/// Whether the disc in a drive has audio tracks. Any failure to read it counts as not.
+pub async fn is_audio_disc(drive_number: u8) -> bool {
+    read_toc(drive_number).await.is_ok()
+}
+
+/// Reads a track with `cdparanoia` and encodes it to FLAC with `flac`, tagged with the
+/// details of the album. Returns the path the track was saved to.
+///
+/// The WAV is only kept in a temporary directory within the output directory, so the
+/// library index skips it and it is cleaned up even if encoding fails.
+///
+/// # Errors
+///
+/// Returns an `AudioError` if either command can't be run, or fails.
+pub async fn rip_track(
+    drive_number: u8,
+    album: &Album,
+    number: u8,
+    output_dir: &Path,
+) -> Result<PathBuf> {
+    let temp_dir = tempfile::Builder::new()
+        .prefix(TEMP_DIR_PREFIX)
+        .tempdir_in(output_dir)?;
+    let wav = temp_dir.path().join(format!("track{number:02}.wav"));
+
+    debug!("Reading track {} of disc {}", number, drive_number);
+    let output = Command::new("cdparanoia")
+        .args(["-q", "-d", &device(drive_number), "-w", &number.to_string()])
+        .arg(&wav)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(AudioError::ReadError(
+            number,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let destination = output_dir.join(album.track_path(number));
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut tags = vec![
+        format!("ARTIST={}", album.artist),
+        format!("ALBUMARTIST={}", album.artist),
+        format!("ALBUM={}", album.title),
+        format!("TITLE={}", album.track_title(number)),
+        format!("TRACKNUMBER={number}"),
+        format!("TRACKTOTAL={}", album.tracks.len()),
+    ];
+    if let Some(year) = album.year {
+        tags.push(format!("DATE={year}"));
+    }
+    if let Some(release_id) = &album.release_id {
+        tags.push(format!("MUSICBRAINZ_ALBUMID={release_id}"));
+    }
+
+    debug!("Encoding track {} to {}", number, destination.display());
+    let output = Command::new("flac")
+        .args(["--best", "--silent", "--force", "-o"])
+        .arg(&destination)
+        .args(tags.iter().map(|tag| format!("--tag={tag}")))
+        .arg(&wav)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(AudioError::EncodeError(
+            number,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    info!("Ripped track {} to {}", number, destination.display());
+    Ok(destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CDPARANOIA_OUTPUT: &str = "\
+cdparanoia III release 10.2 (September 11, 2008)
+
+Table of contents (audio tracks only):
+track        length               begin        copy pre ch
+===========================================================
+  1.    16407 [03:38.57]        0 [00:00.00]    no   no  2
+  2.    20680 [04:35.55]    16407 [03:38.57]    no   no  2
+  3.    18133 [04:01.58]    37087 [08:14.37]    no   no  2
+TOTAL   55220 [12:16.20]    (audio only)
+";
+
+    #[test]
+    fn parses_cdparanoia_toc() {
+        let toc = Toc::parse(CDPARANOIA_OUTPUT).unwrap();
+        assert_eq!(toc.tracks.len(), 3);
+        assert_eq!(
+            toc.tracks[1],
+            TocTrack {
+                number: 2,
+                start: 16407,
+                sectors: 20680
+            }
+        );
+    }
+
+    #[test]
+    fn no_tracks_is_not_a_toc() {
+        assert_eq!(Toc::parse("cdparanoia III release 10.2\n"), None);
+    }
+
+    #[test]
+    fn musicbrainz_toc_counts_the_lead_in() {
+        let toc = Toc::parse(CDPARANOIA_OUTPUT).unwrap();
+        assert_eq!(toc.musicbrainz_toc(), "1+3+55370+150+16557+37237");
+    }
+
+    #[test]
+    fn track_paths_are_file_name_safe() {
+        let album = Album {
+            artist: "AC/DC".to_string(),
+            title: "Back in Black".to_string(),
+            year: Some(1980),
+            release_id: None,
+            tracks: vec!["Hells Bells".to_string()],
+        };
+
+        assert_eq!(
+            album.track_path(1),
+            PathBuf::from("music/AC-DC/Back in Black (1980)/01 - Hells Bells.flac")
+        );
+        assert_eq!(
+            album.track_path(2),
+            PathBuf::from("music/AC-DC/Back in Black (1980)/02 - Track 2.flac")
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use std::io;
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, AudioError>;
+
+#[derive(Debug, Error)]
+pub enum AudioError {
+    #[error("Failed to execute command: {0}")]
+    CommandExecutionError(String),
+
+    #[error("No audio tracks found on disc {0}")]
+    NotAnAudioDisc(u8),
+
+    #[error("Failed to read track {0}: {1}")]
+    ReadError(u8, String),
+
+    #[error("Failed to encode track {0}: {1}")]
+    EncodeError(u8, String),
+
+    #[error("MusicBrainz lookup failed: {0}")]
+    LookupError(String),
+}
+
+impl AudioError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            AudioError::CommandExecutionError(_) => {
+                tr!("Failed to run cdparanoia or flac, make sure both are installed.")
+            }
+            AudioError::NotAnAudioDisc(drive_number) => {
+                tr!(
+                    "Disc {drive_number} doesn't have any audio tracks.",
+                    drive_number = drive_number
+                )
+            }
+            AudioError::ReadError(track, _) => {
+                tr!(
+                    "Failed to read track {track}, the disc may be scratched.",
+                    track = track
+                )
+            }
+            AudioError::EncodeError(track, _) => {
+                tr!("Failed to encode track {track}.", track = track)
+            }
+            AudioError::LookupError(_) => tr!("Failed to look the disc up on MusicBrainz."),
+        }
+    }
+}
+
+impl From<io::Error> for AudioError {
+    fn from(error: io::Error) -> Self {
+        AudioError::CommandExecutionError(error.to_string())
+    }
+}
+
+impl From<reqwest::Error> for AudioError {
+    fn from(error: reqwest::Error) -> Self {
+        AudioError::LookupError(error.to_string())
+    }
+}
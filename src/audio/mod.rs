@@ -0,0 +1,6 @@
+pub mod audio_core;
+pub mod errors;
+pub mod musicbrainz;
+
+pub use audio_core::{is_audio_disc, read_toc, rip_track, Album, Toc, TocTrack};
+pub use musicbrainz::lookup_album;
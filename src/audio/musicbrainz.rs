@@ -0,0 +1,132 @@
+//! # `MusicBrainz` Lookups
+//!
+//! Audio CDs don't carry their own track names, so they are looked up on `MusicBrainz` by
+//! their table of contents. The fuzzy `toc` lookup is used rather than the disc id, which
+//! saves hashing the table of contents and still finds discs with slightly different
+//! pressings.
+//!
+//! `MusicBrainz` asks every client to identify itself with a user agent, and to keep to
+//! around one request a second, which a rip at a time is well within.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{debug, trace};
+
+use super::audio_core::{Album, Toc};
+use super::errors::{AudioError, Result};
+
+const MUSICBRAINZ_URL: &str = "https://musicbrainz.org/ws/2";
+
+/// How long a lookup can take, the audio rip waits on it
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+const USER_AGENT: &str = concat!(
+    "cord-ripper/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/qmusillo/cord-ripper )"
+);
+
+#[derive(Debug, Deserialize)]
+struct DiscLookup {
+    #[serde(default)]
+    releases: Vec<Release>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    id: String,
+    title: String,
+    date: Option<String>,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<ArtistCredit>,
+    #[serde(default)]
+    media: Vec<Medium>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtistCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Medium {
+    #[serde(default)]
+    tracks: Vec<Track>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Track {
+    title: String,
+}
+
+/// Looks an audio CD up on `MusicBrainz` by its table of contents. Returns `None` if no
+/// release has a disc with the same number of tracks.
+///
+/// # Errors
+///
+/// Returns an `AudioError` if `MusicBrainz` can't be reached or responds with an error.
+pub async fn lookup_album(toc: &Toc) -> Result<Option<Album>> {
+    let http = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+
+    trace!("Looking up toc {} on MusicBrainz", toc.musicbrainz_toc());
+    let response = http
+        .get(format!("{MUSICBRAINZ_URL}/discid/-"))
+        .query(&[
+            ("toc", toc.musicbrainz_toc().as_str()),
+            ("inc", "recordings artist-credits"),
+            ("fmt", "json"),
+        ])
+        .send()
+        .await?;
+
+    // Nothing matching the table of contents
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(AudioError::LookupError(format!(
+            "MusicBrainz responded with status {}",
+            response.status().as_u16()
+        )));
+    }
+
+    let lookup: DiscLookup = response.json().await?;
+
+    // A release can have several discs, the one with this disc's track count is the match
+    let album = lookup.releases.into_iter().find_map(|release| {
+        let medium = release
+            .media
+            .into_iter()
+            .find(|medium| medium.tracks.len() == toc.tracks.len())?;
+
+        Some(Album {
+            artist: release
+                .artist_credit
+                .iter()
+                .map(|credit| credit.name.clone() + &credit.joinphrase)
+                .collect(),
+            title: release.title,
+            year: release
+                .date
+                .as_deref()
+                .and_then(|date| date.get(..4))
+                .and_then(|year| year.parse().ok()),
+            release_id: Some(release.id),
+            tracks: medium.tracks.into_iter().map(|track| track.title).collect(),
+        })
+    });
+
+    match &album {
+        Some(album) => debug!("Found {} by {} on MusicBrainz", album.title, album.artist),
+        None => debug!("No release on MusicBrainz matches the disc"),
+    }
+
+    Ok(album)
+}
@@ -0,0 +1,296 @@
+//! # Audit Core Module
+//!
+//! This module keeps a record of who asked the bot to do what, so questions such as "who
+//! ripped this?" or "who cancelled my rip?" can be answered after the fact.
+//!
+//! ## Overview
+//!
+//! - **`AuditEntry`**: A single action, the Discord user who took it, the command it came
+//!   from, its parameters and how it turned out.
+//!
+//! - **`AuditFilter`**: Narrows the log down to a user and a time range.
+//!
+//! - **`AuditLog`**: The log itself, stored in the data directory with one JSON entry per
+//!   line.
+//!
+//! - **`AUDIT_LOG`**: A globally accessible, thread-safe instance of `AuditLog`.
+//!
+//! ## Notes
+//!
+//! - Entries are only ever appended, never rewritten, so the log can't lose old entries to
+//!   a crash partway through a save. Lines that can't be parsed are skipped when reading.
+//! - Rips are recorded once they end, with whether they finished, failed or were
+//!   cancelled, and whether the disc was set to be ejected after them. Pressing cancel is
+//!   recorded on its own too, since it isn't always whoever started the rip.
+//! - Times are stored in seconds since the unix epoch, the dates given to filter by are
+//!   read as UTC.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{debug, info, trace, warn};
+
+use super::errors::{AuditError, Result};
+
+/// The name of the audit log within the data directory
+const AUDIT_FILE: &str = "audit.jsonl";
+/// The number of seconds in a day, for filtering by date
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// A globally accessible instance of `AuditLog` for recording who did what.
+pub static AUDIT_LOG: LazyLock<Mutex<AuditLog>> = LazyLock::new(Mutex::default);
+
+/// What was done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditAction {
+    Rip,
+    Cancel,
+    Delete,
+}
+
+/// How an action turned out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditOutcome {
+    Succeeded,
+    Failed(String),
+    Cancelled,
+}
+
+/// A single action someone took.
+///
+/// # Fields
+///
+/// * `time` - When the action ended, in seconds since the unix epoch.
+/// * `user_id` - The Discord id of the user who took the action.
+/// * `user_name` - The Discord name of the user at the time, ids are what filters use.
+/// * `command` - The command the action came from, e.g. `rip` or `delete_rip`.
+/// * `action` - What was done.
+/// * `parameters` - What the action was done with, such as the title and drive of a rip.
+/// * `outcome` - How the action turned out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: u64,
+    pub user_id: u64,
+    pub user_name: String,
+    pub command: String,
+    pub action: AuditAction,
+    pub parameters: String,
+    pub outcome: AuditOutcome,
+}
+
+/// Which entries to read from the log.
+///
+/// # Fields
+///
+/// * `user_id` - Only entries of this user, `None` for every user.
+/// * `from` - Only entries from this time on, in seconds since the unix epoch.
+/// * `until` - Only entries before this time, in seconds since the unix epoch.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuditFilter {
+    pub user_id: Option<u64>,
+    pub from: Option<u64>,
+    pub until: Option<u64>,
+}
+
+impl AuditFilter {
+    /// Whether an entry passes the filter
+    #[must_use]
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.user_id.is_none_or(|user_id| entry.user_id == user_id)
+            && self.from.is_none_or(|from| entry.time >= from)
+            && self.until.is_none_or(|until| entry.time < until)
+    }
+}
+
+/// The record of every action taken through the bot.
+///
+/// # Fields
+///
+/// * `path` - Where the log is written, empty until `init` has been called.
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// Points the audit log at the data directory, which has already been created by the
+    /// history. The log is created with its first entry.
+    pub fn init(&mut self, data_dir: &Path) {
+        self.path = data_dir.join(AUDIT_FILE);
+        info!("Audit log initialized successfully!");
+    }
+
+    /// Appends an entry to the log.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `AuditError` if the log can't be written to.
+    pub fn record(&self, entry: &AuditEntry) -> Result<()> {
+        // The audit log is disabled until init has been called
+        if self.path.as_os_str().is_empty() {
+            warn!("Audit log has not been initialized, not recording");
+            return Ok(());
+        }
+
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+
+        trace!("Recorded audit entry: {:?}", entry);
+        Ok(())
+    }
+
+    /// The entries that pass a filter, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `AuditError` if the log exists but can't be read.
+    pub fn entries(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file =
+            std::fs::File::open(&self.path).map_err(|e| AuditError::ReadError(e.to_string()))?;
+
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| AuditError::ReadError(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // A line cut off by a crash shouldn't hide the rest of the log
+            match serde_json::from_str::<AuditEntry>(&line) {
+                Ok(entry) if filter.matches(&entry) => entries.push(entry),
+                Ok(_) => {}
+                Err(e) => debug!("Skipping unreadable audit entry: {}", e),
+            }
+        }
+
+        entries.reverse();
+        Ok(entries)
+    }
+}
+
+/// Reads a `YYYY-MM-DD` date as the start of that day in UTC, in seconds since the unix
+/// epoch. `None` if it isn't a valid date, or is before 1970.
+#[must_use]
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+
+    // Days since the epoch of a civil date, counting years from March so leap days
+    // fall at the end of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days).ok().map(|days| days * SECONDS_PER_DAY)
+}
+
+/// The end of the day a `YYYY-MM-DD` date starts, so a range can include its last day
+#[must_use]
+pub fn parse_end_date(date: &str) -> Option<u64> {
+    parse_date(date).map(|start| start + SECONDS_PER_DAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user_id: u64, time: u64) -> AuditEntry {
+        AuditEntry {
+            time,
+            user_id,
+            user_name: format!("user{user_id}"),
+            command: "rip".to_string(),
+            action: AuditAction::Rip,
+            parameters: "Movie".to_string(),
+            outcome: AuditOutcome::Succeeded,
+        }
+    }
+
+    #[test]
+    fn parses_dates_as_utc_midnight() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-02-29"), Some(1_709_164_800));
+        assert_eq!(parse_end_date("2024-02-29"), Some(1_709_251_200));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("1969-12-31"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn filters_by_user_and_time() {
+        let filter = AuditFilter {
+            user_id: Some(1),
+            from: Some(100),
+            until: Some(200),
+        };
+        assert!(filter.matches(&entry(1, 100)));
+        assert!(!filter.matches(&entry(1, 200)));
+        assert!(!filter.matches(&entry(1, 99)));
+        assert!(!filter.matches(&entry(2, 150)));
+        assert!(AuditFilter::default().matches(&entry(2, 0)));
+    }
+
+    #[test]
+    fn reads_back_recorded_entries_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::default();
+        log.init(dir.path());
+
+        log.record(&entry(1, 10)).unwrap();
+        log.record(&entry(2, 20)).unwrap();
+        log.record(&entry(1, 30)).unwrap();
+        // A partly written line is skipped
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(AUDIT_FILE))
+            .unwrap()
+            .write_all(b"{\"time\":")
+            .unwrap();
+
+        let filter = AuditFilter {
+            user_id: Some(1),
+            ..AuditFilter::default()
+        };
+        let times: Vec<u64> = log
+            .entries(&filter)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.time)
+            .collect();
+        assert_eq!(times, vec![30, 10]);
+    }
+}
@@ -0,0 +1,25 @@
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, AuditError>;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("Failed to read audit log: {0}")]
+    ReadError(String),
+
+    #[error("Failed to write audit log: {0}")]
+    WriteError(String),
+}
+
+impl From<io::Error> for AuditError {
+    fn from(error: io::Error) -> Self {
+        AuditError::WriteError(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AuditError {
+    fn from(error: serde_json::Error) -> Self {
+        AuditError::WriteError(error.to_string())
+    }
+}
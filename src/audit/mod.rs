@@ -0,0 +1,4 @@
+pub mod audit_core;
+pub mod errors;
+
+pub use audit_core::{AuditAction, AuditEntry, AuditFilter, AuditOutcome, AUDIT_LOG};
@@ -0,0 +1,267 @@
+//! # Barcode Lookups
+//!
+//! Plenty of discs are labelled something useless, like `LOGICAL_VOLUME_ID` or `DVD_VIDEO`,
+//! which says nothing about what is on them. The barcode on the case does, so it can be
+//! typed in, or scanned with a barcode scanner that types it, and looked up on
+//! `UPCitemdb` to find the title, year and edition of the disc.
+//!
+//! ## Overview
+//!
+//! - **`normalize_barcode`**: Checks a UPC or EAN barcode by its check digit.
+//!
+//! - **`lookup_barcode`**: Looks a barcode up on `UPCitemdb`.
+//!
+//! - **`Product`**: What a barcode was found to be.
+//!
+//! ## Notes
+//!
+//! - The free `UPCitemdb` endpoint is used, which needs no account but only allows around
+//!   100 lookups a day. That is plenty for looking up discs one at a time.
+//! - Product names are written for shops, such as `Heat (Blu-ray) (1995)`, so the format and
+//!   edition are split off the title to get a name fit for the library.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{debug, trace};
+
+use super::errors::{BarcodeError, Result};
+
+const UPCITEMDB_URL: &str = "https://api.upcitemdb.com/prod/trial/lookup";
+
+/// How long a lookup can take, the rip waits on it
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Words shops put after a title to say what format it is on
+const FORMAT_WORDS: [&str; 7] = [
+    "blu-ray", "bluray", "blu ray", "dvd", "4k", "uhd", "ultra hd",
+];
+
+/// Words that say the part of a name after a dash is an edition rather than part of the title
+const EDITION_WORDS: [&str; 6] = [
+    "edition",
+    "cut",
+    "collection",
+    "anniversary",
+    "steelbook",
+    "remaster",
+];
+
+/// What a barcode was found to be.
+///
+/// # Fields
+///
+/// * `title` - The title, with the format and edition taken off.
+/// * `year` - The year it was released, if the name has one.
+/// * `edition` - The format and edition, such as `Blu-ray, Director's Cut`, if the name has
+///   them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Product {
+    pub title: String,
+    pub year: Option<u16>,
+    pub edition: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Lookup {
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    title: String,
+}
+
+/// Takes the spaces and dashes out of a barcode, returning `None` unless it is a 12 digit
+/// UPC or 13 digit EAN with the right check digit. A wrong check digit means it was typed
+/// or scanned wrong.
+#[must_use]
+pub fn normalize_barcode(input: &str) -> Option<String> {
+    let barcode: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if !matches!(barcode.len(), 12 | 13) || !barcode.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits: Vec<u32> = barcode.bytes().map(|b| u32::from(b - b'0')).collect();
+    let (check, rest) = digits.split_last()?;
+
+    // Counting from the right, every other digit is weighted 3
+    let sum: u32 = rest
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 0 { digit * 3 } else { *digit })
+        .sum();
+
+    ((10 - sum % 10) % 10 == *check).then_some(barcode)
+}
+
+/// Looks a barcode up on `UPCitemdb`. Returns `None` if nothing has the barcode.
+///
+/// # Errors
+///
+/// Returns a `BarcodeError` if `UPCitemdb` can't be reached, has had too many lookups, or
+/// responds with an error.
+pub async fn lookup_barcode(barcode: &str) -> Result<Option<Product>> {
+    trace!("Looking up barcode {} on UPCitemdb", barcode);
+    let response = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?
+        .get(UPCITEMDB_URL)
+        .query(&[("upc", barcode)])
+        .send()
+        .await?;
+
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(BarcodeError::RateLimited);
+    }
+    // Barcodes UPCitemdb doesn't recognise at all are a bad request rather than no items
+    if response.status() == reqwest::StatusCode::BAD_REQUEST {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(BarcodeError::LookupError(format!(
+            "UPCitemdb responded with status {}",
+            response.status().as_u16()
+        )));
+    }
+
+    let lookup: Lookup = response.json().await?;
+    let product = lookup
+        .items
+        .first()
+        .map(|item| parse_product(&item.title))
+        .filter(|product| !product.title.is_empty());
+
+    match &product {
+        Some(product) => debug!("Found {} for barcode {}", product.title, barcode),
+        None => debug!("Nothing was found for barcode {}", barcode),
+    }
+
+    Ok(product)
+}
+
+/// Splits a shop's product name into the title, year and edition
+fn parse_product(name: &str) -> Product {
+    // Anything in brackets is the year, format or edition
+    let mut outside = String::new();
+    let mut groups = Vec::new();
+    let mut group = String::new();
+    let mut depth = 0;
+    for c in name.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(group.trim().to_string());
+                    group.clear();
+                    outside.push(' ');
+                }
+            }
+            _ if depth > 0 => group.push(c),
+            _ => outside.push(c),
+        }
+    }
+
+    let year = groups.iter().find_map(|group| {
+        group
+            .parse::<u16>()
+            .ok()
+            .filter(|year| (1870..=2100).contains(year))
+    });
+    let mut edition: Vec<String> = groups
+        .into_iter()
+        .filter(|group| !group.is_empty() && group.parse::<u16>().is_err())
+        .collect();
+
+    let mut title = outside.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    // e.g. "Heat - Director's Definitive Edition"
+    if let Some((before, after)) = title.split_once(" - ") {
+        let lowercase = after.to_lowercase();
+        if EDITION_WORDS.iter().any(|word| lowercase.contains(word)) {
+            edition.insert(0, after.to_string());
+            title = before.to_string();
+        }
+    }
+
+    // e.g. "Heat Blu-ray"
+    while let Some(cut) = FORMAT_WORDS.iter().find_map(|format| {
+        let cut = title.len().checked_sub(format.len() + 1)?;
+        let ending = title.get(cut..)?;
+        ending
+            .eq_ignore_ascii_case(&format!(" {format}"))
+            .then_some(cut)
+    }) {
+        edition.push(title[cut + 1..].to_string());
+        title.truncate(cut);
+    }
+
+    Product {
+        title: title.trim_end_matches([',', ':', '-', ' ']).to_string(),
+        year,
+        edition: (!edition.is_empty()).then(|| edition.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_barcodes_by_their_check_digit() {
+        assert_eq!(
+            normalize_barcode("0 36000 29145 2"),
+            Some("036000291452".to_string())
+        );
+        assert_eq!(
+            normalize_barcode("4006381333931"),
+            Some("4006381333931".to_string())
+        );
+        assert_eq!(normalize_barcode("036000291453"), None);
+        assert_eq!(normalize_barcode("12345"), None);
+        assert_eq!(normalize_barcode("03600029145a"), None);
+    }
+
+    #[test]
+    fn splits_product_names() {
+        assert_eq!(
+            parse_product("Heat (Blu-ray) (1995)"),
+            Product {
+                title: "Heat".to_string(),
+                year: Some(1995),
+                edition: Some("Blu-ray".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_product("Heat - Director's Definitive Edition [Blu-ray]"),
+            Product {
+                title: "Heat".to_string(),
+                year: None,
+                edition: Some("Director's Definitive Edition, Blu-ray".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_product("The Thing DVD"),
+            Product {
+                title: "The Thing".to_string(),
+                year: None,
+                edition: Some("DVD".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_product("Spider-Man - Into the Spider-Verse"),
+            Product {
+                title: "Spider-Man - Into the Spider-Verse".to_string(),
+                year: None,
+                edition: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, BarcodeError>;
+
+#[derive(Debug, Error)]
+pub enum BarcodeError {
+    #[error("Barcode lookup was rate limited")]
+    RateLimited,
+
+    #[error("Barcode lookup failed: {0}")]
+    LookupError(String),
+}
+
+impl BarcodeError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            BarcodeError::RateLimited => {
+                tr!("Too many barcodes have been looked up today, try again tomorrow.")
+            }
+            BarcodeError::LookupError(_) => tr!("The barcode couldn't be looked up."),
+        }
+    }
+}
+
+impl From<reqwest::Error> for BarcodeError {
+    fn from(error: reqwest::Error) -> Self {
+        BarcodeError::LookupError(error.to_string())
+    }
+}
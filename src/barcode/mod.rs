@@ -0,0 +1,4 @@
+pub mod barcode_core;
+pub mod errors;
+
+pub use barcode_core::{lookup_barcode, normalize_barcode, Product};
@@ -0,0 +1,522 @@
+//! # Cord Ripper Agent
+//!
+//! Runs `makemkvcon` for a Cord Ripper bot on another machine, so the drives can be on a
+//! different machine from the bot. Start one on each machine with drives, and pass each to
+//! the bot with `--agent name=url`.
+//!
+//! ## Command-Line Arguments
+//! - `--log-level` or `-l`: Optional log level, as with the bot. Defaults to `info`.
+//! - `--listen`: The address to answer the bot on. Defaults to `0.0.0.0:9000`.
+//! - `--work-dir`: The directory rips are kept in until the bot downloads them. Defaults to
+//!   `cord-ripper-agent` in the temporary directory.
+//! - `--makemkvcon`: How to run `makemkvcon`, as with the bot. Defaults to `makemkvcon`.
+//! - `--makemkv-command-prefix`: A command to run `makemkvcon` through, as with the bot.
+//!
+//! ## Environment Variables
+//! - `AGENT_TOKEN`: Optional token the bot has to send, or `AGENT_TOKEN_FILE` naming a file
+//!   to read it from. Without one, anyone who can reach the agent can use its drives.
+//!
+//! ## Endpoints
+//! - `POST /makemkvcon`: Runs `makemkvcon` with the `args` of a JSON body, answering with its
+//!   exit code and output.
+//! - `POST /rip`: Runs `makemkvcon` with a job directory to rip into added to the `args`,
+//!   answering with the job and the files saved to it. Closing the connection stops the rip.
+//! - `GET /jobs/{job}/{file}`: Downloads a ripped file.
+//! - `DELETE /jobs/{job}`: Removes the files of a job once downloaded.
+//! - `POST /eject`: Opens the tray of the device given as the first of the `args`.
+//! - `GET /health`: Answers `200 OK`, for the agent to be health checked.
+//!
+//! ## Notes
+//!
+//! - HTTP is handled by hand, one request per connection, as the bot is the only client.
+//! - Put the agent behind a TLS terminating proxy if the bot reaches it over the internet,
+//!   the token is sent in the clear otherwise.
+#![warn(clippy::pedantic)]
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use clap::Parser;
+use cord_ripper::logging;
+use cord_ripper::{debug, error, info, warn};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    process::Command,
+};
+
+/// The most a request's headers can take up
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Counts jobs, so two rips started in the same second get their own directories
+static JOBS: AtomicU64 = AtomicU64::new(0);
+
+/// Command line arguments for the agent.
+///
+/// # Fields
+/// - `log_level`: Optional level of logging
+/// - `listen`: The address to answer the bot on
+/// - `work_dir`: Optional directory rips are kept in until they are downloaded
+/// - `makemkvcon`: How to run `makemkvcon`
+/// - `makemkv_command_prefix`: Optional command `makemkvcon` is run through
+#[derive(clap::Parser, Debug)]
+struct AgentArgs {
+    /// Optional level of logging
+    #[clap(short, long, help = "Level of logging [info by default]")]
+    log_level: Option<String>,
+    /// The address to answer the bot on
+    #[clap(
+        long,
+        default_value = "0.0.0.0:9000",
+        help = "Address to answer the bot on [0.0.0.0:9000 by default]"
+    )]
+    listen: String,
+    /// Optional directory rips are kept in until they are downloaded
+    #[clap(
+        long,
+        help = "Directory rips are kept in until the bot downloads them [cord-ripper-agent in the temporary directory by default]"
+    )]
+    work_dir: Option<PathBuf>,
+    /// How to run `makemkvcon`
+    #[clap(
+        long,
+        default_value = "makemkvcon",
+        help = "How to run makemkvcon, its path or a wrapper such as 'flatpak run --command=makemkvcon com.makemkv.MakeMKV' [makemkvcon by default]"
+    )]
+    makemkvcon: String,
+    /// Optional command `makemkvcon` is run through
+    #[clap(
+        long,
+        help = "Command to run makemkvcon through, such as 'docker exec makemkv' [none by default]"
+    )]
+    makemkv_command_prefix: Option<String>,
+}
+
+/// What the agent was started with.
+///
+/// # Fields
+/// - `token`: The token requests have to be sent with, if any
+/// - `work_dir`: The directory jobs are kept in
+/// - `command`: The program and arguments `makemkvcon` is run with, through the prefix if
+///   one was set
+struct Agent {
+    token: Option<String>,
+    work_dir: PathBuf,
+    command: Vec<String>,
+}
+
+/// A request, as far as the agent cares.
+///
+/// # Fields
+/// - `method` and `path`: From the request line
+/// - `authorization`: The `Authorization` header, if sent
+/// - `content_length`: The `Content-Length` header, 0 if not sent
+/// - `body`: The body, empty until it has been read
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    content_length: usize,
+    body: Vec<u8>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = AgentArgs::parse();
+
+    if let Some(log_level) = &args.log_level {
+        if let Some(log_level) = logging::log_level_from_str(log_level) {
+            logging::set_log_level(log_level);
+        } else {
+            warn!("Invalid log level provided, using default level: info");
+        }
+    }
+
+    let token = match std::env::var("AGENT_TOKEN") {
+        Ok(token) => Some(token),
+        Err(_) => std::env::var("AGENT_TOKEN_FILE").ok().map(|path| {
+            std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| {
+                    error!("Failed to read AGENT_TOKEN_FILE {}: {}", path, e);
+                    std::process::exit(1);
+                })
+                .trim()
+                .to_string()
+        }),
+    };
+    match &token {
+        // Kept out of the logs, such as a request logged with its headers
+        Some(token) => logging::add_secret(token),
+        None => warn!("AGENT_TOKEN is not set, anyone who can reach the agent can use its drives"),
+    }
+
+    let work_dir = args
+        .work_dir
+        .unwrap_or_else(|| std::env::temp_dir().join("cord-ripper-agent"));
+    if let Err(e) = std::fs::create_dir_all(&work_dir) {
+        error!(
+            "Failed to create work directory {}: {}",
+            work_dir.display(),
+            e
+        );
+        std::process::exit(1);
+    }
+
+    let agent = Arc::new(Agent {
+        token,
+        work_dir,
+        command: args
+            .makemkv_command_prefix
+            .unwrap_or_default()
+            .split_whitespace()
+            .chain(args.makemkvcon.split_whitespace())
+            .map(String::from)
+            .collect(),
+    });
+
+    let listener = TcpListener::bind(&args.listen).await.unwrap_or_else(|e| {
+        error!("Failed to listen on {}: {}", args.listen, e);
+        std::process::exit(1);
+    });
+    info!("Listening on {}", args.listen);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, address)) => {
+                let agent = agent.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle(&agent, stream).await {
+                        warn!("Failed to answer {}: {}", address, e);
+                    }
+                });
+            }
+            Err(e) => warn!("Failed to accept connection: {}", e),
+        }
+    }
+}
+
+/// Answers a single request
+async fn handle(agent: &Agent, mut stream: TcpStream) -> std::io::Result<()> {
+    let Some(request) = read_request(&mut stream).await? else {
+        return respond(&mut stream, 400, &json!({ "error": "Invalid request" })).await;
+    };
+    debug!("{} {}", request.method, request.path);
+
+    if request.path == "/health" {
+        return respond(&mut stream, 200, &json!({ "status": "ok" })).await;
+    }
+
+    if agent.token.is_some()
+        && request
+            .authorization
+            .as_deref()
+            .and_then(|auth| auth.strip_prefix("Bearer "))
+            != agent.token.as_deref()
+    {
+        return respond(&mut stream, 401, &json!({ "error": "Invalid token" })).await;
+    }
+
+    let segments: Vec<&str> = request.path.trim_start_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["makemkvcon"]) => {
+            let args = request_args(&request.body);
+            let output = run(agent.makemkvcon(&args)).await?;
+            respond(&mut stream, 200, &output).await
+        }
+        ("POST", ["rip"]) => rip(agent, stream, request_args(&request.body)).await,
+        ("POST", ["eject"]) => {
+            let args = request_args(&request.body);
+            let Some(device) = args.first() else {
+                return respond(&mut stream, 400, &json!({ "error": "No device given" })).await;
+            };
+            let (program, args) = eject_command(device);
+            let mut command = Command::new(program);
+            command.args(args);
+            let output = run(command).await?;
+            respond(&mut stream, 200, &output).await
+        }
+        ("GET", ["jobs", job, file]) if valid_name(job) && valid_name(file) => {
+            send_file(&mut stream, &agent.work_dir.join(job).join(file)).await
+        }
+        ("DELETE", ["jobs", job]) if valid_name(job) => {
+            let _ = tokio::fs::remove_dir_all(agent.work_dir.join(job)).await;
+            respond(&mut stream, 200, &json!({})).await
+        }
+        _ => respond(&mut stream, 404, &json!({ "error": "Not found" })).await,
+    }
+}
+
+impl Agent {
+    /// Builds a `makemkvcon` command, run the way it was configured
+    fn makemkvcon(&self, args: &[String]) -> Command {
+        let (program, command_args) = self
+            .command
+            .split_first()
+            .map_or(("makemkvcon", &[][..]), |(program, rest)| {
+                (program.as_str(), rest)
+            });
+        let mut command = Command::new(program);
+        command.args(command_args).args(args);
+        command
+    }
+}
+
+/// Rips into a new job directory, stopping `makemkvcon` if the bot goes away before it
+/// finishes
+async fn rip(agent: &Agent, mut stream: TcpStream, mut args: Vec<String>) -> std::io::Result<()> {
+    let job = format!(
+        "{}-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        JOBS.fetch_add(1, Ordering::Relaxed)
+    );
+    let job_dir = agent.work_dir.join(&job);
+    tokio::fs::create_dir_all(&job_dir).await?;
+
+    args.push(job_dir.to_string_lossy().to_string());
+    let mut command = agent.makemkvcon(&args);
+    command.kill_on_drop(true);
+
+    // The bot sends nothing more, so anything read is the connection closing
+    let (mut reader, mut writer) = stream.split();
+    let output = tokio::select! {
+        output = run(command) => output?,
+        () = closed(&mut reader) => {
+            info!("Rip {} was cancelled", job);
+            let _ = tokio::fs::remove_dir_all(&job_dir).await;
+            return Ok(());
+        }
+    };
+
+    let mut files = Vec::new();
+    let mut entries = tokio::fs::read_dir(&job_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            files.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    files.sort();
+
+    let mut output = output;
+    output["job"] = json!(job);
+    output["files"] = json!(files);
+    respond(&mut writer, 200, &output).await
+}
+
+/// Waits for the other end of a connection to close it
+async fn closed(reader: &mut (impl AsyncRead + Unpin)) {
+    let mut buffer = [0; 64];
+    while matches!(reader.read(&mut buffer).await, Ok(read) if read > 0) {}
+}
+
+/// Runs a command, answering with how it went in the form the bot expects
+async fn run(mut command: Command) -> std::io::Result<Value> {
+    let output = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await?;
+
+    Ok(json!({
+        "status": output.status.code(),
+        "stdout": String::from_utf8_lossy(&output.stdout),
+        "stderr": String::from_utf8_lossy(&output.stderr),
+    }))
+}
+
+/// The command that opens the tray of the drive at a device on this platform, the same as
+/// the bot's
+fn eject_command(device: &str) -> (&'static str, Vec<String>) {
+    if cfg!(target_os = "windows") {
+        let script = format!(
+            "(New-Object -ComObject Shell.Application).Namespace(17).ParseName('{}\\').InvokeVerb('Eject')",
+            device.trim_end_matches('\\')
+        );
+        (
+            "powershell",
+            vec!["-NoProfile".to_string(), "-Command".to_string(), script],
+        )
+    } else if cfg!(target_os = "macos") {
+        (
+            "diskutil",
+            vec![
+                "eject".to_string(),
+                device.replace("/dev/rdisk", "/dev/disk"),
+            ],
+        )
+    } else {
+        ("eject", vec![device.to_string()])
+    }
+}
+
+/// The `args` of a JSON request body, none if it has none
+fn request_args(body: &[u8]) -> Vec<String> {
+    serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| serde_json::from_value(body["args"].clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Whether a job or file name is safe to join onto the work directory
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+/// Reads a request, `None` if it isn't one
+async fn read_request(stream: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Option<Request>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+
+    let head_end = loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Ok(None);
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let Some(mut request) = parse_head(&String::from_utf8_lossy(&buffer[..head_end])) else {
+        return Ok(None);
+    };
+
+    // The body may have come with the headers, the rest is read by its length
+    let content_length = request.content_length;
+    let mut body = buffer.split_off(head_end + 4);
+    body.truncate(content_length);
+    while body.len() < content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        body.extend_from_slice(&chunk[..read.min(content_length - body.len())]);
+    }
+    request.body = body;
+
+    Ok(Some(request))
+}
+
+/// Parses the request line and headers of a request, leaving the body to be read
+fn parse_head(head: &str) -> Option<Request> {
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let (method, path) = (request_line.next()?, request_line.next()?);
+
+    let mut authorization = None;
+    let mut content_length = 0;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "authorization" => authorization = Some(value.trim().to_string()),
+            "content-length" => content_length = value.trim().parse().ok()?,
+            _ => {}
+        }
+    }
+
+    // Bodies are only ever a short list of arguments
+    if content_length > MAX_HEAD_SIZE {
+        return None;
+    }
+
+    Some(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        authorization,
+        content_length,
+        body: Vec::new(),
+    })
+}
+
+/// Writes a JSON response and closes the connection
+async fn respond(
+    stream: &mut (impl tokio::io::AsyncWrite + Unpin),
+    status: u16,
+    body: &Value,
+) -> std::io::Result<()> {
+    let body = body.to_string();
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                reason(status),
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await?;
+    stream.shutdown().await
+}
+
+/// Streams a file as the response, rips are too large to hold in memory
+async fn send_file(stream: &mut TcpStream, path: &Path) -> std::io::Result<()> {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return respond(stream, 404, &json!({ "error": "Not found" })).await;
+    };
+    let length = file.metadata().await?.len();
+
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {length}\r\nConnection: close\r\n\r\n"
+            )
+            .as_bytes(),
+        )
+        .await?;
+    tokio::io::copy(&mut file, stream).await?;
+    stream.shutdown().await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_request_heads() {
+        let request =
+            parse_head("POST /rip HTTP/1.1\r\nAuthorization: Bearer secret\r\ncontent-length: 12")
+                .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/rip");
+        assert_eq!(request.authorization.as_deref(), Some("Bearer secret"));
+        assert_eq!(request.content_length, 12);
+
+        assert_eq!(parse_head("GET"), None);
+    }
+
+    #[test]
+    fn rejects_names_outside_of_jobs() {
+        assert!(valid_name("1700000000-3"));
+        assert!(valid_name("title_t00.mkv"));
+        assert!(!valid_name(".."));
+        assert!(!valid_name("../etc"));
+        assert!(!valid_name(""));
+    }
+}
@@ -0,0 +1,243 @@
+//! # Collection Core Module
+//!
+//! This module keeps the list of discs the household owns and still wants ripped, so the
+//! shelf of discs waiting for a free evening isn't tracked on a sticky note.
+//!
+//! ## Overview
+//!
+//! - **`CollectionEntry`**: A disc on the list, with who added it and whether it has been
+//!   ripped yet.
+//!
+//! - **`Collection`**: Every disc on the list, stored as JSON in the data directory.
+//!
+//! - **`COLLECTION`**: A globally accessible, thread-safe instance of `Collection`.
+//!
+//! ## Notes
+//!
+//! - Discs are matched to the list by their volume label, such as `HEAT_DISC_1`. Labels
+//!   are compared by their words, ignoring case and punctuation, so an entry for `Heat`
+//!   matches a disc labelled `HEAT_DISC_1`. An entry can be given the exact label when the
+//!   title isn't on the disc.
+//! - Ripped discs are kept on the list, marked done, so the household can see what has
+//!   been done as well as what is left.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::history::history_core::{now, write_json_atomic};
+use crate::{debug, info, trace};
+
+use super::errors::{CollectionError, Result};
+
+/// The name of the collection file within the data directory
+const COLLECTION_FILE: &str = "collection.json";
+
+/// A globally accessible instance of `Collection` for the discs waiting to be ripped.
+pub static COLLECTION: LazyLock<Mutex<Collection>> = LazyLock::new(Mutex::default);
+
+/// A disc on the list.
+///
+/// # Fields
+///
+/// * `id` - The number the disc is marked done by.
+/// * `title` - The title of the disc, as it was added.
+/// * `label` - The volume label to match discs by, `None` to match by the title.
+/// * `added_by` - The name of the Discord user who added the disc.
+/// * `added` - When the disc was added, in seconds since the unix epoch.
+/// * `done` - When the disc was marked as ripped, `None` while it still needs ripping.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionEntry {
+    pub id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub added_by: String,
+    pub added: u64,
+    #[serde(default)]
+    pub done: Option<u64>,
+}
+
+impl CollectionEntry {
+    /// Whether a disc with the given volume label is this one
+    #[must_use]
+    pub fn matches(&self, disc_label: &str) -> bool {
+        let wanted = words(self.label.as_deref().unwrap_or(&self.title));
+        let disc = words(disc_label);
+
+        // The label often has the disc number or edition after the title
+        !wanted.is_empty() && disc.starts_with(&wanted)
+    }
+}
+
+/// Every disc on the list.
+///
+/// # Fields
+///
+/// * `entries` - The discs, in the order they were added.
+/// * `next_id` - The id given to the next disc added.
+/// * `path` - Where the collection is saved, empty until `init` has been called.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Collection {
+    entries: Vec<CollectionEntry>,
+    next_id: u64,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Collection {
+    /// Loads the collection from the data directory, which has already been created by the
+    /// history.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CollectionError` if the collection file exists but can't be read or
+    /// parsed.
+    pub fn init(&mut self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join(COLLECTION_FILE);
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| CollectionError::ReadError(e.to_string()))?;
+            *self = serde_json::from_str(&contents)?;
+            debug!("Loaded {} discs in the collection", self.entries.len());
+        } else {
+            debug!("No collection file found, starting with an empty collection");
+        }
+
+        self.path = path;
+
+        info!("Collection initialized successfully!");
+        Ok(())
+    }
+
+    /// Writes the collection to disk
+    fn save(&self) -> Result<()> {
+        // Nothing is saved until init has been called
+        if self.path.as_os_str().is_empty() {
+            return Ok(());
+        }
+
+        write_json_atomic(&self.path, self)?;
+
+        trace!("Saved collection to {}", self.path.display());
+        Ok(())
+    }
+
+    /// Adds a disc to the list, returning it
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CollectionError` if the collection can't be saved.
+    pub fn add(
+        &mut self,
+        title: &str,
+        label: Option<&str>,
+        added_by: &str,
+    ) -> Result<CollectionEntry> {
+        self.next_id += 1;
+        let entry = CollectionEntry {
+            id: self.next_id,
+            title: title.trim().to_string(),
+            label: label
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .map(str::to_string),
+            added_by: added_by.to_string(),
+            added: now(),
+            done: None,
+        };
+
+        self.entries.push(entry.clone());
+        self.save()?;
+
+        debug!(
+            "Added {} to the collection as disc {}",
+            entry.title, entry.id
+        );
+        Ok(entry)
+    }
+
+    /// Marks a disc as ripped, returning it
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CollectionError` if there is no disc with the id, it was already marked
+    /// as ripped, or the collection can't be saved.
+    pub fn done(&mut self, id: u64) -> Result<CollectionEntry> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or(CollectionError::NotFound(id))?;
+
+        if entry.done.is_some() {
+            return Err(CollectionError::AlreadyDone(id));
+        }
+        entry.done = Some(now());
+        let entry = entry.clone();
+
+        self.save()?;
+
+        debug!("Marked disc {} in the collection as ripped", id);
+        Ok(entry)
+    }
+
+    /// Every disc on the list, in the order they were added
+    #[must_use]
+    pub fn entries(&self) -> &[CollectionEntry] {
+        &self.entries
+    }
+
+    /// The disc still waiting to be ripped that a volume label matches, if any
+    #[must_use]
+    pub fn find_label(&self, disc_label: &str) -> Option<&CollectionEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.done.is_none())
+            .find(|entry| entry.matches(disc_label))
+    }
+}
+
+/// The words of a title or volume label, lowercased, with punctuation and underscores
+/// treated as spaces
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_labels_by_their_words() {
+        let mut collection = Collection::default();
+        let heat = collection.add("Heat", None, "sam").unwrap();
+        collection
+            .add("The Thing", Some("THING_1982"), "sam")
+            .unwrap();
+
+        assert_eq!(collection.find_label("HEAT_DISC_1"), Some(&heat));
+        assert_eq!(collection.find_label("heat"), Some(&heat));
+        assert_eq!(collection.find_label("HEATWAVE"), None);
+        assert_eq!(
+            collection.find_label("THING_1982_BD").map(|entry| entry.id),
+            Some(2)
+        );
+        assert_eq!(collection.find_label("THE_THING"), None);
+
+        collection.done(heat.id).unwrap();
+        assert_eq!(collection.find_label("HEAT_DISC_1"), None);
+        assert!(matches!(
+            collection.done(heat.id),
+            Err(CollectionError::AlreadyDone(1))
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use std::io;
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, CollectionError>;
+
+#[derive(Debug, Error)]
+pub enum CollectionError {
+    #[error("No disc with id {0} in the collection")]
+    NotFound(u64),
+
+    #[error("Disc {0} is already ripped")]
+    AlreadyDone(u64),
+
+    #[error("Failed to read collection file: {0}")]
+    ReadError(String),
+
+    #[error("Failed to write collection file: {0}")]
+    WriteError(String),
+
+    #[error("Failed to parse collection file: {0}")]
+    ParseError(String),
+}
+
+impl CollectionError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            CollectionError::NotFound(id) => tr!(
+                "There is no disc {id} in the collection, see `/collection list` for their numbers.",
+                id = id
+            ),
+            CollectionError::AlreadyDone(id) => {
+                tr!("Disc {id} is already marked as ripped.", id = id)
+            }
+            _ => tr!("The collection couldn't be read or saved."),
+        }
+    }
+}
+
+impl From<io::Error> for CollectionError {
+    fn from(error: io::Error) -> Self {
+        CollectionError::WriteError(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CollectionError {
+    fn from(error: serde_json::Error) -> Self {
+        CollectionError::ParseError(error.to_string())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod collection_core;
+pub mod errors;
+
+pub use collection_core::{Collection, CollectionEntry, COLLECTION};
@@ -0,0 +1,203 @@
+//! # Container Core Module
+//!
+//! This module holds what Cord Ripper needs to run well in a container, such as with
+//! Docker, where settings are mounted as secrets and drives have to be passed through.
+//!
+//! ## Overview
+//!
+//! - **`secret`**: Reads a setting from the environment, or from the file named by its
+//!   `_FILE` variable, which is how Docker and Kubernetes mount secrets.
+//!
+//! - **`discord_token`**: Reads the Discord token from the environment or a file, checks
+//!   it looks like a token and hides it from the logs.
+//!
+//! - **`check_devices`**: Checks the drives were passed through to the container, so a
+//!   missing `--device` is reported at startup instead of as a rip that finds no drives.
+//!
+//! ## Notes
+//!
+//! - A variable wins over its `_FILE` variable when both are set.
+//! - The shape of the token is all that is checked, whether Discord accepts it is only
+//!   known once the bot connects.
+//! - Drives without a disc can't be opened, only a device that is missing or can't be
+//!   opened for lack of permissions fails the check.
+
+use std::{env, fs::File, io::ErrorKind, path::Path};
+
+use crate::{debug, info};
+
+use super::errors::{ContainerError, Result};
+
+/// Reads a setting from the environment variable `var`, or failing that from the file named
+/// by `{var}_FILE`, trimming the newline secret files usually end with.
+///
+/// # Errors
+///
+/// Returns a `ContainerError` if `{var}_FILE` is set but the file can't be read.
+pub fn secret(var: &str) -> Result<Option<String>> {
+    if let Ok(value) = env::var(var) {
+        return Ok(Some(value));
+    }
+
+    let file_var = format!("{var}_FILE");
+    let Ok(path) = env::var(&file_var) else {
+        return Ok(None);
+    };
+
+    let value = std::fs::read_to_string(&path).map_err(|e| ContainerError::SecretUnreadable {
+        var: file_var,
+        path: path.clone(),
+        reason: e.to_string(),
+    })?;
+    debug!("Read {} from {}", var, path);
+
+    Ok(Some(value.trim().to_string()))
+}
+
+/// Reads the Discord token from `DISCORD_TOKEN`, the file named by `DISCORD_TOKEN_FILE`, or
+/// failing both the `token_path` file of the settings. The token is hidden from the logs
+/// from then on, and checked to look like a token before anything connects with it.
+///
+/// # Errors
+///
+/// Returns a `ContainerError` if the file the token is in can't be read, or the token
+/// doesn't look like one.
+pub fn discord_token(token_path: Option<&Path>) -> Result<Option<String>> {
+    let token = match (secret("DISCORD_TOKEN")?, token_path) {
+        (Some(token), _) => token,
+        (None, Some(path)) => {
+            let token =
+                std::fs::read_to_string(path).map_err(|e| ContainerError::SecretUnreadable {
+                    var: "token_path".to_string(),
+                    path: path.display().to_string(),
+                    reason: e.to_string(),
+                })?;
+            debug!("Read DISCORD_TOKEN from {}", path.display());
+            token.trim().to_string()
+        }
+        (None, None) => return Ok(None),
+    };
+
+    crate::logging::add_secret(&token);
+    check_token(&token)?;
+    Ok(Some(token))
+}
+
+/// Checks `token` looks like a Discord bot token, three dot separated parts of base64,
+/// optionally after `Bot `. The reason it doesn't never includes the token.
+fn check_token(token: &str) -> Result<()> {
+    let token = token.strip_prefix("Bot ").unwrap_or(token);
+    if token.is_empty() {
+        return Err(ContainerError::InvalidToken("it is empty".to_string()));
+    }
+    if token.chars().any(char::is_whitespace) {
+        return Err(ContainerError::InvalidToken(
+            "it has spaces or line breaks in it".to_string(),
+        ));
+    }
+
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 || parts.iter().any(|part| part.is_empty()) {
+        return Err(ContainerError::InvalidToken(format!(
+            "it should have three parts separated by dots, but has {}",
+            parts.iter().filter(|part| !part.is_empty()).count()
+        )));
+    }
+
+    let base64 = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+' | '/' | '=');
+    if !token.chars().all(|c| c == '.' || base64(c)) {
+        return Err(ContainerError::InvalidToken(
+            "it has characters Discord tokens don't".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that each of `devices` was passed through to the container and can be opened.
+/// With no devices given, the `/dev/sr` devices found are checked, and there has to be at
+/// least one.
+///
+/// # Errors
+///
+/// Returns a `ContainerError` naming the first device that is missing or can't be opened,
+/// and how to pass it through.
+pub fn check_devices(devices: &[String]) -> Result<()> {
+    let devices = if devices.is_empty() {
+        let mut found: Vec<String> = std::fs::read_dir("/dev")
+            .map(|entries| {
+                entries
+                    .filter_map(std::result::Result::ok)
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| {
+                        name.strip_prefix("sr")
+                            .is_some_and(|index| index.parse::<u8>().is_ok())
+                    })
+                    .map(|name| format!("/dev/{name}"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        found.sort();
+
+        if found.is_empty() {
+            return Err(ContainerError::NoDevicesMapped);
+        }
+        found
+    } else {
+        devices.to_vec()
+    };
+
+    for device in &devices {
+        if !Path::new(device).exists() {
+            return Err(ContainerError::DeviceNotMapped(device.clone()));
+        }
+
+        // An empty drive fails to open with 'No medium found', which is fine
+        match File::open(device) {
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                return Err(ContainerError::DeviceNotReadable(
+                    device.clone(),
+                    e.to_string(),
+                ));
+            }
+            Err(e) => debug!("Opening {} failed, likely without a disc: {}", device, e),
+            Ok(_) => (),
+        }
+    }
+
+    info!(
+        "Drives passed through to the container: {}",
+        devices.join(", ")
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GaBcDe.abcdefghijklmnopqrstuvwxyz_-0123456789";
+
+    #[test]
+    fn accepts_token_shapes() {
+        assert!(check_token(TOKEN).is_ok());
+        assert!(check_token(&format!("Bot {TOKEN}")).is_ok());
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_token() {
+        assert!(check_token("").is_err());
+        assert!(check_token("your_token_here").is_err());
+        assert!(check_token("abc..def").is_err());
+        assert!(check_token(&format!("{TOKEN} ")).is_err());
+        assert!(check_token(&format!("{TOKEN}!")).is_err());
+    }
+
+    #[test]
+    fn errors_leave_the_token_out() {
+        let token = format!("{TOKEN}.extra");
+        let error = check_token(&token).unwrap_err().to_string();
+        assert!(!error.contains(&token));
+        assert!(!error.contains("GaBcDe"));
+    }
+}
@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, ContainerError>;
+
+#[derive(Debug, Error)]
+pub enum ContainerError {
+    #[error("Failed to read {var} from {path}: {reason}")]
+    SecretUnreadable {
+        var: String,
+        path: String,
+        reason: String,
+    },
+
+    #[error("The Discord token doesn't look like one, {0}. Copy it again from the Bot page of the Discord developer portal")]
+    InvalidToken(String),
+
+    #[error("{0} isn't mapped into the container, pass it through with '--device {0}:{0}'")]
+    DeviceNotMapped(String),
+
+    #[error("No drives are mapped into the container, pass each one through with '--device /dev/sr0:/dev/sr0'")]
+    NoDevicesMapped,
+
+    #[error("{0} can't be read: {1}. Add the container to the group that owns it with '--group-add', or run it with '--privileged'")]
+    DeviceNotReadable(String, String),
+
+    #[error("Failed to listen for health checks on port {0}: {1}")]
+    HealthCheckBindFailed(u16, String),
+
+    #[error("Failed to write the PID file {0}: {1}")]
+    PidFileUnwritable(String, String),
+}
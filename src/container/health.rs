@@ -0,0 +1,116 @@
+//! # Health Checks
+//!
+//! Serves a health check endpoint over HTTP, for container orchestrators and `HEALTHCHECK`
+//! instructions to poll. `GET /health` answers `200 OK` once the bot has connected to
+//! Discord, and `503 Service Unavailable` until then.
+//!
+//! ## Notes
+//!
+//! - Only the request line is read, a full HTTP server would be a lot of dependency for a
+//!   single endpoint.
+//! - `/healthz` is answered as well, as Kubernetes probes are often pointed there.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{debug, info, trace};
+
+use super::errors::{ContainerError, Result};
+
+/// Whether the bot has connected to Discord
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Marks the bot as healthy, once it has connected to Discord
+pub fn set_ready() {
+    READY.store(true, Ordering::Relaxed);
+}
+
+/// Starts answering health checks on `port`, on every interface so they can be reached
+/// from outside of the container.
+///
+/// # Errors
+///
+/// Returns a `ContainerError` if the port can't be listened on.
+pub async fn serve_health(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| ContainerError::HealthCheckBindFailed(port, e.to_string()))?;
+    info!("Answering health checks on port {}", port);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(respond(stream));
+                }
+                Err(e) => debug!("Failed to accept health check: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Answers a single health check
+async fn respond(mut stream: TcpStream) {
+    let mut buffer = [0; 1024];
+    let Ok(read) = stream.read(&mut buffer).await else {
+        return;
+    };
+
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let request_line = request.lines().next().unwrap_or_default();
+    trace!("Health check: {}", request_line);
+
+    if let Err(e) = stream
+        .write_all(response(request_line, READY.load(Ordering::Relaxed)).as_bytes())
+        .await
+    {
+        debug!("Failed to answer health check: {}", e);
+    }
+}
+
+/// The response to a request, by its request line, e.g. `GET /health HTTP/1.1`
+fn response(request_line: &str, ready: bool) -> String {
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or_default(),
+    );
+
+    let (status, body) = match (method, path) {
+        ("GET" | "HEAD", "/health" | "/healthz") if ready => ("200 OK", r#"{"status":"ok"}"#),
+        ("GET" | "HEAD", "/health" | "/healthz") => {
+            ("503 Service Unavailable", r#"{"status":"starting"}"#)
+        }
+        _ => ("404 Not Found", r#"{"status":"not found"}"#),
+    };
+
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_once_ready() {
+        assert!(response("GET /health HTTP/1.1", false).starts_with("HTTP/1.1 503"));
+        assert!(response("GET /health HTTP/1.1", true).starts_with("HTTP/1.1 200"));
+        assert!(response("GET /healthz HTTP/1.1", true).ends_with(r#"{"status":"ok"}"#));
+    }
+
+    #[test]
+    fn rejects_other_paths() {
+        assert!(response("GET / HTTP/1.1", true).starts_with("HTTP/1.1 404"));
+        assert!(response("POST /health HTTP/1.1", true).starts_with("HTTP/1.1 404"));
+        assert!(response("", true).starts_with("HTTP/1.1 404"));
+    }
+}
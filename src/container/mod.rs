@@ -0,0 +1,8 @@
+pub mod container_core;
+pub mod errors;
+pub mod health;
+pub mod systemd;
+
+pub use container_core::{check_devices, discord_token, secret};
+pub use health::{serve_health, set_ready};
+pub use systemd::{exit, Exit};
@@ -0,0 +1,238 @@
+//! # Systemd
+//!
+//! Lets Cord Ripper run as a systemd service of `Type=notify`, telling systemd once it has
+//! connected to Discord, keeping its watchdog fed, and writing a PID file for service
+//! managers that track the bot by one.
+//!
+//! ## Overview
+//!
+//! - **`notify`**: Sends a state, such as `READY=1`, to systemd.
+//!
+//! - **`start_watchdog`**: Feeds the watchdog of `WatchdogSec=` for as long as the bot runs.
+//!
+//! - **`write_pid_file`**: Writes the PID of the bot to the file given with `--pid-file`.
+//!
+//! - **`Exit`** and **`exit`**: Stops the bot with an exit code telling configuration
+//!   mistakes apart from failures, removing the PID file first.
+//!
+//! - **`wait_for_stop`**: Resolves once the bot is asked to stop, by systemd or `Ctrl+C`.
+//!
+//! ## Notes
+//!
+//! - Nothing is sent when the bot isn't run by systemd, `NOTIFY_SOCKET` is only set by it.
+//! - Configuration mistakes exit with 78 (`EX_CONFIG`), which `RestartPreventExitStatus=78`
+//!   keeps systemd from restarting over and over, everything else exits with 1.
+//! - The watchdog is fed from the runtime the bot runs on, so a bot that hangs stops
+//!   feeding it and is restarted.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use crate::{debug, info, trace, warn};
+
+use super::errors::{ContainerError, Result};
+
+/// The PID file written at startup, removed again on exit
+static PID_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Why the bot stopped, told apart by its exit code.
+///
+/// * `Stopped` - It was asked to stop, exits with 0.
+/// * `Config` - It was set up wrong, such as with an invalid argument or a missing token.
+///   Restarting won't help until that is fixed, exits with 78 (`EX_CONFIG`).
+/// * `Failure` - Something failed while starting or running, a restart may help. Exits
+///   with 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Stopped,
+    Config,
+    Failure,
+}
+
+impl Exit {
+    /// The exit code of the process
+    #[must_use]
+    pub fn code(self) -> i32 {
+        match self {
+            Exit::Stopped => 0,
+            Exit::Config => 78,
+            Exit::Failure => 1,
+        }
+    }
+}
+
+/// Sends `state` to systemd. Not being run by systemd isn't an error, nothing is sent then.
+pub fn notify(state: &str) {
+    let Some(socket) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+
+    match send(&socket.to_string_lossy(), state) {
+        Ok(()) => trace!("Notified systemd: {}", state.replace('\n', ", ")),
+        Err(e) => warn!("Failed to notify systemd: {}", e),
+    }
+}
+
+#[cfg(unix)]
+fn send(socket: &str, state: &str) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let datagram = UnixDatagram::unbound()?;
+
+    // A socket starting with @ is in the abstract namespace, which only Linux has
+    if let Some(name) = socket.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(state.as_bytes(), &address)?;
+            return Ok(());
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("abstract socket @{name} isn't supported here"),
+            ));
+        }
+    }
+
+    datagram.send_to(state.as_bytes(), socket)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &str, _state: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "systemd isn't supported here",
+    ))
+}
+
+/// How often systemd expects to hear from the bot, from `WATCHDOG_USEC`. The watchdog is
+/// only meant for the bot if `WATCHDOG_PID` is its PID, or isn't set.
+fn watchdog_timeout(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    if pid.is_some_and(|pid| pid.trim().parse::<u32>().ok() != Some(own_pid)) {
+        return None;
+    }
+
+    let usec = usec?.trim().parse::<u64>().ok().filter(|usec| *usec > 0)?;
+    Some(Duration::from_micros(usec))
+}
+
+/// Feeds the watchdog at half its timeout, as systemd recommends, if `WatchdogSec=` is set
+/// for the service.
+pub fn start_watchdog() {
+    let Some(timeout) = watchdog_timeout(
+        env::var("WATCHDOG_USEC").ok().as_deref(),
+        env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    ) else {
+        trace!("No systemd watchdog to feed");
+        return;
+    };
+
+    let interval = timeout / 2;
+    info!("Feeding the systemd watchdog every {:?}", interval);
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            notify("WATCHDOG=1");
+        }
+    });
+}
+
+/// Writes the PID of the bot to `path`, which is removed again when the bot exits.
+///
+/// # Errors
+///
+/// Returns a `ContainerError` if the file can't be written.
+pub fn write_pid_file(path: &Path) -> Result<()> {
+    std::fs::write(path, format!("{}\n", std::process::id())).map_err(|e| {
+        ContainerError::PidFileUnwritable(path.display().to_string(), e.to_string())
+    })?;
+    debug!("Wrote PID {} to {}", std::process::id(), path.display());
+
+    let _ = PID_FILE.set(path.to_path_buf());
+    Ok(())
+}
+
+/// Tells systemd the bot is stopping and removes the PID file, for a bot stopping on its own
+pub fn stopped() {
+    notify("STOPPING=1");
+
+    if let Some(path) = PID_FILE.get() {
+        if let Err(e) = std::fs::remove_file(path) {
+            debug!("Failed to remove the PID file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Stops the bot for `reason`, telling systemd it is stopping and removing the PID file
+pub fn exit(reason: Exit) -> ! {
+    stopped();
+    std::process::exit(reason.code());
+}
+
+/// Resolves once the bot is asked to stop, with `SIGTERM` as systemd does or `Ctrl+C`
+pub async fn wait_for_stop() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => (),
+                    _ = tokio::signal::ctrl_c() => (),
+                }
+                return;
+            }
+            Err(e) => warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Failed to listen for Ctrl+C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdogs_are_only_fed_for_this_process() {
+        assert_eq!(
+            watchdog_timeout(Some("30000000"), None, 42),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            watchdog_timeout(Some("30000000"), Some("42"), 42),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(watchdog_timeout(Some("30000000"), Some("7"), 42), None);
+        assert_eq!(watchdog_timeout(Some("0"), None, 42), None);
+        assert_eq!(watchdog_timeout(None, None, 42), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn states_are_sent_to_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify");
+        let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        send(&path.to_string_lossy(), "READY=1").unwrap();
+        let mut buffer = [0; 64];
+        let received = socket.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..received], b"READY=1");
+    }
+}
@@ -0,0 +1,443 @@
+//! # Diagnostics
+//!
+//! Checks everything the bot relies on, so a broken setup is found in one go rather than
+//! one failed rip at a time. The checks are shown as a checklist by `/diagnostics`, and
+//! logged by `--self-test`, which exits with whether they all passed.
+//!
+//! ## Overview
+//!
+//! - **`Check`**: A single thing that was checked, and whether it passed.
+//!
+//! - **`run_checks`**: Checks `MakeMKV` and its license on this machine and every agent,
+//!   the drives, the output directory and its free space, and the optional tools.
+//!
+//! - **`permission_checks`**: Checks the bot can post rips to a channel, from the
+//!   permissions Discord sends with each command.
+//!
+//! - **`token_checks`**: Checks the token and guild id, for `--self-test`, which runs
+//!   before the bot connects.
+//!
+//! - **`self_test`**: Runs every check that doesn't need a channel, and logs them.
+//!
+//! ## Notes
+//!
+//! - Optional tools that are missing only warn, since only the feature using them fails.
+//! - Listing the drives asks `MakeMKV` to scan them, which takes a few seconds for each.
+//! - The Discord checks need the `discord` feature.
+
+use std::path::Path;
+
+#[cfg(feature = "discord")]
+use serenity::all::{GuildId, Permissions};
+#[cfg(feature = "discord")]
+use serenity::http::Http;
+
+use crate::disk::{free_space, SpaceLevel, DISK_MONITOR};
+use crate::makemkv::{
+    errors::MakeMkvError,
+    get_drives,
+    makemkv_helpers::{check_makemkv_output, makemkvcon_command},
+    messages::makemkv_version,
+    rippers::rippers,
+};
+use crate::upload::UPLOADER;
+use crate::{error, info, tr, warn};
+
+/// Whether a check passed.
+///
+/// * `Pass` - Everything is as it should be.
+/// * `Warn` - Works, but something is missing or running low.
+/// * `Fail` - Rips, or a part of them, will fail until it is fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// A single thing that was checked.
+///
+/// # Fields
+///
+/// * `name` - What was checked.
+/// * `status` - Whether it passed.
+/// * `detail` - What was found, or what is wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: String, status: CheckStatus, detail: String) -> Self {
+        Check {
+            name,
+            status,
+            detail,
+        }
+    }
+}
+
+/// Runs every check that doesn't need Discord, for an output directory
+pub async fn run_checks(output_dir: &Path) -> Vec<Check> {
+    let mut checks = makemkv_checks().await;
+    checks.push(drive_check().await);
+    checks.push(output_dir_check(output_dir));
+    checks.push(free_space_check(output_dir).await);
+    checks.extend(tool_checks().await);
+    checks
+}
+
+/// Checks `MakeMKV` can be run on this machine and every agent, and that its key or
+/// evaluation period hasn't expired
+async fn makemkv_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    for ripper in rippers() {
+        let name = match ripper.host() {
+            Some(host) => tr!("MakeMKV on {host}", host = host),
+            None => tr!("MakeMKV"),
+        };
+
+        let output = ripper
+            .makemkvcon(vec![
+                "-r".to_string(),
+                "--cache=1".to_string(),
+                "info".to_string(),
+                "disc:9999".to_string(),
+            ])
+            .await;
+
+        let check = match output {
+            Ok(output) => {
+                if let Err(e @ (MakeMkvError::RegistrationExpired | MakeMkvError::VersionTooOld)) =
+                    check_makemkv_output(&output)
+                {
+                    Check::new(name, CheckStatus::Fail, e.user_message())
+                } else {
+                    let version = makemkv_version(&String::from_utf8_lossy(&output.stdout))
+                        .unwrap_or_else(|| tr!("unknown"));
+                    let detail = match ripper.host() {
+                        Some(_) => tr!("Version {version}", version = version),
+                        None => tr!(
+                            "Version {version}, run as `{command}`",
+                            version = version,
+                            command = makemkvcon_command().join(" ")
+                        ),
+                    };
+                    Check::new(name, CheckStatus::Pass, detail)
+                }
+            }
+            Err(e) => Check::new(name, CheckStatus::Fail, e.user_message()),
+        };
+        checks.push(check);
+    }
+
+    checks
+}
+
+/// Checks `MakeMKV` finds at least one drive
+async fn drive_check() -> Check {
+    let name = tr!("Drives");
+
+    match get_drives().await {
+        Ok(drives) => Check::new(
+            name,
+            CheckStatus::Pass,
+            drives
+                .iter()
+                .map(|drive| match &drive.host {
+                    Some(host) => tr!(
+                        "Drive {drive} on {host}",
+                        drive = drive.drive_number,
+                        host = host
+                    ),
+                    None => tr!("Drive {drive}", drive = drive.drive_number),
+                })
+                .collect::<Vec<String>>()
+                .join(", "),
+        ),
+        Err(e) => Check::new(name, CheckStatus::Fail, e.user_message()),
+    }
+}
+
+/// Checks a file can be created in the output directory
+fn output_dir_check(output_dir: &Path) -> Check {
+    let name = tr!("Output Directory");
+
+    match tempfile::NamedTempFile::new_in(output_dir) {
+        Ok(_) => Check::new(
+            name,
+            CheckStatus::Pass,
+            tr!(
+                "{directory} can be written to",
+                directory = output_dir.display()
+            ),
+        ),
+        Err(e) => Check::new(
+            name,
+            CheckStatus::Fail,
+            tr!(
+                "{directory} can't be written to: {error}",
+                directory = output_dir.display(),
+                error = e
+            ),
+        ),
+    }
+}
+
+/// Checks the free space of the output directory against the configured thresholds
+async fn free_space_check(output_dir: &Path) -> Check {
+    let name = tr!("Free Space");
+
+    let free_bytes = match free_space(output_dir).await {
+        Ok(free_bytes) => free_bytes,
+        Err(e) => return Check::new(name, CheckStatus::Warn, e.to_string()),
+    };
+
+    #[allow(clippy::cast_precision_loss)]
+    let detail = tr!(
+        "{size} GB free",
+        size = format!("{:.1}", free_bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    );
+    let status = match DISK_MONITOR.lock().await.level(free_bytes) {
+        SpaceLevel::Ok => CheckStatus::Pass,
+        SpaceLevel::Low => CheckStatus::Warn,
+        SpaceLevel::Critical => CheckStatus::Fail,
+    };
+
+    Check::new(name, status, detail)
+}
+
+/// Checks the optional tools are installed, and `rclone` when rips are uploaded
+async fn tool_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    // Each tool, how to ask it for its version, and what it is needed for
+    let optional_tools = [
+        ("ffmpeg", "-version", tr!("Previewing titles")),
+        ("ffprobe", "-version", tr!("Summing up the quality of rips")),
+        ("mkvmerge", "--version", tr!("Splitting titles by chapters")),
+        (
+            "mkvpropedit",
+            "--version",
+            tr!("Writing rip notes into files"),
+        ),
+        (
+            "mkvextract",
+            "--version",
+            tr!("Extracting forced subtitles"),
+        ),
+        ("cdparanoia", "--version", tr!("Ripping audio CDs")),
+        ("flac", "--version", tr!("Ripping audio CDs")),
+        (
+            "ddrescue",
+            "--version",
+            tr!("Archiving data discs with retries"),
+        ),
+    ];
+
+    for (tool, version_arg, feature) in optional_tools {
+        checks.push(if tool_installed(tool, version_arg).await {
+            Check::new(tool.to_string(), CheckStatus::Pass, tr!("Installed"))
+        } else {
+            Check::new(
+                tool.to_string(),
+                CheckStatus::Warn,
+                tr!("Not installed, needed for: {feature}", feature = feature),
+            )
+        });
+    }
+
+    let uploads = {
+        let uploader = UPLOADER.lock().await;
+        uploader.movies.is_some() || uploader.shows.is_some()
+    };
+    if uploads {
+        checks.push(if tool_installed("rclone", "version").await {
+            Check::new("rclone".to_string(), CheckStatus::Pass, tr!("Installed"))
+        } else {
+            Check::new(
+                "rclone".to_string(),
+                CheckStatus::Fail,
+                tr!("Not installed, but rips are set to be uploaded"),
+            )
+        });
+    }
+
+    checks
+}
+
+/// Whether a tool can be run, whatever it answers
+async fn tool_installed(tool: &str, version_arg: &str) -> bool {
+    tokio::process::Command::new(tool)
+        .arg(version_arg)
+        .output()
+        .await
+        .is_ok()
+}
+
+/// Checks the permissions the bot has in a channel are enough to post rips to it, and to
+/// give them threads when `--rip-threads` is on. `None` when Discord didn't send them.
+#[cfg(feature = "discord")]
+#[must_use]
+pub fn permission_checks(permissions: Option<Permissions>, threads: bool) -> Vec<Check> {
+    let mut required = vec![
+        (Permissions::VIEW_CHANNEL, "View Channel"),
+        (Permissions::SEND_MESSAGES, "Send Messages"),
+        (Permissions::EMBED_LINKS, "Embed Links"),
+    ];
+    if threads {
+        required.push((Permissions::CREATE_PUBLIC_THREADS, "Create Public Threads"));
+        required.push((
+            Permissions::SEND_MESSAGES_IN_THREADS,
+            "Send Messages in Threads",
+        ));
+    }
+
+    let Some(permissions) = permissions else {
+        return vec![Check::new(
+            tr!("Discord Permissions"),
+            CheckStatus::Warn,
+            tr!("Discord didn't say what the bot can do in this channel"),
+        )];
+    };
+
+    let missing: Vec<&str> = required
+        .into_iter()
+        .filter(|(permission, _)| !permissions.contains(*permission))
+        .map(|(_, name)| name)
+        .collect();
+
+    let check = if missing.is_empty() {
+        Check::new(
+            tr!("Discord Permissions"),
+            CheckStatus::Pass,
+            tr!("The bot can post rips to this channel"),
+        )
+    } else {
+        Check::new(
+            tr!("Discord Permissions"),
+            CheckStatus::Fail,
+            tr!("Missing: {permissions}", permissions = missing.join(", ")),
+        )
+    };
+    vec![check]
+}
+
+/// Checks the token logs in, and the bot has been added to the guild
+#[cfg(feature = "discord")]
+pub async fn token_checks(token: &str, guild_id: Option<u64>) -> Vec<Check> {
+    let http = Http::new(token);
+
+    let user = match http.get_current_user().await {
+        Ok(user) => Check::new(
+            tr!("Discord Token"),
+            CheckStatus::Pass,
+            tr!("Logged in as {name}", name = user.name),
+        ),
+        Err(e) => {
+            return vec![Check::new(
+                tr!("Discord Token"),
+                CheckStatus::Fail,
+                e.to_string(),
+            )]
+        }
+    };
+
+    let guild = match guild_id {
+        Some(guild_id) => match http.get_guild(GuildId::new(guild_id)).await {
+            Ok(guild) => Check::new(tr!("Guild"), CheckStatus::Pass, guild.name),
+            Err(e) => Check::new(
+                tr!("Guild"),
+                CheckStatus::Fail,
+                tr!(
+                    "The bot isn't in guild {guild}: {error}",
+                    guild = guild_id,
+                    error = e
+                ),
+            ),
+        },
+        None => Check::new(
+            tr!("Guild"),
+            CheckStatus::Fail,
+            tr!("GUILD_ID isn't set, or isn't a number"),
+        ),
+    };
+
+    vec![user, guild]
+}
+
+/// Checks the token and guild id the bot was given, as `DISCORD_TOKEN` and `GUILD_ID`
+#[cfg(feature = "discord")]
+async fn secret_token_checks() -> Vec<Check> {
+    let guild_id = crate::container::secret("GUILD_ID")
+        .ok()
+        .flatten()
+        .and_then(|guild| guild.parse().ok());
+    let token_path = crate::settings::SETTINGS.lock().await.token_path.clone();
+    match crate::container::discord_token(token_path.as_deref()) {
+        Ok(Some(token)) => token_checks(&token, guild_id).await,
+        Ok(None) => vec![Check::new(
+            tr!("Discord Token"),
+            CheckStatus::Fail,
+            tr!("DISCORD_TOKEN isn't set"),
+        )],
+        Err(e) => vec![Check::new(
+            tr!("Discord Token"),
+            CheckStatus::Fail,
+            e.to_string(),
+        )],
+    }
+}
+
+/// Runs every check that doesn't need a channel and logs them, for `--self-test`. With the
+/// `discord` feature, this includes checking the Discord token, which calls the Discord
+/// API. Returns whether none failed.
+pub async fn self_test(output_dir: &Path) -> bool {
+    let checks = run_checks(output_dir).await;
+    #[cfg(feature = "discord")]
+    let checks = [checks, secret_token_checks().await].concat();
+
+    for check in &checks {
+        match check.status {
+            CheckStatus::Pass => info!("[PASS] {}: {}", check.name, check.detail),
+            CheckStatus::Warn => warn!("[WARN] {}: {}", check.name, check.detail),
+            CheckStatus::Fail => error!("[FAIL] {}: {}", check.name, check.detail),
+        }
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+    if failed == 0 {
+        info!("Self test passed");
+    } else {
+        error!("Self test failed, {} check(s) failed", failed);
+    }
+
+    failed == 0
+}
+
+#[cfg(all(test, feature = "discord"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_missing_permissions() {
+        let permissions = Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES;
+
+        let checks = permission_checks(Some(permissions), false);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+        assert!(checks[0].detail.contains("Embed Links"));
+
+        let checks = permission_checks(Some(permissions | Permissions::EMBED_LINKS), false);
+        assert_eq!(checks[0].status, CheckStatus::Pass);
+
+        // Threads need permissions of their own
+        let checks = permission_checks(Some(permissions | Permissions::EMBED_LINKS), true);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+    }
+}
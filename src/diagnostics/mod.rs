@@ -0,0 +1,5 @@
+pub mod diagnostics_core;
+
+#[cfg(feature = "discord")]
+pub use diagnostics_core::{permission_checks, token_checks};
+pub use diagnostics_core::{run_checks, self_test, Check, CheckStatus};
@@ -0,0 +1,298 @@
+//! # Digest Core Module
+//!
+//! Digitizing a family's shelf of discs takes months, and it is easy to lose track of how
+//! far along it is. This module sums up each week, what was ripped, who ripped the most,
+//! what failed and what is still wanted, for the bot to post as a weekly digest.
+//!
+//! ## Overview
+//!
+//! - **`Digest`**: What happened over a week.
+//!
+//! - **`summarize`**: Sums up a week from the history, audit log and collection.
+//!
+//! - **`weekly_digest`**: Sums up the last week from the global history, audit log and
+//!   collection.
+//!
+//! - **`DigestSchedule`**: When the last digest was posted, stored as JSON in the data
+//!   directory so a restart doesn't post another one early.
+//!
+//! - **`DIGEST_SCHEDULE`**: A globally accessible, thread-safe instance of
+//!   `DigestSchedule`.
+//!
+//! ## Notes
+//!
+//! - Rips are counted from the history, files found by `/import_library` aren't rips. The
+//!   size is of the files as they are now, rips deleted since don't count.
+//! - Requesters and failures are counted from the audit log, by rip job, since the history
+//!   only keeps rips that were saved and not who asked for them.
+//! - The first digest is posted as soon as the bot starts, then every `DIGEST_INTERVAL`
+//!   after the last one was posted.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::audit::{AuditAction, AuditEntry, AuditFilter, AuditOutcome, AUDIT_LOG};
+use crate::collection::{CollectionEntry, COLLECTION};
+use crate::history::{history_core::now, HistoryEntry, HISTORY};
+use crate::{debug, info, trace, warn};
+
+/// How long a digest covers, and how long after one the next is posted, in seconds
+pub const DIGEST_INTERVAL: u64 = 7 * 24 * 60 * 60;
+
+/// The name of the schedule file within the data directory
+const DIGEST_FILE: &str = "digest.json";
+
+/// A globally accessible instance of `DigestSchedule` for posting digests once a week.
+pub static DIGEST_SCHEDULE: LazyLock<Mutex<DigestSchedule>> = LazyLock::new(Mutex::default);
+
+/// What happened over a week.
+///
+/// # Fields
+///
+/// * `since` - When the week started, in seconds since the unix epoch.
+/// * `rips` - The number of titles ripped.
+/// * `bytes` - The size of the files they saved.
+/// * `top_requester` - The name of whoever started the most rip jobs, and how many.
+/// * `failures` - The number of rip jobs that failed.
+/// * `collected` - The number of discs marked done in the collection.
+/// * `wanted` - The titles of the discs still wanted, in the order they were added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Digest {
+    pub since: u64,
+    pub rips: usize,
+    pub bytes: u64,
+    pub top_requester: Option<(String, usize)>,
+    pub failures: usize,
+    pub collected: usize,
+    pub wanted: Vec<String>,
+}
+
+/// Sums up what happened since `since`. `audit` are the audit entries of the week.
+#[must_use]
+pub fn summarize(
+    history: &[HistoryEntry],
+    audit: &[AuditEntry],
+    collection: &[CollectionEntry],
+    since: u64,
+) -> Digest {
+    let ripped: Vec<&HistoryEntry> = history
+        .iter()
+        .filter(|entry| entry.timestamp >= since && !entry.imported)
+        .collect();
+    let bytes = ripped
+        .iter()
+        .flat_map(|entry| &entry.files)
+        .filter_map(|file| std::fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    // Counted by id, names can change during the week. Audit entries are newest first,
+    // so the name kept is the latest
+    let mut requesters: HashMap<u64, (&str, usize)> = HashMap::new();
+    let mut failures = 0;
+    for entry in audit
+        .iter()
+        .filter(|entry| entry.action == AuditAction::Rip)
+    {
+        requesters
+            .entry(entry.user_id)
+            .or_insert((&entry.user_name, 0))
+            .1 += 1;
+        if matches!(entry.outcome, AuditOutcome::Failed(_)) {
+            failures += 1;
+        }
+    }
+    // Ties go to the lowest id, so the same week always has the same top requester
+    let top_requester = requesters
+        .into_iter()
+        .max_by_key(|(user_id, (_, count))| (*count, std::cmp::Reverse(*user_id)))
+        .map(|(_, (name, count))| (name.to_string(), count));
+
+    Digest {
+        since,
+        rips: ripped.len(),
+        bytes,
+        top_requester,
+        failures,
+        collected: collection
+            .iter()
+            .filter(|entry| entry.done.is_some_and(|done| done >= since))
+            .count(),
+        wanted: collection
+            .iter()
+            .filter(|entry| entry.done.is_none())
+            .map(|entry| entry.title.clone())
+            .collect(),
+    }
+}
+
+/// Sums up the last `DIGEST_INTERVAL` from the history, audit log and collection. An
+/// audit log that can't be read is only logged, the rest of the digest is still worth
+/// posting.
+pub async fn weekly_digest() -> Digest {
+    let since = now().saturating_sub(DIGEST_INTERVAL);
+
+    let filter = AuditFilter {
+        from: Some(since),
+        ..AuditFilter::default()
+    };
+    let audit = AUDIT_LOG.lock().await.entries(&filter).unwrap_or_else(|e| {
+        warn!("Failed to read the audit log for the digest: {}", e);
+        Vec::new()
+    });
+
+    let history = HISTORY.lock().await;
+    let collection = COLLECTION.lock().await;
+    summarize(history.entries(), &audit, collection.entries(), since)
+}
+
+/// When the last digest was posted.
+///
+/// # Fields
+///
+/// * `last_posted` - When the last digest was posted, in seconds since the unix epoch.
+///   `None` if none has been yet.
+/// * `path` - Where the schedule is saved, empty until `init` has been called.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DigestSchedule {
+    last_posted: Option<u64>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl DigestSchedule {
+    /// Loads when the last digest was posted. A file that can't be read is only logged, at
+    /// worst a digest is posted early.
+    pub fn init(&mut self, data_dir: &Path) {
+        let path = data_dir.join(DIGEST_FILE);
+
+        if path.exists() {
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    serde_json::from_str::<DigestSchedule>(&contents).map_err(|e| e.to_string())
+                });
+            match loaded {
+                Ok(loaded) => {
+                    self.last_posted = loaded.last_posted;
+                    debug!("Last digest was posted at {:?}", self.last_posted);
+                }
+                Err(e) => warn!("Failed to load the digest schedule: {}", e),
+            }
+        }
+
+        self.path = path;
+
+        info!("Digest schedule initialized successfully!");
+    }
+
+    /// Whether a digest is due at `now`
+    #[must_use]
+    pub fn is_due(&self, now: u64) -> bool {
+        self.last_posted
+            .is_none_or(|last_posted| now >= last_posted + DIGEST_INTERVAL)
+    }
+
+    /// Records a digest posted at `now`. Failing to save is only logged, at worst the next
+    /// digest is posted early.
+    pub fn posted(&mut self, now: u64) {
+        self.last_posted = Some(now);
+
+        // The schedule is only kept in memory until init has been called
+        if self.path.as_os_str().is_empty() {
+            return;
+        }
+
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| std::fs::write(&self.path, contents).map_err(|e| e.to_string()));
+
+        match result {
+            Ok(()) => trace!("Saved the digest schedule to {}", self.path.display()),
+            Err(e) => warn!("Failed to save the digest schedule: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rip(timestamp: u64, imported: bool) -> HistoryEntry {
+        HistoryEntry {
+            year: Some(1995),
+            imported,
+            ..HistoryEntry::test("Heat", timestamp)
+        }
+    }
+
+    fn job(user_id: u64, user_name: &str, outcome: AuditOutcome) -> AuditEntry {
+        AuditEntry {
+            time: 150,
+            user_id,
+            user_name: user_name.to_string(),
+            command: "rip".to_string(),
+            action: AuditAction::Rip,
+            parameters: "Heat (1995) on drive 0".to_string(),
+            outcome,
+        }
+    }
+
+    fn disc(title: &str, done: Option<u64>) -> CollectionEntry {
+        CollectionEntry {
+            id: 1,
+            title: title.to_string(),
+            label: None,
+            added_by: "mom".to_string(),
+            added: 0,
+            done,
+        }
+    }
+
+    #[test]
+    fn sums_up_the_week() {
+        let history = [rip(50, false), rip(120, false), rip(130, true)];
+        let audit = [
+            job(2, "kid", AuditOutcome::Succeeded),
+            job(
+                1,
+                "dad",
+                AuditOutcome::Failed("Disc is scratched".to_string()),
+            ),
+            job(2, "kiddo", AuditOutcome::Succeeded),
+        ];
+        let collection = [
+            disc("Heat", Some(120)),
+            disc("Ran", None),
+            disc("Alien", Some(10)),
+            disc("Brazil", None),
+        ];
+
+        let digest = summarize(&history, &audit, &collection, 100);
+        assert_eq!(digest.rips, 1);
+        assert_eq!(digest.top_requester, Some(("kid".to_string(), 2)));
+        assert_eq!(digest.failures, 1);
+        assert_eq!(digest.collected, 1);
+        assert_eq!(digest.wanted, vec!["Ran".to_string(), "Brazil".to_string()]);
+    }
+
+    #[test]
+    fn posts_once_a_week() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut schedule = DigestSchedule::default();
+        schedule.init(dir.path());
+        assert!(schedule.is_due(1000));
+        schedule.posted(1000);
+
+        let mut restarted = DigestSchedule::default();
+        restarted.init(dir.path());
+        assert!(!restarted.is_due(1000 + DIGEST_INTERVAL - 1));
+        assert!(restarted.is_due(1000 + DIGEST_INTERVAL));
+    }
+}
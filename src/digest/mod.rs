@@ -0,0 +1,3 @@
+pub mod digest_core;
+
+pub use digest_core::{weekly_digest, Digest, DIGEST_SCHEDULE};
@@ -0,0 +1,99 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, Http};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::disk::{disk_core::SpaceChange, SpaceLevel, DISK_MONITOR};
+use crate::{debug, error, tr, warn};
+
+/// How often the free space of the watched directories is checked
+const DISK_CHECK_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Watches the free space of the output directories, posting an embed to the alert
+/// channel whenever a directory crosses one of the thresholds.
+///
+/// Runs for the lifetime of the bot. When no alert channel is configured the changes are
+/// only logged.
+pub async fn watch_disk_space(http: Arc<Http>, channel: Option<ChannelId>) {
+    debug!("Started watching disk space");
+
+    let mut interval = tokio::time::interval(DISK_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let changes = DISK_MONITOR.lock().await.check().await;
+
+        for change in changes {
+            log_space_change(&change);
+
+            let Some(channel) = channel else {
+                continue;
+            };
+
+            if let Err(e) = channel
+                .send_message(&http, CreateMessage::new().embed(space_embed(&change)))
+                .await
+            {
+                error!("Failed to send disk space alert: {:?}", e);
+            }
+        }
+    }
+}
+
+fn log_space_change(change: &SpaceChange) {
+    let free_gb = to_gb(change.free_bytes);
+    match change.level {
+        SpaceLevel::Ok => debug!(
+            "{} is back to {:.1} GB free",
+            change.path.display(),
+            free_gb
+        ),
+        SpaceLevel::Low => warn!(
+            "{} is low on space, {:.1} GB free",
+            change.path.display(),
+            free_gb
+        ),
+        SpaceLevel::Critical => error!(
+            "{} is critically low on space, {:.1} GB free, new rips are blocked",
+            change.path.display(),
+            free_gb
+        ),
+    }
+}
+
+/// Builds the embed posted when a directory crosses a threshold
+fn space_embed(change: &SpaceChange) -> CreateEmbed {
+    let (title, description) = match change.level {
+        SpaceLevel::Ok => (
+            tr!("Disk Space Recovered"),
+            tr!("There is enough free space to rip again."),
+        ),
+        SpaceLevel::Low => (
+            tr!("Low Disk Space"),
+            tr!("Free space is running low, consider clearing out some rips."),
+        ),
+        SpaceLevel::Critical => (
+            tr!("Disk Space Critical"),
+            tr!("New rips are blocked until more space is freed."),
+        ),
+    };
+
+    themed_embed()
+        .title(title)
+        .description(description)
+        .field(tr!("Directory"), display_path(&change.path), false)
+        .field(
+            tr!("Free Space"),
+            format!("{:.1} GB", to_gb(change.free_bytes)),
+            true,
+        )
+}
+
+fn display_path(path: &Path) -> String {
+    format!("`{}`", path.display())
+}
+
+fn to_gb(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+}
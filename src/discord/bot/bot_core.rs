@@ -1,59 +1,264 @@
 pub struct DiscordHandler;
 
-use std::env;
+use std::{
+    env,
+    sync::{Arc, Once},
+};
 
-use serenity::all::GuildId;
+use serenity::all::{
+    ChannelId, CreateCommand, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    CreateInteractionResponseMessage, GuildId, Http,
+};
 use serenity::async_trait;
-use serenity::model::{application::Interaction, gateway::Ready};
+use serenity::http::RatelimitInfo;
+use serenity::model::{application::Interaction, event::ResumedEvent, gateway::Ready};
 use serenity::prelude::*;
 
+use crate::discord::bot::{
+    alerts, controls, digest, edits, flows, incoming, ops_log, progress, updates,
+};
+use crate::discord::components::{custom_ids, error_embeds::error_embed, modals};
 use crate::discord::errors::DiscordError;
 use crate::discord::{commands, errors::Result};
-use crate::{debug, error, info, trace};
+use crate::makemkv::MakeMkv;
+use crate::settings::SETTINGS;
+use crate::{debug, error, info, trace, warn};
+
+/// Keys the `MakeMkv` the bot rips with in the client's data, commands get it with
+/// `command_helpers::makemkv`
+pub struct MakeMkvKey;
+
+impl TypeMapKey for MakeMkvKey {
+    type Value = Arc<MakeMkv>;
+}
+
+/// What the bot is sent by Discord besides interactions, which come without any intents.
+/// Nothing else is needed yet, features that read messages or follow guild members add
+/// their intents here. Privileged ones, such as `MESSAGE_CONTENT`, also have to be turned
+/// on for the bot in the Discord developer portal or the bot can't connect.
+pub const GATEWAY_INTENTS: GatewayIntents = GatewayIntents::empty();
+
+/// The slash and message commands of the bot, as they are registered with Discord
+#[must_use]
+pub fn slash_commands() -> Vec<CreateCommand> {
+    vec![
+        commands::rip::register(),
+        commands::quickrip::register(),
+        commands::retry_rip::register(),
+        commands::view_drives::register(),
+        commands::eject_disc::register(),
+        commands::get_titles::register(),
+        commands::rename::register(),
+        commands::delete_rip::register(),
+        commands::queue::register(),
+        commands::drive_stats::register(),
+        commands::rescan::register(),
+        commands::import_library::register(),
+        commands::library::register(),
+        commands::status::register(),
+        commands::diagnostics::register(),
+        commands::audit::register(),
+        commands::rip_log::register(),
+        commands::export_history::register(),
+        commands::collection::register(),
+        commands::setup::register(),
+        commands::sync_commands::register(),
+    ]
+}
+
+/// Registers the commands of the bot with `guild_id`, replacing whatever was registered
+/// before, and returns how many there are now.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if Discord rejects the commands.
+pub async fn register_commands(http: &Http, guild_id: GuildId) -> Result<usize> {
+    let commands = guild_id
+        .set_commands(http, slash_commands())
+        .await
+        .map_err(|e| DiscordError::HttpRequestFailed(e.to_string()))?;
+
+    trace!("Server now has the following guild slash commands: {commands:#?}");
+    info!("Registered {} commands with the server", commands.len());
+    Ok(commands.len())
+}
+
+/// Ready is sent again whenever the bot reconnects, this keeps the background tasks from
+/// being started more than once
+static START_BACKGROUND_TASKS: Once = Once::new();
 
 #[async_trait]
 impl EventHandler for DiscordHandler {
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        // Menus of flows that timed out are refused, so they can't start anything later,
+        // and double clicks are dropped, so they can't start anything twice
+        let Some(step) = flows::check(&ctx, &interaction).await else {
+            return;
+        };
+
         if let Err(err) = handle_interaction(&ctx, &interaction).await {
             error!("Error handling interaction: {:?}", err);
+            report_error(&ctx, &interaction, &err).await;
         }
+        drop(step);
+
+        if let Interaction::Command(command) = &interaction {
+            if flows::is_flow_command(&command.data.name) {
+                flows::start(&ctx, &interaction).await;
+            }
+        }
+    }
+
+    async fn ratelimit(&self, data: RatelimitInfo) {
+        edits::rate_limited(&data);
+    }
+
+    async fn resume(&self, ctx: Context, _resumed: ResumedEvent) {
+        debug!("Resumed the connection to Discord");
+        tokio::spawn(progress::recover_progress(ctx.http.clone()));
     }
 
     async fn ready(&self, ctx: Context, _ready: Ready) {
-        let guild = match env::var("GUILD_ID") {
-            Ok(guild) => match guild.parse::<u64>() {
+        // The guild can also be read from a file, such as a Docker secret
+        let guild = match crate::container::secret("GUILD_ID") {
+            Ok(Some(guild)) => match guild.parse::<u64>() {
                 Ok(guild) => guild,
                 Err(_) => {
                     error!("Invalid GUILD_ID provided, please provide a valid ID");
-                    std::process::exit(1);
+                    crate::container::exit(crate::container::Exit::Config);
                 }
             },
-            Err(_) => {
+            Ok(None) => {
                 error!("GUILD_ID environment variable not set, use the command 'export GUILD_ID=your_guild_id_here'");
-                std::process::exit(1);
+                crate::container::exit(crate::container::Exit::Config);
+            }
+            Err(e) => {
+                error!("{}", e);
+                crate::container::exit(crate::container::Exit::Config);
             }
         };
 
         let guild_id = GuildId::new(guild);
 
-        let commands = guild_id
-            .set_commands(
-                &ctx.http,
-                vec![
-                    commands::rip::register(),
-                    commands::view_drives::register(),
-                    commands::eject_disc::register(),
-                    commands::get_titles::register(),
-                ],
-            )
-            .await;
-
-        trace!("Server now has the following guild slash commands: {commands:#?}");
+        if let Err(e) = register_commands(&ctx.http, guild_id).await {
+            error!("Failed to register the commands: {}", e);
+        }
+
+        // Alerts are optional, without a channel they are only logged. The channel picked
+        // with /setup is used unless one is given in the environment
+        let alert_channel = match env::var("ALERT_CHANNEL_ID").map(|id| id.parse::<u64>()) {
+            Ok(Ok(id)) => Some(ChannelId::new(id)),
+            Ok(Err(_)) => {
+                warn!("Invalid ALERT_CHANNEL_ID provided, alerts will only be logged");
+                None
+            }
+            Err(_) => SETTINGS.lock().await.alert_channel_id.map(ChannelId::new),
+        };
+
+        // Warnings and errors are only mirrored when an ops channel is set
+        let ops_channel = match env::var("OPS_CHANNEL_ID").map(|id| id.parse::<u64>()) {
+            Ok(Ok(id)) => Some(ChannelId::new(id)),
+            Ok(Err(_)) => {
+                warn!("Invalid OPS_CHANNEL_ID provided, warnings and errors will only be logged");
+                None
+            }
+            Err(_) => None,
+        };
+
+        // Digests are only posted when a channel is set for them
+        let digest_channel = match env::var("DIGEST_CHANNEL_ID").map(|id| id.parse::<u64>()) {
+            Ok(Ok(id)) => Some(ChannelId::new(id)),
+            Ok(Err(_)) => {
+                warn!("Invalid DIGEST_CHANNEL_ID provided, no weekly digest will be posted");
+                None
+            }
+            Err(_) => None,
+        };
+
+        // New releases are announced where alerts go, unless the check was opted out of
+        let update_check = !SETTINGS.lock().await.skip_update_check;
+
+        // Imports are announced where alerts go, the bot only watches when asked to
+        let makemkv = ctx.data.read().await.get::<MakeMkvKey>().cloned();
+        let watching = crate::watch::WATCH_FOLDER.lock().await.dir().is_some();
+
+        START_BACKGROUND_TASKS.call_once(|| {
+            tokio::spawn(alerts::watch_disk_space(ctx.http.clone(), alert_channel));
+            if let (Some(makemkv), true) = (makemkv, watching) {
+                tokio::spawn(incoming::watch_incoming(
+                    ctx.http.clone(),
+                    alert_channel,
+                    makemkv,
+                ));
+            }
+            tokio::spawn(flows::expire_flows(ctx.clone()));
+            if let Some(ops_channel) = ops_channel {
+                tokio::spawn(ops_log::mirror_logs(ctx.http.clone(), ops_channel));
+            }
+            if let Some(digest_channel) = digest_channel {
+                tokio::spawn(digest::post_digests(ctx.http.clone(), digest_channel));
+            }
+            if update_check {
+                tokio::spawn(updates::check_for_updates(ctx.http.clone(), alert_channel));
+            }
+        });
+
+        // Progress messages nothing edits anymore, after a restart or a lost connection
+        tokio::spawn(progress::recover_progress(ctx.http.clone()));
+
+        crate::container::set_ready();
+        crate::container::systemd::notify("READY=1\nSTATUS=Connected to Discord");
+
         info!("The Discord bot has initialized successfully!");
         info!("Server is running...");
     }
 }
 
+/// Shows an error that wasn't already shown to whoever used the interaction. It is sent
+/// as an ephemeral message, so the message the interaction came from is left as it was.
+async fn report_error(ctx: &Context, interaction: &Interaction, err: &DiscordError) {
+    if err.is_reported() {
+        return;
+    }
+
+    let embed = error_embed(err);
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .ephemeral(true)
+            .embed(embed.clone()),
+    );
+    let followup = CreateInteractionResponseFollowup::new()
+        .ephemeral(true)
+        .embed(embed);
+
+    // Interactions that were already responded to, such as deferred ones, take a followup
+    let result = match interaction {
+        Interaction::Command(command) => {
+            if command.create_response(&ctx.http, response).await.is_ok() {
+                return;
+            }
+            command.create_followup(&ctx.http, followup).await
+        }
+        Interaction::Component(component) => {
+            if component.create_response(&ctx.http, response).await.is_ok() {
+                return;
+            }
+            component.create_followup(&ctx.http, followup).await
+        }
+        Interaction::Modal(modal) => {
+            if modal.create_response(&ctx.http, response).await.is_ok() {
+                return;
+            }
+            modal.create_followup(&ctx.http, followup).await
+        }
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!("Failed to send error message: {:?}", e);
+    }
+}
+
 pub async fn handle_interaction(ctx: &Context, interaction: &Interaction) -> Result<()> {
     trace!("Received interaction: {:?}", interaction);
     match interaction {
@@ -63,6 +268,16 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction) -> Res
                 commands::rip::run(ctx, interaction).await?;
                 Ok(())
             }
+            "quickrip" => {
+                trace!("Got quickrip command");
+                commands::quickrip::run(ctx, interaction).await?;
+                Ok(())
+            }
+            commands::retry_rip::RETRY_RIP_COMMAND => {
+                trace!("Got retry rip message command");
+                commands::retry_rip::run(ctx, interaction).await?;
+                Ok(())
+            }
             "view_drives" => {
                 trace!("Got view_drives command");
                 commands::view_drives::run(ctx, interaction).await;
@@ -78,12 +293,101 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction) -> Res
                 commands::get_titles::run(ctx, interaction).await;
                 Ok(())
             }
+            "rename" => {
+                trace!("Got rename command");
+                commands::rename::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "delete_rip" => {
+                trace!("Got delete_rip command");
+                commands::delete_rip::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "queue" => {
+                trace!("Got queue command");
+                commands::queue::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "drive_stats" => {
+                trace!("Got drive_stats command");
+                commands::drive_stats::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "rescan" => {
+                trace!("Got rescan command");
+                commands::rescan::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "import_library" => {
+                trace!("Got import_library command");
+                commands::import_library::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "library" => {
+                trace!("Got library command");
+                commands::library::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "status" => {
+                trace!("Got status command");
+                commands::status::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "diagnostics" => {
+                trace!("Got diagnostics command");
+                commands::diagnostics::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "audit" => {
+                trace!("Got audit command");
+                commands::audit::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "rip_log" => {
+                trace!("Got rip_log command");
+                commands::rip_log::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "export_history" => {
+                trace!("Got export_history command");
+                commands::export_history::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "collection" => {
+                trace!("Got collection command");
+                commands::collection::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "setup" => {
+                trace!("Got setup command");
+                commands::setup::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "sync_commands" => {
+                trace!("Got sync_commands command");
+                commands::sync_commands::run(ctx, interaction).await?;
+                Ok(())
+            }
             _ => {
                 debug!("Unknown command: {}, ignoring", command.data.name);
                 return Err(DiscordError::InvalidInteractionCall);
             }
         },
-        Interaction::Component(component) => match component.data.custom_id.as_str() {
+        // Options suggested as they are typed
+        Interaction::Autocomplete(command) => {
+            if command.data.name == "quickrip" {
+                trace!("Got quickrip autocomplete");
+                commands::quickrip::run(ctx, interaction).await?;
+            } else if command.data.name == "rip_log" {
+                trace!("Got rip_log autocomplete");
+                commands::rip_log::run(ctx, interaction).await?;
+            } else {
+                debug!("Unknown autocomplete: {}, ignoring", command.data.name);
+            }
+            Ok(())
+        }
+        // Components of a job carry its id after their name, they are routed by name
+        Interaction::Component(component) => match custom_ids::name(&component.data.custom_id) {
             "select_disc_to_grab_titles" => {
                 trace!("Got select_disc_to_grab_titles component");
                 commands::get_titles::run(ctx, interaction).await;
@@ -104,6 +408,16 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction) -> Res
                 commands::rip::run(ctx, interaction).await?;
                 Ok(())
             }
+            "lookup_barcode" => {
+                trace!("Got lookup_barcode component");
+                commands::lookup_barcode::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "select_rip_preset" => {
+                trace!("Got select_rip_preset component");
+                commands::rip::run(ctx, interaction).await?;
+                Ok(())
+            }
             "select_titles_to_rip" => {
                 trace!("Got select_titles_to_rip component");
                 commands::rip::run(ctx, interaction).await?;
@@ -114,17 +428,111 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction) -> Res
                 commands::rip::run(ctx, interaction).await?;
                 Ok(())
             }
-            "cancel_rip" => {
-                trace!("Got cancel_rip component");
+            "confirm_rip"
+            | "edit_rip"
+            | "edit_episodes"
+            | "split_by_chapters"
+            | "toggle_eject_after_rip"
+            | "add_note"
+            | "toggle_3d"
+            | "select_library"
+            | "cancel_pending_rip"
+            | "rerip_suspicious"
+            | "resume_rips" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::rip::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "map_episodes" | "map_episode" | "map_episodes_back" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::map_episodes::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "filter_titles_20" | "filter_titles_40" | "filter_titles_all" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::filter_titles::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "sort_titles_by_number" | "sort_titles_by_duration" | "sort_titles_by_size" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::get_titles::sort(ctx, interaction).await?;
+                Ok(())
+            }
+            "fix_modal_input" => modals::reopen(ctx, component).await,
+            "preview_titles" => {
+                trace!("Got preview_titles component");
+                commands::preview_titles::run(ctx, interaction).await?;
                 Ok(())
             }
+            "archive_disc" => {
+                trace!("Got archive_disc component");
+                commands::archive_disc::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "audio_rip" | "confirm_audio_rip" | "edit_audio_rip" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::rip_audio::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "retry_failed_rips" => {
+                trace!("Got retry_failed_rips component");
+                commands::rip::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "select_rip_to_rename" => {
+                trace!("Got select_rip_to_rename component");
+                commands::rename::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "fix_library" => {
+                trace!("Got fix_library component");
+                commands::library::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "select_rip_to_delete" | "confirm_delete_rip" | "cancel_delete_rip" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::delete_rip::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "bump_queued_job" | "resume_batch" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::queue::run(ctx, interaction).await?;
+                Ok(())
+            }
+            "setup_layout"
+            | "setup_alert_channel"
+            | "setup_exempt_roles"
+            | "save_setup"
+            | "cancel_setup" => {
+                trace!("Got {} component", component.data.custom_id);
+                commands::setup::run(ctx, interaction).await?;
+                Ok(())
+            }
+            // Handed to the running job they are for
+            "cancel_rip" | "cancel_episode" | "skip_title" => {
+                trace!("Got {} component", component.data.custom_id);
+                let job_id = custom_ids::split(&component.data.custom_id)
+                    .1
+                    .and_then(|id| id.parse::<u64>().ok());
+                match job_id.map(|job_id| controls::route(job_id, component)) {
+                    Some(controls::Route::Handed) => Ok(()),
+                    Some(controls::Route::NotOwner) => Err(DiscordError::NotOwner),
+                    Some(controls::Route::NotRunning) | None => Err(DiscordError::JobNotRunning),
+                }
+            }
             _ => {
                 debug!("Unknown component: {}, ignoring", component.data.custom_id);
                 Ok(())
             }
         },
         Interaction::Modal(modal) => {
-            match modal.data.custom_id.as_str() {
+            // Bad input is sent back to be fixed before any command sees it
+            let problems = modals::validate(modal);
+            if !problems.is_empty() {
+                return modals::reprompt(ctx, modal, problems).await;
+            }
+
+            match custom_ids::name(&modal.data.custom_id) {
                 "get_title_of_movie_rip" => {
                     trace!("Got get_title_of_movie_rip modal");
                     commands::rip::run(ctx, interaction).await?;
@@ -133,6 +541,42 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction) -> Res
                     trace!("Got get_title_of_show_rip modal");
                     commands::rip::run(ctx, interaction).await?;
                 }
+                "get_barcode" => {
+                    trace!("Got get_barcode modal");
+                    commands::lookup_barcode::run(ctx, interaction).await?;
+                }
+                "get_episode_start" => {
+                    trace!("Got get_episode_start modal");
+                    commands::rip::run(ctx, interaction).await?;
+                }
+                "get_chapter_split" => {
+                    trace!("Got get_chapter_split modal");
+                    commands::rip::run(ctx, interaction).await?;
+                }
+                "get_rip_note" => {
+                    trace!("Got get_rip_note modal");
+                    commands::rip::run(ctx, interaction).await?;
+                }
+                "get_extra_names" => {
+                    trace!("Got get_extra_names modal");
+                    commands::rip::run(ctx, interaction).await?;
+                }
+                "get_archive_details" => {
+                    trace!("Got get_archive_details modal");
+                    commands::archive_disc::run(ctx, interaction).await?;
+                }
+                "get_audio_details" => {
+                    trace!("Got get_audio_details modal");
+                    commands::rip_audio::run(ctx, interaction).await?;
+                }
+                "get_rename_details" => {
+                    trace!("Got get_rename_details modal");
+                    commands::rename::run(ctx, interaction).await?;
+                }
+                "get_setup_templates" => {
+                    trace!("Got get_setup_templates modal");
+                    commands::setup::run(ctx, interaction).await?;
+                }
                 _ => {
                     debug!("Unknown modal: {}, ignoring", modal.data.custom_id);
                     return Err(DiscordError::InvalidInteractionCall);
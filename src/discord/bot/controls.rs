@@ -0,0 +1,144 @@
+//! # Controls
+//!
+//! The buttons on the progress message of a running job, such as cancelling a rip or
+//! skipping a title. Their custom ids carry the id of the job, `cancel_rip:42`, and
+//! `handle_interaction` hands each click to the job it is for, which listens for them
+//! while it runs.
+//!
+//! ## Overview
+//!
+//! - **`CONTROLS`**: Which jobs are listening for their controls, and on which message.
+//!
+//! - **`listen`**: Starts taking the controls of a job, until the `Controls` it returns is
+//!   dropped.
+//!
+//! - **`route`**: Hands a click to the job it is for.
+//!
+//! ## Notes
+//!
+//! - Job ids start over when the bot restarts, so a click is only handed to a job if it
+//!   came from that job's own message, a button left over from before can't reach a new job
+//!   that got the same id.
+//! - Clicks for a job that isn't running are refused, the user is told it has finished.
+//! - Only whoever started a job, or an administrator, can use its controls.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use serenity::all::{ComponentInteraction, MessageId, UserId};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::discord::commands::command_helpers::is_admin;
+use crate::{debug, trace};
+
+/// A globally accessible map of the jobs listening for their controls, by job id
+pub static CONTROLS: LazyLock<Mutex<HashMap<u64, Listener>>> = LazyLock::new(Mutex::default);
+
+/// A job listening for its controls.
+///
+/// # Fields
+///
+/// * `message_id` - The message the job's controls are on.
+/// * `owner` - Who started the job.
+/// * `sender` - Where clicks are handed to the job.
+#[derive(Debug)]
+pub struct Listener {
+    message_id: MessageId,
+    owner: UserId,
+    sender: UnboundedSender<ComponentInteraction>,
+}
+
+/// What became of a click on a control
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    /// The click was handed to the job
+    Handed,
+    /// The job isn't running, or its controls aren't on the message clicked
+    NotRunning,
+    /// Someone other than whoever started the job clicked it
+    NotOwner,
+}
+
+/// The clicks on the controls of one job, which stops listening once dropped.
+///
+/// # Fields
+///
+/// * `job_id` - The job the controls are for.
+/// * `receiver` - The clicks handed to the job.
+#[derive(Debug)]
+pub struct Controls {
+    job_id: u64,
+    receiver: UnboundedReceiver<ComponentInteraction>,
+}
+
+impl Controls {
+    /// Waits for the next click on the job's controls
+    pub async fn next(&mut self) -> Option<ComponentInteraction> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for Controls {
+    fn drop(&mut self) {
+        controls().remove(&self.job_id);
+        trace!("Job {} stopped listening for its controls", self.job_id);
+    }
+}
+
+fn controls() -> std::sync::MutexGuard<'static, HashMap<u64, Listener>> {
+    CONTROLS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Starts taking the clicks on the controls of `job_id`, which are on `message_id`, from
+/// `owner` or an administrator
+#[must_use]
+pub fn listen(job_id: u64, message_id: MessageId, owner: UserId) -> Controls {
+    let (sender, receiver) = unbounded_channel();
+    controls().insert(
+        job_id,
+        Listener {
+            message_id,
+            owner,
+            sender,
+        },
+    );
+    trace!("Job {} is listening for its controls", job_id);
+
+    Controls { job_id, receiver }
+}
+
+/// Hands a click on a control to `job_id`, if the job is listening for clicks from the
+/// message it came from and whoever clicked it may use it
+#[must_use]
+pub fn route(job_id: u64, component: &ComponentInteraction) -> Route {
+    let controls = controls();
+    let Some(listener) = controls.get(&job_id) else {
+        debug!("Job {} isn't running, refusing its controls", job_id);
+        return Route::NotRunning;
+    };
+
+    if listener.message_id != component.message.id {
+        debug!(
+            "Control of job {} came from message {} rather than its own, refusing it",
+            job_id, component.message.id
+        );
+        return Route::NotRunning;
+    }
+
+    if component.user.id != listener.owner && !is_admin(component.member.as_ref()) {
+        debug!(
+            "{} tried to use the controls of job {}, which isn't theirs",
+            component.user.name, job_id
+        );
+        return Route::NotOwner;
+    }
+
+    match listener.sender.send(component.clone()) {
+        Ok(()) => Route::Handed,
+        Err(_) => Route::NotRunning,
+    }
+}
@@ -0,0 +1,97 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, Http};
+
+use crate::digest::{weekly_digest, Digest, DIGEST_SCHEDULE};
+use crate::discord::components::embeds::themed_embed;
+use crate::history::history_core::now;
+use crate::{debug, error, info, tr};
+
+/// How often the bot checks whether a digest is due
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_hours(1);
+
+/// The most wanted titles listed, the rest are only counted
+const MAX_WANTED_SHOWN: usize = 10;
+
+/// Posts a digest of the last week to the digest channel once a week.
+///
+/// Runs for the lifetime of the bot. A digest that fails to post is tried again on the
+/// next check.
+pub async fn post_digests(http: Arc<Http>, channel: ChannelId) {
+    debug!("Started posting weekly digests to {}", channel);
+
+    let mut interval = tokio::time::interval(DIGEST_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if !DIGEST_SCHEDULE.lock().await.is_due(now()) {
+            continue;
+        }
+
+        let digest = weekly_digest().await;
+        match channel
+            .send_message(&http, CreateMessage::new().embed(digest_embed(&digest)))
+            .await
+        {
+            Ok(_) => {
+                info!("Posted the weekly digest, {} rip(s)", digest.rips);
+                DIGEST_SCHEDULE.lock().await.posted(now());
+            }
+            Err(e) => error!("Failed to post the weekly digest: {:?}", e),
+        }
+    }
+}
+
+/// Builds the embed of a week's digest
+fn digest_embed(digest: &Digest) -> CreateEmbed {
+    let top_requester = digest.top_requester.as_ref().map_or_else(
+        || tr!("Nobody ripped anything"),
+        |(name, jobs)| tr!("{name}, {jobs} rip job(s)", name = name, jobs = jobs),
+    );
+
+    let wanted = if digest.wanted.is_empty() {
+        tr!("Nothing, the collection is all ripped!")
+    } else {
+        let mut shown: Vec<String> = digest
+            .wanted
+            .iter()
+            .take(MAX_WANTED_SHOWN)
+            .map(|title| format!("- {title}"))
+            .collect();
+        if digest.wanted.len() > MAX_WANTED_SHOWN {
+            shown.push(tr!(
+                "and {count} more",
+                count = digest.wanted.len() - MAX_WANTED_SHOWN
+            ));
+        }
+        shown.join("\n")
+    };
+
+    themed_embed()
+        .title(tr!("Weekly Digest"))
+        .description(tr!(
+            "Here's how ripping went since <t:{since}:D>.",
+            since = digest.since
+        ))
+        .field(tr!("Rips"), digest.rips.to_string(), true)
+        .field(tr!("Total Size"), format_size(digest.bytes), true)
+        .field(tr!("Failures"), digest.failures.to_string(), true)
+        .field(tr!("Top Requester"), top_requester, false)
+        .field(
+            tr!("Collection"),
+            tr!(
+                "{collected} disc(s) ticked off this week, {wanted} still wanted.",
+                collected = digest.collected,
+                wanted = digest.wanted.len()
+            ),
+            false,
+        )
+        .field(tr!("Still Wanted"), wanted, false)
+}
+
+/// Formats bytes as gigabytes with one decimal, whole numbers keep the cast out
+fn format_size(bytes: u64) -> String {
+    let tenths = bytes / (1024 * 1024 * 1024 / 10);
+    format!("{}.{} GB", tenths / 10, tenths % 10)
+}
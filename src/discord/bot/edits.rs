@@ -0,0 +1,286 @@
+//! # Edits
+//!
+//! Progress messages are edited over and over while rips run, and several rips posting to
+//! the same channel can run into Discord's rate limits, which turns every edit after into
+//! a wait or a failure. This module paces those edits, so progress slows down under
+//! pressure instead of piling up.
+//!
+//! ## Overview
+//!
+//! - **`MessageEditor`**: Edits one message, keeping only the latest edit waiting to be
+//!   sent.
+//!
+//! - **`Pacing`**: How often a channel's messages can be edited, which backs off when
+//!   Discord rate limits the bot and recovers as edits go through.
+//!
+//! - **`rate_limited`**: Told whenever Discord rate limits a request, from the event
+//!   handler.
+//!
+//! ## Notes
+//!
+//! - Edits are paced by channel, which is how Discord limits them, so rips posting to the
+//!   same channel share its edits between them.
+//! - An edit made while another is waiting replaces it, only the newest progress is worth
+//!   showing.
+//! - Waiting edits are dropped along with their `MessageEditor`, so they can't land after
+//!   the summary of a rip that has finished.
+//! - Only progress goes through here, edits that have to be seen, such as a summary, are
+//!   sent straight away.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use serenity::all::{ChannelId, EditMessage, Http, Message, MessageId};
+use serenity::http::RatelimitInfo;
+use tokio::time::Instant;
+
+use crate::{debug, trace, warn};
+
+/// The least time between edits of a channel's messages
+const MIN_EDIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The most time between edits of a channel's messages, however much pressure there is
+const MAX_EDIT_INTERVAL: Duration = Duration::from_mins(2);
+
+/// A globally accessible map of how often each channel's messages can be edited
+static PACING: LazyLock<Mutex<HashMap<ChannelId, Pacing>>> = LazyLock::new(Mutex::default);
+
+fn pacing() -> std::sync::MutexGuard<'static, HashMap<ChannelId, Pacing>> {
+    PACING
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// How often the messages of a channel can be edited.
+///
+/// # Fields
+///
+/// * `interval` - The time between edits, longer while Discord is pushing back.
+/// * `next_edit` - When the next edit can be sent, `None` if it can be straight away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacing {
+    interval: Duration,
+    next_edit: Option<Instant>,
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Pacing {
+            interval: MIN_EDIT_INTERVAL,
+            next_edit: None,
+        }
+    }
+}
+
+impl Pacing {
+    /// When the next edit can be sent
+    #[must_use]
+    pub fn ready_at(&self, now: Instant) -> Instant {
+        self.next_edit.map_or(now, |next_edit| next_edit.max(now))
+    }
+
+    /// Records an edit sent at `now`, easing back towards the shortest interval
+    pub fn sent(&mut self, now: Instant) {
+        self.next_edit = Some(now + self.interval);
+        self.interval = self
+            .interval
+            .saturating_sub(self.interval / 4)
+            .max(MIN_EDIT_INTERVAL);
+    }
+
+    /// Records an edit that failed at `now`, backing off in case Discord is struggling
+    pub fn failed(&mut self, now: Instant) {
+        self.interval = (self.interval * 2).min(MAX_EDIT_INTERVAL);
+        self.hold_until(now + self.interval);
+    }
+
+    /// Records being rate limited for `retry_after` at `now`, holding edits until it is
+    /// over and spacing them out further after
+    pub fn rate_limited(&mut self, retry_after: Duration, now: Instant) {
+        self.interval = (self.interval * 2).min(MAX_EDIT_INTERVAL);
+        self.hold_until(now + retry_after.max(self.interval));
+    }
+
+    /// Holds edits until `until`, unless they already are for longer
+    fn hold_until(&mut self, until: Instant) {
+        self.next_edit = Some(
+            self.next_edit
+                .map_or(until, |next_edit| next_edit.max(until)),
+        );
+    }
+}
+
+/// The channel a rate limited request was for, from its path such as
+/// `/channels/123/messages/456`
+fn rate_limited_channel(path: &str) -> Option<ChannelId> {
+    let (_, rest) = path.split_once("channels/")?;
+    let id: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    id.parse::<u64>()
+        .ok()
+        .filter(|id| *id > 0)
+        .map(ChannelId::new)
+}
+
+/// Backs off the edits of the channel a request was rate limited on, or of every channel
+/// when the limit is global. Called by the event handler whenever Discord rate limits a
+/// request, Discord's own retry of the request is left to the HTTP client.
+pub fn rate_limited(info: &RatelimitInfo) {
+    debug!(
+        "Rate limited for {:?} on {:?} {}",
+        info.timeout, info.method, info.path
+    );
+    let now = Instant::now();
+    let mut pacing = pacing();
+
+    if info.global {
+        for channel in pacing.values_mut() {
+            channel.rate_limited(info.timeout, now);
+        }
+    } else if let Some(channel_id) = rate_limited_channel(&info.path) {
+        pacing
+            .entry(channel_id)
+            .or_default()
+            .rate_limited(info.timeout, now);
+    }
+}
+
+/// Edits a message as fast as its channel allows, keeping only the latest edit waiting.
+///
+/// # Fields
+///
+/// * `http` - What the edits are sent with.
+/// * `channel_id` - The channel the message is in.
+/// * `message_id` - The message being edited.
+/// * `pending` - The edit waiting to be sent, if any.
+pub struct MessageEditor {
+    http: Arc<Http>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    pending: Option<EditMessage>,
+}
+
+impl MessageEditor {
+    /// Creates an editor for `message`
+    #[must_use]
+    pub fn new(http: &Arc<Http>, message: &Message) -> Self {
+        Self::for_message(http, message.channel_id, message.id)
+    }
+
+    /// Creates an editor for the message `message_id` in `channel_id`
+    #[must_use]
+    pub fn for_message(http: &Arc<Http>, channel_id: ChannelId, message_id: MessageId) -> Self {
+        MessageEditor {
+            http: Arc::clone(http),
+            channel_id,
+            message_id,
+            pending: None,
+        }
+    }
+
+    /// Queues an edit of the message, replacing the one waiting if there is one
+    pub fn queue(&mut self, edit: EditMessage) {
+        if self.pending.replace(edit).is_some() {
+            trace!(
+                "Replaced a waiting edit of message {}, the channel is busy",
+                self.message_id
+            );
+        }
+    }
+
+    /// Resolves once the waiting edit can be sent, never if there is none. Meant to be
+    /// raced against whatever else is going on, then followed by `send`.
+    pub async fn ready(&self) {
+        if self.pending.is_none() {
+            return std::future::pending().await;
+        }
+
+        // Checked again after waiting, a rate limit may have pushed it back meanwhile
+        loop {
+            let now = Instant::now();
+            let ready_at = pacing()
+                .get(&self.channel_id)
+                .map_or(now, |pacing| pacing.ready_at(now));
+            if ready_at <= now {
+                return;
+            }
+            tokio::time::sleep_until(ready_at).await;
+        }
+    }
+
+    /// Sends the waiting edit, if there is one
+    pub async fn send(&mut self) {
+        let Some(edit) = self.pending.take() else {
+            return;
+        };
+
+        // Claimed before sending, so other messages in the channel wait their turn
+        pacing()
+            .entry(self.channel_id)
+            .or_default()
+            .sent(Instant::now());
+
+        if let Err(e) = self
+            .channel_id
+            .edit_message(&self.http, self.message_id, edit)
+            .await
+        {
+            // Only informative, the progress is shown again on the next edit
+            warn!("Failed to edit message {}: {:?}", self.message_id, e);
+            pacing()
+                .entry(self.channel_id)
+                .or_default()
+                .failed(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_under_pressure_and_recovers() {
+        let now = Instant::now();
+        let mut pacing = Pacing::default();
+        assert_eq!(pacing.ready_at(now), now);
+
+        pacing.sent(now);
+        assert_eq!(pacing.ready_at(now), now + MIN_EDIT_INTERVAL);
+
+        pacing.rate_limited(Duration::from_secs(5), now);
+        assert_eq!(pacing.ready_at(now), now + Duration::from_secs(5));
+        pacing.failed(now);
+        assert_eq!(pacing.interval, MIN_EDIT_INTERVAL * 4);
+
+        for _ in 0..20 {
+            pacing.rate_limited(Duration::from_secs(1), now);
+        }
+        assert_eq!(pacing.interval, MAX_EDIT_INTERVAL);
+
+        for _ in 0..50 {
+            pacing.sent(now);
+        }
+        assert_eq!(pacing.interval, MIN_EDIT_INTERVAL);
+    }
+
+    #[test]
+    fn rate_limits_never_shorten_the_wait() {
+        let now = Instant::now();
+        let mut pacing = Pacing::default();
+        pacing.rate_limited(Duration::from_secs(30), now);
+        pacing.rate_limited(Duration::from_secs(1), now);
+        assert_eq!(pacing.ready_at(now), now + Duration::from_secs(30));
+    }
+
+    #[test]
+    fn finds_the_channel_of_a_request() {
+        assert_eq!(
+            rate_limited_channel("/api/v10/channels/123/messages/456"),
+            Some(ChannelId::new(123))
+        );
+        assert_eq!(rate_limited_channel("/api/v10/guilds/1/commands"), None);
+    }
+}
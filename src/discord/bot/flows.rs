@@ -0,0 +1,611 @@
+//! # Flows
+//!
+//! Commands such as `/rip` walk the user through a few steps of menus and modals on one
+//! message before anything happens. This module keeps track of those messages, so a flow
+//! the user walked away from has its menus disabled once its current step times out,
+//! instead of sitting there live until someone clicks it days later.
+//!
+//! It also keeps a flow from being run twice. Double clicks, and interactions Discord
+//! delivers more than once, are acknowledged without being handled again. And it keeps a
+//! flow to whoever started it, others using its menus are told it isn't theirs.
+//!
+//! ## Overview
+//!
+//! - **`Flow`**: A message part way through a command, and the step it is on.
+//!
+//! - **`FlowStore`**: The flows waiting on the user.
+//!
+//! - **`FLOWS`**: A globally accessible, thread-safe instance of `FlowStore`.
+//!
+//! ## Notes
+//!
+//! - The store is lost on restart, so interactions on messages it doesn't know are timed
+//!   out from when the message was last edited instead.
+//! - Ephemeral messages can only be edited with an interaction token, which Discord expires
+//!   after 15 minutes, so an abandoned ephemeral flow may be left as is. Its menus are still
+//!   refused once it has timed out.
+//! - While a step of a flow is being handled, other steps on the same message are
+//!   acknowledged and dropped. Confirming a rip is handled for as long as the rip runs, so
+//!   the rip can't be started a second time, while cancelling it isn't a step and still
+//!   goes through.
+//! - Administrators can use anyone's flow. After a restart, the owner of a flow is read
+//!   from the command its message answered, which Discord keeps with the message.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use serenity::all::{
+    Context, CreateInteractionResponse, CreateInteractionResponseMessage, EditMessage, Interaction,
+    InteractionId, Member, Message, MessageId, MessageInteractionMetadata, Timestamp, UserId,
+};
+use tokio::sync::Mutex;
+
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::error_embeds::error_embed;
+use crate::discord::components::messages::edit_message;
+use crate::discord::errors::DiscordError;
+use crate::{debug, error, tr, trace};
+
+/// How often flows are checked for timed out steps
+const FLOW_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Timeout of steps that only need something picked from a menu, such as a disc
+const PICK_TIMEOUT: Duration = Duration::from_mins(5);
+
+/// Timeout of steps that need details entered or titles looked through
+const SELECT_TIMEOUT: Duration = Duration::from_mins(15);
+
+/// Timeout of the confirmation step, which is often left open while checking the details,
+/// and of messages whose step isn't known
+const CONFIRM_TIMEOUT: Duration = Duration::from_mins(30);
+
+/// The number of handled interactions remembered, to spot ones Discord delivers again
+const MAX_HANDLED_INTERACTIONS: usize = 1000;
+
+/// Commands that start a flow on their response
+const FLOW_COMMANDS: [&str; 6] = [
+    "rip",
+    "get_titles",
+    "rename",
+    "delete_rip",
+    "setup",
+    "library",
+];
+
+/// Components and modals that move a flow on to its next step
+const FLOW_STEPS: [&str; 38] = [
+    "select_rip_preset",
+    "select_disc_to_rip",
+    "movie_rip",
+    "show_rip",
+    "lookup_barcode",
+    "select_titles_to_rip",
+    "select_title_to_rip",
+    "edit_rip",
+    "edit_episodes",
+    "split_by_chapters",
+    "map_episodes",
+    "map_episode",
+    "map_episodes_back",
+    "toggle_eject_after_rip",
+    "add_note",
+    "toggle_3d",
+    "select_library",
+    "preview_titles",
+    "filter_titles_20",
+    "filter_titles_40",
+    "filter_titles_all",
+    "archive_disc",
+    "audio_rip",
+    "edit_audio_rip",
+    "select_rip_to_rename",
+    "select_rip_to_delete",
+    "get_title_of_movie_rip",
+    "get_title_of_show_rip",
+    "get_barcode",
+    "get_episode_start",
+    "get_chapter_split",
+    "get_rip_note",
+    "get_extra_names",
+    "get_audio_details",
+    "setup_layout",
+    "setup_alert_channel",
+    "setup_exempt_roles",
+    "get_setup_templates",
+];
+
+/// Components and modals that finish a flow, by starting or cancelling what it set up
+const FINAL_STEPS: [&str; 11] = [
+    "confirm_rip",
+    "cancel_pending_rip",
+    "confirm_audio_rip",
+    "get_archive_details",
+    "select_disc_to_grab_titles",
+    "get_rename_details",
+    "confirm_delete_rip",
+    "cancel_delete_rip",
+    "save_setup",
+    "cancel_setup",
+    "fix_library",
+];
+
+/// A globally accessible instance of `FlowStore` for tracking flows.
+pub static FLOWS: LazyLock<Arc<Mutex<FlowStore>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(FlowStore::default())));
+
+/// A message part way through a command.
+///
+/// # Fields
+///
+/// * `message` - The message the flow is on.
+/// * `token` - The token of the latest interaction, to edit the message if it is ephemeral.
+/// * `step` - The command, component or modal that last moved the flow on.
+/// * `owner` - Who started the flow.
+/// * `touched` - When the flow was last moved on.
+#[derive(Debug, Clone)]
+pub struct Flow {
+    pub message: Message,
+    pub token: String,
+    pub step: String,
+    pub owner: UserId,
+    pub touched: Instant,
+}
+
+/// The `FlowStore` struct holds the flows waiting on the user, by message.
+///
+/// # Methods
+///
+/// ## `touch`
+/// Records that a flow moved on to a new step.
+///
+/// ## `end`
+/// Forgets a flow once it is finished.
+///
+/// ## `timed_out`
+/// Removes and returns the flows whose step has timed out.
+///
+/// ## `first_delivery`
+/// Records an interaction, returning false if it was already seen.
+///
+/// ## `begin` and `release`
+/// Mark a message as having a step handled, and done with it.
+#[derive(Debug, Default)]
+pub struct FlowStore {
+    flows: HashMap<MessageId, Flow>,
+    handled: VecDeque<InteractionId>,
+    busy: HashSet<MessageId>,
+}
+
+impl FlowStore {
+    /// Records that the flow of `owner` on `message` moved on to `step`
+    pub fn touch(&mut self, message: Message, token: &str, step: &str, owner: UserId) {
+        trace!("Flow on message {} moved on to {}", message.id, step);
+        self.flows.insert(
+            message.id,
+            Flow {
+                message,
+                token: token.to_string(),
+                step: step.to_string(),
+                owner,
+                touched: Instant::now(),
+            },
+        );
+    }
+
+    /// Forgets the flow on a message once it is finished
+    pub fn end(&mut self, message_id: MessageId) {
+        if self.flows.remove(&message_id).is_some() {
+            trace!("Flow on message {} finished", message_id);
+        }
+    }
+
+    /// The flow on a message, if it is being tracked
+    #[must_use]
+    pub fn get(&self, message_id: MessageId) -> Option<&Flow> {
+        self.flows.get(&message_id)
+    }
+
+    /// Records that an interaction was delivered, returning false if it already was
+    pub fn first_delivery(&mut self, id: InteractionId) -> bool {
+        if self.handled.contains(&id) {
+            return false;
+        }
+
+        if self.handled.len() >= MAX_HANDLED_INTERACTIONS {
+            self.handled.pop_front();
+        }
+        self.handled.push_back(id);
+        true
+    }
+
+    /// Marks a message as having a step handled, returning false if one already is
+    pub fn begin(&mut self, message_id: MessageId) -> bool {
+        self.busy.insert(message_id)
+    }
+
+    /// Marks a message as done with the step being handled on it
+    pub fn release(&mut self, message_id: MessageId) {
+        self.busy.remove(&message_id);
+    }
+
+    /// Removes and returns the flows whose current step has timed out
+    pub fn timed_out(&mut self) -> Vec<Flow> {
+        let expired: Vec<MessageId> = self
+            .flows
+            .iter()
+            .filter(|(_, flow)| flow.touched.elapsed() > step_timeout(&flow.step))
+            .map(|(id, _)| *id)
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|id| self.flows.remove(&id))
+            .collect()
+    }
+}
+
+/// How long a flow waits on the user after `step` before it times out
+fn step_timeout(step: &str) -> Duration {
+    match step {
+        // Menus to pick a disc, a rip or the kind of rip from
+        "rip" | "get_titles" | "rename" | "delete_rip" | "select_rip_preset"
+        | "select_disc_to_rip" => PICK_TIMEOUT,
+        // These show the confirmation summary
+        "select_titles_to_rip"
+        | "select_title_to_rip"
+        | "edit_episodes"
+        | "get_episode_start"
+        | "split_by_chapters"
+        | "get_chapter_split"
+        | "add_note"
+        | "get_rip_note"
+        | "get_extra_names"
+        | "toggle_eject_after_rip"
+        | "toggle_3d"
+        | "select_library"
+        | "audio_rip"
+        | "get_audio_details"
+        | "select_rip_to_delete"
+        // The setup is left open while deciding on its settings
+        | "setup"
+        | "setup_layout"
+        | "setup_alert_channel"
+        | "setup_exempt_roles"
+        | "get_setup_templates" => CONFIRM_TIMEOUT,
+        _ => SELECT_TIMEOUT,
+    }
+}
+
+/// Whether a command starts a flow that should be tracked
+#[must_use]
+pub fn is_flow_command(command: &str) -> bool {
+    FLOW_COMMANDS.contains(&command)
+}
+
+/// The embed a flow's message is left with once it times out, keeping its title
+fn timed_out_embed(message: &Message) -> serenity::all::CreateEmbed {
+    let title = message
+        .embeds
+        .first()
+        .and_then(|embed| embed.title.clone())
+        .unwrap_or_default();
+
+    themed_embed().title(title).description(tr!(
+        "Selection timed out, run the command again to start over."
+    ))
+}
+
+/// Starts tracking the flow a command started on its response
+pub async fn start(ctx: &Context, interaction: &Interaction) {
+    let Interaction::Command(command) = interaction else {
+        return;
+    };
+
+    // The command may have failed before responding, in which case there is no flow
+    match command.get_response(&ctx.http).await {
+        Ok(message) => {
+            let owner = command.user.id;
+            FLOWS
+                .lock()
+                .await
+                .touch(message, &command.token, &command.data.name, owner);
+        }
+        Err(e) => debug!("No response to track a flow on: {:?}", e),
+    }
+}
+
+/// Who ran the command `message` answered, which Discord keeps with the message
+fn command_user(message: &Message) -> Option<UserId> {
+    match message.interaction_metadata.as_deref()? {
+        MessageInteractionMetadata::Command(command) => Some(command.user.id),
+        MessageInteractionMetadata::Component(component) => Some(component.user.id),
+        MessageInteractionMetadata::ModalSubmit(modal) => Some(modal.user.id),
+        _ => None,
+    }
+}
+
+/// Whether `user` may use a flow of `owner`, anyone may if its owner isn't known
+fn may_use(owner: Option<UserId>, user: UserId, member: Option<&Member>) -> bool {
+    owner.is_none_or(|owner| owner == user) || is_admin(member)
+}
+
+/// Marks the message of a flow step that went ahead in `check` as done with it once
+/// dropped, so the flow's next step can be handled. It is released even if handling the
+/// step panics, which would otherwise leave every later step on the message dropped.
+#[derive(Debug)]
+#[must_use]
+pub struct StepGuard {
+    message_id: Option<MessageId>,
+}
+
+impl Drop for StepGuard {
+    fn drop(&mut self) {
+        let Some(message_id) = self.message_id else {
+            return;
+        };
+
+        // The store is behind an async lock, so it is released from a task when it is held
+        if let Ok(mut flows) = FLOWS.try_lock() {
+            flows.release(message_id);
+        } else {
+            tokio::spawn(async move {
+                FLOWS.lock().await.release(message_id);
+            });
+        }
+    }
+}
+
+/// Checks an interaction against the flow it is part of, moving the flow on to its step.
+/// Returns `None` if it shouldn't be handled: when it was already delivered, when another
+/// step of its flow is still being handled, or when the flow had timed out or isn't the
+/// user's, which the user is told about. Otherwise returns a `StepGuard` to hold for as
+/// long as the step is handled.
+///
+/// Interactions that aren't part of a flow, such as cancelling a running rip, go ahead
+/// unless they were already delivered.
+pub async fn check(ctx: &Context, interaction: &Interaction) -> Option<StepGuard> {
+    if !FLOWS.lock().await.first_delivery(interaction.id()) {
+        debug!(
+            "Interaction {} was delivered again, ignoring",
+            interaction.id()
+        );
+        return None;
+    }
+
+    let no_flow = StepGuard { message_id: None };
+
+    let (step, message, token, user, member) = match interaction {
+        Interaction::Component(component) => (
+            custom_ids::name(&component.data.custom_id),
+            Some(&*component.message),
+            &component.token,
+            &component.user,
+            component.member.as_ref(),
+        ),
+        Interaction::Modal(modal) => (
+            custom_ids::name(&modal.data.custom_id),
+            modal.message.as_deref(),
+            &modal.token,
+            &modal.user,
+            modal.member.as_ref(),
+        ),
+        _ => return Some(no_flow),
+    };
+
+    let is_final = FINAL_STEPS.contains(&step);
+    let Some(message) = message else {
+        return Some(no_flow);
+    };
+    if !is_final && !FLOW_STEPS.contains(&step) {
+        return Some(no_flow);
+    }
+
+    let mut flows = FLOWS.lock().await;
+
+    // Checked before anything else, so someone else's click can't time out or end the flow
+    let owner = flows
+        .get(message.id)
+        .map(|flow| flow.owner)
+        .or_else(|| command_user(message));
+    if !may_use(owner, user.id, member) {
+        drop(flows);
+        debug!(
+            "{} tried to use {} on flow {}, which isn't theirs",
+            user.name, step, message.id
+        );
+
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .ephemeral(true)
+                .embed(error_embed(&DiscordError::NotOwner)),
+        );
+        if let Err(e) = respond(ctx, interaction, response).await {
+            error!("Failed to send not your rip message: {:?}", e);
+        }
+
+        return None;
+    }
+
+    let timed_out = if let Some(flow) = flows.get(message.id) {
+        flow.touched.elapsed() > step_timeout(&flow.step)
+    } else {
+        // Without a tracked flow, such as after a restart, the message's own timestamps are
+        // all there is to go on. A message with no components left has already finished.
+        let last_edited = message.edited_timestamp.unwrap_or(message.timestamp);
+        let age = Timestamp::now().unix_timestamp() - last_edited.unix_timestamp();
+        message.components.is_empty()
+            || age > i64::try_from(CONFIRM_TIMEOUT.as_secs()).unwrap_or(i64::MAX)
+    };
+
+    if timed_out {
+        flows.end(message.id);
+        drop(flows);
+        debug!("Refusing {} on timed out flow {}", step, message.id);
+
+        let response = CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .components(vec![])
+                .embed(timed_out_embed(message)),
+        );
+        if let Err(e) = respond(ctx, interaction, response).await {
+            error!("Failed to send selection timed out message: {:?}", e);
+        }
+
+        return None;
+    }
+
+    if !flows.begin(message.id) {
+        drop(flows);
+        debug!(
+            "A step of flow {} is already being handled, dropping {}",
+            message.id, step
+        );
+
+        // Acknowledged so the user isn't shown an error for their double click
+        let result = match interaction {
+            Interaction::Component(component) => component.defer(&ctx.http).await,
+            Interaction::Modal(modal) => modal.defer(&ctx.http).await,
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            debug!("Failed to acknowledge dropped interaction: {:?}", e);
+        }
+
+        return None;
+    }
+
+    if is_final {
+        flows.end(message.id);
+    } else {
+        flows.touch(message.clone(), token, step, owner.unwrap_or(user.id));
+    }
+
+    Some(StepGuard {
+        message_id: Some(message.id),
+    })
+}
+
+/// Responds to the component or modal of a flow step that isn't handled
+async fn respond(
+    ctx: &Context,
+    interaction: &Interaction,
+    response: CreateInteractionResponse,
+) -> serenity::Result<()> {
+    match interaction {
+        Interaction::Component(component) => component.create_response(&ctx.http, response).await,
+        Interaction::Modal(modal) => modal.create_response(&ctx.http, response).await,
+        _ => Ok(()),
+    }
+}
+
+/// Disables the menus of flows whose step has timed out, so they can't be used later.
+///
+/// Runs for the lifetime of the bot.
+pub async fn expire_flows(ctx: Context) {
+    debug!("Started expiring abandoned flows");
+
+    let mut interval = tokio::time::interval(FLOW_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let timed_out = FLOWS.lock().await.timed_out();
+
+        for flow in timed_out {
+            debug!(
+                "Flow on message {} timed out on {}",
+                flow.message.id, flow.step
+            );
+
+            // Ephemeral messages can't be edited once the token expires, the flow is still
+            // refused if it is used again
+            if let Err(e) = edit_message(
+                &ctx,
+                &flow.token,
+                &flow.message,
+                EditMessage::new()
+                    .components(vec![])
+                    .embed(timed_out_embed(&flow.message)),
+            )
+            .await
+            {
+                debug!("Failed to disable timed out flow: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_time_out_by_what_they_wait_on() {
+        assert_eq!(step_timeout("rip"), PICK_TIMEOUT);
+        assert_eq!(step_timeout("get_title_of_show_rip"), SELECT_TIMEOUT);
+        assert_eq!(step_timeout("select_titles_to_rip"), CONFIRM_TIMEOUT);
+    }
+
+    #[test]
+    fn handles_interactions_once() {
+        let mut store = FlowStore::default();
+        assert!(store.first_delivery(InteractionId::new(1)));
+        assert!(store.first_delivery(InteractionId::new(2)));
+        assert!(!store.first_delivery(InteractionId::new(1)));
+
+        let message_id = MessageId::new(10);
+        assert!(store.begin(message_id));
+        assert!(!store.begin(message_id));
+        store.release(message_id);
+        assert!(store.begin(message_id));
+    }
+
+    #[tokio::test]
+    async fn steps_are_released_when_their_guard_drops() {
+        let message_id = MessageId::new(20);
+        assert!(FLOWS.lock().await.begin(message_id));
+
+        let step = StepGuard {
+            message_id: Some(message_id),
+        };
+        let handled = tokio::spawn(async move {
+            let _step = step;
+            panic!("handling the step failed");
+        })
+        .await;
+        assert!(handled.is_err());
+
+        assert!(FLOWS.lock().await.begin(message_id));
+        FLOWS.lock().await.release(message_id);
+    }
+
+    #[test]
+    fn forgets_the_oldest_interactions() {
+        let mut store = FlowStore::default();
+        for id in 1..=MAX_HANDLED_INTERACTIONS as u64 + 1 {
+            assert!(store.first_delivery(InteractionId::new(id)));
+        }
+        assert_eq!(store.handled.len(), MAX_HANDLED_INTERACTIONS);
+        assert!(store.first_delivery(InteractionId::new(1)));
+    }
+
+    #[test]
+    fn flows_are_kept_to_their_owner() {
+        let owner = UserId::new(1);
+        assert!(may_use(Some(owner), owner, None));
+        assert!(!may_use(Some(owner), UserId::new(2), None));
+        assert!(may_use(None, UserId::new(2), None));
+    }
+
+    #[test]
+    fn steps_either_move_on_or_finish() {
+        for step in FINAL_STEPS {
+            assert!(!FLOW_STEPS.contains(&step), "{step}");
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, Http};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::makemkv::MakeMkv;
+use crate::watch::{WatchEvent, WATCH_FOLDER};
+use crate::{debug, error, tr};
+
+/// How often the watch folder is checked for files to import
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Imports the files dropped into the watch folder, posting an embed to the alert channel
+/// for each one that was imported or couldn't be.
+///
+/// Runs for the lifetime of the bot. When no alert channel is configured the imports are
+/// only logged.
+pub async fn watch_incoming(http: Arc<Http>, channel: Option<ChannelId>, makemkv: Arc<MakeMkv>) {
+    debug!("Started watching the watch folder");
+
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let events = WATCH_FOLDER.lock().await.poll(&makemkv).await;
+
+        let Some(channel) = channel else {
+            continue;
+        };
+        for event in events {
+            if let Err(e) = channel
+                .send_message(&http, CreateMessage::new().embed(event_embed(&event)))
+                .await
+            {
+                error!("Failed to send watch folder notification: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Builds the embed posted when a file is imported, or couldn't be
+fn event_embed(event: &WatchEvent) -> CreateEmbed {
+    match event {
+        WatchEvent::Imported {
+            file,
+            rip,
+            destination,
+        } => themed_embed()
+            .title(tr!("Imported From Watch Folder"))
+            .description(tr!("{title} was added to the library.", title = rip.title))
+            .field(tr!("File"), display_path(file), false)
+            .field(tr!("Saved To"), display_path(destination), false),
+        WatchEvent::Failed { file, error } => themed_embed()
+            .title(tr!("Watch Folder Import Failed"))
+            .description(error.user_message())
+            .field(tr!("File"), display_path(file), false),
+    }
+}
+
+fn display_path(path: &Path) -> String {
+    format!("`{}`", path.display())
+}
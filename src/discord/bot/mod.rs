@@ -1 +1,10 @@
+pub mod alerts;
 pub mod bot_core;
+pub mod controls;
+pub mod digest;
+pub mod edits;
+pub mod flows;
+pub mod incoming;
+pub mod ops_log;
+pub mod progress;
+pub mod updates;
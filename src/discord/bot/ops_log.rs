@@ -0,0 +1,131 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::all::{ChannelId, CreateMessage, Http};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::logging::{enable_mirror, take_mirrored};
+use crate::{debug, tr};
+
+/// How often held warnings and errors are posted, at most one message goes out per interval
+const OPS_LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Discord's limit on the length of an embed description
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+/// Lines longer than this are cut short, so one long error can't fill the whole post
+const MAX_LINE_LENGTH: usize = 300;
+
+/// Mirrors warnings and errors to the ops channel, batching whatever was logged since the
+/// last post into a single embed so a burst of errors doesn't flood the channel.
+///
+/// Runs for the lifetime of the bot.
+pub async fn mirror_logs(http: Arc<Http>, channel: ChannelId) {
+    debug!("Started mirroring logs to {}", channel);
+    enable_mirror();
+
+    let mut interval = tokio::time::interval(OPS_LOG_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let (lines, dropped) = take_mirrored();
+        if lines.is_empty() {
+            continue;
+        }
+
+        let mut description = format_batch(&lines);
+        if dropped > 0 {
+            description.push('\n');
+            description.push_str(&tr!(
+                "{count} more lines were dropped, see the logs for the rest.",
+                count = dropped
+            ));
+        }
+
+        // Logged as debug, as a warning it would be mirrored and posted again
+        if let Err(e) = channel
+            .send_message(
+                &http,
+                CreateMessage::new().embed(
+                    themed_embed()
+                        .title(tr!("Warnings and Errors"))
+                        .description(description),
+                ),
+            )
+            .await
+        {
+            debug!(
+                "Failed to post warnings and errors to the ops channel: {:?}",
+                e
+            );
+        }
+    }
+}
+
+/// Formats held log lines as a code block for an embed, folding repeats of a line into
+/// its first occurrence with a count, and leaving off whatever doesn't fit.
+fn format_batch(lines: &[String]) -> String {
+    let mut counted: Vec<(String, usize)> = Vec::new();
+    for line in lines {
+        let line = if line.chars().count() > MAX_LINE_LENGTH {
+            let cut: String = line.chars().take(MAX_LINE_LENGTH).collect();
+            format!("{cut}...")
+        } else {
+            line.clone()
+        };
+
+        match counted.iter_mut().find(|(seen, _)| *seen == line) {
+            Some((_, count)) => *count += 1,
+            None => counted.push((line, 1)),
+        }
+    }
+
+    // Room is left for the code fences and the dropped lines note
+    let budget = MAX_DESCRIPTION_LENGTH - 200;
+    let mut body = String::new();
+    for (line, count) in counted {
+        // Backticks in a line would close the code block early
+        let line = line.replace("```", "'''");
+        let line = if count > 1 {
+            format!("{line} (x{count})\n")
+        } else {
+            format!("{line}\n")
+        };
+
+        if body.len() + line.len() > budget {
+            body.push_str("...\n");
+            break;
+        }
+        body.push_str(&line);
+    }
+
+    format!("```\n{body}```")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_repeated_lines() {
+        let lines = vec![
+            "[ERROR] Drive 0 failed".to_string(),
+            "[WARNING] Low space".to_string(),
+            "[ERROR] Drive 0 failed".to_string(),
+        ];
+        assert_eq!(
+            format_batch(&lines),
+            "```\n[ERROR] Drive 0 failed (x2)\n[WARNING] Low space\n```"
+        );
+    }
+
+    #[test]
+    fn fits_in_an_embed() {
+        let lines: Vec<String> = (0..50)
+            .map(|i| format!("[ERROR] {i} {}", "x".repeat(400)))
+            .collect();
+        let batch = format_batch(&lines);
+        assert!(batch.len() < MAX_DESCRIPTION_LENGTH);
+        assert!(batch.ends_with("...\n```"));
+    }
+}
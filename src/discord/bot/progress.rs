@@ -0,0 +1,149 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::all::{ChannelId, CreateActionRow, CreateEmbed, EditMessage, Http, MessageId};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::discord::bot::edits::MessageEditor;
+use crate::discord::commands::rip::{remaining_titles, resume_button, rip_progress};
+use crate::discord::components::embeds::themed_embed;
+use crate::history::history_core::now;
+use crate::makemkv::events::{subscribe, RipEvent};
+use crate::queue::batches::batches;
+use crate::queue::messages::{Following, JobMessage, JOB_MESSAGES};
+use crate::queue::QUEUE;
+use crate::{debug, info, tr, warn};
+
+/// How often a picked up progress message is refreshed, the same as the rip's own updates
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Picks up the progress messages nothing is editing anymore, run whenever the bot
+/// connects to Discord.
+///
+/// The messages of rips the bot was running when it last stopped are marked as
+/// interrupted, and the messages of running rips whose interaction is gone are followed
+/// again until their rip is over.
+pub async fn recover_progress(http: Arc<Http>) {
+    // The lock can't be held while a `Following` might be dropped
+    let (interrupted, unfollowed) = {
+        let mut messages = JOB_MESSAGES
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        (messages.take_interrupted(), messages.take_unfollowed())
+    };
+
+    for message in interrupted {
+        info!("Marking the rip of {} as interrupted", message.label);
+        let embed = themed_embed().title(tr!("Rip Interrupted")).field(
+            tr!("Disc Number"),
+            message.drive_number.to_string(),
+            true,
+        );
+
+        // A batch of show rips picks up where it stopped, anything else starts over
+        let remaining = batches()
+            .get(message.message_id)
+            .map(|batch| remaining_titles(&batch.rips));
+        let (embed, components) = match remaining {
+            Some(remaining) => (
+                embed
+                    .description(tr!(
+                        "The bot restarted while ripping {title}, press Resume to rip the titles it had left.",
+                        title = message.label
+                    ))
+                    .field(tr!("Remaining Titles"), remaining, false),
+                vec![CreateActionRow::Buttons(vec![resume_button()])],
+            ),
+            None => (
+                embed.description(tr!(
+                    "The bot restarted while ripping {title}, start the rip again to finish it.",
+                    title = message.label
+                )),
+                vec![],
+            ),
+        };
+        edit(&http, &message, embed, components).await;
+    }
+
+    for (job_id, message, following) in unfollowed {
+        info!("Picking up the progress of {} again", message.label);
+        tokio::spawn(follow_job(http.clone(), job_id, message, following));
+    }
+}
+
+/// Edits the progress of a job into its message until the rip is over, which then says
+/// how it ended
+async fn follow_job(http: Arc<Http>, job_id: u64, message: JobMessage, _following: Following) {
+    let mut events = subscribe();
+    let mut updates = tokio::time::interval(FOLLOW_INTERVAL);
+    updates.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut editor = MessageEditor::for_message(
+        &http,
+        ChannelId::new(message.channel_id),
+        MessageId::new(message.message_id),
+    );
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let embed = match event {
+                    Ok(RipEvent::Completed { rip, .. }) if rip.drive_number == message.drive_number => {
+                        themed_embed()
+                            .title(tr!("Rip Finished"))
+                            .description(tr!("Ripped {title}!", title = rip.title))
+                    }
+                    Ok(RipEvent::Failed { rip, error }) if rip.drive_number == message.drive_number => {
+                        themed_embed()
+                            .title(tr!("Rip Failed"))
+                            .description(tr!(
+                                "Failed to rip {title}: {reason}",
+                                title = rip.title,
+                                reason = error
+                            ))
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                edit(&http, &message, embed, vec![]).await;
+                return;
+            }
+            _ = updates.tick() => {
+                // A job that left the queue without an event was cancelled
+                if !QUEUE.lock().await.jobs().iter().any(|job| job.id == job_id) {
+                    debug!("Job {} is over, no longer following it", job_id);
+                    return;
+                }
+
+                let elapsed = now().saturating_sub(message.started);
+                let mut embed = themed_embed()
+                    .title(tr!("Rip in Progress"))
+                    .description(tr!("Ripping {title}...", title = message.label))
+                    .field(tr!("Disc Number"), message.drive_number.to_string(), true);
+                if let Some(progress) = rip_progress(message.drive_number, None, elapsed) {
+                    embed = embed.field(tr!("Progress"), progress, false);
+                }
+                editor.queue(EditMessage::new().embed(embed).components(vec![]));
+            }
+            () = editor.ready() => editor.send().await,
+        }
+    }
+}
+
+/// Replaces the embed of a progress message, and its buttons with `components` since the
+/// old ones went with the interaction
+async fn edit(
+    http: &Http,
+    message: &JobMessage,
+    embed: CreateEmbed,
+    components: Vec<CreateActionRow>,
+) {
+    if let Err(e) = ChannelId::new(message.channel_id)
+        .edit_message(
+            http,
+            MessageId::new(message.message_id),
+            EditMessage::new().embed(embed).components(components),
+        )
+        .await
+    {
+        warn!("Failed to edit the progress of {}: {:?}", message.label, e);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, Http};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::update::{newer_release, Release};
+use crate::{debug, error, info, tr, warn};
+
+/// How often GitHub is checked for a newer release
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_hours(24);
+
+/// Checks GitHub for a newer release when the bot starts and once a day after, posting a
+/// notice with its changelog to the alert channel.
+///
+/// Runs for the lifetime of the bot. Each release is only announced once, and when no
+/// alert channel is configured it is only logged.
+pub async fn check_for_updates(http: Arc<Http>, channel: Option<ChannelId>) {
+    debug!("Started checking for updates");
+
+    let mut interval = tokio::time::interval(UPDATE_CHECK_INTERVAL);
+    let mut announced: Option<String> = None;
+
+    loop {
+        interval.tick().await;
+
+        let release = match newer_release().await {
+            Ok(Some(release)) => release,
+            Ok(None) => {
+                debug!("Cord Ripper is up to date");
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to check for updates: {}", e);
+                continue;
+            }
+        };
+
+        if announced.as_ref() == Some(&release.version) {
+            continue;
+        }
+
+        info!(
+            "Cord Ripper {} is out, this is {}, see {}",
+            release.version,
+            env!("CARGO_PKG_VERSION"),
+            release.url
+        );
+
+        if let Some(channel) = channel {
+            if let Err(e) = channel
+                .send_message(&http, CreateMessage::new().embed(update_embed(&release)))
+                .await
+            {
+                error!("Failed to post the update notice: {:?}", e);
+                continue;
+            }
+        }
+        announced = Some(release.version);
+    }
+}
+
+/// Builds the embed announcing a newer release
+fn update_embed(release: &Release) -> CreateEmbed {
+    let mut embed = themed_embed()
+        .title(tr!(
+            "Cord Ripper {version} Is Out",
+            version = release.version
+        ))
+        .url(&release.url)
+        .description(tr!(
+            "This is {current}. Update when nothing is ripping, see the release for what changed.",
+            current = env!("CARGO_PKG_VERSION")
+        ));
+
+    if let Some(name) = release.name.as_deref().filter(|name| !name.is_empty()) {
+        embed = embed.field(tr!("Release"), name, false);
+    }
+    if let Some(summary) = release.summary() {
+        embed = embed.field(tr!("Changelog"), summary, false);
+    }
+    embed
+}
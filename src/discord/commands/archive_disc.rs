@@ -0,0 +1,339 @@
+use serenity::all::{
+    Context, CreateActionRow, CreateButton, CreateInputText, CreateInteractionResponse,
+    CreateMessage, CreateModal, EditMessage, InputTextStyle, Interaction, Message,
+};
+
+use crate::archive::{archive_disc, volume_label};
+use crate::audit::AuditAction;
+use crate::discord::bot::controls;
+use crate::discord::commands::command_helpers::get_modal_input;
+use crate::discord::commands::command_helpers::{
+    audit, audit_cancel, audit_outcome, makemkv, rip_parameters, take_rip_quota,
+};
+use crate::discord::commands::rip::{
+    eject_after_rip, embed_eject, finished_in, format_size, parse_priority, priority_input,
+    priority_name, start_rip_message, wait_for_turn, RipOptions,
+};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::errors::{DiscordError, Result};
+use crate::library::LIBRARY;
+use crate::queue::{JobKind, JobTicket};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// Runs the archive branch of the `rip` command
+///
+/// Offered when `MakeMKV` finds no video titles on a disc. "Archive Disc" asks for a label,
+/// defaulting to the disc's volume label, then images the disc into `archives/{label}/`.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the disc can't be archived, or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Archive disc was called");
+
+    match interaction {
+        Interaction::Component(component)
+            if custom_ids::name(&component.data.custom_id) == "archive_disc" =>
+        {
+            trace!("Got archive_disc component interaction");
+
+            let drive_number: u8 = component
+                .message
+                .embeds
+                .first()
+                .and_then(|embed| {
+                    embed
+                        .fields
+                        .iter()
+                        .find(|field| field.name == tr!("Disc Number"))
+                })
+                .and_then(|field| field.value.parse().ok())
+                .ok_or_else(|| {
+                    warn!("Failed to parse disc number from message, ignoring");
+                    DiscordError::Unexpected("Failed to parse disc number".to_string())
+                })?;
+
+            let label = volume_label(drive_number)
+                .await
+                .unwrap_or_else(|| tr!("Disc {disc}", disc = crate::history::history_core::now()));
+
+            component
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Modal(
+                        CreateModal::new("get_archive_details", tr!("Please enter a label"))
+                            .components(vec![
+                                CreateActionRow::InputText(
+                                    CreateInputText::new(
+                                        InputTextStyle::Short,
+                                        tr!("Disc Number"),
+                                        "disc_number",
+                                    )
+                                    .value(drive_number.to_string())
+                                    .required(true),
+                                ),
+                                CreateActionRow::InputText(
+                                    CreateInputText::new(
+                                        InputTextStyle::Short,
+                                        tr!("Label"),
+                                        "label",
+                                    )
+                                    .value(label)
+                                    .required(true),
+                                ),
+                                priority_input(None),
+                            ]),
+                    ),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create get_archive_details modal: {:?}", e);
+                    DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        Interaction::Modal(modal)
+            if custom_ids::name(&modal.data.custom_id) == "get_archive_details" =>
+        {
+            trace!("Got get_archive_details modal");
+
+            let Some(message) = modal.message.clone() else {
+                trace!("Modal interaction has no message, ignoring");
+                return Err(DiscordError::InvalidInteractionCall);
+            };
+
+            modal.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            let drive_number: u8 = get_modal_input(modal, "disc_number")
+                .and_then(|drive_number| drive_number.parse().ok())
+                .ok_or(DiscordError::InvalidComponentData)?;
+            let label =
+                get_modal_input(modal, "label").ok_or(DiscordError::InvalidComponentData)?;
+
+            let options = RipOptions {
+                priority: parse_priority(modal)?,
+                eject_when_done: embed_eject(ctx, &message).await?,
+                owner: modal.user.id,
+            };
+
+            let parameters = rip_parameters(&label, drive_number, None, options.eject_when_done);
+
+            take_rip_quota(modal.user.id, modal.member.as_ref()).await?;
+            let message = start_rip_message(ctx, &modal.token, &message, &label).await?;
+            let result = run_archive(ctx, &message, drive_number, &label, options).await;
+
+            let outcome = audit_outcome(&result);
+            audit(&modal.user, "rip", AuditAction::Rip, parameters, outcome).await;
+            result
+        }
+        _ => {
+            debug!("Unknown interaction calling archive disc, ignoring");
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+/// Images a disc, updating the message while it runs and allowing the user to cancel.
+/// Archiving takes a rip slot in the queue, since it reads from the drive the same way.
+async fn run_archive(
+    ctx: &Context,
+    message: &Message,
+    drive_number: u8,
+    label: &str,
+    options: RipOptions,
+) -> Result<()> {
+    let RipOptions {
+        priority,
+        eject_when_done,
+        owner,
+    } = options;
+
+    let ticket = JobTicket::submit(
+        &tr!(
+            "Archive {label} (Disc {disc})",
+            label = label,
+            disc = drive_number
+        ),
+        JobKind::Rip { drive_number },
+        priority,
+    )
+    .await;
+
+    let output_dir = makemkv(ctx).await?.output_dir.clone();
+    let now = std::time::Instant::now();
+
+    let archiving_embed = themed_embed()
+        .title(tr!("Archive Disc"))
+        .description(tr!(
+            "Imaging {label}, this can take a while for a full disc...",
+            label = label
+        ))
+        .field(tr!("Label"), label, true)
+        .field(tr!("Disc Number"), drive_number.to_string(), true)
+        .field(tr!("Priority"), priority_name(priority), true);
+
+    message
+        .clone()
+        .edit(
+            &ctx.http,
+            EditMessage::new()
+                .components(vec![])
+                .embed(archiving_embed.clone())
+                .button(
+                    CreateButton::new(custom_ids::with_id("cancel_rip", ticket.id))
+                        .label(tr!("Cancel"))
+                        .style(serenity::all::ButtonStyle::Danger),
+                ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send archive in progress message: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    let mut controls = controls::listen(ticket.id, message.id, owner);
+
+    // Dropping the archive future kills ddrescue, leaving a partial image behind
+    let manifest = tokio::select! {
+        result = async {
+            wait_for_turn(ctx, message, &ticket, archiving_embed).await;
+            archive_disc(drive_number, label, &output_dir).await
+        } => {
+            match result {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    error!("Failed to archive disc: {:?}", e);
+
+                    if let Err(e) = message
+                        .clone()
+                        .edit(
+                            &ctx.http,
+                            EditMessage::new().components(vec![]).embed(
+                                themed_embed()
+                                    .title(tr!("Archive Failed"))
+                                    .description(e.user_message())
+                                    .field(tr!("Label"), label, true)
+                                    .field(tr!("Disc Number"), drive_number.to_string(), true),
+                            ),
+                        )
+                        .await
+                    {
+                        error!("Failed to send archive failed message: {:?}", e);
+                    }
+
+                    return Err(DiscordError::reported(e));
+                }
+            }
+        }
+        Some(interaction) = controls.next() => {
+            debug!("Recieved cancel request");
+
+            if let Err(e) = interaction.defer(&ctx.http).await {
+                error!("Failed to defer cancel request: {:?}", e);
+            }
+            audit_cancel(&interaction.user, "rip", label, drive_number).await;
+
+            if let Err(e) = message
+                .clone()
+                .edit(
+                    &ctx.http,
+                    EditMessage::new().components(vec![]).embed(
+                        themed_embed()
+                            .title(tr!("Archive Cancelled"))
+                            .description(tr!(
+                                "Archive cancelled! The partial image is left in {directory}, \
+                                 archiving again with the same label resumes it.",
+                                directory = format!(
+                                    "{}/{}",
+                                    crate::archive::archive_core::ARCHIVE_DIR,
+                                    crate::makemkv::naming::path_safe(label)
+                                )
+                            ))
+                            .field(tr!("Label"), label, true)
+                            .field(tr!("Disc Number"), drive_number.to_string(), true),
+                    ),
+                )
+                .await
+            {
+                error!("Failed to send archive cancelled message: {:?}", e);
+            }
+            info!("Archive cancelled");
+            return Err(DiscordError::TaskCancelled);
+        }
+    };
+
+    let archive_time = now.elapsed().as_secs_f64() / 60.00;
+
+    if eject_when_done {
+        eject_after_rip(drive_number, ticket.id).await;
+    }
+
+    let mut library = LIBRARY.lock().await;
+    for file in &manifest.files {
+        library.add(&manifest.directory.join(&file.path));
+    }
+    drop(library);
+
+    message
+        .clone()
+        .edit(
+            &ctx.http,
+            EditMessage::new().components(vec![]).embed(
+                themed_embed()
+                    .title(tr!("Archived {label}", label = label))
+                    .description(tr!("Archive completed!")),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send archive completed message: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    let checksums = manifest
+        .files
+        .iter()
+        .map(|file| format!("{}: `{}`", file.path, file.sha256))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .embed(
+                    themed_embed()
+                        .title(tr!("Archive Summary"))
+                        .description(finished_in(archive_time))
+                        .field(tr!("Label"), label, true)
+                        .field(tr!("Disc Number"), drive_number.to_string(), true)
+                        .field(
+                            tr!("Size"),
+                            format_size(manifest.files.iter().map(|file| file.size).sum()),
+                            true,
+                        )
+                        .field(tr!("Method"), &manifest.method, true)
+                        .field(
+                            tr!("Destination"),
+                            manifest.directory.display().to_string(),
+                            false,
+                        )
+                        .field(tr!("SHA-256"), checksums, false),
+                )
+                .reference_message(message),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send archive summary message: {:?}", e);
+            DiscordError::SendMessageFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
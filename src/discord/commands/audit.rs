@@ -0,0 +1,156 @@
+use serenity::all::{
+    CommandOptionType, Context, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, Interaction, Permissions, ResolvedValue,
+};
+
+use crate::audit::audit_core::{parse_date, parse_end_date};
+use crate::audit::{AuditAction, AuditEntry, AuditFilter, AuditOutcome, AUDIT_LOG};
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::errors::{DiscordError, Result};
+
+use crate::{debug, error, tr, trace, warn};
+
+/// The most entries shown at once, to stay within Discord's embed length limit
+const MAX_SHOWN_ENTRIES: usize = 15;
+/// The longest an entry is shown, long season batches are cut off
+const MAX_ENTRY_LENGTH: usize = 250;
+
+pub fn register() -> CreateCommand {
+    debug!("Registered audit command");
+    CreateCommand::new("audit")
+        .description(tr!("View who ripped, cancelled or deleted what"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            tr!("Only show what this user did"),
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "from",
+            tr!("Only show what was done on or after this date, as YYYY-MM-DD"),
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "to",
+            tr!("Only show what was done on or before this date, as YYYY-MM-DD"),
+        ))
+}
+
+/// Runs the `audit` command
+///
+/// Shows the latest entries of the audit log, optionally narrowed down to a user and a
+/// range of dates in UTC. The response is only shown to whoever ran it, since it names
+/// other members.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, a date can't be read, the
+/// audit log can't be read, or Discord rejects the response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running audit command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    if !is_admin(command.member.as_deref()) {
+        warn!(
+            "{} tried to view the audit log without permission",
+            command.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    let mut filter = AuditFilter::default();
+    for option in command.data.options() {
+        match (option.name, option.value) {
+            ("user", ResolvedValue::User(user, _)) => filter.user_id = Some(user.id.get()),
+            ("from", ResolvedValue::String(date)) => {
+                filter.from = Some(parse_date(date).ok_or(DiscordError::InvalidComponentData)?);
+            }
+            ("to", ResolvedValue::String(date)) => {
+                filter.until =
+                    Some(parse_end_date(date).ok_or(DiscordError::InvalidComponentData)?);
+            }
+            _ => trace!("Ignoring unknown audit option: {}", option.name),
+        }
+    }
+    trace!("Reading audit log with {:?}", filter);
+
+    let entries = AUDIT_LOG.lock().await.entries(&filter)?;
+
+    let description = if entries.is_empty() {
+        tr!("Nothing matching was found in the audit log.")
+    } else {
+        let lines = entries
+            .iter()
+            .take(MAX_SHOWN_ENTRIES)
+            .map(entry_line)
+            .collect::<Vec<String>>()
+            .join("\n");
+        format!(
+            "{}\n\n{lines}",
+            tr!(
+                "Showing the latest {shown} of {total} entries.",
+                shown = entries.len().min(MAX_SHOWN_ENTRIES),
+                total = entries.len()
+            )
+        )
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Audit Log"))
+                            .description(description),
+                    ),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Describes an entry on one line, the time is shown in each viewer's own time zone
+fn entry_line(entry: &AuditEntry) -> String {
+    let action = match entry.action {
+        AuditAction::Rip => tr!("ripped"),
+        AuditAction::Cancel => tr!("cancelled"),
+        AuditAction::Delete => tr!("deleted"),
+    };
+    let outcome = match &entry.outcome {
+        AuditOutcome::Succeeded => tr!("done"),
+        AuditOutcome::Failed(reason) => tr!("failed: {reason}", reason = reason),
+        AuditOutcome::Cancelled => tr!("was cancelled"),
+    };
+
+    let line = tr!(
+        "<t:{time}:f> **{user}** {action} {parameters} ({outcome})",
+        time = entry.time,
+        user = entry.user_name,
+        action = action,
+        parameters = entry.parameters,
+        outcome = outcome
+    );
+
+    if line.chars().count() > MAX_ENTRY_LENGTH {
+        let cut: String = line.chars().take(MAX_ENTRY_LENGTH - 1).collect();
+        format!("{cut}…")
+    } else {
+        line
+    }
+}
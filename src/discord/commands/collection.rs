@@ -0,0 +1,226 @@
+use serenity::all::{
+    CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+    Interaction, ResolvedOption, ResolvedValue,
+};
+
+use crate::collection::{CollectionEntry, COLLECTION};
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::ephemeral;
+use crate::discord::errors::{DiscordError, Result};
+
+use crate::{debug, error, info, tr, trace};
+
+/// The most discs listed at once, to stay within Discord's embed length limit
+const MAX_LISTED_DISCS: usize = 25;
+
+pub fn register() -> CreateCommand {
+    debug!("Registered collection command");
+    CreateCommand::new("collection")
+        .description(tr!("Keep a list of the discs still to be ripped"))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "add",
+                tr!("Add a disc to the list"),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "title",
+                    tr!("The title of the disc"),
+                )
+                .required(true),
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "label",
+                tr!("The volume label of the disc, if it isn't the title, e.g. HEAT_DISC_1"),
+            )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            tr!("List the discs still to be ripped"),
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "done",
+                tr!("Mark a disc on the list as ripped"),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "id",
+                    tr!("The number of the disc, as shown by /collection list"),
+                )
+                .min_int_value(1)
+                .required(true),
+            ),
+        )
+}
+
+/// Runs the `collection` command
+///
+/// The subcommands are:
+/// - `/collection add` puts a disc on the list, matched to inserted discs by its title or
+///   the volume label given
+/// - `/collection list` shows the discs still to be ripped
+/// - `/collection done` marks a disc as ripped, taking it off the list
+///
+/// Discs on the list are pointed out by `/view_drives` and `/rip` when they are inserted.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the subcommand is unknown, there is no disc with the id
+/// given, the collection can't be saved, or Discord rejects the response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running collection command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    let Some(ResolvedOption {
+        name,
+        value: ResolvedValue::SubCommand(options),
+        ..
+    }) = command.data.options().into_iter().next()
+    else {
+        debug!("Collection called without a subcommand, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    let embed = match name {
+        "add" => {
+            let mut title = None;
+            let mut label = None;
+            for option in options {
+                match (option.name, option.value) {
+                    ("title", ResolvedValue::String(value)) => title = Some(value),
+                    ("label", ResolvedValue::String(value)) => label = Some(value),
+                    _ => trace!("Ignoring unknown collection option: {}", option.name),
+                }
+            }
+            let title = title
+                .filter(|title| !title.trim().is_empty())
+                .ok_or(DiscordError::InvalidComponentData)?;
+
+            let entry = COLLECTION
+                .lock()
+                .await
+                .add(title, label, &command.user.name)?;
+            info!(
+                "{} added {} to the collection as disc {}",
+                command.user.name, entry.title, entry.id
+            );
+
+            themed_embed()
+                .title(tr!("Added to Collection"))
+                .description(tr!(
+                "**{title}** is disc {id} on the list, it will be pointed out when it is inserted.",
+                title = entry.title,
+                id = entry.id
+            ))
+        }
+        "list" => list_embed(COLLECTION.lock().await.entries()),
+        "done" => {
+            let id = options
+                .iter()
+                .find_map(|option| match (option.name, &option.value) {
+                    ("id", ResolvedValue::Integer(id)) => u64::try_from(*id).ok(),
+                    _ => None,
+                })
+                .ok_or(DiscordError::InvalidComponentData)?;
+
+            let entry = COLLECTION.lock().await.done(id)?;
+            info!(
+                "{} marked {} in the collection as ripped",
+                command.user.name, entry.title
+            );
+
+            themed_embed()
+                .title(tr!("Marked as Ripped"))
+                .description(tr!("**{title}** is off the list.", title = entry.title))
+        }
+        _ => {
+            debug!("Unknown collection subcommand: {}, ignoring", name);
+            return Err(DiscordError::InvalidInteractionCall);
+        }
+    };
+
+    respond(ctx, command, embed).await
+}
+
+/// Lists the discs still to be ripped, oldest first
+fn list_embed(entries: &[CollectionEntry]) -> CreateEmbed {
+    let pending: Vec<&CollectionEntry> = entries
+        .iter()
+        .filter(|entry| entry.done.is_none())
+        .collect();
+    let ripped = entries.len() - pending.len();
+
+    let embed = themed_embed().title(tr!("Collection"));
+    if pending.is_empty() {
+        return embed.description(tr!(
+            "No discs are waiting to be ripped, add them with `/collection add`."
+        ));
+    }
+
+    let mut lines: Vec<String> = pending
+        .iter()
+        .take(MAX_LISTED_DISCS)
+        .map(|entry| match &entry.label {
+            Some(label) => tr!(
+                "**{id}.** {title} (`{label}`), added by {user}",
+                id = entry.id,
+                title = entry.title,
+                label = label,
+                user = entry.added_by
+            ),
+            None => tr!(
+                "**{id}.** {title}, added by {user}",
+                id = entry.id,
+                title = entry.title,
+                user = entry.added_by
+            ),
+        })
+        .collect();
+    if pending.len() > MAX_LISTED_DISCS {
+        lines.push(tr!(
+            "...and {count} more",
+            count = pending.len() - MAX_LISTED_DISCS
+        ));
+    }
+
+    embed
+        .description(lines.join("\n"))
+        .footer(CreateEmbedFooter::new(tr!(
+            "{pending} to rip, {ripped} ripped",
+            pending = pending.len(),
+            ripped = ripped
+        )))
+}
+
+async fn respond(ctx: &Context, command: &CommandInteraction, embed: CreateEmbed) -> Result<()> {
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(ephemeral())
+                    .embed(embed),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
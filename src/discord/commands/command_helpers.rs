@@ -1 +1,158 @@
+use std::sync::Arc;
 
+use serenity::all::{
+    ActionRowComponent, Context, Member, ModalInteraction, Permissions, User, UserId,
+};
+
+use crate::audit::{AuditAction, AuditEntry, AuditOutcome, AUDIT_LOG};
+use crate::discord::bot::bot_core::MakeMkvKey;
+use crate::discord::errors::{DiscordError, Result};
+use crate::history::history_core::now;
+use crate::makemkv::MakeMkv;
+use crate::quota::QUOTAS;
+use crate::{error, trace, warn};
+
+/// Gets the value of a text input from a submitted modal by its custom id.
+///
+/// Returns `None` if the input doesn't exist or was left empty.
+#[must_use]
+pub fn get_modal_input(modal: &ModalInteraction, custom_id: &str) -> Option<String> {
+    modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == custom_id => {
+                input.value.clone()
+            }
+            _ => None,
+        })
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Checks whether the member who triggered an interaction is a server administrator.
+///
+/// Discord only hides admin commands from other members, anyone who can see the resulting
+/// message can still press its buttons, so components need to check this themselves.
+#[must_use]
+pub fn is_admin(member: Option<&Member>) -> bool {
+    member
+        .and_then(|member| member.permissions)
+        .is_some_and(Permissions::administrator)
+}
+
+/// Counts a rip against the quota of the user starting it. Server administrators and
+/// members with one of the exempt roles are never limited.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user is cooling down or has reached their daily or
+/// weekly quota, in which case the rip isn't counted.
+pub async fn take_rip_quota(user: UserId, member: Option<&Member>) -> Result<()> {
+    if is_admin(member) {
+        return Ok(());
+    }
+
+    let roles: Vec<u64> = member
+        .map(|member| member.roles.iter().map(|role| role.get()).collect())
+        .unwrap_or_default();
+
+    let mut quotas = QUOTAS.lock().await;
+    if quotas.is_exempt(&roles) {
+        trace!("User {} has an exempt role, not counting the rip", user);
+        return Ok(());
+    }
+
+    quotas.take(user.get(), now())?;
+    Ok(())
+}
+
+/// Records an action in the audit log. The action has already happened, so a log that
+/// can't be written to is only warned about.
+pub async fn audit(
+    user: &User,
+    command: &str,
+    action: AuditAction,
+    parameters: String,
+    outcome: AuditOutcome,
+) {
+    let entry = AuditEntry {
+        time: now(),
+        user_id: user.id.get(),
+        user_name: user.name.clone(),
+        command: command.to_string(),
+        action,
+        parameters,
+        outcome,
+    };
+
+    if let Err(e) = AUDIT_LOG.lock().await.record(&entry) {
+        warn!(
+            "Failed to record {:?} by {} in the audit log: {}",
+            action, user.name, e
+        );
+    }
+}
+
+/// How the result of an interaction is recorded in the audit log
+#[must_use]
+pub fn audit_outcome(result: &Result<()>) -> AuditOutcome {
+    match result {
+        Ok(()) => AuditOutcome::Succeeded,
+        Err(e) if matches!(e.unreported(), DiscordError::TaskCancelled) => AuditOutcome::Cancelled,
+        Err(e) => AuditOutcome::Failed(e.to_string()),
+    }
+}
+
+/// Records someone pressing cancel on a running rip, which isn't always whoever started it
+pub async fn audit_cancel(user: &User, command: &str, name: &str, drive_number: u8) {
+    let parameters = rip_parameters(name, drive_number, None, false);
+    audit(
+        user,
+        command,
+        AuditAction::Cancel,
+        parameters,
+        AuditOutcome::Succeeded,
+    )
+    .await;
+}
+
+/// Describes a rip for the audit log, such as
+/// `Inception (2010) on drive 1, Title 3, ejected when done`
+#[must_use]
+pub fn rip_parameters(
+    name: &str,
+    drive_number: u8,
+    titles: Option<&str>,
+    eject_when_done: bool,
+) -> String {
+    let mut parameters = format!("{name} on drive {drive_number}");
+    if let Some(titles) = titles.filter(|titles| !titles.is_empty()) {
+        parameters.push_str(", ");
+        parameters.push_str(&titles.replace('\n', ", "));
+    }
+    if eject_when_done {
+        parameters.push_str(", ejected when done");
+    }
+    parameters
+}
+
+/// Gets the `MakeMkv` the bot was started with, from the client's data.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if it was never added, which only happens if the client was
+/// built without it.
+pub async fn makemkv(ctx: &Context) -> Result<Arc<MakeMkv>> {
+    ctx.data
+        .read()
+        .await
+        .get::<MakeMkvKey>()
+        .cloned()
+        .ok_or_else(|| {
+            error!("MakeMKV was not added to the client's data");
+            DiscordError::Unexpected("MakeMKV is not set up".to_string())
+        })
+}
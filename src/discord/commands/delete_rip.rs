@@ -0,0 +1,259 @@
+use serenity::all::{
+    ButtonStyle, ComponentInteractionDataKind, Context, CreateButton, CreateCommand,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, Interaction, Permissions,
+};
+
+use crate::audit::{AuditAction, AuditOutcome};
+use crate::discord::commands::command_helpers::makemkv;
+use crate::discord::commands::command_helpers::{audit, is_admin};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::{edit_message, ephemeral};
+use crate::discord::errors::{DiscordError, Result};
+use crate::history::HISTORY;
+use crate::library::LIBRARY;
+
+use crate::{debug, error, info, tr, trace, warn};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered delete_rip command");
+    CreateCommand::new("delete_rip")
+        .description(tr!("Delete a recently ripped title"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+}
+
+/// Runs the `delete_rip` command
+///
+/// The flow is:
+/// 1. `/delete_rip` lists the most recent rips from the history in a select menu
+/// 2. Selecting a rip shows its files along with confirm and cancel buttons
+/// 3. Confirming moves the files to the trash (or deletes them) and removes the rip
+///    from the history
+///
+/// Only server administrators are able to use this command.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, the interaction data is
+/// invalid, or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running delete_rip command");
+
+    match interaction {
+        Interaction::Command(command) => {
+            trace!("Got request from command interaction");
+
+            if !is_admin(command.member.as_deref()) {
+                warn!(
+                    "{} tried to delete a rip without permission",
+                    command.user.name
+                );
+                return Err(DiscordError::PermissionDenied);
+            }
+
+            // Discord only allows 25 options in a select menu
+            let options: Vec<CreateSelectMenuOption> = HISTORY
+                .lock()
+                .await
+                .recent(25)
+                .into_iter()
+                .map(|entry| CreateSelectMenuOption::new(entry.label(), entry.id.to_string()))
+                .collect();
+
+            let response = if options.is_empty() {
+                CreateInteractionResponseMessage::new().embed(
+                    themed_embed()
+                        .title(tr!("Delete Rip"))
+                        .description(tr!("There are no rips in the history to delete.")),
+                )
+            } else {
+                CreateInteractionResponseMessage::new()
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Delete Rip"))
+                            .description(tr!("Please select the rip to delete.")),
+                    )
+                    .select_menu(CreateSelectMenu::new(
+                        "select_rip_to_delete",
+                        CreateSelectMenuKind::String { options },
+                    ))
+            };
+
+            command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(response.ephemeral(ephemeral())),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create response: {:?}", e);
+                    DiscordError::CommandInteractionResponseFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        Interaction::Component(component) => {
+            trace!("Got request from component interaction");
+
+            if !is_admin(component.member.as_ref()) {
+                warn!(
+                    "{} tried to delete a rip without permission",
+                    component.user.name
+                );
+                return Err(DiscordError::PermissionDenied);
+            }
+
+            let message = &component.message;
+
+            component.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            let edit = match custom_ids::name(&component.data.custom_id) {
+                "select_rip_to_delete" => {
+                    let ComponentInteractionDataKind::StringSelect { values } =
+                        &component.data.kind
+                    else {
+                        warn!("Recieved invalid component data, ignoring");
+                        return Err(DiscordError::InvalidComponentData);
+                    };
+                    let id: u64 = values[0].parse().map_err(|_| {
+                        warn!("Failed to parse selected rip, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let entry = HISTORY.lock().await.get(id).cloned().ok_or_else(|| {
+                        warn!("Selected rip {} is no longer in the history", id);
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let files = entry
+                        .files
+                        .iter()
+                        .map(|file| format!("`{}`", file.display()))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+
+                    // The rip id is stored in the embed so the confirm button knows
+                    // which rip to delete
+                    EditMessage::new()
+                        .embed(
+                            themed_embed()
+                                .title(tr!("Delete Rip"))
+                                .description(tr!(
+                                    "Are you sure you want to delete **{rip}**?",
+                                    rip = entry.label()
+                                ))
+                                .field(tr!("Rip Id"), entry.id.to_string(), true)
+                                .field(tr!("Files"), files, false),
+                        )
+                        .components(vec![])
+                        .button(
+                            CreateButton::new("confirm_delete_rip")
+                                .label(tr!("Delete"))
+                                .style(ButtonStyle::Danger),
+                        )
+                        .button(
+                            CreateButton::new("cancel_delete_rip")
+                                .label(tr!("Cancel"))
+                                .style(ButtonStyle::Secondary),
+                        )
+                }
+                "confirm_delete_rip" => {
+                    let id: u64 = message
+                        .embeds
+                        .first()
+                        .and_then(|embed| {
+                            embed
+                                .fields
+                                .iter()
+                                .find(|field| field.name == tr!("Rip Id"))
+                        })
+                        .and_then(|field| field.value.parse().ok())
+                        .ok_or_else(|| {
+                            warn!("Failed to parse rip id from message, ignoring");
+                            DiscordError::InvalidComponentData
+                        })?;
+
+                    // Empty folders are only tidied up within the library the rip is in
+                    let makemkv = makemkv(ctx).await?;
+                    let result = {
+                        let mut history = HISTORY.lock().await;
+                        let root = history
+                            .get(id)
+                            .and_then(|entry| entry.files.first())
+                            .map_or(makemkv.output_dir.clone(), |file| {
+                                makemkv.library_root(makemkv.library_of(file)).to_path_buf()
+                            });
+                        history.delete(id, &root).await
+                    };
+
+                    // The rip is gone from the history once deleted, so it's named from it
+                    // in the log rather than by its id alone
+                    let (parameters, outcome) = match &result {
+                        Ok(entry) => (
+                            format!("{} (rip {id})", entry.label()),
+                            AuditOutcome::Succeeded,
+                        ),
+                        Err(e) => (format!("Rip {id}"), AuditOutcome::Failed(e.to_string())),
+                    };
+                    audit(
+                        &component.user,
+                        "delete_rip",
+                        AuditAction::Delete,
+                        parameters,
+                        outcome,
+                    )
+                    .await;
+
+                    let embed = match result {
+                        Ok(entry) => {
+                            info!("{} deleted {}", component.user.name, entry.label());
+
+                            let mut library = LIBRARY.lock().await;
+                            for file in &entry.files {
+                                library.remove(file);
+                            }
+
+                            themed_embed()
+                                .title(tr!("Rip Deleted"))
+                                .description(tr!("Deleted **{rip}**", rip = entry.label()))
+                        }
+                        Err(e) => {
+                            error!("Failed to delete rip {}: {}", id, e);
+                            themed_embed()
+                                .title(tr!("Delete Failed"))
+                                .description(tr!("Failed to delete rip: {error}", error = e))
+                        }
+                    };
+
+                    EditMessage::new().components(vec![]).embed(embed)
+                }
+                "cancel_delete_rip" => EditMessage::new().components(vec![]).embed(
+                    themed_embed()
+                        .title(tr!("Delete Cancelled"))
+                        .description(tr!("No files were deleted.")),
+                ),
+                _ => {
+                    debug!("Unknown component: {}, ignoring", component.data.custom_id);
+                    return Err(DiscordError::InvalidComponentData);
+                }
+            };
+
+            edit_message(ctx, &component.token, message, edit)
+                .await
+                .map_err(|e| {
+                    error!("Failed to edit message: {:?}", e);
+                    DiscordError::EditMessageFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
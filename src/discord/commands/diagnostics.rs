@@ -0,0 +1,124 @@
+use serenity::all::{
+    Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage,
+    EditInteractionResponse, Interaction, Permissions,
+};
+
+use crate::diagnostics::{permission_checks, run_checks, Check, CheckStatus};
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::commands::command_helpers::makemkv;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::response_options;
+use crate::discord::errors::{DiscordError, Result};
+
+use crate::{debug, error, tr, trace, warn};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered diagnostics command");
+    CreateCommand::new("diagnostics")
+        .description(tr!("Check everything the bot needs to rip discs"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+}
+
+/// Runs the `diagnostics` command
+///
+/// Checks `MakeMKV`, the drives, the output directory, the optional tools and the bot's
+/// permissions in the channel, and shows them as a checklist. The response is only shown
+/// to whoever ran it, since it can include paths on the server.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, or Discord rejects a
+/// response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running diagnostics command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    if !is_admin(command.member.as_deref()) {
+        warn!(
+            "{} tried to run diagnostics without permission",
+            command.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    // Scanning the drives takes longer than Discord waits for a response
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Running Diagnostics"))
+                            .description(tr!("This takes a few seconds for each drive...")),
+                    ),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    let output_dir = makemkv(ctx).await?.output_dir.clone();
+    let mut checks = run_checks(&output_dir).await;
+    checks.extend(permission_checks(
+        command.app_permissions,
+        response_options().threads,
+    ));
+
+    command
+        .edit_response(
+            &ctx.http,
+            EditInteractionResponse::new().embed(
+                themed_embed()
+                    .title(tr!("Diagnostics"))
+                    .description(checks_report(&checks)),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to edit response: {:?}", e);
+            DiscordError::EditResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Sums up the checks, followed by the checklist
+pub(super) fn checks_report(checks: &[Check]) -> String {
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+    let summary = if failed == 0 {
+        tr!("Every check passed.")
+    } else {
+        tr!("{failed} check(s) failed.", failed = failed)
+    };
+
+    format!("{summary}\n\n{}", checklist(checks))
+}
+
+/// Formats the checks as a checklist, one line for each
+fn checklist(checks: &[Check]) -> String {
+    checks
+        .iter()
+        .map(|check| {
+            let icon = match check.status {
+                CheckStatus::Pass => "✅",
+                CheckStatus::Warn => "⚠️",
+                CheckStatus::Fail => "❌",
+            };
+            format!("{icon} **{}**: {}", check.name, check.detail)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,101 @@
+use serenity::all::{
+    Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage,
+    Interaction,
+};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::ephemeral;
+use crate::discord::errors::{DiscordError, Result};
+use crate::history::{DriveStats, HISTORY};
+
+use crate::{debug, error, tr, trace};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered drive_stats command");
+    CreateCommand::new("drive_stats").description(tr!("View rip statistics for each drive"))
+}
+
+/// Runs the `drive_stats` command
+///
+/// Shows the all time and recent failure rate and read speed of each drive, a drive whose
+/// recent numbers are much worse than its all time numbers may be starting to fail.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if Discord rejects the response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running drive_stats command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    let fields: Vec<(String, String, bool)> = HISTORY
+        .lock()
+        .await
+        .drive_stats()
+        .iter()
+        .map(|(drive_number, stats)| {
+            (
+                tr!("Drive {drive}", drive = drive_number),
+                format_stats(stats),
+                false,
+            )
+        })
+        .collect();
+
+    let embed = themed_embed().title(tr!("Drive Statistics"));
+
+    let embed = if fields.is_empty() {
+        embed.description(tr!("No rips have been recorded yet."))
+    } else {
+        embed
+            .description(tr!("Recent stats cover each drive's last 20 rips."))
+            .fields(fields)
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(ephemeral())
+                    .embed(embed),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Formats the stats of a drive, showing the all time values with the recent ones beside them
+fn format_stats(stats: &DriveStats) -> String {
+    tr!(
+        "Rips completed: {completed}\nFailures: {failed}\nRetries: {retries}\nFailure rate: {rate} (recent {recent_rate})\nAverage speed: {speed} (recent {recent_speed})",
+        completed = stats.completed,
+        failed = stats.failed,
+        retries = stats.retries,
+        rate = format_rate(stats.failure_rate()),
+        recent_rate = format_rate(stats.recent_failure_rate()),
+        speed = format_speed(stats.average_speed()),
+        recent_speed = format_speed(stats.recent_average_speed()),
+    )
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map_or_else(|| tr!("n/a"), |rate| format!("{:.0}%", rate * 100.0))
+}
+
+fn format_speed(bytes_per_second: Option<f64>) -> String {
+    bytes_per_second.map_or_else(
+        || tr!("n/a"),
+        |speed| format!("{:.2} MB/s", speed / (1024.0 * 1024.0)),
+    )
+}
@@ -1,10 +1,10 @@
 use serenity::all::CreateCommand;
 
-use crate::debug;
+use crate::{debug, tr};
 
 pub fn register() -> CreateCommand {
     debug!("Regisered eject_disc command");
-    CreateCommand::new("eject_disc").description("Eject the disc from the drive")
+    CreateCommand::new("eject_disc").description(tr!("Eject the disc from the drive"))
 }
 
 pub fn run() {
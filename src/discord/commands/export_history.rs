@@ -0,0 +1,103 @@
+use serenity::all::{
+    CommandOptionType, Context, CreateAttachment, CreateCommand, CreateCommandOption,
+    CreateInteractionResponse, CreateInteractionResponseMessage, Interaction, Permissions,
+    ResolvedValue,
+};
+
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::errors::{DiscordError, Result};
+use crate::history::export::{export, ExportFormat};
+use crate::history::HISTORY;
+
+use crate::{debug, error, tr, trace, warn};
+
+/// The largest export sent, Discord refuses bigger attachments on servers without boosts
+const MAX_EXPORT_SIZE: usize = 8 * 1024 * 1024;
+
+pub fn register() -> CreateCommand {
+    debug!("Registered export_history command");
+    CreateCommand::new("export_history")
+        .description(tr!("Download the rip history and drive statistics"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "format",
+                tr!("The format to export as, CSV by default"),
+            )
+            .add_string_choice("CSV", "csv")
+            .add_string_choice("JSON", "json"),
+        )
+}
+
+/// Runs the `export_history` command
+///
+/// Sends every rip in the history and the statistics of every drive as files, CSV for
+/// spreadsheets or JSON, only to whoever ran it.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, the export is too large
+/// to attach, or Discord rejects the response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running export_history command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    if !is_admin(command.member.as_deref()) {
+        warn!(
+            "{} tried to export the history without permission",
+            command.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    let format = command
+        .data
+        .options()
+        .into_iter()
+        .find_map(|option| match (option.name, option.value) {
+            ("format", ResolvedValue::String(format)) => ExportFormat::from_name(format),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let history = HISTORY.lock().await;
+    let rips = history.entries().len();
+    let drives = history.drive_stats().len();
+    let files = export(history.entries(), history.drive_stats(), format)?;
+    drop(history);
+
+    if files.iter().map(|file| file.contents.len()).sum::<usize>() > MAX_EXPORT_SIZE {
+        return Err(DiscordError::InvalidInput(tr!(
+            "The history is too large to attach, export it with `cord-ripper export-history` instead."
+        )));
+    }
+
+    let mut response = CreateInteractionResponseMessage::new()
+        .ephemeral(true)
+        .embed(themed_embed().title(tr!("History Export")).description(tr!(
+            "Exported {rips} rip(s) and the statistics of {drives} drive(s).",
+            rips = rips,
+            drives = drives
+        )));
+    for file in files {
+        response = response.add_file(CreateAttachment::bytes(file.contents, file.name));
+    }
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await
+        .map_err(|e| {
+            error!("Failed to send history export: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use serenity::all::{Context, CreateActionRow, EditMessage, Embed, Interaction};
+
+use crate::arr::movie_runtime;
+use crate::discord::commands::rip::{
+    field, movie_title_menu, movie_title_options, show_title_menu, show_title_options,
+    title_buttons,
+};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::edit_message;
+use crate::discord::errors::{DiscordError, Result};
+use crate::makemkv::makemkv_helpers::{parse_length, SCANNED_TITLES};
+use crate::makemkv::{get_title_info, Title};
+
+use crate::{debug, error, tr, trace, warn};
+
+/// Runs the length filter buttons of the title select step of the `rip` command
+///
+/// Episode lengths vary wildly between shows, so the titles can be narrowed down to those
+/// over 20 or 40 minutes, or all of them shown again. The titles are filtered from the
+/// last scan of the disc, so the disc isn't read again unless the bot restarted since.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the message isn't a title select message, the disc can't
+/// be scanned again, or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Filter titles was called");
+
+    let Interaction::Component(component) = interaction else {
+        debug!("Unknown interaction calling filter titles, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+    trace!("Got {} component interaction", component.data.custom_id);
+
+    let min_minutes: u32 = match custom_ids::name(&component.data.custom_id) {
+        "filter_titles_all" => 0,
+        id => id
+            .strip_prefix("filter_titles_")
+            .and_then(|minutes| minutes.parse().ok())
+            .ok_or_else(|| {
+                warn!("Unknown title filter: {}, ignoring", id);
+                DiscordError::InvalidComponentData
+            })?,
+    };
+
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    let message = &component.message;
+    let embed = message.embeds.first().ok_or_else(|| {
+        warn!("Title select message has no embed, ignoring");
+        DiscordError::InvalidComponentData
+    })?;
+
+    let drive_number: u8 = field(Some(embed), &tr!("Disc Number"))
+        .and_then(|drive_number| drive_number.parse().ok())
+        .ok_or_else(|| {
+            warn!("Failed to parse disc number from message, ignoring");
+            DiscordError::InvalidComponentData
+        })?;
+
+    // The scan is only missing if the bot was restarted since the titles were listed
+    let scanned = SCANNED_TITLES.lock().await.get(&drive_number).cloned();
+    let titles = match scanned {
+        Some(disc_info) => disc_info.titles,
+        None => {
+            get_title_info(drive_number)
+                .await
+                .map_err(|e| {
+                    error!("Failed to get title info: {:?}", e);
+                    DiscordError::MakeMkvError(e)
+                })?
+                .titles
+        }
+    };
+
+    let titles: Vec<Title> = titles
+        .into_iter()
+        .filter(|title| {
+            parse_length(&title.length).is_none_or(|seconds| seconds >= min_minutes * 60)
+        })
+        .collect();
+    trace!(
+        "{} titles are at least {} minutes long",
+        titles.len(),
+        min_minutes
+    );
+
+    let is_movie = embed.title.as_deref() == Some(tr!("Rip Movie").as_str());
+    let menu = if titles.is_empty() {
+        None
+    } else if is_movie {
+        let title = field(Some(embed), &tr!("Title")).unwrap_or_default();
+        let year = field(Some(embed), &tr!("Year")).and_then(|year| year.parse().ok());
+        let runtime = movie_runtime(title, year).await;
+        Some(movie_title_menu(movie_title_options(&titles, runtime)))
+    } else {
+        Some(show_title_menu(show_title_options(&titles)))
+    };
+
+    let mut components: Vec<CreateActionRow> =
+        menu.into_iter().map(CreateActionRow::SelectMenu).collect();
+    components.push(title_buttons(min_minutes));
+
+    // Previews are of the titles that were listed, so they are dropped along with them
+    edit_message(
+        ctx,
+        &component.token,
+        message,
+        EditMessage::new()
+            .remove_all_attachments()
+            .embeds(vec![filtered_embed(embed, &titles, min_minutes, is_movie)])
+            .components(components),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to send filtered titles: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Rebuilds the title select embed for the filtered titles, noting when none of them are
+/// long enough or some of them don't fit in the menu
+fn filtered_embed(
+    embed: &Embed,
+    titles: &[Title],
+    min_minutes: u32,
+    is_movie: bool,
+) -> serenity::all::CreateEmbed {
+    let description = if titles.is_empty() {
+        tr!(
+            "No titles are longer than {minutes} minutes, try a shorter filter.",
+            minutes = min_minutes
+        )
+    } else if is_movie {
+        tr!("Please select the movie to rip, and any extras to rip along with it")
+    } else {
+        tr!("Please select titles to rip")
+    };
+
+    let note = tr!("Note");
+    let fields = embed
+        .fields
+        .iter()
+        .filter(|field| field.name != note)
+        .map(|field| (field.name.clone(), field.value.clone(), field.inline));
+
+    let mut filtered = themed_embed()
+        .title(embed.title.clone().unwrap_or_default())
+        .description(description)
+        .fields(fields);
+
+    if titles.len() > 25 {
+        filtered = filtered.field(
+            note,
+            tr!("Only the first 25 titles are shown due to Discord API limitations."),
+            false,
+        );
+    }
+
+    filtered
+}
@@ -1,17 +1,63 @@
 use serenity::all::{
-    ComponentInteractionDataKind, Context, CreateCommand, CreateEmbed, CreateInteractionResponse,
-    CreateInteractionResponseMessage, CreateSelectMenu, EditMessage, Interaction,
+    ButtonStyle, ComponentInteractionDataKind, Context, CreateActionRow, CreateButton,
+    CreateCommand, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, EditMessage, Interaction,
 };
 
 use serenity::builder::{CreateSelectMenuKind, CreateSelectMenuOption};
 
-use crate::makemkv::get_title_info;
+use crate::discord::commands::rip::field;
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::{edit_message, ephemeral};
+use crate::discord::errors::{DiscordError, Result};
+use crate::history::{RipSource, HISTORY};
+use crate::library::LIBRARY;
+use crate::makemkv::makemkv_helpers::{parse_length, parse_size, SCANNED_TITLES};
+use crate::makemkv::{get_title_info, DiscInfo, Title};
 
-use crate::{debug, trace};
+use crate::{debug, error, tr, trace, warn};
+
+/// The orders titles can be listed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TitleSort {
+    /// By title number, as `MakeMKV` lists them
+    Number,
+    /// Longest first
+    Duration,
+    /// Largest first
+    Size,
+}
+
+impl TitleSort {
+    const ALL: [Self; 3] = [Self::Number, Self::Duration, Self::Size];
+
+    fn custom_id(self) -> &'static str {
+        match self {
+            Self::Number => "sort_titles_by_number",
+            Self::Duration => "sort_titles_by_duration",
+            Self::Size => "sort_titles_by_size",
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            Self::Number => tr!("Title Number"),
+            Self::Duration => tr!("Duration"),
+            Self::Size => tr!("Size"),
+        }
+    }
+
+    fn from_custom_id(custom_id: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|sort| sort.custom_id() == custom_id)
+    }
+}
 
 pub fn register() -> CreateCommand {
     debug!("Regisered get_titles command");
-    CreateCommand::new("get_titles").description("View the available titles on the disc")
+    CreateCommand::new("get_titles").description(tr!("View the available titles on the disc"))
 }
 
 pub async fn run(ctx: &Context, interaction: &Interaction) {
@@ -26,21 +72,30 @@ pub async fn run(ctx: &Context, interaction: &Interaction) {
                     &ctx.http,
                     CreateInteractionResponse::Message(
                         CreateInteractionResponseMessage::new()
+                            .ephemeral(ephemeral())
                             .add_embed(
-                                CreateEmbed::new()
-                                    .title("Select a disc to view titles")
-                                    .description(
-                                        "Please select a disc to view the available titles.",
-                                    )
-                                    .color(0xfe0000),
+                                themed_embed()
+                                    .title(tr!("Select a disc to view titles"))
+                                    .description(tr!(
+                                        "Please select a disc to view the available titles."
+                                    )),
                             )
                             .select_menu(CreateSelectMenu::new(
                                 "select_disc_to_grab_titles",
                                 CreateSelectMenuKind::String {
                                     options: vec![
-                                        CreateSelectMenuOption::new("Disc 1", "disc_1"),
-                                        CreateSelectMenuOption::new("Disc 2", "disc_2"),
-                                        CreateSelectMenuOption::new("Disc 3", "disc_3"),
+                                        CreateSelectMenuOption::new(
+                                            tr!("Disc {disc}", disc = 1),
+                                            "disc_1",
+                                        ),
+                                        CreateSelectMenuOption::new(
+                                            tr!("Disc {disc}", disc = 2),
+                                            "disc_2",
+                                        ),
+                                        CreateSelectMenuOption::new(
+                                            tr!("Disc {disc}", disc = 3),
+                                            "disc_3",
+                                        ),
                                     ],
                                 },
                             )),
@@ -67,59 +122,37 @@ pub async fn run(ctx: &Context, interaction: &Interaction) {
 
             let title_info_future = get_title_info(drive_number);
 
-            let mut message = component.message.clone();
-            message
-                .edit(
-                    &ctx.http,
-                    EditMessage::new()
-                        .embed(
-                            CreateEmbed::new()
-                                .title(format!("Getting titles for Disc {}", drive_number))
-                                .description("Please wait...")
-                                .color(0xfe0000),
-                        )
-                        .components(vec![]),
-                )
-                .await
-                .unwrap();
-
-            let title_info = title_info_future.await.unwrap();
+            // Acknowledges the select, so the message can be edited through the interaction
+            component.defer(&ctx.http).await.unwrap();
 
-            let mut embeds = vec![CreateEmbed::new()
-                .title(title_info.disc_name)
-                .color(0xfe0000)
-                .description(format!("Found {} titles", title_info.titles.len()))];
-
-            let mut description = String::new();
-            for title in &title_info.titles {
-                description.push_str(&format!(
-                    "**Title {}**\nDuration: {}\nChapters: {}\nSize: {}\nResolution: {}\nFrame Rate: {}\n\n",
-                    title.title_id, title.length, title.chapters, title.size, title.resolution, title.frame_rate
-                ));
-
-                // If the description gets too long, create a new embed
-                if description.len() > 1000 {
-                    embeds.push(
-                        CreateEmbed::new()
-                            .description(description.clone())
-                            .color(0xfe0000),
-                    );
-                    description.clear();
-                }
-            }
+            let message = component.message.clone();
+            edit_message(
+                ctx,
+                &component.token,
+                &message,
+                EditMessage::new()
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Getting titles for Disc {disc}", disc = drive_number))
+                            .description(tr!("Please wait...")),
+                    )
+                    .components(vec![]),
+            )
+            .await
+            .unwrap();
 
-            // Add the remaining description as an embed
-            if !description.is_empty() {
-                embeds.push(CreateEmbed::new().description(description).color(0xfe0000));
-            }
+            let title_info = title_info_future.await.unwrap();
 
-            message
-                .edit(
-                    &ctx.http,
-                    EditMessage::new().embeds(embeds).components(vec![]),
-                )
-                .await
-                .unwrap();
+            edit_message(
+                ctx,
+                &component.token,
+                &message,
+                EditMessage::new()
+                    .embeds(title_embeds(drive_number, &title_info, TitleSort::Number).await)
+                    .components(vec![sort_buttons(TitleSort::Number)]),
+            )
+            .await
+            .unwrap();
         }
 
         _ => {
@@ -128,3 +161,175 @@ pub async fn run(ctx: &Context, interaction: &Interaction) {
         }
     }
 }
+
+/// Runs the sort buttons under the titles listed by the `get_titles` command
+///
+/// The titles are sorted from the last scan of the disc, so the disc isn't read again
+/// unless the bot restarted since.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the message isn't a list of titles, the disc can't be
+/// scanned again, or Discord rejects a response.
+pub async fn sort(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Sort titles was called");
+
+    let Interaction::Component(component) = interaction else {
+        debug!("Unknown interaction calling sort titles, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+    trace!("Got {} component interaction", component.data.custom_id);
+
+    let sort = TitleSort::from_custom_id(custom_ids::name(&component.data.custom_id)).ok_or_else(
+        || {
+            warn!("Unknown title sort: {}, ignoring", component.data.custom_id);
+            DiscordError::InvalidComponentData
+        },
+    )?;
+
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    let message = &component.message;
+    let drive_number: u8 = field(message.embeds.first(), &tr!("Disc Number"))
+        .and_then(|drive_number| drive_number.parse().ok())
+        .ok_or_else(|| {
+            warn!("Failed to parse disc number from message, ignoring");
+            DiscordError::InvalidComponentData
+        })?;
+
+    // The scan is only missing if the bot was restarted since the titles were listed
+    let scanned = SCANNED_TITLES.lock().await.get(&drive_number).cloned();
+    let disc_info = match scanned {
+        Some(disc_info) => disc_info,
+        None => get_title_info(drive_number).await.map_err(|e| {
+            error!("Failed to get title info: {:?}", e);
+            DiscordError::MakeMkvError(e)
+        })?,
+    };
+
+    edit_message(
+        ctx,
+        &component.token,
+        message,
+        EditMessage::new()
+            .embeds(title_embeds(drive_number, &disc_info, sort).await)
+            .components(vec![sort_buttons(sort)]),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to send sorted titles: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    Ok(())
+}
+
+/// The buttons to sort the titles by, with the current order highlighted
+fn sort_buttons(current: TitleSort) -> CreateActionRow {
+    CreateActionRow::Buttons(
+        TitleSort::ALL
+            .into_iter()
+            .map(|sort| {
+                CreateButton::new(sort.custom_id())
+                    .label(sort.label())
+                    .style(if sort == current {
+                        ButtonStyle::Primary
+                    } else {
+                        ButtonStyle::Secondary
+                    })
+            })
+            .collect(),
+    )
+}
+
+/// Lists the titles of a disc in the given order, split over as many embeds as they need
+async fn title_embeds(drive_number: u8, disc_info: &DiscInfo, sort: TitleSort) -> Vec<CreateEmbed> {
+    let mut titles: Vec<&Title> = disc_info.titles.iter().collect();
+    // Titles that can't be parsed go last, sort_by_key is stable so they keep their order
+    match sort {
+        TitleSort::Number => titles.sort_by_key(|title| title.title_id),
+        TitleSort::Duration => {
+            titles.sort_by_key(|title| std::cmp::Reverse(parse_length(&title.length)));
+        }
+        TitleSort::Size => titles.sort_by_key(|title| std::cmp::Reverse(parse_size(&title.size))),
+    }
+
+    let ripped = ripped_titles(disc_info).await;
+
+    let mut embeds = vec![themed_embed()
+        .title(disc_info.disc_name.clone())
+        .description(tr!("Found {count} titles", count = disc_info.titles.len()))
+        .field(tr!("Disc Number"), drive_number.to_string(), true)];
+
+    let mut description = String::new();
+    for title in titles {
+        description.push_str(&tr!(
+            "**Title {title}**\nSource: {source}\nDuration: {length}\nChapters: {chapters}\nSize: {size}\nResolution: {resolution}\nFrame Rate: {frame_rate}",
+            title = title.title_id,
+            source = title.source_file,
+            length = title.length,
+            chapters = title.chapters,
+            size = title.size,
+            resolution = title.resolution,
+            frame_rate = title.frame_rate
+        ));
+        if !title.audio_languages.is_empty() {
+            description.push_str(&tr!(
+                "\nAudio: {languages}",
+                languages = title.audio_languages.join(", ")
+            ));
+        }
+        // The segments tell apart playlists that only differ in their order
+        if !title.segments_map.is_empty() {
+            description.push_str(&tr!(
+                "\nSegments: {segments}",
+                segments = title.segments_map
+            ));
+        }
+        if let Some((_, label)) = ripped.iter().find(|(id, _)| *id == title.title_id) {
+            description.push_str(&tr!("\n✅ Already ripped as **{rip}**", rip = label));
+        }
+        description.push_str("\n\n");
+
+        // If the description gets too long, create a new embed
+        if description.len() > 1000 {
+            embeds.push(themed_embed().description(description.clone()));
+            description.clear();
+        }
+    }
+
+    // Add the remaining description as an embed
+    if !description.is_empty() {
+        embeds.push(themed_embed().description(description));
+    }
+
+    embeds
+}
+
+/// The titles of a disc that were ripped before and are still in the library, with the
+/// label of the rip each was ripped as
+async fn ripped_titles(disc_info: &DiscInfo) -> Vec<(u16, String)> {
+    // Locked in the same order as rips do, the history first
+    let history = HISTORY.lock().await;
+    let library = LIBRARY.lock().await;
+
+    disc_info
+        .titles
+        .iter()
+        .filter_map(|title| {
+            let entry = history.find_source(&RipSource {
+                disc_name: disc_info.disc_name.clone(),
+                source_file: title.source_file.clone(),
+            })?;
+            // Rips deleted or moved out of the library since don't count
+            entry
+                .files
+                .iter()
+                .any(|file| library.contains(file))
+                .then(|| (title.title_id, entry.label()))
+        })
+        .collect()
+}
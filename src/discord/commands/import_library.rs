@@ -0,0 +1,121 @@
+use serenity::all::{
+    Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage,
+    EditInteractionResponse, Interaction, Permissions,
+};
+
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::ephemeral;
+use crate::discord::errors::{DiscordError, Result};
+use crate::history::HISTORY;
+use crate::library::{LibraryIndex, LIBRARY};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered import_library command");
+    CreateCommand::new("import_library")
+        .description(tr!(
+            "Add movies and shows already in the libraries to the history"
+        ))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+}
+
+/// Runs the `import_library` command
+///
+/// The library is imported on the first run, this imports anything added by hand since.
+/// The libraries are rescanned first, so the files found are up to date.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, or Discord rejects the
+/// response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running import_library command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    if !is_admin(command.member.as_deref()) {
+        warn!(
+            "{} tried to import the library without permission",
+            command.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    // Scanning a large library can take longer than Discord waits for a response
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(ephemeral())
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Importing Library"))
+                            .description(tr!("This may take a while for large libraries...")),
+                    ),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    let roots = LIBRARY.lock().await.roots().to_vec();
+    let result = tokio::task::spawn_blocking(move || LibraryIndex::scan(&roots))
+        .await
+        .map_err(|e| DiscordError::Unexpected(e.to_string()))?;
+
+    let embed = match result {
+        Ok(index) => {
+            // Locked in the same order as rips do, the history first
+            let mut history = HISTORY.lock().await;
+            let mut library = LIBRARY.lock().await;
+            *library = index;
+
+            match history.import_library(&library) {
+                Ok(count) => {
+                    info!(
+                        "{} imported {} rips from the library",
+                        command.user.name, count
+                    );
+                    themed_embed()
+                        .title(tr!("Library Imported"))
+                        .description(tr!(
+                            "{count} movies and episodes were added to the history.",
+                            count = count
+                        ))
+                }
+                Err(e) => {
+                    error!("Failed to import the library: {}", e);
+                    themed_embed()
+                        .title(tr!("Import Failed"))
+                        .description(tr!("Failed to import the library: {error}", error = e))
+                }
+            }
+        }
+        Err(e) => {
+            error!("Failed to scan the library for import: {}", e);
+            themed_embed()
+                .title(tr!("Import Failed"))
+                .description(tr!("Failed to import the library: {error}", error = e))
+        }
+    };
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+        .await
+        .map_err(|e| {
+            error!("Failed to edit response: {:?}", e);
+            DiscordError::EditResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
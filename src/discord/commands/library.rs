@@ -0,0 +1,300 @@
+use std::path::{Path, PathBuf};
+
+use serenity::all::{
+    ButtonStyle, CommandOptionType, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, EditInteractionResponse, EditMessage, Interaction,
+    Permissions,
+};
+
+use crate::discord::commands::command_helpers::{is_admin, makemkv};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::{edit_message, ephemeral};
+use crate::discord::errors::{DiscordError, Result};
+use crate::library::doctor::{self, DoctorReport, FixOutcome};
+use crate::library::{LibraryIndex, LIBRARY};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// The most problems of a kind listed, the rest are counted
+const MAX_LISTED_PROBLEMS: usize = 10;
+/// The longest an embed field can be
+const MAX_FIELD_LENGTH: usize = 1024;
+
+pub fn register() -> CreateCommand {
+    debug!("Registered library command");
+    CreateCommand::new("library")
+        .description(tr!("Look after the files in the output directory"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "doctor",
+            tr!("Find empty files, leftover rips, missing episodes and misnamed movies"),
+        ))
+}
+
+/// Runs the `library` command
+///
+/// The flow is:
+/// 1. `/library doctor` rescans the output directory and reports what is wrong with it
+/// 2. If any of it can be fixed, a fix button removes the empty files and leftover
+///    temporary directories, then shows the report again
+///
+/// Only server administrators are able to use this command.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, the subcommand is
+/// unknown, or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running library command");
+
+    match interaction {
+        Interaction::Command(command) => {
+            trace!("Got request from command interaction");
+
+            if !is_admin(command.member.as_deref()) {
+                warn!(
+                    "{} tried to run the library doctor without permission",
+                    command.user.name
+                );
+                return Err(DiscordError::PermissionDenied);
+            }
+
+            let subcommand = command.data.options().first().map(|option| option.name);
+            if subcommand != Some("doctor") {
+                debug!("Unknown library subcommand: {:?}, ignoring", subcommand);
+                return Err(DiscordError::InvalidInteractionCall);
+            }
+
+            // Scanning a large library can take longer than Discord waits for a response
+            command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .ephemeral(ephemeral())
+                            .embed(
+                                themed_embed()
+                                    .title(tr!("Examining Library"))
+                                    .description(tr!(
+                                        "This may take a while for large libraries..."
+                                    )),
+                            ),
+                    ),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create response: {:?}", e);
+                    DiscordError::CommandInteractionResponseFailed(e.to_string())
+                })?;
+
+            let (root, report) = examine(ctx).await?;
+            let mut edit = EditInteractionResponse::new().embed(report_embed(&root, &report));
+            if report.is_fixable() {
+                edit = edit.components(vec![CreateActionRow::Buttons(vec![fix_button()])]);
+            }
+
+            command.edit_response(&ctx.http, edit).await.map_err(|e| {
+                error!("Failed to edit response: {:?}", e);
+                DiscordError::EditResponseFailed(e.to_string())
+            })?;
+
+            Ok(())
+        }
+        Interaction::Component(component) => {
+            trace!("Got request from component interaction");
+
+            if custom_ids::name(&component.data.custom_id) != "fix_library" {
+                debug!("Unknown component: {}, ignoring", component.data.custom_id);
+                return Err(DiscordError::InvalidComponentData);
+            }
+
+            fix_library(ctx, component).await
+        }
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+/// Removes the empty files and leftover temporary directories, then shows what is left
+async fn fix_library(ctx: &Context, component: &ComponentInteraction) -> Result<()> {
+    if !is_admin(component.member.as_ref()) {
+        warn!(
+            "{} tried to fix the library without permission",
+            component.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    // The library is examined again rather than trusting the report on the message,
+    // which could be stale by now
+    let (_, report) = examine(ctx).await?;
+    let outcome = tokio::task::spawn_blocking(move || doctor::fix(&report))
+        .await
+        .map_err(|e| DiscordError::Unexpected(e.to_string()))?;
+
+    info!(
+        "{} fixed the library, removing {} empty files and {} temporary directories",
+        component.user.name,
+        outcome.removed_files.len(),
+        outcome.removed_dirs
+    );
+
+    let (root, report) = examine(ctx).await?;
+    let edit = EditMessage::new()
+        .embeds(vec![fix_embed(&outcome), report_embed(&root, &report)])
+        .components(vec![]);
+
+    edit_message(ctx, &component.token, &component.message, edit)
+        .await
+        .map_err(|e| {
+            error!("Failed to edit message: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Rescans the libraries and looks through them for problems, returning the output
+/// directory along with the report
+async fn examine(ctx: &Context) -> Result<(PathBuf, DoctorReport)> {
+    let active = makemkv(ctx).await?.active_temp_dirs().await;
+    let roots = LIBRARY.lock().await.roots().to_vec();
+
+    let root = roots.first().cloned().unwrap_or_default();
+    let (index, report) = tokio::task::spawn_blocking(move || {
+        let index = LibraryIndex::scan(&roots)?;
+        let report = doctor::examine(&index, &active);
+        Ok::<_, crate::library::errors::LibraryError>((index, report))
+    })
+    .await
+    .map_err(|e| DiscordError::Unexpected(e.to_string()))?
+    .map_err(|e| DiscordError::Unexpected(e.to_string()))?;
+
+    // The scan is kept, so the index picks up anything changed by hand while at it
+    *LIBRARY.lock().await = index;
+
+    Ok((root, report))
+}
+
+fn fix_button() -> CreateButton {
+    CreateButton::new("fix_library")
+        .label(tr!("Remove Empty Files and Leftovers"))
+        .style(ButtonStyle::Danger)
+}
+
+/// Describes what was found in the library
+fn report_embed(root: &Path, report: &DoctorReport) -> CreateEmbed {
+    let embed = themed_embed().title(tr!("Library Doctor"));
+
+    if report.is_healthy() {
+        return embed.description(tr!("No problems were found in the library."));
+    }
+
+    let relative =
+        |path: &PathBuf| format!("`{}`", path.strip_prefix(root).unwrap_or(path).display());
+
+    let mut fields = Vec::new();
+    if !report.empty_files.is_empty() {
+        fields.push((
+            tr!("Empty Files"),
+            list_field(report.empty_files.iter().map(relative).collect()),
+            false,
+        ));
+    }
+    if !report.temp_dirs.is_empty() {
+        fields.push((
+            tr!("Leftover Temporary Directories"),
+            list_field(report.temp_dirs.iter().map(relative).collect()),
+            false,
+        ));
+    }
+    if !report.episode_gaps.is_empty() {
+        let gaps = report
+            .episode_gaps
+            .iter()
+            .map(|gap| {
+                tr!(
+                    "**{show}** season {season}: missing {episodes}",
+                    show = gap.show,
+                    season = gap.season,
+                    episodes = gap
+                        .missing
+                        .iter()
+                        .map(u8::to_string)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            })
+            .collect();
+        fields.push((tr!("Episode Gaps"), list_field(gaps), false));
+    }
+    if !report.name_mismatches.is_empty() {
+        fields.push((
+            tr!("Movies Named Differently to Their Folder"),
+            list_field(report.name_mismatches.iter().map(relative).collect()),
+            false,
+        ));
+    }
+
+    let description = if report.is_fixable() {
+        tr!("Empty files and leftover temporary directories can be removed with the button below. Fix gaps and names with `/rename`, or by hand followed by `/rescan`.")
+    } else {
+        tr!("Fix gaps and names with `/rename`, or by hand followed by `/rescan`.")
+    };
+
+    embed.description(description).fields(fields)
+}
+
+/// Describes what a fix cleaned up
+fn fix_embed(outcome: &FixOutcome) -> CreateEmbed {
+    let mut description = tr!(
+        "Removed {files} empty files and {dirs} temporary directories.",
+        files = outcome.removed_files.len(),
+        dirs = outcome.removed_dirs
+    );
+
+    if !outcome.failed.is_empty() {
+        description.push_str("\n\n");
+        description.push_str(&tr!(
+            "Failed to remove:\n{paths}",
+            paths = list_field(outcome.failed.clone())
+        ));
+    }
+
+    themed_embed()
+        .title(tr!("Library Fixed"))
+        .description(description)
+}
+
+/// Lists items one per line, counting any that don't fit in an embed field
+fn list_field(items: Vec<String>) -> String {
+    let total = items.len();
+    let mut field = String::new();
+    let mut listed = 0;
+
+    for item in items.into_iter().take(MAX_LISTED_PROBLEMS) {
+        // Leaves room for the count of the rest
+        if field.len() + item.len() + 1 > MAX_FIELD_LENGTH - 50 {
+            break;
+        }
+        field.push_str(&item);
+        field.push('\n');
+        listed += 1;
+    }
+
+    if listed < total {
+        field.push_str(&tr!("...and {count} more", count = total - listed));
+    }
+
+    field.trim_end().to_string()
+}
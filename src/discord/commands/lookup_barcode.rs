@@ -0,0 +1,141 @@
+use serenity::all::{
+    Context, CreateActionRow, CreateInputText, CreateInteractionResponse, CreateModal,
+    InputTextStyle, Interaction,
+};
+
+use crate::barcode::{lookup_barcode, normalize_barcode};
+use crate::discord::commands::command_helpers::get_modal_input;
+use crate::discord::commands::rip::{embed_drive_number, field, preset_fields, rip_type_message};
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::edit_message;
+use crate::discord::errors::{DiscordError, Result};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// Runs the barcode lookup of the rip type step of the `rip` command
+///
+/// The flow is:
+/// 1. The "Look Up Barcode" button opens a modal for the UPC or EAN on the disc's case,
+///    which a barcode scanner can type in
+/// 2. The barcode is looked up, and the rip type step is shown again with what was found,
+///    so the title and year are filled in when a rip type is picked
+///
+/// The lookup is optional, so if it finds nothing or fails the rip type can still be
+/// picked and the details entered by hand.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the message isn't a rip type message, or Discord rejects a
+/// response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running barcode lookup");
+
+    match interaction {
+        Interaction::Component(component) => {
+            trace!("Got request from component interaction");
+
+            component
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Modal(
+                        CreateModal::new("get_barcode", tr!("Look up the barcode on the case"))
+                            .components(vec![CreateActionRow::InputText(
+                                CreateInputText::new(
+                                    InputTextStyle::Short,
+                                    tr!("UPC or EAN Barcode"),
+                                    "barcode",
+                                )
+                                .placeholder(tr!("e.g. 883929106796"))
+                                .max_length(20)
+                                .required(true),
+                            )]),
+                    ),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create get_barcode modal: {:?}", e);
+                    DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        Interaction::Modal(modal) => {
+            trace!("Got request from modal interaction");
+
+            modal.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            let message = modal.message.as_deref().ok_or_else(|| {
+                warn!("Barcode modal has no message, ignoring");
+                DiscordError::InvalidComponentData
+            })?;
+            let drive_number = embed_drive_number(message.embeds.first())?;
+
+            // Already checked when the modal was submitted
+            let barcode = get_modal_input(modal, "barcode")
+                .and_then(|barcode| normalize_barcode(&barcode))
+                .ok_or(DiscordError::InvalidComponentData)?;
+
+            let mut embed = themed_embed().title(tr!("Select a rip type")).field(
+                tr!("Disc Number"),
+                drive_number.to_string(),
+                false,
+            );
+            if let Some(label) = field(message.embeds.first(), "Disc Label") {
+                embed = embed.field(tr!("Disc Label"), label, false);
+            }
+            embed = embed.fields(preset_fields(message.embeds.first()).await);
+
+            embed = match lookup_barcode(&barcode).await {
+                Ok(Some(product)) => {
+                    info!(
+                        "{} looked up barcode {}, found {}",
+                        modal.user.name, barcode, product.title
+                    );
+
+                    // Named like the rip modals' fields, so they are filled in from them
+                    embed = embed
+                        .description(tr!(
+                            "Found **{title}** for barcode {barcode}. Pick a rip type, the title and year are filled in.",
+                            title = product.title,
+                            barcode = barcode
+                        ))
+                        .field(tr!("Title"), &product.title, true);
+                    if let Some(year) = product.year {
+                        embed = embed.field(tr!("Year"), year.to_string(), true);
+                    }
+                    match product.edition {
+                        Some(edition) => embed.field(tr!("Edition"), edition, true),
+                        None => embed,
+                    }
+                }
+                Ok(None) => embed.description(tr!(
+                    "Nothing was found for barcode {barcode}. Pick a rip type and enter the details yourself.",
+                    barcode = barcode
+                )),
+                Err(e) => {
+                    warn!("Failed to look up barcode {}: {:?}", barcode, e);
+                    embed.description(tr!(
+                        "{reason} Pick a rip type and enter the details yourself.",
+                        reason = e.user_message()
+                    ))
+                }
+            };
+
+            edit_message(ctx, &modal.token, message, rip_type_message(embed))
+                .await
+                .map_err(|e| {
+                    error!("Failed to edit message: {:?}", e);
+                    DiscordError::EditMessageFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use serenity::all::{
+    ComponentInteraction, ComponentInteractionDataKind, Context, CreateActionRow, CreateButton,
+    CreateEmbed, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditMessage,
+    Embed, Interaction,
+};
+
+use crate::arr::episode_titles;
+use crate::discord::commands::rip::{
+    confirm_rips, embed_rips, format_episode_list, format_episode_titles, format_title_episode,
+};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::edit_message;
+use crate::discord::errors::{DiscordError, Result};
+use crate::makemkv::makemkv_helpers::SCANNED_TITLES;
+use crate::makemkv::{Rip, RipType};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// Runs the episode mapping of the confirmation step of show rips
+///
+/// The flow is:
+/// 1. The "Map Episodes" button lists the season's episodes, by name, from Sonarr
+/// 2. Each selected title is matched to an episode in turn, from a select menu that leaves
+///    out the episodes already picked
+/// 3. Once every title is matched, or "Back to Summary" is pressed, the confirmation is
+///    shown again with the episode names, which end up in the file names
+///
+/// Everything picked so far is kept in the confirmation embed, like the rest of the rip.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the message isn't a show confirmation message, the
+/// selection can't be read, or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running episode mapping");
+
+    let Interaction::Component(component) = interaction else {
+        debug!("Unknown interaction calling map episodes, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+    trace!("Got {} component interaction", component.data.custom_id);
+
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    let message = &component.message;
+    let embed = message.embeds.first().ok_or_else(|| {
+        warn!("Confirmation message has no embed, ignoring");
+        DiscordError::InvalidComponentData
+    })?;
+    let mut rips = embed_rips(embed)?;
+
+    let Some(RipType::Show { season, .. }) = rips.first().map(|rip| rip.rip_type) else {
+        warn!("Episodes can only be mapped for shows, ignoring");
+        return Err(DiscordError::InvalidComponentData);
+    };
+
+    match custom_ids::name(&component.data.custom_id) {
+        "map_episodes" => {
+            let names = episode_titles(&rips[0].title, season).await;
+            if names.is_empty() {
+                info!(
+                    "Sonarr has no episode names for {} season {}",
+                    rips[0].title, season
+                );
+                let notice = mapping_embed(embed, &rips).description(tr!(
+                    "Sonarr has no episodes listed for season {season} of **{title}**, so they can't be mapped. Check the show is in Sonarr with the same name.",
+                    season = season,
+                    title = rips[0].title
+                ));
+                return update(ctx, component, notice, vec![back_row()]).await;
+            }
+
+            let (embed, components) = mapping_step(embed, &rips, &names, 0).await;
+            update(ctx, component, embed, components).await
+        }
+        "map_episode" => {
+            let (index, episode) = match &component.data.kind {
+                ComponentInteractionDataKind::StringSelect { values } => values.first(),
+                _ => None,
+            }
+            .and_then(|value| value.split_once(':'))
+            .and_then(|(index, episode)| {
+                Some((index.parse::<usize>().ok()?, episode.parse::<u8>().ok()?))
+            })
+            .filter(|(index, _)| *index < rips.len())
+            .ok_or_else(|| {
+                warn!("Recieved invalid component data, ignoring");
+                DiscordError::InvalidComponentData
+            })?;
+
+            // Fetched again rather than kept, since the names may be too long for the
+            // values of the select menu
+            let names = episode_titles(&rips[0].title, season).await;
+            let name = names
+                .iter()
+                .find(|(number, _)| *number == episode)
+                .map(|(_, name)| name.clone());
+            remap(&mut rips[index], episode, name);
+            trace!(
+                "Mapped title {} to episode {}",
+                rips[index].title_id,
+                episode
+            );
+
+            if index + 1 < rips.len() {
+                let (embed, components) = mapping_step(embed, &rips, &names, index + 1).await;
+                return update(ctx, component, embed, components).await;
+            }
+
+            info!(
+                "{} mapped the episodes of {} season {}",
+                component.user.name, rips[0].title, season
+            );
+            confirm_rips(ctx, &component.token, message, &rips).await
+        }
+        "map_episodes_back" => confirm_rips(ctx, &component.token, message, &rips).await,
+        id => {
+            debug!("Unknown episode mapping component: {}, ignoring", id);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+/// The step matching the title at `index` to an episode, with a menu of the episodes not
+/// already picked for the titles before it
+async fn mapping_step(
+    embed: &Embed,
+    rips: &[Rip],
+    names: &[(u8, String)],
+    index: usize,
+) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let rip = &rips[index];
+
+    let taken: Vec<u8> = rips[..index].iter().filter_map(Rip::episode).collect();
+    // Discs are usually in order, so the episode after the last one picked is likeliest
+    let expected = match index {
+        0 => rip.episode(),
+        _ => rips[index - 1]
+            .episode()
+            .map(|episode| episode.saturating_add(1)),
+    }
+    .unwrap_or(1);
+
+    // Only 25 fit in a menu, so the episodes from the likeliest one on come first, and
+    // the ones before it after them
+    let mut available: Vec<&(u8, String)> = names
+        .iter()
+        .filter(|(episode, _)| !taken.contains(episode))
+        .collect();
+    available.sort_by_key(|(episode, _)| (*episode < expected, *episode));
+    available.truncate(25);
+
+    let options = available
+        .iter()
+        .map(|(episode, name)| {
+            let label: String = format!("{episode}. {name}").chars().take(100).collect();
+            CreateSelectMenuOption::new(label, format!("{index}:{episode}"))
+        })
+        .collect();
+
+    let length = SCANNED_TITLES
+        .lock()
+        .await
+        .get(&rip.drive_number)
+        .and_then(|disc| {
+            disc.titles
+                .iter()
+                .find(|title| title.title_id == rip.title_id)
+                .map(|title| title.length.clone())
+        });
+    let placeholder = match length {
+        Some(length) => tr!(
+            "Episode of title {title} ({length})",
+            title = rip.title_id,
+            length = length
+        ),
+        None => tr!("Episode of title {title}", title = rip.title_id),
+    };
+
+    let embed = mapping_embed(embed, rips).description(tr!(
+        "Pick the episode on title {title}, {step} of {count}.",
+        title = rip.title_id,
+        step = index + 1,
+        count = rips.len()
+    ));
+    let menu = CreateSelectMenu::new("map_episode", CreateSelectMenuKind::String { options })
+        .placeholder(placeholder);
+
+    (embed, vec![CreateActionRow::SelectMenu(menu), back_row()])
+}
+
+/// Rebuilds the confirmation embed with the episodes mapped so far
+fn mapping_embed(embed: &Embed, rips: &[Rip]) -> CreateEmbed {
+    let selected_titles = tr!("Selected Titles");
+    let episode_titles = tr!("Episode Titles");
+    let episodes = tr!("Episodes");
+
+    let mut fields = Vec::new();
+    for field in &embed.fields {
+        if field.name == episode_titles {
+            continue;
+        }
+        if field.name == selected_titles {
+            let titles = rips
+                .iter()
+                .map(format_title_episode)
+                .collect::<Vec<String>>()
+                .join("\n");
+            fields.push((field.name.clone(), titles, field.inline));
+            if let Some(names) = format_episode_titles(rips) {
+                fields.push((episode_titles.clone(), names, false));
+            }
+        } else if field.name == episodes {
+            let numbers: Vec<u8> = rips.iter().filter_map(Rip::episode).collect();
+            fields.push((
+                field.name.clone(),
+                format_episode_list(&numbers),
+                field.inline,
+            ));
+        } else {
+            fields.push((field.name.clone(), field.value.clone(), field.inline));
+        }
+    }
+
+    themed_embed()
+        .title(embed.title.clone().unwrap_or_default())
+        .fields(fields)
+}
+
+/// Numbers a rip as a different episode with its name, moving its absolute episode along
+/// with it
+fn remap(rip: &mut Rip, episode: u8, name: Option<String>) {
+    if let RipType::Show {
+        episode: current,
+        absolute_episode,
+        ..
+    } = &mut rip.rip_type
+    {
+        *absolute_episode = absolute_episode.and_then(|absolute| {
+            let shifted = i32::from(absolute) + i32::from(episode) - i32::from(*current);
+            u16::try_from(shifted).ok()
+        });
+        *current = episode;
+    }
+    rip.episode_title = name;
+}
+
+fn back_row() -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new("map_episodes_back")
+        .label(tr!("Back to Summary"))
+        .style(serenity::all::ButtonStyle::Secondary)])
+}
+
+async fn update(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    embed: CreateEmbed,
+    components: Vec<CreateActionRow>,
+) -> Result<()> {
+    edit_message(
+        ctx,
+        &component.token,
+        &component.message,
+        EditMessage::new().embed(embed).components(components),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to send episode mapping message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    Ok(())
+}
@@ -1,5 +1,28 @@
+pub mod archive_disc;
+pub mod audit;
+pub mod collection;
 pub mod command_helpers;
+pub mod delete_rip;
+pub mod diagnostics;
+pub mod drive_stats;
 pub mod eject_disc;
+pub mod export_history;
+pub mod filter_titles;
 pub mod get_titles;
+pub mod import_library;
+pub mod library;
+pub mod lookup_barcode;
+pub mod map_episodes;
+pub mod preview_titles;
+pub mod queue;
+pub mod quickrip;
+pub mod rename;
+pub mod rescan;
+pub mod retry_rip;
 pub mod rip;
+pub mod rip_audio;
+pub mod rip_log;
+pub mod setup;
+pub mod status;
+pub mod sync_commands;
 pub mod view_drives;
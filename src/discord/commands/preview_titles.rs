@@ -0,0 +1,148 @@
+use serenity::all::{
+    ActionRowComponent, ComponentInteraction, Context, CreateAttachment, CreateEmbed, EditMessage,
+    Interaction,
+};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::{edit_message, edit_message_attachments};
+use crate::discord::errors::{DiscordError, Result};
+use crate::preview::generate_previews;
+
+use crate::{debug, error, tr, trace, warn};
+
+/// Runs the "Show Previews" button of the title select step of the `rip` command
+///
+/// Grabs a frame from each title in the select menu and adds them to the message, one
+/// embed per title, so the main feature can be told apart from the extras before picking.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the message isn't a title select message, or Discord
+/// rejects a response. Previews that can't be generated are noted on the message instead.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Preview titles was called");
+
+    let Interaction::Component(component) = interaction else {
+        debug!("Unknown interaction calling preview titles, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+    trace!("Got preview_titles component interaction");
+
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    let message = &component.message;
+    let embed = message.embeds.first().ok_or_else(|| {
+        warn!("Title select message has no embed, ignoring");
+        DiscordError::InvalidComponentData
+    })?;
+
+    let drive_number: u8 = embed
+        .fields
+        .iter()
+        .find(|field| field.name == tr!("Disc Number"))
+        .and_then(|field| field.value.parse().ok())
+        .ok_or_else(|| {
+            warn!("Failed to parse disc number from message, ignoring");
+            DiscordError::Unexpected("Failed to parse disc number".to_string())
+        })?;
+
+    // The titles are read back from the select menu, so the previews match its order
+    let options = title_options(component);
+    let title_ids: Vec<u16> = options
+        .iter()
+        .filter_map(|(value, _)| value.parse().ok())
+        .collect();
+
+    edit_message(
+        ctx,
+        &component.token,
+        message,
+        EditMessage::new().embed(
+            CreateEmbed::from(embed.clone())
+                .description(tr!("Generating previews, this can take a minute...")),
+        ),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to send generating previews message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    let previews = match generate_previews(drive_number, &title_ids).await {
+        Ok(previews) => previews,
+        Err(e) => {
+            warn!("Failed to generate previews: {:?}", e);
+
+            edit_message(
+                ctx,
+                &component.token,
+                message,
+                EditMessage::new().embed(CreateEmbed::from(embed.clone()).field(
+                    tr!("Previews"),
+                    e.user_message(),
+                    false,
+                )),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to send previews failed message: {:?}", e);
+                DiscordError::EditMessageFailed(e.to_string())
+            })?;
+
+            return Err(DiscordError::reported(e));
+        }
+    };
+
+    let mut embeds = vec![CreateEmbed::from(embed.clone())];
+    let mut attachments = Vec::new();
+
+    for (title_id, path) in &previews.frames {
+        let attachment = CreateAttachment::path(path).await.map_err(|e| {
+            error!("Failed to read preview of title {}: {:?}", title_id, e);
+            DiscordError::Unexpected(e.to_string())
+        })?;
+
+        let label = options
+            .iter()
+            .find(|(value, _)| *value == title_id.to_string())
+            .map_or_else(
+                || tr!("Title: {title}", title = title_id),
+                |(_, label)| label.clone(),
+            );
+
+        embeds.push(
+            themed_embed()
+                .title(label)
+                .image(format!("attachment://{}", attachment.filename)),
+        );
+        attachments.push(attachment);
+    }
+
+    edit_message_attachments(ctx, &component.token, message, embeds, attachments)
+        .await
+        .map_err(|e| {
+            error!("Failed to send previews: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// The value and label of each option in the message's title select menu
+fn title_options(component: &ComponentInteraction) -> Vec<(String, String)> {
+    component
+        .message
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .filter_map(|component| match component {
+            ActionRowComponent::SelectMenu(menu) => Some(&menu.options),
+            _ => None,
+        })
+        .flatten()
+        .map(|option| (option.value.clone(), option.label.clone()))
+        .collect()
+}
@@ -0,0 +1,278 @@
+use serenity::all::{
+    ChannelId, ComponentInteraction, ComponentInteractionDataKind, Context, CreateActionRow,
+    CreateCommand, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, Interaction,
+    MessageId,
+};
+
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::commands::rip::{eta_text, priority_name, remaining_titles, resume_batch};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::{edit_message, ephemeral};
+use crate::discord::errors::{DiscordError, Result};
+use crate::makemkv::eta::estimate;
+use crate::queue::batches::batches;
+use crate::queue::queue_core::{JobKind, JobState, WaitReason};
+use crate::queue::transfers;
+use crate::queue::QUEUE;
+
+use crate::{debug, error, info, tr, trace, warn};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered queue command");
+    CreateCommand::new("queue").description(tr!("View the rips that are running or waiting"))
+}
+
+/// Runs the queue command
+///
+/// Lists the running and waiting jobs along with their priorities. Administrators also get
+/// a select menu to bump a waiting job to the front of the queue. Batches of show rips that
+/// stopped partway are listed too, with a select menu to resume one.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the interaction data is invalid, the job can't be bumped, or
+/// Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running queue command");
+
+    match interaction {
+        Interaction::Command(command) => {
+            trace!("Got request from command interaction");
+
+            let (embed, menus) = queue_view(is_admin(command.member.as_deref())).await;
+
+            let response = CreateInteractionResponseMessage::new()
+                .ephemeral(ephemeral())
+                .embed(embed)
+                .components(menus);
+
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await
+                .map_err(|e| {
+                    error!("Failed to create response: {:?}", e);
+                    DiscordError::CommandInteractionResponseFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        Interaction::Component(component)
+            if custom_ids::name(&component.data.custom_id) == "resume_batch" =>
+        {
+            trace!("Got request from resume menu");
+            resume(ctx, component).await
+        }
+        // Otherwise called by the bump menu
+        Interaction::Component(component) => {
+            trace!("Got request from component interaction");
+
+            if !is_admin(component.member.as_ref()) {
+                warn!(
+                    "{} tried to bump a job without permission",
+                    component.user.name
+                );
+                return Err(DiscordError::PermissionDenied);
+            }
+
+            let ComponentInteractionDataKind::StringSelect { values } = &component.data.kind else {
+                warn!("Recieved invalid component data, ignoring");
+                return Err(DiscordError::InvalidComponentData);
+            };
+            let id: u64 = values[0].parse().map_err(|_| {
+                warn!("Failed to parse selected job, ignoring");
+                DiscordError::InvalidComponentData
+            })?;
+
+            component.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            // The job may have started or finished since the menu was shown
+            if let Err(e) = QUEUE.lock().await.bump(id) {
+                warn!("Failed to bump job {}: {}", id, e);
+            } else {
+                info!("{} bumped job {}", component.user.name, id);
+            }
+
+            let (embed, menus) = queue_view(true).await;
+            let edit = EditMessage::new().components(menus).embed(embed);
+
+            edit_message(ctx, &component.token, &component.message, edit)
+                .await
+                .map_err(|e| {
+                    error!("Failed to edit message: {:?}", e);
+                    DiscordError::EditMessageFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+/// Resumes the batch picked from the resume menu, in the message it was shown on
+async fn resume(ctx: &Context, component: &ComponentInteraction) -> Result<()> {
+    let ComponentInteractionDataKind::StringSelect { values } = &component.data.kind else {
+        warn!("Recieved invalid component data, ignoring");
+        return Err(DiscordError::InvalidComponentData);
+    };
+    let message_id: u64 = values[0].parse().map_err(|_| {
+        warn!("Failed to parse selected batch, ignoring");
+        DiscordError::InvalidComponentData
+    })?;
+
+    // The batch may have been resumed or finished since the menu was shown
+    let Some(batch) = batches().get(message_id).cloned() else {
+        return Err(DiscordError::InvalidInput(tr!(
+            "This rip has nothing left to resume."
+        )));
+    };
+
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    let message = ChannelId::new(batch.channel_id)
+        .message(&ctx.http, MessageId::new(batch.message_id))
+        .await
+        .map_err(|e| {
+            error!("Failed to get the message of {}: {:?}", batch.label, e);
+            DiscordError::HttpRequestFailed(e.to_string())
+        })?;
+
+    // Points to where the batch carries on, it takes a while
+    edit_message(
+        ctx,
+        &component.token,
+        &component.message,
+        EditMessage::new().components(vec![]).embed(
+            themed_embed().title(tr!("Resuming Rip")).description(tr!(
+                "Resuming {label} in {link}",
+                label = batch.label,
+                link = message.link()
+            )),
+        ),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to edit message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    resume_batch(ctx, &message, &component.user, component.member.as_ref()).await
+}
+
+/// Builds the queue embed, along with a menu to resume a batch that stopped partway and,
+/// for administrators, a menu to bump a waiting job if there are any
+async fn queue_view(admin: bool) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let queue = QUEUE.lock().await;
+    let jobs = queue.jobs();
+
+    let mut embed = themed_embed().title(tr!("Rip Queue"));
+    let mut menus = Vec::new();
+
+    let lines = jobs
+        .iter()
+        .enumerate()
+        .map(|(index, job)| {
+            let state = match (job.state, queue.wait_reason(job.id)) {
+                (JobState::Running, _) => tr!("Running"),
+                (JobState::Copying, _) => tr!(
+                    "Copying to library ({percent}%)",
+                    percent = transfers::progress(job.id).unwrap_or_default()
+                ),
+                (_, Ok(Some(WaitReason::WaitingForSlot))) => tr!("Waiting for slot"),
+                (_, Ok(Some(WaitReason::WaitingForDrive))) => tr!("Waiting for drive"),
+                _ => format!("#{}", index + 1),
+            };
+            let label = match &job.host {
+                Some(host) => tr!("{label} on {host}", label = job.label, host = host),
+                None => job.label.clone(),
+            };
+            let line = tr!(
+                "**{state}** {label} [{priority} priority]",
+                state = state,
+                label = label,
+                priority = priority_name(job.priority)
+            );
+
+            // Running rips say how long they have left, once there is an estimate
+            let eta = match (job.state, job.kind) {
+                (JobState::Running, JobKind::Rip { drive_number }) => estimate(drive_number),
+                _ => None,
+            };
+            match eta {
+                Some(eta) => format!("{line}\n{}", eta_text(&eta)),
+                None => line,
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    embed = embed.description(if lines.is_empty() {
+        tr!("Nothing is ripping or waiting.")
+    } else {
+        lines
+    });
+
+    // Discord only allows 25 options in a select menu
+    let options: Vec<CreateSelectMenuOption> = jobs
+        .iter()
+        .filter(|job| job.state == JobState::Queued)
+        .take(25)
+        .map(|job| CreateSelectMenuOption::new(&job.label, job.id.to_string()))
+        .collect();
+    if admin && !options.is_empty() {
+        menus.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new("bump_queued_job", CreateSelectMenuKind::String { options })
+                .placeholder(tr!("Bump a job to the front of the queue")),
+        ));
+    }
+
+    // Batches that stopped partway aren't in the queue, but can be put back in it
+    let stopped: Vec<(String, String, u64)> = batches()
+        .stopped()
+        .iter()
+        .map(|batch| {
+            (
+                batch.label.clone(),
+                remaining_titles(&batch.rips),
+                batch.message_id,
+            )
+        })
+        .collect();
+    if !stopped.is_empty() {
+        let list = stopped
+            .iter()
+            .map(|(label, _, _)| label.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        embed = embed.field(tr!("Stopped Partway"), list, false);
+
+        let options = stopped
+            .into_iter()
+            .take(25)
+            .map(|(label, remaining, message_id)| {
+                CreateSelectMenuOption::new(label, message_id.to_string()).description(
+                    remaining
+                        .lines()
+                        .next()
+                        .map(|first| tr!("From {title}", title = first))
+                        .unwrap_or_default(),
+                )
+            })
+            .collect();
+        menus.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new("resume_batch", CreateSelectMenuKind::String { options })
+                .placeholder(tr!("Resume a rip that stopped partway")),
+        ));
+    }
+
+    (embed, menus)
+}
@@ -0,0 +1,524 @@
+use std::time::Duration;
+
+use serenity::all::{
+    AutocompleteChoice, CommandInteraction, CommandOptionType, Context, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, EditInteractionResponse, Interaction, ResolvedOption,
+    ResolvedValue, UserId,
+};
+
+use crate::arr::{canonical_movie, canonical_series, movie_runtime};
+use crate::audit::AuditAction;
+use crate::discord::commands::command_helpers::{
+    audit, audit_outcome, makemkv, rip_parameters, take_rip_quota,
+};
+use crate::discord::commands::rip::{
+    first_episode, format_title_episode, run_movie_rip, run_show_rips, show_rips,
+    start_rip_message, RipOptions,
+};
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::error_embeds::error_embed;
+use crate::discord::components::messages::ephemeral;
+use crate::discord::errors::{DiscordError, Result};
+use crate::disk::DISK_MONITOR;
+use crate::history::HISTORY;
+use crate::makemkv::heuristics::{likely_episodes, likely_main_feature, rank_titles};
+use crate::makemkv::makemkv_helpers::{DISC_LABELS, SCANNED_TITLES};
+use crate::makemkv::naming::{path_safe, MAX_NAME_LENGTH};
+use crate::makemkv::{get_drives, get_title_info, Rip, RipType, Title};
+use crate::queue::Priority;
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// How long the drives are listed for to suggest them, Discord only waits 3 seconds for
+/// the suggestions
+const DRIVE_LIST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The most suggestions Discord shows for an option
+const MAX_SUGGESTIONS: usize = 25;
+
+pub fn register() -> CreateCommand {
+    debug!("Registered quickrip command");
+    CreateCommand::new("quickrip")
+        .description(tr!("Start a rip in one command, without the menus of /rip"))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "drive",
+                tr!("The drive the disc is in"),
+            )
+            .min_int_value(0)
+            .max_int_value(u8::MAX.into())
+            .set_autocomplete(true)
+            .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "type",
+                tr!("Whether the disc is a movie or a show"),
+            )
+            .add_string_choice(tr!("Movie"), "movie")
+            .add_string_choice(tr!("Show"), "show")
+            .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "title",
+                tr!("The title of the movie or show"),
+            )
+            .max_length(u16::try_from(MAX_NAME_LENGTH).unwrap_or(u16::MAX))
+            .set_autocomplete(true)
+            .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "season",
+                tr!("The season of a show, 0 for specials"),
+            )
+            .min_int_value(0)
+            .max_int_value(u8::MAX.into()),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "title_id",
+                tr!("The title to rip, as shown by /get_titles, picked for you if left out"),
+            )
+            .min_int_value(0)
+            .max_int_value(u16::MAX.into()),
+        )
+}
+
+/// What to rip, from the options of the command.
+///
+/// # Fields
+///
+/// * `drive_number` - The drive the disc is in.
+/// * `is_show` - Whether the disc is a show rather than a movie.
+/// * `title` - The title of the movie or show, made safe for file names.
+/// * `season` - The season of a show.
+/// * `title_id` - The title to rip, `None` to pick the main feature or the episodes.
+struct QuickRip {
+    drive_number: u8,
+    is_show: bool,
+    title: String,
+    season: Option<u8>,
+    title_id: Option<u16>,
+}
+
+/// Runs the `quickrip` command
+///
+/// The movie or show is ripped straight away with the details given, for those who
+/// already know them from `/get_titles`. Without a `title_id`, movies rip the likely main
+/// feature and shows rip every title that looks like an episode. Shows are numbered on
+/// from the last episode ripped, like `/rip` does.
+///
+/// The drive and title options are suggested as they are typed, from the drives last
+/// listed and the shows already ripped.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if an option is missing or out of range, the titles to rip
+/// can't be picked, the rip quota is used up, or the rip fails.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running quickrip command");
+
+    match interaction {
+        Interaction::Autocomplete(command) => suggest(ctx, command).await,
+        Interaction::Command(command) => quick_rip(ctx, command).await,
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+async fn quick_rip(ctx: &Context, command: &CommandInteraction) -> Result<()> {
+    let options = parse_options(&command.data.options())?;
+
+    // Starting a rip with a nearly full disk would only fail partway through
+    if DISK_MONITOR.lock().await.is_blocked() {
+        warn!("Refusing to start a quick rip, disk space is critically low");
+        return Err(DiscordError::InvalidInput(tr!(
+            "New rips are blocked until more space is freed."
+        )));
+    }
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(ephemeral())
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Quick Rip"))
+                            .description(tr!("Checking the disc, this may take a minute...")),
+                    ),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    // Anything going wrong before the rip starts is shown in place of the loading message
+    let planned = match plan(ctx, options, command.user.id).await {
+        Ok(planned) => planned,
+        Err(e) => return Err(show_failure(ctx, command, e).await),
+    };
+    if let Err(e) = take_rip_quota(command.user.id, command.member.as_deref()).await {
+        return Err(show_failure(ctx, command, e).await);
+    }
+
+    info!(
+        "{} quick ripped {} from drive {}",
+        command.user.name, planned.name, planned.drive_number
+    );
+    let message = command.get_response(&ctx.http).await.map_err(|e| {
+        error!("Failed to get response: {:?}", e);
+        DiscordError::EditResponseFailed(e.to_string())
+    })?;
+    let message = start_rip_message(ctx, &command.token, &message, &planned.name).await?;
+
+    let result = match planned.rips {
+        PlannedRips::Movie(rip) => {
+            run_movie_rip(ctx, &message, rip, Vec::new(), planned.options).await
+        }
+        PlannedRips::Show {
+            title,
+            season,
+            rips,
+        } => {
+            run_show_rips(
+                ctx,
+                &message,
+                &title,
+                planned.drive_number,
+                season,
+                planned.options,
+                rips,
+            )
+            .await
+        }
+    };
+
+    audit(
+        &command.user,
+        "quickrip",
+        AuditAction::Rip,
+        planned.parameters,
+        audit_outcome(&result),
+    )
+    .await;
+    result
+}
+
+/// A rip worked out from the options, ready to start.
+///
+/// # Fields
+///
+/// * `name` - The name of the movie or season, for the rip message and the logs.
+/// * `drive_number` - The drive the disc is in.
+/// * `parameters` - The rip as it is written to the audit log.
+/// * `options` - The priority of the rip and whether to eject the disc after it.
+/// * `rips` - The titles to rip.
+struct PlannedRip {
+    name: String,
+    drive_number: u8,
+    parameters: String,
+    options: RipOptions,
+    rips: PlannedRips,
+}
+
+enum PlannedRips {
+    Movie(Rip),
+    Show {
+        title: String,
+        season: u8,
+        rips: Vec<Rip>,
+    },
+}
+
+/// Works out what to rip from the options, scanning the disc when the titles are left to
+/// be picked
+async fn plan(ctx: &Context, options: QuickRip, owner: UserId) -> Result<PlannedRip> {
+    let QuickRip {
+        drive_number,
+        is_show,
+        title,
+        season,
+        title_id,
+    } = options;
+
+    let eject_when_done = makemkv(ctx).await?.eject_after_rip;
+    let options = RipOptions {
+        priority: Priority::Normal,
+        eject_when_done,
+        owner,
+    };
+
+    if is_show {
+        let season = season.ok_or_else(|| {
+            DiscordError::InvalidInput(tr!("Shows need a season, add the season option."))
+        })?;
+        // Uses Sonarr's name for the show, if it is configured
+        let title = canonical_series(title).await;
+
+        let title_ids = match title_id {
+            Some(title_id) => vec![check_title(drive_number, title_id).await?],
+            None => likely_episodes(&scan(drive_number).await?),
+        };
+        if title_ids.is_empty() {
+            return Err(DiscordError::InvalidInput(tr!(
+                "No titles on the disc look like episodes, pick one with the title_id option."
+            )));
+        }
+
+        let first_episode = first_episode(ctx, None, &title, season).await?;
+        let entries = (0u8..)
+            .zip(title_ids)
+            .map(|(index, title_id)| (title_id, first_episode.saturating_add(index), None))
+            .collect();
+        let rips = show_rips(&title, drive_number, season, entries, None);
+
+        let name = tr!("{title} Season {season}", title = title, season = season);
+        let selected_titles = rips
+            .iter()
+            .map(format_title_episode)
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        Ok(PlannedRip {
+            parameters: rip_parameters(
+                &name,
+                drive_number,
+                Some(&selected_titles),
+                eject_when_done,
+            ),
+            name,
+            drive_number,
+            options,
+            rips: PlannedRips::Show {
+                title,
+                season,
+                rips,
+            },
+        })
+    } else {
+        // Uses Radarr's name and year for the movie, if it is configured
+        let (title, year) = canonical_movie(title, None).await;
+
+        let title_id = if let Some(title_id) = title_id {
+            check_title(drive_number, title_id).await?
+        } else {
+            let (titles, runtime) = tokio::join!(scan(drive_number), movie_runtime(&title, year));
+            likely_main_feature(&rank_titles(&titles?, runtime)).ok_or_else(|| {
+                DiscordError::InvalidInput(tr!(
+                    "The main feature can't be told apart from the other titles, pick one with the title_id option."
+                ))
+            })?
+        };
+
+        let rip = Rip {
+            title: title.clone(),
+            drive_number,
+            rip_type: RipType::Movie,
+            title_id,
+            year,
+            chapters_per_episode: None,
+            library: None,
+            episode_title: None,
+            note: None,
+            extra: None,
+            base_view: false,
+        };
+
+        let name = match year {
+            Some(year) => format!("{title} ({year})"),
+            None => title,
+        };
+
+        Ok(PlannedRip {
+            parameters: rip_parameters(
+                &name,
+                drive_number,
+                Some(&format_title_episode(&rip)),
+                eject_when_done,
+            ),
+            name,
+            drive_number,
+            options,
+            rips: PlannedRips::Movie(rip),
+        })
+    }
+}
+
+/// Shows an error in place of the loading message, returning it marked as shown
+async fn show_failure(
+    ctx: &Context,
+    command: &CommandInteraction,
+    e: DiscordError,
+) -> DiscordError {
+    match command
+        .edit_response(
+            &ctx.http,
+            EditInteractionResponse::new().embed(error_embed(&e)),
+        )
+        .await
+    {
+        Ok(_) => DiscordError::reported(e),
+        Err(edit_error) => {
+            error!("Failed to edit response: {:?}", edit_error);
+            e
+        }
+    }
+}
+
+/// Reads the options of the command, making the title safe for file names
+fn parse_options(options: &[ResolvedOption]) -> Result<QuickRip> {
+    let mut drive_number = None;
+    let mut is_show = None;
+    let mut title = None;
+    let mut season = None;
+    let mut title_id = None;
+
+    for option in options {
+        match (option.name, &option.value) {
+            ("drive", ResolvedValue::Integer(value)) => drive_number = u8::try_from(*value).ok(),
+            ("type", ResolvedValue::String(value)) => is_show = Some(*value == "show"),
+            ("title", ResolvedValue::String(value)) => title = Some(path_safe(value.trim())),
+            ("season", ResolvedValue::Integer(value)) => season = u8::try_from(*value).ok(),
+            ("title_id", ResolvedValue::Integer(value)) => title_id = u16::try_from(*value).ok(),
+            _ => trace!("Ignoring unknown quickrip option: {}", option.name),
+        }
+    }
+
+    let (Some(drive_number), Some(is_show)) = (drive_number, is_show) else {
+        warn!("Quickrip called without a drive or type, ignoring");
+        return Err(DiscordError::InvalidComponentData);
+    };
+    let title = title.filter(|title| !title.is_empty()).ok_or_else(|| {
+        DiscordError::InvalidInput(tr!(
+            "The title can't be empty, or only characters that can't be used in names."
+        ))
+    })?;
+
+    Ok(QuickRip {
+        drive_number,
+        is_show,
+        title,
+        season,
+        title_id,
+    })
+}
+
+/// Scans the titles on the disc, the same as the title select step of `/rip`
+async fn scan(drive_number: u8) -> Result<Vec<Title>> {
+    let disc_info = get_title_info(drive_number).await.map_err(|e| {
+        error!("Failed to get title info: {:?}", e);
+        DiscordError::MakeMkvError(e)
+    })?;
+    Ok(disc_info.titles)
+}
+
+/// Checks a title given by hand is on the disc, when the disc was scanned since it went in
+async fn check_title(drive_number: u8, title_id: u16) -> Result<u16> {
+    let scanned = SCANNED_TITLES.lock().await;
+    match scanned.get(&drive_number) {
+        Some(disc) if !disc.titles.iter().any(|title| title.title_id == title_id) => {
+            Err(DiscordError::InvalidInput(tr!(
+                "Disc {disc} has no title {title}, see /get_titles for the titles on it.",
+                disc = drive_number,
+                title = title_id
+            )))
+        }
+        _ => Ok(title_id),
+    }
+}
+
+/// Suggests the drives, or the shows already ripped, for the option being typed
+async fn suggest(ctx: &Context, command: &CommandInteraction) -> Result<()> {
+    let Some(focused) = command.data.autocomplete() else {
+        debug!("Autocomplete without a focused option, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+    let typed = focused.value.trim().to_lowercase();
+
+    let choices: Vec<AutocompleteChoice> = match focused.name {
+        "drive" => drive_choices()
+            .await
+            .into_iter()
+            .filter(|(drive_number, _)| drive_number.to_string().starts_with(&typed))
+            .map(|(drive_number, label)| {
+                let name: String = tr!("Disc {disc}: {title}", disc = drive_number, title = label)
+                    .chars()
+                    .take(100)
+                    .collect();
+                AutocompleteChoice::new(name, i64::from(drive_number))
+            })
+            .collect(),
+        // Only shows are suggested, movies are rarely ripped twice
+        "title" => HISTORY
+            .lock()
+            .await
+            .show_titles()
+            .into_iter()
+            .filter(|title| title.to_lowercase().contains(&typed))
+            .map(|title| {
+                let title: String = title.chars().take(100).collect();
+                AutocompleteChoice::new(title.clone(), title)
+            })
+            .collect(),
+        name => {
+            trace!("No suggestions for quickrip option: {}", name);
+            Vec::new()
+        }
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Autocomplete(
+                CreateAutocompleteResponse::new()
+                    .set_choices(choices.into_iter().take(MAX_SUGGESTIONS).collect()),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send suggestions: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// The drives and the labels of their discs, from when the drives were last listed. They
+/// are listed again if they never were, as long as it is quick enough to suggest them.
+async fn drive_choices() -> Vec<(u8, String)> {
+    let listed = DISC_LABELS.lock().await.clone();
+    if !listed.is_empty() {
+        let mut drives: Vec<(u8, String)> = listed.into_iter().collect();
+        drives.sort_unstable();
+        return drives;
+    }
+
+    match tokio::time::timeout(DRIVE_LIST_TIMEOUT, get_drives()).await {
+        Ok(Ok(drives)) => drives
+            .into_iter()
+            .map(|drive| (drive.drive_number, drive.drive_media_title))
+            .collect(),
+        Ok(Err(e)) => {
+            warn!("Failed to list drives to suggest: {}", e);
+            Vec::new()
+        }
+        Err(_) => {
+            debug!("Listing the drives took too long to suggest them");
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use serenity::all::{
+    ComponentInteractionDataKind, Context, CreateActionRow, CreateCommand, CreateInputText,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateModal, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, InputTextStyle, Interaction,
+};
+
+use crate::discord::commands::command_helpers::get_modal_input;
+use crate::discord::commands::command_helpers::makemkv;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::{edit_message, ephemeral};
+use crate::discord::errors::{DiscordError, Result};
+use crate::history::HISTORY;
+use crate::library::LIBRARY;
+use crate::makemkv::naming::part_path;
+use crate::makemkv::{Rip, RipType};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered rename command");
+    CreateCommand::new("rename").description(tr!("Fix the name of a recently ripped title"))
+}
+
+/// Runs the rename command
+///
+/// The flow is:
+/// 1. `/rename` lists the most recent rips from the history in a select menu
+/// 2. Selecting a rip opens a modal prefilled with its current details
+/// 3. Submitting the modal moves the files on disk and updates the history
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the interaction data is invalid or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running rename command");
+
+    match interaction {
+        Interaction::Command(command) => {
+            trace!("Got request from command interaction");
+
+            // Discord only allows 25 options in a select menu
+            let options: Vec<CreateSelectMenuOption> = HISTORY
+                .lock()
+                .await
+                .recent(25)
+                .into_iter()
+                .map(|entry| {
+                    let description = entry
+                        .files
+                        .first()
+                        .and_then(|file| file.file_name())
+                        .map(|file| file.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    CreateSelectMenuOption::new(entry.label(), entry.id.to_string())
+                        .description(description)
+                })
+                .collect();
+
+            let response = if options.is_empty() {
+                CreateInteractionResponseMessage::new().embed(
+                    themed_embed()
+                        .title(tr!("Rename Rip"))
+                        .description(tr!("There are no rips in the history to rename.")),
+                )
+            } else {
+                CreateInteractionResponseMessage::new()
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Rename Rip"))
+                            .description(tr!("Please select the rip to rename.")),
+                    )
+                    .select_menu(CreateSelectMenu::new(
+                        "select_rip_to_rename",
+                        CreateSelectMenuKind::String { options },
+                    ))
+            };
+
+            command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(response.ephemeral(ephemeral())),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create response: {:?}", e);
+                    DiscordError::CommandInteractionResponseFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        Interaction::Component(component) => {
+            trace!("Got request from component interaction");
+
+            let ComponentInteractionDataKind::StringSelect { values } = &component.data.kind else {
+                warn!("Recieved invalid component data, ignoring");
+                return Err(DiscordError::InvalidComponentData);
+            };
+            let id: u64 = values[0].parse().map_err(|_| {
+                warn!("Failed to parse selected rip, ignoring");
+                DiscordError::InvalidComponentData
+            })?;
+
+            let entry = HISTORY.lock().await.get(id).cloned().ok_or_else(|| {
+                warn!("Selected rip {} is no longer in the history", id);
+                DiscordError::InvalidComponentData
+            })?;
+
+            // The rip id is carried through the modal the same way the disc number
+            // is in the rip command
+            let mut components = vec![
+                CreateActionRow::InputText(
+                    CreateInputText::new(InputTextStyle::Short, tr!("Rip Id"), "rip_id")
+                        .value(entry.id.to_string())
+                        .required(true),
+                ),
+                CreateActionRow::InputText(
+                    CreateInputText::new(InputTextStyle::Short, tr!("Title"), "title")
+                        .value(&entry.title)
+                        .required(true),
+                ),
+            ];
+
+            match entry.rip_type {
+                RipType::Movie => components.push(CreateActionRow::InputText(
+                    CreateInputText::new(InputTextStyle::Short, tr!("Year"), "year")
+                        .value(entry.year.map(|year| year.to_string()).unwrap_or_default())
+                        .required(false),
+                )),
+                RipType::Show {
+                    season, episode, ..
+                } => {
+                    components.push(CreateActionRow::InputText(
+                        CreateInputText::new(InputTextStyle::Short, tr!("Season"), "season")
+                            .value(season.to_string())
+                            .required(true),
+                    ));
+                    components.push(CreateActionRow::InputText(
+                        CreateInputText::new(InputTextStyle::Short, tr!("Episode"), "episode")
+                            .value(episode.to_string())
+                            .required(true),
+                    ));
+                }
+            }
+
+            component
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Modal(
+                        CreateModal::new(
+                            "get_rename_details",
+                            tr!("Please enter the correct details"),
+                        )
+                        .components(components),
+                    ),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create get_rename_details modal: {:?}", e);
+                    DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        Interaction::Modal(modal) => {
+            trace!("Got request from modal interaction");
+
+            let Some(message) = modal.message.clone() else {
+                trace!("Modal interaction has no message, ignoring");
+                return Err(DiscordError::InvalidInteractionCall);
+            };
+
+            modal.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            let id: u64 = get_modal_input(modal, "rip_id")
+                .and_then(|id| id.parse().ok())
+                .ok_or(DiscordError::InvalidComponentData)?;
+            let title =
+                get_modal_input(modal, "title").ok_or(DiscordError::InvalidComponentData)?;
+
+            let entry = HISTORY
+                .lock()
+                .await
+                .get(id)
+                .cloned()
+                .ok_or(DiscordError::InvalidComponentData)?;
+
+            // Rebuild the rip type with the corrected numbers, keeping anything the
+            // modal doesn't ask for
+            let (rip_type, year) = match entry.rip_type {
+                RipType::Movie => (
+                    RipType::Movie,
+                    get_modal_input(modal, "year").and_then(|year| year.parse().ok()),
+                ),
+                RipType::Show {
+                    absolute_episode, ..
+                } => {
+                    let season = get_modal_input(modal, "season")
+                        .and_then(|season| season.parse().ok())
+                        .ok_or(DiscordError::InvalidComponentData)?;
+                    let episode = get_modal_input(modal, "episode")
+                        .and_then(|episode| episode.parse().ok())
+                        .ok_or(DiscordError::InvalidComponentData)?;
+                    (
+                        RipType::Show {
+                            season,
+                            episode,
+                            absolute_episode,
+                        },
+                        None,
+                    )
+                }
+            };
+
+            // Build the new paths with the same naming template used when ripping, in
+            // the library the rip was saved to
+            let makemkv = makemkv(ctx).await?;
+            let library = entry
+                .files
+                .first()
+                .and_then(|file| makemkv.library_of(file))
+                .map(str::to_string);
+            let output_dir = makemkv.library_root(library.as_deref()).to_path_buf();
+            let renamed = Rip {
+                title: title.clone(),
+                drive_number: entry.drive_number,
+                rip_type,
+                title_id: entry.title_id,
+                year,
+                chapters_per_episode: None,
+                library,
+                episode_title: None,
+                note: entry.note.clone(),
+                extra: entry.extra.clone(),
+                base_view: false,
+            };
+            let base_path = makemkv.destination_path(&renamed);
+            let new_paths = if entry.files.len() == 1 {
+                vec![base_path]
+            } else {
+                (1..=entry.files.len())
+                    .map(|part| part_path(&base_path, part))
+                    .collect()
+            };
+
+            let result = HISTORY
+                .lock()
+                .await
+                .relocate(id, &title, year, rip_type, new_paths, &output_dir)
+                .map(|renamed| (renamed.label(), renamed.files.clone()));
+
+            // Keeps lookups such as the last episode of a season in step with the move
+            if let Ok((_, new_files)) = &result {
+                let mut library = LIBRARY.lock().await;
+                for file in &entry.files {
+                    library.remove(file);
+                }
+                for file in new_files {
+                    library.add(file);
+                }
+            }
+
+            let embed = match result {
+                Ok((label, _)) => {
+                    info!("Renamed {} to {}", entry.label(), label);
+                    themed_embed().title(tr!("Rip Renamed")).description(tr!(
+                        "Renamed **{old}** to **{new}**",
+                        old = entry.label(),
+                        new = label
+                    ))
+                }
+                Err(e) => {
+                    error!("Failed to rename rip {}: {}", id, e);
+                    themed_embed().title(tr!("Rename Failed")).description(tr!(
+                        "Failed to rename **{rip}**: {error}",
+                        rip = entry.label(),
+                        error = e
+                    ))
+                }
+            };
+
+            edit_message(
+                ctx,
+                &modal.token,
+                &message,
+                EditMessage::new().components(vec![]).embed(embed),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to edit message: {:?}", e);
+                DiscordError::EditMessageFailed(e.to_string())
+            })?;
+
+            Ok(())
+        }
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use serenity::all::{
+    Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage,
+    EditInteractionResponse, Interaction,
+};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::ephemeral;
+use crate::discord::errors::{DiscordError, Result};
+use crate::library::{LibraryIndex, LIBRARY};
+
+use crate::{debug, error, info, tr, trace};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered rescan command");
+    CreateCommand::new("rescan").description(tr!(
+        "Rebuild the library index from the output directory and libraries"
+    ))
+}
+
+/// Runs the `rescan` command
+///
+/// The library index is only updated for changes cord-ripper makes itself, this picks up
+/// files that were added, moved or removed by hand.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if Discord rejects the response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running rescan command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    // Scanning a large library can take longer than Discord waits for a response
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(ephemeral())
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Rescanning Library"))
+                            .description(tr!("This may take a while for large libraries...")),
+                    ),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    // The scan runs without holding the lock, so rips can carry on in the meantime
+    let roots = LIBRARY.lock().await.roots().to_vec();
+    let result = tokio::task::spawn_blocking(move || LibraryIndex::scan(&roots))
+        .await
+        .map_err(|e| DiscordError::Unexpected(e.to_string()))?;
+
+    let embed = match result {
+        Ok(index) => {
+            let count = index.len();
+            *LIBRARY.lock().await = index;
+            info!("Library rescanned, {} files indexed", count);
+            themed_embed()
+                .title(tr!("Library Rescanned"))
+                .description(tr!("{count} files indexed.", count = count))
+        }
+        Err(e) => {
+            error!("Failed to rescan library: {}", e);
+            themed_embed()
+                .title(tr!("Rescan Failed"))
+                .description(tr!("Failed to rescan the library: {error}", error = e))
+        }
+    };
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+        .await
+        .map_err(|e| {
+            error!("Failed to edit response: {:?}", e);
+            DiscordError::EditResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use serenity::all::{
+    CommandInteraction, CommandType, Context, CreateCommand, CreateInteractionResponse,
+    CreateInteractionResponseMessage, Interaction, ResolvedTarget,
+};
+
+use crate::audit::AuditAction;
+use crate::discord::commands::command_helpers::{audit, audit_outcome, rip_parameters};
+use crate::discord::commands::rip::{
+    failed_rips, format_title_episode, retry_rips, start_rip_message,
+};
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::ephemeral;
+use crate::discord::errors::{DiscordError, Result};
+use crate::disk::DISK_MONITOR;
+use crate::makemkv::RipType;
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// The name of the command in the message menu, which is also what it is routed by
+pub const RETRY_RIP_COMMAND: &str = "Retry this rip";
+
+pub fn register() -> CreateCommand {
+    debug!("Registered retry rip command");
+    CreateCommand::new(RETRY_RIP_COMMAND).kind(CommandType::Message)
+}
+
+/// Runs the `Retry this rip` message command
+///
+/// Used on the message of a failed movie rip, or the summary of a show rip with failed
+/// titles, the titles that failed are ripped again with the same details, without going
+/// through the steps of `/rip` again. Episodes go into the same slots they were meant to
+/// fill, in the same library. The rip is shown on a new message, like any other.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the message isn't one of the bot's failed rips, or the rip
+/// fails again.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running retry rip command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+    trace!("Got request from message command interaction");
+
+    let Some(ResolvedTarget::Message(target)) = command.data.target() else {
+        warn!("Retry this rip was used without a message, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    // Starting a rip with a nearly full disk would only fail partway through
+    if DISK_MONITOR.lock().await.is_blocked() {
+        warn!("Refusing to retry a rip, disk space is critically low");
+        return Err(DiscordError::InvalidInput(tr!(
+            "New rips are blocked until more space is freed."
+        )));
+    }
+
+    // Only the bot's own messages are read back, anyone could post a lookalike embed
+    let rips = target
+        .embeds
+        .first()
+        .filter(|_| target.author.id == ctx.cache.current_user().id)
+        .and_then(|embed| failed_rips(embed).ok())
+        .ok_or_else(|| {
+            DiscordError::InvalidInput(tr!(
+                "Retry this rip only works on a failed movie rip, or the summary of a show rip with failed titles."
+            ))
+        })?;
+
+    let first = &rips[0];
+    let name = match first.rip_type {
+        RipType::Movie => first.title.clone(),
+        RipType::Show { season, .. } => {
+            tr!(
+                "{title} Season {season}",
+                title = first.title,
+                season = season
+            )
+        }
+    };
+    let selected_titles = rips
+        .iter()
+        .map(format_title_episode)
+        .collect::<Vec<String>>()
+        .join("\n");
+    let parameters = rip_parameters(&name, first.drive_number, Some(&selected_titles), false);
+    info!(
+        "{} is retrying {} on drive {}",
+        command.user.name, name, first.drive_number
+    );
+
+    let message = respond(ctx, command, &name).await?;
+    let message = start_rip_message(ctx, &command.token, &message, &name).await?;
+    let result = retry_rips(ctx, &message, rips, command.user.id).await;
+
+    audit(
+        &command.user,
+        "retry_rip",
+        AuditAction::Rip,
+        parameters,
+        audit_outcome(&result),
+    )
+    .await;
+    result
+}
+
+/// Responds to the command with the message the retry is shown on
+async fn respond(
+    ctx: &Context,
+    command: &CommandInteraction,
+    name: &str,
+) -> Result<serenity::all::Message> {
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(ephemeral())
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Retrying Rip"))
+                            .description(tr!("Retrying {name}...", name = name)),
+                    ),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    command.get_response(&ctx.http).await.map_err(|e| {
+        error!("Failed to get response: {:?}", e);
+        DiscordError::EditResponseFailed(e.to_string())
+    })
+}
@@ -1,27 +1,178 @@
-use std::vec;
+use std::{collections::HashMap, sync::Arc, time::Duration, vec};
 
 use serenity::all::{
-    ActionRowComponent, ComponentInteractionDataKind, Context, CreateActionRow, CreateButton,
-    CreateCommand, CreateInputText, CreateInteractionResponse, CreateInteractionResponseMessage,
-    CreateMessage, CreateModal, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
-    EditInteractionResponse, EditMessage, InputTextStyle, Interaction, Timestamp,
+    ActionRowComponent, ComponentInteractionDataKind, Context, CreateActionRow, CreateAttachment,
+    CreateButton, CreateCommand, CreateInputText, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, CreateModal, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse, EditMessage, Embed,
+    InputTextStyle, Interaction, Member, Message, ModalInteraction, User, UserId,
 };
 use serenity::builder::CreateEmbed;
 
-use crate::makemkv::{errors::MakeMkvError, get_drives, get_title_info, Rip, RipType};
+use crate::arr::{canonical_movie, canonical_series, movie_runtime, next_missing_episode, ARR};
+use crate::audit::AuditAction;
+use crate::collection::COLLECTION;
+use crate::discord::bot::controls;
+use crate::discord::bot::edits::MessageEditor;
+use crate::discord::commands::command_helpers::{
+    audit, audit_cancel, audit_outcome, get_modal_input, is_admin, makemkv, rip_parameters,
+    take_rip_quota,
+};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::{progress_bar, themed_embed};
+use crate::discord::components::error_embeds::error_embed;
+use crate::discord::components::messages::{edit_message, ephemeral, progress_message};
+use crate::disk::{free_space, DISK_MONITOR};
+use crate::history::{history_core::now, HISTORY};
+use crate::i18n::untranslate;
+use crate::library::LIBRARY;
+use crate::makemkv::heuristics::{
+    detect_obfuscation, likely_main_feature, rank_titles, Obfuscation,
+};
+use crate::makemkv::{
+    check_capabilities,
+    drive_map::drive_location,
+    eject_drive,
+    errors::MakeMkvError,
+    eta::{estimate, phase, RipEta, RipPhase},
+    get_drives, get_title_info,
+    labels::parse_disc_label,
+    logs,
+    makemkv_core::DEFAULT_LIBRARY,
+    makemkv_helpers::{parse_length, DISC_LABELS, SCANNED_TITLES},
+    naming::part_path,
+    parse_size, HashRecovery, MakeMkv, Rip, RipOutcome, RipType, SizeWarning, Title,
+};
+use crate::probe::{probe_files, MediaStats};
+use crate::queue::batches::{self, batches, Batch};
+use crate::queue::messages::{follow, JobMessage};
+use crate::queue::{worker, JobKind, JobTicket, Priority, QUEUE};
+use crate::settings::{RipPreset, SETTINGS};
+use crate::upload::{upload_core::Uploader, UploadProgress, UPLOADER};
 
 use crate::discord::errors::{DiscordError, Result};
 
-use crate::{debug, error, info, trace, warn};
+use crate::{debug, error, info, tr, trace, warn};
 
 pub fn register() -> CreateCommand {
     debug!("Registered rip command");
-    CreateCommand::new("rip").description("Rip a disc")
+    CreateCommand::new("rip").description(tr!("Rip a disc"))
 }
 
-// Wow this is gonna be the biggest roller coater of a function yet!
+/// Steps of setting up a rip whose failures are shown on the rip message, along with the
+/// component that runs the step again, if it can be. Later steps are left out, so a failure
+/// doesn't replace the summary being confirmed, and rips show their own failures.
+const SETUP_STEPS: [(&str, Option<&str>); 5] = [
+    ("select_disc_to_rip", None),
+    ("movie_rip", None),
+    ("show_rip", None),
+    ("get_title_of_movie_rip", Some("movie_rip")),
+    ("get_title_of_show_rip", Some("show_rip")),
+];
+
+/// Inputs of the title modals and the fields they are shown as, so a retried modal is
+/// filled in with what was entered
+const MODAL_FIELDS: [(&str, &str); 7] = [
+    ("disc_number", "Disc Number"),
+    ("title_of_movie", "Title"),
+    ("year_of_movie", "Year"),
+    ("title_of_show", "Title"),
+    ("season", "Season"),
+    ("absolute_episode", "Absolute Start"),
+    ("priority", "Priority"),
+];
+
 /// Runs the rip command
+///
+/// A step of setting up the rip that fails shows why on the rip message, with a "Retry"
+/// button when the step can be run again, rather than leaving the message loading.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the interaction data is invalid, `MakeMKV` fails, or Discord
+/// rejects a response.
 pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    let Err(e) = handle(ctx, interaction).await else {
+        return Ok(());
+    };
+
+    if !e.is_reported() && show_setup_failure(ctx, interaction, &e).await {
+        return Err(DiscordError::reported(e));
+    }
+    Err(e)
+}
+
+/// Shows why a step of setting up a rip failed on its message, see `SETUP_STEPS`. Returns
+/// false if the step isn't one of them, or the message couldn't be edited.
+async fn show_setup_failure(
+    ctx: &Context,
+    interaction: &Interaction,
+    error: &DiscordError,
+) -> bool {
+    let (step, message, token) = match interaction {
+        Interaction::Component(component) => (
+            custom_ids::name(&component.data.custom_id),
+            Some(&*component.message),
+            &component.token,
+        ),
+        Interaction::Modal(modal) => (
+            custom_ids::name(&modal.data.custom_id),
+            modal.message.as_deref(),
+            &modal.token,
+        ),
+        _ => return false,
+    };
+    let Some(message) = message else {
+        return false;
+    };
+    let Some((_, retry)) = SETUP_STEPS
+        .iter()
+        .find(|(setup_step, _)| *setup_step == step)
+    else {
+        return false;
+    };
+
+    // The fields are kept for the retried step to read back, a modal's from what was entered
+    let fields: Vec<(String, String, bool)> = match interaction {
+        Interaction::Modal(modal) => MODAL_FIELDS
+            .iter()
+            .filter_map(|(input, name)| {
+                get_modal_input(modal, input).map(|value| (tr!(name), value, true))
+            })
+            .collect(),
+        _ => message
+            .embeds
+            .first()
+            .map(|embed| {
+                embed
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.value.clone(), field.inline))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    let mut edit = EditMessage::new()
+        .components(vec![])
+        .embed(error_embed(error).fields(fields));
+    if let Some(retry) = retry {
+        edit = edit.button(
+            CreateButton::new(*retry)
+                .label(tr!("Retry"))
+                .style(serenity::all::ButtonStyle::Primary),
+        );
+    }
+
+    if let Err(e) = edit_message(ctx, token, message, edit).await {
+        error!("Failed to send rip failed message: {:?}", e);
+        return false;
+    }
+    true
+}
+
+// Wow this is gonna be the biggest roller coater of a function yet!
+async fn handle(ctx: &Context, interaction: &Interaction) -> Result<()> {
     debug!("Rip command was called");
 
     // Match the interaction type to it's associated sub function based on
@@ -31,18 +182,44 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
         Interaction::Command(command) => {
             trace!("Got request from command interaction");
 
+            // Starting a rip with a nearly full disk would only fail partway through
+            if DISK_MONITOR.lock().await.is_blocked() {
+                warn!("Refusing to start a rip, disk space is critically low");
+                command
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .ephemeral(ephemeral())
+                                .embed(
+                                    themed_embed()
+                                        .title(tr!("Disk Space Critical"))
+                                        .description(tr!(
+                                            "New rips are blocked until more space is freed."
+                                        )),
+                                ),
+                        ),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to create response: {:?}", e);
+                        DiscordError::CommandInteractionResponseFailed(e.to_string())
+                    })?;
+                return Ok(());
+            }
+
             // Satisfy discord interaction with a temperary loading message
             command
                 .create_response(
                     &ctx.http,
                     CreateInteractionResponse::Message(
                         CreateInteractionResponseMessage::new()
+                            .ephemeral(ephemeral())
                             .components(vec![])
                             .embed(
-                                CreateEmbed::new()
-                                    .title("Loading Discs")
-                                    .description("This may take a few seconds...")
-                                    .color(0xfe0000),
+                                themed_embed()
+                                    .title(tr!("Loading Discs"))
+                                    .description(tr!("This may take a few seconds...")),
                             ),
                     ),
                 )
@@ -62,12 +239,9 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                         .edit_response(
                             &ctx.http,
                             EditInteractionResponse::new().embed(
-                                CreateEmbed::new()
-                                    .title("Error")
-                                    .description(
-                                        "Failed to retrieve drives. Please try again later.",
-                                    )
-                                    .color(0xfe0000),
+                                themed_embed().title(tr!("Error")).description(tr!(
+                                    "Failed to retrieve drives. Please try again later."
+                                )),
                             ),
                         )
                         .await
@@ -75,44 +249,62 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                             error!("Failed to edit response: {:?}", e);
                             DiscordError::EditResponseFailed(e.to_string())
                         })?;
-                    return Err(DiscordError::MakeMkvError(e));
+                    return Err(DiscordError::reported(e));
                 }
             };
 
             // Use a HashSet to track unique values and ensure no duplicates
             let mut seen_values = std::collections::HashSet::new();
+            let collection = COLLECTION.lock().await;
             // Create a vector of select menu options for each drive
             let options: Vec<CreateSelectMenuOption> = drives
                 .iter()
                 .filter_map(|drive| {
                     let value = format!("disc_{}", drive.drive_number);
                     if seen_values.insert(value.clone()) {
-                        Some(CreateSelectMenuOption::new(
-                            format!("Disc {}: {}", drive.drive_number, drive.drive_media_title),
+                        let option = CreateSelectMenuOption::new(
+                            tr!(
+                                "Disc {disc}: {title}",
+                                disc = drive.drive_number,
+                                title = drive.drive_media_title
+                            ),
                             value,
-                        ))
+                        );
+                        // Discs someone has been waiting on are pointed out
+                        Some(match collection.find_label(&drive.drive_media_title) {
+                            Some(entry) => option.description(tr!(
+                                "On the collection list as {title}",
+                                title = entry.title
+                            )),
+                            None => option,
+                        })
                     } else {
                         None
                     }
                 })
                 .collect();
+            drop(collection);
 
             // Create a select menu with the options
             // When the disc is selected, it will call the select_disc_to_rip component
             // interaction
+            let mut components = vec![CreateActionRow::SelectMenu(CreateSelectMenu::new(
+                "select_disc_to_rip",
+                CreateSelectMenuKind::String { options },
+            ))];
+            // Presets are picked before the disc, so they can set up the steps after it
+            if let Some(menu) = preset_menu().await {
+                components.push(CreateActionRow::SelectMenu(menu));
+            }
             command
                 .edit_response(
                     &ctx.http,
                     EditInteractionResponse::new()
-                        .components(vec![CreateActionRow::SelectMenu(CreateSelectMenu::new(
-                            "select_disc_to_rip",
-                            CreateSelectMenuKind::String { options },
-                        ))])
+                        .components(components)
                         .add_embed(
-                            CreateEmbed::new()
-                                .title("Select Disc")
-                                .description("Please select a disc to run rip on.")
-                                .color(0xfe0000),
+                            themed_embed()
+                                .title(tr!("Select Disc"))
+                                .description(tr!("Please select a disc to run rip on.")),
                         ),
                 )
                 .await
@@ -128,19 +320,92 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
             trace!("Got request from component interaction");
 
             // Satify rust borrow checker and make it easier to call
-            let mut message = component.message.clone();
+            let message = component.message.clone();
 
             // We check what type of component interaction it is by its unique id
-            match component.data.custom_id.as_str() {
+            match custom_ids::name(&component.data.custom_id) {
+                // This would be recieved when a preset is picked before the disc
+                "select_rip_preset" => {
+                    trace!("Got select_rip_preset component interaction");
+
+                    let value = match &component.data.kind {
+                        ComponentInteractionDataKind::StringSelect { values } => {
+                            values.first().cloned()
+                        }
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        warn!("Recieved invalid component data, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    // Picked by its place in the list, since names can be longer than
+                    // Discord allows values to be
+                    let preset = match value.strip_prefix("preset_") {
+                        Some(index) => {
+                            let index: usize = index.parse().map_err(|_| {
+                                warn!("Failed to parse preset from menu, ignoring");
+                                DiscordError::InvalidComponentData
+                            })?;
+                            let presets = &SETTINGS.lock().await.presets;
+                            Some(presets.get(index).cloned().ok_or_else(|| {
+                                warn!("Preset {} no longer exists, ignoring", index);
+                                DiscordError::InvalidComponentData
+                            })?)
+                        }
+                        None => None,
+                    };
+
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let current = message.embeds.first();
+                    let preset_field = tr!("Preset");
+                    let fields = current
+                        .iter()
+                        .flat_map(|embed| embed.fields.iter())
+                        .filter(|field| field.name != preset_field)
+                        .map(|field| (field.name.clone(), field.value.clone(), field.inline))
+                        .chain(
+                            preset
+                                .as_ref()
+                                .map(|preset| (preset_field.clone(), preset.name.clone(), false)),
+                        );
+                    let embed = themed_embed()
+                        .title(tr!("Select Disc"))
+                        .description(tr!("Please select a disc to run rip on."))
+                        .fields(fields);
+
+                    // The menus are left as they are, only the embed keeps the preset
+                    edit_message(
+                        ctx,
+                        &component.token,
+                        &message,
+                        EditMessage::new().embed(embed),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to edit message: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
+
+                    Ok(())
+                }
                 // This would be recieved by the initial interaction from the command
                 "select_disc_to_rip" => {
                     trace!("Got select_disc_to_rip component intertaction");
 
                     // Get the drive number from the component data
                     let drive_number: u8 = match &component.data.kind {
-                        ComponentInteractionDataKind::StringSelect { values } => {
-                            values[0].replace("disc_", "").parse().unwrap()
-                        }
+                        ComponentInteractionDataKind::StringSelect { values } => values
+                            .first()
+                            .and_then(|value| value.replace("disc_", "").parse().ok())
+                            .ok_or_else(|| {
+                                warn!("Failed to parse disc number from menu, ignoring");
+                                DiscordError::InvalidComponentData
+                            })?,
                         _ => {
                             warn!("Recieved invalid component data, ignoring");
                             return Ok(());
@@ -156,34 +421,19 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                     // Creates and embed to select which type of rip will be running
                     // The user will select either a movie or show rip
                     // This will split off into their respecive component interaction ids
-                    message
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new()
-                                .embed(
-                                    CreateEmbed::new()
-                                        .title("Select a rip type")
-                                        .description("Please select a rip type to start the rip.")
-                                        .color(0xfe0000)
-                                        .field("Disc Number", format!("{drive_number}"), false),
-                                )
-                                .button(
-                                    // This will call the movie_rip component interaction
-                                    // Prompting the user to input a title
-                                    // Will attempt to auto grab from the disc in the future
-                                    CreateButton::new("movie_rip")
-                                        .label("Rip Movie")
-                                        .style(serenity::all::ButtonStyle::Primary),
-                                )
-                                .button(
-                                    // This will call the show_rip component interaction
-                                    // Prompting the user to input a title and season
-                                    // Will attempt to auto grab from the disc in the future
-                                    CreateButton::new("show_rip")
-                                        .label("Rip Show")
-                                        .style(serenity::all::ButtonStyle::Primary),
-                                ),
-                        )
+                    let mut embed = themed_embed()
+                        .title(tr!("Select a rip type"))
+                        .description(tr!("Please select a rip type to start the rip."))
+                        .field(tr!("Disc Number"), format!("{drive_number}"), false);
+
+                    // Kept on the message for the show modal to be filled in from
+                    let label = DISC_LABELS.lock().await.get(&drive_number).cloned();
+                    if let Some(label) = label.filter(|label| !label.is_empty()) {
+                        embed = embed.field(tr!("Disc Label"), label, false);
+                    }
+                    embed = embed.fields(preset_fields(message.embeds.first()).await);
+
+                    edit_message(ctx, &component.token, &message, rip_type_message(embed))
                         .await
                         .map_err(|e| {
                             error!("Failed to edit message: {:?}", e);
@@ -196,49 +446,19 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                 "movie_rip" => {
                     trace!("Got movie_rip component interaction");
 
-                    // Grabs the disc number from the message embed and parses it
-                    // This logic will hopefully be moved to its own function in the future
-                    // Taking slices of the messege embed should be safe due to its constant
-                    // positioning provided by the previous component interaction
-                    let drive_number: u8 =
-                        message.embeds[0].fields[0].value.parse().map_err(|_| {
-                            warn!("Failed to parse disc number from message, ignoring");
-                            DiscordError::Unexpected("Failed to parse disc number".to_string())
-                        })?;
+                    // Grabs the disc number from the message embed and parses it, by name
+                    // since a failed step's message is retried from here too
+                    let embed = message.embeds.first();
+                    let drive_number = embed_drive_number(embed)?;
 
                     // Creates the modal for the user to input the title of the movie
+                    // Once a title is input and the modal is submmited it will call the
+                    // get_title_of_movie_rip modal interaction
+                    // This will then lead to prompting the user to select a title to rip
                     component
                         .create_response(
                             &ctx.http,
-                            CreateInteractionResponse::Modal(
-                                // Once a title is input and the modal is submmited
-                                // it will call the get_title_of_movie_rip modal interaction
-                                // This will then lead to prompting the user to select
-                                // a title to rip
-                                CreateModal::new(
-                                    "get_title_of_movie_rip",
-                                    "Please enter the title of the movie",
-                                )
-                                .components(vec![
-                                    CreateActionRow::InputText(
-                                        CreateInputText::new(
-                                            InputTextStyle::Short,
-                                            "Disc Number",
-                                            "disc_number",
-                                        )
-                                        .value(drive_number.to_string())
-                                        .required(true),
-                                    ),
-                                    CreateActionRow::InputText(
-                                        CreateInputText::new(
-                                            InputTextStyle::Short,
-                                            "Movie Title",
-                                            "title_of_movie",
-                                        )
-                                        .required(true),
-                                    ),
-                                ]),
-                            ),
+                            CreateInteractionResponse::Modal(movie_modal(drive_number, embed)),
                         )
                         .await
                         .map_err(|e| {
@@ -253,53 +473,17 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                     trace!("Got show_rip component interaction");
 
                     // Repeated code I had talked about in the rip_movie component
-                    let drive_number: u8 =
-                        message.embeds[0].fields[0].value.parse().map_err(|_| {
-                            warn!("Failed to parse disc number from message, ignoring");
-                            DiscordError::Unexpected("Failed to parse disc number".to_string())
-                        })?;
+                    let embed = message.embeds.first();
+                    let drive_number = embed_drive_number(embed)?;
 
                     // Creates the modal for the user to input the title and season of the show
+                    // Once title and season are input and the modal is submmited it will
+                    // call the get_title_of_show_rip modal interaction
+                    // This will then lead to prompting the user to select titles to rip
                     component
                         .create_response(
                             &ctx.http,
-                            CreateInteractionResponse::Modal(
-                                // Once title and season are input and the modal is submmited
-                                // it will call the get_title_of_show_rip modal interaction
-                                // This will then lead to prompting the user to select
-                                // titles to rip
-                                CreateModal::new(
-                                    "get_title_of_show_rip",
-                                    "Please enter the title & season",
-                                )
-                                .components(vec![
-                                    CreateActionRow::InputText(
-                                        CreateInputText::new(
-                                            InputTextStyle::Short,
-                                            "Disc Number",
-                                            "disc_number",
-                                        )
-                                        .value(drive_number.to_string())
-                                        .required(true),
-                                    ),
-                                    CreateActionRow::InputText(
-                                        CreateInputText::new(
-                                            InputTextStyle::Short,
-                                            "Show Title",
-                                            "title_of_show",
-                                        )
-                                        .required(true),
-                                    ),
-                                    CreateActionRow::InputText(
-                                        CreateInputText::new(
-                                            InputTextStyle::Short,
-                                            "Season",
-                                            "season",
-                                        )
-                                        .required(true),
-                                    ),
-                                ]),
-                            ),
+                            CreateInteractionResponse::Modal(show_modal(drive_number, embed)),
                         )
                         .await
                         .map_err(|e| {
@@ -320,28 +504,11 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                         DiscordError::DeferFailed(e.to_string())
                     })?;
 
-                    // The next 3 statements are the same as the previous component
-                    // interactions, but for title_name and season as well
-                    // This should be safe due to the constant positioning of the
-                    // message embed fields
-                    // These should be moved to their own function in the future
-
-                    let drive_number: u8 =
-                        message.embeds[0].fields[1].value.parse().map_err(|_| {
-                            warn!("Failed to parse disc number from message, ignoring");
-                            DiscordError::Unexpected("Failed to parse disc number".to_string())
-                        })?;
-
-                    let title_name: String =
-                        message.embeds[0].fields[0].value.parse().map_err(|_| {
-                            warn!("Failed to parse title from message, ignoring");
-                            DiscordError::Unexpected("Failed to parse title".to_string())
-                        })?;
-
-                    let season: u8 = message.embeds[0].fields[2].value.parse().map_err(|_| {
-                        warn!("Failed to parse season from message, ignoring");
-                        DiscordError::Unexpected("Failed to parse season".to_string())
-                    })?;
+                    // The details entered in the modal are read back from the embed
+                    let embed = message.embeds.first();
+                    let drive_number = embed_drive_number(embed)?;
+                    let title_name = embed_title(embed)?;
+                    let season = embed_season(embed)?;
 
                     // Get the selected titles from the component data
                     // This will be a vector of u8s, which are the title ids
@@ -365,10 +532,17 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                         }
                     };
 
-                    // Gets the last episode in the directory for the show,
-                    // this will be used to determine the episode number for the rip
-                    let last_episode =
-                        crate::makemkv::get_last_episode_in_dir(&title_name, season).await?;
+                    // Anime style libraries number episodes across seasons, this is
+                    // only present when a starting absolute episode was given
+                    let absolute_start: Option<u16> = message.embeds[0]
+                        .fields
+                        .iter()
+                        .find(|field| field.name == tr!("Absolute Start"))
+                        .and_then(|field| field.value.parse().ok());
+
+                    // Numbered for the output directory, the confirmation numbers them
+                    // again if they are saved to another library
+                    let first_episode = first_episode(ctx, None, &title_name, season).await?;
 
                     // Iteractes over the selected titles and creates a rip for each one
                     // This will be a vector of rips, which will be used to execute the
@@ -381,464 +555,582 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
                             drive_number,
                             rip_type: RipType::Show {
                                 season,
-                                episode: last_episode + (index as u8) + 1,
+                                episode: first_episode + (index as u8),
+                                absolute_episode: absolute_start.map(|start| start + index as u16),
                             },
                             title_id: title_id.into(),
+                            year: None,
+                            chapters_per_episode: None,
+                            library: None,
+                            episode_title: None,
+                            note: None,
+                            extra: None,
+                            base_view: false,
                         })
                         .collect();
 
                     trace!("Created rips: {:?}", rips);
 
-                    let now = std::time::Instant::now();
-
-                    let num_rips = &rips.len();
-                    trace!("Number of rips: {:?}", num_rips);
-
-                    // Satifies rust lifetime issues
-                    let mut was_cancelled = false;
-
-                    // Run the rips in sequence, updating the message with the current rip
-                    // and allowing the user to cancel the rip
-                    // This will be a loop that will run until all rips are complete
-                    // or the user cancels the rip
-                    for (index, rip) in rips.iter().enumerate() {
-                        // This should only fail if the rip details are invalid and also
-                        // passed previous validation
-                        let episode = if let Some(episode) = rip.episode() {
-                            format!("Episode {}", episode)
-                        } else {
-                            warn!("No episode found for rip; very strange... ignoring");
-                            continue;
-                        };
-
-                        // An async handle to a 'Collector' that will be used to
-                        // collect a cancel request from the user
-                        // This will be used to cancel the rip if the user requests it
-                        // This will be a future that will be awaited later
-                        let interaction_component = message
-                            .await_component_interaction(&ctx.shard)
-                            .custom_ids(vec!["cancel_rip".to_string()]);
-
-                        // Edit the message to show the current rip details
-                        message
-                            .clone()
-                            .edit(
-                                &ctx.http,
-                                EditMessage::new()
-                                    .components(vec![])
-                                    .embed(
-                                        CreateEmbed::new()
-                                            .title("Rip Show")
-                                            .timestamp(Timestamp::now())
-                                            .description(format!(
-                                                "Ripping {}, {}... \n(Rip {}/{})",
-                                                rip.title,
-                                                episode,
-                                                index + 1,
-                                                rips.len()
-                                            ))
-                                            .field("Title", &rip.title, true)
-                                            .field("Disc Number", drive_number.to_string(), true)
-                                            .field("Season", season.to_string(), true)
-                                            .color(0xfe0000),
-                                    )
-                                    .button(
-                                        // Add a cancel button to the message
-                                        CreateButton::new("cancel_rip")
-                                            .label("Cancel")
-                                            .style(serenity::all::ButtonStyle::Danger),
-                                    ),
-                            )
-                            .await
-                            .map_err(|e| {
-                                error!("Failed to send rip in progress message: {:?}", e);
-                                DiscordError::EditMessageFailed(e.to_string())
-                            })?;
+                    // Nothing is ripped until the user confirms the details
+                    confirm_rips(ctx, &component.token, &message, &rips).await
+                }
+                // This will be called when the user clicks the retry button on a
+                // summary message where some of the titles failed to rip
+                "retry_failed_rips" => {
+                    trace!("Got retry_failed_rips component interaction");
 
-                        // The 'magic sauce' to the interaction collector
-                        // tokio::select! will wait for either the rip to complete
-                        // or the user to cancel the rip by waiting for either to
-                        // reslove first
-                        // The other statement will be cancelled
-                        // sets the 'was_cancelled' variable to true if the user cancels
-                        // the rip
-                        // Error handling was not fixed here; waiting to figure
-                        // out how to handle sending the error from within the
-                        // non async function
-                        was_cancelled = tokio::select! {
-                            // Starts the rip and waits for it to complete
-                            rip_result = rip.execute() => {
-                                if let Err(e) = rip_result {
-                                    error!("Failed to execute rip: {:?}", e);
-                                    if let Err(e) = message
-                                        .clone()
-                                        .edit(
-                                            &ctx.http,
-                                            EditMessage::new().components(vec![])
-                                            .embed(
-                                                CreateEmbed::new()
-                                                    .title("Rip Failed")
-                                                    .timestamp(Timestamp::now())
-                                                    .description("This rip failed! Please try again.")
-                                                    .field("Title", &rip.title, true)
-                                                    .field("Disc Number", drive_number.to_string(), true)
-                                                    .field("Season", season.to_string(), true)
-                                                    .color(0xfe0000),
-                                            )
-                                        )
-                                        .await
-                                    {
-                                        error!("Failed to send rip failed message: {:?}", e);
-                                        return Err(DiscordError::EditMessageFailed(e.to_string()));
-                                    }
-                                }
-                                false
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
 
-                            }
-                            // Calls on the 'next()' method to asyncronously wait for
-                            // the user to cancel the rip
-                            Some(interaction) = interaction_component.next() => {
-                                debug!("Recieved canel request");
-
-                                // Defer the interaction to satify discord
-                                interaction.defer(&ctx.http).await?;
-                                rip.cancel().await?;
-
-                                // Edit the message to show that the rip was cancelled
-                                message
-                                    .clone()
-                                    .edit(
-                                        &ctx.http,
-                                        EditMessage::new().components(vec![])
-                                        .embed(
-                                            CreateEmbed::new()
-                                                .title("Rip Cancelled")
-                                                .timestamp(Timestamp::now())
-                                                .description("Rip cancelled!")
-                                                .field("Title", &rip.title, true)
-                                                .field("Disc Number", drive_number.to_string(), true)
-                                                .field("Season", season.to_string(), true)
-                                                .color(0xfe0000)
-                                                .timestamp(Timestamp::now())
-                                        )
-                                    )
-                                    .await
-                                    .map_err(|e| {
-                                        error!("Failed to send rip cancelled message: {:?}", e);
-                                        DiscordError::EditMessageFailed(e.to_string())
-                                    })?;
-                                info!("Rip cancelled");
-                                true
-                            }
-                        };
+                    // The summary embed holds everything needed to rebuild the rips
+                    let embed = message.embeds.first().ok_or_else(|| {
+                        warn!("Retry message has no embed, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
 
-                        // Breaks out of rip loop if the user cancels the rip
-                        if was_cancelled {
-                            break;
-                        }
-                    }
+                    let rips = failed_rips(embed)?;
+                    trace!("Created retry rips: {:?}", rips);
 
-                    // If the rip was cancelled, do not send the summary message
-                    if was_cancelled {
-                        return Err(DiscordError::TaskCancelled);
-                    }
+                    retry_rips(ctx, &message, rips, component.user.id).await
+                }
+                // This will be called when the user clicks the re-rip button on a summary
+                // listing rips that came out suspiciously small
+                "rerip_suspicious" => {
+                    trace!("Got rerip_suspicious component interaction");
 
-                    // Format the episode range for the summary message
-                    let episode_range = if num_rips > &1 {
-                        format!("{}-{}", last_episode + 1, last_episode + *num_rips as u8)
-                    } else {
-                        format!("{}", last_episode + 1)
-                    };
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
 
-                    let rip_time = now.elapsed().as_secs_f64() / 60.00;
+                    let embed = message.embeds.first().ok_or_else(|| {
+                        warn!("Re-rip message has no embed, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
 
-                    // Edit the message to show that the rip was completed
-                    message
-                        .clone()
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new().components(vec![]).embed(
-                                CreateEmbed::new()
-                                    .title(format!("Ripped {}", title_name))
-                                    .description("Rips completed!")
-                                    .color(0xfe0000)
-                                    .timestamp(Timestamp::now()),
-                            ),
-                        )
-                        .await
-                        .map_err(|e| {
-                            error!("Failed to send rip completed message: {:?}", e);
-                            DiscordError::EditMessageFailed(e.to_string())
-                        })?;
+                    let rips = suspicious_rips(embed)?;
+                    trace!("Created re-rips: {:?}", rips);
 
-                    // Send a summary message to the channel with the rip details
-                    // This will send a push notification to the user
-                    message
-                        .channel_id
-                        .send_message(
-                            &ctx.http,
-                            CreateMessage::new()
-                                .embed(
-                                    CreateEmbed::new()
-                                        .title("Rip Summary")
-                                        .description(format!(
-                                            "Finished in: {} minutes and {:.0} seconds",
-                                            rip_time.floor() as u64,
-                                            (rip_time.fract() * 60.0).round()
-                                        ))
-                                        .field("Title", &title_name, true)
-                                        .field("Disc Number", drive_number.to_string(), true)
-                                        .field("Season\n", season.to_string(), true)
-                                        .field("Episodes", &episode_range, true)
-                                        .color(0xfe0000),
-                                )
-                                .reference_message(&*message),
-                        )
-                        .await
-                        .map_err(|e| {
-                            error!("Failed to send rip summary message: {:?}", e);
-                            DiscordError::SendMessageFailed(e.to_string())
-                        })?;
-                    Ok(())
+                    discard_suspicious(ctx, &rips).await?;
+                    retry_rips(ctx, &message, rips, component.user.id).await
                 }
-                // This will be called when the user inputs a title
-                // for a movie rip
-                "select_title_to_rip" => {
-                    trace!("Got select_title_to_rip modal");
+                // This will be called when the user clicks the resume button on a batch
+                // of show rips that stopped partway
+                "resume_rips" => {
+                    trace!("Got resume_rips component interaction");
 
-                    // Satify the interaction
                     component.defer(&ctx.http).await.map_err(|e| {
                         error!("Failed to defer interaction: {:?}", e);
                         DiscordError::DeferFailed(e.to_string())
                     })?;
 
-                    // Same 'needs to be extracted' code as the previous component interactions
-                    let drive_number: u8 =
-                        message.embeds[0].fields[1].value.parse().map_err(|_| {
-                            warn!("Failed to parse disc number from message, ignoring");
-                            DiscordError::Unexpected("Failed to parse disc number".to_string())
-                        })?;
-
-                    let title_name: String =
-                        message.embeds[0].fields[0].value.parse().map_err(|_| {
-                            warn!("Failed to parse title from message, ignoring");
-                            DiscordError::Unexpected("Failed to parse title".to_string())
-                        })?;
+                    resume_batch(ctx, &message, &component.user, component.member.as_ref()).await
+                }
+                // This will be called when the user inputs a title
+                // for a movie rip
+                "select_title_to_rip" => {
+                    trace!("Got select_title_to_rip modal");
 
-                    // let drive_number: u8 = match message.embeds[0].fields[1].value.parse() {
-                    //     Ok(value) => value,
-                    //     Err(_) => {
-                    //         warn!("Failed to parse disc number from message, ignoring");
-                    //         return;
-                    //     }
-                    // };
-
-                    // let title_name: String = match message.embeds[0].fields[0].value.parse() {
-                    //     Ok(value) => value,
-                    //     Err(_) => {
-                    //         warn!("Failed to parse title from message, ignoring");
-                    //         return;
-                    //     }
-                    // };
-
-                    let selected_title: u8 = match &component.data.kind {
-                        ComponentInteractionDataKind::StringSelect { values } => {
-                            values[0].parse().map_err(|_| {
+                    let selected_titles: Vec<u16> = match &component.data.kind {
+                        ComponentInteractionDataKind::StringSelect { values } => values
+                            .iter()
+                            .map(|value| value.parse())
+                            .collect::<std::result::Result<_, _>>()
+                            .map_err(|_| {
                                 warn!("Failed to parse selected title, ignoring");
                                 DiscordError::Unexpected(
                                     "Failed to parse selected title".to_string(),
                                 )
-                            })?
-                        }
+                            })?,
                         _ => {
                             warn!("Recieved invalid component data, ignoring");
                             return Err(DiscordError::InvalidComponentData);
                         }
                     };
 
-                    // Only creates one rip for a movie
-                    let rip = Rip {
-                        title: title_name.clone(),
-                        drive_number,
-                        rip_type: RipType::Movie,
-                        title_id: selected_title.into(),
-                    };
+                    // Extras picked along with the movie are named before anything else
+                    if selected_titles.len() > 1 {
+                        let drive_number = embed_drive_number(message.embeds.first())?;
+                        let modal = extras_modal(drive_number, &selected_titles).await;
+                        component
+                            .create_response(&ctx.http, CreateInteractionResponse::Modal(modal))
+                            .await
+                            .map_err(|e| {
+                                error!("Failed to create get_extra_names modal: {:?}", e);
+                                DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                            })?;
+                        return Ok(());
+                    }
 
-                    trace!("Created rip: {:?}", rip);
+                    // Satify the interaction
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
 
-                    let now = std::time::Instant::now();
+                    let embed = message.embeds.first();
+                    let drive_number = embed_drive_number(embed)?;
+                    let title_name = embed_title(embed)?;
 
-                    // Sends a loading message to the user
-                    message
-                        .clone()
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new()
-                                .components(vec![])
-                                .embed(
-                                    CreateEmbed::new()
-                                        .title("Rip Movie")
-                                        .timestamp(Timestamp::now())
-                                        .description(format!("Ripping {}...", rip.title))
-                                        .field("Title", &rip.title, true)
-                                        .field("Disc Number", drive_number.to_string(), true)
-                                        .color(0xfe0000),
-                                )
-                                .button(
-                                    CreateButton::new("cancel_rip")
-                                        .label("Cancel")
-                                        .style(serenity::all::ButtonStyle::Danger),
-                                ),
-                        )
-                        .await
-                        .map_err(|e| {
-                            error!("Failed to send the rip in progress message: {:?}", e);
-                            DiscordError::EditMessageFailed(e.to_string())
-                        })?;
+                    // The year is optional, so its field is only present when given
+                    let year: Option<u16> = field(embed, "Year").and_then(|year| year.parse().ok());
 
-                    // This is the same magic sauce from the show rip
-                    let interaction_component = message
-                        .await_component_interaction(&ctx.shard)
-                        .custom_ids(vec!["cancel_rip".to_string()]);
-
-                    let was_cancelled = tokio::select! {
-                        rip_result = rip.execute() => {
-                            if let Err(e) = rip_result {
-                                error!("Failed to execute rip: {:?}", e);
-
-                                if let MakeMkvError::FileAlreadyExists(_) = e {
-                                    if let Err(e) = message
-                                        .clone()
-                                        .edit(
-                                            &ctx.http,
-                                            EditMessage::new().components(vec![])
-                                                .embed(
-                                                    CreateEmbed::new()
-                                                        .title("Rip Failed")
-                                                        .timestamp(Timestamp::now())
-                                                        .description("This movie is already on the server!")
-                                                        .field("Title", &rip.title, true)
-                                                        .field("Disc Number", drive_number.to_string(), true)
-                                                        .color(0xfe0000),
-                                                )
-                                        )
-                                        .await
-                                    {
-                                        error!("Failed to send rip failed message: {:?}", e);
-                                    }
-                                } else {
-                                    if let Err(e) = message
-                                    .clone()
-                                    .edit(
-                                        &ctx.http,
-                                        EditMessage::new().components(vec![])
-                                            .embed(
-                                            CreateEmbed::new()
-                                                .title("Rip Failed")
-                                                .timestamp(Timestamp::now())
-                                                .description("This rip failed! Please try again.")
-                                                .field("Title", &rip.title, true)
-                                                .field("Disc Number", drive_number.to_string(), true)
-                                                .color(0xfe0000),
-                                        )
-                                    )
-                                    .await
-                                {
-                                    error!("Failed to send rip failed message: {:?}", e);
-                                }
-                                }
+                    // Only creates one rip for a movie without extras
+                    let rip = movie_rip(&title_name, drive_number, selected_titles[0], year, None);
 
-                                return Err(DiscordError::MakeMkvError(e));
-                            }
-                            false
+                    trace!("Created rip: {:?}", rip);
 
-                        }
-                        Some(interaction) = interaction_component.next() => {
-                            debug!("Recieved canel request");
-                            if let Err(e) = interaction.defer(&ctx.http).await {
-                                error!("Failed to defer cancel request: {:?}", e);
-                            }
+                    // Nothing is ripped until the user confirms the details
+                    confirm_rips(ctx, &component.token, &message, &[rip]).await
+                }
+                // This will be called when the user confirms the details of a rip,
+                // everything needed to start it is read back from the confirmation embed
+                "confirm_rip" => {
+                    trace!("Got confirm_rip component interaction");
 
-                            if let Err(e) = rip.cancel().await{
-                                error!("Failed to cancel rip: {:?}", e);
-                            };
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
 
-                            if let Err(e) = message
-                                .clone()
-                                .edit(
-                                    &ctx.http,
-                                    EditMessage::new().components(vec![])
-                                    .embed(
-                                        CreateEmbed::new()
-                                            .title("Rip Cancelled")
-                                            .timestamp(Timestamp::now())
-                                            .description("Rip cancelled!")
-                                            .field("Title", &rip.title, true)
-                                            .field("Disc Number", drive_number.to_string(), true)
-                                            .color(0xfe0000)
-                                            .timestamp(Timestamp::now())
-                                    )
-                                )
-                                .await
-                            {
-                                error!("Failed to send rip cancelled message: {:?}", e);
-                            }
-                            info!("Rip cancelled");
-                            true
-                        }
+                    let embed = message.embeds.first().ok_or_else(|| {
+                        warn!("Confirmation message has no embed, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let mut rips = embed_rips(embed)?;
+                    let (title_name, drive_number) = (rips[0].title.clone(), rips[0].drive_number);
+
+                    let selected_titles = embed
+                        .fields
+                        .iter()
+                        .find(|field| field.name == tr!("Selected Titles"))
+                        .map(|field| field.value.as_str())
+                        .unwrap_or_default();
+
+                    let options = RipOptions {
+                        priority: embed_priority(&message),
+                        eject_when_done: embed_eject(ctx, &message).await?,
+                        owner: component.user.id,
                     };
 
-                    // If the rip was cancelled, do not send the summary message
-                    if was_cancelled {
-                        return Err(DiscordError::TaskCancelled);
-                    }
+                    if embed.title.as_deref() == Some(tr!(CONFIRM_MOVIE_TITLE).as_str()) {
+                        let rip = rips.remove(0);
+                        let year = rip.year;
 
-                    let rip_time = now.elapsed().as_secs_f64() / 60.00;
+                        let name = match year {
+                            Some(year) => format!("{} ({year})", rip.title),
+                            None => rip.title.clone(),
+                        };
+                        let parameters = rip_parameters(
+                            &name,
+                            drive_number,
+                            Some(selected_titles.trim()),
+                            options.eject_when_done,
+                        );
 
-                    message
-                        .clone()
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new().components(vec![]).embed(
-                                CreateEmbed::new()
-                                    .title(format!("Ripped {}", title_name))
-                                    .description("Rip completed!")
-                                    .color(0xfe0000)
-                                    .timestamp(Timestamp::now()),
-                            ),
-                        )
-                        .await
-                        .map_err(|e| {
-                            error!("Failed to send rip completed message: {:?}", e);
-                            DiscordError::EditMessageFailed(e.to_string())
-                        })?;
+                        take_rip_quota(component.user.id, component.member.as_ref()).await?;
+                        let message =
+                            start_rip_message(ctx, &component.token, &message, &rip.title).await?;
+                        let result = run_movie_rip(ctx, &message, rip, rips, options).await;
 
-                    message
-                        .channel_id
-                        .send_message(
-                            &ctx.http,
-                            CreateMessage::new()
-                                .embed(
-                                    CreateEmbed::new()
-                                        .title("Rip Summary")
-                                        .description(format!(
-                                            "Finished in: {} minutes and {:.0} seconds",
-                                            rip_time.floor() as u64,
-                                            (rip_time.fract() * 60.0).round()
-                                        ))
-                                        .field("Title", &title_name, true)
-                                        .field("Disc Number", drive_number.to_string(), true)
-                                        .color(0xfe0000),
-                                )
-                                .reference_message(&*message),
+                        let outcome = audit_outcome(&result);
+                        audit(
+                            &component.user,
+                            "rip",
+                            AuditAction::Rip,
+                            parameters,
+                            outcome,
                         )
-                        .await
-                        .unwrap();
-                    Ok(())
-                }
-                _ => {
-                    debug!(
-                        "Unknown component calling rip: {}, ignoring",
+                        .await;
+                        result
+                    } else {
+                        let RipType::Show { season, .. } = rips[0].rip_type else {
+                            warn!("Show confirmation has a movie rip, ignoring");
+                            return Err(DiscordError::InvalidComponentData);
+                        };
+
+                        let name = tr!(
+                            "{title} Season {season}",
+                            title = title_name,
+                            season = season
+                        );
+                        let parameters = rip_parameters(
+                            &name,
+                            drive_number,
+                            Some(selected_titles.trim()),
+                            options.eject_when_done,
+                        );
+
+                        take_rip_quota(component.user.id, component.member.as_ref()).await?;
+                        let message =
+                            start_rip_message(ctx, &component.token, &message, &name).await?;
+                        let result = run_show_rips(
+                            ctx,
+                            &message,
+                            &title_name,
+                            drive_number,
+                            season,
+                            options,
+                            rips,
+                        )
+                        .await;
+
+                        let outcome = audit_outcome(&result);
+                        audit(
+                            &component.user,
+                            "rip",
+                            AuditAction::Rip,
+                            parameters,
+                            outcome,
+                        )
+                        .await;
+                        result
+                    }
+                }
+                // Reopens the details modal filled in with the current details, so naming
+                // mistakes can be fixed before the rip starts. The titles are picked again
+                // once the modal is submitted
+                "edit_rip" => {
+                    trace!("Got edit_rip component interaction");
+
+                    let embed = message.embeds.first();
+
+                    let drive_number: u8 = embed
+                        .and_then(|embed| embed.fields.get(1))
+                        .and_then(|field| field.value.parse().ok())
+                        .ok_or_else(|| {
+                            warn!("Failed to parse disc number from message, ignoring");
+                            DiscordError::Unexpected("Failed to parse disc number".to_string())
+                        })?;
+
+                    let modal = if embed.and_then(|embed| embed.title.as_deref())
+                        == Some(tr!(CONFIRM_MOVIE_TITLE).as_str())
+                    {
+                        movie_modal(drive_number, embed)
+                    } else {
+                        show_modal(drive_number, embed)
+                    };
+
+                    component
+                        .create_response(&ctx.http, CreateInteractionResponse::Modal(modal))
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to create edit rip modal: {:?}", e);
+                            DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                        })?;
+
+                    Ok(())
+                }
+                // Opens a modal to override the episode numbers of a show rip, for when
+                // a gap is being filled or a bad episode is being re-ripped
+                "edit_episodes" => {
+                    trace!("Got edit_episodes component interaction");
+
+                    let embed = message.embeds.first().ok_or_else(|| {
+                        warn!("Confirmation message has no embed, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let titles = embed
+                        .fields
+                        .iter()
+                        .find(|field| field.name == tr!("Selected Titles"))
+                        .map(|field| parse_title_episodes(&field.value))
+                        .unwrap_or_default();
+
+                    let Some(&(_, first_episode, absolute_start)) = titles.first() else {
+                        warn!("No selected titles found on confirmation message, ignoring");
+                        return Err(DiscordError::InvalidComponentData);
+                    };
+
+                    // The range is only shown in the title, modals can't hold plain text
+                    let episodes: Vec<u8> = titles.iter().map(|&(_, episode, _)| episode).collect();
+
+                    component
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Modal(
+                                CreateModal::new(
+                                    "get_episode_start",
+                                    tr!(
+                                        "Episodes {episodes}",
+                                        episodes = format_episode_list(&episodes)
+                                    ),
+                                )
+                                .components(vec![
+                                    CreateActionRow::InputText(
+                                        CreateInputText::new(
+                                            InputTextStyle::Short,
+                                            tr!(
+                                                "First Episode ({count} titles)",
+                                                count = titles.len()
+                                            ),
+                                            "first_episode",
+                                        )
+                                        .value(first_episode.to_string())
+                                        .required(true),
+                                    ),
+                                    CreateActionRow::InputText({
+                                        let input = CreateInputText::new(
+                                            InputTextStyle::Short,
+                                            tr!("Absolute Episode Start (optional)"),
+                                            "absolute_episode",
+                                        )
+                                        .required(false);
+
+                                        match absolute_start {
+                                            Some(start) => input.value(start.to_string()),
+                                            None => input,
+                                        }
+                                    }),
+                                ]),
+                            ),
+                        )
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to create get_episode_start modal: {:?}", e);
+                            DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                        })?;
+
+                    Ok(())
+                }
+                // Opens a modal to split each title into an episode every few chapters,
+                // for discs that store a whole season as one long title
+                "split_by_chapters" => {
+                    trace!("Got split_by_chapters component interaction");
+
+                    let input = prefill(
+                        CreateInputText::new(
+                            InputTextStyle::Short,
+                            tr!("Chapters Per Episode (empty to not split)"),
+                            "chapters_per_episode",
+                        )
+                        .placeholder(tr!("e.g. 6"))
+                        .required(false),
+                        message.embeds.first(),
+                        "Chapters Per Episode",
+                    );
+
+                    component
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Modal(
+                                CreateModal::new(
+                                    "get_chapter_split",
+                                    tr!("Split titles by chapters"),
+                                )
+                                .components(vec![CreateActionRow::InputText(input)]),
+                            ),
+                        )
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to create get_chapter_split modal: {:?}", e);
+                            DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                        })?;
+
+                    Ok(())
+                }
+                // Opens a modal to attach a note to the rips, such as the edition of the
+                // disc, which is kept in the history and on the summary
+                "add_note" => {
+                    trace!("Got add_note component interaction");
+
+                    let input = prefill(
+                        CreateInputText::new(InputTextStyle::Short, tr!("Note"), "note")
+                            .placeholder(tr!("e.g. Extended cut, region B import"))
+                            .max_length(MAX_NOTE_LENGTH)
+                            .required(false),
+                        message.embeds.first(),
+                        "Rip Note",
+                    );
+
+                    component
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Modal(
+                                CreateModal::new("get_rip_note", tr!("Add a note to the rip"))
+                                    .components(vec![CreateActionRow::InputText(input)]),
+                            ),
+                        )
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to create get_rip_note modal: {:?}", e);
+                            DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                        })?;
+
+                    Ok(())
+                }
+                // Drops a rip that hasn't started yet
+                "cancel_pending_rip" => {
+                    trace!("Got cancel_pending_rip component interaction");
+
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let mut embed = themed_embed()
+                        .title(tr!("Rip Cancelled"))
+                        .description(tr!("Rip cancelled before it started!"));
+
+                    if let Some(current) = message.embeds.first() {
+                        embed = embed.fields(
+                            current
+                                .fields
+                                .iter()
+                                .take(2)
+                                .map(|field| (field.name.clone(), field.value.clone(), true)),
+                        );
+                    }
+
+                    edit_message(
+                        ctx,
+                        &component.token,
+                        &message,
+                        EditMessage::new().components(vec![]).embed(embed),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send rip cancelled message: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
+
+                    info!("Rip cancelled before it started");
+                    Ok(())
+                }
+                // Picks the library the rip is saved to, from the confirmation step. The
+                // choice is kept in the embed like the rest of the rip, so the confirmation
+                // is rebuilt around it
+                "select_library" => {
+                    trace!("Got select_library component interaction");
+
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let library = match &component.data.kind {
+                        ComponentInteractionDataKind::StringSelect { values } => values.first(),
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        warn!("Recieved invalid component data, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let mut updated = (*message).clone();
+                    let numbered_for = updated.embeds.first().and_then(embed_library);
+                    let field = updated
+                        .embeds
+                        .first_mut()
+                        .and_then(|embed| {
+                            embed
+                                .fields
+                                .iter_mut()
+                                .find(|field| field.name == tr!("Library"))
+                        })
+                        .ok_or_else(|| {
+                            warn!("No library found on the rip message, ignoring");
+                            DiscordError::InvalidComponentData
+                        })?;
+                    field.value.clone_from(library);
+
+                    // Episodes carry on from the last one in the library they are saved to
+                    let mut rips = embed_rips(&updated.embeds[0])?;
+                    let library = embed_library(&updated.embeds[0]);
+                    if library != numbered_for {
+                        renumber_episodes(ctx, &mut rips, library.as_deref()).await?;
+                    }
+                    confirm_rips(ctx, &component.token, &updated, &rips).await
+                }
+                // Flips between keeping 3D titles in 3D and keeping only their 2D version,
+                // from the confirmation step. A library the 3D titles were routed to is
+                // swapped for the output directory along with it, and back
+                "toggle_3d" => {
+                    trace!("Got toggle_3d component interaction");
+
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let make_mkv = makemkv(ctx).await?;
+                    let mut updated = (*message).clone();
+                    let Some(embed) = updated.embeds.first_mut() else {
+                        warn!("No embed found on the rip message, ignoring");
+                        return Err(DiscordError::InvalidComponentData);
+                    };
+
+                    let numbered_for = embed_library(embed);
+                    let base_view = !embed_base_view(embed);
+                    for field in &mut embed.fields {
+                        if field.name == tr!("3D") {
+                            field.value = three_d_mode(base_view);
+                        } else if field.name == tr!("Library") {
+                            match (&make_mkv.library_3d, base_view) {
+                                (Some(library_3d), true) if field.value == *library_3d => {
+                                    field.value = DEFAULT_LIBRARY.to_string();
+                                }
+                                (Some(library_3d), false) if field.value == DEFAULT_LIBRARY => {
+                                    field.value.clone_from(library_3d);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+
+                    let mut rips = embed_rips(embed)?;
+                    let library = embed_library(embed);
+                    if library != numbered_for {
+                        renumber_episodes(ctx, &mut rips, library.as_deref()).await?;
+                    }
+                    confirm_rips(ctx, &component.token, &updated, &rips).await
+                }
+                // Flips whether the drive is ejected once the rip finishes, from the
+                // confirmation step
+                "toggle_eject_after_rip" => {
+                    trace!("Got toggle_eject_after_rip component interaction");
+
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let eject_when_done = !embed_eject(ctx, &message).await?;
+
+                    let Some(mut embed) = message.embeds.first().cloned() else {
+                        warn!("No embed found on the rip message, ignoring");
+                        return Err(DiscordError::InvalidComponentData);
+                    };
+
+                    if let Some(field) = embed
+                        .fields
+                        .iter_mut()
+                        .find(|field| field.name == tr!("Eject When Done"))
+                    {
+                        field.value = yes_no(eject_when_done);
+                    }
+
+                    edit_message(
+                        ctx,
+                        &component.token,
+                        &message,
+                        EditMessage::new().embed(CreateEmbed::from(embed)),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to update eject option: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
+
+                    Ok(())
+                }
+                _ => {
+                    debug!(
+                        "Unknown component calling rip: {}, ignoring",
                         component.data.custom_id
                     );
                     return Err(DiscordError::InvalidInteractionCall);
@@ -859,7 +1151,7 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
             };
 
             // Match on the modal custom id to determine which modal was called
-            match modal.data.custom_id.as_str() {
+            match custom_ids::name(&modal.data.custom_id) {
                 // This will be called when the user inputs a title for a movie rip
                 "get_title_of_movie_rip" => {
                     // Satify the interaction
@@ -870,138 +1162,129 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
 
                     // Some more stupid parse stuff, just now matching for ther
                     // Action row component type as well
-                    let drive_number: u8 = match modal.data.components[0].components[0] {
-                        ActionRowComponent::InputText(ref input) => {
-                            if let Some(value) = &input.value {
-                                value.parse().unwrap()
-                            } else {
-                                debug!("No value found for disc number, ignoring");
-                                return Err(DiscordError::InvalidComponentData);
-                            }
-                        }
-                        _ => {
+                    let drive_number: u8 = get_modal_input(modal, "disc_number")
+                        .and_then(|drive_number| drive_number.parse().ok())
+                        .ok_or_else(|| {
                             warn!("Failed to parse disc number from modal, ignoring");
-                            return Err(DiscordError::InvalidComponentData);
-                        }
-                    };
+                            DiscordError::InvalidComponentData
+                        })?;
+
+                    let title = get_modal_input(modal, "title_of_movie").ok_or_else(|| {
+                        debug!("No value found for title, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
 
-                    let title = match modal.data.components[1].components[0] {
-                        ActionRowComponent::InputText(ref input) => {
-                            if let Some(value) = &input.value {
-                                value.clone()
-                            } else {
-                                debug!("No value found for title, ignoring");
-                                return Err(DiscordError::InvalidComponentData);
+                    // The year is optional, an empty input means no year was given
+                    let year: Option<u16> =
+                        match modal.data.components.get(2).map(|row| &row.components[0]) {
+                            Some(ActionRowComponent::InputText(input)) => {
+                                match input.value.as_deref().map(str::trim) {
+                                    None | Some("") => None,
+                                    Some(value) => Some(value.parse().map_err(|_| {
+                                        warn!("Failed to parse year from modal, ignoring");
+                                        DiscordError::InvalidComponentData
+                                    })?),
+                                }
                             }
-                        }
-                        _ => {
-                            warn!("Failed to parse title from modal, ignoring");
-                            return Err(DiscordError::InvalidComponentData);
-                        }
-                    };
+                            _ => None,
+                        };
+
+                    let priority = parse_priority(modal)?;
+                    let preset = embed_preset(message.embeds.first()).await;
+
+                    // Uses Radarr's name for the movie, if it is configured
+                    let (title, year) = canonical_movie(title, year).await;
 
                     // Starts the process of getting the title info from makemkv
                     let titles_future = get_title_info(drive_number);
 
                     // Sends a loading message to the user
-                    message
-                        .clone()
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new().components(vec![]).embed(
-                                CreateEmbed::new()
-                                    .title("Rip Movie")
-                                    .description("Please wait while titles are loaded...")
-                                    .field("Title", &title, true)
-                                    .field("Disc Number", drive_number.to_string(), true)
-                                    .color(0xfe0000),
-                            ),
-                        )
-                        .await
-                        .unwrap();
+                    edit_message(
+                        ctx,
+                        &modal.token,
+                        &message,
+                        EditMessage::new().components(vec![]).embed(
+                            themed_embed()
+                                .title(tr!("Rip Movie"))
+                                .description(tr!("Please wait while titles are loaded..."))
+                                .field(tr!("Title"), &title, true)
+                                .field(tr!("Disc Number"), drive_number.to_string(), true)
+                                .fields(year.map(|year| (tr!("Year"), year.to_string(), true)))
+                                .field(tr!("Priority"), priority_name(priority), true)
+                                .fields(preset_field(preset.as_ref())),
+                        ),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send loading titles message: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
 
-                    // Awaits the title info from makemkv
-                    let titles = titles_future.await.unwrap().titles;
+                    // Awaits the title info from makemkv, along with the runtime from
+                    // Radarr to help pick out the main feature
+                    let (disc_info, runtime) =
+                        tokio::join!(titles_future, movie_runtime(&title, year));
+                    let titles = disc_info
+                        .map_err(|e| {
+                            error!("Failed to get title info: {:?}", e);
+                            DiscordError::MakeMkvError(e)
+                        })?
+                        .titles;
+                    let (titles, min_minutes) = preset_titles(titles, preset.as_ref());
 
-                    // Limit the options to the first 25 to comply with Discord API's limit
-                    let options: Vec<CreateSelectMenuOption> = titles
-                        .iter()
-                        .take(25)
-                        .map(|title| {
-                            let title_details =
-                                format!("Title: {}, Duration: {}", title.title_id, title.length);
-                            let description = format!(
-                                "Chapters: {}, Size: {}, Resolution: {}, Frame Rate: {}",
-                                title.chapters, title.size, title.resolution, title.frame_rate
-                            );
-                            CreateSelectMenuOption::new(title_details, title.title_id.to_string())
-                                .description(description)
-                        })
-                        .collect();
+                    let obfuscation = detect_obfuscation(&titles);
+                    let options = movie_title_options(&titles, runtime);
 
                     trace!("Got options: {:?}", options);
 
-                    if options.len() < 1 {
+                    if options.is_empty() {
                         warn!("No titles found for disc number: {}", drive_number);
-                        message
-                            .clone()
-                            .edit(
-                                &ctx.http,
-                                EditMessage::new().components(vec![]).embed(
-                                    CreateEmbed::new()
-                                        .title("Rip Failed")
-                                        .description("No titles found for this disc number")
-                                        .field("Disc Number", drive_number.to_string(), true)
-                                        .color(0xfe0000),
-                                ),
-                            )
-                            .await
-                            .map_err(|e| {
-                                error!("Failed to send no titles found message: {:?}", e);
-                                return DiscordError::EditMessageFailed(e.to_string());
-                            })?;
-                        return Err(DiscordError::Unexpected(
-                            "No titles found for disc number".to_string(),
-                        ));
+                        return no_titles_found(ctx, &modal.token, &message, drive_number).await;
                     }
 
                     // Add a note to the embed if some titles were excluded
-                    let mut embed = CreateEmbed::new()
-                        .title("Rip Movie")
-                        .description("Please select title to rip")
-                        .field("Title", &title, true)
-                        .field("Disc Number", drive_number.to_string(), true)
-                        .color(0xfe0000);
+                    let mut embed = themed_embed()
+                        .title(tr!("Rip Movie"))
+                        .description(tr!(
+                            "Please select the movie to rip, and any extras to rip along with it"
+                        ))
+                        .field(tr!("Title"), &title, true)
+                        .field(tr!("Disc Number"), drive_number.to_string(), true)
+                        .fields(year.map(|year| (tr!("Year"), year.to_string(), true)))
+                        .field(tr!("Priority"), priority_name(priority), true)
+                        .fields(preset_field(preset.as_ref()))
+                        .fields(obfuscation.map(|obfuscation| {
+                            (tr!("Warning"), obfuscation_warning(&obfuscation), false)
+                        }));
 
                     if titles.len() > 25 {
-                        embed = embed.field(
-                            "Note",
-                            "Only the first 25 titles are shown due to Discord API limitations.",
+                        embed = embed.field(tr!("Note"),
+                            tr!("Only the first 25 titles are shown due to Discord API limitations."),
                             false,
                         );
                     }
 
                     // Spawns the select menu for the user to select the title to rip
-                    message
-                        .clone()
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new()
-                                .components(vec![CreateActionRow::SelectMenu(
+                    edit_message(
+                        ctx,
+                        &modal.token,
+                        &message,
+                        EditMessage::new()
+                            .components(vec![
+                                CreateActionRow::SelectMenu(
                                     // Will call the select_title_to_rip component
                                     // when the user selects a title
-                                    CreateSelectMenu::new(
-                                        "select_title_to_rip",
-                                        CreateSelectMenuKind::String { options },
-                                    ),
-                                )])
-                                .embed(embed),
-                        )
-                        .await
-                        .map_err(|e| {
-                            error!("Failed to send select title menu: {:?}", e);
-                            DiscordError::EditMessageFailed(e.to_string())
-                        })?;
+                                    movie_title_menu(options),
+                                ),
+                                title_buttons(min_minutes),
+                            ])
+                            .embed(embed),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send select title menu: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
 
                     Ok(())
                 }
@@ -1015,162 +1298,321 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
 
                     // You know the drill, same as the previous modal just with more
                     // ... *seasoning*
-                    let drive_number: u8 = match modal.data.components[0].components[0] {
-                        ActionRowComponent::InputText(ref input) => {
-                            if let Some(value) = &input.value {
-                                value.parse().unwrap()
-                            } else {
-                                debug!("No value found for disc number, ignoring");
-                                return Err(DiscordError::InvalidComponentData);
-                            }
-                        }
-                        _ => {
+                    let drive_number: u8 = get_modal_input(modal, "disc_number")
+                        .and_then(|drive_number| drive_number.parse().ok())
+                        .ok_or_else(|| {
                             warn!("Failed to parse disc number from modal, ignoring");
-                            return Err(DiscordError::InvalidComponentData);
-                        }
-                    };
+                            DiscordError::InvalidComponentData
+                        })?;
 
-                    let title = match modal.data.components[1].components[0] {
-                        ActionRowComponent::InputText(ref input) => {
-                            if let Some(value) = &input.value {
-                                value.clone()
-                            } else {
-                                warn!("No value found for title, ignoring");
-                                return Err(DiscordError::InvalidComponentData);
-                            }
-                        }
-                        _ => {
-                            warn!("Failed to parse title from modal, ignoring");
-                            return Err(DiscordError::InvalidComponentData);
-                        }
-                    };
+                    let title = get_modal_input(modal, "title_of_show").ok_or_else(|| {
+                        warn!("No value found for title, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let season = get_modal_input(modal, "season").ok_or_else(|| {
+                        warn!("No value found for season, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
 
-                    let season = match modal.data.components[2].components[0] {
-                        ActionRowComponent::InputText(ref input) => {
-                            if let Some(value) = &input.value {
-                                value.clone()
-                            } else {
-                                warn!("No value found for season, ignoring");
-                                return Err(DiscordError::InvalidComponentData);
+                    // The absolute episode start is optional, an empty input means the
+                    // show is numbered per season
+                    let absolute_start: Option<u16> =
+                        match modal.data.components.get(3).map(|row| &row.components[0]) {
+                            Some(ActionRowComponent::InputText(input)) => {
+                                match input.value.as_deref().map(str::trim) {
+                                    None | Some("") => None,
+                                    Some(value) => Some(value.parse().map_err(|_| {
+                                        warn!(
+                                            "Failed to parse absolute episode from modal, ignoring"
+                                        );
+                                        DiscordError::InvalidComponentData
+                                    })?),
+                                }
                             }
-                        }
-                        _ => {
-                            warn!("Failed to parse season from modal, ignoring");
-                            return Err(DiscordError::InvalidComponentData);
-                        }
-                    };
+                            _ => None,
+                        };
+
+                    let priority = parse_priority(modal)?;
+                    let preset = embed_preset(message.embeds.first()).await;
+
+                    // Uses Sonarr's name for the show, if it is configured
+                    let title = canonical_series(title).await;
 
                     let titles_future = get_title_info(drive_number);
 
-                    message
-                        .clone()
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new().components(vec![]).embed(
-                                CreateEmbed::new()
-                                    .title("Rip Show")
-                                    .description("Please wait while titles are loaded...")
-                                    .field("Title", &title, true)
-                                    .field("Disc Number", drive_number.to_string(), true)
-                                    .field("Season", &season, true)
-                                    .color(0xfe0000),
-                            ),
-                        )
-                        .await
-                        .unwrap();
+                    edit_message(
+                        ctx,
+                        &modal.token,
+                        &message,
+                        EditMessage::new().components(vec![]).embed(
+                            themed_embed()
+                                .title(tr!("Rip Show"))
+                                .description(tr!("Please wait while titles are loaded..."))
+                                .field(tr!("Title"), &title, true)
+                                .field(tr!("Disc Number"), drive_number.to_string(), true)
+                                .field(tr!("Season"), &season, true)
+                                .fields(
+                                    absolute_start.map(|start| {
+                                        (tr!("Absolute Start"), start.to_string(), true)
+                                    }),
+                                )
+                                .field(tr!("Priority"), priority_name(priority), true)
+                                .fields(preset_field(preset.as_ref())),
+                        ),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send loading titles message: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
 
-                    let titles = titles_future.await.unwrap().titles;
+                    let titles = titles_future
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to get title info: {:?}", e);
+                            DiscordError::MakeMkvError(e)
+                        })?
+                        .titles;
+                    let (titles, min_minutes) = preset_titles(titles, preset.as_ref());
 
-                    // Limit the options to the first 25 to comply with Discord API's limit
-                    let options: Vec<CreateSelectMenuOption> = titles
-                        .iter()
-                        .take(25)
-                        .map(|title| {
-                            let title_details =
-                                format!("Title: {}, Duration: {}", title.title_id, title.length);
-                            let description = format!(
-                                "Chapters: {}, Size: {}, Resolution: {}, Frame Rate: {}",
-                                title.chapters, title.size, title.resolution, title.frame_rate
-                            );
-                            CreateSelectMenuOption::new(title_details, title.title_id.to_string())
-                                .description(description)
-                        })
-                        .collect();
+                    let options = show_title_options(&titles);
 
                     trace!("Got options: {:?}", options);
 
-                    if options.len() < 1 {
+                    if options.is_empty() {
                         warn!("No titles found for disc number: {}", drive_number);
-                        message
-                            .clone()
-                            .edit(
-                                &ctx.http,
-                                EditMessage::new().components(vec![]).embed(
-                                    CreateEmbed::new()
-                                        .title("Rip Failed")
-                                        .description("No titles found for this disc number")
-                                        .field("Disc Number", drive_number.to_string(), true)
-                                        .color(0xfe0000),
-                                ),
-                            )
-                            .await
-                            .map_err(|e| {
-                                error!("Failed to send no titles found message: {:?}", e);
-                                return DiscordError::EditMessageFailed(e.to_string());
-                            })?;
-                        return Err(DiscordError::Unexpected(
-                            "No titles found for disc number".to_string(),
-                        ));
+                        return no_titles_found(ctx, &modal.token, &message, drive_number).await;
                     }
 
                     // Add a note to the embed if some titles were excluded
-                    let mut embed = CreateEmbed::new()
-                        .title("Rip Show")
-                        .description("Please select titles to rip")
-                        .field("Title", &title, true)
-                        .field("Disc Number", drive_number.to_string(), true)
-                        .field("Season", season, true)
-                        .color(0xfe0000);
+                    let mut embed = themed_embed()
+                        .title(tr!("Rip Show"))
+                        .description(tr!("Please select titles to rip"))
+                        .field(tr!("Title"), &title, true)
+                        .field(tr!("Disc Number"), drive_number.to_string(), true)
+                        .field(tr!("Season"), season, true)
+                        .fields(
+                            absolute_start
+                                .map(|start| (tr!("Absolute Start"), start.to_string(), true)),
+                        )
+                        .field(tr!("Priority"), priority_name(priority), true)
+                        .fields(preset_field(preset.as_ref()));
 
                     if titles.len() > 25 {
-                        embed = embed.field(
-                            "Note",
-                            "Only the first 25 titles are shown due to Discord API limitations.",
+                        embed = embed.field(tr!("Note"),
+                            tr!("Only the first 25 titles are shown due to Discord API limitations."),
                             false,
                         );
                     }
-                    trace!("Got options: {:?}", options);
-
-                    let max_values = options.len() as u8;
-
-                    trace!("Max values: {}", max_values);
-
                     // Spawns the select menu for the user to select multiple titles to rip
-                    // This will be a multi select menu, so the max values is the number of titles
-                    message
-                        .clone()
-                        .edit(
-                            &ctx.http,
-                            EditMessage::new()
-                                .components(vec![CreateActionRow::SelectMenu(
-                                    // Will call the select_titles_to_rip component
-                                    CreateSelectMenu::new(
-                                        "select_titles_to_rip",
-                                        CreateSelectMenuKind::String { options },
-                                    )
-                                    .min_values(1)
-                                    .max_values(max_values),
-                                )])
-                                .embed(embed),
-                        )
-                        .await
-                        .map_err(|e| {
-                            error!("Failed to send select titles menu: {:?}", e);
-                            DiscordError::EditMessageFailed(e.to_string())
-                        })?;
+                    edit_message(
+                        ctx,
+                        &modal.token,
+                        &message,
+                        EditMessage::new()
+                            .components(vec![
+                                // Will call the select_titles_to_rip component
+                                CreateActionRow::SelectMenu(show_title_menu(options)),
+                                title_buttons(min_minutes),
+                            ])
+                            .embed(embed),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send select titles menu: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
 
                     Ok(())
                 }
+                // This will be called when the user overrides the episode numbers of a
+                // show rip from the confirmation step
+                "get_episode_start" => {
+                    modal.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let first_episode: u8 = get_modal_input(modal, "first_episode")
+                        .and_then(|episode| episode.trim().parse().ok())
+                        .ok_or_else(|| {
+                            warn!("Failed to parse first episode from modal, ignoring");
+                            DiscordError::InvalidComponentData
+                        })?;
+
+                    let absolute_start: Option<u16> =
+                        match get_modal_input(modal, "absolute_episode") {
+                            Some(start) => Some(start.trim().parse().map_err(|_| {
+                                warn!("Failed to parse absolute episode from modal, ignoring");
+                                DiscordError::InvalidComponentData
+                            })?),
+                            None => None,
+                        };
+
+                    let embed = message.embeds.first().ok_or_else(|| {
+                        warn!("Confirmation message has no embed, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let title_name = embed_title(Some(embed))?;
+                    let drive_number = embed_drive_number(Some(embed))?;
+                    let season = embed_season(Some(embed))?;
+
+                    // Keeps the titles in the order they were picked, numbering them from
+                    // the new starting episode
+                    let titles = embed
+                        .fields
+                        .iter()
+                        .find(|field| field.name == tr!("Selected Titles"))
+                        .map(|field| parse_title_episodes(&field.value))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, (title_id, _, _))| {
+                            let index = u8::try_from(index).ok()?;
+                            let episode = first_episode.checked_add(index)?;
+                            let absolute_episode = match absolute_start {
+                                Some(start) => Some(start.checked_add(index.into())?),
+                                None => None,
+                            };
+                            Some((title_id, episode, absolute_episode))
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| {
+                            warn!("Episode numbers are out of range, ignoring");
+                            DiscordError::InvalidComponentData
+                        })?;
+
+                    let rips = show_rips(
+                        &title_name,
+                        drive_number,
+                        season,
+                        titles,
+                        embed_chapters_per_episode(embed),
+                    );
+
+                    trace!("Renumbered rips: {:?}", rips);
+
+                    confirm_rips(ctx, &modal.token, &message, &rips).await
+                }
+                // This will be called when the user sets how many chapters make up an
+                // episode from the confirmation step
+                "get_chapter_split" => {
+                    modal.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    // Empty or 0 turns splitting back off
+                    let chapters_per_episode: Option<u16> =
+                        match get_modal_input(modal, "chapters_per_episode") {
+                            Some(chapters) => {
+                                let chapters: u16 = chapters.parse().map_err(|_| {
+                                    warn!("Failed to parse chapters per episode, ignoring");
+                                    DiscordError::InvalidComponentData
+                                })?;
+                                (chapters > 0).then_some(chapters)
+                            }
+                            None => None,
+                        };
+
+                    let embed = message.embeds.first().ok_or_else(|| {
+                        warn!("Confirmation message has no embed, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+
+                    let title_name = embed_title(Some(embed))?;
+                    let drive_number = embed_drive_number(Some(embed))?;
+                    let season = embed_season(Some(embed))?;
+
+                    let titles = embed
+                        .fields
+                        .iter()
+                        .find(|field| field.name == tr!("Selected Titles"))
+                        .map(|field| parse_title_episodes(&field.value))
+                        .unwrap_or_default();
+
+                    let rips = show_rips(
+                        &title_name,
+                        drive_number,
+                        season,
+                        titles,
+                        chapters_per_episode,
+                    );
+
+                    confirm_rips(ctx, &modal.token, &message, &rips).await
+                }
+                // This will be called when the user names the extras picked along with a
+                // movie, the movie's title is carried in the modal's custom id
+                "get_extra_names" => {
+                    modal.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let embed = message.embeds.first();
+                    let drive_number = embed_drive_number(embed)?;
+                    let (Some(title_name), Some(movie)) = (
+                        field(embed, "Title"),
+                        custom_ids::split(&modal.data.custom_id)
+                            .1
+                            .and_then(|title_id| title_id.parse().ok()),
+                    ) else {
+                        warn!("Failed to parse the movie of the extras, ignoring");
+                        return Err(DiscordError::InvalidComponentData);
+                    };
+                    let year: Option<u16> = field(embed, "Year").and_then(|year| year.parse().ok());
+
+                    let extras = modal
+                        .data
+                        .components
+                        .iter()
+                        .flat_map(|row| row.components.iter())
+                        .filter_map(|component| match component {
+                            ActionRowComponent::InputText(input) => {
+                                let title_id =
+                                    input.custom_id.strip_prefix("extra_")?.parse().ok()?;
+                                let name = get_modal_input(modal, &input.custom_id)?;
+                                Some(movie_rip(
+                                    title_name,
+                                    drive_number,
+                                    title_id,
+                                    year,
+                                    Some(name),
+                                ))
+                            }
+                            _ => None,
+                        });
+                    let rips: Vec<Rip> =
+                        std::iter::once(movie_rip(title_name, drive_number, movie, year, None))
+                            .chain(extras)
+                            .collect();
+
+                    confirm_rips(ctx, &modal.token, &message, &rips).await
+                }
+                // This will be called when the user attaches a note to the rips from the
+                // confirmation step, an empty note removes it
+                "get_rip_note" => {
+                    modal.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let mut updated = message.clone();
+                    let embed = updated.embeds.first_mut().ok_or_else(|| {
+                        warn!("Confirmation message has no embed, ignoring");
+                        DiscordError::InvalidComponentData
+                    })?;
+                    embed.fields.retain(|field| field.name != tr!("Rip Note"));
+
+                    let mut rips = embed_rips(embed)?;
+                    let note = get_modal_input(modal, "note");
+                    for rip in &mut rips {
+                        rip.note.clone_from(&note);
+                    }
+                    confirm_rips(ctx, &modal.token, &updated, &rips).await
+                }
                 _ => {
                     debug!(
                         "Unknown modal calling rip: {}, ignoring",
@@ -1186,3 +1628,2869 @@ pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
         }
     }
 }
+
+/// Runs a single movie rip, updating the message while it waits in the queue and rips,
+/// and allowing the user to cancel it.
+///
+/// The `extras` picked along with the movie are ripped after it as part of the same job.
+/// They are only ripped once the movie has been, and one that fails doesn't undo the rest,
+/// it is listed on the summary to be retried instead.
+///
+/// Once the rip is over, the drive is ejected if `eject_when_done` is set, unless the rip
+/// was cancelled.
+pub(super) async fn run_movie_rip(
+    ctx: &Context,
+    message: &Message,
+    rip: Rip,
+    extras: Vec<Rip>,
+    options: RipOptions,
+) -> Result<()> {
+    let RipOptions {
+        priority,
+        eject_when_done,
+        owner,
+    } = options;
+    let drive_number = rip.drive_number;
+
+    // A UHD disc in a drive that can't read it would only fail once it is its turn
+    check_capabilities(drive_number).await?;
+    let makemkv = makemkv(ctx).await?;
+    let disc_label = disc_label(drive_number).await;
+    let selected_titles = std::iter::once(&rip)
+        .chain(&extras)
+        .map(format_title_episode)
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // The job leaves the queue once the ticket is dropped, here and by the
+    // worker running the rip, however this interaction ends
+    let ticket = JobTicket::submit(
+        &tr!(
+            "{title} (Disc {disc})",
+            title = rip.title,
+            disc = drive_number
+        ),
+        JobKind::Rip { drive_number },
+        priority,
+    )
+    .await;
+    let ticket = Arc::new(ticket);
+    for rip in std::iter::once(&rip).chain(&extras) {
+        rip.queued();
+    }
+
+    let now = std::time::Instant::now();
+
+    let ripping_embed = with_host(
+        themed_embed()
+            .title(tr!("Rip Movie"))
+            .description(tr!("Ripping {title}...", title = rip.title))
+            .field(tr!("Title"), &rip.title, true)
+            .field(tr!("Disc Number"), drive_number.to_string(), true)
+            .field(tr!("Priority"), priority_name(priority), true),
+        drive_number,
+    );
+
+    // Sends a loading message to the user
+    message
+        .clone()
+        .edit(
+            &ctx.http,
+            EditMessage::new()
+                .components(vec![])
+                .embed(ripping_embed.clone())
+                .button(
+                    CreateButton::new(custom_ids::with_id("cancel_rip", ticket.id))
+                        .label(tr!("Cancel"))
+                        .style(serenity::all::ButtonStyle::Danger),
+                ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send the rip in progress message: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    // This is the same magic sauce from the show rip
+    let mut controls = controls::listen(ticket.id, message.id, owner);
+
+    // Filled in once the rip completes, for the upload
+    let mut saved_files = Vec::new();
+    let mut recovery = None;
+    let mut suspicious: Vec<(Rip, SizeWarning)> = Vec::new();
+    let mut ripped_extras: Vec<String> = Vec::new();
+    let mut failed_extras: Vec<(Rip, MakeMkvError)> = Vec::new();
+    let mut failed_logs: Vec<String> = Vec::new();
+    let total = extras.len() + 1;
+    let position = |index: usize| (total > 1).then_some((index, total));
+
+    let was_cancelled = tokio::select! {
+        rip_result = async {
+            wait_for_turn(ctx, message, &ticket, ripping_embed.clone()).await;
+            let outcome = execute_with_eta(
+                ctx,
+                message,
+                &ticket,
+                &rip,
+                &makemkv,
+                ripping_embed.clone(),
+                position(1),
+            )
+            .await?;
+
+            let mut extra_outcomes = Vec::new();
+            for (index, extra) in extras.iter().enumerate() {
+                let extra_embed = ripping_embed.clone().description(tr!(
+                    "Ripping {extra} of {title}...",
+                    extra = extra.extra.as_deref().unwrap_or_default(),
+                    title = extra.title
+                ));
+                let result = execute_with_eta(
+                    ctx,
+                    message,
+                    &ticket,
+                    extra,
+                    &makemkv,
+                    extra_embed,
+                    position(index + 2),
+                )
+                .await;
+                extra_outcomes.push((extra.clone(), result));
+            }
+            Ok::<_, MakeMkvError>((outcome, extra_outcomes))
+        } => {
+            let (outcome, extra_outcomes) = match rip_result {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                error!("Failed to execute rip: {:?}", e);
+
+                // What MakeMKV printed is attached, so the disc can be looked into from here
+                let mut failed_message = EditMessage::new().components(vec![]);
+                if let Some(log) = logs::take(drive_number) {
+                    failed_message = failed_message.new_attachment(rip_log_attachment(log));
+                }
+
+                if let Err(e) = message
+                    .clone()
+                    .edit(
+                        &ctx.http,
+                        failed_message
+                            .embed(
+                                themed_embed()
+                                    .title(tr!("Rip Failed"))
+                                    .description(e.user_message())
+                                    .field(tr!("Title"), &rip.title, true)
+                                    .field(tr!("Disc Number"), drive_number.to_string(), true)
+                                    .fields(rip.year.map(|year| (tr!("Year"), year.to_string(), true)))
+                                    // Kept so the rip can be retried from the message
+                                    .field(tr!("Selected Titles"), selected_titles, true)
+                                    .fields(rip.library.clone().map(|library| (tr!("Library"), library, true)))
+                                    .fields(rip.base_view.then(|| (tr!("3D"), three_d_mode(true), true)))
+                                    .fields(rip.note.clone().map(|note| (tr!("Rip Note"), note, false))),
+                            )
+                    )
+                    .await
+                {
+                    error!("Failed to send rip failed message: {:?}", e);
+                }
+
+                // The job is over either way, so the disc can still be swapped
+                if eject_when_done {
+                    eject_after_rip(drive_number, ticket.id).await;
+                }
+
+                return Err(DiscordError::reported(e));
+                }
+            };
+            saved_files = outcome.files;
+            recovery = outcome.recovery;
+            suspicious.extend(outcome.size_warning.map(|warning| (rip.clone(), warning)));
+
+            for (extra, result) in extra_outcomes {
+                match result {
+                    Ok(outcome) => {
+                        saved_files.extend(outcome.files);
+                        ripped_extras.extend(extra.extra.clone());
+                        suspicious.extend(outcome.size_warning.map(|warning| (extra, warning)));
+                    }
+                    Err(e) => {
+                        error!("Failed to rip extra {:?}: {:?}", extra.extra, e);
+                        failed_logs.extend(logs::take(drive_number));
+                        failed_extras.push((extra, e));
+                    }
+                }
+            }
+            false
+        }
+        Some(interaction) = controls.next() => {
+            debug!("Recieved canel request");
+            if let Err(e) = interaction.defer(&ctx.http).await {
+                error!("Failed to defer cancel request: {:?}", e);
+            }
+            audit_cancel(&interaction.user, "rip", &rip.title, drive_number).await;
+
+            // Stops MakeMKV and unlocks the drive, the rip runs apart from this interaction
+            worker::cancel(ticket.id).await;
+
+            if let Err(e) = message
+                .clone()
+                .edit(
+                    &ctx.http,
+                    EditMessage::new().components(vec![])
+                    .embed(
+                        themed_embed()
+                            .title(tr!("Rip Cancelled"))
+                            .description(tr!("Rip cancelled!"))
+                            .field(tr!("Title"), &rip.title, true)
+                            .field(tr!("Disc Number"), drive_number.to_string(), true)
+                    )
+                )
+                .await
+            {
+                error!("Failed to send rip cancelled message: {:?}", e);
+            }
+            info!("Rip cancelled");
+            true
+        }
+    };
+
+    // If the rip was cancelled, do not send the summary message
+    if was_cancelled {
+        return Err(DiscordError::TaskCancelled);
+    }
+
+    let rip_time = now.elapsed().as_secs_f64() / 60.00;
+    let quality = probe_files(&saved_files)
+        .await
+        .map(|stats| quality_text(&stats, now.elapsed().as_secs()));
+
+    if eject_when_done {
+        eject_after_rip(drive_number, ticket.id).await;
+    }
+
+    let upload_status = upload_rips(
+        ctx,
+        message,
+        themed_embed()
+            .title(tr!("Upload Movie"))
+            .field(tr!("Title"), &rip.title, true)
+            .field(tr!("Disc Number"), drive_number.to_string(), true),
+        rip.rip_type,
+        &saved_files,
+    )
+    .await;
+
+    message
+        .clone()
+        .edit(
+            &ctx.http,
+            EditMessage::new().components(vec![]).embed(
+                themed_embed()
+                    .title(tr!("Ripped {title}", title = rip.title))
+                    .description(tr!("Rip completed!")),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip completed message: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    let summary_embed = themed_embed()
+        .title(tr!("Rip Summary"))
+        .description(finished_in(rip_time))
+        .field(tr!("Title"), &rip.title, true)
+        .field(tr!("Disc Number"), drive_number.to_string(), true)
+        .fields(rip.year.map(|year| (tr!("Year"), year.to_string(), true)))
+        .fields(
+            rip.library
+                .clone()
+                .map(|library| (tr!("Library"), library, true)),
+        )
+        .fields(disc_label.map(|label| (tr!("Disc Label"), label, true)))
+        .fields(rip.base_view.then(|| (tr!("3D"), three_d_mode(true), true)))
+        .fields(rip.note.clone().map(|note| (tr!("Rip Note"), note, false)))
+        .fields(
+            (!ripped_extras.is_empty()).then(|| (tr!("Extras"), ripped_extras.join("\n"), false)),
+        )
+        .fields(quality.map(|quality| (tr!("Quality"), quality, false)))
+        .fields(recovery.map(|recovery| (tr!("Damaged Segments"), recovery_text(recovery), false)))
+        .fields(upload_status.map(|status| (tr!("Upload"), status, false)));
+    let (summary_embed, summary_message) =
+        with_failed_titles(summary_embed, CreateMessage::new(), &failed_extras);
+    let (summary_embed, mut summary_message) =
+        with_size_warnings(summary_embed, summary_message, &suspicious);
+    if !failed_logs.is_empty() {
+        summary_message = summary_message.add_file(rip_log_attachment(failed_logs.join("\n\n")));
+    }
+
+    message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            summary_message
+                .embed(summary_embed)
+                .reference_message(message),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip summary message: {:?}", e);
+            DiscordError::SendMessageFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Runs a batch of show rips in sequence, updating the message with the current rip
+/// and allowing the user to cancel the batch.
+///
+/// The message shows how far the batch is next to the progress of the current title.
+/// While more titles are to come, the current one can be cancelled on its own, it is then
+/// listed as failed so it can be retried, and the batch carries on with the next. A title
+/// can also be skipped, when it turns out not to be wanted, the titles after it then take
+/// its episode number and the summary lists it as skipped rather than failed.
+///
+/// Titles that fail to rip do not stop the batch. The successfully ripped episodes are
+/// kept, and the summary message lists exactly which titles failed along with a
+/// `retry_failed_rips` button that re-runs only those titles. Once the batch is over, the
+/// drive is ejected if `eject_when_done` is set, unless the batch was cancelled.
+///
+/// The titles left are remembered as the batch goes, so a batch whose remaining episodes
+/// are cancelled, or that the bot stopped partway through, can be resumed, see
+/// `resume_batch`.
+pub(super) async fn run_show_rips(
+    ctx: &Context,
+    message: &Message,
+    title_name: &str,
+    drive_number: u8,
+    season: u8,
+    options: RipOptions,
+    rips: Vec<Rip>,
+) -> Result<()> {
+    let RipOptions {
+        priority,
+        eject_when_done,
+        owner,
+    } = options;
+
+    check_capabilities(drive_number).await?;
+    let makemkv = makemkv(ctx).await?;
+    let disc_label = disc_label(drive_number).await;
+
+    // The whole batch is a single job, so a disc is ripped in one go once it starts
+    let ticket = JobTicket::submit(
+        &tr!(
+            "{title} Season {season} (Disc {disc})",
+            title = title_name,
+            season = season,
+            disc = drive_number
+        ),
+        JobKind::Rip { drive_number },
+        priority,
+    )
+    .await;
+    let ticket = Arc::new(ticket);
+    // Episodes after a title split by chapters are numbered again once they start
+    for rip in &rips {
+        rip.queued();
+    }
+
+    let now = std::time::Instant::now();
+
+    let num_rips = rips.len();
+    trace!("Number of rips: {:?}", num_rips);
+
+    // Satifies rust lifetime issues
+    let mut was_cancelled = false;
+
+    // Takes the cancel and skip requests from the user, for as long as the job runs
+    let mut controls = controls::listen(ticket.id, message.id, owner);
+    let _running = batches::run(message.id.get());
+    let label = tr!(
+        "{title} Season {season}",
+        title = title_name,
+        season = season
+    );
+
+    // Keeps track of which episodes made it to disk and which titles failed
+    let mut ripped_episodes: Vec<u8> = Vec::new();
+    let mut failed_rips: Vec<(Rip, MakeMkvError)> = Vec::new();
+    let mut skipped_titles: Vec<u16> = Vec::new();
+    let mut failed_logs: Vec<String> = Vec::new();
+    let mut disconnected = false;
+    let mut saved_files = Vec::new();
+    let mut recovery: Option<HashRecovery> = None;
+    let mut suspicious: Vec<(Rip, SizeWarning)> = Vec::new();
+
+    // A title split by chapters turns into several episodes, which pushes the numbers of
+    // every title after it back, and a skipped title brings them forward again
+    let mut episode_offset: isize = 0;
+
+    // Run the rips in sequence, updating the message with the current rip
+    // and allowing the user to cancel the rip
+    // This will be a loop that will run until all rips are complete
+    // or the user cancels the rip
+    for (index, rip) in rips.iter().enumerate() {
+        // This should only fail if the rip details are invalid and also
+        // passed previous validation
+        let Some(rip) = rip.with_episode_offset(episode_offset) else {
+            warn!("Episode numbers out of range for rip, ignoring");
+            continue;
+        };
+        let Some(episode_number) = rip.episode() else {
+            warn!("No episode found for rip; very strange... ignoring");
+            continue;
+        };
+        let episode = match rip.chapters_per_episode {
+            Some(_) => tr!("Episodes {episode} onwards", episode = episode_number),
+            None => tr!("Episode {episode}", episode = episode_number),
+        };
+
+        // Everything from this title on, numbered as it will be saved
+        let remaining: Vec<Rip> = rips
+            .iter()
+            .skip(index)
+            .filter_map(|rip| rip.with_episode_offset(episode_offset))
+            .collect();
+        batches().remember(Batch {
+            channel_id: message.channel_id.get(),
+            message_id: message.id.get(),
+            label: label.clone(),
+            owner: owner.get(),
+            rips: remaining.clone(),
+        });
+
+        let ripping_embed = with_host(
+            themed_embed()
+                .title(tr!("Rip Show"))
+                .description(tr!(
+                    "Ripping {title}, {episode}... \n(Rip {number}/{total})",
+                    title = rip.title,
+                    episode = episode,
+                    number = index + 1,
+                    total = num_rips
+                ))
+                .field(tr!("Title"), &rip.title, true)
+                .field(tr!("Disc Number"), drive_number.to_string(), true)
+                .field(tr!("Season"), season.to_string(), true)
+                .field(tr!("Priority"), priority_name(priority), true)
+                .field(
+                    tr!("Overall"),
+                    batch_progress(index, num_rips, rip.chapters_per_episode.is_some()),
+                    false,
+                ),
+            drive_number,
+        );
+
+        // Cancelling only the current title is the same as cancelling it all on the last
+        let mut cancel_buttons = if index + 1 < num_rips {
+            vec![
+                CreateButton::new(custom_ids::with_id("cancel_episode", ticket.id))
+                    .label(tr!("Cancel current episode"))
+                    .style(serenity::all::ButtonStyle::Secondary),
+                CreateButton::new(custom_ids::with_id("cancel_rip", ticket.id))
+                    .label(tr!("Cancel remaining episodes"))
+                    .style(serenity::all::ButtonStyle::Danger),
+            ]
+        } else {
+            vec![
+                CreateButton::new(custom_ids::with_id("cancel_rip", ticket.id))
+                    .label(tr!("Cancel"))
+                    .style(serenity::all::ButtonStyle::Danger),
+            ]
+        };
+        if num_rips > 1 {
+            cancel_buttons.insert(
+                0,
+                CreateButton::new(custom_ids::with_id("skip_title", ticket.id))
+                    .label(tr!("Skip this title"))
+                    .style(serenity::all::ButtonStyle::Secondary),
+            );
+        }
+
+        // Edit the message to show the current rip details
+        message
+            .clone()
+            .edit(
+                &ctx.http,
+                EditMessage::new()
+                    .embed(ripping_embed.clone())
+                    .components(vec![CreateActionRow::Buttons(cancel_buttons)]),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to send rip in progress message: {:?}", e);
+                DiscordError::EditMessageFailed(e.to_string())
+            })?;
+
+        // The 'magic sauce' to the interaction collector
+        // tokio::select! will wait for either the rip to complete
+        // or the user to cancel the rip by waiting for either to
+        // reslove first
+        // The other statement will be cancelled
+        // sets the 'was_cancelled' variable to true if the user cancels
+        // the rip
+        was_cancelled = tokio::select! {
+            // Waits for the job's turn in the queue, which only takes time before
+            // the first rip, then starts the rip and waits for it to complete
+            rip_result = async {
+                wait_for_turn(ctx, message, &ticket, ripping_embed.clone()).await;
+                let position = Some((index + 1, num_rips));
+                execute_with_eta(ctx, message, &ticket, &rip, &makemkv, ripping_embed, position)
+                    .await
+            } => {
+                match rip_result {
+                    Ok(outcome) => {
+                        // A split title saves one file per episode
+                        let episodes = if rip.chapters_per_episode.is_some() {
+                            outcome.files.len().max(1)
+                        } else {
+                            1
+                        };
+                        ripped_episodes.extend(
+                            (0..episodes).filter_map(|i| {
+                                episode_number.checked_add(u8::try_from(i).ok()?)
+                            }),
+                        );
+                        episode_offset += isize::try_from(episodes - 1).unwrap_or_default();
+                        saved_files.extend(outcome.files);
+                        if let Some(warning) = outcome.size_warning {
+                            suspicious.push((rip.clone(), warning));
+                        }
+
+                        // Summed over the whole batch for the summary
+                        if let Some(retried) = outcome.recovery {
+                            let total = recovery.get_or_insert_default();
+                            total.failed_segments += retried.failed_segments;
+                            total.recovered_segments += retried.recovered_segments;
+                        }
+                    }
+                    // Any titles MakeMKV did manage to save are kept on disk, but the
+                    // episode still needs a retry to be complete
+                    Err(e) => {
+                        error!("Failed to execute rip: {:?}", e);
+                        disconnected = matches!(e, MakeMkvError::DriveDisconnected(_));
+                        if let Some(log) = logs::take(drive_number) {
+                            let title = tr!("Title {title}", title = rip.title_id);
+                            failed_logs.push(format!("{title}\n{log}"));
+                        }
+                        failed_rips.push((rip.clone(), e));
+                    }
+                }
+                false
+            }
+            // Calls on the 'next()' method to asyncronously wait for
+            // the user to cancel the rip
+            Some(interaction) = controls.next() => {
+                debug!("Recieved canel request");
+
+                // Defer the interaction to satify discord
+                interaction.defer(&ctx.http).await?;
+                let name = label.clone();
+
+                // Stops MakeMKV, and its temporary files go with it
+                worker::cancel(ticket.id).await;
+                match custom_ids::name(&interaction.data.custom_id) {
+                    "cancel_episode" => {
+                        let name = format!("{name}, {episode}");
+                        audit_cancel(&interaction.user, "rip", &name, drive_number).await;
+                        info!("Cancelled {}, moving on to the next title", name);
+                        failed_rips.push((rip.clone(), MakeMkvError::Cancelled));
+                        continue;
+                    }
+                    // The next title takes the episode this one was going to be
+                    "skip_title" => {
+                        let name = format!("{name}, {}", tr!("Title {title}", title = rip.title_id));
+                        audit_cancel(&interaction.user, "rip", &name, drive_number).await;
+                        info!("Skipped {}, moving on to the next title", name);
+                        skipped_titles.push(rip.title_id);
+                        episode_offset -= 1;
+                        continue;
+                    }
+                    _ => {}
+                }
+                audit_cancel(&interaction.user, "rip", &name, drive_number).await;
+
+                // Edit the message to show that the rip was cancelled, the titles it had
+                // left can still be picked up again
+                message
+                    .clone()
+                    .edit(
+                        &ctx.http,
+                        EditMessage::new()
+                        .components(vec![CreateActionRow::Buttons(vec![resume_button()])])
+                        .embed(
+                            themed_embed()
+                                .title(tr!("Rip Cancelled"))
+                                .description(tr!("Rip cancelled! Press Resume to rip the titles it had left."))
+                                .field(tr!("Title"), &rip.title, true)
+                                .field(tr!("Disc Number"), drive_number.to_string(), true)
+                                .field(tr!("Season"), season.to_string(), true)
+                                .field(tr!("Remaining Titles"), remaining_titles(&remaining), false)
+                        )
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send rip cancelled message: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
+                info!("Rip cancelled");
+                true
+            }
+        };
+
+        // Breaks out of rip loop if the user cancels the rip
+        if was_cancelled {
+            break;
+        }
+
+        // The rest of the titles can't be read from a drive that is gone, they are left
+        // failed so they can be retried once it is reconnected
+        if disconnected {
+            failed_rips.extend(
+                rips.iter()
+                    .skip(index + 1)
+                    .filter_map(|rip| rip.with_episode_offset(episode_offset))
+                    .map(|rip| (rip, MakeMkvError::DriveDisconnected(drive_number))),
+            );
+            break;
+        }
+    }
+
+    // If the rip was cancelled, do not send the summary message
+    if was_cancelled {
+        return Err(DiscordError::TaskCancelled);
+    }
+    // Titles that failed are retried from the summary rather than resumed
+    batches().forget(message.id.get());
+
+    let rip_time = now.elapsed().as_secs_f64() / 60.00;
+    let quality = probe_files(&saved_files)
+        .await
+        .map(|stats| quality_text(&stats, now.elapsed().as_secs()));
+
+    // Even with some failed titles, the batch is done with the disc
+    if eject_when_done && !disconnected {
+        eject_after_rip(drive_number, ticket.id).await;
+    }
+
+    let upload_status = upload_rips(
+        ctx,
+        message,
+        themed_embed()
+            .title(tr!("Upload Show"))
+            .field(tr!("Title"), title_name, true)
+            .field(tr!("Disc Number"), drive_number.to_string(), true)
+            .field(tr!("Season"), season.to_string(), true),
+        RipType::Show {
+            season,
+            episode: 0,
+            absolute_episode: None,
+        },
+        &saved_files,
+    )
+    .await;
+
+    let (status_title, status_description) = if failed_rips.is_empty() {
+        (
+            tr!("Ripped {title}", title = title_name),
+            tr!("Rips completed!"),
+        )
+    } else if ripped_episodes.is_empty() {
+        (
+            tr!("Failed to rip {title}", title = title_name),
+            tr!("All rips failed!"),
+        )
+    } else {
+        (
+            tr!("Partially ripped {title}", title = title_name),
+            tr!("Some rips failed, see the summary for details."),
+        )
+    };
+
+    // Edit the message to show that the rip was completed
+    message
+        .clone()
+        .edit(
+            &ctx.http,
+            EditMessage::new().components(vec![]).embed(
+                themed_embed()
+                    .title(status_title)
+                    .description(status_description),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip completed message: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    let summary_embed = themed_embed()
+        .title(tr!("Rip Summary"))
+        .description(finished_in(rip_time))
+        .field(tr!("Title"), title_name, true)
+        .field(tr!("Disc Number"), drive_number.to_string(), true)
+        .field(tr!("Season"), season.to_string(), true)
+        .field(tr!("Episodes"), format_episode_list(&ripped_episodes), true)
+        .fields(
+            rips.first()
+                .and_then(|rip| rip.chapters_per_episode)
+                .map(|chapters| (tr!("Chapters Per Episode"), chapters.to_string(), true)),
+        )
+        .fields(
+            rips.first()
+                .and_then(|rip| rip.library.clone())
+                .map(|library| (tr!("Library"), library, true)),
+        )
+        .fields(disc_label.map(|label| (tr!("Disc Label"), label, true)))
+        .fields(
+            rips.first()
+                .filter(|rip| rip.base_view)
+                .map(|_| (tr!("3D"), three_d_mode(true), true)),
+        )
+        .fields(
+            rips.first()
+                .and_then(|rip| rip.note.clone())
+                .map(|note| (tr!("Rip Note"), note, false)),
+        )
+        .fields((!skipped_titles.is_empty()).then(|| {
+            (
+                tr!("Skipped Titles"),
+                skipped_titles
+                    .iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                true,
+            )
+        }))
+        .fields(quality.map(|quality| (tr!("Quality"), quality, false)))
+        .fields(recovery.map(|recovery| (tr!("Damaged Segments"), recovery_text(recovery), false)))
+        .fields(upload_status.map(|status| (tr!("Upload"), status, false)));
+
+    let (summary_embed, summary_message) =
+        with_failed_titles(summary_embed, CreateMessage::new(), &failed_rips);
+    let (summary_embed, mut summary_message) =
+        with_size_warnings(summary_embed, summary_message, &suspicious);
+
+    // One file for every title that failed, a summary can't have many attachments
+    if !failed_logs.is_empty() {
+        summary_message = summary_message.add_file(rip_log_attachment(failed_logs.join("\n\n")));
+    }
+
+    // Send a summary message to the channel with the rip details
+    // This will send a push notification to the user
+    message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            summary_message
+                .embed(summary_embed)
+                .reference_message(message),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip summary message: {:?}", e);
+            DiscordError::SendMessageFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Uploads the files saved by a rip to the remote configured for its library, editing
+/// `embed` into the message with the progress as it goes.
+///
+/// Returns a line for the summary describing how the upload went, or `None` if uploads
+/// aren't enabled for the library or nothing was saved. A failed upload keeps the local
+/// copy, so it only shows up in the summary.
+async fn upload_rips(
+    ctx: &Context,
+    message: &Message,
+    embed: CreateEmbed,
+    rip_type: RipType,
+    files: &[std::path::PathBuf],
+) -> Option<String> {
+    let remote = UPLOADER.lock().await.remote_for(rip_type)?;
+    if files.is_empty() {
+        return None;
+    }
+
+    // Files keep their path within the library they were saved to
+    let makemkv = makemkv(ctx).await.ok()?;
+    let root = makemkv
+        .library_root(files.first().and_then(|file| makemkv.library_of(file)))
+        .to_path_buf();
+
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel::<UploadProgress>();
+    let upload = Uploader::upload(&root, files, &remote, &progress_tx);
+    tokio::pin!(upload);
+
+    let mut progress = tr!("Starting upload to {remote}...", remote = remote);
+    let result = loop {
+        if let Err(e) = message
+            .clone()
+            .edit(
+                &ctx.http,
+                EditMessage::new()
+                    .components(vec![])
+                    .embed(embed.clone().description(&progress)),
+            )
+            .await
+        {
+            // Progress is only informative, the upload carries on regardless
+            warn!("Failed to send upload progress message: {:?}", e);
+        }
+
+        tokio::select! {
+            result = &mut upload => break result,
+            Some(update) = progress_rx.recv() => {
+                progress = tr!(
+                    "Uploading to {remote} (File {file}/{files})\n{stats}",
+                    remote = remote,
+                    file = update.file,
+                    files = update.files,
+                    stats = update.stats
+                );
+            }
+        }
+    };
+
+    Some(match result {
+        Ok(()) => tr!("Uploaded to {remote}", remote = remote),
+        Err(e) => {
+            error!("Failed to upload rip: {:?}", e);
+            tr!(
+                "Failed to upload to {remote}: {error}",
+                remote = remote,
+                error = e
+            )
+        }
+    })
+}
+
+/// Tells the user `MakeMKV` found no video titles on the disc, offering to archive it as
+/// a data disc instead.
+async fn no_titles_found(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    drive_number: u8,
+) -> Result<()> {
+    edit_message(
+        ctx,
+        token,
+        message,
+        EditMessage::new()
+            .components(vec![])
+            .embed(
+                themed_embed()
+                    .title(tr!("Rip Failed"))
+                    .description(tr!(
+                        "No video titles found on this disc. If it is a data disc, it can \
+                             be archived as a disc image instead."
+                    ))
+                    .field(tr!("Disc Number"), drive_number.to_string(), true),
+            )
+            .button(
+                // This will call the archive_disc component interaction, asking
+                // for a label to archive the disc under
+                CreateButton::new("archive_disc")
+                    .label(tr!("Archive Disc"))
+                    .style(serenity::all::ButtonStyle::Primary),
+            ),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to send no titles found message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    Err(DiscordError::reported(DiscordError::Unexpected(
+        "No titles found for disc number".to_string(),
+    )))
+}
+
+/// Formats a list of episode numbers into a compact string of ranges, e.g. `1-3, 5`
+pub(super) fn format_episode_list(episodes: &[u8]) -> String {
+    if episodes.is_empty() {
+        return tr!("None");
+    }
+
+    let mut episodes = episodes.to_vec();
+    episodes.sort_unstable();
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut start = episodes[0];
+    let mut end = episodes[0];
+
+    for &episode in &episodes[1..] {
+        if episode == end + 1 {
+            end = episode;
+        } else {
+            ranges.push(format_episode_range(start, end));
+            start = episode;
+            end = episode;
+        }
+    }
+    ranges.push(format_episode_range(start, end));
+
+    ranges.join(", ")
+}
+
+fn format_episode_range(start: u8, end: u8) -> String {
+    if start == end {
+        start.to_string()
+    } else {
+        format!("{start}-{end}")
+    }
+}
+
+/// Formats the title a rip reads from and the episode it fills, e.g.
+/// `Title {id}: Episode {episode} (Absolute {absolute})`. Movies only have the title, and
+/// the name of the extra if they are one.
+pub(super) fn format_title_episode(rip: &Rip) -> String {
+    match rip.rip_type {
+        RipType::Show {
+            episode,
+            absolute_episode: Some(absolute),
+            ..
+        } => tr!(
+            TITLE_ABSOLUTE_EPISODE,
+            title = rip.title_id,
+            episode = episode,
+            absolute = absolute
+        ),
+        RipType::Show { episode, .. } => {
+            tr!(TITLE_EPISODE, title = rip.title_id, episode = episode)
+        }
+        RipType::Movie => match &rip.extra {
+            Some(name) => tr!(TITLE_EXTRA, title = rip.title_id, name = name),
+            None => tr!("Title {title}", title = rip.title_id),
+        },
+    }
+}
+
+/// Reads back the titles of a movie rip listed by `format_title_episode`, along with the
+/// names of those that are extras
+fn parse_movie_titles(value: &str) -> Vec<(u16, Option<String>)> {
+    value
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(values) = untranslate(TITLE_EXTRA, line) {
+                return Some((
+                    values["title"].trim().parse().ok()?,
+                    Some(values["name"].clone()),
+                ));
+            }
+            let values = untranslate("Title {title}", line)?;
+            Some((values["title"].trim().parse().ok()?, None))
+        })
+        .collect()
+}
+
+/// Reads back the `Title {id}: Episode {episode} (Absolute {absolute})` lines written to
+/// the confirmation and summary embeds, the absolute episode is only present for anime
+/// style numbering
+fn parse_title_episodes(value: &str) -> Vec<(u16, u8, Option<u16>)> {
+    value
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let values = untranslate(TITLE_ABSOLUTE_EPISODE, line)
+                .or_else(|| untranslate(TITLE_EPISODE, line))?;
+            Some((
+                values["title"].trim().parse().ok()?,
+                values["episode"].trim().parse().ok()?,
+                values
+                    .get("absolute")
+                    .and_then(|absolute| absolute.trim().parse().ok()),
+            ))
+        })
+        .collect()
+}
+
+/// Lists the names of the episodes of show rips, e.g. `7. The One with the Racecar Bed`,
+/// for the confirmation embed. `None` if none of them are named. Names that don't fit in
+/// an embed field are left off.
+pub(super) fn format_episode_titles(rips: &[Rip]) -> Option<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut length = 0;
+    for rip in rips {
+        let (Some(episode), Some(name)) = (rip.episode(), &rip.episode_title) else {
+            continue;
+        };
+        let line = format!("{episode}. {name}");
+        if length + line.len() + 1 > MAX_FIELD_LENGTH {
+            warn!("Too many episode names to show, leaving off {}", line);
+            continue;
+        }
+        length += line.len() + 1;
+        lines.push(line);
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Reads back the `{episode}. {name}` lines written by `format_episode_titles`
+pub(super) fn parse_episode_titles(value: &str) -> Vec<(u8, String)> {
+    value
+        .lines()
+        .filter_map(|line| {
+            let (episode, name) = line.trim().split_once(". ")?;
+            Some((episode.parse().ok()?, name.to_string()))
+        })
+        .collect()
+}
+
+/// Names each show rip from `names`, by the episode it fills. Rips of split titles aren't
+/// named, since which episodes they hold isn't known until they are ripped.
+fn name_episodes(rips: &mut [Rip], names: &[(u8, String)]) {
+    for rip in rips {
+        if rip.chapters_per_episode.is_some() {
+            rip.episode_title = None;
+            continue;
+        }
+        if rip.episode_title.is_none() {
+            rip.episode_title = names
+                .iter()
+                .find(|(episode, _)| Some(*episode) == rip.episode())
+                .map(|(_, name)| name.clone());
+        }
+    }
+}
+
+/// Reads the episode names back from the embed of a confirmation message
+fn embed_episode_titles(embed: Option<&Embed>) -> Vec<(u8, String)> {
+    field(embed, "Episode Titles")
+        .map(parse_episode_titles)
+        .unwrap_or_default()
+}
+
+/// The rip type step of the `rip` command, showing `embed` with a button for each type
+/// of rip
+pub(super) fn rip_type_message(embed: CreateEmbed) -> EditMessage {
+    EditMessage::new()
+        .embed(embed)
+        .button(
+            // This will call the movie_rip component interaction
+            // Prompting the user to input a title, filled in if the barcode was looked up
+            CreateButton::new("movie_rip")
+                .label(tr!("Rip Movie"))
+                .style(serenity::all::ButtonStyle::Primary),
+        )
+        .button(
+            // This will call the show_rip component interaction
+            // Prompting the user to input a title and season
+            CreateButton::new("show_rip")
+                .label(tr!("Rip Show"))
+                .style(serenity::all::ButtonStyle::Primary),
+        )
+        .button(
+            // This will call the audio_rip component interaction,
+            // which looks the disc up on MusicBrainz
+            CreateButton::new("audio_rip")
+                .label(tr!("Rip Audio CD"))
+                .style(serenity::all::ButtonStyle::Primary),
+        )
+        .button(
+            // Opens a modal for the barcode on the case, for discs with useless labels
+            CreateButton::new("lookup_barcode")
+                .label(tr!("Look Up Barcode"))
+                .style(serenity::all::ButtonStyle::Secondary),
+        )
+}
+
+/// The modal asking for the details of a movie rip. When editing a rip, the inputs are
+/// filled in from the fields of `current`.
+fn movie_modal(drive_number: u8, current: Option<&Embed>) -> CreateModal {
+    CreateModal::new(
+        "get_title_of_movie_rip",
+        tr!("Please enter the title of the movie"),
+    )
+    .components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, tr!("Disc Number"), "disc_number")
+                .value(drive_number.to_string())
+                .required(true),
+        ),
+        CreateActionRow::InputText(prefill(
+            CreateInputText::new(InputTextStyle::Short, tr!("Movie Title"), "title_of_movie")
+                .required(true),
+            current,
+            "Title",
+        )),
+        // Optional, but media servers match movies far
+        // better with a 'Title (Year)' folder
+        CreateActionRow::InputText(prefill(
+            CreateInputText::new(InputTextStyle::Short, tr!("Year"), "year_of_movie")
+                .min_length(4)
+                .max_length(4)
+                .required(false),
+            current,
+            "Year",
+        )),
+        priority_input(current),
+    ])
+}
+
+/// The modal asking for a name for each extra picked along with a movie. The longest of
+/// the `selected` titles is taken to be the movie, extras are rarely longer, and is
+/// carried in the modal's custom id.
+async fn extras_modal(drive_number: u8, selected: &[u16]) -> CreateModal {
+    let lengths: HashMap<u16, String> = SCANNED_TITLES
+        .lock()
+        .await
+        .get(&drive_number)
+        .map(|disc_info| {
+            disc_info
+                .titles
+                .iter()
+                .map(|title| (title.title_id, title.length.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let length = |title_id: &u16| {
+        lengths
+            .get(title_id)
+            .and_then(|length| parse_length(length))
+    };
+
+    // Without the scan to go on, the first title picked is the movie
+    let movie = selected
+        .iter()
+        .rev()
+        .max_by_key(|title_id| length(title_id))
+        .copied()
+        .unwrap_or_default();
+
+    let inputs = selected
+        .iter()
+        .filter(|title_id| **title_id != movie)
+        .map(|title_id| {
+            let mut input = CreateInputText::new(
+                InputTextStyle::Short,
+                tr!("Name of Title {title}", title = title_id),
+                format!("extra_{title_id}"),
+            )
+            .required(true);
+            if let Some(length) = lengths.get(title_id) {
+                input = input.placeholder(tr!("{length} long, e.g. Making Of", length = length));
+            }
+            CreateActionRow::InputText(input)
+        })
+        .collect();
+
+    CreateModal::new(
+        custom_ids::with_id("get_extra_names", movie),
+        tr!("Name the extras of Title {title}", title = movie),
+    )
+    .components(inputs)
+}
+
+/// The modal asking for the details of a show rip. When editing a rip, the inputs are
+/// filled in from the fields of `current`, otherwise from the disc's label if it names the
+/// show and season.
+fn show_modal(drive_number: u8, current: Option<&Embed>) -> CreateModal {
+    let guess = field(current, "Disc Label").and_then(parse_disc_label);
+    let title = CreateInputText::new(InputTextStyle::Short, tr!("Show Title"), "title_of_show")
+        .required(true);
+    let season = CreateInputText::new(
+        InputTextStyle::Short,
+        tr!("Season (0 for specials)"),
+        "season",
+    )
+    .required(true);
+    let (title, season) = match guess {
+        Some(guess) => (
+            title.value(guess.show),
+            season.value(guess.season.to_string()),
+        ),
+        None => (title, season),
+    };
+
+    CreateModal::new(
+        "get_title_of_show_rip",
+        tr!("Please enter the title & season"),
+    )
+    .components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, tr!("Disc Number"), "disc_number")
+                .value(drive_number.to_string())
+                .required(true),
+        ),
+        CreateActionRow::InputText(prefill(title, current, "Title")),
+        CreateActionRow::InputText(prefill(season, current, "Season")),
+        // Only needed for anime style libraries that number
+        // episodes across every season
+        CreateActionRow::InputText(prefill(
+            CreateInputText::new(
+                InputTextStyle::Short,
+                tr!("Absolute Episode Start (optional)"),
+                "absolute_episode",
+            )
+            .placeholder(tr!("e.g. 25 for the first episode of season 2"))
+            .required(false),
+            current,
+            "Absolute Start",
+        )),
+        priority_input(current),
+    ])
+}
+
+/// The optional priority input shared by the rip modals
+pub(super) fn priority_input(current: Option<&Embed>) -> CreateActionRow {
+    CreateActionRow::InputText(prefill(
+        CreateInputText::new(
+            InputTextStyle::Short,
+            tr!("Priority (low, normal or high)"),
+            "priority",
+        )
+        .placeholder(tr!("normal"))
+        .required(false),
+        current,
+        "Priority",
+    ))
+}
+
+/// Reads the value of a field of the rip message's embed by its English name
+pub(super) fn field<'a>(embed: Option<&'a Embed>, name: &str) -> Option<&'a str> {
+    embed?
+        .fields
+        .iter()
+        .find(|field| field.name == tr!(name))
+        .map(|field| field.value.as_str())
+}
+
+/// The menu to pick a preset from at the start of a rip, `None` when there are none
+async fn preset_menu() -> Option<CreateSelectMenu> {
+    let settings = SETTINGS.lock().await;
+    if settings.presets.is_empty() {
+        return None;
+    }
+
+    let options = std::iter::once(CreateSelectMenuOption::new(tr!("No preset"), "no_preset"))
+        .chain(settings.presets.iter().enumerate().map(|(i, preset)| {
+            let name: String = preset.name.chars().take(100).collect();
+            CreateSelectMenuOption::new(name, format!("preset_{i}"))
+        }))
+        .collect();
+
+    Some(
+        CreateSelectMenu::new(
+            "select_rip_preset",
+            CreateSelectMenuKind::String { options },
+        )
+        .placeholder(tr!("Rip preset (optional)")),
+    )
+}
+
+/// Reads the preset picked at the start of a rip back from its message, `None` when none
+/// was picked or it has since been removed from the settings
+pub(super) async fn embed_preset(embed: Option<&Embed>) -> Option<RipPreset> {
+    let name = field(embed, "Preset")?;
+    let preset = SETTINGS.lock().await.preset(name).cloned();
+    if preset.is_none() {
+        warn!("Preset {} no longer exists, ignoring it", name);
+    }
+    preset
+}
+
+/// The field naming the preset of a rip, kept on its message for the steps after
+fn preset_field(preset: Option<&RipPreset>) -> Option<(String, String, bool)> {
+    preset.map(|preset| (tr!("Preset"), preset.name.clone(), false))
+}
+
+/// The fields the rip type message gets from the preset picked on `current`. High priority
+/// is added as a field, so the rip modals are filled in with it.
+pub(super) async fn preset_fields(current: Option<&Embed>) -> Vec<(String, String, bool)> {
+    let Some(preset) = embed_preset(current).await else {
+        return Vec::new();
+    };
+
+    let mut fields = Vec::new();
+    if preset.high_priority {
+        fields.push((tr!("Priority"), priority_name(Priority::High), true));
+    }
+    fields.extend(preset_field(Some(&preset)));
+    fields
+}
+
+/// Leaves out the titles shorter than the preset's minimum length, unless none of them
+/// are long enough. Returns the titles along with the length they were filtered to.
+fn preset_titles(titles: Vec<Title>, preset: Option<&RipPreset>) -> (Vec<Title>, u32) {
+    let Some(min_minutes) = preset.and_then(|preset| preset.min_minutes) else {
+        return (titles, 0);
+    };
+
+    let long_enough: Vec<Title> = titles
+        .iter()
+        .filter(|title| {
+            parse_length(&title.length).is_none_or(|seconds| seconds >= min_minutes * 60)
+        })
+        .cloned()
+        .collect();
+    if long_enough.is_empty() {
+        debug!(
+            "No titles are over {} minutes, listing them all",
+            min_minutes
+        );
+        (titles, 0)
+    } else {
+        (long_enough, min_minutes)
+    }
+}
+
+/// Reads the disc number back from the embed of a rip message
+pub(super) fn embed_drive_number(embed: Option<&Embed>) -> Result<u8> {
+    field(embed, "Disc Number")
+        .and_then(|drive_number| drive_number.parse().ok())
+        .ok_or_else(|| {
+            warn!("Failed to parse disc number from message, ignoring");
+            DiscordError::Unexpected("Failed to parse disc number".to_string())
+        })
+}
+
+/// Reads the title back from the embed of a rip message
+pub(super) fn embed_title(embed: Option<&Embed>) -> Result<String> {
+    field(embed, "Title").map(str::to_string).ok_or_else(|| {
+        warn!("Failed to read title from message, ignoring");
+        DiscordError::InvalidComponentData
+    })
+}
+
+/// Reads the season back from the embed of a show rip message
+pub(super) fn embed_season(embed: Option<&Embed>) -> Result<u8> {
+    field(embed, "Season")
+        .and_then(|season| season.parse().ok())
+        .ok_or_else(|| {
+            warn!("Failed to parse season from message, ignoring");
+            DiscordError::InvalidComponentData
+        })
+}
+
+/// Fills an input in with the value of a field of `current`, if it has one. `field` is
+/// the English name of the field, which is translated to find it
+pub(super) fn prefill(
+    input: CreateInputText,
+    current: Option<&Embed>,
+    field: &str,
+) -> CreateInputText {
+    match current.and_then(|embed| embed.fields.iter().find(|f| f.name == tr!(field))) {
+        Some(field) => input.value(&field.value),
+        None => input,
+    }
+}
+
+/// Reads the priority from a submitted modal, an empty input is normal priority
+pub(super) fn parse_priority(modal: &ModalInteraction) -> Result<Priority> {
+    get_modal_input(modal, "priority").map_or(Ok(Priority::Normal), |priority| {
+        priority_from_name(&priority).ok_or_else(|| {
+            warn!("Failed to parse priority from modal, ignoring");
+            DiscordError::InvalidComponentData
+        })
+    })
+}
+
+/// Adds the agent a drive is on to a progress embed, drives on this machine aren't marked
+pub(super) fn with_host(embed: CreateEmbed, drive_number: u8) -> CreateEmbed {
+    match drive_location(drive_number).0 {
+        Some(host) => embed.field(tr!("Host"), host, true),
+        None => embed,
+    }
+}
+
+/// The name of a priority in the configured locale
+pub(super) fn priority_name(priority: Priority) -> String {
+    tr!(&priority.to_string())
+}
+
+/// Reads a priority from its name, in the configured locale or English
+fn priority_from_name(name: &str) -> Option<Priority> {
+    [Priority::Low, Priority::Normal, Priority::High]
+        .into_iter()
+        .find(|priority| priority_name(*priority).eq_ignore_ascii_case(name.trim()))
+        .or_else(|| name.parse().ok())
+}
+
+/// Explains to the user that a disc hides its main feature among decoy playlists, and how
+/// far the suggested title can be trusted
+fn obfuscation_warning(obfuscation: &Obfuscation) -> String {
+    let intro = tr!(
+        "{count} titles share the same duration, this disc likely hides the movie among decoy playlists.",
+        count = obfuscation.decoys.len()
+    );
+
+    let advice = match (obfuscation.suggested, obfuscation.confident) {
+        (Some(title_id), true) => tr!(
+            "Title {title} plays its segments in order and is most likely the movie.",
+            title = title_id
+        ),
+        (Some(title_id), false) => tr!(
+            "Title {title} is the best guess, but it is a close call. Check the previews, or \
+             the MakeMKV forums for this disc, before ripping.",
+            title = title_id
+        ),
+        (None, _) => tr!(
+            "MakeMKV didn't report enough to pick one, check the previews, or the MakeMKV \
+             forums for this disc, before ripping."
+        ),
+    };
+
+    format!("{intro} {advice}")
+}
+
+/// Reads the number of chapters per episode back from the embed of a show rip message,
+/// `None` if the titles aren't being split
+fn embed_chapters_per_episode(embed: &Embed) -> Option<u16> {
+    embed
+        .fields
+        .iter()
+        .find(|field| field.name == tr!("Chapters Per Episode"))
+        .and_then(|field| field.value.parse().ok())
+}
+
+/// Reads the named library back from the embed of a rip message, `None` for the output
+/// directory
+fn embed_library(embed: &Embed) -> Option<String> {
+    embed
+        .fields
+        .iter()
+        .find(|field| field.name == tr!("Library"))
+        .map(|field| field.value.clone())
+        .filter(|library| library != DEFAULT_LIBRARY)
+}
+
+/// Reads the note the user attached to a rip back from the embed of a rip message
+fn embed_note(embed: &Embed) -> Option<String> {
+    field(Some(embed), "Rip Note").map(str::to_string)
+}
+
+/// Reads whether only the 2D base view of 3D titles is kept back from the embed of a rip
+/// message
+fn embed_base_view(embed: &Embed) -> bool {
+    field(Some(embed), "3D") == Some(tr!("2D Only").as_str())
+}
+
+/// How 3D titles are ripped, as shown on the confirmation and summaries
+fn three_d_mode(base_view: bool) -> String {
+    if base_view {
+        tr!("2D Only")
+    } else {
+        tr!("Keep 3D")
+    }
+}
+
+/// Whether any of the titles of `rips` is 3D, as the disc scan found
+async fn has_3d_titles(rips: &[Rip]) -> bool {
+    let Some(first) = rips.first() else {
+        return false;
+    };
+    SCANNED_TITLES
+        .lock()
+        .await
+        .get(&first.drive_number)
+        .is_some_and(|disc_info| {
+            disc_info
+                .titles
+                .iter()
+                .any(|title| title.three_d && rips.iter().any(|rip| rip.title_id == title.title_id))
+        })
+}
+
+/// The label of the disc in a drive, as it was last read, for the summary of its rips
+async fn disc_label(drive_number: u8) -> Option<String> {
+    DISC_LABELS
+        .lock()
+        .await
+        .get(&drive_number)
+        .cloned()
+        .filter(|label| !label.is_empty())
+}
+
+/// Reads the rips back from the embed of a confirmation message, which holds everything
+/// needed to start them
+pub(super) fn embed_rips(embed: &Embed) -> Result<Vec<Rip>> {
+    let title_name = embed_title(Some(embed))?;
+    let drive_number = embed_drive_number(Some(embed))?;
+    let selected_titles = field(Some(embed), "Selected Titles").unwrap_or_default();
+
+    let mut rips = if embed.title.as_deref() == Some(tr!(CONFIRM_MOVIE_TITLE).as_str()) {
+        embed_movie_rips(embed, &title_name, drive_number, selected_titles)?
+    } else {
+        let season = embed_season(Some(embed))?;
+
+        show_rips(
+            &title_name,
+            drive_number,
+            season,
+            parse_title_episodes(selected_titles),
+            embed_chapters_per_episode(embed),
+        )
+    };
+
+    if rips.is_empty() {
+        warn!("No selected titles found on confirmation message, ignoring");
+        return Err(DiscordError::InvalidComponentData);
+    }
+
+    let library = embed_library(embed);
+    let note = embed_note(embed);
+    let base_view = embed_base_view(embed);
+    for rip in &mut rips {
+        rip.library.clone_from(&library);
+        rip.note.clone_from(&note);
+        rip.base_view = base_view;
+    }
+    name_episodes(&mut rips, &embed_episode_titles(Some(embed)));
+    Ok(rips)
+}
+
+/// Reads the rips of a movie and its extras back from a message listing their titles in
+/// `selected_titles`, the movie first
+fn embed_movie_rips(
+    embed: &Embed,
+    title_name: &str,
+    drive_number: u8,
+    selected_titles: &str,
+) -> Result<Vec<Rip>> {
+    let titles = parse_movie_titles(selected_titles);
+    if titles.is_empty() {
+        warn!("Failed to parse selected title from message, ignoring");
+        return Err(DiscordError::InvalidComponentData);
+    }
+
+    let year: Option<u16> = embed
+        .fields
+        .iter()
+        .find(|field| field.name == tr!("Year"))
+        .and_then(|field| field.value.parse().ok());
+
+    Ok(titles
+        .into_iter()
+        .map(|(title_id, extra)| movie_rip(title_name, drive_number, title_id, year, extra))
+        .collect())
+}
+
+/// A rip of the movie `title_name`, or of one of its extras when `extra` is named
+fn movie_rip(
+    title_name: &str,
+    drive_number: u8,
+    title_id: u16,
+    year: Option<u16>,
+    extra: Option<String>,
+) -> Rip {
+    Rip {
+        title: title_name.to_string(),
+        drive_number,
+        rip_type: RipType::Movie,
+        title_id,
+        year,
+        chapters_per_episode: None,
+        library: None,
+        episode_title: None,
+        note: None,
+        extra,
+        base_view: false,
+    }
+}
+
+/// Reads the rips that failed back from the message they failed on, either a failed movie
+/// rip or the summary of a show's rips listing its failed titles. The titles are ripped
+/// again into the same episodes and library they were meant for.
+pub(super) fn failed_rips(embed: &Embed) -> Result<Vec<Rip>> {
+    if embed.title.as_deref() == Some(tr!("Rip Failed").as_str()) {
+        listed_rips(embed, "Selected Titles")
+    } else {
+        listed_rips(embed, "Failed Titles")
+    }
+}
+
+/// Reads the rips that came out suspiciously small back from the summary listing them, to
+/// be ripped again into the same episodes and library
+pub(super) fn suspicious_rips(embed: &Embed) -> Result<Vec<Rip>> {
+    listed_rips(embed, "Suspicious Titles")
+}
+
+/// Reads back the rips listed in the `list` field of a rip message, the title of a movie or
+/// the titles of a show along with the episodes they fill
+fn listed_rips(embed: &Embed, list: &str) -> Result<Vec<Rip>> {
+    let field = |name: &str| {
+        embed
+            .fields
+            .iter()
+            .find(|field| field.name == tr!(name))
+            .map(|field| field.value.as_str())
+    };
+    let (Some(title_name), Some(drive_number)) = (field("Title"), field("Disc Number")) else {
+        warn!("Message has no failed rip on it, ignoring");
+        return Err(DiscordError::InvalidComponentData);
+    };
+    let drive_number: u8 = drive_number.parse().map_err(|_| {
+        warn!("Failed to parse disc number from message, ignoring");
+        DiscordError::Unexpected("Failed to parse disc number".to_string())
+    })?;
+
+    // Only shows have a season
+    let mut rips = match field("Season") {
+        None => embed_movie_rips(
+            embed,
+            title_name,
+            drive_number,
+            field(list).unwrap_or_default(),
+        )?,
+        Some(season) => {
+            let season: u8 = season.parse().map_err(|_| {
+                warn!("Failed to parse season from message, ignoring");
+                DiscordError::InvalidComponentData
+            })?;
+            let titles = field(list).map(parse_title_episodes).unwrap_or_default();
+
+            show_rips(
+                title_name,
+                drive_number,
+                season,
+                titles,
+                embed_chapters_per_episode(embed),
+            )
+        }
+    };
+
+    if rips.is_empty() {
+        warn!("No titles found in {} on message, ignoring", list);
+        return Err(DiscordError::InvalidComponentData);
+    }
+
+    let library = embed_library(embed);
+    let note = embed_note(embed);
+    let base_view = embed_base_view(embed);
+    for rip in &mut rips {
+        rip.library.clone_from(&library);
+        rip.note.clone_from(&note);
+        rip.base_view = base_view;
+    }
+    Ok(rips)
+}
+
+/// Moves the files of rips that came out suspiciously small to the trash, like deleting
+/// them with `/delete_rip`, so they can be ripped again. Only rips the history flagged as
+/// suspicious are touched, anything else is left for the rip to run into.
+async fn discard_suspicious(ctx: &Context, rips: &[Rip]) -> Result<()> {
+    let makemkv = makemkv(ctx).await?;
+    let mut history = HISTORY.lock().await;
+
+    for rip in rips {
+        // Titles saved as several files are found by their first part
+        let destination_path = makemkv.destination_path(rip);
+        let Some(id) = history
+            .find_file(&destination_path)
+            .or_else(|| history.find_file(&part_path(&destination_path, 1)))
+            .filter(|entry| entry.suspicious)
+            .map(|entry| entry.id)
+        else {
+            debug!(
+                "No suspicious rip saved at {}, nothing to discard",
+                destination_path.display()
+            );
+            continue;
+        };
+
+        let root = makemkv.library_root(rip.library.as_deref()).to_path_buf();
+        let entry = history.delete(id, &root).await?;
+        info!("Discarded {} to rip it again", entry.label());
+
+        let mut library = LIBRARY.lock().await;
+        for file in &entry.files {
+            library.remove(file);
+        }
+    }
+
+    Ok(())
+}
+
+/// Rips titles that failed again on `message` for `owner`, through the queue like any
+/// other rip
+pub(super) async fn retry_rips(
+    ctx: &Context,
+    message: &Message,
+    mut rips: Vec<Rip>,
+    owner: UserId,
+) -> Result<()> {
+    let Some(first) = rips.first() else {
+        return Err(DiscordError::InvalidComponentData);
+    };
+    let (title_name, drive_number, rip_type) =
+        (first.title.clone(), first.drive_number, first.rip_type);
+
+    if let Err(e) = HISTORY
+        .lock()
+        .await
+        .record_drive_retries(drive_number, rips.len() as u64)
+    {
+        warn!("Failed to record drive retries in history: {}", e);
+    }
+
+    let options = RipOptions {
+        priority: Priority::Normal,
+        eject_when_done: makemkv(ctx).await?.eject_after_rip,
+        owner,
+    };
+
+    match rip_type {
+        RipType::Movie => {
+            let rip = rips.remove(0);
+            run_movie_rip(ctx, message, rip, rips, options).await
+        }
+        RipType::Show { season, .. } => {
+            run_show_rips(
+                ctx,
+                message,
+                &title_name,
+                drive_number,
+                season,
+                options,
+                rips,
+            )
+            .await
+        }
+    }
+}
+
+/// The button that resumes a batch of show rips that stopped partway
+pub fn resume_button() -> CreateButton {
+    CreateButton::new("resume_rips")
+        .label(tr!("Resume"))
+        .style(serenity::all::ButtonStyle::Primary)
+}
+
+/// Lists the titles a batch has left and the episodes they fill, one per line
+#[must_use]
+pub fn remaining_titles(rips: &[Rip]) -> String {
+    rips.iter()
+        .map(format_title_episode)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Resumes the batch of show rips shown on `message` for `user`, from where it stopped.
+///
+/// Titles that are already saved, such as one that finished just as the bot stopped, are
+/// left out, the rest are ripped into the episodes they were going to fill. The batch
+/// carries on in the same message, under whoever started it.
+pub(super) async fn resume_batch(
+    ctx: &Context,
+    message: &Message,
+    user: &User,
+    member: Option<&Member>,
+) -> Result<()> {
+    let batch = {
+        let batches = batches();
+        if batches.is_running(message.id.get()) {
+            return Err(DiscordError::InvalidInput(tr!(
+                "This rip is already running."
+            )));
+        }
+        batches.get(message.id.get()).cloned()
+    }
+    .ok_or_else(|| DiscordError::InvalidInput(tr!("This rip has nothing left to resume.")))?;
+
+    if batch.owner != user.id.get() && !is_admin(member) {
+        warn!("{} tried to resume a rip that isn't theirs", user.name);
+        return Err(DiscordError::NotOwner);
+    }
+
+    // Starting a rip with a nearly full disk would only fail partway through
+    if DISK_MONITOR.lock().await.is_blocked() {
+        warn!("Refusing to resume a rip, disk space is critically low");
+        return Err(DiscordError::InvalidInput(tr!(
+            "New rips are blocked until more space is freed."
+        )));
+    }
+
+    let makemkv = makemkv(ctx).await?;
+    let (saved, rips): (Vec<Rip>, Vec<Rip>) = batch
+        .rips
+        .into_iter()
+        .partition(|rip| makemkv.is_ripped(rip));
+    if !saved.is_empty() {
+        debug!(
+            "Leaving out {} title(s) of {} that are already saved",
+            saved.len(),
+            batch.label
+        );
+    }
+
+    let Some(first) = rips.first() else {
+        info!("Every title of {} is already saved", batch.label);
+        batches().forget(message.id.get());
+        message
+            .clone()
+            .edit(
+                &ctx.http,
+                EditMessage::new().components(vec![]).embed(
+                    themed_embed()
+                        .title(tr!("Nothing to Resume"))
+                        .description(tr!(
+                            "Every title of {label} is already saved.",
+                            label = batch.label
+                        )),
+                ),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to send nothing to resume message: {:?}", e);
+                DiscordError::EditMessageFailed(e.to_string())
+            })?;
+        return Ok(());
+    };
+    let RipType::Show { season, .. } = first.rip_type else {
+        warn!("Batch of {} isn't a show, ignoring", batch.label);
+        return Err(DiscordError::InvalidComponentData);
+    };
+    let (title_name, drive_number) = (first.title.clone(), first.drive_number);
+
+    info!(
+        "{} is resuming {} with {} title(s) left",
+        user.name,
+        batch.label,
+        rips.len()
+    );
+    let options = RipOptions {
+        priority: Priority::Normal,
+        eject_when_done: makemkv.eject_after_rip,
+        owner: UserId::new(batch.owner),
+    };
+    run_show_rips(
+        ctx,
+        message,
+        &title_name,
+        drive_number,
+        season,
+        options,
+        rips,
+    )
+    .await
+}
+
+/// The menu the library a rip is saved to is picked from, with `selected` picked
+fn library_menu(makemkv: &MakeMkv, selected: Option<&str>) -> CreateSelectMenu {
+    let options = std::iter::once(DEFAULT_LIBRARY)
+        .chain(makemkv.libraries.iter().map(|(name, _)| name.as_str()))
+        .take(25)
+        .map(|name| {
+            CreateSelectMenuOption::new(name, name)
+                .description(
+                    makemkv
+                        .library_root(Some(name))
+                        .display()
+                        .to_string()
+                        .chars()
+                        .take(MAX_OPTION_DESCRIPTION_LENGTH)
+                        .collect::<String>(),
+                )
+                .default_selection(name == selected.unwrap_or(DEFAULT_LIBRARY))
+        })
+        .collect();
+
+    CreateSelectMenu::new("select_library", CreateSelectMenuKind::String { options })
+        .placeholder(tr!("Library to save to"))
+}
+
+/// Reads the priority back from the embed of a rip message, defaulting to normal
+pub(super) fn embed_priority(message: &Message) -> Priority {
+    message
+        .embeds
+        .first()
+        .and_then(|embed| {
+            embed
+                .fields
+                .iter()
+                .find(|field| field.name == tr!("Priority"))
+        })
+        .and_then(|field| priority_from_name(&field.value))
+        .unwrap_or_default()
+}
+
+/// Waits for a job's turn in the queue, showing how many jobs are ahead of it in the
+/// message until it starts. Once started, the message is set back to `ripping_embed`.
+///
+/// Returns straight away if the job is already running.
+pub(super) async fn wait_for_turn(
+    ctx: &Context,
+    message: &Message,
+    ticket: &JobTicket,
+    ripping_embed: CreateEmbed,
+) {
+    let mut last_ahead = None;
+    let mut editor = MessageEditor::new(&ctx.http, message);
+
+    loop {
+        // Created before checking so a change in between still wakes this up
+        let changed = JobTicket::changed();
+
+        let ahead = match ticket.try_start().await {
+            Ok(None) => break,
+            Ok(Some(ahead)) => ahead,
+            Err(e) => {
+                // Should never happen while the ticket is alive, rather than getting
+                // stuck the rip goes ahead
+                error!("Failed to check the queue: {}", e);
+                break;
+            }
+        };
+
+        if last_ahead != Some(ahead) {
+            debug!("Job {} is waiting behind {} job(s)", ticket.id, ahead);
+            editor.queue(
+                EditMessage::new().embed(ripping_embed.clone().description(tr!(
+                    "Queued, waiting for {ahead} job(s) to finish...",
+                    ahead = ahead
+                ))),
+            );
+            last_ahead = Some(ahead);
+        }
+
+        // A queue moving quickly only shows where the job ended up
+        tokio::select! {
+            () = changed => (),
+            () = editor.ready() => editor.send().await,
+        }
+    }
+
+    // Only needs restoring if the queued message was shown
+    if last_ahead.is_some() {
+        if let Err(e) = message
+            .clone()
+            .edit(&ctx.http, EditMessage::new().embed(ripping_embed))
+            .await
+        {
+            error!("Failed to send rip in progress message: {:?}", e);
+        }
+    }
+}
+
+/// Executes a rip, showing how far along it is on the ripping embed until it is done,
+/// with what `MakeMKV` is doing, how long it has left, its speed and how long it has been
+/// going. `position` is the number of the rip in a batch and the size of the batch. The
+/// progress is only refreshed every `ETA_UPDATE_INTERVAL`, or less often while Discord is
+/// rate limiting the channel.
+///
+/// The rip itself runs on a worker of the job of `ticket`, so it carries on if this
+/// interaction fails, and has to be stopped with `worker::cancel`.
+async fn execute_with_eta(
+    ctx: &Context,
+    message: &Message,
+    ticket: &Arc<JobTicket>,
+    rip: &Rip,
+    makemkv: &Arc<MakeMkv>,
+    ripping_embed: CreateEmbed,
+    position: Option<(usize, usize)>,
+) -> std::result::Result<RipOutcome, MakeMkvError> {
+    let work = {
+        let rip = rip.clone();
+        let makemkv = Arc::clone(makemkv);
+        async move { rip.execute(&makemkv).await }
+    };
+    let execute = worker::spawn(Arc::clone(ticket), work).result();
+    tokio::pin!(execute);
+
+    // Remembered so the progress can be picked up again if this stops following it
+    let _following = follow(
+        ticket.id,
+        JobMessage {
+            channel_id: message.channel_id.get(),
+            message_id: message.id.get(),
+            label: rip.title.clone(),
+            drive_number: rip.drive_number,
+            started: now(),
+        },
+    );
+    let started = std::time::Instant::now();
+
+    // The first estimate is shown shortly after the rip starts, once it has one
+    let mut updates = tokio::time::interval_at(
+        tokio::time::Instant::now() + FIRST_ETA_DELAY,
+        ETA_UPDATE_INTERVAL,
+    );
+    updates.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last_shown = None;
+    let mut editor = MessageEditor::new(&ctx.http, message);
+
+    loop {
+        tokio::select! {
+            // Only a panic leaves no result, cancelling drops this future first
+            result = &mut execute => return result.unwrap_or(Err(MakeMkvError::UnknownError)),
+            _ = updates.tick() => {
+                let Some(progress) =
+                    rip_progress(rip.drive_number, position, started.elapsed().as_secs())
+                else {
+                    continue;
+                };
+                if last_shown.as_ref() == Some(&progress) {
+                    continue;
+                }
+
+                // Only informative, the rip carries on regardless of whether it is shown
+                editor.queue(EditMessage::new().embed(
+                    ripping_embed
+                        .clone()
+                        .field(tr!("Progress"), &progress, false),
+                ));
+                last_shown = Some(progress);
+            }
+            () = editor.ready() => editor.send().await,
+        }
+    }
+}
+
+/// The trimmed log of a failed rip as a text file, to attach to the failure
+fn rip_log_attachment(log: String) -> CreateAttachment {
+    CreateAttachment::bytes(log.into_bytes(), "makemkv.log")
+}
+
+/// Describes how far a batch of `total` titles is as title `index` starts, e.g. a bar and
+/// "Episode 3/8", counted in titles when they are split into several episodes each
+// Batches are far too small to lose precision
+#[allow(clippy::cast_precision_loss)]
+fn batch_progress(index: usize, total: usize, split: bool) -> String {
+    let position = if split {
+        tr!("Title {number}/{total}", number = index + 1, total = total)
+    } else {
+        tr!(
+            "Episode {number}/{total}",
+            number = index + 1,
+            total = total
+        )
+    };
+    format!(
+        "{}\n{position}",
+        progress_bar(index as f64 / total.max(1) as f64)
+    )
+}
+
+/// Describes how far the rip on a drive is, with what `MakeMKV` is doing, a progress bar,
+/// how long it has left and how long it has been going, `None` until there is anything to
+/// tell. `position` is the number of the rip in a batch and the size of the batch.
+#[must_use]
+pub fn rip_progress(
+    drive_number: u8,
+    position: Option<(usize, usize)>,
+    elapsed_seconds: u64,
+) -> Option<String> {
+    let phase = phase(drive_number);
+    let eta = estimate(drive_number);
+    if phase.is_none() && eta.is_none() {
+        return None;
+    }
+
+    // Rips on agents only report progress once they are done
+    let bar = eta
+        .filter(|eta| eta.fraction > 0.0)
+        .map(|eta| progress_bar(eta.fraction));
+    let elapsed = tr!("{minutes} min elapsed", minutes = elapsed_seconds / 60);
+    let progress = phase
+        .map(|phase| phase_text(&phase, position))
+        .into_iter()
+        .chain(bar)
+        .chain(eta.as_ref().map(eta_text))
+        .chain([elapsed])
+        .collect::<Vec<String>>()
+        .join("\n");
+    Some(progress)
+}
+
+/// Names what `MakeMKV` is doing, e.g. "Analyzing" or "Saving title 2/5" for the second
+/// rip of a batch of five
+fn phase_text(phase: &RipPhase, position: Option<(usize, usize)>) -> String {
+    match (phase, position) {
+        (RipPhase::Opening, _) => tr!("Opening disc"),
+        (RipPhase::Analyzing, _) => tr!("Analyzing"),
+        (RipPhase::Saving, Some((number, total))) if total > 1 => tr!(
+            "Saving title {number}/{total}",
+            number = number,
+            total = total
+        ),
+        (RipPhase::Saving, _) => tr!("Saving"),
+        (RipPhase::Other(name), _) => name.clone(),
+    }
+}
+
+/// Describes the estimate of a running rip, e.g. "~38 min remaining at 22 MB/s"
+pub(super) fn eta_text(eta: &RipEta) -> String {
+    let minutes = eta.remaining_seconds().div_ceil(60);
+    tr!(
+        "~{minutes} min remaining at {speed} MB/s",
+        minutes = minutes,
+        speed = format!("{:.0}", eta.bytes_per_second / (1024.0 * 1024.0))
+    )
+}
+
+/// Lists the rips that failed on a summary, and why, along with a button to retry them
+fn with_failed_titles(
+    embed: CreateEmbed,
+    message: CreateMessage,
+    failed: &[(Rip, MakeMkvError)],
+) -> (CreateEmbed, CreateMessage) {
+    if failed.is_empty() {
+        return (embed, message);
+    }
+
+    // Listed in a format the retry button can read back
+    let titles = failed
+        .iter()
+        .map(|(rip, _)| format_title_episode(rip))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let reasons = failed
+        .iter()
+        .map(|(rip, e)| {
+            tr!(
+                "Title {title}: {reason}",
+                title = rip.title_id,
+                reason = e.user_message()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    (
+        embed
+            .field(tr!("Failed Titles"), titles, false)
+            .field(tr!("Reasons"), reasons, false),
+        message.button(
+            CreateButton::new("retry_failed_rips")
+                .label(tr!("Retry Failed"))
+                .style(serenity::all::ButtonStyle::Primary),
+        ),
+    )
+}
+
+/// Lists the rips that came out suspiciously small on a summary, and how far short they
+/// fell, along with a button to rip them again
+fn with_size_warnings(
+    embed: CreateEmbed,
+    message: CreateMessage,
+    suspicious: &[(Rip, SizeWarning)],
+) -> (CreateEmbed, CreateMessage) {
+    if suspicious.is_empty() {
+        return (embed, message);
+    }
+
+    // Listed in a format the re-rip button can read back
+    let titles = suspicious
+        .iter()
+        .map(|(rip, _)| format_title_episode(rip))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let warnings = suspicious
+        .iter()
+        .map(|(rip, warning)| {
+            tr!(
+                "Title {title}: saved {saved} of the {expected} the disc scan found",
+                title = rip.title_id,
+                saved = format_size(warning.saved_bytes),
+                expected = format_size(warning.expected_bytes)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    (
+        embed.field(tr!("Suspicious Titles"), titles, false).field(
+            tr!("Size Warnings"),
+            warnings,
+            false,
+        ),
+        message.button(
+            CreateButton::new("rerip_suspicious")
+                .label(tr!("Re-rip Suspicious"))
+                .style(serenity::all::ButtonStyle::Primary),
+        ),
+    )
+}
+
+/// Describes how the retry of segments that failed hash checks went, for the summaries
+pub(super) fn recovery_text(recovery: HashRecovery) -> String {
+    tr!(
+        "{failed} segment(s) failed hash checks, a slower retry recovered {recovered}",
+        failed = recovery.failed_segments,
+        recovered = recovery.recovered_segments
+    )
+}
+
+/// Gets the message a rip named `name`, set up on `message`, shows its progress on, see
+/// `progress_message`
+pub(super) async fn start_rip_message(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    name: &str,
+) -> Result<Message> {
+    progress_message(ctx, token, message, name)
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip in progress message: {:?}", e);
+            DiscordError::SendMessageFailed(e.to_string())
+        })
+}
+
+/// Describes how long a job took for its summary, from its elapsed time in minutes
+pub(super) fn finished_in(minutes: f64) -> String {
+    tr!(
+        "Finished in: {minutes} minutes and {seconds} seconds",
+        minutes = minutes.floor() as u64,
+        seconds = format!("{:.0}", (minutes.fract() * 60.0).round())
+    )
+}
+
+/// Options chosen for a rip job before it starts, and who started it, the only one
+/// besides administrators who can use its buttons
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RipOptions {
+    pub(super) priority: Priority,
+    pub(super) eject_when_done: bool,
+    pub(super) owner: UserId,
+}
+
+pub(super) fn yes_no(value: bool) -> String {
+    if value {
+        tr!("Yes")
+    } else {
+        tr!("No")
+    }
+}
+
+/// Reads whether to eject when done back from the embed of a rip message, defaulting to
+/// the `--eject-after-rip` setting
+pub(super) async fn embed_eject(ctx: &Context, message: &Message) -> Result<bool> {
+    let field = message
+        .embeds
+        .first()
+        .and_then(|embed| {
+            embed
+                .fields
+                .iter()
+                .find(|field| field.name == tr!("Eject When Done"))
+        })
+        .map(|field| field.value == tr!("Yes"));
+
+    match field {
+        Some(eject) => Ok(eject),
+        None => Ok(makemkv(ctx).await?.eject_after_rip),
+    }
+}
+
+/// Ejects the drive once the rip job `job_id` is done, a failure is only logged since the
+/// rip itself has already finished. A drive another job started ripping from, while this
+/// one was copying to the library, is left alone.
+pub(super) async fn eject_after_rip(drive_number: u8, job_id: u64) {
+    if QUEUE
+        .lock()
+        .await
+        .running_rip(drive_number)
+        .is_some_and(|job| job.id != job_id)
+    {
+        debug!(
+            "Not ejecting drive {}, another job is ripping from it",
+            drive_number
+        );
+        return;
+    }
+
+    if let Err(e) = eject_drive(drive_number).await {
+        warn!("Failed to eject drive {} after rip: {}", drive_number, e);
+    }
+}
+
+/// How long after a rip starts its first estimate is shown, and how often it is refreshed.
+/// Several rips can share a channel, so each only edits its message every 20 seconds
+const FIRST_ETA_DELAY: Duration = Duration::from_secs(10);
+const ETA_UPDATE_INTERVAL: Duration = Duration::from_secs(20);
+
+/// The title of the confirmation embed of movie rips, used to tell them apart from shows
+const CONFIRM_MOVIE_TITLE: &str = "Confirm Movie Rip";
+
+/// The details of a title in the select menus, read back for the size of the selected titles.
+/// The source file comes first, since it is what tells playlists of the same length apart
+const TITLE_DETAILS: &str =
+    "{source} · Chapters: {chapters}, Size: {size}, Resolution: {resolution}, Frame Rate: {frame_rate}";
+
+/// Discord's limit on the description of a select menu option
+const MAX_OPTION_DESCRIPTION_LENGTH: usize = 100;
+
+/// Discord's limit on the value of an embed field
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// The longest note that can be attached to a rip, it also ends up in the title of the file
+/// when `--tag-notes` is set
+const MAX_NOTE_LENGTH: u16 = 200;
+
+/// The lengths, in minutes, titles can be filtered to on the title select steps. 0 shows
+/// every title `MakeMKV` found, which are at least 10 minutes long.
+pub(super) const TITLE_LENGTH_FILTERS: [u32; 3] = [20, 40, 0];
+
+/// How the titles of show rips are listed in the confirmation and summary embeds, and read
+/// back from them
+const TITLE_EPISODE: &str = "Title {title}: Episode {episode}";
+const TITLE_ABSOLUTE_EPISODE: &str = "Title {title}: Episode {episode} (Absolute {absolute})";
+
+/// How the extras of movie rips are listed in the confirmation and summary embeds, under
+/// the title of the movie itself
+const TITLE_EXTRA: &str = "Title {title}: {name}";
+
+/// The most titles a movie rip can be given, the movie and an extra for each of the five
+/// inputs Discord fits in the modal they are named in
+const MAX_MOVIE_TITLES: u8 = 6;
+
+/// Replaces the title select menu with a summary of the rips, so naming mistakes are caught
+/// before anything is ripped. The rips start once `confirm_rip` is pressed, `edit_rip`
+/// reopens the details modal and `cancel_pending_rip` drops them.
+///
+/// Everything needed to start the rips is kept in the embed, since that is all the
+/// confirm button has to go on. Episode names already on the message are kept for the
+/// episodes that are still being ripped.
+pub(super) async fn confirm_rips(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    rips: &[Rip],
+) -> Result<()> {
+    let mut rips = rips.to_vec();
+    let Some(first) = rips.first() else {
+        warn!("No titles selected to rip, ignoring");
+        return Err(DiscordError::InvalidComponentData);
+    };
+
+    let make_mkv = makemkv(ctx).await?;
+    let embed = message.embeds.first();
+    let preset = embed_preset(embed).await;
+
+    // Keeps the library picked before the details were edited, or the preset's the first
+    // time round, unless it has since been removed from the configuration
+    // 3D titles are kept in 3D until toggled, and saved to the 3D library if there is one
+    let three_d = has_3d_titles(&rips).await;
+    let base_view = three_d && first.base_view;
+    let library = match embed.filter(|_| field(embed, "Library").is_some()) {
+        Some(embed) => embed_library(embed),
+        None => preset
+            .as_ref()
+            .and_then(|preset| preset.library.clone())
+            .or_else(|| make_mkv.library_3d.clone().filter(|_| three_d)),
+    }
+    .filter(|library| make_mkv.libraries.iter().any(|(name, _)| name == library));
+    let note = first.note.clone().or_else(|| embed.and_then(embed_note));
+
+    // Episodes are numbered for the output directory when the titles are picked, so the
+    // first time round they are numbered again for a library the preset or 3D picked
+    if library.is_some() && field(embed, "Library").is_none() {
+        renumber_episodes(ctx, &mut rips, library.as_deref()).await?;
+    }
+    name_episodes(&mut rips, &embed_episode_titles(embed));
+    let rips = rips.as_slice();
+
+    let first = &Rip {
+        library: library.clone(),
+        ..rips[0].clone()
+    };
+    let (destination, output_dir) = (
+        make_mkv.destination_path(first),
+        make_mkv.library_root(library.as_deref()).to_path_buf(),
+    );
+
+    // Keeps the choice when the confirmation is shown again after editing episodes
+    let preset_eject = preset
+        .as_ref()
+        .and_then(|preset| preset.eject_when_done)
+        .filter(|_| field(embed, "Eject When Done").is_none());
+    let eject_when_done = match preset_eject {
+        Some(eject) => eject,
+        None => embed_eject(ctx, message).await?,
+    };
+
+    let destination = match rips.len() {
+        1 => destination.display().to_string(),
+        count => tr!(
+            "{destination} (and {count} more)",
+            destination = destination.display(),
+            count = count - 1
+        ),
+    };
+
+    // The sizes were already read from the disc for the select menu, or for the previous
+    // confirmation if the episodes were edited
+    let estimated_size = selected_titles_size(message, rips).or_else(|| {
+        message
+            .embeds
+            .first()?
+            .fields
+            .iter()
+            .find(|field| field.name == tr!("Estimated Size"))
+            .and_then(|field| parse_size(&field.value))
+    });
+
+    let free_space = match free_space(&output_dir).await {
+        Ok(free_space) => Some(free_space),
+        Err(e) => {
+            warn!("Failed to check free space for rip confirmation: {}", e);
+            None
+        }
+    };
+
+    let selected_titles = rips
+        .iter()
+        .map(format_title_episode)
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut embed = match first.rip_type {
+        RipType::Movie => themed_embed()
+            .title(tr!(CONFIRM_MOVIE_TITLE))
+            .field(tr!("Title"), &first.title, true)
+            .field(tr!("Disc Number"), first.drive_number.to_string(), true)
+            .fields(first.year.map(|year| (tr!("Year"), year.to_string(), true))),
+        RipType::Show {
+            season,
+            absolute_episode,
+            ..
+        } => {
+            let episodes: Vec<u8> = rips.iter().filter_map(Rip::episode).collect();
+
+            // How many episodes a split title holds isn't known until it is ripped
+            let episodes = match (first.chapters_per_episode, first.episode()) {
+                (Some(_), Some(episode)) => tr!("{episode} onwards", episode = episode),
+                _ => format_episode_list(&episodes),
+            };
+
+            themed_embed()
+                .title(tr!("Confirm Show Rip"))
+                .field(tr!("Title"), &first.title, true)
+                .field(tr!("Disc Number"), first.drive_number.to_string(), true)
+                .field(tr!("Season"), season.to_string(), true)
+                .fields(
+                    absolute_episode.map(|start| (tr!("Absolute Start"), start.to_string(), true)),
+                )
+                .field(tr!("Episodes"), episodes, true)
+                .fields(
+                    first
+                        .chapters_per_episode
+                        .map(|chapters| (tr!("Chapters Per Episode"), chapters.to_string(), true)),
+                )
+        }
+    };
+
+    embed = embed
+        .description(tr!(
+            "Please check the details below, nothing is ripped until confirmed."
+        ))
+        .field(
+            tr!("Priority"),
+            priority_name(embed_priority(message)),
+            true,
+        )
+        .field(tr!("Eject When Done"), yes_no(eject_when_done), true)
+        .fields(preset_field(preset.as_ref()))
+        .fields((!make_mkv.libraries.is_empty()).then(|| {
+            (
+                tr!("Library"),
+                library
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_LIBRARY.to_string()),
+                true,
+            )
+        }))
+        .fields(three_d.then(|| (tr!("3D"), three_d_mode(base_view), true)))
+        .fields(note.map(|note| (tr!("Rip Note"), note, false)))
+        .field(tr!("Selected Titles"), selected_titles, false)
+        .fields(format_episode_titles(rips).map(|names| (tr!("Episode Titles"), names, false)))
+        .field(tr!("Destination"), destination, false)
+        .field(
+            tr!("Estimated Size"),
+            estimated_size.map_or_else(|| tr!("Unknown"), format_size),
+            true,
+        )
+        .field(
+            tr!("Free Space"),
+            free_space.map_or_else(|| tr!("Unknown"), format_size),
+            true,
+        );
+
+    if let (Some(estimated_size), Some(free_space)) = (estimated_size, free_space) {
+        if estimated_size > free_space {
+            embed = embed.field(
+                tr!("Warning"),
+                tr!("The rip is larger than the free space left in the output directory."),
+                false,
+            );
+        }
+    }
+
+    if three_d && !base_view {
+        embed = embed.field(
+            tr!("Warning"),
+            tr!("Some of these titles are 3D, which many players can't play. \"Toggle 3D\" keeps only their 2D version."),
+            false,
+        );
+    }
+
+    let buttons = vec![
+        CreateButton::new("confirm_rip")
+            .label(tr!("Confirm"))
+            .style(serenity::all::ButtonStyle::Success),
+        CreateButton::new("edit_rip")
+            .label(tr!("Edit"))
+            .style(serenity::all::ButtonStyle::Primary),
+        CreateButton::new("toggle_eject_after_rip")
+            .label(tr!("Toggle Eject When Done"))
+            .style(serenity::all::ButtonStyle::Secondary),
+        CreateButton::new("add_note")
+            .label(tr!("Add Note"))
+            .style(serenity::all::ButtonStyle::Secondary),
+        CreateButton::new("cancel_pending_rip")
+            .label(tr!("Cancel"))
+            .style(serenity::all::ButtonStyle::Danger),
+    ];
+    let mut components = vec![CreateActionRow::Buttons(buttons)];
+
+    // Episodes are numbered on from the last one in the show's folder, which is wrong
+    // when filling a gap or re-ripping an episode. They get their own row, since Discord
+    // only fits five buttons in one
+    if matches!(first.rip_type, RipType::Show { .. }) {
+        let mut buttons = vec![
+            CreateButton::new("edit_episodes")
+                .label(tr!("Edit Episodes"))
+                .style(serenity::all::ButtonStyle::Primary),
+            CreateButton::new("split_by_chapters")
+                .label(tr!("Split by Chapters"))
+                .style(serenity::all::ButtonStyle::Secondary),
+        ];
+        // The episode names come from Sonarr, and split titles can't be named
+        if first.chapters_per_episode.is_none() && ARR.lock().await.sonarr.is_some() {
+            buttons.push(
+                CreateButton::new("map_episodes")
+                    .label(tr!("Map Episodes"))
+                    .style(serenity::all::ButtonStyle::Primary),
+            );
+        }
+        components.push(CreateActionRow::Buttons(buttons));
+    }
+
+    if three_d {
+        components.push(CreateActionRow::Buttons(vec![CreateButton::new(
+            "toggle_3d",
+        )
+        .label(tr!("Toggle 3D"))
+        .style(serenity::all::ButtonStyle::Secondary)]));
+    }
+
+    // Libraries are only picked from when there is more than the output directory
+    if !make_mkv.libraries.is_empty() {
+        components.push(CreateActionRow::SelectMenu(library_menu(
+            &make_mkv,
+            library.as_deref(),
+        )));
+    }
+
+    edit_message(
+        ctx,
+        token,
+        message,
+        EditMessage::new()
+            .components(components)
+            .embed(embed)
+            // Drops any title previews from the select step
+            .remove_all_attachments(),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to send rip confirmation message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    Ok(())
+}
+
+/// The episode the rips of a season start at. Sonarr knows which episodes are missing,
+/// otherwise the rips carry on from the last episode in the show's directory in `library`
+pub(super) async fn first_episode(
+    ctx: &Context,
+    library: Option<&str>,
+    title: &str,
+    season: u8,
+) -> Result<u8> {
+    match next_missing_episode(title, season).await {
+        Some(episode) => Ok(episode),
+        None => {
+            let makemkv = makemkv(ctx).await?;
+            let last_episode =
+                crate::makemkv::get_last_episode_in_dir(&makemkv, library, title, season).await;
+            Ok(last_episode + 1)
+        }
+    }
+}
+
+/// Numbers the episodes of `rips` on from the last one in `library`, keeping the gaps
+/// between them. The rips of a movie are left as they are.
+async fn renumber_episodes(ctx: &Context, rips: &mut [Rip], library: Option<&str>) -> Result<()> {
+    let (Some(first), Some(old_first)) = (rips.first(), rips.iter().filter_map(Rip::episode).min())
+    else {
+        return Ok(());
+    };
+    let RipType::Show { season, .. } = first.rip_type else {
+        return Ok(());
+    };
+    let title = first.title.clone();
+
+    let new_first = first_episode(ctx, library, &title, season).await?;
+    debug!(
+        "Numbering {} Season {} from episode {} for library {:?}",
+        title, season, new_first, library
+    );
+    for rip in rips {
+        if let RipType::Show { episode, .. } = &mut rip.rip_type {
+            let renumbered = new_first.saturating_add(episode.saturating_sub(old_first));
+            // Names are kept by episode, so a renumbered rip is named again
+            if renumbered != *episode {
+                *episode = renumbered;
+                rip.episode_title = None;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds the rips of a show from `(title id, episode, absolute episode)` entries
+pub(super) fn show_rips(
+    title_name: &str,
+    drive_number: u8,
+    season: u8,
+    titles: Vec<(u16, u8, Option<u16>)>,
+    chapters_per_episode: Option<u16>,
+) -> Vec<Rip> {
+    titles
+        .into_iter()
+        .map(|(title_id, episode, absolute_episode)| Rip {
+            title: title_name.to_string(),
+            drive_number,
+            rip_type: RipType::Show {
+                season,
+                episode,
+                absolute_episode,
+            },
+            title_id,
+            year: None,
+            chapters_per_episode,
+            library: None,
+            episode_title: None,
+            note: None,
+            extra: None,
+            base_view: false,
+        })
+        .collect()
+}
+
+/// Adds up the sizes of the selected titles from the descriptions of the select menu they
+/// were picked from. `None` if any of the sizes are missing.
+fn selected_titles_size(message: &Message, rips: &[Rip]) -> Option<u64> {
+    let options = message
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::SelectMenu(menu) => Some(&menu.options),
+            _ => None,
+        })?;
+
+    rips.iter()
+        .map(|rip| {
+            let option = options
+                .iter()
+                .find(|option| option.value == rip.title_id.to_string())?;
+
+            untranslate(TITLE_DETAILS, option.description.as_deref()?)
+                .and_then(|details| parse_size(&details["size"]))
+        })
+        .sum()
+}
+
+/// The options of the title select menu of movie rips, limited to the first 25 to comply
+/// with Discord API's limit. The likeliest main feature is listed first and marked.
+pub(super) fn movie_title_options(
+    titles: &[Title],
+    runtime: Option<u32>,
+) -> Vec<CreateSelectMenuOption> {
+    let mut ranked = rank_titles(titles, runtime);
+
+    // Decoy playlists all score the same, so an obfuscated disc's suggestion is listed
+    // first instead
+    let obfuscation = detect_obfuscation(titles);
+    let main_feature = match obfuscation.as_ref().and_then(|o| o.suggested) {
+        Some(suggested) => {
+            if let Some(index) = ranked
+                .iter()
+                .position(|scored| scored.title.title_id == suggested)
+            {
+                let scored = ranked.remove(index);
+                ranked.insert(0, scored);
+            }
+            Some(suggested)
+        }
+        None => likely_main_feature(&ranked),
+    };
+
+    ranked
+        .iter()
+        .map(|scored| &scored.title)
+        .take(25)
+        .map(|title| {
+            let mut title_details = tr!(
+                "Title: {title}, Duration: {length}",
+                title = title.title_id,
+                length = title.length
+            );
+            if title.three_d {
+                title_details = tr!("{title} (3D)", title = title_details);
+            }
+            if main_feature == Some(title.title_id) {
+                title_details = tr!("⭐ {title} (likely main feature)", title = title_details);
+            }
+            CreateSelectMenuOption::new(title_details, title.title_id.to_string())
+                .description(title_description(title))
+        })
+        .collect()
+}
+
+/// The options of the title select menu of show rips, limited to the first 25 to comply
+/// with Discord API's limit
+pub(super) fn show_title_options(titles: &[Title]) -> Vec<CreateSelectMenuOption> {
+    titles
+        .iter()
+        .take(25)
+        .map(|title| {
+            let mut title_details = tr!(
+                "Title: {title}, Duration: {length}",
+                title = title.title_id,
+                length = title.length
+            );
+            if title.three_d {
+                title_details = tr!("{title} (3D)", title = title_details);
+            }
+            CreateSelectMenuOption::new(title_details, title.title_id.to_string())
+                .description(title_description(title))
+        })
+        .collect()
+}
+
+/// The select menu to pick the title of a movie rip from, along with any extras to rip
+/// with it
+pub(super) fn movie_title_menu(options: Vec<CreateSelectMenuOption>) -> CreateSelectMenu {
+    let max_values = u8::try_from(options.len())
+        .unwrap_or(u8::MAX)
+        .min(MAX_MOVIE_TITLES);
+    CreateSelectMenu::new(
+        "select_title_to_rip",
+        CreateSelectMenuKind::String { options },
+    )
+    .min_values(1)
+    .max_values(max_values)
+}
+
+/// The select menu to pick the titles of a show rip from, any number of them can be picked
+pub(super) fn show_title_menu(options: Vec<CreateSelectMenuOption>) -> CreateSelectMenu {
+    let max_values = options.len() as u8;
+    CreateSelectMenu::new(
+        "select_titles_to_rip",
+        CreateSelectMenuKind::String { options },
+    )
+    .min_values(1)
+    .max_values(max_values)
+}
+
+/// The buttons under the title select menus, to preview the titles and filter them by
+/// length. The filter in use, `min_minutes` long or 0 for every title, is highlighted.
+pub(super) fn title_buttons(min_minutes: u32) -> CreateActionRow {
+    let filters = TITLE_LENGTH_FILTERS.iter().map(|&minutes| {
+        let (id, label) = if minutes == 0 {
+            ("filter_titles_all".to_string(), tr!("All"))
+        } else {
+            (
+                format!("filter_titles_{minutes}"),
+                tr!("> {minutes} min", minutes = minutes),
+            )
+        };
+        let style = if minutes == min_minutes {
+            serenity::all::ButtonStyle::Primary
+        } else {
+            serenity::all::ButtonStyle::Secondary
+        };
+        CreateButton::new(id).label(label).style(style)
+    });
+
+    CreateActionRow::Buttons(
+        std::iter::once(
+            CreateButton::new("preview_titles")
+                .label(tr!("Show Previews"))
+                .style(serenity::all::ButtonStyle::Secondary),
+        )
+        .chain(filters)
+        .collect(),
+    )
+}
+
+/// The description of a title in the select menus, cut to fit Discord's limit. Only the
+/// frame rate at the end can be cut off, so the size can still be read back.
+fn title_description(title: &Title) -> String {
+    tr!(
+        TITLE_DETAILS,
+        source = title.source_file,
+        chapters = title.chapters,
+        size = title.size,
+        resolution = title.resolution,
+        frame_rate = title.frame_rate
+    )
+    .chars()
+    .take(MAX_OPTION_DESCRIPTION_LENGTH)
+    .collect()
+}
+
+/// What the files of a rip hold and how fast they were ripped, for its summary
+fn quality_text(stats: &MediaStats, rip_seconds: u64) -> String {
+    // Whole numbers with a single decimal, without going through floats
+    let one_decimal =
+        |value: u64, unit: u64| format!("{}.{}", value / unit, value % unit * 10 / unit);
+
+    let bitrate = stats.video_bitrate.map_or_else(
+        || tr!("Unknown"),
+        |bitrate| format!("{} Mbps", one_decimal(bitrate, 1_000_000)),
+    );
+    let audio = if stats.audio_tracks.is_empty() {
+        tr!("None")
+    } else {
+        stats
+            .audio_tracks
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    let speed = stats.size.checked_div(rip_seconds).map_or_else(
+        || tr!("Unknown"),
+        |bytes_per_second| format!("{} MB/s", one_decimal(bytes_per_second, 1024 * 1024)),
+    );
+
+    tr!(
+        "Size: {size}\nDuration: {duration}\nVideo Bitrate: {bitrate}\nAudio: {audio}\nRip Speed: {speed}",
+        size = format_size(stats.size),
+        duration = format!(
+            "{}:{:02}:{:02}",
+            stats.duration / 3600,
+            stats.duration % 3600 / 60,
+            stats.duration % 60
+        ),
+        bitrate = bitrate,
+        audio = audio,
+        speed = speed
+    )
+}
+
+pub(super) fn format_size(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
@@ -0,0 +1,587 @@
+use serenity::all::{
+    Context, CreateActionRow, CreateButton, CreateInputText, CreateInteractionResponse,
+    CreateMessage, CreateModal, EditMessage, Embed, InputTextStyle, Interaction, Message,
+};
+
+use crate::audio::{errors::AudioError, lookup_album, read_toc, rip_track, Album};
+use crate::audit::AuditAction;
+use crate::discord::bot::controls;
+use crate::discord::commands::command_helpers::get_modal_input;
+use crate::discord::commands::command_helpers::{
+    audit, audit_cancel, audit_outcome, makemkv, rip_parameters, take_rip_quota,
+};
+use crate::discord::commands::rip::{
+    eject_after_rip, embed_drive_number, embed_eject, embed_priority, field, finished_in,
+    format_size, parse_priority, prefill, priority_input, priority_name, start_rip_message,
+    wait_for_turn, yes_no, RipOptions,
+};
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::edit_message;
+use crate::discord::errors::{DiscordError, Result};
+use crate::disk::free_space;
+use crate::library::LIBRARY;
+use crate::queue::{JobKind, JobTicket, Priority};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// Discord only shows up to 1024 characters in a field
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// Runs the audio CD branch of the `rip` command
+///
+/// Audio CDs can't be ripped by `MakeMKV`, so once "Rip Audio CD" is picked the disc is
+/// looked up on `MusicBrainz` and every track is ripped with `cdparanoia`, rather than
+/// picking titles.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the disc can't be read, or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Audio rip was called");
+
+    match interaction {
+        Interaction::Component(component) => {
+            trace!("Got request from component interaction");
+
+            let message = component.message.clone();
+
+            match custom_ids::name(&component.data.custom_id) {
+                // Called from the rip type buttons, looks the disc up and asks to confirm
+                "audio_rip" => {
+                    trace!("Got audio_rip component interaction");
+
+                    let drive_number = embed_drive_number(message.embeds.first())?;
+
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    edit_message(
+                        ctx,
+                        &component.token,
+                        &message,
+                        EditMessage::new().components(vec![]).embed(
+                            themed_embed()
+                                .title(tr!("Looking Up Disc"))
+                                .description(tr!("Reading the disc and searching MusicBrainz..."))
+                                .field(tr!("Disc Number"), drive_number.to_string(), true),
+                        ),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send disc lookup message: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
+
+                    let album = disc_album(ctx, &component.token, &message, drive_number).await?;
+                    confirm_audio_rip(
+                        ctx,
+                        &component.token,
+                        &message,
+                        drive_number,
+                        &album,
+                        Priority::Normal,
+                    )
+                    .await
+                }
+                // Starts the rip with the details on the confirmation message
+                "confirm_audio_rip" => {
+                    trace!("Got confirm_audio_rip component interaction");
+
+                    component.defer(&ctx.http).await.map_err(|e| {
+                        error!("Failed to defer interaction: {:?}", e);
+                        DiscordError::DeferFailed(e.to_string())
+                    })?;
+
+                    let embed = message.embeds.first();
+                    let drive_number = embed_drive_number(embed)?;
+
+                    // Track names don't fit in the embed for every album, so they are
+                    // looked up again and only the edited details are kept from it
+                    let mut album =
+                        disc_album(ctx, &component.token, &message, drive_number).await?;
+                    if let Some(artist) = field(embed, "Artist") {
+                        album.artist = artist.to_string();
+                    }
+                    if let Some(title) = field(embed, "Album") {
+                        album.title = title.to_string();
+                    }
+                    album.year = field(embed, "Year").and_then(|year| year.parse().ok());
+
+                    let options = RipOptions {
+                        priority: embed_priority(&message),
+                        eject_when_done: embed_eject(ctx, &message).await?,
+                        owner: component.user.id,
+                    };
+
+                    take_rip_quota(component.user.id, component.member.as_ref()).await?;
+                    let name = format!("{} - {}", album.artist, album.title);
+                    let parameters =
+                        rip_parameters(&name, drive_number, None, options.eject_when_done);
+                    let message = start_rip_message(ctx, &component.token, &message, &name).await?;
+                    let result = run_audio_rip(ctx, &message, drive_number, album, options).await;
+
+                    let outcome = audit_outcome(&result);
+                    audit(
+                        &component.user,
+                        "rip",
+                        AuditAction::Rip,
+                        parameters,
+                        outcome,
+                    )
+                    .await;
+                    result
+                }
+                // Opens a modal to correct the artist, album and year
+                "edit_audio_rip" => {
+                    trace!("Got edit_audio_rip component interaction");
+
+                    let embed = message.embeds.first();
+                    let drive_number = embed_drive_number(embed)?;
+
+                    component
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Modal(audio_modal(drive_number, embed)),
+                        )
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to create get_audio_details modal: {:?}", e);
+                            DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                        })?;
+
+                    Ok(())
+                }
+                _ => {
+                    debug!(
+                        "Unknown component calling audio rip: {}, ignoring",
+                        component.data.custom_id
+                    );
+                    Err(DiscordError::InvalidInteractionCall)
+                }
+            }
+        }
+        Interaction::Modal(modal)
+            if custom_ids::name(&modal.data.custom_id) == "get_audio_details" =>
+        {
+            trace!("Got get_audio_details modal");
+
+            let Some(message) = modal.message.clone() else {
+                trace!("Modal interaction has no message, ignoring");
+                return Err(DiscordError::InvalidInteractionCall);
+            };
+
+            modal.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            let drive_number: u8 = get_modal_input(modal, "disc_number")
+                .and_then(|drive_number| drive_number.parse().ok())
+                .ok_or(DiscordError::InvalidComponentData)?;
+            let priority = parse_priority(modal)?;
+
+            let mut album = disc_album(ctx, &modal.token, &message, drive_number).await?;
+            album.artist =
+                get_modal_input(modal, "artist").ok_or(DiscordError::InvalidComponentData)?;
+            album.title =
+                get_modal_input(modal, "album").ok_or(DiscordError::InvalidComponentData)?;
+            album.year = match get_modal_input(modal, "year") {
+                Some(year) => Some(year.parse().map_err(|_| {
+                    warn!("Failed to parse year from modal, ignoring");
+                    DiscordError::InvalidComponentData
+                })?),
+                None => None,
+            };
+
+            confirm_audio_rip(ctx, &modal.token, &message, drive_number, &album, priority).await
+        }
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+/// Reads the disc and looks it up on `MusicBrainz`. A disc that isn't found, or a lookup
+/// that fails, falls back to placeholder names that can be edited before ripping.
+///
+/// Shows the error on the message if the disc has no audio tracks.
+async fn disc_album(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    drive_number: u8,
+) -> Result<Album> {
+    let toc = match read_toc(drive_number).await {
+        Ok(toc) => toc,
+        Err(e) => {
+            error!("Failed to read disc {}: {:?}", drive_number, e);
+
+            if let Err(e) = edit_message(
+                ctx,
+                token,
+                message,
+                EditMessage::new().components(vec![]).embed(
+                    themed_embed()
+                        .title(tr!("Rip Failed"))
+                        .description(e.user_message())
+                        .field(tr!("Disc Number"), drive_number.to_string(), true),
+                ),
+            )
+            .await
+            {
+                error!("Failed to send rip failed message: {:?}", e);
+            }
+
+            return Err(DiscordError::reported(e));
+        }
+    };
+
+    match lookup_album(&toc).await {
+        Ok(Some(album)) => Ok(album),
+        Ok(None) => {
+            info!("Disc {} wasn't found on MusicBrainz", drive_number);
+            Ok(Album::unknown(&toc))
+        }
+        Err(e) => {
+            warn!("Failed to look disc {} up: {}", drive_number, e);
+            Ok(Album::unknown(&toc))
+        }
+    }
+}
+
+/// Shows the album that will be ripped, so it can be corrected before anything is ripped.
+/// The rip starts once `confirm_audio_rip` is pressed, `edit_audio_rip` opens the details
+/// modal, and the eject and cancel buttons are shared with video rips.
+async fn confirm_audio_rip(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    drive_number: u8,
+    album: &Album,
+    priority: Priority,
+) -> Result<()> {
+    let output_dir = makemkv(ctx).await?.output_dir.clone();
+
+    // Keeps the choice when the confirmation is shown again after editing
+    let eject_when_done = embed_eject(ctx, message).await?;
+
+    let destination = album
+        .track_path(1)
+        .parent()
+        .map(|parent| output_dir.join(parent).display().to_string())
+        .unwrap_or_default();
+
+    let free_space = match free_space(&output_dir).await {
+        Ok(free_space) => format_size(free_space),
+        Err(e) => {
+            warn!("Failed to check free space for rip confirmation: {}", e);
+            tr!("Unknown")
+        }
+    };
+
+    let mut tracks = String::new();
+    for (index, title) in album.tracks.iter().enumerate() {
+        let line = format!("{:02}. {}\n", index + 1, title);
+        if tracks.len() + line.len() > MAX_FIELD_LENGTH - 4 {
+            tracks.push_str("...");
+            break;
+        }
+        tracks.push_str(&line);
+    }
+
+    let source = if album.release_id.is_some() {
+        "MusicBrainz".to_string()
+    } else {
+        tr!("Not found on MusicBrainz, please check the details")
+    };
+
+    let embed = themed_embed()
+        .title(tr!("Confirm Audio Rip"))
+        .description(tr!(
+            "Please check the details below, nothing is ripped until confirmed."
+        ))
+        .field(tr!("Album"), &album.title, true)
+        .field(tr!("Disc Number"), drive_number.to_string(), true)
+        .field(tr!("Artist"), &album.artist, true)
+        .fields(album.year.map(|year| (tr!("Year"), year.to_string(), true)))
+        .field(tr!("Priority"), priority_name(priority), true)
+        .field(tr!("Eject When Done"), yes_no(eject_when_done), true)
+        .field(tr!("Tracks"), tracks, false)
+        .field(tr!("Details From"), source, false)
+        .field(tr!("Destination"), destination, false)
+        .field(tr!("Free Space"), free_space, true);
+
+    edit_message(
+        ctx,
+        token,
+        message,
+        EditMessage::new()
+            .components(vec![CreateActionRow::Buttons(vec![
+                CreateButton::new("confirm_audio_rip")
+                    .label(tr!("Confirm"))
+                    .style(serenity::all::ButtonStyle::Success),
+                CreateButton::new("edit_audio_rip")
+                    .label(tr!("Edit"))
+                    .style(serenity::all::ButtonStyle::Primary),
+                CreateButton::new("toggle_eject_after_rip")
+                    .label(tr!("Toggle Eject When Done"))
+                    .style(serenity::all::ButtonStyle::Secondary),
+                CreateButton::new("cancel_pending_rip")
+                    .label(tr!("Cancel"))
+                    .style(serenity::all::ButtonStyle::Danger),
+            ])])
+            .embed(embed),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to send rip confirmation message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Rips every track of an audio CD in order, updating the message with the current track
+/// and allowing the user to cancel.
+///
+/// Like show rips, a track that fails doesn't stop the rest, the summary lists which
+/// tracks failed and why.
+async fn run_audio_rip(
+    ctx: &Context,
+    message: &Message,
+    drive_number: u8,
+    album: Album,
+    options: RipOptions,
+) -> Result<()> {
+    let RipOptions {
+        priority,
+        eject_when_done,
+        owner,
+    } = options;
+
+    let ticket = JobTicket::submit(
+        &tr!(
+            "{artist} - {album} (Disc {disc})",
+            artist = album.artist,
+            album = album.title,
+            disc = drive_number
+        ),
+        JobKind::Rip { drive_number },
+        priority,
+    )
+    .await;
+
+    let output_dir = makemkv(ctx).await?.output_dir.clone();
+    let now = std::time::Instant::now();
+
+    let num_tracks = album.tracks.len();
+    let mut ripped_tracks = 0;
+    let mut failed_tracks: Vec<(u8, AudioError)> = Vec::new();
+    let mut was_cancelled = false;
+
+    let mut controls = controls::listen(ticket.id, message.id, owner);
+
+    for number in 1..=u8::try_from(num_tracks).unwrap_or(u8::MAX) {
+        let ripping_embed = themed_embed()
+            .title(tr!("Rip Audio CD"))
+            .description(tr!(
+                "Ripping {track}... \n(Track {number}/{total})",
+                track = album.track_title(number),
+                number = number,
+                total = num_tracks
+            ))
+            .field(tr!("Album"), &album.title, true)
+            .field(tr!("Disc Number"), drive_number.to_string(), true)
+            .field(tr!("Artist"), &album.artist, true)
+            .field(tr!("Priority"), priority_name(priority), true);
+
+        message
+            .clone()
+            .edit(
+                &ctx.http,
+                EditMessage::new()
+                    .components(vec![])
+                    .embed(ripping_embed.clone())
+                    .button(
+                        CreateButton::new(custom_ids::with_id("cancel_rip", ticket.id))
+                            .label(tr!("Cancel"))
+                            .style(serenity::all::ButtonStyle::Danger),
+                    ),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to send rip in progress message: {:?}", e);
+                DiscordError::EditMessageFailed(e.to_string())
+            })?;
+
+        // Dropping the rip future kills cdparanoia and removes the temporary WAV
+        was_cancelled = tokio::select! {
+            rip_result = async {
+                wait_for_turn(ctx, message, &ticket, ripping_embed).await;
+                rip_track(drive_number, &album, number, &output_dir).await
+            } => {
+                match rip_result {
+                    Ok(file) => {
+                        LIBRARY.lock().await.add(&file);
+                        ripped_tracks += 1;
+                    }
+                    Err(e) => {
+                        error!("Failed to rip track {}: {:?}", number, e);
+                        failed_tracks.push((number, e));
+                    }
+                }
+                false
+            }
+            Some(interaction) = controls.next() => {
+                debug!("Recieved cancel request");
+
+                if let Err(e) = interaction.defer(&ctx.http).await {
+                    error!("Failed to defer cancel request: {:?}", e);
+                }
+                let name = format!("{} - {}", album.artist, album.title);
+                audit_cancel(&interaction.user, "rip", &name, drive_number).await;
+
+                message
+                    .clone()
+                    .edit(
+                        &ctx.http,
+                        EditMessage::new().components(vec![]).embed(
+                            themed_embed()
+                                .title(tr!("Rip Cancelled"))
+                                .description(tr!("Rip cancelled!"))
+                                .field(tr!("Album"), &album.title, true)
+                                .field(tr!("Disc Number"), drive_number.to_string(), true),
+                        ),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send rip cancelled message: {:?}", e);
+                        DiscordError::EditMessageFailed(e.to_string())
+                    })?;
+                info!("Rip cancelled");
+                true
+            }
+        };
+
+        if was_cancelled {
+            break;
+        }
+    }
+
+    if was_cancelled {
+        return Err(DiscordError::TaskCancelled);
+    }
+
+    let rip_time = now.elapsed().as_secs_f64() / 60.00;
+
+    if eject_when_done {
+        eject_after_rip(drive_number, ticket.id).await;
+    }
+
+    let (status_title, status_description) = if failed_tracks.is_empty() {
+        (
+            tr!("Ripped {title}", title = album.title),
+            tr!("Rip completed!"),
+        )
+    } else if ripped_tracks == 0 {
+        (
+            tr!("Failed to rip {title}", title = album.title),
+            tr!("All tracks failed!"),
+        )
+    } else {
+        (
+            tr!("Partially ripped {title}", title = album.title),
+            tr!("Some tracks failed, see the summary for details."),
+        )
+    };
+
+    message
+        .clone()
+        .edit(
+            &ctx.http,
+            EditMessage::new().components(vec![]).embed(
+                themed_embed()
+                    .title(status_title)
+                    .description(status_description),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip completed message: {:?}", e);
+            DiscordError::EditMessageFailed(e.to_string())
+        })?;
+
+    let mut summary_embed = themed_embed()
+        .title(tr!("Rip Summary"))
+        .description(finished_in(rip_time))
+        .field(tr!("Album"), &album.title, true)
+        .field(tr!("Disc Number"), drive_number.to_string(), true)
+        .field(tr!("Artist"), &album.artist, true)
+        .field(tr!("Tracks"), format!("{ripped_tracks}/{num_tracks}"), true);
+
+    if !failed_tracks.is_empty() {
+        let reasons = failed_tracks
+            .iter()
+            .map(|(number, e)| {
+                tr!(
+                    "Track {number}: {reason}",
+                    number = number,
+                    reason = e.user_message()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        summary_embed = summary_embed.field(tr!("Failed Tracks"), reasons, false);
+    }
+
+    message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .embed(summary_embed)
+                .reference_message(message),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip summary message: {:?}", e);
+            DiscordError::SendMessageFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// The modal asking for the details of an audio rip, filled in from the fields of `current`
+fn audio_modal(drive_number: u8, current: Option<&Embed>) -> CreateModal {
+    CreateModal::new("get_audio_details", tr!("Please enter the album details")).components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, tr!("Disc Number"), "disc_number")
+                .value(drive_number.to_string())
+                .required(true),
+        ),
+        CreateActionRow::InputText(prefill(
+            CreateInputText::new(InputTextStyle::Short, tr!("Artist"), "artist").required(true),
+            current,
+            "Artist",
+        )),
+        CreateActionRow::InputText(prefill(
+            CreateInputText::new(InputTextStyle::Short, tr!("Album"), "album").required(true),
+            current,
+            "Album",
+        )),
+        CreateActionRow::InputText(prefill(
+            CreateInputText::new(InputTextStyle::Short, tr!("Year"), "year")
+                .min_length(4)
+                .max_length(4)
+                .required(false),
+            current,
+            "Year",
+        )),
+        priority_input(current),
+    ])
+}
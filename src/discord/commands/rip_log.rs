@@ -0,0 +1,178 @@
+use serenity::all::{
+    AutocompleteChoice, CommandInteraction, CommandOptionType, Context, CreateAttachment,
+    CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, Interaction, Permissions, ResolvedValue,
+};
+
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::errors::{DiscordError, Result};
+use crate::makemkv::logs::JOB_LOGS;
+
+use crate::{debug, error, tr, trace, warn};
+
+/// The most logs suggested at once, Discord's limit
+const MAX_SUGGESTIONS: usize = 25;
+/// The largest log sent, Discord refuses bigger attachments on servers without boosts
+const MAX_LOG_SIZE: usize = 8 * 1024 * 1024;
+
+pub fn register() -> CreateCommand {
+    debug!("Registered rip_log command");
+    CreateCommand::new("rip_log")
+        .description(tr!("Download everything MakeMKV printed during a rip job"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "job",
+                tr!("The job to get the log of, the newest logs are suggested"),
+            )
+            .required(true)
+            .set_autocomplete(true),
+        )
+}
+
+/// Runs the `rip_log` command
+///
+/// Sends the log of a rip job as a file, only to whoever ran it. The job can be picked
+/// from the newest logs as it is typed, or given by its number, which finds the newest
+/// job with it since job numbers start over when the bot restarts.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, there is no log for the
+/// job, or Discord rejects the response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running rip_log command");
+
+    match interaction {
+        Interaction::Autocomplete(command) => suggest(ctx, command).await,
+        Interaction::Command(command) => send_log(ctx, command).await,
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+async fn send_log(ctx: &Context, command: &CommandInteraction) -> Result<()> {
+    trace!("Got request from command interaction");
+
+    if !is_admin(command.member.as_deref()) {
+        warn!(
+            "{} tried to view a rip log without permission",
+            command.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    let job = command
+        .data
+        .options()
+        .into_iter()
+        .find_map(|option| match (option.name, option.value) {
+            ("job", ResolvedValue::String(job)) => Some(job.to_string()),
+            _ => None,
+        })
+        .ok_or(DiscordError::InvalidComponentData)?;
+
+    let not_found =
+        || DiscordError::InvalidInput(tr!("No rip log was found for {job}.", job = job));
+    let path = JOB_LOGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .find(&job)
+        .ok_or_else(not_found)?;
+
+    let mut contents = tokio::fs::read(&path).await.map_err(|e| {
+        warn!("Failed to read rip log {}: {}", path.display(), e);
+        not_found()
+    })?;
+
+    // The end of a log is what explains how the rip ended
+    let trimmed = contents.len() > MAX_LOG_SIZE;
+    if trimmed {
+        contents.drain(..contents.len() - MAX_LOG_SIZE);
+    }
+
+    let file_name = path.file_name().map_or_else(
+        || "rip.log".to_string(),
+        |name| name.to_string_lossy().to_string(),
+    );
+    // The first line of a log is what the job was, unless it was trimmed off
+    let label = String::from_utf8_lossy(&contents)
+        .lines()
+        .next()
+        .filter(|_| !trimmed)
+        .map_or_else(|| file_name.clone(), str::to_string);
+
+    let mut embed = themed_embed().title(tr!("Rip Log")).description(label);
+    if trimmed {
+        embed = embed.field(
+            tr!("Trimmed"),
+            tr!("The log is too large for Discord, only its end is attached."),
+            false,
+        );
+    }
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .embed(embed)
+                    .add_file(CreateAttachment::bytes(contents, file_name)),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send rip log: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Suggests the newest logs whose job matches what is being typed
+async fn suggest(ctx: &Context, command: &CommandInteraction) -> Result<()> {
+    let Some(focused) = command.data.autocomplete() else {
+        debug!("Autocomplete without a focused option, ignoring");
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+    let typed = focused.value.trim().to_lowercase();
+
+    let logs = JOB_LOGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .recent(usize::MAX);
+
+    let choices: Vec<AutocompleteChoice> = logs
+        .into_iter()
+        .map(|log| {
+            let name: String = tr!("Job {job}: {label}", job = log.job_id, label = log.label)
+                .chars()
+                .take(100)
+                .collect();
+            (name, log.name)
+        })
+        .filter(|(name, _)| name.to_lowercase().contains(&typed))
+        .take(MAX_SUGGESTIONS)
+        .map(|(name, value)| AutocompleteChoice::new(name, value))
+        .collect();
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Autocomplete(
+                CreateAutocompleteResponse::new().set_choices(choices),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send suggestions: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,476 @@
+use serenity::all::{
+    ButtonStyle, ChannelId, ChannelType, ComponentInteraction, ComponentInteractionDataKind,
+    Context, CreateActionRow, CreateButton, CreateCommand, CreateEmbed, CreateInputText,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateModal, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, Embed, InputTextStyle, Interaction,
+    Permissions, RoleId,
+};
+
+use crate::diagnostics::{permission_checks, run_checks};
+use crate::discord::commands::command_helpers::{get_modal_input, is_admin, makemkv};
+use crate::discord::commands::diagnostics::checks_report;
+use crate::discord::components::custom_ids;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::{edit_message, response_options};
+use crate::discord::errors::{DiscordError, Result};
+use crate::makemkv::naming::NamingTemplate;
+use crate::settings::{Settings, SETTINGS};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+/// The layouts offered for the output directory, by the value of their option
+const LAYOUTS: [&str; 3] = ["default", "media_server", "flat"];
+
+pub fn register() -> CreateCommand {
+    debug!("Registered setup command");
+    CreateCommand::new("setup")
+        .description(tr!("Set up how the bot rips, alerts and limits rips"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+}
+
+/// Runs the `setup` command
+///
+/// The flow is:
+/// 1. `/setup` shows the current settings with a menu for each of them
+/// 2. Picking a layout for the output directory fills in its naming templates, or opens a
+///    modal to write custom ones. The alert channel and the roles exempt from the rip
+///    quotas are picked from Discord's own menus
+/// 3. Saving runs the self-test and writes the settings to the data directory, where they
+///    are loaded from the next time the bot starts
+///
+/// The settings being set up are kept in the message's embed until they are saved. Only
+/// server administrators are able to use this command.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, a template can't be used,
+/// the settings can't be saved, or Discord rejects a response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running setup command");
+
+    match interaction {
+        Interaction::Command(command) => {
+            trace!("Got request from command interaction");
+
+            if !is_admin(command.member.as_deref()) {
+                warn!(
+                    "{} tried to run setup without permission",
+                    command.user.name
+                );
+                return Err(DiscordError::PermissionDenied);
+            }
+
+            let settings = SETTINGS.lock().await.clone();
+            let (embed, components) = setup_panel(&settings);
+
+            // The panel shows the server's roles and channels, so it is kept to whoever
+            // ran it
+            command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .ephemeral(true)
+                            .embed(embed)
+                            .components(components),
+                    ),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create response: {:?}", e);
+                    DiscordError::CommandInteractionResponseFailed(e.to_string())
+                })?;
+
+            Ok(())
+        }
+        Interaction::Component(component) => handle_component(ctx, component).await,
+        Interaction::Modal(modal)
+            if custom_ids::name(&modal.data.custom_id) == "get_setup_templates" =>
+        {
+            trace!("Got get_setup_templates modal");
+
+            if !is_admin(modal.member.as_ref()) {
+                warn!(
+                    "{} tried to change the setup without permission",
+                    modal.user.name
+                );
+                return Err(DiscordError::PermissionDenied);
+            }
+
+            let Some(message) = modal.message.as_deref() else {
+                trace!("Modal interaction has no message, ignoring");
+                return Err(DiscordError::InvalidInteractionCall);
+            };
+
+            let mut settings = panel_settings(message.embeds.first());
+            let default = NamingTemplate::default();
+            set_templates(
+                &mut settings,
+                NamingTemplate {
+                    movie: get_modal_input(modal, "movie_template").unwrap_or(default.movie),
+                    show: get_modal_input(modal, "show_template").unwrap_or(default.show),
+                },
+            );
+
+            // Templates that can't be used are refused now, rather than when saving
+            settings.naming()?;
+
+            modal.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            let (embed, components) = setup_panel(&settings);
+            edit_message(
+                ctx,
+                &modal.token,
+                message,
+                EditMessage::new().embed(embed).components(components),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to edit message: {:?}", e);
+                DiscordError::EditMessageFailed(e.to_string())
+            })
+        }
+        _ => {
+            debug!("Unknown interaction type: {:?}, ignoring", interaction);
+            Err(DiscordError::InvalidInteractionCall)
+        }
+    }
+}
+
+/// Handles the menus and buttons of the setup panel, each change is shown on the panel
+/// until it is saved
+async fn handle_component(ctx: &Context, component: &ComponentInteraction) -> Result<()> {
+    trace!("Got request from component interaction");
+
+    if !is_admin(component.member.as_ref()) {
+        warn!(
+            "{} tried to change the setup without permission",
+            component.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    let message = &component.message;
+    let mut settings = panel_settings(message.embeds.first());
+
+    match (
+        custom_ids::name(&component.data.custom_id),
+        &component.data.kind,
+    ) {
+        ("setup_layout", ComponentInteractionDataKind::StringSelect { values }) => {
+            let naming = match values.first().map(String::as_str) {
+                Some("media_server") => NamingTemplate::media_server(),
+                Some("flat") => NamingTemplate::flat(),
+                Some("default") => NamingTemplate::default(),
+                // Custom templates are written in a modal, which updates the panel
+                // once submitted
+                _ => {
+                    component
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Modal(templates_modal(&settings)),
+                        )
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to create setup templates modal: {:?}", e);
+                            DiscordError::ComponentInteractionResponseFailed(e.to_string())
+                        })?;
+                    return Ok(());
+                }
+            };
+            set_templates(&mut settings, naming);
+        }
+        ("setup_alert_channel", ComponentInteractionDataKind::ChannelSelect { values }) => {
+            settings.alert_channel_id = values.first().map(|channel| channel.get());
+        }
+        ("setup_exempt_roles", ComponentInteractionDataKind::RoleSelect { values }) => {
+            settings.quota_exempt_roles = values.iter().map(|role| role.get()).collect();
+        }
+        ("save_setup", _) => {
+            return save_setup(ctx, component, settings).await;
+        }
+        ("cancel_setup", _) => {
+            component.defer(&ctx.http).await.map_err(|e| {
+                error!("Failed to defer interaction: {:?}", e);
+                DiscordError::DeferFailed(e.to_string())
+            })?;
+
+            let edit = EditMessage::new().components(vec![]).embed(
+                themed_embed()
+                    .title(tr!("Setup Cancelled"))
+                    .description(tr!("Nothing was changed.")),
+            );
+            return edit_message(ctx, &component.token, message, edit)
+                .await
+                .map_err(|e| {
+                    error!("Failed to edit message: {:?}", e);
+                    DiscordError::EditMessageFailed(e.to_string())
+                });
+        }
+        _ => {
+            warn!("Recieved invalid component data, ignoring");
+            return Err(DiscordError::InvalidComponentData);
+        }
+    }
+
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    let (embed, components) = setup_panel(&settings);
+    edit_message(
+        ctx,
+        &component.token,
+        message,
+        EditMessage::new().embed(embed).components(components),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to edit message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })
+}
+
+/// Runs the self-test, then saves the settings and shows the results
+async fn save_setup(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    settings: Settings,
+) -> Result<()> {
+    component.defer(&ctx.http).await.map_err(|e| {
+        error!("Failed to defer interaction: {:?}", e);
+        DiscordError::DeferFailed(e.to_string())
+    })?;
+
+    // Checking the drives takes a few seconds each
+    edit_message(
+        ctx,
+        &component.token,
+        &component.message,
+        EditMessage::new().components(vec![]).embed(
+            themed_embed().title(tr!("Saving Setup")).description(tr!(
+                "Running the self-test, this takes a few seconds for each drive..."
+            )),
+        ),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to edit message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })?;
+
+    let output_dir = makemkv(ctx).await?.output_dir.clone();
+    let mut checks = run_checks(&output_dir).await;
+    checks.extend(permission_checks(
+        component.app_permissions,
+        response_options().threads,
+    ));
+
+    SETTINGS.lock().await.replace(settings.clone())?;
+    info!("{} saved the setup: {:?}", component.user.name, settings);
+
+    let embed = settings_fields(
+        themed_embed()
+            .title(tr!("Setup Saved"))
+            .description(format!(
+                "{}\n\n{}",
+                tr!("Restart cord-ripper for the new settings to take effect."),
+                checks_report(&checks)
+            )),
+        &settings,
+    );
+
+    edit_message(
+        ctx,
+        &component.token,
+        &component.message,
+        EditMessage::new().components(vec![]).embed(embed),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to edit message: {:?}", e);
+        DiscordError::EditMessageFailed(e.to_string())
+    })
+}
+
+/// Sets the naming templates, leaving the ones that match the default unset so they keep
+/// following it
+fn set_templates(settings: &mut Settings, naming: NamingTemplate) {
+    let default = NamingTemplate::default();
+    settings.movie_template = (naming.movie != default.movie).then_some(naming.movie);
+    settings.show_template = (naming.show != default.show).then_some(naming.show);
+}
+
+/// The naming templates of the settings, with the defaults filled in
+fn templates(settings: &Settings) -> NamingTemplate {
+    let default = NamingTemplate::default();
+    NamingTemplate {
+        movie: settings.movie_template.clone().unwrap_or(default.movie),
+        show: settings.show_template.clone().unwrap_or(default.show),
+    }
+}
+
+/// Adds the settings to an embed, in the fields `panel_settings` reads them back from
+fn settings_fields(embed: CreateEmbed, settings: &Settings) -> CreateEmbed {
+    let naming = templates(settings);
+    let alert_channel = settings
+        .alert_channel_id
+        .map_or_else(|| tr!("None"), |channel| format!("<#{channel}>"));
+    let exempt_roles = if settings.quota_exempt_roles.is_empty() {
+        tr!("None")
+    } else {
+        settings
+            .quota_exempt_roles
+            .iter()
+            .map(|role| format!("<@&{role}>"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    embed
+        .field(tr!("Movie Template"), format!("`{}`", naming.movie), false)
+        .field(tr!("Show Template"), format!("`{}`", naming.show), false)
+        .field(tr!("Alert Channel"), alert_channel, true)
+        .field(tr!("Quota Exempt Roles"), exempt_roles, true)
+}
+
+/// Reads the settings being set up back from the panel's embed
+fn panel_settings(embed: Option<&Embed>) -> Settings {
+    let field = |name: String| {
+        embed
+            .and_then(|embed| embed.fields.iter().find(|field| field.name == name))
+            .map(|field| field.value.trim().to_string())
+    };
+    let id = |mention: &str| -> Option<u64> {
+        mention
+            .trim()
+            .trim_start_matches(['<', '#', '@', '&'])
+            .trim_end_matches('>')
+            .parse()
+            .ok()
+    };
+
+    let mut settings = Settings::default();
+    let default = NamingTemplate::default();
+    set_templates(
+        &mut settings,
+        NamingTemplate {
+            movie: field(tr!("Movie Template"))
+                .map_or(default.movie, |value| value.trim_matches('`').to_string()),
+            show: field(tr!("Show Template"))
+                .map_or(default.show, |value| value.trim_matches('`').to_string()),
+        },
+    );
+    settings.alert_channel_id = field(tr!("Alert Channel")).and_then(|value| id(&value));
+    settings.quota_exempt_roles = field(tr!("Quota Exempt Roles"))
+        .map(|value| value.split(',').filter_map(id).collect())
+        .unwrap_or_default();
+    settings
+}
+
+/// Builds the setup panel, the settings as they are so far with a menu to change each
+fn setup_panel(settings: &Settings) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let embed = settings_fields(
+        themed_embed().title(tr!("Setup")).description(tr!(
+            "Nothing changes until the setup is saved.\n1. Pick how rips are laid out in the output directory, or write your own naming templates.\n2. Pick the channel low disk space alerts are posted to.\n3. Pick the roles that aren't held to the rip quotas.\n4. Save, which runs the self-test and writes the settings to the data directory."
+        )),
+        settings,
+    );
+
+    let naming = templates(settings);
+    let layout_options = LAYOUTS
+        .iter()
+        .map(|&layout| {
+            let (label, preset) = match layout {
+                "media_server" => (tr!("Plex and Jellyfin"), NamingTemplate::media_server()),
+                "flat" => (tr!("Flat"), NamingTemplate::flat()),
+                _ => (tr!("Movies and Shows (Default)"), NamingTemplate::default()),
+            };
+            CreateSelectMenuOption::new(label, layout)
+                .description(preset.show.clone())
+                .default_selection(preset.movie == naming.movie && preset.show == naming.show)
+        })
+        .chain(std::iter::once(
+            CreateSelectMenuOption::new(tr!("Custom Templates"), "custom")
+                .description(tr!("Write your own naming templates")),
+        ))
+        .collect();
+
+    let components = vec![
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "setup_layout",
+                CreateSelectMenuKind::String {
+                    options: layout_options,
+                },
+            )
+            .placeholder(tr!("Output directory layout")),
+        ),
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "setup_alert_channel",
+                CreateSelectMenuKind::Channel {
+                    channel_types: Some(vec![ChannelType::Text]),
+                    default_channels: settings
+                        .alert_channel_id
+                        .map(|channel| vec![ChannelId::new(channel)]),
+                },
+            )
+            .placeholder(tr!("Alert channel"))
+            .min_values(0)
+            .max_values(1),
+        ),
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "setup_exempt_roles",
+                CreateSelectMenuKind::Role {
+                    default_roles: Some(
+                        settings
+                            .quota_exempt_roles
+                            .iter()
+                            .map(|&role| RoleId::new(role))
+                            .collect(),
+                    ),
+                },
+            )
+            .placeholder(tr!("Roles exempt from rip quotas"))
+            .min_values(0)
+            .max_values(25),
+        ),
+        CreateActionRow::Buttons(vec![
+            CreateButton::new("save_setup")
+                .label(tr!("Save"))
+                .style(ButtonStyle::Success),
+            CreateButton::new("cancel_setup")
+                .label(tr!("Cancel"))
+                .style(ButtonStyle::Secondary),
+        ]),
+    ];
+
+    (embed, components)
+}
+
+/// Builds the modal custom naming templates are written in, filled in with the current ones
+fn templates_modal(settings: &Settings) -> CreateModal {
+    let naming = templates(settings);
+    CreateModal::new("get_setup_templates", tr!("Naming Templates")).components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(
+                InputTextStyle::Short,
+                tr!("Movie Template"),
+                "movie_template",
+            )
+            .value(naming.movie)
+            .required(true),
+        ),
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, tr!("Show Template"), "show_template")
+                .value(naming.show)
+                .required(true),
+        ),
+    ])
+}
@@ -0,0 +1,116 @@
+use serenity::all::{
+    Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage,
+    Interaction,
+};
+
+use crate::discord::commands::command_helpers::makemkv;
+use crate::discord::commands::rip::format_size;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::ephemeral;
+use crate::discord::errors::{DiscordError, Result};
+use crate::disk::free_space;
+use crate::makemkv::makemkv_helpers::makemkvcon_command;
+use crate::makemkv::rippers::rippers;
+use crate::queue::queue_core::JobState;
+use crate::queue::QUEUE;
+
+use crate::{debug, error, tr, trace, warn};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered status command");
+    CreateCommand::new("status").description(tr!("View the status of the server"))
+}
+
+/// Runs the `status` command
+///
+/// Shows the version of `MakeMKV` found at startup and how it is run, the agents drives are
+/// listed from, how many jobs are running or waiting, and the free space of the output
+/// directory.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if Discord rejects the response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running status command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    let (version, output_dir) = {
+        let makemkv = makemkv(ctx).await?;
+        (makemkv.version.clone(), makemkv.output_dir.clone())
+    };
+
+    let makemkv = match version {
+        Some(version) => tr!(
+            "Version {version}, run as `{command}`",
+            version = version,
+            command = makemkvcon_command().join(" ")
+        ),
+        None => tr!("Not used on this machine"),
+    };
+
+    let agents = rippers()
+        .iter()
+        .filter_map(|ripper| ripper.host().map(String::from))
+        .collect::<Vec<String>>();
+    let agents = if agents.is_empty() {
+        tr!("None")
+    } else {
+        agents.join(", ")
+    };
+
+    let (running, waiting) = {
+        let queue = QUEUE.lock().await;
+        let jobs = queue.jobs();
+        let running = jobs
+            .iter()
+            .filter(|job| job.state != JobState::Queued)
+            .count();
+        (running, jobs.len() - running)
+    };
+
+    let free_space = match free_space(&output_dir).await {
+        Ok(bytes) => format_size(bytes),
+        Err(e) => {
+            warn!("Failed to read free space: {}", e);
+            tr!("Unknown")
+        }
+    };
+
+    let embed = themed_embed()
+        .title(tr!("Server Status"))
+        .field(tr!("MakeMKV"), makemkv, false)
+        .field(tr!("Agents"), agents, false)
+        .field(
+            tr!("Jobs"),
+            tr!(
+                "{running} running, {waiting} waiting",
+                running = running,
+                waiting = waiting
+            ),
+            true,
+        )
+        .field(tr!("Free Space"), free_space, true);
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(ephemeral())
+                    .embed(embed),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create response: {:?}", e);
+            DiscordError::CommandInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
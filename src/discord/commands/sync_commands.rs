@@ -0,0 +1,94 @@
+use serenity::all::{
+    Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage,
+    EditInteractionResponse, Interaction, Permissions,
+};
+
+use crate::discord::bot::bot_core::register_commands;
+use crate::discord::commands::command_helpers::is_admin;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::errors::{DiscordError, Result};
+
+use crate::{debug, error, info, tr, trace, warn};
+
+pub fn register() -> CreateCommand {
+    debug!("Registered sync_commands command");
+    CreateCommand::new("sync_commands")
+        .description(tr!("Register the bot's commands with the server again"))
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+}
+
+/// Runs the `sync_commands` command
+///
+/// Commands are registered when the bot connects, this registers them again without a
+/// restart, such as after Discord lost track of them or their options changed.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the user isn't an administrator, or Discord rejects the
+/// response.
+pub async fn run(ctx: &Context, interaction: &Interaction) -> Result<()> {
+    debug!("Running sync_commands command");
+
+    let Interaction::Command(command) = interaction else {
+        debug!("Unknown interaction type: {:?}, ignoring", interaction);
+        return Err(DiscordError::InvalidInteractionCall);
+    };
+
+    trace!("Got request from command interaction");
+
+    if !is_admin(command.member.as_deref()) {
+        warn!(
+            "{} tried to sync the commands without permission",
+            command.user.name
+        );
+        return Err(DiscordError::PermissionDenied);
+    }
+
+    // Commands are only registered with the server, never globally
+    let Some(guild_id) = command.guild_id else {
+        return Err(DiscordError::InvalidInput(tr!(
+            "Commands can only be synced from the server."
+        )));
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Defer(
+                CreateInteractionResponseMessage::new().ephemeral(true),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to defer response: {:?}", e);
+            DiscordError::DeferFailed(e.to_string())
+        })?;
+
+    let embed = match register_commands(&ctx.http, guild_id).await {
+        Ok(count) => {
+            info!("{} synced {} commands", command.user.name, count);
+            themed_embed()
+                .title(tr!("Commands Synced"))
+                .description(tr!(
+                    "{count} commands were registered, Discord may take a moment to show changes.",
+                    count = count
+                ))
+        }
+        Err(e) => {
+            error!("Failed to sync the commands: {}", e);
+            themed_embed()
+                .title(tr!("Sync Failed"))
+                .description(tr!("Failed to register the commands: {error}", error = e))
+        }
+    };
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+        .await
+        .map_err(|e| {
+            error!("Failed to edit response: {:?}", e);
+            DiscordError::EditResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
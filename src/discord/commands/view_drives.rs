@@ -1,15 +1,18 @@
 use serenity::all::{
-    Context, CreateCommand, CreateEmbed, CreateInteractionResponse,
-    CreateInteractionResponseMessage, Interaction,
+    Context, CreateCommand, CreateInteractionResponse, CreateInteractionResponseMessage,
+    Interaction,
 };
 
+use crate::collection::COLLECTION;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::components::messages::ephemeral;
 use crate::makemkv::get_drives;
 
-use crate::{debug, trace};
+use crate::{debug, tr, trace};
 
 pub fn register() -> CreateCommand {
     debug!("Regisered view_drives command");
-    CreateCommand::new("view_drives").description("View the drives on the server")
+    CreateCommand::new("view_drives").description(tr!("View the drives on the server"))
 }
 
 pub async fn run(ctx: &Context, interaction: &Interaction) {
@@ -19,32 +22,57 @@ pub async fn run(ctx: &Context, interaction: &Interaction) {
 
     match interaction {
         Interaction::Command(command) => {
+            let collection = COLLECTION.lock().await;
             let mut fields = Vec::new();
 
             for drive in drives {
-                let title = if drive.drive_media_title.is_empty() {
-                    "No disc inserted".to_string()
+                let mut title = if drive.drive_media_title.is_empty() {
+                    tr!("No disc inserted")
                 } else {
-                    format!("Title: {}", drive.drive_media_title)
+                    tr!("Title: {title}", title = drive.drive_media_title)
                 };
-                fields.push((
-                    format!("Drive {}: {}", drive.drive_number, drive.drive_model),
-                    title,
-                    false,
-                ));
+                if let Some(entry) = collection.find_label(&drive.drive_media_title) {
+                    title.push('\n');
+                    title.push_str(&tr!(
+                        "**On the collection list** as {title} (#{id})",
+                        title = entry.title,
+                        id = entry.id
+                    ));
+                }
+                let mut name = match &drive.alias {
+                    Some(alias) => tr!(
+                        "Drive {drive} ({alias}): {model}",
+                        drive = drive.drive_number,
+                        alias = alias,
+                        model = drive.drive_model
+                    ),
+                    None => tr!(
+                        "Drive {drive}: {model}",
+                        drive = drive.drive_number,
+                        model = drive.drive_model
+                    ),
+                };
+                if let Some(host) = &drive.host {
+                    name = tr!("{name} on {host}", name = name, host = host);
+                }
+                fields.push((name, title, false));
             }
+            drop(collection);
 
             command
                 .create_response(
                     &ctx.http,
                     CreateInteractionResponse::Message(
-                        CreateInteractionResponseMessage::default().add_embed(
-                            CreateEmbed::default()
-                                .title("Available Drives")
-                                .description("Here are the drives available on the server:")
-                                .color(0xfe0000)
-                                .fields(fields),
-                        ),
+                        CreateInteractionResponseMessage::default()
+                            .ephemeral(ephemeral())
+                            .add_embed(
+                                themed_embed()
+                                    .title(tr!("Available Drives"))
+                                    .description(tr!(
+                                        "Here are the drives available on the server:"
+                                    ))
+                                    .fields(fields),
+                            ),
                     ),
                 )
                 .await
@@ -0,0 +1,66 @@
+//! # Custom Ids
+//!
+//! Discord hands back the custom id of whatever component or modal was used, which is all
+//! the bot has to tell them apart. Components that belong to one job, such as the cancel
+//! button of a rip, carry the id of the job after their name, as `cancel_rip:42`, so two
+//! rips running at once can't be mixed up.
+//!
+//! ## Overview
+//!
+//! - **`with_id`**: Builds a custom id carrying an id.
+//!
+//! - **`split`**: Splits a custom id into its name and the id it carries, if any.
+//!
+//! ## Notes
+//!
+//! - Interactions are routed by name, the id is only looked at by what handles them. Every
+//!   handler reads custom ids with `name` or `split`, so any of them can carry an id.
+//! - The steps of a flow, such as the menus of `/rip`, don't carry an id. A flow is on one
+//!   message, which Discord sends along with every interaction on it, so the message id
+//!   already tells two flows apart, see `flows`.
+//! - Names never contain the separator, so custom ids without an id, such as those of
+//!   messages sent before ids were added, are split into just their name.
+
+use std::fmt::Display;
+
+/// What separates the name of a custom id from the id it carries
+const SEPARATOR: char = ':';
+
+/// Builds the custom id of `name` for `id`, such as `cancel_rip:42`
+pub fn with_id(name: &str, id: impl Display) -> String {
+    format!("{name}{SEPARATOR}{id}")
+}
+
+/// Splits a custom id into its name and the id it carries, if any
+#[must_use]
+pub fn split(custom_id: &str) -> (&str, Option<&str>) {
+    match custom_id.split_once(SEPARATOR) {
+        Some((name, id)) => (name, Some(id)),
+        None => (custom_id, None),
+    }
+}
+
+/// The name of a custom id, without the id it carries
+#[must_use]
+pub fn name(custom_id: &str) -> &str {
+    split(custom_id).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_carried_after_the_name() {
+        let custom_id = with_id("cancel_rip", 42);
+        assert_eq!(custom_id, "cancel_rip:42");
+        assert_eq!(split(&custom_id), ("cancel_rip", Some("42")));
+        assert_eq!(name(&custom_id), "cancel_rip");
+    }
+
+    #[test]
+    fn custom_ids_without_an_id_are_just_a_name() {
+        assert_eq!(split("cancel_rip"), ("cancel_rip", None));
+        assert_eq!(name("select_disc_to_rip"), "select_disc_to_rip");
+    }
+}
@@ -1 +1,139 @@
+//! # Embeds
+//!
+//! Every embed the bot sends is started from `themed_embed`, so they all share the look
+//! set by the theme. The theme is configured once at startup from the command line, and
+//! defaults to the red accent with timestamps and no footer or thumbnail.
 
+use std::sync::OnceLock;
+
+use serenity::all::{CreateEmbed, CreateEmbedFooter, Timestamp};
+
+use crate::{debug, warn};
+
+/// The accent color used when none is configured
+pub const DEFAULT_COLOR: u32 = 0xfe_0000;
+
+/// How embeds look, so the bot can match a server's branding.
+///
+/// # Fields
+///
+/// * `color` - The accent color down the side of each embed.
+/// * `footer` - Optional text shown at the bottom of each embed.
+/// * `footer_icon_url` - Optional icon shown next to the footer text.
+/// * `thumbnail_url` - Optional image shown in the corner of each embed.
+/// * `timestamps` - Whether embeds show the time they were sent.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub color: u32,
+    pub footer: Option<String>,
+    pub footer_icon_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub timestamps: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            color: DEFAULT_COLOR,
+            footer: None,
+            footer_icon_url: None,
+            thumbnail_url: None,
+            timestamps: true,
+        }
+    }
+}
+
+/// Global theme, the default until `set_theme` is called
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Set the theme of every embed, this can only be done once at startup
+pub fn set_theme(theme: Theme) {
+    debug!("Embed theme set to: {:?}", theme);
+    if THEME.set(theme).is_err() {
+        warn!("Embed theme was already set, ignoring");
+    }
+}
+
+/// The configured theme
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+/// Starts an embed with the configured theme applied
+pub fn themed_embed() -> CreateEmbed {
+    let theme = theme();
+    let mut embed = CreateEmbed::new().color(theme.color);
+
+    if let Some(footer) = &theme.footer {
+        let mut footer = CreateEmbedFooter::new(footer);
+        if let Some(icon_url) = &theme.footer_icon_url {
+            footer = footer.icon_url(icon_url);
+        }
+        embed = embed.footer(footer);
+    }
+    if let Some(thumbnail_url) = &theme.thumbnail_url {
+        embed = embed.thumbnail(thumbnail_url);
+    }
+    if theme.timestamps {
+        embed = embed.timestamp(Timestamp::now());
+    }
+
+    embed
+}
+
+/// How many blocks make up a progress bar
+const PROGRESS_BAR_WIDTH: usize = 12;
+
+/// Draws how far along something is, e.g. `██████░░░░░░ 54%`, for `fraction` from 0 to 1
+#[must_use]
+// The fraction is clamped, so the results are small and never negative
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn progress_bar(fraction: f64) -> String {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let filled = (fraction * PROGRESS_BAR_WIDTH as f64).round() as usize;
+    format!(
+        "{}{} {}%",
+        "█".repeat(filled),
+        "░".repeat(PROGRESS_BAR_WIDTH - filled),
+        (fraction * 100.0).floor() as u32
+    )
+}
+
+/// Parses a hex color such as `#1e90ff` or `1e90ff`
+#[must_use]
+pub fn parse_color(color: &str) -> Option<u32> {
+    let hex = color
+        .trim()
+        .trim_start_matches('#')
+        .trim_start_matches("0x");
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_color("#1e90ff"), Some(0x1e_90ff));
+        assert_eq!(parse_color("FE0000"), Some(DEFAULT_COLOR));
+        assert_eq!(parse_color("0x00ff00"), Some(0xff00));
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("red"), None);
+    }
+
+    #[test]
+    fn draws_progress_bars() {
+        assert_eq!(progress_bar(0.0), "░░░░░░░░░░░░ 0%");
+        assert_eq!(progress_bar(0.54), "██████░░░░░░ 54%");
+        assert_eq!(progress_bar(0.999), "████████████ 99%");
+        assert_eq!(progress_bar(1.5), "████████████ 100%");
+    }
+}
@@ -0,0 +1,317 @@
+//! # Error Embeds
+//!
+//! Turns errors into embeds users can act on: a title naming what went wrong, an
+//! explanation, and where there is one, the next step to take. Every failure shown in
+//! Discord goes through `present`, so distinguishable failures read differently instead of
+//! all asking the user to try again.
+//!
+//! ## Notes
+//!
+//! - The explanations come from the `user_message` of each error type, this only adds the
+//!   title and next step around them.
+
+use serenity::all::CreateEmbed;
+
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::errors::DiscordError;
+use crate::makemkv::errors::MakeMkvError;
+use crate::tr;
+
+/// How an error is shown to users.
+///
+/// # Fields
+///
+/// * `title` - What went wrong, in a few words.
+/// * `explanation` - Why it went wrong, as far as is known.
+/// * `next_step` - What the user can do about it, if anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorPresentation {
+    pub title: String,
+    pub explanation: String,
+    pub next_step: Option<String>,
+}
+
+impl ErrorPresentation {
+    fn new(title: String, explanation: String, next_step: Option<String>) -> Self {
+        ErrorPresentation {
+            title,
+            explanation,
+            next_step,
+        }
+    }
+}
+
+/// Describes an error for users
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn present(error: &DiscordError) -> ErrorPresentation {
+    let explanation = error.user_message();
+
+    match error {
+        DiscordError::Reported(error) => present(error),
+        DiscordError::MakeMkvError(error) => present_makemkv(error),
+        DiscordError::AudioError(_) => ErrorPresentation::new(
+            tr!("Audio Rip Failed"),
+            explanation,
+            Some(tr!("Check the disc is an audio CD and clean, then try again.")),
+        ),
+        DiscordError::ArchiveError(_) => ErrorPresentation::new(
+            tr!("Archive Failed"),
+            explanation,
+            Some(tr!(
+                "Archive the disc again with the same label to resume where it stopped."
+            )),
+        ),
+        DiscordError::PreviewError(_) => ErrorPresentation::new(
+            tr!("Previews Failed"),
+            explanation,
+            Some(tr!(
+                "Titles can still be picked by their duration and size, or try again."
+            )),
+        ),
+        DiscordError::HistoryError(_) | DiscordError::LibraryError(_) => ErrorPresentation::new(
+            tr!("Library Error"),
+            tr!("The record of ripped files couldn't be read or updated."),
+            Some(tr!(
+                "Check the output directory can be written to, then run `/rescan`."
+            )),
+        ),
+        DiscordError::SettingsError(_) => ErrorPresentation::new(
+            tr!("Setup Failed"),
+            explanation,
+            Some(tr!(
+                "Check the templates, and that the data directory can be written to, then try again."
+            )),
+        ),
+        DiscordError::AuditError(_) => ErrorPresentation::new(
+            tr!("Audit Log Error"),
+            tr!("The audit log couldn't be read."),
+            Some(tr!("Check the data directory can be read, then try again.")),
+        ),
+        DiscordError::CollectionError(_) => ErrorPresentation::new(
+            tr!("Collection Error"),
+            explanation,
+            Some(tr!("Run `/collection list` to see the discs and their numbers.")),
+        ),
+        DiscordError::QueueError(_) => ErrorPresentation::new(
+            tr!("Queue Changed"),
+            tr!("The job has already started or finished."),
+            Some(tr!("Run `/queue` again to see the jobs as they are now.")),
+        ),
+        DiscordError::InvalidComponentData => ErrorPresentation::new(
+            tr!("Invalid Details"),
+            explanation,
+            Some(tr!("Check what was entered, numbers such as the disc and season must be whole numbers.")),
+        ),
+        DiscordError::QuotaError(_) => ErrorPresentation::new(
+            tr!("Rip Limit Reached"),
+            explanation,
+            Some(tr!("Ask a server administrator if the rip can't wait.")),
+        ),
+        DiscordError::InvalidInput(_) => ErrorPresentation::new(
+            tr!("Check the Details"),
+            explanation,
+            Some(tr!("Open the form again and fix the details above.")),
+        ),
+        DiscordError::PermissionDenied => ErrorPresentation::new(
+            tr!("Permission Denied"),
+            explanation,
+            Some(tr!("Ask a server administrator to do it.")),
+        ),
+        DiscordError::TaskCancelled => ErrorPresentation::new(tr!("Cancelled"), explanation, None),
+        DiscordError::NotOwner => ErrorPresentation::new(
+            tr!("Not Your Rip"),
+            explanation,
+            Some(tr!("Run the command yourself to start a rip of your own.")),
+        ),
+        DiscordError::JobNotRunning => ErrorPresentation::new(
+            tr!("Job Finished"),
+            explanation,
+            Some(tr!("Run `/queue` to see the jobs that are running.")),
+        ),
+        DiscordError::InvalidInteractionCall => ErrorPresentation::new(
+            tr!("Unknown Action"),
+            tr!("This menu or button isn't recognized, it may be from before the bot restarted."),
+            Some(tr!("Run the command again.")),
+        ),
+        DiscordError::HttpRequestFailed(_)
+        | DiscordError::CommandInteractionResponseFailed(_)
+        | DiscordError::MessageInteractionResponseFailed(_)
+        | DiscordError::ComponentInteractionResponseFailed(_)
+        | DiscordError::EditResponseFailed(_)
+        | DiscordError::EditMessageFailed(_)
+        | DiscordError::SendMessageFailed(_)
+        | DiscordError::DeferFailed(_)
+        | DiscordError::InvalidToken => ErrorPresentation::new(
+            tr!("Discord Error"),
+            tr!("Discord didn't accept a message from the bot."),
+            Some(tr!("Try again in a moment, Discord may be having issues.")),
+        ),
+        DiscordError::Unexpected(_) => ErrorPresentation::new(
+            tr!("Something Went Wrong"),
+            explanation,
+            Some(tr!("Try again, if it keeps happening check the server's logs.")),
+        ),
+    }
+}
+
+/// Describes a `MakeMKV` error for users
+#[allow(clippy::too_many_lines)]
+fn present_makemkv(error: &MakeMkvError) -> ErrorPresentation {
+    let (title, next_step) = match error {
+        MakeMkvError::DriveInUseError(_) | MakeMkvError::LockError => (
+            tr!("Drive Busy"),
+            Some(tr!(
+                "Wait for the current rip to finish, `/queue` shows what is running."
+            )),
+        ),
+        MakeMkvError::FileAlreadyExists(_) => (
+            tr!("Already Ripped"),
+            Some(tr!(
+                "Fix its name with `/rename`, or remove it with `/delete_rip` to rip it again."
+            )),
+        ),
+        MakeMkvError::DriveError | MakeMkvError::DiscOpenFailed => (
+            tr!("Drive Error"),
+            Some(tr!(
+                "Check a disc is inserted, then try again. Cycling the drive tray often helps."
+            )),
+        ),
+        MakeMkvError::ReadError(_)
+        | MakeMkvError::HashCheckFailed(_)
+        | MakeMkvError::FailedToSaveDisc
+        | MakeMkvError::CopyIncomplete { .. } => (
+            tr!("Disc Read Error"),
+            Some(tr!("Clean the disc and try again.")),
+        ),
+        MakeMkvError::VersionTooOld | MakeMkvError::RegistrationExpired => (
+            tr!("MakeMKV Needs Updating"),
+            Some(tr!(
+                "Update MakeMKV or its key on the server, then try again."
+            )),
+        ),
+        MakeMkvError::NoDrivesFound => (
+            tr!("No Drives Found"),
+            Some(tr!(
+                "Check the drives are connected to the server, then run `/view_drives`."
+            )),
+        ),
+        MakeMkvError::DriveDisconnected(_) => (
+            tr!("Drive Disconnected"),
+            Some(tr!(
+                "Reconnect the drive, check it is listed by `/view_drives`, then rip the disc again."
+            )),
+        ),
+        MakeMkvError::AgentError(..) => (
+            tr!("Agent Unreachable"),
+            Some(tr!(
+                "Check the agent is running and can be reached from the bot, then try again."
+            )),
+        ),
+        MakeMkvError::MissingCapability(..) => (
+            tr!("Wrong Drive"),
+            Some(tr!(
+                "Move the disc to a drive that can read it, then run `/rip` again."
+            )),
+        ),
+        MakeMkvError::EjectError(..) => (tr!("Eject Failed"), None),
+        MakeMkvError::Cancelled => (tr!("Rip Cancelled"), None),
+        MakeMkvError::UnsafePath(_) => (
+            tr!("Output Directory Error"),
+            Some(tr!("An administrator can fix the naming templates with `/setup`.")),
+        ),
+        MakeMkvError::WriteVerificationFailed(_) => (
+            tr!("Copy Failed"),
+            Some(tr!(
+                "Check the library mount is healthy, the rip can be run again once it is."
+            )),
+        ),
+        MakeMkvError::ValidationFailed(_) => (
+            tr!("Validation Failed"),
+            Some(tr!(
+                "The file wasn't saved. Clean the disc and rip it again, a damaged disc can be cut short."
+            )),
+        ),
+        MakeMkvError::SplitError(_) => (
+            tr!("Split Failed"),
+            Some(tr!(
+                "Install MKVToolNix on the server, the title is kept unsplit until then."
+            )),
+        ),
+        MakeMkvError::FileNotFoundError(_)
+        | MakeMkvError::PermissionDenied(_)
+        | MakeMkvError::OutputDirError
+        | MakeMkvError::TempDirError => (
+            tr!("Output Directory Error"),
+            Some(tr!(
+                "Check the output directory exists and can be written to by the bot."
+            )),
+        ),
+        MakeMkvError::CommandExecutionError(_) => (
+            tr!("MakeMKV Not Found"),
+            Some(tr!(
+                "Check MakeMKV is installed on the server and `makemkvcon` is on the path."
+            )),
+        ),
+        MakeMkvError::MakeMkvNotFound(..) => (
+            tr!("MakeMKV Not Found"),
+            Some(tr!(
+                "Check MakeMKV is installed on the server, or set `--makemkvcon` to how it is run."
+            )),
+        ),
+        MakeMkvError::InvalidOutputFormat(_)
+        | MakeMkvError::ParseError(_)
+        | MakeMkvError::InvalidEpisodePattern(_)
+        | MakeMkvError::UnknownError => (
+            tr!("Rip Failed"),
+            Some(tr!(
+                "Try again, if it keeps happening check the server's logs."
+            )),
+        ),
+    };
+
+    ErrorPresentation::new(title, error.user_message(), next_step)
+}
+
+/// Builds the embed an error is shown with
+pub fn error_embed(error: &DiscordError) -> CreateEmbed {
+    let presentation = present(error);
+
+    themed_embed()
+        .title(presentation.title)
+        .description(presentation.explanation)
+        .fields(
+            presentation
+                .next_step
+                .map(|next_step| (tr!("What To Do"), next_step, false)),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_failures_apart() {
+        let busy = present(&DiscordError::MakeMkvError(MakeMkvError::DriveInUseError(
+            1,
+        )));
+        let scratched = present(&DiscordError::MakeMkvError(MakeMkvError::ReadError(
+            "bad sector".to_string(),
+        )));
+        let denied = present(&DiscordError::PermissionDenied);
+
+        assert_ne!(busy.title, scratched.title);
+        assert_ne!(busy.title, denied.title);
+        assert!(busy.next_step.is_some());
+    }
+
+    #[test]
+    fn presents_reported_errors_as_the_error() {
+        let error = || DiscordError::MakeMkvError(MakeMkvError::DiscOpenFailed);
+        assert_eq!(
+            present(&DiscordError::Reported(Box::new(error()))),
+            present(&error())
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! # Messages
+//!
+//! Keeps the channel tidy while commands run. Responses can be made ephemeral, so the
+//! select menus and modals of a command are only shown to whoever ran it, and the
+//! interactive message a rip was set up on can be collapsed or deleted once the rip starts,
+//! so the channel only holds its progress and summary. Rips can also get a thread of their
+//! own, which their progress and summary are posted to, so each rip has a log that can be
+//! followed or muted on its own.
+//!
+//! ## Notes
+//!
+//! - Discord only lets ephemeral messages be edited through the interaction that is
+//!   responding to them, so every edit of a selection message goes through `edit_message`
+//!   with the token of the current interaction.
+//! - Interaction tokens expire after 15 minutes, which rips often outlast, so a rip started
+//!   from an ephemeral message shows its progress on a new message in the channel.
+//! - A rip's thread is started from the message it was set up on where that message stays
+//!   in the channel, so the thread is shown right under it.
+
+use std::sync::OnceLock;
+
+use serenity::all::{
+    AutoArchiveDuration, Builder, ChannelId, ChannelType, Context, CreateAttachment, CreateEmbed,
+    CreateMessage, CreateThread, EditInteractionResponse, EditMessage, Message, MessageFlags,
+};
+
+use crate::discord::components::embeds::themed_embed;
+use crate::{debug, tr, warn};
+
+/// What happens to the message a rip was set up on once the rip starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionCleanup {
+    /// The message becomes the progress message, or is left as is if it is ephemeral
+    #[default]
+    Keep,
+    /// The message is shrunk to a note pointing at the progress message
+    Collapse,
+    /// The message is deleted
+    Delete,
+}
+
+impl SelectionCleanup {
+    /// Convert a cleanup name, such as `collapse`, to its `SelectionCleanup`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<SelectionCleanup> {
+        match name.to_lowercase().as_str() {
+            "keep" => Some(SelectionCleanup::Keep),
+            "collapse" => Some(SelectionCleanup::Collapse),
+            "delete" => Some(SelectionCleanup::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// How the bot responds to commands.
+///
+/// # Fields
+///
+/// * `ephemeral` - Whether command responses are only shown to whoever ran the command.
+/// * `cleanup` - What happens to the message a rip was set up on once the rip starts.
+/// * `threads` - Whether each rip posts its progress and summary to a thread of its own.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResponseOptions {
+    pub ephemeral: bool,
+    pub cleanup: SelectionCleanup,
+    pub threads: bool,
+}
+
+/// Global response options, the defaults until `set_response_options` is called
+static RESPONSE_OPTIONS: OnceLock<ResponseOptions> = OnceLock::new();
+
+/// Set how the bot responds to commands, this can only be done once at startup
+pub fn set_response_options(options: ResponseOptions) {
+    debug!("Response options set to: {:?}", options);
+    if RESPONSE_OPTIONS.set(options).is_err() {
+        warn!("Response options were already set, ignoring");
+    }
+}
+
+/// The configured response options
+pub fn response_options() -> ResponseOptions {
+    *RESPONSE_OPTIONS.get_or_init(ResponseOptions::default)
+}
+
+/// Whether command responses should be ephemeral, for passing to
+/// `CreateInteractionResponseMessage::ephemeral`
+#[must_use]
+pub fn ephemeral() -> bool {
+    response_options().ephemeral
+}
+
+fn is_ephemeral(message: &Message) -> bool {
+    message
+        .flags
+        .is_some_and(|flags| flags.contains(MessageFlags::EPHEMERAL))
+}
+
+/// Edits the message a component or modal was used on. Ephemeral messages are edited
+/// through the interaction, with its `token`, everything else is edited directly.
+///
+/// # Errors
+///
+/// Returns an error if Discord rejects the edit.
+pub async fn edit_message(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    edit: EditMessage,
+) -> serenity::Result<()> {
+    if is_ephemeral(message) {
+        ctx.http
+            .edit_original_interaction_response(token, &edit, vec![])
+            .await?;
+    } else {
+        message.clone().edit(&ctx.http, edit).await?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the embeds and attachments of the message a component was used on, see
+/// `edit_message`. Attachments can't be sent through an `EditMessage` to an ephemeral
+/// message, so they are passed separately.
+///
+/// # Errors
+///
+/// Returns an error if Discord rejects the edit.
+pub async fn edit_message_attachments(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    embeds: Vec<CreateEmbed>,
+    attachments: Vec<CreateAttachment>,
+) -> serenity::Result<()> {
+    if is_ephemeral(message) {
+        let edit = attachments.into_iter().fold(
+            EditInteractionResponse::new().clear_attachments(),
+            EditInteractionResponse::new_attachment,
+        );
+        edit.embeds(embeds).execute(&ctx.http, token).await?;
+    } else {
+        let edit = attachments.into_iter().fold(
+            EditMessage::new().remove_all_attachments(),
+            EditMessage::new_attachment,
+        );
+        message.clone().edit(&ctx.http, edit.embeds(embeds)).await?;
+    }
+
+    Ok(())
+}
+
+/// Discord's limit on the length of a thread name
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+/// Opens a thread named `name` for a job set up on `message`, returning the channel its
+/// messages should be posted to. Falls back to the channel of `message` if the thread can't
+/// be created, so the job can still go ahead.
+async fn job_channel(ctx: &Context, message: &Message, name: &str) -> ChannelId {
+    let options = response_options();
+    if !options.threads {
+        return message.channel_id;
+    }
+
+    let name: String = name.chars().take(MAX_THREAD_NAME_LENGTH).collect();
+    let thread = CreateThread::new(name).auto_archive_duration(AutoArchiveDuration::OneDay);
+
+    // Ephemeral and deleted messages can't have a thread shown under them
+    let result = if is_ephemeral(message) || options.cleanup == SelectionCleanup::Delete {
+        message
+            .channel_id
+            .create_thread(&ctx.http, thread.kind(ChannelType::PublicThread))
+            .await
+    } else {
+        message
+            .channel_id
+            .create_thread_from_message(&ctx.http, message.id, thread)
+            .await
+    };
+
+    match result {
+        Ok(thread) => {
+            debug!("Created thread {} for {:?}", thread.id, thread.name);
+            thread.id
+        }
+        Err(e) => {
+            warn!("Failed to create thread, using the channel: {:?}", e);
+            message.channel_id
+        }
+    }
+}
+
+/// Gets the message a job named `name`, set up on `message`, should show its progress on,
+/// cleaning up `message` as configured.
+///
+/// Without cleanup or threads the job carries on in `message` itself, unless it is
+/// ephemeral, otherwise a new message is posted for it, in its thread if threads are on.
+///
+/// # Errors
+///
+/// Returns an error if the progress message can't be posted. A failed cleanup is only
+/// logged, the job can go ahead without it.
+pub async fn progress_message(
+    ctx: &Context,
+    token: &str,
+    message: &Message,
+    name: &str,
+) -> serenity::Result<Message> {
+    let ResponseOptions {
+        cleanup, threads, ..
+    } = response_options();
+
+    if cleanup == SelectionCleanup::Keep && !threads && !is_ephemeral(message) {
+        return Ok(message.clone());
+    }
+
+    let progress = job_channel(ctx, message, name)
+        .await
+        .send_message(
+            &ctx.http,
+            CreateMessage::new().embed(
+                themed_embed()
+                    .title(tr!("Starting"))
+                    .description(tr!("Please wait...")),
+            ),
+        )
+        .await?;
+
+    let result = match cleanup {
+        SelectionCleanup::Delete if is_ephemeral(message) => {
+            ctx.http.delete_original_interaction_response(token).await
+        }
+        SelectionCleanup::Delete => message.delete(&ctx.http).await,
+        SelectionCleanup::Collapse => {
+            let title = message
+                .embeds
+                .first()
+                .and_then(|embed| embed.title.clone())
+                .unwrap_or_default();
+
+            edit_message(
+                ctx,
+                token,
+                message,
+                EditMessage::new().components(vec![]).embed(
+                    themed_embed()
+                        .title(title)
+                        .description(tr!("Started, the progress is shown below.")),
+                ),
+            )
+            .await
+        }
+        // Kept messages that get here are ephemeral or had a thread started from them, the
+        // buttons are removed so the job isn't started twice
+        SelectionCleanup::Keep => {
+            edit_message(ctx, token, message, EditMessage::new().components(vec![])).await
+        }
+    };
+
+    if let Err(e) = result {
+        warn!("Failed to clean up selection message: {:?}", e);
+    }
+
+    Ok(progress)
+}
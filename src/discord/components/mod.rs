@@ -1,4 +1,7 @@
 pub mod buttons;
+pub mod custom_ids;
 pub mod embeds;
+pub mod error_embeds;
+pub mod messages;
 pub mod modals;
 pub mod text_inputs;
@@ -1 +1,587 @@
+//! # Modals
+//!
+//! Checks what was typed into a modal as soon as it is submitted, before any command sees
+//! it. A season of "abc" or a title of "AC/DC" would otherwise fail to parse deep in a
+//! flow, or make directories nobody asked for. Instead, the message the modal came from
+//! is shown what is wrong, with a button that opens the modal again to fix it.
+//!
+//! ## Overview
+//!
+//! - **`validate`**: Checks the inputs of a submitted modal against their rules.
+//!
+//! - **`reprompt`**: Shows the problems on the modal's message, with a button to fix them.
+//!
+//! - **`reopen`**: Opens the rejected modal again, with what was entered kept.
+//!
+//! ## Notes
+//!
+//! - Discord doesn't let a modal be answered with another modal, so fixing the inputs
+//!   takes one click on the button added to the message.
+//! - Names that can be fixed, such as ones with a `/` in them, are filled in fixed when the
+//!   modal opens again, so only need confirming. Anything else is left empty, with the
+//!   problem shown in its place.
+//! - The message keeps its components, and the problems are added as an extra embed, so
+//!   commands reading the message find it as it was.
+//! - Every input is a single line, so the reopened modal doesn't need to know the style
+//!   each one was first shown with.
 
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use serenity::all::{
+    ActionRow, ActionRowComponent, Button, ButtonKind, ButtonStyle, ComponentInteraction,
+    ComponentType, Context, CreateActionRow, CreateButton, CreateEmbed, CreateInputText,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateModal, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, InputTextStyle, MessageId, ModalInteraction,
+    SelectMenu,
+};
+use tokio::sync::Mutex;
+
+use crate::barcode::normalize_barcode;
+use crate::discord::components::embeds::themed_embed;
+use crate::discord::errors::{DiscordError, Result};
+use crate::makemkv::naming::{filename_profile, path_safe, MAX_NAME_LENGTH};
+use crate::{debug, error, tr, trace};
+
+/// How long a rejected modal can be fixed for, the same as the interaction token it needs
+const REJECTION_TIMEOUT: Duration = Duration::from_mins(15);
+
+/// The most action rows Discord allows on a message
+const MAX_ACTION_ROWS: usize = 5;
+
+/// Rejected modals waiting to be fixed, by the message they were opened from
+static REJECTED_MODALS: LazyLock<Mutex<HashMap<MessageId, RejectedModal>>> =
+    LazyLock::new(Mutex::default);
+
+/// What an input of a modal has to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    /// A whole number between `min` and `max`, inclusive
+    Number { min: u64, max: u64 },
+    /// Text that becomes part of a file or directory name
+    Name,
+    /// A UPC or EAN barcode
+    Barcode,
+}
+
+/// How an input is labelled and checked.
+///
+/// # Fields
+///
+/// * `label` - The label the input is shown with.
+/// * `rule` - What the input has to be, `None` if it is checked by its command.
+/// * `required` - Whether the input has to be filled in.
+struct InputSpec {
+    label: String,
+    rule: Option<Rule>,
+    required: bool,
+}
+
+/// Something wrong with an input of a submitted modal.
+///
+/// # Fields
+///
+/// * `input` - The custom id of the input.
+/// * `label` - The label of the input.
+/// * `message` - What is wrong with it.
+/// * `fixed` - The value fixed, if it could be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputProblem {
+    pub input: String,
+    pub label: String,
+    pub message: String,
+    pub fixed: Option<String>,
+}
+
+/// A modal that was submitted with problems.
+///
+/// # Fields
+///
+/// * `custom_id` - The custom id of the modal.
+/// * `inputs` - The custom id and value of each input, in order.
+/// * `problems` - What was wrong with the inputs.
+/// * `rejected` - When it was rejected.
+#[derive(Debug, Clone)]
+struct RejectedModal {
+    custom_id: String,
+    inputs: Vec<(String, String)>,
+    problems: Vec<InputProblem>,
+    rejected: Instant,
+}
+
+/// The label and rule of each input used in a modal. The same input has the same rule in
+/// every modal it is in.
+fn input_spec(input: &str) -> InputSpec {
+    let spec = |label: String, rule: Option<Rule>, required: bool| InputSpec {
+        label,
+        rule,
+        required,
+    };
+
+    match input {
+        "disc_number" => spec(
+            tr!("Disc Number"),
+            Some(Rule::Number {
+                min: 0,
+                max: u8::MAX.into(),
+            }),
+            true,
+        ),
+        "title_of_movie" => spec(tr!("Movie Title"), Some(Rule::Name), true),
+        "title_of_show" => spec(tr!("Show Title"), Some(Rule::Name), true),
+        "title" => spec(tr!("Title"), Some(Rule::Name), true),
+        "label" => spec(tr!("Label"), Some(Rule::Name), true),
+        "artist" => spec(tr!("Artist"), Some(Rule::Name), true),
+        "album" => spec(tr!("Album"), Some(Rule::Name), true),
+        "year_of_movie" | "year" => spec(
+            tr!("Year"),
+            Some(Rule::Number {
+                min: 1870,
+                max: 2100,
+            }),
+            false,
+        ),
+        "season" => spec(
+            tr!("Season (0 for specials)"),
+            Some(Rule::Number {
+                min: 0,
+                max: u8::MAX.into(),
+            }),
+            true,
+        ),
+        "episode" | "first_episode" => spec(
+            tr!("Episode"),
+            Some(Rule::Number {
+                min: 0,
+                max: u8::MAX.into(),
+            }),
+            true,
+        ),
+        "absolute_episode" => spec(
+            tr!("Absolute Episode Start (optional)"),
+            Some(Rule::Number {
+                min: 1,
+                max: u16::MAX.into(),
+            }),
+            false,
+        ),
+        "chapters_per_episode" => spec(
+            tr!("Chapters Per Episode (empty to not split)"),
+            Some(Rule::Number {
+                min: 0,
+                max: u16::MAX.into(),
+            }),
+            false,
+        ),
+        "rip_id" => spec(
+            tr!("Rip Id"),
+            Some(Rule::Number {
+                min: 1,
+                max: u64::MAX,
+            }),
+            true,
+        ),
+        "barcode" => spec(tr!("UPC or EAN Barcode"), Some(Rule::Barcode), true),
+        "priority" => spec(tr!("Priority (low, normal or high)"), None, false),
+        "note" => spec(tr!("Note"), None, false),
+        "movie_template" => spec(tr!("Movie Template"), None, true),
+        "show_template" => spec(tr!("Show Template"), None, true),
+        // Extras are named by the title they are ripped from, as `extra_{title}`
+        _ if input.starts_with("extra_") => spec(
+            tr!(
+                "Name of Title {title}",
+                title = input.trim_start_matches("extra_")
+            ),
+            Some(Rule::Name),
+            true,
+        ),
+        // Inputs added without a spec are still kept when the modal is opened again
+        _ => spec(input.to_string(), None, false),
+    }
+}
+
+/// Checks a value against a rule, returning what is wrong with it and the value fixed, if
+/// it can be
+fn check(rule: Rule, value: &str) -> std::result::Result<(), (String, Option<String>)> {
+    match rule {
+        Rule::Number { min, max } => match value.parse::<u64>() {
+            Ok(number) if (min..=max).contains(&number) => Ok(()),
+            Ok(_) if max == u64::MAX => Err((tr!("Has to be {min} or more.", min = min), None)),
+            Ok(_) => Err((
+                tr!("Has to be between {min} and {max}.", min = min, max = max),
+                None,
+            )),
+            Err(_) => Err((tr!("Has to be a whole number, such as 1."), None)),
+        },
+        Rule::Name => {
+            let fixed = path_safe(value);
+            if fixed == value {
+                return Ok(());
+            }
+
+            let reserved = filename_profile().reserved_characters();
+            let message = if value.contains(reserved) {
+                tr!(
+                    "Names can't contain {characters}, they were replaced with `-`.",
+                    characters = format!(
+                        "`{}`",
+                        reserved
+                            .iter()
+                            .map(char::to_string)
+                            .collect::<Vec<String>>()
+                            .join(" ")
+                    )
+                )
+            } else if value.chars().count() > MAX_NAME_LENGTH {
+                tr!(
+                    "Names can be at most {length} characters, it was shortened.",
+                    length = MAX_NAME_LENGTH
+                )
+            } else {
+                tr!("Some characters can't be used in names in this library, they were replaced or removed.")
+            };
+
+            Err((message, (!fixed.is_empty()).then_some(fixed)))
+        }
+        Rule::Barcode => match normalize_barcode(value) {
+            Some(_) => Ok(()),
+            None => Err((
+                tr!("Has to be the 12 or 13 digits under the barcode, check it was typed right."),
+                None,
+            )),
+        },
+    }
+}
+
+/// The custom id and trimmed value of each input of a submitted modal, in order
+fn submitted_inputs(modal: &ModalInteraction) -> Vec<(String, String)> {
+    modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .filter_map(|component| match component {
+            ActionRowComponent::InputText(input) => Some((
+                input.custom_id.clone(),
+                input
+                    .value
+                    .as_deref()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks the inputs of a submitted modal against their rules, empty inputs are left to
+/// Discord's own required check
+fn validate_inputs(inputs: &[(String, String)]) -> Vec<InputProblem> {
+    inputs
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .filter_map(|(input, value)| {
+            let spec = input_spec(input);
+            let (message, fixed) = check(spec.rule?, value).err()?;
+            Some(InputProblem {
+                input: input.clone(),
+                label: spec.label,
+                message,
+                fixed,
+            })
+        })
+        .collect()
+}
+
+/// Checks the inputs of a submitted modal, returning anything wrong with them
+#[must_use]
+pub fn validate(modal: &ModalInteraction) -> Vec<InputProblem> {
+    let problems = validate_inputs(&submitted_inputs(modal));
+    if !problems.is_empty() {
+        debug!(
+            "Modal {} was submitted with problems: {:?}",
+            modal.data.custom_id, problems
+        );
+    }
+    problems
+}
+
+/// Shows the problems with a submitted modal on the message it came from, with a button
+/// to open it again and fix them. Modals without a message can't be opened again, so
+/// their problems are returned as an error instead.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the modal has no message, or Discord rejects the response.
+pub async fn reprompt(
+    ctx: &Context,
+    modal: &ModalInteraction,
+    problems: Vec<InputProblem>,
+) -> Result<()> {
+    let Some(message) = modal.message.as_deref() else {
+        return Err(DiscordError::InvalidInput(problem_list(&problems)));
+    };
+
+    // A previous attempt's problems are replaced, rather than piling up
+    let mut embeds: Vec<CreateEmbed> = message
+        .embeds
+        .iter()
+        .filter(|embed| embed.title.as_deref() != Some(tr!("Check the Details").as_str()))
+        .cloned()
+        .map(CreateEmbed::from)
+        .collect();
+    embeds.push(problems_embed(&problems));
+
+    let mut components: Vec<CreateActionRow> = message
+        .components
+        .iter()
+        .filter(|row| !is_fix_row(row))
+        .filter_map(rebuild_row)
+        .take(MAX_ACTION_ROWS - 1)
+        .collect();
+    components.push(CreateActionRow::Buttons(vec![fix_button()]));
+
+    let mut rejected_modals = REJECTED_MODALS.lock().await;
+    rejected_modals.retain(|_, rejected| rejected.rejected.elapsed() < REJECTION_TIMEOUT);
+    rejected_modals.insert(
+        message.id,
+        RejectedModal {
+            custom_id: modal.data.custom_id.clone(),
+            inputs: submitted_inputs(modal),
+            problems,
+            rejected: Instant::now(),
+        },
+    );
+    drop(rejected_modals);
+
+    modal
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embeds(embeds)
+                    .components(components),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to show modal problems: {:?}", e);
+            DiscordError::MessageInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+/// Runs the button added by `reprompt`, opening the rejected modal again with what was
+/// entered. Fixed names are filled in fixed, anything else wrong is left empty with the
+/// problem in its place.
+///
+/// # Errors
+///
+/// Returns a `DiscordError` if the modal can't be opened again, or Discord rejects the
+/// response.
+pub async fn reopen(ctx: &Context, component: &ComponentInteraction) -> Result<()> {
+    trace!("Got fix_modal_input component interaction");
+
+    let rejected = REJECTED_MODALS
+        .lock()
+        .await
+        .remove(&component.message.id)
+        .filter(|rejected| rejected.rejected.elapsed() < REJECTION_TIMEOUT)
+        .ok_or_else(|| {
+            debug!("No rejected modal to fix on {}", component.message.id);
+            DiscordError::InvalidInput(tr!(
+                "These details expired, please open the form again with its own button."
+            ))
+        })?;
+
+    let rows = rejected
+        .inputs
+        .iter()
+        .map(|(input, value)| {
+            let spec = input_spec(input);
+            let mut text = CreateInputText::new(InputTextStyle::Short, spec.label, input)
+                .required(spec.required);
+
+            match rejected
+                .problems
+                .iter()
+                .find(|problem| &problem.input == input)
+            {
+                Some(InputProblem {
+                    fixed: Some(fixed), ..
+                }) => text = text.value(fixed),
+                // Placeholders are limited to 100 characters
+                Some(problem) => {
+                    text = text.placeholder(problem.message.chars().take(100).collect::<String>());
+                }
+                None if !value.is_empty() => text = text.value(value),
+                None => {}
+            }
+
+            CreateActionRow::InputText(text)
+        })
+        .collect();
+
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Modal(
+                CreateModal::new(&rejected.custom_id, tr!("Fix the Details")).components(rows),
+            ),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to open {} again: {:?}", rejected.custom_id, e);
+            DiscordError::ComponentInteractionResponseFailed(e.to_string())
+        })?;
+
+    Ok(())
+}
+
+fn fix_button() -> CreateButton {
+    CreateButton::new("fix_modal_input")
+        .label(tr!("Fix Details"))
+        .style(ButtonStyle::Primary)
+}
+
+/// Whether a row is the one holding the button added by an earlier `reprompt`
+fn is_fix_row(row: &ActionRow) -> bool {
+    row.components.iter().any(|component| {
+        matches!(
+            component,
+            ActionRowComponent::Button(Button {
+                data: ButtonKind::NonLink { custom_id, .. },
+                ..
+            }) if custom_id == "fix_modal_input"
+        )
+    })
+}
+
+fn problems_embed(problems: &[InputProblem]) -> CreateEmbed {
+    themed_embed()
+        .title(tr!("Check the Details"))
+        .description(tr!(
+            "Some of the details need fixing before going on:\n{problems}\n\nPress **Fix Details** to change them, everything else entered is kept.",
+            problems = problem_list(problems)
+        ))
+}
+
+/// Lists the problems one per line, by the label of their input
+fn problem_list(problems: &[InputProblem]) -> String {
+    problems
+        .iter()
+        .map(|problem| format!("- **{}**: {}", problem.label, problem.message))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Turns a row of a message back into one that can be sent, so the message keeps its
+/// components when it is updated. `None` for rows holding anything that can't be sent
+/// again.
+fn rebuild_row(row: &ActionRow) -> Option<CreateActionRow> {
+    match row.components.first()? {
+        ActionRowComponent::Button(_) => Some(CreateActionRow::Buttons(
+            row.components
+                .iter()
+                .filter_map(|component| match component {
+                    ActionRowComponent::Button(button) => Some(CreateButton::from(button.clone())),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        ActionRowComponent::SelectMenu(menu) => {
+            rebuild_select_menu(menu).map(CreateActionRow::SelectMenu)
+        }
+        _ => None,
+    }
+}
+
+fn rebuild_select_menu(menu: &SelectMenu) -> Option<CreateSelectMenu> {
+    let kind = match menu.kind {
+        ComponentType::StringSelect => CreateSelectMenuKind::String {
+            options: menu
+                .options
+                .iter()
+                .map(|option| {
+                    let mut rebuilt = CreateSelectMenuOption::new(&option.label, &option.value)
+                        .default_selection(option.default);
+                    if let Some(description) = &option.description {
+                        rebuilt = rebuilt.description(description);
+                    }
+                    if let Some(emoji) = &option.emoji {
+                        rebuilt = rebuilt.emoji(emoji.clone());
+                    }
+                    rebuilt
+                })
+                .collect(),
+        },
+        ComponentType::UserSelect => CreateSelectMenuKind::User {
+            default_users: None,
+        },
+        ComponentType::RoleSelect => CreateSelectMenuKind::Role {
+            default_roles: None,
+        },
+        ComponentType::MentionableSelect => CreateSelectMenuKind::Mentionable {
+            default_users: None,
+            default_roles: None,
+        },
+        ComponentType::ChannelSelect => CreateSelectMenuKind::Channel {
+            channel_types: (!menu.channel_types.is_empty()).then(|| menu.channel_types.clone()),
+            default_channels: None,
+        },
+        _ => return None,
+    };
+
+    let mut rebuilt = CreateSelectMenu::new(menu.custom_id.clone()?, kind).disabled(menu.disabled);
+    if let Some(placeholder) = &menu.placeholder {
+        rebuilt = rebuilt.placeholder(placeholder);
+    }
+    if let Some(min_values) = menu.min_values {
+        rebuilt = rebuilt.min_values(min_values);
+    }
+    if let Some(max_values) = menu.max_values {
+        rebuilt = rebuilt.max_values(max_values);
+    }
+    Some(rebuilt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values
+            .iter()
+            .map(|(input, value)| ((*input).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn rejects_numbers_that_arent() {
+        let problems = validate_inputs(&inputs(&[
+            ("disc_number", "1"),
+            ("season", "abc"),
+            ("year_of_movie", "1066"),
+            ("absolute_episode", ""),
+        ]));
+
+        let rejected: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.input.as_str())
+            .collect();
+        assert_eq!(rejected, vec!["season", "year_of_movie"]);
+        assert!(problems.iter().all(|problem| problem.fixed.is_none()));
+    }
+
+    #[test]
+    fn fixes_names_that_arent_path_safe() {
+        let problems = validate_inputs(&inputs(&[("title_of_movie", "AC/DC"), ("priority", "??")]));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].fixed.as_deref(), Some("AC-DC"));
+    }
+}
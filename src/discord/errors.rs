@@ -1,6 +1,8 @@
 use serenity::Error as SerenityError;
 use thiserror::Error;
 
+use crate::tr;
+
 /// Custom error type for the application
 #[derive(Debug, Error)]
 pub enum DiscordError {
@@ -37,9 +39,21 @@ pub enum DiscordError {
     #[error("Permission denied")]
     PermissionDenied,
 
+    /// What was entered into a modal can't be used, with what is wrong with it
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
     #[error("Unexpected error occurred: {0}")]
     Unexpected(String),
 
+    /// A menu or button of a rip was used by someone other than whoever started it
+    #[error("Not the owner of the interaction")]
+    NotOwner,
+
+    /// A button of a job was used after the job finished
+    #[error("Job is not running")]
+    JobNotRunning,
+
     #[error("Failed to defer interaction: {0}")]
     DeferFailed(String),
 
@@ -48,6 +62,94 @@ pub enum DiscordError {
 
     #[error("MakeMKV error: {0}")]
     MakeMkvError(#[from] crate::makemkv::errors::MakeMkvError),
+
+    #[error("History error: {0}")]
+    HistoryError(#[from] crate::history::errors::HistoryError),
+
+    #[error("Queue error: {0}")]
+    QueueError(#[from] crate::queue::errors::QueueError),
+
+    #[error("Library error: {0}")]
+    LibraryError(#[from] crate::library::errors::LibraryError),
+
+    #[error("Audio error: {0}")]
+    AudioError(#[from] crate::audio::errors::AudioError),
+
+    #[error("Archive error: {0}")]
+    ArchiveError(#[from] crate::archive::errors::ArchiveError),
+
+    #[error("Preview error: {0}")]
+    PreviewError(#[from] crate::preview::errors::PreviewError),
+
+    #[error("Quota error: {0}")]
+    QuotaError(#[from] crate::quota::errors::QuotaError),
+
+    #[error("Settings error: {0}")]
+    SettingsError(#[from] crate::settings::errors::SettingsError),
+
+    #[error("Audit error: {0}")]
+    AuditError(#[from] crate::audit::errors::AuditError),
+
+    #[error("Collection error: {0}")]
+    CollectionError(#[from] crate::collection::errors::CollectionError),
+
+    /// An error that was already shown to the user, such as a failed rip on its message,
+    /// so it is only logged
+    #[error("{0}")]
+    Reported(Box<DiscordError>),
+}
+
+impl DiscordError {
+    /// Marks an error as already shown to the user
+    pub fn reported(error: impl Into<DiscordError>) -> Self {
+        DiscordError::Reported(Box::new(error.into()))
+    }
+
+    /// The error itself, whether or not it was already shown to the user
+    #[must_use]
+    pub fn unreported(&self) -> &DiscordError {
+        match self {
+            DiscordError::Reported(e) => e.unreported(),
+            e => e,
+        }
+    }
+
+    /// Whether the error was already shown to the user, or needs no explanation
+    #[must_use]
+    pub fn is_reported(&self) -> bool {
+        matches!(
+            self,
+            DiscordError::Reported(_) | DiscordError::TaskCancelled
+        )
+    }
+
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            DiscordError::Reported(e) => e.user_message(),
+            DiscordError::MakeMkvError(e) => e.user_message(),
+            DiscordError::AudioError(e) => e.user_message(),
+            DiscordError::ArchiveError(e) => e.user_message(),
+            DiscordError::PreviewError(e) => e.user_message(),
+            DiscordError::QuotaError(e) => e.user_message(),
+            DiscordError::SettingsError(e) => e.user_message(),
+            DiscordError::CollectionError(e) => e.user_message(),
+            DiscordError::InvalidComponentData => {
+                tr!("Some of the details couldn't be read, please check them and try again.")
+            }
+            DiscordError::PermissionDenied => {
+                tr!("Only server administrators can do this.")
+            }
+            DiscordError::InvalidInput(problems) => problems.clone(),
+            DiscordError::TaskCancelled => tr!("Cancelled."),
+            DiscordError::NotOwner => {
+                tr!("This isn't your rip, only whoever started it or an administrator can use it.")
+            }
+            DiscordError::JobNotRunning => tr!("This job isn't running anymore."),
+            _ => tr!("Something went wrong, please try again."),
+        }
+    }
 }
 
 impl From<SerenityError> for DiscordError {
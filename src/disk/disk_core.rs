@@ -0,0 +1,192 @@
+//! # Disk Core Module
+//!
+//! This module keeps track of the free space left on the directories rips are written to,
+//! so the bot can warn before a disk fills up halfway through a rip.
+//!
+//! ## Overview
+//!
+//! - **`SpaceLevel`**: How close a directory is to running out of space.
+//!
+//! - **`DiskMonitor`**: The directories being watched and the thresholds they are
+//!   compared against.
+//!
+//! - **`DISK_MONITOR`**: A globally accessible, thread-safe instance of `DiskMonitor`.
+//!
+//! ## Notes
+//!
+//! - Free space is read with `df`, which is available on every Linux distro that `MakeMKV`
+//!   supports.
+//! - Only changes in level are reported, so a disk sitting below a threshold doesn't
+//!   produce an alert every check.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+use tokio::{process::Command, sync::Mutex};
+
+use crate::{debug, info, trace, warn};
+
+use super::errors::{DiskError, Result};
+
+/// The number of bytes in a gigabyte, thresholds are configured in gigabytes
+const BYTES_PER_GB: u64 = 1024 * 1024 * 1024;
+
+/// A globally accessible instance of `DiskMonitor` for checking free space.
+pub static DISK_MONITOR: LazyLock<Arc<Mutex<DiskMonitor>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(DiskMonitor::default())));
+
+/// How close a directory is to running out of space.
+///
+/// * `Ok` - There is more free space than the warning threshold.
+/// * `Low` - Free space is below the warning threshold, rips can still be started.
+/// * `Critical` - Free space is below the minimum threshold, new rips are blocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpaceLevel {
+    #[default]
+    Ok,
+    Low,
+    Critical,
+}
+
+/// A change in the space level of a watched directory.
+///
+/// # Fields
+///
+/// * `path` - The directory whose level changed.
+/// * `free_bytes` - The free space left on the directory's disk.
+/// * `level` - The new level of the directory.
+#[derive(Debug, Clone)]
+pub struct SpaceChange {
+    pub path: PathBuf,
+    pub free_bytes: u64,
+    pub level: SpaceLevel,
+}
+
+#[derive(Debug, Default)]
+pub struct DiskMonitor {
+    warn_bytes: u64,
+    min_bytes: u64,
+    levels: HashMap<PathBuf, SpaceLevel>,
+}
+
+/// The `DiskMonitor` struct compares the free space of the watched directories against
+/// a warning and a minimum threshold.
+///
+/// # Methods
+///
+/// ## `init`
+/// Sets the directories to watch and the thresholds, in gigabytes.
+///
+/// ## `check`
+/// Reads the free space of every watched directory and returns those whose level changed.
+///
+/// ## `level`
+/// Returns the level a directory with some free space is at.
+///
+/// ## `is_blocked`
+/// Returns true if any watched directory is below the minimum threshold.
+impl DiskMonitor {
+    /// Sets the directories to watch and the thresholds, in gigabytes.
+    pub fn init(&mut self, paths: Vec<PathBuf>, warn_gb: u64, min_gb: u64) {
+        self.warn_bytes = warn_gb * BYTES_PER_GB;
+        self.min_bytes = min_gb * BYTES_PER_GB;
+        self.levels = paths
+            .into_iter()
+            .map(|path| (path, SpaceLevel::Ok))
+            .collect();
+
+        info!(
+            "Disk monitor initialized, warning below {} GB and blocking rips below {} GB",
+            warn_gb, min_gb
+        );
+    }
+
+    /// Reads the free space of every watched directory and returns those whose level
+    /// changed since the last check.
+    pub async fn check(&mut self) -> Vec<SpaceChange> {
+        let mut changes = Vec::new();
+
+        for (path, level) in &mut self.levels {
+            let free_bytes = match free_space(path).await {
+                Ok(free_bytes) => free_bytes,
+                Err(e) => {
+                    warn!("Failed to check free space: {}", e);
+                    continue;
+                }
+            };
+
+            trace!("{} has {} bytes free", path.display(), free_bytes);
+
+            let new_level = Self::level_between(free_bytes, self.warn_bytes, self.min_bytes);
+
+            if new_level != *level {
+                debug!(
+                    "{} changed from {:?} to {:?}",
+                    path.display(),
+                    level,
+                    new_level
+                );
+                *level = new_level;
+                changes.push(SpaceChange {
+                    path: path.clone(),
+                    free_bytes,
+                    level: new_level,
+                });
+            }
+        }
+
+        changes
+    }
+
+    /// Returns the level a directory with `free_bytes` free is at.
+    #[must_use]
+    pub fn level(&self, free_bytes: u64) -> SpaceLevel {
+        Self::level_between(free_bytes, self.warn_bytes, self.min_bytes)
+    }
+
+    fn level_between(free_bytes: u64, warn_bytes: u64, min_bytes: u64) -> SpaceLevel {
+        if free_bytes < min_bytes {
+            SpaceLevel::Critical
+        } else if free_bytes < warn_bytes {
+            SpaceLevel::Low
+        } else {
+            SpaceLevel::Ok
+        }
+    }
+
+    /// Returns true if any watched directory is below the minimum threshold.
+    #[must_use]
+    pub fn is_blocked(&self) -> bool {
+        self.levels
+            .values()
+            .any(|level| *level == SpaceLevel::Critical)
+    }
+}
+
+/// Reads the free space, in bytes, of the disk a path is on.
+///
+/// # Errors
+///
+/// Returns a `DiskError` if `df` can't be run or its output can't be parsed.
+pub async fn free_space(path: &Path) -> Result<u64> {
+    // -P keeps the output on one line per disk, -k reports sizes in kilobytes
+    let output = Command::new("df").arg("-Pk").arg(path).output().await?;
+
+    if !output.status.success() {
+        return Err(DiskError::CommandExecutionError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    // The second line holds the sizes, the fourth column is the available space
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(3))
+        .and_then(|available| available.parse::<u64>().ok())
+        .map(|available| available * 1024)
+        .ok_or_else(|| DiskError::ParseError(path.to_string_lossy().to_string()))
+}
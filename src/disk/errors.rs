@@ -0,0 +1,19 @@
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, DiskError>;
+
+#[derive(Debug, Error)]
+pub enum DiskError {
+    #[error("Failed to execute command: {0}")]
+    CommandExecutionError(String),
+
+    #[error("Failed to read free space of {0}")]
+    ParseError(String),
+}
+
+impl From<io::Error> for DiskError {
+    fn from(error: io::Error) -> Self {
+        DiskError::CommandExecutionError(error.to_string())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod disk_core;
+pub mod errors;
+
+pub use disk_core::{free_space, SpaceLevel, DISK_MONITOR};
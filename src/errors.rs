@@ -7,9 +7,46 @@ pub enum CordRipperError {
     #[error("MakeMkv error: {0}")]
     MakeMkvError(#[from] crate::makemkv::errors::MakeMkvError),
 
+    #[cfg(feature = "discord")]
     #[error("Discord error: {0}")]
     DiscordError(#[from] crate::discord::errors::DiscordError),
 
+    #[error("History error: {0}")]
+    HistoryError(#[from] crate::history::errors::HistoryError),
+
+    #[error("Disk error: {0}")]
+    DiskError(#[from] crate::disk::errors::DiskError),
+
+    #[error("Queue error: {0}")]
+    QueueError(#[from] crate::queue::errors::QueueError),
+
+    #[error("Library error: {0}")]
+    LibraryError(#[from] crate::library::errors::LibraryError),
+
+    #[error("Arr error: {0}")]
+    ArrError(#[from] crate::arr::errors::ArrError),
+
+    #[error("Archive error: {0}")]
+    ArchiveError(#[from] crate::archive::errors::ArchiveError),
+
+    #[error("Preview error: {0}")]
+    PreviewError(#[from] crate::preview::errors::PreviewError),
+
+    #[error("Probe error: {0}")]
+    ProbeError(#[from] crate::probe::errors::ProbeError),
+
+    #[error("Audio error: {0}")]
+    AudioError(#[from] crate::audio::errors::AudioError),
+
+    #[error("Upload error: {0}")]
+    UploadError(#[from] crate::upload::errors::UploadError),
+
+    #[error("Update error: {0}")]
+    UpdateError(#[from] crate::update::errors::UpdateError),
+
+    #[error("Watch error: {0}")]
+    WatchError(#[from] crate::watch::errors::WatchError),
+
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
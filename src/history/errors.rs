@@ -0,0 +1,37 @@
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, HistoryError>;
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("Failed to read history file: {0}")]
+    ReadError(String),
+
+    #[error("Failed to write history file: {0}")]
+    WriteError(String),
+
+    #[error("Failed to parse history file: {0}")]
+    ParseError(String),
+
+    #[error("No rip found in history with id {0}")]
+    EntryNotFound(u64),
+
+    #[error("File already exists: {0}")]
+    FileAlreadyExists(String),
+
+    #[error("Failed to move file: {0}")]
+    MoveError(String),
+}
+
+impl From<io::Error> for HistoryError {
+    fn from(error: io::Error) -> Self {
+        HistoryError::WriteError(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for HistoryError {
+    fn from(error: serde_json::Error) -> Self {
+        HistoryError::ParseError(error.to_string())
+    }
+}
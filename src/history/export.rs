@@ -0,0 +1,305 @@
+//! # History Export
+//!
+//! The history and drive statistics are kept as one JSON file made for the bot, not for
+//! reading. This module dumps them as CSV for spreadsheets, or as tidier JSON, so the
+//! progress of digitizing a collection can be tracked elsewhere.
+//!
+//! ## Notes
+//!
+//! - A CSV can only hold one table, so CSV exports are two files, `rip_history.csv` and
+//!   `drive_stats.csv`. JSON exports are a single `rip_history.json` holding both.
+//! - Times are written in UTC as `YYYY-MM-DD HH:MM:SS`, which spreadsheets read as dates.
+//!   JSON keeps them in seconds since the unix epoch, as the history does.
+//! - The files of a rip are joined with `; ` in the CSV, most rips only have one.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::makemkv::RipType;
+
+use super::errors::Result;
+use super::{DriveStats, HistoryEntry};
+
+/// The number of seconds in a day, for turning timestamps into dates
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// What the history is exported as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Convert a name, such as `csv`, to its `ExportFormat`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A file of an export.
+///
+/// # Fields
+///
+/// * `name` - The name to save it as, e.g. `rip_history.csv`.
+/// * `contents` - What goes in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportFile {
+    pub name: String,
+    pub contents: String,
+}
+
+/// The history and drive statistics, as they are written to JSON exports
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    rips: &'a [HistoryEntry],
+    drive_stats: &'a BTreeMap<u8, DriveStats>,
+}
+
+/// Exports the rips and drive statistics of the history as `format`.
+///
+/// # Errors
+///
+/// Returns a `HistoryError` if the JSON can't be written.
+pub fn export(
+    entries: &[HistoryEntry],
+    drive_stats: &BTreeMap<u8, DriveStats>,
+    format: ExportFormat,
+) -> Result<Vec<ExportFile>> {
+    match format {
+        ExportFormat::Csv => Ok(vec![
+            ExportFile {
+                name: "rip_history.csv".to_string(),
+                contents: rips_csv(entries),
+            },
+            ExportFile {
+                name: "drive_stats.csv".to_string(),
+                contents: drive_stats_csv(drive_stats),
+            },
+        ]),
+        ExportFormat::Json => Ok(vec![ExportFile {
+            name: "rip_history.json".to_string(),
+            contents: serde_json::to_string_pretty(&JsonExport {
+                rips: entries,
+                drive_stats,
+            })?,
+        }]),
+    }
+}
+
+/// One row of every rip, oldest first
+fn rips_csv(entries: &[HistoryEntry]) -> String {
+    let mut rows = vec![csv_row(&[
+        "id",
+        "completed",
+        "title",
+        "year",
+        "type",
+        "season",
+        "episode",
+        "absolute_episode",
+        "extra",
+        "note",
+        "drive",
+        "title_id",
+        "imported",
+        "suspicious",
+        "disc",
+        "source_file",
+        "files",
+    ])];
+
+    for entry in entries {
+        let (kind, season, episode, absolute) = match entry.rip_type {
+            RipType::Movie => ("movie", None, None, None),
+            RipType::Show {
+                season,
+                episode,
+                absolute_episode,
+            } => ("show", Some(season), Some(episode), absolute_episode),
+        };
+        let files = entry
+            .files
+            .iter()
+            .map(|file| file.display().to_string())
+            .collect::<Vec<String>>()
+            .join("; ");
+
+        rows.push(csv_row(&[
+            &entry.id.to_string(),
+            &format_timestamp(entry.timestamp),
+            &entry.title,
+            &optional(entry.year),
+            kind,
+            &optional(season),
+            &optional(episode),
+            &optional(absolute),
+            entry.extra.as_deref().unwrap_or_default(),
+            entry.note.as_deref().unwrap_or_default(),
+            &entry.drive_number.to_string(),
+            &entry.title_id.to_string(),
+            &entry.imported.to_string(),
+            &entry.suspicious.to_string(),
+            entry
+                .source
+                .as_ref()
+                .map_or("", |source| source.disc_name.as_str()),
+            entry
+                .source
+                .as_ref()
+                .map_or("", |source| source.source_file.as_str()),
+            &files,
+        ]));
+    }
+
+    rows.concat()
+}
+
+/// One row for every drive that has ripped something
+fn drive_stats_csv(drive_stats: &BTreeMap<u8, DriveStats>) -> String {
+    let mut rows = vec![csv_row(&[
+        "drive",
+        "completed",
+        "failed",
+        "retries",
+        "bytes_ripped",
+        "seconds_ripping",
+        "average_bytes_per_second",
+        "failure_rate",
+    ])];
+
+    for (drive_number, stats) in drive_stats {
+        rows.push(csv_row(&[
+            &drive_number.to_string(),
+            &stats.completed.to_string(),
+            &stats.failed.to_string(),
+            &stats.retries.to_string(),
+            &stats.bytes_ripped.to_string(),
+            &format!("{:.0}", stats.seconds_ripping),
+            &stats
+                .average_speed()
+                .map(|speed| format!("{speed:.0}"))
+                .unwrap_or_default(),
+            &stats
+                .failure_rate()
+                .map(|rate| format!("{rate:.3}"))
+                .unwrap_or_default(),
+        ]));
+    }
+
+    rows.concat()
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// A row of a CSV, quoting the fields that need it
+fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                (*field).to_string()
+            }
+        })
+        .collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Formats seconds since the unix epoch as `YYYY-MM-DD HH:MM:SS` in UTC
+fn format_timestamp(timestamp: u64) -> String {
+    let days = timestamp / SECONDS_PER_DAY;
+    let seconds = timestamp % SECONDS_PER_DAY;
+
+    // The civil date of a day since the epoch, counting years from March so leap days
+    // fall at the end of the year
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::history::RipSource;
+
+    fn entry() -> HistoryEntry {
+        HistoryEntry {
+            id: 7,
+            year: Some(2000),
+            drive_number: 1,
+            title_id: 2,
+            files: vec![PathBuf::from("/movies/Crouching Tiger.mkv")],
+            source: Some(RipSource {
+                disc_name: "CROUCHING_TIGER".to_string(),
+                source_file: "00800.mpls".to_string(),
+            }),
+            note: Some("The \"Director's\" cut".to_string()),
+            ..HistoryEntry::test("Crouching Tiger, Hidden Dragon", 951_827_696)
+        }
+    }
+
+    #[test]
+    fn formats_timestamps_as_utc_dates() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(951_827_696), "2000-02-29 12:34:56");
+        assert_eq!(format_timestamp(1_735_689_599), "2024-12-31 23:59:59");
+    }
+
+    #[test]
+    fn quotes_csv_fields_that_need_it() {
+        let csv = rips_csv(&[entry()]);
+        let row = csv.lines().nth(1).unwrap();
+        assert_eq!(
+            row,
+            "7,2000-02-29 12:34:56,\"Crouching Tiger, Hidden Dragon\",2000,movie,,,,,\
+             \"The \"\"Director's\"\" cut\",1,2,false,false,CROUCHING_TIGER,00800.mpls,\
+             /movies/Crouching Tiger.mkv"
+        );
+    }
+
+    #[test]
+    fn exports_json_as_one_file() {
+        let mut drive_stats = BTreeMap::new();
+        drive_stats.insert(1, DriveStats::default());
+
+        let files = export(&[entry()], &drive_stats, ExportFormat::Json).unwrap();
+        assert_eq!(files.len(), 1);
+        let json: serde_json::Value = serde_json::from_str(&files[0].contents).unwrap();
+        assert_eq!(json["rips"][0]["title_id"], 2);
+        assert_eq!(json["drive_stats"]["1"]["completed"], 0);
+
+        let files = export(&[entry()], &drive_stats, ExportFormat::Csv).unwrap();
+        assert_eq!(files[1].contents.lines().count(), 2);
+    }
+}
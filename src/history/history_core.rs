@@ -0,0 +1,823 @@
+//! # History Core Module
+//!
+//! This module keeps a persistent record of every rip that has been saved to the output
+//! directory, so ripped files can be found, renamed, and reported on later without
+//! rescanning the library.
+//!
+//! ## Overview
+//!
+//! - **`HistoryEntry`**: A single completed rip, including where its files were saved.
+//!
+//! - **`History`**: The collection of entries, stored as JSON in the data directory and
+//!   saved after every change.
+//!
+//! - **`DriveStats`**: Rip counts and speeds for a single drive, used to spot a drive that
+//!   is starting to fail.
+//!
+//! - **`HISTORY`**: A globally accessible, thread-safe instance of `History`.
+//!
+//! ## Notes
+//!
+//! - The history file is rewritten in full after every change, it is expected to stay
+//!   small enough (a few thousand rips) for this not to matter.
+//! - If the history file does not exist, an empty history is created on the first save.
+//! - Deleted rips are moved to a `trash` directory within the data directory and purged once
+//!   they are older than the retention period. A retention of 0 days deletes files outright.
+//! - Files that were in the output directory before cord-ripper are imported into the
+//!   history on the first run, and again with `/import_library`. Imported rips are marked
+//!   as such, they have no drive or title to go with them.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::library::LibraryIndex;
+use crate::makemkv::transfer::move_verified;
+use crate::makemkv::{Rip, RipType};
+use crate::{debug, error, info, trace, warn};
+
+use super::errors::{HistoryError, Result};
+use super::import::{parse_library_path, ImportedRip};
+
+/// The name of the history file within the data directory
+const HISTORY_FILE: &str = "history.json";
+/// The name of the trash directory within the data directory
+const TRASH_DIR: &str = "trash";
+/// The number of seconds in a day, used for the trash retention period
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+/// The number of recent rips kept per drive, for comparing against the all time stats
+const RECENT_DRIVE_SAMPLES: usize = 20;
+
+/// A globally accessible instance of `History` for recording completed rips.
+pub static HISTORY: LazyLock<Arc<Mutex<History>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(History::default())));
+
+/// A single rip that was saved to the output directory.
+///
+/// # Fields
+///
+/// * `id` - A unique, incrementing identifier for the rip.
+/// * `timestamp` - When the rip completed, in seconds since the unix epoch.
+/// * `title` - The title of the movie or show.
+/// * `year` - The release year of a movie, if one was given.
+/// * `rip_type` - Whether the rip was a movie or a show episode.
+/// * `drive_number` - The drive the disc was ripped from.
+/// * `title_id` - The title on the disc that was ripped.
+/// * `files` - The files that were saved for the rip.
+/// * `imported` - Whether the rip was found in the output directory by an import, rather
+///   than ripped by cord-ripper. The drive and title of imported rips are always 0.
+/// * `source` - The disc and title the rip was made from, if they were known.
+/// * `log` - The name of the log of the job the rip was made by, see `/rip_log`.
+/// * `suspicious` - Whether the rip saved far less than the disc scan said its title holds,
+///   so it may be truncated.
+/// * `note` - The note the user attached to the rip, such as the edition of the disc.
+/// * `extra` - The name of the extra of a movie the rip is, `None` for the movie itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub timestamp: u64,
+    pub title: String,
+    #[serde(default)]
+    pub year: Option<u16>,
+    pub rip_type: RipType,
+    pub drive_number: u8,
+    pub title_id: u16,
+    pub files: Vec<PathBuf>,
+    #[serde(default)]
+    pub imported: bool,
+    #[serde(default)]
+    pub source: Option<RipSource>,
+    #[serde(default)]
+    pub log: Option<String>,
+    #[serde(default)]
+    pub suspicious: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub extra: Option<String>,
+}
+
+/// Where on a disc a rip came from, so the same title can be recognized on the disc again.
+/// Title numbers depend on `--minlength`, so the file the title plays from is used instead.
+///
+/// # Fields
+///
+/// * `disc_name` - The name `MakeMKV` reads from the disc.
+/// * `source_file` - The file the title is played from on the disc, e.g. `00800.mpls`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RipSource {
+    pub disc_name: String,
+    pub source_file: String,
+}
+
+impl HistoryEntry {
+    /// A short, human readable description of the rip, e.g. `Show S1E5`, `Movie (2019)` or
+    /// `Movie (2019) - Making Of` for an extra
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self.rip_type {
+            RipType::Movie => {
+                let movie = match self.year {
+                    Some(year) => format!("{} ({})", self.title, year),
+                    None => self.title.clone(),
+                };
+                match &self.extra {
+                    Some(extra) => format!("{movie} - {extra}"),
+                    None => movie,
+                }
+            }
+            RipType::Show {
+                season, episode, ..
+            } => format!("{} S{}E{}", self.title, season, episode),
+        }
+    }
+}
+
+#[cfg(test)]
+impl HistoryEntry {
+    /// A movie rip of title 1 on drive 0 for tests, change the rest with struct update syntax
+    pub(crate) fn test(title: &str, timestamp: u64) -> Self {
+        HistoryEntry {
+            id: timestamp,
+            timestamp,
+            title: title.to_string(),
+            year: None,
+            rip_type: RipType::Movie,
+            drive_number: 0,
+            title_id: 1,
+            files: Vec::new(),
+            imported: false,
+            source: None,
+            log: None,
+            suspicious: false,
+            note: None,
+            extra: None,
+        }
+    }
+}
+
+/// The outcome of a single rip on a drive.
+///
+/// # Fields
+///
+/// * `timestamp` - When the rip finished, in seconds since the unix epoch.
+/// * `succeeded` - Whether the rip succeeded.
+/// * `bytes_per_second` - The read speed of the rip, only recorded for successful rips.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveSample {
+    pub timestamp: u64,
+    pub succeeded: bool,
+    pub bytes_per_second: Option<f64>,
+}
+
+/// Rip counts and speeds for a single drive.
+///
+/// # Fields
+///
+/// * `completed` - The number of rips that succeeded.
+/// * `failed` - The number of rips that failed, including partial failures.
+/// * `retries` - The number of titles that were retried after failing.
+/// * `bytes_ripped` - The total size of every successful rip.
+/// * `seconds_ripping` - The total time spent on every successful rip.
+/// * `recent` - The outcomes of the most recent rips, oldest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DriveStats {
+    pub completed: u64,
+    pub failed: u64,
+    pub retries: u64,
+    pub bytes_ripped: u64,
+    pub seconds_ripping: f64,
+    pub recent: Vec<DriveSample>,
+}
+
+impl DriveStats {
+    /// The average read speed of every successful rip, in bytes per second.
+    #[must_use]
+    pub fn average_speed(&self) -> Option<f64> {
+        (self.seconds_ripping > 0.0).then(|| self.bytes_ripped as f64 / self.seconds_ripping)
+    }
+
+    /// The average read speed of the recent successful rips, in bytes per second.
+    #[must_use]
+    pub fn recent_average_speed(&self) -> Option<f64> {
+        let speeds: Vec<f64> = self
+            .recent
+            .iter()
+            .filter_map(|sample| sample.bytes_per_second)
+            .collect();
+
+        (!speeds.is_empty()).then(|| speeds.iter().sum::<f64>() / speeds.len() as f64)
+    }
+
+    /// The share of every rip that failed, from 0 to 1.
+    #[must_use]
+    pub fn failure_rate(&self) -> Option<f64> {
+        let total = self.completed + self.failed;
+        (total > 0).then(|| self.failed as f64 / total as f64)
+    }
+
+    /// The share of the recent rips that failed, from 0 to 1.
+    #[must_use]
+    pub fn recent_failure_rate(&self) -> Option<f64> {
+        let failed = self
+            .recent
+            .iter()
+            .filter(|sample| !sample.succeeded)
+            .count();
+
+        (!self.recent.is_empty()).then(|| failed as f64 / self.recent.len() as f64)
+    }
+
+    /// Adds a sample, dropping the oldest once there are too many
+    fn push_sample(&mut self, sample: DriveSample) {
+        self.recent.push(sample);
+        if self.recent.len() > RECENT_DRIVE_SAMPLES {
+            self.recent.remove(0);
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    trash_dir: PathBuf,
+    #[serde(skip)]
+    trash_retention_days: u64,
+    next_id: u64,
+    entries: Vec<HistoryEntry>,
+    #[serde(default)]
+    drive_stats: BTreeMap<u8, DriveStats>,
+    #[serde(default)]
+    library_imported: bool,
+}
+
+/// The `History` struct stores every completed rip in a JSON file within the data directory.
+///
+/// # Methods
+///
+/// ## `init`
+/// Loads the history from the data directory, creating the directory if needed.
+///
+/// ## `record`
+/// Adds a completed rip to the history and saves it, returning the id of the new entry.
+///
+/// ## `import_library`
+/// Adds the files in the library that aren't in the history yet, as imported rips.
+///
+/// ## `library_imported`
+/// Returns whether the library has ever been imported.
+///
+/// ## `find_source`
+/// Returns the latest entry ripped from a title of a disc.
+///
+/// ## `recent`
+/// Returns the most recent entries, newest first.
+///
+/// ## `entries`
+/// Returns every entry, oldest first.
+///
+/// ## `get`
+/// Returns the entry with the given id.
+///
+/// ## `relocate`
+/// Moves the files of an entry to a new location and updates the entry to match.
+///
+/// ## `delete`
+/// Removes an entry from the history, moving its files to the trash or deleting them.
+///
+/// ## `purge_trash`
+/// Deletes anything in the trash older than the retention period.
+///
+/// ## `record_drive_success`, `record_drive_failure` and `record_drive_retries`
+/// Update the statistics of a drive after a rip.
+///
+/// ## `drive_stats`
+/// Returns the statistics of every drive that has ripped something.
+impl History {
+    /// Loads the history from the data directory, creating the directory if needed.
+    /// Deleted rips are kept in the trash for `trash_retention_days` before being purged.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the directory can't be created or the history file can't
+    /// be read or parsed.
+    pub fn init(&mut self, data_dir: &Path, trash_retention_days: u64) -> Result<()> {
+        std::fs::create_dir_all(data_dir).map_err(|e| HistoryError::WriteError(e.to_string()))?;
+
+        let path = data_dir.join(HISTORY_FILE);
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| HistoryError::ReadError(e.to_string()))?;
+            *self = serde_json::from_str(&contents)?;
+            debug!("Loaded {} rips from history", self.entries.len());
+        } else {
+            debug!("No history file found, starting a new history");
+        }
+
+        self.path = path;
+        self.trash_dir = data_dir.join(TRASH_DIR);
+        self.trash_retention_days = trash_retention_days;
+
+        // A failed purge shouldn't stop the bot from starting, it will be retried
+        // after the next delete
+        if let Err(e) = self.purge_trash() {
+            warn!("Failed to purge trash: {}", e);
+        }
+
+        info!("History initialized successfully!");
+        Ok(())
+    }
+
+    /// Writes the history to disk
+    fn save(&self) -> Result<()> {
+        // History is disabled until init has been called
+        if self.path.as_os_str().is_empty() {
+            warn!("History has not been initialized, not saving");
+            return Ok(());
+        }
+
+        write_json_atomic(&self.path, self)?;
+
+        trace!("Saved history to {}", self.path.display());
+        Ok(())
+    }
+
+    /// Adds a completed rip to the history and saves it, returning the id of the new entry.
+    /// `source` is where on the disc the rip came from, if the disc was scanned first, `log`
+    /// the name of the log `MakeMKV`'s output was written to, and `suspicious` whether the
+    /// rip came out far smaller than expected.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the history can't be saved.
+    pub fn record(
+        &mut self,
+        rip: &Rip,
+        files: Vec<PathBuf>,
+        source: Option<RipSource>,
+        log: Option<String>,
+        suspicious: bool,
+    ) -> Result<u64> {
+        self.next_id += 1;
+        let id = self.next_id;
+
+        self.entries.push(HistoryEntry {
+            id,
+            timestamp: now(),
+            title: rip.title.clone(),
+            year: rip.year,
+            rip_type: rip.rip_type,
+            drive_number: rip.drive_number,
+            title_id: rip.title_id,
+            files,
+            imported: false,
+            source,
+            log,
+            suspicious,
+            note: rip.note.clone(),
+            extra: rip.extra.clone(),
+        });
+
+        self.save()?;
+        debug!("Recorded rip {} in history", id);
+        Ok(id)
+    }
+
+    /// Adds every movie and episode in the library that isn't in the history yet, so rips
+    /// made before cord-ripper are known too. Returns the number of rips added.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the history can't be saved.
+    pub fn import_library(&mut self, library: &LibraryIndex) -> Result<usize> {
+        let known: HashSet<&PathBuf> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.files.iter())
+            .collect();
+
+        // The parts of a multi-file rip are grouped by their folder and shared name
+        let mut found: BTreeMap<(PathBuf, String), (ImportedRip, Vec<PathBuf>)> = BTreeMap::new();
+        for file in library.files() {
+            if known.contains(&file) {
+                continue;
+            }
+            let Some(relative) = library
+                .root_of(&file)
+                .and_then(|root| file.strip_prefix(root).ok())
+            else {
+                continue;
+            };
+            let Some(rip) = parse_library_path(relative) else {
+                trace!("Not importing {}", file.display());
+                continue;
+            };
+
+            let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            found
+                .entry((dir, rip.key.clone()))
+                .or_insert_with(|| (rip, Vec::new()))
+                .1
+                .push(file);
+        }
+
+        let count = found.len();
+        let timestamp = now();
+        for (rip, files) in found.into_values() {
+            self.next_id += 1;
+            debug!("Importing {} as rip {}", rip.title, self.next_id);
+            self.entries.push(HistoryEntry {
+                id: self.next_id,
+                timestamp,
+                title: rip.title,
+                year: rip.year,
+                rip_type: rip.rip_type,
+                drive_number: 0,
+                title_id: 0,
+                files,
+                imported: true,
+                source: None,
+                log: None,
+                suspicious: false,
+                note: None,
+                extra: None,
+            });
+        }
+
+        self.library_imported = true;
+        self.save()?;
+
+        info!("Imported {} rips from the library into history", count);
+        Ok(count)
+    }
+
+    /// Whether the library has been imported into the history, it is only done
+    /// automatically once
+    #[must_use]
+    pub fn library_imported(&self) -> bool {
+        self.library_imported
+    }
+
+    /// Returns the most recent entries, newest first.
+    #[must_use]
+    pub fn recent(&self, count: usize) -> Vec<&HistoryEntry> {
+        self.entries.iter().rev().take(count).collect()
+    }
+
+    /// Returns every entry, oldest first.
+    #[must_use]
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Returns the titles of the shows ripped or imported, most recently ripped first.
+    #[must_use]
+    pub fn show_titles(&self) -> Vec<&str> {
+        let mut titles: Vec<&str> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if matches!(entry.rip_type, RipType::Show { .. })
+                && !titles.contains(&entry.title.as_str())
+            {
+                titles.push(&entry.title);
+            }
+        }
+        titles
+    }
+
+    /// Returns the latest entry ripped from `source`, if it was ever ripped.
+    #[must_use]
+    pub fn find_source(&self, source: &RipSource) -> Option<&HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.source.as_ref() == Some(source))
+    }
+
+    /// Returns the entry with the given id.
+    #[must_use]
+    pub fn get(&self, id: u64) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Returns the latest entry that saved `file`.
+    #[must_use]
+    pub fn find_file(&self, file: &Path) -> Option<&HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.files.iter().any(|saved| saved == file))
+    }
+
+    /// Moves the files of an entry to `new_paths` and updates the entry
+    /// to match the new details.
+    ///
+    /// All destinations are checked before anything is moved, so a conflict leaves the
+    /// files untouched. Directories left empty by the move are removed, up to `root`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the entry doesn't exist, a destination already exists, or
+    /// a file can't be moved.
+    pub fn relocate(
+        &mut self,
+        id: u64,
+        title: &str,
+        year: Option<u16>,
+        rip_type: RipType,
+        new_paths: Vec<PathBuf>,
+        root: &Path,
+    ) -> Result<&HistoryEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or(HistoryError::EntryNotFound(id))?;
+
+        let old_paths = self.entries[index].files.clone();
+
+        // Make sure nothing will be overwritten before moving any files
+        for (old_path, new_path) in old_paths.iter().zip(&new_paths) {
+            if old_path != new_path && new_path.exists() {
+                return Err(HistoryError::FileAlreadyExists(
+                    new_path.to_string_lossy().to_string(),
+                ));
+            }
+        }
+
+        for (old_path, new_path) in old_paths.iter().zip(&new_paths) {
+            if old_path == new_path {
+                continue;
+            }
+
+            if let Some(parent) = new_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| HistoryError::MoveError(e.to_string()))?;
+            }
+
+            std::fs::rename(old_path, new_path)
+                .map_err(|e| HistoryError::MoveError(e.to_string()))?;
+            debug!("Moved {} to {}", old_path.display(), new_path.display());
+
+            remove_empty_parents(old_path, root);
+        }
+
+        let entry = &mut self.entries[index];
+        entry.title = title.to_string();
+        entry.year = year;
+        entry.rip_type = rip_type;
+        entry.files = new_paths;
+
+        self.save()?;
+        Ok(&self.entries[index])
+    }
+
+    /// Removes an entry from the history and returns it.
+    ///
+    /// The files of the entry are moved to `trash/<timestamp>-<id>` within the data
+    /// directory, or deleted outright when the trash retention is 0 days. Directories left
+    /// empty are removed, up to `root`. Files in a library on another mount are copied to
+    /// the trash and checked before they are removed, see `transfer::move_verified`.
+    ///
+    /// If a file can't be moved, the files already moved to the trash are moved back and
+    /// the entry is kept, without any files that were deleted outright.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the entry doesn't exist, a file can't be moved or deleted,
+    /// or the history can't be saved.
+    pub async fn delete(&mut self, id: u64, root: &Path) -> Result<HistoryEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or(HistoryError::EntryNotFound(id))?;
+
+        let files = self.entries[index].files.clone();
+        let use_trash = self.trash_retention_days > 0;
+        let trash_dir = self.trash_dir.join(format!("{}-{}", now(), id));
+
+        if use_trash {
+            std::fs::create_dir_all(&trash_dir)
+                .map_err(|e| HistoryError::MoveError(e.to_string()))?;
+        }
+
+        let mut trashed = Vec::new();
+        for file in &files {
+            // Files may have already been removed by hand, which is fine
+            if !file.exists() {
+                warn!("{} no longer exists, skipping", file.display());
+                continue;
+            }
+
+            let result = match file.file_name() {
+                Some(file_name) if use_trash => {
+                    let trash_path = trash_dir.join(file_name);
+                    move_verified(file, &trash_path)
+                        .await
+                        .map(|()| Some(trash_path))
+                        .map_err(|e| HistoryError::MoveError(e.to_string()))
+                }
+                _ => std::fs::remove_file(file)
+                    .map(|()| None)
+                    .map_err(HistoryError::from),
+            };
+
+            match result {
+                Ok(Some(trash_path)) => {
+                    debug!("Moved {} to {}", file.display(), trash_path.display());
+                    trashed.push((file, trash_path));
+                }
+                Ok(None) => debug!("Deleted {}", file.display()),
+                Err(e) => {
+                    error!("Failed to delete {}: {}", file.display(), e);
+                    self.undo_delete(index, &trashed).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        for file in &files {
+            remove_empty_parents(file, root);
+        }
+
+        let entry = self.entries.remove(index);
+        self.save()?;
+
+        if let Err(e) = self.purge_trash() {
+            warn!("Failed to purge trash: {}", e);
+        }
+
+        info!("Deleted rip {} from history", id);
+        Ok(entry)
+    }
+
+    /// Moves the files of a failed `delete` back out of the trash, and drops the files that
+    /// are gone for good from the entry at `index`
+    async fn undo_delete(&mut self, index: usize, trashed: &[(&PathBuf, PathBuf)]) {
+        for (file, trash_path) in trashed {
+            match move_verified(trash_path, file).await {
+                Ok(()) => debug!("Moved {} back out of the trash", file.display()),
+                Err(e) => warn!(
+                    "Failed to move {} back out of the trash: {}",
+                    trash_path.display(),
+                    e
+                ),
+            }
+        }
+
+        let entry = &mut self.entries[index];
+        let before = entry.files.len();
+        entry.files.retain(|file| file.exists());
+        if entry.files.len() != before {
+            if let Err(e) = self.save() {
+                warn!("Failed to save the history after a failed delete: {}", e);
+            }
+        }
+    }
+
+    /// Deletes anything in the trash older than the retention period, returning the number
+    /// of bytes reclaimed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the trash directory can't be read or cleaned up.
+    pub fn purge_trash(&self) -> Result<u64> {
+        if !self.trash_dir.exists() {
+            return Ok(0);
+        }
+
+        let cutoff =
+            now().saturating_sub(self.trash_retention_days.saturating_mul(SECONDS_PER_DAY));
+        let mut reclaimed = 0;
+
+        for dir in std::fs::read_dir(&self.trash_dir)? {
+            let dir = dir?.path();
+
+            // Trash directories are named `<timestamp>-<id>`, anything else is left alone
+            let Some(timestamp) = dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split_once('-'))
+                .and_then(|(timestamp, _)| timestamp.parse::<u64>().ok())
+            else {
+                continue;
+            };
+
+            if timestamp > cutoff {
+                continue;
+            }
+
+            reclaimed += fs_extra::dir::get_size(&dir).unwrap_or_default();
+            std::fs::remove_dir_all(&dir)?;
+            debug!("Purged {} from trash", dir.display());
+        }
+
+        if reclaimed > 0 {
+            info!("Purged {} bytes from trash", reclaimed);
+        }
+
+        Ok(reclaimed)
+    }
+
+    /// Records a successful rip of `bytes` that took `seconds` on a drive.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the history can't be saved.
+    pub fn record_drive_success(
+        &mut self,
+        drive_number: u8,
+        bytes: u64,
+        seconds: f64,
+    ) -> Result<()> {
+        let stats = self.drive_stats.entry(drive_number).or_default();
+        stats.completed += 1;
+        stats.bytes_ripped += bytes;
+        stats.seconds_ripping += seconds;
+        stats.push_sample(DriveSample {
+            timestamp: now(),
+            succeeded: true,
+            bytes_per_second: (seconds > 0.0).then(|| bytes as f64 / seconds),
+        });
+
+        self.save()
+    }
+
+    /// Records a failed rip on a drive.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the history can't be saved.
+    pub fn record_drive_failure(&mut self, drive_number: u8) -> Result<()> {
+        let stats = self.drive_stats.entry(drive_number).or_default();
+        stats.failed += 1;
+        stats.push_sample(DriveSample {
+            timestamp: now(),
+            succeeded: false,
+            bytes_per_second: None,
+        });
+
+        self.save()
+    }
+
+    /// Records that `count` failed titles are being retried on a drive.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HistoryError` if the history can't be saved.
+    pub fn record_drive_retries(&mut self, drive_number: u8, count: u64) -> Result<()> {
+        self.drive_stats.entry(drive_number).or_default().retries += count;
+        self.save()
+    }
+
+    /// Returns the statistics of every drive that has ripped something, by drive number.
+    #[must_use]
+    pub fn drive_stats(&self) -> &BTreeMap<u8, DriveStats> {
+        &self.drive_stats
+    }
+}
+
+/// Removes the parent directories of a path while they are empty, stopping at `root`
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == root || !dir.starts_with(root) {
+            break;
+        }
+        // Fails when the directory isn't empty, which is exactly when to stop
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+        trace!("Removed empty directory {}", dir.display());
+        current = dir.parent();
+    }
+}
+
+/// The current time in seconds since the unix epoch
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Writes `value` to `path` as JSON. It is written to a temporary file next to it first,
+/// and renamed over it once written, so a crash can't leave a half written file.
+pub(crate) fn write_json_atomic<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+) -> std::io::Result<()> {
+    let contents = serde_json::to_string_pretty(value)?;
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path)
+}
@@ -0,0 +1,299 @@
+//! # Library Import
+//!
+//! Files ripped before cord-ripper was set up, or ripped by hand since, aren't in the
+//! history, so nothing knows about them. The import reads the title, year, season and
+//! episode back out of the paths of the files in the output directory, so they can be
+//! added to the history like any other rip.
+//!
+//! ## Notes
+//!
+//! - Libraries aren't always named by cord-ripper, so names are read loosely rather than
+//!   by reversing the naming templates. A file is an episode if it is in a `Season N` or
+//!   `Specials` folder, or its name has `S01E05` or `1x05` in it. Any other video file is
+//!   a movie, named `Title (Year)` or just `Title`.
+//! - The show of an episode is the folder above its season folder, or the folder it is
+//!   in for flat layouts. Files straight in a top level folder such as `shows/` use the
+//!   start of their name instead.
+//! - The parts of a multi-file rip, e.g. `Movie - Part 2.mkv`, are imported together as
+//!   one rip.
+
+use std::path::Path;
+
+use crate::makemkv::episodes::EpisodePattern;
+use crate::makemkv::RipType;
+
+/// The extensions of the video files that are imported, anything else is ignored
+const VIDEO_EXTENSIONS: [&str; 6] = ["mkv", "mp4", "m4v", "avi", "ts", "m2ts"];
+
+/// A rip read back from the path of a file in the library.
+///
+/// # Fields
+///
+/// * `title` - The title of the movie or show.
+/// * `year` - The release year of a movie, if its name has one.
+/// * `rip_type` - Whether the file is a movie or a show episode.
+/// * `key` - The name of the file without its extension or part number, files in the
+///   same folder with the same key are parts of the same rip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedRip {
+    pub title: String,
+    pub year: Option<u16>,
+    pub rip_type: RipType,
+    pub key: String,
+}
+
+/// Reads a rip from the path of a file, relative to the output directory. `None` if it
+/// isn't a video file, or no title could be found for it.
+#[must_use]
+pub fn parse_library_path(relative: &Path) -> Option<ImportedRip> {
+    let extension = relative.extension()?.to_string_lossy().to_lowercase();
+    if !VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+
+    let stem = relative.file_stem()?.to_string_lossy();
+    let key = strip_part(&stem).to_string();
+
+    let folders: Vec<String> = relative
+        .parent()
+        .map(|parent| {
+            parent
+                .iter()
+                .map(|folder| folder.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let rip = if let Some((title, season, episode)) = parse_episode(&key, &folders) {
+        ImportedRip {
+            title,
+            year: None,
+            rip_type: RipType::Show {
+                season,
+                episode,
+                absolute_episode: None,
+            },
+            key,
+        }
+    } else {
+        let (title, year) = split_year(&key);
+        ImportedRip {
+            title: title.to_string(),
+            year,
+            rip_type: RipType::Movie,
+            key,
+        }
+    };
+
+    (!rip.title.is_empty()).then_some(rip)
+}
+
+/// Reads the show, season and episode of a file, if it is an episode
+fn parse_episode(name: &str, folders: &[String]) -> Option<(String, u8, u8)> {
+    // A season folder names the season, and the folder above it the show
+    if let Some(index) = folders
+        .iter()
+        .rposition(|folder| season_folder(folder).is_some())
+    {
+        let season = season_folder(&folders[index])?;
+        let episode = season_episode(name)
+            .filter(|&(found, _)| found == season)
+            .map(|(_, episode)| episode)
+            .or_else(|| {
+                EpisodePattern::defaults()
+                    .iter()
+                    .find_map(|pattern| pattern.episode(name, season))
+            })?;
+        let title = match index {
+            0 => show_from_name(name),
+            _ => folders[index - 1].clone(),
+        };
+        return Some((title, season, episode));
+    }
+
+    let (season, episode) = season_episode(name)?;
+
+    // Flat layouts keep each show in its own folder, right under a top level folder
+    let title = if folders.len() >= 2 {
+        folders[folders.len() - 1].clone()
+    } else {
+        show_from_name(name)
+    };
+    Some((title, season, episode))
+}
+
+/// The season of a `Season N` or `Specials` folder
+fn season_folder(folder: &str) -> Option<u8> {
+    let folder = folder.trim().to_lowercase();
+    if folder == "specials" {
+        return Some(0);
+    }
+    folder.strip_prefix("season")?.trim().parse().ok()
+}
+
+/// Finds `S01E05` or `1x05` in a file name, returning the season and episode
+fn season_episode(name: &str) -> Option<(u8, u8)> {
+    find_season_episode(name).map(|(_, season, episode)| (season, episode))
+}
+
+/// Finds `S01E05` or `1x05` in a file name, returning where it starts along with the
+/// season and episode
+fn find_season_episode(name: &str) -> Option<(usize, u8, u8)> {
+    // Only ASCII is lowercased, so positions still line up with the original name
+    let name = name.to_ascii_lowercase();
+    let bytes = name.as_bytes();
+
+    for start in 0..bytes.len() {
+        // A number can't start partway through a word or another number
+        if !name.is_char_boundary(start) || (start > 0 && bytes[start - 1].is_ascii_alphanumeric())
+        {
+            continue;
+        }
+
+        let rest = &name[start..];
+        let (rest, season_prefix) = match rest.strip_prefix('s') {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+
+        let Some((season, rest)) = leading_number(rest) else {
+            continue;
+        };
+        let separator = if season_prefix { 'e' } else { 'x' };
+        let Some(rest) = rest.strip_prefix(separator) else {
+            continue;
+        };
+        let Some((episode, rest)) = leading_number(rest) else {
+            continue;
+        };
+        if rest.starts_with(|c: char| c.is_ascii_alphanumeric()) && !season_prefix {
+            continue;
+        }
+
+        if let (Ok(season), Ok(episode)) = (season.parse(), episode.parse()) {
+            return Some((start, season, episode));
+        }
+    }
+
+    None
+}
+
+/// Splits the digits off the start of a string
+fn leading_number(text: &str) -> Option<(&str, &str)> {
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    (digits > 0).then(|| text.split_at(digits))
+}
+
+/// The show in a file name such as `Show - S01E05 - The Title`, everything before the
+/// episode number
+fn show_from_name(name: &str) -> String {
+    let end = find_season_episode(name).map_or(name.len(), |(start, _, _)| start);
+
+    name[..end]
+        .trim_end_matches([' ', '-', '.', '_'])
+        .replace('.', " ")
+        .trim()
+        .to_string()
+}
+
+/// Splits the year off a name such as `Title (2019)`
+fn split_year(name: &str) -> (&str, Option<u16>) {
+    let trimmed = name.trim_end();
+    if let Some(rest) = trimmed.strip_suffix(')') {
+        if let Some((title, year)) = rest.rsplit_once('(') {
+            if year.len() == 4 {
+                if let Ok(year) = year.parse() {
+                    return (title.trim_end(), Some(year));
+                }
+            }
+        }
+    }
+    (trimmed, None)
+}
+
+/// Removes the ` - Part N` a multi-file rip adds to the name of each part
+fn strip_part(stem: &str) -> &str {
+    stem.rsplit_once(" - Part ")
+        .filter(|(_, part)| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+        .map_or(stem, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(path: &str) -> Option<(String, Option<u16>, RipType)> {
+        parse_library_path(Path::new(path)).map(|rip| (rip.title, rip.year, rip.rip_type))
+    }
+
+    fn show(season: u8, episode: u8) -> RipType {
+        RipType::Show {
+            season,
+            episode,
+            absolute_episode: None,
+        }
+    }
+
+    #[test]
+    fn reads_movies() {
+        assert_eq!(
+            parse("movies/Heat (1995)/Heat (1995).mkv"),
+            Some(("Heat".into(), Some(1995), RipType::Movie))
+        );
+        assert_eq!(
+            parse("movies/Heat (1995)/Heat (1995) - Part 2.mkv"),
+            Some(("Heat".into(), Some(1995), RipType::Movie))
+        );
+        assert_eq!(
+            parse("Alien.mp4"),
+            Some(("Alien".into(), None, RipType::Movie))
+        );
+        assert_eq!(parse("movies/Heat (1995)/poster.jpg"), None);
+    }
+
+    #[test]
+    fn reads_episodes_in_season_folders() {
+        assert_eq!(
+            parse("shows/Lost/Season 2/Episode 5.mkv"),
+            Some(("Lost".into(), None, show(2, 5)))
+        );
+        assert_eq!(
+            parse("TV Shows/Lost/Season 02/Lost - S02E05.mkv"),
+            Some(("Lost".into(), None, show(2, 5)))
+        );
+        assert_eq!(
+            parse("shows/Lost/Specials/Episode 1.mkv"),
+            Some(("Lost".into(), None, show(0, 1)))
+        );
+    }
+
+    #[test]
+    fn reads_episodes_in_flat_layouts() {
+        assert_eq!(
+            parse("shows/Lost/Lost - S01E04.mkv"),
+            Some(("Lost".into(), None, show(1, 4)))
+        );
+        assert_eq!(
+            parse("shows/Lost - 1x04 - Walkabout.mkv"),
+            Some(("Lost".into(), None, show(1, 4)))
+        );
+        assert_eq!(
+            parse("lost.s01e04.1080p.mkv"),
+            Some(("lost".into(), None, show(1, 4)))
+        );
+        // Resolutions aren't episodes
+        assert_eq!(
+            parse("movies/Heat 1920x1080.mkv"),
+            Some(("Heat 1920x1080".into(), None, RipType::Movie))
+        );
+    }
+
+    #[test]
+    fn parts_share_a_key() {
+        let first = parse_library_path(Path::new("movies/Heat/Heat - Part 1.mkv")).unwrap();
+        let second = parse_library_path(Path::new("movies/Heat/Heat - Part 2.mkv")).unwrap();
+        assert_eq!(first.key, second.key);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod errors;
+pub mod export;
+pub mod history_core;
+pub mod import;
+
+pub use history_core::{DriveStats, HistoryEntry, RipSource, HISTORY};
@@ -0,0 +1,287 @@
+//! # Internationalization
+//!
+//! Every message the Discord bot shows is written in English, and looked up in the
+//! catalog of the configured locale as it is shown. Catalogs are JSON files in `locales/`
+//! mapping the English text to its translation, so the English text doubles as the key
+//! and a missing translation falls back to English.
+//!
+//! Messages with values in them name their placeholders, e.g. `Ripping {title}...`, so a
+//! translation can put them wherever its grammar needs. The `tr!` macro fills them in.
+//!
+//! ## Adding a locale
+//!
+//! Copy `locales/es.json`, translate the values, and add a variant to `Locale` that
+//! includes the new file.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::{debug, warn};
+
+/// The locales messages can be shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Locale {
+    /// The catalog of translations for the locale, `None` for English
+    fn catalog(self) -> Option<&'static str> {
+        match self {
+            Locale::English => None,
+            Locale::Spanish => Some(include_str!("../locales/es.json")),
+        }
+    }
+}
+
+/// Global catalog of the configured locale, empty for English
+static CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Convert a locale code, such as `es`, to its `Locale`
+#[must_use]
+pub fn locale_from_str(locale: &str) -> Option<Locale> {
+    match locale.to_lowercase().as_str() {
+        "en" | "english" => Some(Locale::English),
+        "es" | "spanish" | "español" => Some(Locale::Spanish),
+        _ => None,
+    }
+}
+
+/// Set the locale messages are shown in, this can only be done once at startup
+pub fn set_locale(locale: Locale) {
+    let catalog = match locale.catalog().map(serde_json::from_str) {
+        Some(Ok(catalog)) => catalog,
+        Some(Err(e)) => {
+            warn!(
+                "Failed to read the {:?} catalog, using English: {}",
+                locale, e
+            );
+            HashMap::new()
+        }
+        None => HashMap::new(),
+    };
+
+    if CATALOG.set(catalog).is_err() {
+        warn!("Locale was already set, ignoring");
+        return;
+    }
+    debug!("Locale set to: {:?}", locale);
+}
+
+/// Looks up the translation of a message, falling back to the message itself
+#[must_use]
+pub fn translate(message: &str) -> String {
+    CATALOG
+        .get()
+        .and_then(|catalog| catalog.get(message))
+        .map_or(message, String::as_str)
+        .to_string()
+}
+
+/// Looks up the translation of a message and fills in its named placeholders
+#[must_use]
+pub fn translate_with(message: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(translate(message), |translated, (name, value)| {
+            translated.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+/// Reads the values of the placeholders back out of `text`, which was made from `message`
+/// by `tr!`. Used for embed values that are read back later, e.g. `Title {title}`.
+///
+/// Returns `None` if `text` doesn't match the translated message.
+#[must_use]
+pub fn untranslate(message: &str, text: &str) -> Option<HashMap<String, String>> {
+    let template = translate(message);
+
+    // Splits the template into its literal text, and the placeholder following each
+    let mut parts: Vec<(&str, Option<&str>)> = Vec::new();
+    let mut rest = template.as_str();
+    while let Some((literal, after)) = rest.split_once('{') {
+        let (name, after) = after.split_once('}')?;
+        parts.push((literal, Some(name)));
+        rest = after;
+    }
+    parts.push((rest, None));
+
+    let mut values = HashMap::new();
+    let mut text = text.strip_prefix(parts[0].0)?;
+
+    for (index, (_, name)) in parts.iter().enumerate() {
+        let Some(name) = name else {
+            break;
+        };
+
+        // A placeholder runs up to the literal text after it, or the end of the text
+        let next = parts[index + 1].0;
+        let end = if next.is_empty() {
+            text.len()
+        } else {
+            text.find(next)?
+        };
+
+        values.insert((*name).to_string(), text[..end].to_string());
+        text = &text[end + next.len()..];
+    }
+
+    text.is_empty().then_some(values)
+}
+
+/// Translates a message to the configured locale, filling in any named placeholders.
+///
+/// ```ignore
+/// tr!("Ripping {title}...", title = rip.title)
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($message:expr) => {
+        $crate::i18n::translate($message)
+    };
+    ($message:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate_with(
+            $message,
+            &[$((stringify!($name), $value.to_string())),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `{name}` placeholders in a message, sorted
+    fn placeholders(message: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = message
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn catalogs_keep_their_placeholders() {
+        for locale in [Locale::Spanish] {
+            let catalog: HashMap<String, String> =
+                serde_json::from_str(locale.catalog().unwrap()).unwrap();
+
+            for (message, translation) in &catalog {
+                assert_eq!(
+                    placeholders(message),
+                    placeholders(translation),
+                    "{locale:?} translation of {message:?}"
+                );
+            }
+        }
+    }
+
+    /// The messages given to `tr!` as string literals in `source`
+    fn literal_messages(source: &str) -> Vec<String> {
+        let mut messages = Vec::new();
+        for (start, call) in source.match_indices("tr!(") {
+            let rest = source[start + call.len()..].trim_start();
+            if let Some(raw) = rest.strip_prefix("r#\"") {
+                if let Some((message, _)) = raw.split_once("\"#") {
+                    messages.push(message.to_string());
+                }
+                continue;
+            }
+            let Some(quoted) = rest.strip_prefix('"') else {
+                continue;
+            };
+
+            let mut message = String::new();
+            let mut chars = quoted.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (c, chars.next_if(|_| c == '\\')) {
+                    ('"', _) => break,
+                    (_, Some('n')) => message.push('\n'),
+                    // A line continuation skips the line break and the indentation after it
+                    (_, Some('\n')) => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+                    (_, Some(escaped)) => message.push(escaped),
+                    (c, None) => message.push(c),
+                }
+            }
+            messages.push(message);
+        }
+        messages
+    }
+
+    /// The source files under `dir`, other than this one, which mentions `tr!` itself
+    fn source_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                source_files(&path, files);
+            } else if path.extension().is_some_and(|extension| extension == "rs")
+                && !path.ends_with("i18n.rs")
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn catalogs_translate_every_message() {
+        let mut files = Vec::new();
+        source_files(
+            &std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut files,
+        );
+
+        for locale in [Locale::Spanish] {
+            let catalog: HashMap<String, String> =
+                serde_json::from_str(locale.catalog().unwrap()).unwrap();
+
+            let mut missing: Vec<String> = files
+                .iter()
+                .flat_map(|file| literal_messages(&std::fs::read_to_string(file).unwrap()))
+                .filter(|message| !catalog.contains_key(message))
+                .collect();
+            missing.sort_unstable();
+            missing.dedup();
+            assert!(missing.is_empty(), "{locale:?} is missing {missing:#?}");
+        }
+    }
+
+    #[test]
+    fn reads_placeholders_back() {
+        let values = untranslate(
+            "Title {title}: Episode {episode} (Absolute {absolute})",
+            "Title 3: Episode 5 (Absolute 29)",
+        )
+        .unwrap();
+        assert_eq!(values["title"], "3");
+        assert_eq!(values["episode"], "5");
+        assert_eq!(values["absolute"], "29");
+
+        assert_eq!(
+            untranslate("Title {title}", "Title 12").unwrap()["title"],
+            "12"
+        );
+        assert_eq!(
+            untranslate("Title {title}: Episode {episode}", "Title 3"),
+            None
+        );
+        assert_eq!(untranslate("Title {title}", "Disc 3"), None);
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        assert_eq!(
+            translate_with(
+                "Ripping {title}, {episode}",
+                &[
+                    ("title", "Show".to_string()),
+                    ("episode", "Episode 2".to_string())
+                ]
+            ),
+            "Ripping Show, Episode 2"
+        );
+    }
+}
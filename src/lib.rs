@@ -0,0 +1,105 @@
+//! # Cord Ripper
+//!
+//! The ripping engine behind the Cord Ripper Discord bot. Discs are ripped with `MakeMKV`,
+//! named for Plex, Jellyfin, Sonarr or Radarr, queued so only so many run at once, and
+//! recorded in a history. The bot and its command line are thin binaries on top of this
+//! library, and the Discord parts can be left out to embed the engine in another app.
+//!
+//! ## Features
+//! - `discord` (default): The Discord bot, in the `discord` module, and the Discord checks
+//!   of the `diagnostics` module. Turn off default features to leave `serenity` out.
+//!
+//! ## Usage
+//!
+//! Ripping only needs a `MakeMkv` of its own, nothing has to be set up globally. Drives are
+//! listed with `get_drives`, which also numbers them for the rips.
+//!
+//! ```no_run
+//! use cord_ripper::makemkv::{get_drives, get_title_info, MakeMkv, Rip, RipType};
+//!
+//! # async fn example() -> Result<(), cord_ripper::makemkv::errors::MakeMkvError> {
+//! let mut makemkv = MakeMkv::new("/srv/media");
+//! makemkv.init("/srv/media").await?;
+//!
+//! let drives = get_drives().await?;
+//! let disc = get_title_info(drives[0].drive_number).await?;
+//! let rip = Rip {
+//!     title: disc.disc_name.clone(),
+//!     drive_number: drives[0].drive_number,
+//!     rip_type: RipType::Movie,
+//!     title_id: 1,
+//!     year: None,
+//!     chapters_per_episode: None,
+//!     library: None,
+//!     episode_title: None,
+//!     note: None,
+//!     extra: None,
+//!     base_view: false,
+//! };
+//! let files = makemkv.run_rip(&rip).await?.files;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Rips started by the bot go through `Rip::execute`, which also records them in the
+//! history and library, and rip events can be followed with `makemkv::events::subscribe`.
+//!
+//! ## Modules
+//! - `archive`: Images data discs with a manifest and checksums.
+//! - `audit`: Records who asked the bot to rip, cancel or delete what.
+//! - `digest`: Sums up each week of ripping for the weekly digest.
+//! - `diagnostics`: Checks everything the bot needs, for `/diagnostics` and `--self-test`.
+//! - `container`: Reads secrets, checks passed through drives and answers health checks
+//!   when running in a container.
+//! - `arr`: Integrates with Sonarr and Radarr for naming and importing rips.
+//! - `barcode`: Looks up the barcodes of disc cases to name discs with useless labels.
+//! - `collection`: Keeps the list of discs the household still wants ripped.
+//! - `audio`: Rips audio CDs to FLAC, tagged with details from `MusicBrainz`.
+//! - `discord`: Contains the Discord bot implementation, with the `discord` feature.
+//! - `disk`: Monitors the free space of the output directories.
+//! - `history`: Keeps a persistent record of completed rips.
+//! - `i18n`: Translates the Discord bot's messages to the configured locale.
+//! - `library`: Keeps an in-memory index of the files in the output directory.
+//! - `logging`: Provides logging utilities.
+//! - `makemkv`: Handles `MakeMKV` integration.
+//! - `preview`: Grabs frames from the titles on a disc to preview them with `ffmpeg`.
+//! - `probe`: Reads the size, bitrate and audio tracks of ripped files with `ffprobe`.
+//! - `quota`: Limits how many rips each user can start in a day or a week.
+//! - `settings`: Keeps the settings chosen with `/setup` in the data directory.
+//! - `queue`: Schedules rips and transcodes so only a limited number run at once, in order
+//!   of priority.
+//! - `update`: Checks GitHub for a newer release of Cord Ripper.
+//! - `upload`: Copies finished rips to remote storage with `rclone`.
+//! - `watch`: Imports MKVs ripped by hand from a watch folder into the library.
+
+#![warn(clippy::pedantic)]
+
+pub mod archive;
+pub mod arr;
+pub mod audio;
+pub mod audit;
+pub mod barcode;
+pub mod collection;
+pub mod container;
+pub mod diagnostics;
+pub mod digest;
+#[cfg(feature = "discord")]
+pub mod discord;
+pub mod disk;
+pub mod errors;
+pub mod history;
+pub mod i18n;
+pub mod library;
+pub mod logging;
+pub mod makemkv;
+pub mod metadata;
+pub mod preview;
+pub mod probe;
+pub mod queue;
+pub mod quota;
+pub mod settings;
+pub mod update;
+pub mod upload;
+pub mod watch;
+
+pub use logging::{current_log_level, DEBUG, ERROR, INFO, TRACE, WARN};
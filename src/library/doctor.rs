@@ -0,0 +1,335 @@
+//! # Library Doctor
+//!
+//! Rips that crash, files moved around by hand and discs that were never finished leave
+//! the library in a state that is easy to miss until someone goes to watch something. The
+//! doctor looks through the library for the usual suspects.
+//!
+//! ## Overview
+//!
+//! - **`DoctorReport`**: What was found, by kind of problem.
+//!
+//! - **`examine`**: Looks through the library for problems.
+//!
+//! - **`fix`**: Removes the empty files and leftover temporary directories of a report.
+//!
+//! ## Notes
+//!
+//! - Only empty files and temporary directories are fixed, they can't be anything worth
+//!   keeping. Gaps and mismatched names are only reported, since fixing them needs a
+//!   person to know which episode is missing or which name is right.
+//! - Episodes and movies are recognized the same way as by the library import, so a
+//!   library that wasn't named by cord-ripper is checked too.
+//! - Specials aren't checked for gaps, they are rarely a complete set.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    hash::BuildHasher,
+    path::{Path, PathBuf},
+};
+
+use crate::history::import::parse_library_path;
+use crate::makemkv::makemkv_helpers::TEMP_DIR_PREFIX;
+use crate::makemkv::RipType;
+use crate::{debug, trace, warn};
+
+use super::LibraryIndex;
+
+/// Episodes missing from a season.
+///
+/// # Fields
+///
+/// * `show` - The title of the show.
+/// * `season` - The season with the gap.
+/// * `missing` - The episodes missing before the last one in the season.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpisodeGap {
+    pub show: String,
+    pub season: u8,
+    pub missing: Vec<u8>,
+}
+
+/// The problems found in the library.
+///
+/// # Fields
+///
+/// * `empty_files` - Files with nothing in them, usually from a rip that crashed.
+/// * `temp_dirs` - Temporary directories left behind by failed rips.
+/// * `episode_gaps` - Seasons with episodes missing from their numbering.
+/// * `name_mismatches` - Movies whose file name doesn't match the folder they are in.
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub empty_files: Vec<PathBuf>,
+    pub temp_dirs: Vec<PathBuf>,
+    pub episode_gaps: Vec<EpisodeGap>,
+    pub name_mismatches: Vec<PathBuf>,
+}
+
+impl DoctorReport {
+    /// Whether nothing was found
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.empty_files.is_empty()
+            && self.temp_dirs.is_empty()
+            && self.episode_gaps.is_empty()
+            && self.name_mismatches.is_empty()
+    }
+
+    /// Whether anything found can be fixed with `fix`
+    #[must_use]
+    pub fn is_fixable(&self) -> bool {
+        !self.empty_files.is_empty() || !self.temp_dirs.is_empty()
+    }
+}
+
+/// What `fix` cleaned up.
+///
+/// # Fields
+///
+/// * `removed_files` - The empty files that were removed.
+/// * `removed_dirs` - The number of temporary directories that were removed.
+/// * `failed` - The paths that couldn't be removed, with why.
+#[derive(Debug, Clone, Default)]
+pub struct FixOutcome {
+    pub removed_files: Vec<PathBuf>,
+    pub removed_dirs: usize,
+    pub failed: Vec<String>,
+}
+
+/// Looks through the library for problems. The temporary directories in `active` belong
+/// to rips that are still running and are left out. This reads the size of every file,
+/// so should be run with `spawn_blocking`.
+#[must_use]
+pub fn examine<S: BuildHasher>(
+    library: &LibraryIndex,
+    active: &HashSet<PathBuf, S>,
+) -> DoctorReport {
+    let files: Vec<PathBuf> = library.files().collect();
+
+    let empty_files = files
+        .iter()
+        .filter(|file| std::fs::metadata(file).is_ok_and(|metadata| metadata.len() == 0))
+        .cloned()
+        .collect();
+
+    // Rips are only written to temporary directories in the output directory
+    let mut report = DoctorReport {
+        empty_files,
+        temp_dirs: library
+            .roots()
+            .first()
+            .map(|output_dir| temp_dirs(output_dir, active))
+            .unwrap_or_default(),
+        ..DoctorReport::default()
+    };
+
+    // Each library is checked on its own, so the same show in two of them isn't mixed up
+    for root in library.roots() {
+        let relative: Vec<&Path> = files
+            .iter()
+            .filter(|file| library.root_of(file) == Some(root.as_path()))
+            .filter_map(|file| file.strip_prefix(root).ok())
+            .collect();
+
+        report.episode_gaps.extend(episode_gaps(&relative));
+        report.name_mismatches.extend(
+            relative
+                .iter()
+                .filter(|file| is_name_mismatch(file))
+                .map(|file| root.join(file)),
+        );
+    }
+    report.empty_files.sort();
+    report.name_mismatches.sort();
+
+    debug!("Library doctor found {:?}", report);
+    report
+}
+
+/// Removes the empty files and temporary directories of a report. Anything that can't
+/// be removed is skipped, and listed in the outcome.
+#[must_use]
+pub fn fix(report: &DoctorReport) -> FixOutcome {
+    let mut outcome = FixOutcome::default();
+
+    for file in &report.empty_files {
+        // Only removed if it's still empty, in case it was written to since
+        if !std::fs::metadata(file).is_ok_and(|metadata| metadata.len() == 0) {
+            trace!("{} is no longer empty, skipping", file.display());
+            continue;
+        }
+        match std::fs::remove_file(file) {
+            Ok(()) => outcome.removed_files.push(file.clone()),
+            Err(e) => {
+                warn!("Failed to remove {}: {}", file.display(), e);
+                outcome.failed.push(format!("{}: {e}", file.display()));
+            }
+        }
+    }
+
+    for dir in &report.temp_dirs {
+        match std::fs::remove_dir_all(dir) {
+            Ok(()) => outcome.removed_dirs += 1,
+            Err(e) => {
+                warn!("Failed to remove {}: {}", dir.display(), e);
+                outcome.failed.push(format!("{}: {e}", dir.display()));
+            }
+        }
+    }
+
+    outcome
+}
+
+/// The temporary rip directories in the output directory, other than those in `active`
+fn temp_dirs<S: BuildHasher>(root: &Path, active: &HashSet<PathBuf, S>) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        warn!(
+            "Failed to read {} for temporary directories",
+            root.display()
+        );
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(TEMP_DIR_PREFIX)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| !active.contains(path))
+        .collect();
+
+    dirs.sort();
+    dirs
+}
+
+/// Finds the seasons with episodes missing, from the paths of the files in the library
+/// relative to the output directory
+fn episode_gaps(files: &[&Path]) -> Vec<EpisodeGap> {
+    // Seasons are told apart by their folder too, so two shows of the same name in
+    // different places aren't mixed together
+    let mut seasons: BTreeMap<(PathBuf, String, u8), BTreeSet<u8>> = BTreeMap::new();
+
+    for file in files {
+        let Some(rip) = parse_library_path(file) else {
+            continue;
+        };
+        let RipType::Show {
+            season, episode, ..
+        } = rip.rip_type
+        else {
+            continue;
+        };
+        if season == 0 {
+            continue;
+        }
+
+        let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        seasons
+            .entry((dir, rip.title, season))
+            .or_default()
+            .insert(episode);
+    }
+
+    seasons
+        .into_iter()
+        .filter_map(|((_, show, season), episodes)| {
+            let last = *episodes.last()?;
+            let missing: Vec<u8> = (1..last)
+                .filter(|episode| !episodes.contains(episode))
+                .collect();
+
+            (!missing.is_empty()).then_some(EpisodeGap {
+                show,
+                season,
+                missing,
+            })
+        })
+        .collect()
+}
+
+/// Whether a movie's file is named differently to the folder it has to itself, e.g.
+/// `movies/Heat (1995)/Heat.mkv`. Files straight in a top level folder such as `movies/`
+/// have no folder of their own to match.
+fn is_name_mismatch(file: &Path) -> bool {
+    if file.components().count() < 3 {
+        return false;
+    }
+    let Some(rip) = parse_library_path(file) else {
+        return false;
+    };
+    if rip.rip_type != RipType::Movie {
+        return false;
+    }
+
+    file.parent()
+        .and_then(Path::file_name)
+        .is_some_and(|folder| folder.to_string_lossy() != rip.key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_missing_episodes() {
+        let files = [
+            Path::new("shows/Lost/Season 1/Episode 1.mkv"),
+            Path::new("shows/Lost/Season 1/Episode 2.mkv"),
+            Path::new("shows/Lost/Season 1/Episode 5.mkv"),
+            Path::new("shows/Lost/Season 2/Episode 1.mkv"),
+            Path::new("shows/Lost/Season 2/Episode 2.mkv"),
+            Path::new("shows/Lost/Specials/Episode 3.mkv"),
+        ];
+
+        assert_eq!(
+            episode_gaps(&files),
+            vec![EpisodeGap {
+                show: "Lost".into(),
+                season: 1,
+                missing: vec![3, 4],
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_movies_named_differently_to_their_folder() {
+        assert!(is_name_mismatch(Path::new("movies/Heat (1995)/Heat.mkv")));
+        assert!(!is_name_mismatch(Path::new(
+            "movies/Heat (1995)/Heat (1995).mkv"
+        )));
+        assert!(!is_name_mismatch(Path::new(
+            "movies/Heat (1995)/Heat (1995) - Part 2.mkv"
+        )));
+        assert!(!is_name_mismatch(Path::new("movies/Heat.mkv")));
+        assert!(!is_name_mismatch(Path::new(
+            "shows/Lost/Season 1/Episode 1.mkv"
+        )));
+    }
+
+    #[test]
+    fn fixes_empty_files_and_temp_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let movie = dir.path().join("movies/Heat/Heat.mkv");
+        std::fs::create_dir_all(movie.parent().unwrap()).unwrap();
+        std::fs::write(&movie, "").unwrap();
+        let active = dir.path().join(format!("{TEMP_DIR_PREFIX}active"));
+        let stale = dir.path().join(format!("{TEMP_DIR_PREFIX}stale"));
+        std::fs::create_dir(&active).unwrap();
+        std::fs::create_dir(&stale).unwrap();
+
+        let library = LibraryIndex::scan(&[dir.path().to_path_buf()]).unwrap();
+        let report = examine(&library, &HashSet::from([active.clone()]));
+        assert_eq!(report.empty_files, vec![movie.clone()]);
+        assert_eq!(report.temp_dirs, vec![stale.clone()]);
+        assert!(report.is_fixable());
+
+        let outcome = fix(&report);
+        assert_eq!(outcome.removed_files, vec![movie.clone()]);
+        assert_eq!(outcome.removed_dirs, 1);
+        assert!(!movie.exists() && !stale.exists() && active.exists());
+    }
+}
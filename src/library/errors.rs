@@ -0,0 +1,16 @@
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, LibraryError>;
+
+#[derive(Debug, Error)]
+pub enum LibraryError {
+    #[error("Failed to scan library: {0}")]
+    ScanError(String),
+}
+
+impl From<io::Error> for LibraryError {
+    fn from(error: io::Error) -> Self {
+        LibraryError::ScanError(error.to_string())
+    }
+}
@@ -0,0 +1,252 @@
+//! # Library Core Module
+//!
+//! This module keeps an in-memory index of the files in the output directory and every
+//! named library, so lookups
+//! such as finding the last episode of a season don't hit the filesystem every time. On a
+//! large network mount, those scans get slow quickly.
+//!
+//! ## Overview
+//!
+//! - **`LibraryIndex`**: The files in each library root, grouped by directory.
+//!
+//! - **`LIBRARY`**: A globally accessible, thread-safe instance of `LibraryIndex`.
+//!
+//! ## Notes
+//!
+//! - The index is built once at startup and updated as files are ripped, renamed and
+//!   deleted. Changes made outside of cord-ripper are only picked up by `/rescan`.
+//! - Hidden directories, such as the data directory, and the temporary directories rips
+//!   are written to are not indexed.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+use tokio::sync::Mutex;
+
+use crate::makemkv::makemkv_helpers::TEMP_DIR_PREFIX;
+use crate::{debug, info, trace, warn};
+
+use super::errors::Result;
+
+/// A globally accessible instance of `LibraryIndex` for looking up ripped files.
+pub static LIBRARY: LazyLock<Arc<Mutex<LibraryIndex>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(LibraryIndex::default())));
+
+/// The files in the output directory and the named libraries, grouped by the directory
+/// they are in.
+///
+/// # Fields
+///
+/// * `roots` - The directories that were scanned, the output directory first.
+/// * `dirs` - The names of the files in each directory, keyed by the full directory path.
+///
+/// # Methods
+///
+/// ## `scan`
+/// Builds an index by walking every directory under each of `roots`. This blocks, so
+/// should be run with `spawn_blocking` once the bot is running.
+///
+/// ## `roots`
+/// Returns the directories the index was built from.
+///
+/// ## `root_of`
+/// Returns the root a file is under.
+///
+/// ## `files_in`
+/// Returns the names of the files directly within a directory.
+///
+/// ## `contains`
+/// Checks whether a file is in the index.
+///
+/// ## `add` and `remove`
+/// Update the index after a file is ripped, moved or deleted.
+///
+/// ## `files`
+/// Returns the full path of every file in the index.
+///
+/// ## `len`
+/// Returns the number of files in the index.
+#[derive(Debug, Default)]
+pub struct LibraryIndex {
+    roots: Vec<PathBuf>,
+    dirs: HashMap<PathBuf, BTreeSet<String>>,
+}
+
+impl LibraryIndex {
+    /// # Errors
+    ///
+    /// Returns a `LibraryError` if the first of `roots`, the output directory, can't be
+    /// read. Named libraries and subdirectories that can't be read are skipped with a
+    /// warning.
+    pub fn scan(roots: &[PathBuf]) -> Result<Self> {
+        let mut index = LibraryIndex {
+            roots: roots.to_vec(),
+            dirs: HashMap::new(),
+        };
+
+        for (i, root) in roots.iter().enumerate() {
+            match index.scan_root(root) {
+                Ok(()) => {}
+                // Named libraries are only needed once a rip is saved to them
+                Err(e) if i > 0 => warn!("Failed to scan library {}: {}", root.display(), e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn scan_root(&mut self, root: &Path) -> Result<()> {
+        let mut count = 0;
+        let mut dir_count = 0;
+
+        // The root has to be readable, anything below it is best effort
+        let mut pending = vec![(root.to_path_buf(), std::fs::read_dir(root)?)];
+
+        while let Some((dir, entries)) = pending.pop() {
+            let mut files = BTreeSet::new();
+
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+
+                if file_type.is_dir() {
+                    if name.starts_with('.') || name.starts_with(TEMP_DIR_PREFIX) {
+                        trace!("Skipping {} while scanning library", entry.path().display());
+                        continue;
+                    }
+                    match std::fs::read_dir(entry.path()) {
+                        Ok(entries) => pending.push((entry.path(), entries)),
+                        Err(e) => warn!("Failed to scan {}: {}", entry.path().display(), e),
+                    }
+                } else if file_type.is_file() {
+                    files.insert(name);
+                }
+            }
+
+            if !files.is_empty() {
+                count += files.len();
+                dir_count += 1;
+                self.dirs.insert(dir, files);
+            }
+        }
+
+        info!(
+            "Indexed {} files in {} directories under {}",
+            count,
+            dir_count,
+            root.display()
+        );
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// The innermost root, so a named library within the output directory is found as
+    /// itself
+    #[must_use]
+    pub fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(PathBuf::as_path)
+    }
+
+    pub fn files_in(&self, dir: &Path) -> impl Iterator<Item = &str> {
+        self.dirs
+            .get(dir)
+            .into_iter()
+            .flat_map(|files| files.iter().map(String::as_str))
+    }
+
+    #[must_use]
+    pub fn contains(&self, path: &Path) -> bool {
+        match (path.parent(), path.file_name()) {
+            (Some(dir), Some(name)) => self
+                .dirs
+                .get(dir)
+                .is_some_and(|files| files.contains(name.to_string_lossy().as_ref())),
+            _ => false,
+        }
+    }
+
+    pub fn add(&mut self, path: &Path) {
+        if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+            debug!("Adding {} to the library index", path.display());
+            self.dirs
+                .entry(dir.to_path_buf())
+                .or_default()
+                .insert(name.to_string_lossy().to_string());
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+            debug!("Removing {} from the library index", path.display());
+            if let Some(files) = self.dirs.get_mut(dir) {
+                files.remove(name.to_string_lossy().as_ref());
+                if files.is_empty() {
+                    self.dirs.remove(dir);
+                }
+            }
+        }
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.dirs
+            .iter()
+            .flat_map(|(dir, files)| files.iter().map(move |name| dir.join(name)))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.dirs.values().map(BTreeSet::len).sum()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexes_every_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main");
+        let kids = dir.path().join("kids");
+        for file in [
+            main.join("Heat/Heat.mkv"),
+            kids.join("Bluey/Season 01/Bluey - S01E01.mkv"),
+        ] {
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(&file, "").unwrap();
+        }
+        let missing = dir.path().join("missing");
+
+        let index = LibraryIndex::scan(&[main.clone(), kids.clone(), missing]).unwrap();
+        let episode = kids.join("Bluey/Season 01/Bluey - S01E01.mkv");
+        assert_eq!(index.len(), 2);
+        assert!(index.contains(&episode));
+        assert_eq!(index.root_of(&episode), Some(kids.as_path()));
+        assert_eq!(
+            index.root_of(&main.join("Heat/Heat.mkv")),
+            Some(main.as_path())
+        );
+
+        assert!(LibraryIndex::scan(&[dir.path().join("missing")]).is_err());
+    }
+}
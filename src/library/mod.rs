@@ -0,0 +1,5 @@
+pub mod doctor;
+pub mod errors;
+pub mod library_core;
+
+pub use library_core::{LibraryIndex, LIBRARY};
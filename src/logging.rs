@@ -1,4 +1,5 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use crate::{debug, trace};
 
@@ -12,6 +13,22 @@ pub const ERROR: usize = 4;
 /// Global log level
 static LOG_LEVEL: AtomicUsize = AtomicUsize::new(INFO);
 
+/// The most lines held for the ops channel between posts, so a burst of errors can't pile up
+const MAX_MIRRORED_LINES: usize = 50;
+
+/// Whether warnings and errors are being held for the ops channel
+static MIRROR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Warnings and errors waiting to be posted to the ops channel, and how many were dropped
+/// since the last post because too many came in at once
+static MIRRORED: Mutex<(Vec<String>, usize)> = Mutex::new((Vec::new(), 0));
+
+/// Secrets, such as the Discord token, that are hidden from every logged line
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// What secrets are replaced with in logged lines
+const REDACTED: &str = "[REDACTED]";
+
 /// Set the log level dynamically
 pub fn set_log_level(level: usize) {
     LOG_LEVEL.store(level, Ordering::Relaxed);
@@ -23,6 +40,68 @@ pub fn current_log_level() -> usize {
     LOG_LEVEL.load(Ordering::Relaxed)
 }
 
+/// Start holding warnings and errors for the ops channel, see `take_mirrored`
+pub fn enable_mirror() {
+    MIRROR_ENABLED.store(true, Ordering::Relaxed);
+    debug!("Mirroring warnings and errors to the ops channel");
+}
+
+/// Holds a logged line for the ops channel, if mirroring is enabled. Called by `log!`, so
+/// it must not log anything itself.
+pub fn mirror(level: usize, tag: &str, message: &str) {
+    if level < WARN || !MIRROR_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    // A poisoned lock only means another thread panicked mid-push, the lines are still fine
+    let mut mirrored = MIRRORED
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if mirrored.0.len() < MAX_MIRRORED_LINES {
+        mirrored.0.push(format!("{tag} {message}"));
+    } else {
+        mirrored.1 += 1;
+    }
+}
+
+/// Takes the lines held for the ops channel, and the number dropped since the last take
+pub fn take_mirrored() -> (Vec<String>, usize) {
+    let mut mirrored = MIRRORED
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    std::mem::take(&mut *mirrored)
+}
+
+/// Hides `secret` from every line logged from now on, at every level. Anything logging a
+/// value the secret is part of, such as an error from the Discord client, is covered too.
+pub fn add_secret(secret: &str) {
+    // Short values would hide unrelated parts of lines, and no secret is that short
+    if secret.len() < 8 {
+        return;
+    }
+
+    let mut secrets = SECRETS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if !secrets.iter().any(|known| known == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// Replaces the secrets in a logged line. Called by `log!`, so it must not log anything
+/// itself.
+pub fn redact(mut message: String) -> String {
+    let secrets = SECRETS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    for secret in secrets.iter() {
+        if message.contains(secret.as_str()) {
+            message = message.replace(secret.as_str(), REDACTED);
+        }
+    }
+    message
+}
+
 /// Convert a log level string to its corresponding usize value
 pub fn log_level_from_str(level: &str) -> Option<usize> {
     let level_usize = match level.to_lowercase().as_str() {
@@ -41,7 +120,9 @@ pub fn log_level_from_str(level: &str) -> Option<usize> {
 macro_rules! log {
     ($level:expr, $color:expr, $tag:expr, $($arg:tt)*) => {
         if $level >= $crate::current_log_level() {
-            println!(concat!("\x1b[", $color, "m", $tag, "\x1b[0m {}"), format!($($arg)*));
+            let message = $crate::logging::redact(format!($($arg)*));
+            println!(concat!("\x1b[", $color, "m", $tag, "\x1b[0m {}"), message);
+            $crate::logging::mirror($level, $tag, &message);
         }
     };
 }
@@ -26,9 +26,114 @@
 //! ## Command-Line Arguments
 //! - `--log-level` or `-l`: Optional log level (e.g., `info`, `debug`, `warn`, etc.). Defaults to `info`.
 //! - `--output-dir` or `-o`: Required path to the desired output directory.
+//! - `--library`: Another directory rips can be saved to as `name=path`, such as
+//!   `kids=/mnt/kids`. Can be given more than once, each rip picks one before it starts. The
+//!   output directory is the `main` library.
+//! - `--library-3d`: The library 3D titles kept in 3D are saved to, one of those given with
+//!   `--library`, to keep them apart from players that can't play them.
+//! - `--data-dir` or `-d`: Optional path to store the rip history in. Defaults to `.cord-ripper`
+//!   within the output directory.
+//! - `--trash-retention-days`: Optional number of days to keep deleted rips in the trash before
+//!   they are removed for good. Defaults to 7, use 0 to delete rips immediately.
+//! - `--rip-log-retention-days`: Optional number of days to keep the `MakeMKV` log of each
+//!   rip job, see `/rip_log`. Defaults to 30, use 0 to keep no logs.
+//! - `--warn-free-space-gb`: Free space, in gigabytes, below which a low disk space alert is
+//!   posted. Defaults to 50.
+//! - `--min-free-space-gb`: Free space, in gigabytes, below which new rips are blocked.
+//!   Defaults to 10.
+//! - `--max-concurrent-rips`: The number of rips that can run at the same time on each
+//!   machine, this one and every agent, on different drives. Defaults to 1.
+//! - `--max-concurrent-transcodes`: The number of finished rips that can have their notes
+//!   and forced subtitles written into them at the same time. Defaults to 1.
+//! - `--max-concurrent-transfers`: The number of rips that can be copying to a library on
+//!   another mount at the same time, without holding their drives. Defaults to 1.
+//! - `--daily-rip-quota` and `--weekly-rip-quota`: The most rips each user can start in a
+//!   rolling day or week. Unlimited by default.
+//! - `--rip-cooldown-minutes`: How long each user has to wait after starting a rip before
+//!   they can start another. Defaults to 0.
+//! - `--quota-exempt-role`: The id of a Discord role whose members aren't held to the
+//!   quotas or cooldown. Can be given more than once, server administrators are always
+//!   exempt, as are the roles picked with `/setup`.
+//! - `--eject-after-rip`: Eject the disc once a rip finishes by default, this can still be
+//!   toggled for each rip.
+//! - `--episode-pattern`: A filename pattern to recognize ripped episodes by, such as
+//!   `S{season}E{episode}`. Can be given more than once, replacing the default patterns.
+//! - `--arr-import`: Hand finished rips to Sonarr or Radarr to import, rather than naming
+//!   them with cord-ripper's own templates.
+//! - `--nfo`: Write Kodi `.nfo` files next to finished rips, filled in from Sonarr and
+//!   Radarr when they are configured.
+//! - `--artwork`: Download the poster and background art of finished rips into their
+//!   folders, from Sonarr and Radarr.
+//! - `--tag-notes`: Write the note given to a rip into the title tag of its files, with
+//!   `mkvpropedit`.
+//! - `--forced-subtitles`: What to do with the forced subtitles of ripped titles, the
+//!   subtitles for scenes in another language. `flag` marks them as forced and default so
+//!   players show them, `extract` saves them next to the file, `off` leaves the file as is.
+//!   Defaults to `off`.
+//! - `--filename-profile`: The systems the names of ripped files have to work on, `posix`
+//!   to only replace `/`, `windows` to also replace `\ : * ? " < > |` and trim trailing
+//!   dots for SMB shares, or `strict` to also keep to ASCII letters, digits and simple
+//!   punctuation. Defaults to `windows`.
+//! - `--verify-copies`: Compare rips copied onto another mount, such as a library folder
+//!   mounted over NFS or SMB, byte for byte with the ripped file. Copies are always
+//!   checked by size, and copied again if they came up short.
+//! - `--upload-movies-to` and `--upload-shows-to`: An `rclone` remote, such as
+//!   `media-box:/srv/media`, to copy finished movie or show rips to. SFTP, S3 and anything
+//!   else `rclone` supports can be used by setting up a remote with `rclone config`.
+//! - `--watch-dir`: A folder to import MKVs ripped by hand from, such as with the `MakeMKV`
+//!   GUI. Files named like `Title (Year).mkv` or `Show S01E05.mkv`, or saved by the GUI in
+//!   a folder named after the disc, are named and moved into the output directory like
+//!   rips, and announced in the alert channel.
+//! - `--locale`: The language the Discord bot responds in, `en` or `es`. Defaults to `en`.
+//! - `--embed-color`: The accent color of the bot's embeds as hex, such as `#1e90ff`.
+//!   Defaults to `#fe0000`.
+//! - `--embed-footer` and `--embed-footer-icon-url`: Optional footer text, and an icon next
+//!   to it, shown on every embed.
+//! - `--embed-thumbnail-url`: Optional image shown in the corner of every embed.
+//! - `--no-embed-timestamps`: Leave the time sent off of embeds.
+//! - `--ephemeral-responses`: Only show command responses, such as the select menus of a
+//!   rip, to whoever ran the command. Rips post their progress to the channel once started.
+//! - `--selection-cleanup`: What happens to the message a rip was set up on once it starts,
+//!   `keep`, `collapse` or `delete`. Defaults to `keep`.
+//! - `--rip-threads`: Post the progress and summary of each rip to a thread of its own.
+//! - `--container`: Check the drives were passed through to the container at startup, and
+//!   answer health checks on port 8080.
+//! - `--health-check-port`: Answer health checks at `/health` on this port.
+//! - `--require-device`: A device, such as `/dev/sr0`, that has to be there at startup. Can
+//!   be given more than once, replacing the `/dev/sr` devices checked in container mode.
+//! - `--makemkvcon`: How to run `makemkvcon`, its path or a wrapper such as
+//!   `flatpak run --command=makemkvcon com.makemkv.MakeMKV`. Defaults to `makemkvcon`.
+//! - `--makemkv-command-prefix`: A command to run `makemkvcon` through, such as
+//!   `docker exec makemkv` to run it in a sibling container.
+//! - `--agent`: A `cord-ripper-agent` to rip from as `name=url`, such as
+//!   `garage=http://garage-pc:9000`. Can be given more than once.
+//! - `--self-test`: Check `MakeMKV`, the drives, the output directory, the optional tools and
+//!   the Discord token, log the results and exit, with a failure if any check failed.
+//! - `--pid-file`: Write the PID of the bot to this file while it runs, for service managers
+//!   that track it by one.
+//!
+//! ## Subcommands
+//! - `export-history`: Write the rip history and drive statistics to files and exit, as
+//!   `rip_history.csv` and `drive_stats.csv`, or `rip_history.json` with
+//!   `--format json`. They are written to the current directory, or the one given with
+//!   `--to`, e.g. `cord-ripper -o /srv/media export-history --format json --to ~/exports`.
 //!
 //! ## Environment Variables
 //! - `DISCORD_TOKEN`: The token for the Discord bot. This must be set before running the application.
+//! - `DISCORD_TOKEN_FILE` and `GUILD_ID_FILE`: Files to read the token and guild id from
+//!   instead, such as Docker secrets. Failing both, the token is read from the file named by
+//!   `token_path` in the settings file. The token is hidden from the logs, and checked to
+//!   look like a token before connecting.
+//! - `ALERT_CHANNEL_ID`: Optional id of the channel to post alerts, such as low disk space, to.
+//!   Replaces the alert channel picked with `/setup`.
+//! - `OPS_CHANNEL_ID`: Optional id of the channel to mirror logged warnings and errors to.
+//! - `DIGEST_CHANNEL_ID`: Optional id of the channel to post a weekly digest to, with the
+//!   rips, their size, the top requester, the failures and the discs still wanted.
+//! - `SONARR_URL` and `SONARR_API_KEY`: Optional Sonarr instance to resolve show names and
+//!   episode numbers with, and to import show rips.
+//! - `RADARR_URL` and `RADARR_API_KEY`: Optional Radarr instance to resolve movie names with,
+//!   and to import movie rips.
+//! - `AGENT_TOKEN`: Optional token sent to agents, which they were started with.
 //!
 //! ## Logging
 //! The application uses a custom logging module to manage log levels. You can specify the log level using the `--log-level` argument.
@@ -37,29 +142,28 @@
 //! - If the `DISCORD_TOKEN` environment variable is not set, the application will log an error and exit.
 //! - If MakeMKV initialization fails, the application will log the error and exit.
 //! - If the Discord client fails to start, the application will log the error and exit.
+//! - Configuration mistakes, such as an invalid argument or a missing token, exit with 78
+//!   (`EX_CONFIG`), other failures with 1, and being stopped with `SIGTERM` or `Ctrl+C`
+//!   with 0.
 //!
-//! ## Modules
-//! - `discord`: Contains the Discord bot implementation.
-//! - `logging`: Provides logging utilities.
-//! - `makemkv`: Handles MakeMKV integration.
+//! ## Library
+//! The bot is built on the `cord_ripper` library, which holds the ripping engine and the
+//! Discord bot behind the `discord` feature. This binary only reads the arguments, sets up
+//! the engine and starts the bot.
 
 #![warn(clippy::pedantic)]
 
-pub mod discord;
-pub mod errors;
-pub mod logging;
-pub mod makemkv;
-
-pub use logging::{current_log_level, DEBUG, ERROR, INFO, TRACE, WARN};
-
 use clap::Parser;
-use tokio;
 
-use discord::bot::bot_core::DiscordHandler;
+use cord_ripper::container::{self, Exit};
+use cord_ripper::discord::bot::bot_core::{DiscordHandler, MakeMkvKey, GATEWAY_INTENTS};
+use cord_ripper::{debug, error, info, warn};
+use cord_ripper::{diagnostics, discord, i18n, logging};
 
-use serenity::prelude::{Client, GatewayIntents};
+use serenity::prelude::Client;
 
-use std::env;
+/// The port health checks are answered on in container mode, unless another is given
+const DEFAULT_HEALTH_CHECK_PORT: u16 = 8080;
 
 #[tokio::main]
 async fn main() {
@@ -75,55 +179,557 @@ async fn main() {
         }
     }
 
+    // Exports only read the history, nothing else has to be set up for them
+    if let Some(CliCommand::ExportHistory { format, to }) = &args.command {
+        export_history(&args, format, std::path::Path::new(to));
+    }
+
+    // Written before anything else can fail, so every exit removes it again
+    if let Some(pid_file) = args.pid_file.as_deref().filter(|_| !args.self_test) {
+        if let Err(e) = container::systemd::write_pid_file(std::path::Path::new(pid_file)) {
+            error!("{}", e);
+            container::exit(Exit::Config);
+        }
+    }
+    container::systemd::start_watchdog();
+
+    // The locale has to be set before the commands are registered, which translates them
+    match i18n::locale_from_str(&args.locale) {
+        Some(locale) => i18n::set_locale(locale),
+        None => {
+            error!("Invalid locale provided: {}", args.locale);
+            container::exit(Exit::Config);
+        }
+    }
+
+    // Embeds are themed from the start, so alerts and commands all match
+    let Some(color) = discord::components::embeds::parse_color(&args.embed_color) else {
+        error!("Invalid embed color provided: {}", args.embed_color);
+        container::exit(Exit::Config);
+    };
+    discord::components::embeds::set_theme(discord::components::embeds::Theme {
+        color,
+        footer: args.embed_footer.clone(),
+        footer_icon_url: args.embed_footer_icon_url.clone(),
+        thumbnail_url: args.embed_thumbnail_url.clone(),
+        timestamps: !args.no_embed_timestamps,
+    });
+
+    let Some(cleanup) =
+        discord::components::messages::SelectionCleanup::from_name(&args.selection_cleanup)
+    else {
+        error!(
+            "Invalid selection cleanup provided: {}",
+            args.selection_cleanup
+        );
+        container::exit(Exit::Config);
+    };
+    discord::components::messages::set_response_options(
+        discord::components::messages::ResponseOptions {
+            ephemeral: args.ephemeral_responses,
+            cleanup,
+            threads: args.rip_threads,
+        },
+    );
+
+    let Some(filename_profile) =
+        cord_ripper::makemkv::naming::FilenameProfile::from_name(&args.filename_profile)
+    else {
+        error!(
+            "Invalid filename profile provided: {}",
+            args.filename_profile
+        );
+        container::exit(Exit::Config);
+    };
+    cord_ripper::makemkv::naming::set_filename_profile(filename_profile);
+    cord_ripper::makemkv::transfer::set_verify_contents(args.verify_copies);
+
     info!("Starting server, please wait...");
 
-    // Locks the shared MakeMKV instance and initializes it
+    // MakeMKV may be run through a wrapper or in a sibling container, which has to be known
+    // before checking for it
+    cord_ripper::makemkv::makemkv_helpers::set_makemkv_command(&args.makemkvcon);
+    if let Some(prefix) = &args.makemkv_command_prefix {
+        cord_ripper::makemkv::makemkv_helpers::set_makemkv_prefix(prefix);
+    }
+
+    // Drives are listed from this machine if MakeMKV is installed, and from every agent
+    let mut agents = Vec::new();
+    for agent in &args.agent {
+        let Some(agent) = cord_ripper::makemkv::rippers::parse_agent(agent) else {
+            error!("Invalid agent provided, expected name=url: {}", agent);
+            container::exit(Exit::Config);
+        };
+        agents.push(agent);
+    }
+    let agent_token = container::secret("AGENT_TOKEN").unwrap_or_else(|e| {
+        error!("{}", e);
+        container::exit(Exit::Config);
+    });
+    if let Some(agent_token) = &agent_token {
+        cord_ripper::logging::add_secret(agent_token);
+    }
+    // Without agents MakeMKV has to be here, which is checked with a clearer error when
+    // MakeMKV is initialized
+    let local = agents.is_empty() || cord_ripper::makemkv::makemkv_helpers::makemkv_exists().await;
+    if !local {
+        info!("MakeMKV is not installed, only drives on agents are listed");
+    }
+    cord_ripper::makemkv::rippers::init_rippers(local, &agents, agent_token.as_deref());
+
+    // Drives that weren't passed through to the container are reported now, rather than as
+    // a rip that finds no drives. With MakeMKV in another container, only the drives asked
+    // for are checked, since the rest are passed through to that one
+    if args.container || !args.require_device.is_empty() {
+        if args.makemkv_command_prefix.is_some() && args.require_device.is_empty() {
+            debug!("MakeMKV runs through a command prefix, not checking for drives");
+        } else if let Err(e) = container::check_devices(&args.require_device) {
+            error!("{}", e);
+            container::exit(Exit::Config);
+        }
+    }
+
+    // Health checks are answered from the start, as starting until Discord connects
+    let health_check_port = args
+        .health_check_port
+        .or(args.container.then_some(DEFAULT_HEALTH_CHECK_PORT));
+    if let Some(port) = health_check_port {
+        container::serve_health(port).await.unwrap_or_else(|e| {
+            error!("{}", e);
+            container::exit(Exit::Failure);
+        });
+    }
+
+    // Loads the settings saved by /setup, which the rest of the setup builds on
+    let data_dir = args.data_dir();
+    let settings = {
+        let mut settings = cord_ripper::settings::SETTINGS.lock().await;
+        settings.init(&data_dir).unwrap_or_else(|e| {
+            error!("Error loading the settings: {:?}", e);
+            container::exit(Exit::Failure);
+        });
+        settings.clone()
+    };
+
+    // Creates the MakeMKV instance rips are run with and initializes it, it is handed to
+    // the Discord bot once set up
     // If initialization fails, it logs the error and exits
-    crate::makemkv::makemkv_core::MAKE_MKV
+    let mut make_mkv = cord_ripper::makemkv::MakeMkv::new(&args.output_dir);
+    make_mkv.init(&args.output_dir).await.unwrap_or_else(|e| {
+        error!("Error initializing MakeMKV: {}", e);
+        // The self test reports MakeMKV along with everything else
+        if !args.self_test {
+            container::exit(Exit::Failure);
+        }
+    });
+
+    // Named libraries are picked for each rip, a missing one would only fail once ripped
+    for library in &args.library {
+        let Some((name, root)) = cord_ripper::makemkv::makemkv_core::parse_library(library) else {
+            error!(
+                "Invalid library provided, expected name=path other than main: {}",
+                library
+            );
+            container::exit(Exit::Config);
+        };
+        if !root.is_dir() {
+            error!("Library directory does not exist: {}", root.display());
+            container::exit(Exit::Config);
+        }
+        make_mkv.libraries.push((name, root));
+    }
+
+    // 3D rips can only be routed to a library rips can be saved to anyway
+    if let Some(library_3d) = &args.library_3d {
+        if !make_mkv
+            .libraries
+            .iter()
+            .any(|(name, _)| name == library_3d)
+        {
+            error!(
+                "3D library {} isn't one of the libraries given with --library",
+                library_3d
+            );
+            container::exit(Exit::Config);
+        }
+        make_mkv.library_3d = Some(library_3d.clone());
+    }
+
+    // A preset's library may have been dropped from the flags since it was set up, its
+    // rips then go to the output directory
+    for preset in &settings.presets {
+        if let Some(library) = &preset.library {
+            if !make_mkv.libraries.iter().any(|(name, _)| name == library) {
+                warn!(
+                    "Preset {} saves to library {}, which isn't configured",
+                    preset.name, library
+                );
+            }
+        }
+    }
+
+    // The default for whether to eject discs after a rip, which can be changed for each rip
+    make_mkv.eject_after_rip = args.eject_after_rip;
+    make_mkv.nfo = args.nfo;
+    make_mkv.artwork = args.artwork;
+    make_mkv.tag_notes = args.tag_notes;
+    let Some(forced_subtitles) =
+        cord_ripper::makemkv::subtitles::ForcedSubtitles::from_name(&args.forced_subtitles)
+    else {
+        error!(
+            "Invalid forced subtitles mode provided: {}",
+            args.forced_subtitles
+        );
+        container::exit(Exit::Config);
+    };
+    make_mkv.forced_subtitles = forced_subtitles;
+
+    // Custom episode patterns replace the defaults, an invalid one is a configuration
+    // mistake worth stopping for
+    if !args.episode_pattern.is_empty() {
+        make_mkv.episode_patterns = args
+            .episode_pattern
+            .iter()
+            .map(|pattern| cord_ripper::makemkv::episodes::EpisodePattern::parse(pattern))
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|e| {
+                error!("Error parsing episode patterns: {:?}", e);
+                container::exit(Exit::Config);
+            });
+    }
+
+    // Imported rips are renamed by Sonarr and Radarr, so they only need to be named
+    // well enough for them to recognize
+    if args.arr_import {
+        make_mkv.naming = cord_ripper::makemkv::naming::NamingTemplate::arr_import();
+    } else if let Ok(Some(naming)) = settings.naming() {
+        make_mkv.naming = naming;
+    }
+
+    // Shared from here on, rips on different drives run side by side
+    let make_mkv = std::sync::Arc::new(make_mkv);
+
+    cord_ripper::arr::ARR.lock().await.init(args.arr_import);
+
+    // Artwork is only found through Sonarr and Radarr
+    if args.artwork && std::env::var("SONARR_URL").is_err() && std::env::var("RADARR_URL").is_err()
+    {
+        warn!("Downloading artwork is enabled, but neither Sonarr nor Radarr is configured");
+    }
+
+    // Sonarr and Radarr move imported files out of the output directory, so there is
+    // nothing left to upload once they have
+    if args.arr_import && (args.upload_movies_to.is_some() || args.upload_shows_to.is_some()) {
+        warn!("Rips imported by Sonarr or Radarr may be moved before they can be uploaded");
+    }
+    cord_ripper::upload::UPLOADER
         .lock()
         .await
-        .init(&args.output_dir)
+        .init(args.upload_movies_to.clone(), args.upload_shows_to.clone());
+
+    // Files ripped by hand are imported once the bot is connected, a missing folder would
+    // only be noticed when nothing ever shows up
+    if let Some(watch_dir) = &args.watch_dir {
+        let watch_dir = std::path::PathBuf::from(watch_dir);
+        if !watch_dir.is_dir() {
+            error!("Watch folder does not exist: {}", watch_dir.display());
+            container::exit(Exit::Config);
+        }
+        cord_ripper::watch::WATCH_FOLDER
+            .lock()
+            .await
+            .init(watch_dir);
+    }
+
+    // Indexes the output directory and named libraries so lookups don't rescan them every
+    // time. If the output directory can't be read, it logs the error and exits
+    let library = cord_ripper::library::LibraryIndex::scan(&make_mkv.library_roots())
+        .unwrap_or_else(|e| {
+            error!("Error indexing the output directory: {:?}", e);
+            container::exit(Exit::Failure);
+        });
+    *cord_ripper::library::LIBRARY.lock().await = library;
+
+    // Loads the rip history from the data directory
+    // If the history can't be loaded, it logs the error and exits rather than
+    // risk overwriting it
+    cord_ripper::history::HISTORY
+        .lock()
         .await
+        .init(&data_dir, args.trash_retention_days)
         .unwrap_or_else(|e| {
-            error!("Error initializing MakeMKV: {:?}", e);
-            std::process::exit(1);
+            error!("Error initializing history: {:?}", e);
+            container::exit(Exit::Failure);
         });
 
-    // Retrieves the GUILD_ID from the environment variable
-    // If the variable is not set or invalid, it logs the error and exits
-    let discord_token = match env::var("DISCORD_TOKEN") {
-        Ok(token) => token,
-        Err(_) => {
-            error!("DISCORD_TOKEN environment variable not set, use the command 'export DISCORD_TOKEN=your_token_here'");
-            std::process::exit(1);
+    // Keeps what MakeMKV printed for each rip job next to the history
+    cord_ripper::makemkv::logs::JOB_LOGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .init(&data_dir, args.rip_log_retention_days);
+
+    // On the first run, files already in the output directory are added to the history, so
+    // rips made before cord-ripper are known too
+    {
+        let mut history = cord_ripper::history::HISTORY.lock().await;
+        if !history.library_imported() {
+            let library = cord_ripper::library::LIBRARY.lock().await;
+            if let Err(e) = history.import_library(&library) {
+                warn!("Failed to import the library into history: {}", e);
+            }
+        }
+    }
+
+    // Loads the numbers given to drives, so they keep them across reboots and re-plugs
+    cord_ripper::makemkv::drive_map::DRIVE_MAP
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .init(&data_dir)
+        .unwrap_or_else(|e| {
+            error!("Error loading the drive map: {:?}", e);
+            container::exit(Exit::Failure);
+        });
+
+    // Records who rips, cancels and deletes what alongside the history
+    cord_ripper::audit::AUDIT_LOG.lock().await.init(&data_dir);
+
+    // Remembers the messages rips show their progress on, so they can be picked up again
+    cord_ripper::queue::messages::JOB_MESSAGES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .init(&data_dir);
+
+    // Remembers the titles show rips have left, so a batch that stops partway can resume
+    cord_ripper::queue::batches::batches().init(&data_dir);
+
+    // Loads when each user started their recent rips, so a restart doesn't reset quotas
+    cord_ripper::quota::QUOTAS
+        .lock()
+        .await
+        .init(
+            &data_dir,
+            cord_ripper::quota::QuotaLimits {
+                daily: args.daily_rip_quota,
+                weekly: args.weekly_rip_quota,
+                cooldown_seconds: args.rip_cooldown_minutes * 60,
+                exempt_roles: args
+                    .quota_exempt_role
+                    .iter()
+                    .chain(&settings.quota_exempt_roles)
+                    .copied()
+                    .collect(),
+            },
+        )
+        .unwrap_or_else(|e| {
+            error!("Error loading the rip quotas: {:?}", e);
+            container::exit(Exit::Failure);
+        });
+
+    // Loads when the last weekly digest was posted, so a restart doesn't post another
+    cord_ripper::digest::DIGEST_SCHEDULE
+        .lock()
+        .await
+        .init(&data_dir);
+
+    // Loads the discs the household still wants ripped
+    cord_ripper::collection::COLLECTION
+        .lock()
+        .await
+        .init(&data_dir)
+        .unwrap_or_else(|e| {
+            error!("Error loading the collection: {:?}", e);
+            container::exit(Exit::Failure);
+        });
+
+    // Watches the output directory, and the data directory if it is stored elsewhere,
+    // since deleted rips are moved to the trash within it
+    let mut watched_dirs = vec![std::path::PathBuf::from(&args.output_dir)];
+    if !data_dir.starts_with(&args.output_dir) {
+        watched_dirs.push(data_dir.clone());
+    }
+    cord_ripper::disk::DISK_MONITOR.lock().await.init(
+        watched_dirs,
+        args.warn_free_space_gb,
+        args.min_free_space_gb,
+    );
+
+    cord_ripper::queue::QUEUE.lock().await.init(
+        args.max_concurrent_rips,
+        args.max_concurrent_transcodes,
+        args.max_concurrent_transfers,
+    );
+
+    if args.self_test {
+        let passed = diagnostics::self_test(std::path::Path::new(&args.output_dir)).await;
+        container::exit(if passed { Exit::Stopped } else { Exit::Failure });
+    }
+
+    // Retrieves the DISCORD_TOKEN from the environment variable, the file named by
+    // DISCORD_TOKEN_FILE such as a Docker secret, or the token_path of the settings
+    // If none is set, the file can't be read or the token looks wrong, it logs the error and exits
+    let discord_token = match container::discord_token(settings.token_path.as_deref()) {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            error!("DISCORD_TOKEN environment variable not set, use the command 'export DISCORD_TOKEN=your_token_here', or set token_path in the settings file");
+            container::exit(Exit::Config);
+        }
+        Err(e) => {
+            error!("{}", e);
+            container::exit(Exit::Config);
         }
     };
 
-    debug!("Successfully retrieved Discord token from environment variable");
+    debug!("Successfully retrieved Discord token");
 
     // Creates a new Discord client with the provided token
     // If the client creation fails, it logs the error and exits
-    let mut client = Client::builder(discord_token, GatewayIntents::empty())
+    let mut client = Client::builder(discord_token, GATEWAY_INTENTS)
         .event_handler(DiscordHandler)
+        .type_map_insert::<MakeMkvKey>(make_mkv)
         .await
         .unwrap_or_else(|e| {
             error!("Error creating client: {:?}", e);
-            std::process::exit(1);
+            container::exit(Exit::Failure);
         });
 
-    client.start().await.unwrap_or_else(|e| {
-        error!("Error starting client: {:?}", e);
-        std::process::exit(1);
+    // Stops the bot cleanly when systemd, or Ctrl+C, asks it to
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        container::systemd::wait_for_stop().await;
+        info!("Stopping the Discord bot...");
+        container::systemd::notify("STOPPING=1");
+        shard_manager.shutdown_all().await;
     });
+
+    if let Err(e) = client.start().await {
+        error!("Error starting client: {:?}", e);
+        // A token Discord refuses won't work any better after a restart
+        let invalid_token = matches!(
+            e,
+            serenity::Error::Gateway(serenity::gateway::GatewayError::InvalidAuthentication)
+        );
+        container::exit(if invalid_token {
+            Exit::Config
+        } else {
+            Exit::Failure
+        });
+    }
+
+    // Returning rather than exiting drops the runtime, which stops any running rips
+    container::systemd::stopped();
+    info!("The Discord bot has stopped");
+}
+
+/// Writes the rip history and drive statistics to `to` as `format`, then exits. Exits with
+/// a failure if the history can't be read or the files can't be written.
+fn export_history(args: &CliArgs, format: &str, to: &std::path::Path) -> ! {
+    let Some(format) = cord_ripper::history::export::ExportFormat::from_name(format) else {
+        error!("Invalid export format provided: {}", format);
+        container::exit(Exit::Config);
+    };
+
+    // Nothing else holds the history yet, so it is read without waiting on the lock
+    let mut history = cord_ripper::history::history_core::History::default();
+    if let Err(e) = history.init(&args.data_dir(), args.trash_retention_days) {
+        error!("Error loading the history: {:?}", e);
+        container::exit(Exit::Failure);
+    }
+
+    let files =
+        cord_ripper::history::export::export(history.entries(), history.drive_stats(), format)
+            .unwrap_or_else(|e| {
+                error!("Error exporting the history: {:?}", e);
+                container::exit(Exit::Failure);
+            });
+
+    for file in files {
+        let path = to.join(&file.name);
+        if let Err(e) = std::fs::write(&path, file.contents) {
+            error!("Error writing {}: {}", path.display(), e);
+            container::exit(Exit::Failure);
+        }
+        info!("Exported {}", path.display());
+    }
+
+    container::exit(Exit::Stopped);
+}
+
+/// Things the application can do instead of running the bot
+#[derive(clap::Subcommand, Debug)]
+enum CliCommand {
+    /// Write the rip history and drive statistics to files, then exit
+    ExportHistory {
+        /// The format to export as
+        #[clap(
+            long,
+            default_value = "csv",
+            help = "Format to export as, 'csv' or 'json' [csv by default]"
+        )]
+        format: String,
+        /// The directory to write the export to
+        #[clap(
+            long,
+            default_value = ".",
+            help = "Directory to write the export to [the current directory by default]"
+        )]
+        to: String,
+    },
 }
 
 /// Command line arguments for the application
 /// - `log_level`: Optional level of logging
 /// - `output_dir`: Path to the desired output directory
+/// - `library`: Other directories rips can be saved to, as `name=path`
+/// - `library_3d`: The library 3D titles kept in 3D are saved to
+/// - `data_dir`: Optional path to store the rip history in
+/// - `trash_retention_days`: Number of days to keep deleted rips in the trash
+/// - `rip_log_retention_days`: Number of days to keep the log of each rip job
+/// - `warn_free_space_gb`: Free space below which a low disk space alert is posted
+/// - `min_free_space_gb`: Free space below which new rips are blocked
+/// - `max_concurrent_rips`: Number of rips that can run at the same time on each machine
+/// - `max_concurrent_transcodes`: Number of transcodes that can run at the same time
+/// - `max_concurrent_transfers`: Number of rips that can copy to another mount at the same
+///   time
+/// - `daily_rip_quota`: Optional number of rips each user can start in a day
+/// - `weekly_rip_quota`: Optional number of rips each user can start in a week
+/// - `rip_cooldown_minutes`: Minutes each user has to wait between starting rips
+/// - `quota_exempt_role`: Ids of the roles whose members aren't held to the quotas
+/// - `eject_after_rip`: Whether to eject the disc once a rip finishes by default
+/// - `episode_pattern`: Filename patterns to recognize ripped episodes by
+/// - `arr_import`: Whether to hand finished rips to Sonarr or Radarr to import
+/// - `nfo`: Whether to write Kodi `.nfo` files next to finished rips
+/// - `artwork`: Whether to download the artwork of finished rips into their folders
+/// - `tag_notes`: Whether to write the notes of rips into the title tag of their files
+/// - `forced_subtitles`: What to do with the forced subtitles of ripped titles
+/// - `filename_profile`: The systems the names of ripped files have to work on
+/// - `verify_copies`: Whether rips copied onto another mount are compared byte for byte
+/// - `upload_movies_to`: Optional `rclone` remote to copy finished movie rips to
+/// - `upload_shows_to`: Optional `rclone` remote to copy finished show rips to
+/// - `watch_dir`: Optional folder to import MKVs ripped by hand from
+/// - `locale`: The language the Discord bot responds in
+/// - `embed_color`: The accent color of embeds, as hex
+/// - `embed_footer`: Optional footer text shown on every embed
+/// - `embed_footer_icon_url`: Optional icon shown next to the footer text
+/// - `embed_thumbnail_url`: Optional image shown in the corner of every embed
+/// - `no_embed_timestamps`: Whether to leave the time sent off of embeds
+/// - `ephemeral_responses`: Whether command responses are only shown to whoever ran them
+/// - `selection_cleanup`: What happens to the message a rip was set up on once it starts
+/// - `rip_threads`: Whether each rip posts its progress and summary to a thread of its own
+/// - `container`: Whether to check the drives were passed through and answer health checks
+/// - `health_check_port`: Optional port to answer health checks on
+/// - `require_device`: Devices that have to be there at startup
+/// - `makemkvcon`: How to run `makemkvcon`
+/// - `makemkv_command_prefix`: Optional command `makemkvcon` is run through
+/// - `agent`: Agents to rip from, as `name=url`
+/// - `self_test`: Whether to run the checks, log them and exit
+/// - `pid_file`: Optional file to write the PID of the bot to
+/// - `command`: Optional thing to do instead of running the bot, such as exporting the
+///   history
 ///
 /// This struct is used to parse command line arguments using the `clap` library.
 /// The `log_level` argument is optional and can be specified using the `-l` or `--log-level` flags.
 /// The `output_dir` argument is required and can be specified using the `-o` or `--output-dir` flags.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(clap::Parser, Debug)]
 struct CliArgs {
     /// Optional level of logging
@@ -132,4 +738,284 @@ struct CliArgs {
     /// Path to the desired output directory
     #[clap(short, long, help = "Path to the desired output directory")]
     output_dir: String,
+    /// Other directories rips can be saved to, as `name=path`
+    #[clap(
+        long,
+        help = "Another directory rips can be saved to as name=path, such as kids=/mnt/kids. Can be given more than once, the output directory is 'main' [none by default]"
+    )]
+    library: Vec<String>,
+    /// The library 3D titles kept in 3D are saved to
+    #[clap(
+        long,
+        help = "Library to save 3D titles kept in 3D to, one of those given with --library [none by default]"
+    )]
+    library_3d: Option<String>,
+    /// Optional path to store the rip history in
+    #[clap(
+        short,
+        long,
+        help = "Path to store the rip history in [<output-dir>/.cord-ripper by default]"
+    )]
+    data_dir: Option<String>,
+    /// Number of days to keep deleted rips in the trash
+    #[clap(
+        long,
+        default_value_t = 7,
+        help = "Days to keep deleted rips in the trash, 0 deletes immediately [7 by default]"
+    )]
+    trash_retention_days: u64,
+    /// Number of days to keep the log of each rip job
+    #[clap(
+        long,
+        default_value_t = 30,
+        help = "Days to keep the MakeMKV log of each rip job, 0 keeps none [30 by default]"
+    )]
+    rip_log_retention_days: u64,
+    /// Free space, in gigabytes, below which a low disk space alert is posted
+    #[clap(
+        long,
+        default_value_t = 50,
+        help = "Free space in GB below which a low disk space alert is posted [50 by default]"
+    )]
+    warn_free_space_gb: u64,
+    /// Free space, in gigabytes, below which new rips are blocked
+    #[clap(
+        long,
+        default_value_t = 10,
+        help = "Free space in GB below which new rips are blocked [10 by default]"
+    )]
+    min_free_space_gb: u64,
+    /// Number of rips that can run at the same time on each machine
+    #[clap(
+        long,
+        default_value_t = 1,
+        help = "Number of rips that can run at the same time on each machine, this one and every agent [1 by default]"
+    )]
+    max_concurrent_rips: usize,
+    /// Number of transcodes that can run at the same time
+    #[clap(
+        long,
+        default_value_t = 1,
+        help = "Number of finished rips that can have their notes and forced subtitles written at the same time [1 by default]"
+    )]
+    max_concurrent_transcodes: usize,
+    /// Number of rips that can copy to another mount at the same time
+    #[clap(
+        long,
+        default_value_t = 1,
+        help = "Number of rips that can copy to a library on another mount at the same time, without holding their drives [1 by default]"
+    )]
+    max_concurrent_transfers: usize,
+    /// Optional number of rips each user can start in a day
+    #[clap(
+        long,
+        help = "Most rips each user can start in a rolling day [unlimited by default]"
+    )]
+    daily_rip_quota: Option<u32>,
+    /// Optional number of rips each user can start in a week
+    #[clap(
+        long,
+        help = "Most rips each user can start in a rolling week [unlimited by default]"
+    )]
+    weekly_rip_quota: Option<u32>,
+    /// Minutes each user has to wait between starting rips
+    #[clap(
+        long,
+        default_value_t = 0,
+        help = "Minutes each user has to wait after starting a rip before starting another [0 by default]"
+    )]
+    rip_cooldown_minutes: u64,
+    /// Ids of the roles whose members aren't held to the quotas
+    #[clap(
+        long,
+        help = "Id of a Discord role whose members aren't held to the rip quotas or cooldown, can be repeated [administrators only by default]"
+    )]
+    quota_exempt_role: Vec<u64>,
+    /// Whether to eject the disc once a rip finishes by default
+    #[clap(
+        long,
+        help = "Eject the disc once a rip finishes, can be toggled for each rip [off by default]"
+    )]
+    eject_after_rip: bool,
+    /// Filename patterns to recognize ripped episodes by
+    #[clap(
+        long,
+        help = "Filename pattern to recognize ripped episodes by, e.g. 'S{season}E{episode}', can be repeated [Episode {episode}, S{season}E{episode} and {season}x{episode} by default]"
+    )]
+    episode_pattern: Vec<String>,
+    /// Whether to hand finished rips to Sonarr or Radarr to import
+    #[clap(
+        long,
+        help = "Hand finished rips to Sonarr or Radarr to import, see SONARR_URL and RADARR_URL [off by default]"
+    )]
+    arr_import: bool,
+    /// Whether to write Kodi `.nfo` files next to finished rips
+    #[clap(
+        long,
+        help = "Write Kodi .nfo files next to finished rips, filled in from Sonarr and Radarr if configured [off by default]"
+    )]
+    nfo: bool,
+    /// Whether to download the artwork of finished rips into their folders
+    #[clap(
+        long,
+        help = "Download poster.jpg and fanart.jpg of finished rips into their folders, needs SONARR_URL or RADARR_URL [off by default]"
+    )]
+    artwork: bool,
+    /// Whether to write the notes of rips into the title tag of their files
+    #[clap(
+        long,
+        help = "Write the note given to a rip into the title tag of its files, needs mkvpropedit [off by default]"
+    )]
+    tag_notes: bool,
+    /// What to do with the forced subtitles of ripped titles
+    #[clap(
+        long,
+        default_value = "off",
+        help = "Forced subtitles of ripped titles, 'flag' to mark them so players show them, 'extract' to save them next to the file or 'off', needs MKVToolNix [off by default]"
+    )]
+    forced_subtitles: String,
+    /// The systems the names of ripped files have to work on
+    #[clap(
+        long,
+        default_value = "windows",
+        help = "Systems the names of ripped files have to work on, 'posix', 'windows' or 'strict' [windows by default]"
+    )]
+    filename_profile: String,
+    /// Whether rips copied onto another mount are compared byte for byte
+    #[clap(
+        long,
+        help = "Compare rips copied onto another mount, such as NFS or SMB, byte for byte rather than only by size [off by default]"
+    )]
+    verify_copies: bool,
+    /// Optional `rclone` remote to copy finished movie rips to
+    #[clap(
+        long,
+        help = "rclone remote to copy finished movie rips to, e.g. 'media-box:/srv/media' [off by default]"
+    )]
+    upload_movies_to: Option<String>,
+    /// Optional `rclone` remote to copy finished show rips to
+    #[clap(
+        long,
+        help = "rclone remote to copy finished show rips to, e.g. 'media-box:/srv/media' [off by default]"
+    )]
+    upload_shows_to: Option<String>,
+    /// Optional folder to import MKVs ripped by hand from
+    #[clap(
+        long,
+        help = "Folder to import MKVs ripped by hand from, such as with the MakeMKV GUI, into the output directory [off by default]"
+    )]
+    watch_dir: Option<String>,
+    /// The language the Discord bot responds in
+    #[clap(
+        long,
+        default_value = "en",
+        help = "Language the Discord bot responds in, 'en' or 'es' [en by default]"
+    )]
+    locale: String,
+    /// The accent color of embeds, as hex
+    #[clap(
+        long,
+        default_value = "#fe0000",
+        help = "Accent color of embeds as hex, e.g. '#1e90ff' [#fe0000 by default]"
+    )]
+    embed_color: String,
+    /// Optional footer text shown on every embed
+    #[clap(long, help = "Footer text shown on every embed [none by default]")]
+    embed_footer: Option<String>,
+    /// Optional icon shown next to the footer text
+    #[clap(
+        long,
+        help = "URL of an icon shown next to the embed footer, needs --embed-footer [none by default]"
+    )]
+    embed_footer_icon_url: Option<String>,
+    /// Optional image shown in the corner of every embed
+    #[clap(
+        long,
+        help = "URL of an image shown in the corner of every embed [none by default]"
+    )]
+    embed_thumbnail_url: Option<String>,
+    /// Whether to leave the time sent off of embeds
+    #[clap(long, help = "Leave the time sent off of embeds [shown by default]")]
+    no_embed_timestamps: bool,
+    /// Whether command responses are only shown to whoever ran them
+    #[clap(
+        long,
+        help = "Only show command responses to whoever ran the command [off by default]"
+    )]
+    ephemeral_responses: bool,
+    /// What happens to the message a rip was set up on once it starts
+    #[clap(
+        long,
+        default_value = "keep",
+        help = "What happens to the message a rip was set up on once it starts, 'keep', 'collapse' or 'delete' [keep by default]"
+    )]
+    selection_cleanup: String,
+    /// Whether each rip posts its progress and summary to a thread of its own
+    #[clap(
+        long,
+        help = "Post the progress and summary of each rip to a thread of its own [off by default]"
+    )]
+    rip_threads: bool,
+    /// Whether to check the drives were passed through and answer health checks
+    #[clap(
+        long,
+        help = "Run in container mode, checking the drives were passed through and answering health checks [off by default]"
+    )]
+    container: bool,
+    /// Optional port to answer health checks on
+    #[clap(
+        long,
+        help = "Port to answer health checks on at /health [8080 in container mode, off otherwise]"
+    )]
+    health_check_port: Option<u16>,
+    /// Devices that have to be there at startup
+    #[clap(
+        long,
+        help = "A device that has to be there at startup, such as /dev/sr0. Can be given more than once [every /dev/sr device in container mode]"
+    )]
+    require_device: Vec<String>,
+    /// How to run `makemkvcon`
+    #[clap(
+        long,
+        default_value = "makemkvcon",
+        help = "How to run makemkvcon, its path or a wrapper such as 'flatpak run --command=makemkvcon com.makemkv.MakeMKV' [makemkvcon by default]"
+    )]
+    makemkvcon: String,
+    /// Optional command `makemkvcon` is run through
+    #[clap(
+        long,
+        help = "Command to run makemkvcon through, such as 'docker exec makemkv' [none by default]"
+    )]
+    makemkv_command_prefix: Option<String>,
+    /// Agents to rip from, as `name=url`
+    #[clap(
+        long,
+        help = "A cord-ripper-agent to rip from as name=url, such as garage=http://garage-pc:9000. Can be given more than once [none by default]"
+    )]
+    agent: Vec<String>,
+    /// Whether to run the checks, log them and exit
+    #[clap(
+        long,
+        help = "Check MakeMKV, the drives, the output directory, the optional tools and the Discord token, then exit [off by default]"
+    )]
+    self_test: bool,
+    /// Optional file to write the PID of the bot to
+    #[clap(
+        long,
+        help = "Write the PID of the bot to this file while it runs [none by default]"
+    )]
+    pid_file: Option<String>,
+    /// Optional thing to do instead of running the bot
+    #[clap(subcommand)]
+    command: Option<CliCommand>,
+}
+
+impl CliArgs {
+    /// The directory the history and the rest of the bot's data is kept in
+    fn data_dir(&self) -> std::path::PathBuf {
+        self.data_dir.as_ref().map_or_else(
+            || std::path::Path::new(&self.output_dir).join(".cord-ripper"),
+            std::path::PathBuf::from,
+        )
+    }
 }
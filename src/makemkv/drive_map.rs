@@ -0,0 +1,497 @@
+//! # Drive Map
+//!
+//! Drive numbers used to follow the order of the `/dev/sr` devices, which the kernel hands
+//! out in the order it finds drives, so a reboot or a re-plugged USB drive could swap two
+//! drives around between listing them and ripping. Drives are instead identified by their
+//! model and serial number, and each is given a number the first time it is seen, which it
+//! keeps from then on.
+//!
+//! The map is also what keeps device addressing out of the rest of the bot. Drives are
+//! listed by `MakeMKV` on every platform, so a drive number is translated to whatever
+//! device `MakeMKV` listed it at, `/dev/sr0` on Linux, `D:` on Windows or `/dev/rdisk2` on
+//! macOS.
+//!
+//! ## Overview
+//!
+//! - **`DriveMap`**: The number given to each drive, and an optional alias for it, stored
+//!   as JSON in the data directory.
+//!
+//! - **`DRIVE_MAP`**: A globally accessible, thread-safe instance of `DriveMap`.
+//!
+//! - **`device_path`**: Translates a drive number to the device the drive is at right now,
+//!   used by everything that reads from a drive.
+//!
+//! - **`makemkv_source`**: Translates a drive number to what `makemkvcon` is told to read,
+//!   `dev:` with the device on Linux and `disc:` with `MakeMKV`'s own index elsewhere.
+//!
+//! ## Notes
+//!
+//! - Identities are read from the `/dev/disk/by-id` links udev creates, then from sysfs,
+//!   and failing both from the name `MakeMKV` lists the drive with, which includes the serial
+//!   number for most drives. Windows and macOS always use the name.
+//! - Only Linux has a device numbering to fall back on, so elsewhere drives have to be
+//!   listed, e.g. by `/rip` or `/view_drives`, before they can be used.
+//! - New drives are given the number their device would have had when it is free, so the
+//!   numbers don't change for setups whose drives never moved.
+//! - Aliases are set by editing `drives.json` while the bot is stopped, as are the
+//!   capabilities of a drive, such as `uhd` for drives that can read UHD Blu-rays. Drives
+//!   with no capabilities listed aren't checked, since it isn't known what they can read.
+//! - Drives on agents are told apart by the agent too, two machines can both have a
+//!   `/dev/sr0`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::errors::{MakeMkvError, Result};
+use crate::history::history_core::write_json_atomic;
+use crate::{debug, info, trace, warn};
+
+/// The name of the drive map file within the data directory
+const DRIVE_MAP_FILE: &str = "drives.json";
+
+/// A globally accessible instance of `DriveMap` for translating drive numbers to devices.
+pub static DRIVE_MAP: LazyLock<Mutex<DriveMap>> = LazyLock::new(|| Mutex::new(DriveMap::default()));
+
+/// A drive that has been given a number.
+///
+/// # Fields
+///
+/// * `identity` - The model and serial number of the drive.
+/// * `alias` - A friendly name for the drive, shown alongside its number.
+/// * `capabilities` - What the drive can read beyond DVDs and Blu-rays, such as `uhd`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedDrive {
+    pub identity: String,
+    #[serde(default)]
+    pub alias: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// Where `MakeMKV` listed a drive.
+///
+/// # Fields
+///
+/// * `index` - The index `MakeMKV` gave the drive, which `disc:` addresses it by.
+/// * `path` - The device of the drive, e.g. `/dev/sr0` on Linux, `D:` on Windows, or
+///   `/dev/rdisk2` on macOS.
+/// * `host` - The agent the drive is on, `None` for this machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedDevice {
+    pub index: u8,
+    pub path: String,
+    pub host: Option<String>,
+}
+
+impl ListedDevice {
+    /// The `/dev/sr` index of the device, for drives on this machine on Linux
+    fn sr_index(&self) -> Option<u8> {
+        if self.host.is_some() {
+            return None;
+        }
+        self.path.strip_prefix("/dev/sr")?.parse().ok()
+    }
+}
+
+/// The numbers given to drives, and the devices they were last seen at.
+///
+/// # Fields
+///
+/// * `drives` - Every drive that has been given a number, by number.
+/// * `devices` - Where each drive was when the drives were last listed.
+/// * `path` - Where the map is saved, empty until `init` has been called.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DriveMap {
+    drives: BTreeMap<u8, MappedDrive>,
+    #[serde(skip)]
+    devices: HashMap<u8, ListedDevice>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl DriveMap {
+    /// Loads the drive map from the data directory, which has already been created by the
+    /// history.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `MakeMkvError` if the map exists but can't be read or parsed.
+    pub fn init(&mut self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join(DRIVE_MAP_FILE);
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            *self = serde_json::from_str(&contents)
+                .map_err(|e| MakeMkvError::ParseError(e.to_string()))?;
+            debug!("Loaded {} drives from the drive map", self.drives.len());
+        } else {
+            debug!("No drive map found, drives are numbered as they are found");
+        }
+
+        self.path = path;
+        Ok(())
+    }
+
+    /// Writes the drive map to disk, a failure is only logged since the numbers are still
+    /// kept for as long as the bot runs.
+    fn save(&self) {
+        if self.path.as_os_str().is_empty() {
+            trace!("Drive map has not been initialized, not saving");
+            return;
+        }
+
+        match write_json_atomic(&self.path, self) {
+            Ok(()) => trace!("Saved drive map to {}", self.path.display()),
+            Err(e) => warn!("Failed to save the drive map: {}", e),
+        }
+    }
+
+    /// Returns the number of the drive with an identity, listed by `MakeMKV` at `device`,
+    /// giving it a number if it hasn't been seen before.
+    pub fn assign(&mut self, identity: &str, device: ListedDevice) -> u8 {
+        let known = self
+            .drives
+            .iter()
+            .find(|(_, drive)| drive.identity == identity)
+            .map(|(number, _)| *number);
+
+        let number = if let Some(number) = known {
+            number
+        } else {
+            // The number the device would have had is kept when it is free
+            let preferred = device.sr_index().unwrap_or(device.index).saturating_add(1);
+            let Some(number) = std::iter::once(preferred)
+                .chain(1..=u8::MAX)
+                .find(|number| !self.drives.contains_key(number))
+            else {
+                warn!("Every drive number is taken, using {}", preferred);
+                return preferred;
+            };
+
+            info!("Found new drive {}, numbered {}", identity, number);
+            self.drives.insert(
+                number,
+                MappedDrive {
+                    identity: identity.to_string(),
+                    alias: None,
+                    capabilities: Vec::new(),
+                },
+            );
+            self.save();
+            number
+        };
+
+        // Another drive may have been at this device before a re-plug
+        self.devices
+            .retain(|_, seen| seen.path != device.path || seen.host != device.host);
+        self.devices.insert(number, device);
+        number
+    }
+
+    /// The alias of a drive, if one was set
+    #[must_use]
+    pub fn alias(&self, drive_number: u8) -> Option<String> {
+        self.drives
+            .get(&drive_number)
+            .and_then(|drive| drive.alias.clone())
+    }
+
+    /// The capabilities listed for a drive, in lowercase
+    #[must_use]
+    pub fn capabilities(&self, drive_number: u8) -> Vec<String> {
+        self.drives
+            .get(&drive_number)
+            .map(|drive| {
+                drive
+                    .capabilities
+                    .iter()
+                    .map(|capability| capability.to_lowercase())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The drives listed with a capability, seen since the bot started
+    #[must_use]
+    pub fn drives_with(&self, capability: &str) -> Vec<u8> {
+        self.drives
+            .keys()
+            .copied()
+            .filter(|number| self.devices.contains_key(number))
+            .filter(|number| self.capabilities(*number).iter().any(|c| c == capability))
+            .collect()
+    }
+
+    /// The path of the device a drive is at right now. Drives identified by udev or sysfs
+    /// are looked for again, in case they moved since the drives were listed, otherwise the
+    /// device they were last listed at is used. Returns `None` for drives that haven't been
+    /// listed since the bot started.
+    fn device(&self, drive_number: u8) -> Option<String> {
+        let drive = self.drives.get(&drive_number)?;
+
+        // Drives on an agent are named with it, so tools run here don't read a drive of
+        // this machine that happens to have the same device
+        if let Some(ListedDevice {
+            path,
+            host: Some(host),
+            ..
+        }) = self.devices.get(&drive_number)
+        {
+            return Some(format!("{host}:{path}"));
+        }
+
+        let found = current_devices()
+            .into_iter()
+            .find(|device| device_identity(*device).as_deref() == Some(drive.identity.as_str()));
+        if let Some(device) = found {
+            return Some(format!("/dev/sr{device}"));
+        }
+
+        let listed = self.devices.get(&drive_number)?;
+        if listed.sr_index().and_then(device_identity).is_none() {
+            return Some(listed.path.clone());
+        }
+
+        // Another drive has taken over the device, the path given doesn't exist so anything
+        // reading from this drive fails as disconnected rather than reading the other one
+        warn!("Drive {} is no longer at {}", drive_number, listed.path);
+        Some(format!("/dev/disk/by-id/{}", drive.identity))
+    }
+
+    /// How `makemkvcon` should address a drive. On Linux this is the device, which is
+    /// looked for again in case it moved, elsewhere the index `MakeMKV` listed it at.
+    fn makemkv_source(&self, drive_number: u8) -> String {
+        // Agents look their drives up again themselves, there is nothing to check here
+        if let Some(listed @ ListedDevice { host: Some(_), .. }) = self.devices.get(&drive_number) {
+            return if listed.path.starts_with("/dev/sr") {
+                format!("dev:{}", listed.path)
+            } else {
+                format!("disc:{}", listed.index)
+            };
+        }
+
+        if cfg!(target_os = "linux") {
+            let path = self
+                .device(drive_number)
+                .unwrap_or_else(|| fallback_device(drive_number));
+            return format!("dev:{path}");
+        }
+
+        let index = self
+            .devices
+            .get(&drive_number)
+            .map_or(drive_number.saturating_sub(1), |listed| listed.index);
+        format!("disc:{index}")
+    }
+}
+
+/// The device of a drive that hasn't been listed yet, by the old numbering of one more than
+/// the `/dev/sr` index on Linux. Other platforms have no numbering to guess from, so the
+/// drives must be listed first.
+fn fallback_device(drive_number: u8) -> String {
+    if cfg!(target_os = "linux") {
+        format!("/dev/sr{}", drive_number.saturating_sub(1))
+    } else {
+        warn!("Drive {} hasn't been listed yet", drive_number);
+        String::new()
+    }
+}
+
+/// The path of the device a drive is at right now, e.g. `/dev/sr0`, `D:` or `/dev/rdisk2`.
+///
+/// Drives that haven't been listed yet fall back on the old Linux numbering, of one more
+/// than their `/dev/sr` index.
+#[must_use]
+pub fn device_path(drive_number: u8) -> String {
+    // A poisoned lock only means another thread panicked mid-assign, the map is still fine
+    DRIVE_MAP
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .device(drive_number)
+        .unwrap_or_else(|| fallback_device(drive_number))
+}
+
+/// The agent a drive is on, and its device there. Drives on this machine have no agent, and
+/// the device they are at right now.
+#[must_use]
+pub fn drive_location(drive_number: u8) -> (Option<String>, String) {
+    let drive_map = DRIVE_MAP
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    match drive_map.devices.get(&drive_number) {
+        Some(ListedDevice {
+            path,
+            host: Some(host),
+            ..
+        }) => (Some(host.clone()), path.clone()),
+        _ => (
+            None,
+            drive_map
+                .device(drive_number)
+                .unwrap_or_else(|| fallback_device(drive_number)),
+        ),
+    }
+}
+
+/// The source `makemkvcon` reads a drive from, e.g. `dev:/dev/sr0` or `disc:1`
+#[must_use]
+pub fn makemkv_source(drive_number: u8) -> String {
+    DRIVE_MAP
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .makemkv_source(drive_number)
+}
+
+/// The indexes of the `/dev/sr` devices sysfs knows about
+fn current_devices() -> Vec<u8> {
+    std::fs::read_dir("/sys/block")
+        .map(|entries| {
+            entries
+                .filter_map(std::result::Result::ok)
+                .filter_map(|entry| entry.file_name().to_str()?.strip_prefix("sr")?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the model and serial number of the drive at a `/dev/sr` index, from the links udev
+/// creates in `/dev/disk/by-id` or otherwise from sysfs.
+///
+/// Returns `None` when neither is available, as in most containers.
+#[must_use]
+pub fn device_identity(device: u8) -> Option<String> {
+    let name = format!("sr{device}");
+
+    // Links are named like 'ata-HL-DT-ST_BD-RE_WH16NS60_KLAM6E84510', the 'wwn-' links are
+    // left out since not every drive has one, so the name picked could change
+    let mut links: Vec<String> = std::fs::read_dir("/dev/disk/by-id")
+        .map(|entries| {
+            entries
+                .filter_map(std::result::Result::ok)
+                .filter(|entry| {
+                    std::fs::canonicalize(entry.path()).is_ok_and(|target| {
+                        target.file_name().is_some_and(|file| file == name.as_str())
+                    })
+                })
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|link| !link.starts_with("wwn-"))
+                .collect()
+        })
+        .unwrap_or_default();
+    links.sort();
+
+    if let Some(link) = links.into_iter().next() {
+        return Some(link);
+    }
+
+    // The unit serial number page starts with a 4 byte header
+    let device_dir = Path::new("/sys/block").join(&name).join("device");
+    let read = |file: &str| {
+        std::fs::read_to_string(device_dir.join(file))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+    let serial = std::fs::read(device_dir.join("vpd_pg80"))
+        .ok()
+        .and_then(|page| {
+            page.get(4..)
+                .map(|serial| String::from_utf8_lossy(serial).trim().to_string())
+        })
+        .filter(|serial| !serial.is_empty())?;
+
+    Some(format!(
+        "{} {} {}",
+        read("vendor").unwrap_or_default(),
+        read("model").unwrap_or_default(),
+        serial
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sr(index: u8) -> ListedDevice {
+        ListedDevice {
+            index,
+            path: format!("/dev/sr{index}"),
+            host: None,
+        }
+    }
+
+    #[test]
+    fn keeps_numbers_across_replugs() {
+        let mut map = DriveMap::default();
+        assert_eq!(map.assign("ata-DRIVE_A", sr(0)), 1);
+        assert_eq!(map.assign("usb-DRIVE_B", sr(1)), 2);
+
+        // After a reboot the drives come up the other way around
+        assert_eq!(map.assign("usb-DRIVE_B", sr(0)), 2);
+        assert_eq!(map.assign("ata-DRIVE_A", sr(1)), 1);
+        assert_eq!(map.devices.get(&1), Some(&sr(1)));
+        assert_eq!(map.devices.get(&2), Some(&sr(0)));
+    }
+
+    #[test]
+    fn numbers_new_drives_after_taken_ones() {
+        let mut map = DriveMap::default();
+        assert_eq!(map.assign("ata-DRIVE_A", sr(1)), 2);
+
+        // The new drive would have been 2, which is taken by drive A
+        assert_eq!(map.assign("usb-DRIVE_C", sr(1)), 1);
+        assert_eq!(map.assign("usb-DRIVE_D", sr(1)), 3);
+    }
+
+    #[test]
+    fn numbers_drive_letters_by_makemkv_index() {
+        let mut map = DriveMap::default();
+        let letter = |index, letter: &str| ListedDevice {
+            index,
+            path: letter.to_string(),
+            host: None,
+        };
+        assert_eq!(
+            map.assign("BD-RE ASUS BW-16D1HT 3.10 KLFK1234", letter(1, "E:")),
+            2
+        );
+        assert_eq!(
+            map.assign("DVD+-RW HL-DT-ST GH24NSD1 LK00", letter(0, "D:")),
+            1
+        );
+    }
+
+    #[test]
+    fn keeps_drives_on_agents_apart() {
+        let mut map = DriveMap::default();
+        let on = |host: &str| ListedDevice {
+            host: Some(host.to_string()),
+            ..sr(0)
+        };
+        assert_eq!(map.assign("ata-DRIVE_A", sr(0)), 1);
+        assert_eq!(map.assign("garage: BD-RE DRIVE_B", on("garage")), 2);
+        assert_eq!(map.assign("office: BD-RE DRIVE_C", on("office")), 3);
+
+        // Every drive is at /dev/sr0 of its own machine
+        assert_eq!(map.devices.len(), 3);
+    }
+
+    #[test]
+    fn finds_drives_by_capability() {
+        let mut map = DriveMap::default();
+        map.assign("ata-DRIVE_A", sr(0));
+        map.assign("usb-DRIVE_B", sr(1));
+        if let Some(drive) = map.drives.get_mut(&2) {
+            drive.capabilities = vec!["UHD".to_string()];
+        }
+
+        assert_eq!(map.capabilities(2), vec!["uhd".to_string()]);
+        assert_eq!(map.drives_with("uhd"), vec![2]);
+        assert!(map.capabilities(1).is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! # Episode Patterns
+//!
+//! Show rips continue numbering from the last episode already in the season directory.
+//! Since libraries aren't always named by cord-ripper, the episode of each file is found
+//! with a set of filename patterns. Patterns are plain strings containing tokens:
+//!
+//! - `{episode}`: The episode number, required in every pattern
+//! - `{season}`: The season number, files from another season are ignored
+//!
+//! The rest of the pattern is matched literally, ignoring case, anywhere in the file name.
+//! The defaults recognize `Episode 5.mkv`, `Show - S01E05.mkv` and `Show - 1x05.mkv`.
+
+use super::errors::{MakeMkvError, Result};
+
+/// The patterns used when none are configured
+pub const DEFAULT_EPISODE_PATTERNS: [&str; 3] = [
+    "Episode {episode}",
+    "S{season}E{episode}",
+    "{season}x{episode}",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    Season,
+    Episode,
+}
+
+/// A filename pattern that episode numbers can be read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpisodePattern {
+    tokens: Vec<Token>,
+}
+
+impl EpisodePattern {
+    /// Parses a pattern such as `S{season}E{episode}`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `MakeMkvError` if the pattern has no `{episode}` token, has an unknown
+    /// token, or has two numbers in a row.
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let invalid = || MakeMkvError::InvalidEpisodePattern(pattern.to_string());
+
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '{' {
+                literal.extend(c.to_lowercase());
+                continue;
+            }
+
+            let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+            let token = match name.as_str() {
+                "season" => Token::Season,
+                "episode" => Token::Episode,
+                _ => return Err(invalid()),
+            };
+
+            // Two numbers in a row couldn't be told apart
+            if literal.is_empty() && matches!(tokens.last(), Some(Token::Season | Token::Episode)) {
+                return Err(invalid());
+            }
+
+            if !literal.is_empty() {
+                tokens.push(Token::Literal(std::mem::take(&mut literal)));
+            }
+            tokens.push(token);
+        }
+
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+
+        if !tokens.contains(&Token::Episode) {
+            return Err(invalid());
+        }
+
+        Ok(EpisodePattern { tokens })
+    }
+
+    /// Returns the patterns in `DEFAULT_EPISODE_PATTERNS`.
+    #[must_use]
+    pub fn defaults() -> Vec<Self> {
+        DEFAULT_EPISODE_PATTERNS
+            .iter()
+            .filter_map(|pattern| EpisodePattern::parse(pattern).ok())
+            .collect()
+    }
+
+    /// Reads the episode number from a file name, if it matches the pattern and season.
+    #[must_use]
+    pub fn episode(&self, file_name: &str, season: u8) -> Option<u8> {
+        let file_name = file_name.to_lowercase();
+
+        // Tries every starting point, the first one that fully matches wins
+        file_name
+            .char_indices()
+            .filter(|&(start, _)| {
+                // A number can't start partway through another, e.g. 1920x1080
+                !matches!(self.tokens.first(), Some(Token::Season | Token::Episode))
+                    || !file_name[..start].ends_with(|c: char| c.is_ascii_digit())
+            })
+            .find_map(|(start, _)| self.match_at(&file_name[start..], season))
+    }
+
+    fn match_at(&self, mut rest: &str, season: u8) -> Option<u8> {
+        let mut episode = None;
+
+        for token in &self.tokens {
+            match token {
+                Token::Literal(literal) => rest = rest.strip_prefix(literal.as_str())?,
+                Token::Season | Token::Episode => {
+                    let digits = rest
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(rest.len());
+                    let number: u8 = rest[..digits].parse().ok()?;
+                    rest = &rest[digits..];
+
+                    if *token == Token::Season && number != season {
+                        return None;
+                    }
+                    if *token == Token::Episode {
+                        episode = Some(number);
+                    }
+                }
+            }
+        }
+
+        episode
+    }
+}
+
+/// Finds the highest episode of a season in a list of file names, or 0 if none of them
+/// match any of the patterns.
+#[must_use]
+pub fn last_episode<'a>(
+    file_names: impl IntoIterator<Item = &'a str>,
+    season: u8,
+    patterns: &[EpisodePattern],
+) -> u8 {
+    file_names
+        .into_iter()
+        .filter_map(|file_name| {
+            patterns
+                .iter()
+                .filter_map(|pattern| pattern.episode(file_name, season))
+                .max()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last(file_names: &[&str], season: u8) -> u8 {
+        last_episode(
+            file_names.iter().copied(),
+            season,
+            &EpisodePattern::defaults(),
+        )
+    }
+
+    #[test]
+    fn empty_directory_is_zero() {
+        assert_eq!(last(&[], 1), 0);
+    }
+
+    #[test]
+    fn episode_names() {
+        assert_eq!(
+            last(&["Episode 1.mkv", "Episode 2.mkv", "Episode 10.mkv"], 1),
+            10
+        );
+        assert_eq!(
+            last(
+                &[
+                    "Episode 6.mkv",
+                    "Episode 7 - The One with the Racecar Bed.mkv"
+                ],
+                2
+            ),
+            7
+        );
+    }
+
+    #[test]
+    fn sxxeyy_names() {
+        let listing = [
+            "Show - S01E01.mkv",
+            "Show - S01E05 - The Title.mkv",
+            "show.s01e03.1080p.mkv",
+        ];
+        assert_eq!(last(&listing, 1), 5);
+    }
+
+    #[test]
+    fn mixed_names_take_the_highest() {
+        let listing = ["Episode 3.mkv", "Show - S02E07.mkv", "Show - 2x04.mkv"];
+        assert_eq!(last(&listing, 2), 7);
+    }
+
+    #[test]
+    fn other_seasons_are_ignored() {
+        let listing = ["Show - S01E01.mkv", "Show - S02E09.mkv"];
+        assert_eq!(last(&listing, 1), 1);
+    }
+
+    #[test]
+    fn unrecognized_names_are_ignored() {
+        let listing = [
+            "Show 1920x1080.mkv",
+            "poster.jpg",
+            "Show - Behind the Scenes.mkv",
+        ];
+        assert_eq!(last(&listing, 1), 0);
+    }
+
+    #[test]
+    fn custom_patterns() {
+        let patterns = vec![EpisodePattern::parse("Ep.{episode}").unwrap()];
+        let listing = ["My Show Ep.4.mkv", "My Show Ep.12.mkv", "Episode 20.mkv"];
+        assert_eq!(last_episode(listing, 1, &patterns), 12);
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert!(EpisodePattern::parse("S{season}").is_err());
+        assert!(EpisodePattern::parse("{title} {episode}").is_err());
+        assert!(EpisodePattern::parse("{season}{episode}").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use std::io;
 use thiserror::Error;
 
+use crate::tr;
+
 pub type Result<T> = std::result::Result<T, MakeMkvError>;
 
 #[derive(Debug, Error, PartialEq)]
@@ -46,6 +48,57 @@ pub enum MakeMkvError {
 
     #[error("Directory already exists!{0}")]
     FileAlreadyExists(String),
+
+    #[error("Failed to open disc")]
+    DiscOpenFailed,
+
+    #[error("Read error: {0}")]
+    ReadError(String),
+
+    #[error("Hash check failed for file {0}")]
+    HashCheckFailed(String),
+
+    #[error("Copy incomplete, {saved} titles saved and {failed} failed")]
+    CopyIncomplete { saved: u32, failed: u32 },
+
+    #[error("MakeMKV version is too old")]
+    VersionTooOld,
+
+    #[error("MakeMKV evaluation period or registration key has expired")]
+    RegistrationExpired,
+
+    #[error("Failed to eject drive {0}: {1}")]
+    EjectError(u8, String),
+
+    #[error("Invalid episode pattern: {0}")]
+    InvalidEpisodePattern(String),
+
+    #[error("Failed to split title by chapters: {0}")]
+    SplitError(String),
+
+    #[error("Drive {0} disconnected")]
+    DriveDisconnected(u8),
+
+    #[error("Agent {0} failed: {1}")]
+    AgentError(String, String),
+
+    #[error("makemkvcon couldn't be run as `{0}`: {1}")]
+    MakeMkvNotFound(String, String),
+
+    #[error("Drive {0} can't read {1} discs")]
+    MissingCapability(u8, String, Vec<u8>),
+
+    #[error("Destination is outside the output directory: {0}")]
+    UnsafePath(String),
+
+    #[error("Copy didn't match the ripped file: {0}")]
+    WriteVerificationFailed(String),
+
+    #[error("Ripped file failed validation, {0}")]
+    ValidationFailed(String),
+
+    #[error("Rip cancelled")]
+    Cancelled,
 }
 
 // Example usage
@@ -53,6 +106,127 @@ impl MakeMkvError {
     pub fn log_error(&self) {
         eprintln!("Error: {}", self);
     }
+
+    /// Whether the error is the drive or the disc in it failing, rather than something
+    /// around the rip such as the library or the queue. Only these count against a drive
+    /// in its statistics.
+    #[must_use]
+    pub fn is_drive_failure(&self) -> bool {
+        matches!(
+            self,
+            MakeMkvError::DriveError
+                | MakeMkvError::DiscOpenFailed
+                | MakeMkvError::ReadError(_)
+                | MakeMkvError::HashCheckFailed(_)
+                | MakeMkvError::FailedToSaveDisc
+                | MakeMkvError::CopyIncomplete { .. }
+                | MakeMkvError::DriveDisconnected(_)
+                | MakeMkvError::ValidationFailed(_)
+        )
+    }
+
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            MakeMkvError::DriveInUseError(drive) => {
+                tr!(
+                    "Drive {drive} is already in use by another rip.",
+                    drive = drive
+                )
+            }
+            MakeMkvError::FileAlreadyExists(_) => tr!("This rip is already on the server!"),
+            MakeMkvError::DriveError => {
+                tr!("The drive failed to read the disc. Please wait a moment and try again, if the issue persists please cycle the drive tray.")
+            }
+            MakeMkvError::DiscOpenFailed => {
+                tr!("MakeMKV could not open the disc. Make sure a disc is inserted and try again.")
+            }
+            MakeMkvError::ReadError(_) => {
+                tr!("The drive hit a read error. The disc is likely scratched or dirty.")
+            }
+            MakeMkvError::HashCheckFailed(file) => {
+                tr!(
+                    "Hash check failed for {file}. The disc is likely scratched or corrupt.",
+                    file = file
+                )
+            }
+            MakeMkvError::CopyIncomplete { saved, failed } => {
+                tr!(
+                    "Only {saved} titles were saved, {failed} failed to rip.",
+                    saved = saved,
+                    failed = failed
+                )
+            }
+            MakeMkvError::FailedToSaveDisc => {
+                tr!("MakeMKV failed to save the title. Likely a scratched or corrupt disc.")
+            }
+            MakeMkvError::VersionTooOld => {
+                tr!("The installed MakeMKV version is too old. Please update MakeMKV on the server.")
+            }
+            MakeMkvError::RegistrationExpired => {
+                tr!("The MakeMKV evaluation period or beta key has expired. Please update the key on the server.")
+            }
+            MakeMkvError::NoDrivesFound => tr!("No drives could be found on the server."),
+            MakeMkvError::EjectError(drive, _) => {
+                tr!(
+                    "Drive {drive} could not be ejected, please open the tray by hand.",
+                    drive = drive
+                )
+            }
+            MakeMkvError::DriveDisconnected(drive) => {
+                tr!("Drive {drive} disconnected while ripping.", drive = drive)
+            }
+            MakeMkvError::MissingCapability(drive, capability, capable) if capable.is_empty() => {
+                tr!(
+                    "Drive {drive} can't read {capability} discs, and no drive that can is connected.",
+                    drive = drive,
+                    capability = capability
+                )
+            }
+            MakeMkvError::MissingCapability(drive, capability, capable) => {
+                tr!(
+                    "Drive {drive} can't read {capability} discs, drive {drives} can.",
+                    drive = drive,
+                    capability = capability,
+                    drives = capable
+                        .iter()
+                        .map(u8::to_string)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
+            MakeMkvError::MakeMkvNotFound(command, _) => {
+                tr!(
+                    "MakeMKV couldn't be run on the server as `{command}`.",
+                    command = command
+                )
+            }
+            MakeMkvError::AgentError(agent, _) => {
+                tr!(
+                    "The ripping agent {agent} couldn't be reached, or failed to answer.",
+                    agent = agent
+                )
+            }
+            MakeMkvError::UnsafePath(_) => {
+                tr!("The naming template would save this rip outside the output directory, check it has no `..` in it.")
+            }
+            MakeMkvError::WriteVerificationFailed(_) => {
+                tr!("The title was ripped but couldn't be copied into the library intact, the network mount may be dropping writes.")
+            }
+            MakeMkvError::ValidationFailed(reason) => {
+                tr!(
+                    "The title was ripped but the file doesn't look right, {reason}.",
+                    reason = reason
+                )
+            }
+            MakeMkvError::SplitError(_) => {
+                tr!("The title was ripped but couldn't be split into episodes, is mkvmerge installed?")
+            }
+            MakeMkvError::Cancelled => tr!("Cancelled before it finished."),
+            _ => tr!("This rip failed! Please try again."),
+        }
+    }
 }
 
 impl From<io::Error> for MakeMkvError {
@@ -76,3 +250,18 @@ impl From<std::num::ParseIntError> for MakeMkvError {
         MakeMkvError::InvalidOutputFormat(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_disc_errors_count_against_the_drive() {
+        assert!(MakeMkvError::ReadError("L-EC error".to_string()).is_drive_failure());
+        assert!(MakeMkvError::DriveDisconnected(1).is_drive_failure());
+        assert!(!MakeMkvError::FileAlreadyExists(String::new()).is_drive_failure());
+        assert!(!MakeMkvError::DriveInUseError(1).is_drive_failure());
+        assert!(!MakeMkvError::UnsafePath(String::new()).is_drive_failure());
+        assert!(!MakeMkvError::WriteVerificationFailed(String::new()).is_drive_failure());
+    }
+}
@@ -0,0 +1,340 @@
+//! # Rip ETAs
+//!
+//! Estimates how long the running rips have left, so the rip embed and `/queue` can show
+//! something like "~38 min remaining at 22 MB/s" instead of leaving users guessing.
+//!
+//! ## Overview
+//!
+//! - **`RipEta`**: How far a rip is, and how fast its drive is reading.
+//!
+//! - **`EtaTracker`**: Keeps the estimate of a running rip up to date, and removes it once
+//!   the rip is over.
+//!
+//! - **`estimate`**: The estimate of the rip running on a drive, if there is one.
+//!
+//! - **`RipPhase`** and **`phase`**: What `MakeMKV` is doing on a drive, since it spends a
+//!   while opening the disc and analyzing it before the progress starts moving.
+//!
+//! ## Notes
+//!
+//! - Until `MakeMKV` has reported enough progress to measure the speed of the rip, the
+//!   drive's recent average speed from the `HISTORY` is used, so a drive that never
+//!   finished a rip has no estimate until then.
+//! - Rips on agents don't report progress until they are done, their estimate stays at the
+//!   drive's average speed.
+//! - The phase goes by the operation names `MakeMKV` prints, which are in whatever
+//!   language it is set to, names that aren't recognized are shown as they are.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use tokio::sync::mpsc;
+
+use super::{
+    makemkv_core::Rip,
+    makemkv_helpers::{parse_size, SCANNED_TITLES},
+    robot::{self, RobotLine},
+};
+use crate::history::HISTORY;
+use crate::trace;
+
+/// How far a rip has to be before its own speed is trusted over the drive's average, the
+/// start of a rip is mostly opening the disc
+const MIN_MEASURED_FRACTION: f64 = 0.02;
+
+/// The estimates of the running rips, by drive number
+static RIP_ETAS: OnceLock<Mutex<HashMap<u8, RipEta>>> = OnceLock::new();
+
+/// The phases of the running rips, by drive number
+static RIP_PHASES: OnceLock<Mutex<HashMap<u8, RipPhase>>> = OnceLock::new();
+
+fn etas() -> std::sync::MutexGuard<'static, HashMap<u8, RipEta>> {
+    RIP_ETAS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn phases() -> std::sync::MutexGuard<'static, HashMap<u8, RipPhase>> {
+    RIP_PHASES
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// What `MakeMKV` is doing during a rip, from the name of its current operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum RipPhase {
+    /// Opening the disc and reading its structure
+    Opening,
+    /// Analyzing seamless segments, which can take minutes before any progress shows
+    Analyzing,
+    /// Saving the title to an MKV file
+    Saving,
+    /// An operation that isn't recognized, with the name `MakeMKV` gave it
+    Other(String),
+}
+
+impl RipPhase {
+    /// The phase a `PRGT` or `PRGC` operation name belongs to, e.g. "Analyzing seamless
+    /// segments" or "Saving to MKV file"
+    #[must_use]
+    pub fn from_operation(name: &str) -> Self {
+        let name = name.trim();
+        let lowercase = name.to_lowercase();
+        if lowercase.contains("saving") {
+            RipPhase::Saving
+        } else if lowercase.contains("analyz") || lowercase.contains("analys") {
+            RipPhase::Analyzing
+        } else if ["opening", "scanning", "processing", "reading"]
+            .iter()
+            .any(|word| lowercase.contains(word))
+        {
+            RipPhase::Opening
+        } else {
+            RipPhase::Other(name.to_string())
+        }
+    }
+}
+
+/// How far a rip is, and how fast its drive is reading.
+///
+/// # Fields
+///
+/// * `total_bytes` - The size of the title being ripped.
+/// * `fraction` - How much of the rip is done, from 0 to 1.
+/// * `bytes_per_second` - The speed of the rip, measured once it is far enough along and
+///   the drive's average speed before then.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RipEta {
+    pub total_bytes: u64,
+    pub fraction: f64,
+    pub bytes_per_second: f64,
+}
+
+impl RipEta {
+    /// The seconds the rip has left at its current speed
+    #[must_use]
+    // Title sizes are far too small to lose precision, and the result is never negative
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn remaining_seconds(&self) -> u64 {
+        let remaining = self.total_bytes as f64 * (1.0 - self.fraction.clamp(0.0, 1.0));
+        (remaining / self.bytes_per_second).round() as u64
+    }
+}
+
+/// The speed of a rip that is `fraction` done after `elapsed_seconds`, or `average` until
+/// it is far enough along to be measured
+#[allow(clippy::cast_precision_loss)]
+fn rip_speed(
+    total_bytes: u64,
+    fraction: f64,
+    elapsed_seconds: f64,
+    average: Option<f64>,
+) -> Option<f64> {
+    if fraction >= MIN_MEASURED_FRACTION && elapsed_seconds > 0.0 {
+        return Some(total_bytes as f64 * fraction / elapsed_seconds);
+    }
+    average
+}
+
+/// Starts following a rip, estimating it from the size the disc scan found for its title
+/// and the speed of its drive in the `HISTORY`. Without a size only its phase is followed.
+pub async fn track(rip: &Rip) -> EtaTracker {
+    let total_bytes = SCANNED_TITLES
+        .lock()
+        .await
+        .get(&rip.drive_number)
+        .and_then(|scan| {
+            scan.titles
+                .iter()
+                .find(|title| title.title_id == rip.title_id)
+                .and_then(|title| parse_size(&title.size))
+        });
+
+    // The recent speed reflects the drive as it is now, a drive wears out
+    let average_speed = HISTORY
+        .lock()
+        .await
+        .drive_stats()
+        .get(&rip.drive_number)
+        .and_then(|stats| stats.recent_average_speed().or(stats.average_speed()));
+
+    EtaTracker::start(rip.drive_number, total_bytes, average_speed)
+}
+
+/// Keeps the estimate and phase of the rip on a drive up to date, both are removed once the
+/// tracker is dropped, however the rip ended.
+pub struct EtaTracker {
+    drive_number: u8,
+    total_bytes: Option<u64>,
+    average_speed: Option<f64>,
+    started: Instant,
+}
+
+impl EtaTracker {
+    /// Starts estimating a rip of `total_bytes` on a drive that reads at `average_speed`
+    /// bytes per second, as far as they are known
+    #[must_use]
+    pub fn start(drive_number: u8, total_bytes: Option<u64>, average_speed: Option<f64>) -> Self {
+        let tracker = EtaTracker {
+            drive_number,
+            total_bytes,
+            average_speed,
+            started: Instant::now(),
+        };
+        tracker.update(0.0);
+        tracker
+    }
+
+    /// Updates the estimate once the rip is `fraction` done
+    pub fn update(&self, fraction: f64) {
+        let Some(total_bytes) = self.total_bytes else {
+            return;
+        };
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let Some(bytes_per_second) = rip_speed(total_bytes, fraction, elapsed, self.average_speed)
+            .filter(|speed| *speed > 0.0)
+        else {
+            return;
+        };
+
+        let eta = RipEta {
+            total_bytes,
+            fraction,
+            bytes_per_second,
+        };
+        trace!("ETA of the rip on drive {}: {:?}", self.drive_number, eta);
+        etas().insert(self.drive_number, eta);
+    }
+
+    /// Updates the phase of the rip
+    pub fn set_phase(&self, phase: RipPhase) {
+        let mut phases = phases();
+        if phases.get(&self.drive_number) != Some(&phase) {
+            trace!("Rip on drive {} is now {:?}", self.drive_number, phase);
+            phases.insert(self.drive_number, phase);
+        }
+    }
+
+    /// Updates the estimate and phase from the progress `makemkvcon` prints, until it stops
+    /// printing
+    pub async fn follow(&self, mut lines: mpsc::UnboundedReceiver<String>) {
+        while let Some(line) = lines.recv().await {
+            match robot::parse_line(&line) {
+                // The total is the progress of the whole rip, the current only of its step
+                Some(RobotLine::ProgressValue { total, max, .. }) if max > 0 => {
+                    self.update(f64::from(total) / f64::from(max));
+                }
+                // The step is more telling than the operation it is part of
+                Some(
+                    RobotLine::ProgressTitle { name, .. } | RobotLine::ProgressCurrent { name, .. },
+                ) if !name.trim().is_empty() => {
+                    self.set_phase(RipPhase::from_operation(&name));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for EtaTracker {
+    fn drop(&mut self) {
+        etas().remove(&self.drive_number);
+        phases().remove(&self.drive_number);
+    }
+}
+
+/// The estimate of the rip running on a drive, `None` if nothing is ripping or there is
+/// nothing to estimate from yet
+#[must_use]
+pub fn estimate(drive_number: u8) -> Option<RipEta> {
+    etas().get(&drive_number).copied()
+}
+
+/// The phase of the rip running on a drive, `None` if nothing is ripping or `MakeMKV`
+/// hasn't said what it is doing yet
+#[must_use]
+pub fn phase(drive_number: u8) -> Option<RipPhase> {
+    phases().get(&drive_number).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn uses_the_drive_average_until_the_rip_is_measured() {
+        assert_eq!(rip_speed(GB, 0.0, 0.0, Some(100.0)), Some(100.0));
+        assert_eq!(rip_speed(GB, 0.01, 30.0, None), None);
+        assert_eq!(rip_speed(1000, 0.5, 10.0, Some(1.0)), Some(50.0));
+    }
+
+    #[test]
+    fn remaining_time_shrinks_with_progress() {
+        let eta = RipEta {
+            total_bytes: 22 * 1024 * 1024 * 60,
+            fraction: 0.0,
+            bytes_per_second: 22.0 * 1024.0 * 1024.0,
+        };
+        assert_eq!(eta.remaining_seconds(), 60);
+        assert_eq!(
+            RipEta {
+                fraction: 0.75,
+                ..eta
+            }
+            .remaining_seconds(),
+            15
+        );
+    }
+
+    #[test]
+    fn estimates_are_removed_with_their_tracker() {
+        let tracker = EtaTracker::start(200, Some(GB), Some(1024.0));
+        assert!(estimate(200).is_some());
+        tracker.set_phase(RipPhase::Analyzing);
+        assert_eq!(phase(200), Some(RipPhase::Analyzing));
+        drop(tracker);
+        assert!(estimate(200).is_none());
+        assert!(phase(200).is_none());
+
+        // Nothing to estimate from
+        let _tracker = EtaTracker::start(201, Some(GB), None);
+        assert!(estimate(201).is_none());
+        let _tracker = EtaTracker::start(202, None, Some(1024.0));
+        assert!(estimate(202).is_none());
+    }
+
+    #[test]
+    fn recognizes_makemkv_operations() {
+        assert_eq!(
+            RipPhase::from_operation("Opening Blu-ray disc"),
+            RipPhase::Opening
+        );
+        assert_eq!(
+            RipPhase::from_operation("Processing title sets"),
+            RipPhase::Opening
+        );
+        assert_eq!(
+            RipPhase::from_operation("Analyzing seamless segments"),
+            RipPhase::Analyzing
+        );
+        assert_eq!(
+            RipPhase::from_operation("Saving to MKV file"),
+            RipPhase::Saving
+        );
+        assert_eq!(
+            RipPhase::from_operation(" Speichern "),
+            RipPhase::Other("Speichern".to_string())
+        );
+    }
+}
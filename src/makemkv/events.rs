@@ -0,0 +1,136 @@
+//! # Rip Events
+//!
+//! Rips announce what they are doing on a broadcast channel, so anything that wants to
+//! follow them, such as code embedding Cord Ripper or an HTTP API, can subscribe rather
+//! than going through Discord or the logs.
+//!
+//! ## Overview
+//!
+//! - **`RipEvent`**: Something that happened to a rip, from being queued to completing or
+//!   failing.
+//!
+//! - **`RipStage`**: The step a running rip has reached.
+//!
+//! - **`subscribe`**: Returns a receiver for every event sent from then on.
+//!
+//! ## Notes
+//!
+//! - Events are only kept for subscribers that exist when they are sent, and a subscriber
+//!   that falls more than `EVENT_CAPACITY` events behind misses the oldest ones.
+//! - Sending never blocks a rip, and nothing happens when there are no subscribers.
+
+use std::{path::PathBuf, sync::OnceLock};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::makemkv_core::Rip;
+use crate::trace;
+
+/// How many events a subscriber can fall behind by before it misses some
+const EVENT_CAPACITY: usize = 128;
+
+/// Where rip events are sent, every subscriber gets a copy of each
+static RIP_EVENTS: OnceLock<broadcast::Sender<RipEvent>> = OnceLock::new();
+
+fn sender() -> &'static broadcast::Sender<RipEvent> {
+    RIP_EVENTS.get_or_init(|| broadcast::channel(EVENT_CAPACITY).0)
+}
+
+/// Something that happened to a rip.
+///
+/// # Variants
+///
+/// - `Queued`: The rip was added to the queue and waits for its drive's host to have a slot.
+/// - `Started`: The rip started, on the agent named by `host`, or this machine if `None`.
+/// - `Progress`: The rip reached another `RipStage`.
+/// - `Completed`: The rip was saved to `files`, taking `seconds`.
+/// - `Failed`: The rip failed with `error`, a message suitable for users.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RipEvent {
+    Queued {
+        rip: Rip,
+    },
+    Started {
+        rip: Rip,
+        host: Option<String>,
+    },
+    Progress {
+        rip: Rip,
+        stage: RipStage,
+    },
+    Completed {
+        rip: Rip,
+        files: Vec<PathBuf>,
+        seconds: f64,
+    },
+    Failed {
+        rip: Rip,
+        error: String,
+    },
+}
+
+/// The step a running rip has reached, in the order they happen
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RipStage {
+    /// `MakeMKV` is reading the title from the disc
+    Ripping,
+    /// The ripped title is being split into episodes by chapters
+    Splitting,
+    /// The ripped files are being moved to their destination
+    Saving,
+}
+
+/// Returns a receiver for every rip event sent from now on
+#[must_use]
+pub fn subscribe() -> broadcast::Receiver<RipEvent> {
+    sender().subscribe()
+}
+
+/// Sends a rip event to every subscriber
+pub fn emit(event: RipEvent) {
+    trace!("Rip event: {:?}", event);
+    // An error only means nobody is listening
+    let _ = sender().send(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::makemkv::RipType;
+
+    #[test]
+    fn subscribers_receive_events_sent_after_subscribing() {
+        let rip = Rip {
+            title: "Heat".to_string(),
+            drive_number: 0,
+            rip_type: RipType::Movie,
+            title_id: 1,
+            year: Some(1995),
+            chapters_per_episode: None,
+            library: None,
+            episode_title: None,
+            note: None,
+            extra: None,
+            base_view: false,
+        };
+        emit(RipEvent::Queued { rip: rip.clone() });
+
+        let mut events = subscribe();
+        emit(RipEvent::Progress {
+            rip,
+            stage: RipStage::Saving,
+        });
+
+        match events.try_recv() {
+            Ok(RipEvent::Progress { rip, stage }) => {
+                assert_eq!(rip.title, "Heat");
+                assert_eq!(stage, RipStage::Saving);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert!(events.try_recv().is_err());
+    }
+}
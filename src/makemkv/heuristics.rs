@@ -0,0 +1,352 @@
+//! # Title Heuristics
+//!
+//! Movie discs hold the main feature alongside extras, trailers and sometimes alternate
+//! cuts, which are easy to mix up in a list of title numbers. Each title is scored on how
+//! much it looks like the main feature, so the likely one can be marked and listed first.
+//!
+//! A title scores up to one point for each of:
+//!
+//! - Its duration, relative to the longest title on the disc, counted three times
+//! - Its chapters, relative to the title with the most
+//! - Its size, relative to the largest title
+//! - How close its duration is to the movie's runtime, when Radarr knows it, counted three
+//!   times. Titles off by a fifth of the runtime or more get nothing for it
+//!
+//! The duration outweighs the rest, since extras are rarely longer than the movie, but the
+//! runtime can still tell a theatrical cut from an extended one.
+//!
+//! ## Obfuscated Discs
+//!
+//! Some Blu-rays ship with dozens of playlists of the same duration, only one of which
+//! plays the movie in the right order. They all score the same, so they are checked
+//! separately. The stream files of the real playlist are almost always laid out on the
+//! disc in the order they play, while the decoys jump around, so the playlist whose
+//! segment map is most in order is suggested.
+//!
+//! ## Episodes
+//!
+//! TV discs hold episodes of about the same length, often with a "play all" title of them
+//! back to back and a few extras. When titles aren't picked by hand, the episodes are
+//! taken to be the titles within half of the typical title's length either way, which
+//! leaves the "play all" title and the shorter extras out.
+
+use super::makemkv_helpers::{parse_length, parse_size, Title};
+
+/// How much more the duration and runtime count than the chapters and size
+const DURATION_WEIGHT: f64 = 3.0;
+
+/// How far off the runtime, as a fraction of it, a title can be and still score for it
+const RUNTIME_TOLERANCE: f64 = 0.2;
+
+/// How far ahead of the next title, as a fraction of its score, the best title has to be
+/// to be marked as the main feature
+const MIN_LEAD: f64 = 0.05;
+
+/// How many titles need to share a duration for a disc to be treated as obfuscated
+const OBFUSCATION_THRESHOLD: usize = 5;
+
+/// How much of a segment map has to be in order, and how far ahead of the next title, for
+/// an obfuscated disc's suggestion to be trusted
+const MIN_ORDER_CONFIDENCE: f64 = 0.9;
+const MIN_ORDER_LEAD: f64 = 0.1;
+
+/// A title and its score, the higher the more likely it is the main feature.
+#[derive(Debug, Clone)]
+pub struct ScoredTitle {
+    pub title: Title,
+    pub score: f64,
+}
+
+/// Scores the titles of a disc, returning them sorted from most to least likely to be the
+/// main feature. Titles with the same score keep their order on the disc.
+///
+/// `runtime` is the movie's runtime in minutes, if it is known.
+#[must_use]
+pub fn rank_titles(titles: &[Title], runtime: Option<u32>) -> Vec<ScoredTitle> {
+    let duration = |title: &Title| f64::from(parse_length(&title.length).unwrap_or(0));
+    // Sizes are only compared to each other, so the precision lost doesn't matter
+    #[allow(clippy::cast_precision_loss)]
+    let size = |title: &Title| parse_size(&title.size).unwrap_or(0) as f64;
+
+    let max_duration = titles.iter().map(duration).fold(0.0, f64::max);
+    let max_chapters = titles.iter().map(|title| title.chapters).max().unwrap_or(0);
+    let max_size = titles.iter().map(size).fold(0.0, f64::max);
+
+    let fraction = |value: f64, max: f64| if max > 0.0 { value / max } else { 0.0 };
+
+    let mut scored: Vec<ScoredTitle> = titles
+        .iter()
+        .map(|title| {
+            let mut score = DURATION_WEIGHT * fraction(duration(title), max_duration)
+                + fraction(f64::from(title.chapters), f64::from(max_chapters))
+                + fraction(size(title), max_size);
+
+            if let Some(runtime) = runtime.filter(|runtime| *runtime > 0) {
+                let runtime = f64::from(runtime * 60);
+                let difference = (duration(title) - runtime).abs() / runtime;
+                score += DURATION_WEIGHT * (1.0 - difference / RUNTIME_TOLERANCE).max(0.0);
+            }
+
+            ScoredTitle {
+                title: title.clone(),
+                score,
+            }
+        })
+        .collect();
+
+    // A stable sort, so ties stay in disc order
+    scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+    scored
+}
+
+/// The title most likely to be the main feature, from titles ranked by `rank_titles`.
+/// `None` if there are no titles, or the best two are too close to tell apart.
+#[must_use]
+pub fn likely_main_feature(ranked: &[ScoredTitle]) -> Option<u16> {
+    let best = ranked.first()?;
+
+    match ranked.get(1) {
+        Some(second) if best.score - second.score < best.score * MIN_LEAD => None,
+        _ => Some(best.title.title_id),
+    }
+}
+
+/// The titles of a TV disc that look to be its episodes, in their order on the disc. The
+/// typical length is the median of the titles, which the episodes outnumber.
+#[must_use]
+pub fn likely_episodes(titles: &[Title]) -> Vec<u16> {
+    let mut lengths: Vec<u32> = titles
+        .iter()
+        .filter_map(|title| parse_length(&title.length))
+        .collect();
+    lengths.sort_unstable();
+    let Some(&typical) = lengths.get(lengths.len() / 2) else {
+        return Vec::new();
+    };
+
+    let range = typical / 2..=typical + typical / 2;
+    titles
+        .iter()
+        .filter(|title| parse_length(&title.length).is_some_and(|length| range.contains(&length)))
+        .map(|title| title.title_id)
+        .collect()
+}
+
+/// A disc that looks to be hiding its main feature among decoy playlists.
+///
+/// # Fields
+///
+/// * `decoys` - The titles sharing the same duration, including the suggested one.
+/// * `suggested` - The title whose segments are most in order, if any have a segment map.
+/// * `confident` - Whether the suggestion stands out clearly from the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Obfuscation {
+    pub decoys: Vec<u16>,
+    pub suggested: Option<u16>,
+    pub confident: bool,
+}
+
+/// Expands a `MakeMKV` segment map, e.g. "1,3,5-7", into the segments it lists. Parts that
+/// aren't numbers or ranges are skipped.
+#[must_use]
+pub fn parse_segment_map(map: &str) -> Vec<u32> {
+    map.split(',')
+        .filter_map(|part| match part.trim().split_once('-') {
+            Some((start, end)) => start.trim().parse().ok().zip(end.trim().parse().ok()),
+            None => part.trim().parse().ok().map(|segment| (segment, segment)),
+        })
+        .flat_map(|(start, end)| start..=end)
+        .collect()
+}
+
+/// The fraction of steps between segments that move forward on the disc, 1.0 for a map
+/// that is entirely in order.
+fn segment_order(segments: &[u32]) -> f64 {
+    let steps = segments.len().saturating_sub(1);
+    if steps == 0 {
+        return 1.0;
+    }
+
+    let forward = segments.windows(2).filter(|pair| pair[1] > pair[0]).count();
+    // Segment maps are far too short to lose precision
+    #[allow(clippy::cast_precision_loss)]
+    let order = forward as f64 / steps as f64;
+    order
+}
+
+/// Checks a disc for decoy playlists, returning `None` if it doesn't look obfuscated.
+///
+/// A disc is treated as obfuscated when `OBFUSCATION_THRESHOLD` or more titles share a
+/// duration. Of those, the title whose segment map is most in order is suggested.
+#[must_use]
+pub fn detect_obfuscation(titles: &[Title]) -> Option<Obfuscation> {
+    // The longest group of titles sharing a duration
+    let mut groups: Vec<(u32, Vec<&Title>)> = Vec::new();
+    for title in titles {
+        let Some(duration) = parse_length(&title.length) else {
+            continue;
+        };
+        match groups.iter_mut().find(|(length, _)| *length == duration) {
+            Some((_, group)) => group.push(title),
+            None => groups.push((duration, vec![title])),
+        }
+    }
+
+    let (_, decoys) = groups
+        .into_iter()
+        .filter(|(_, group)| group.len() >= OBFUSCATION_THRESHOLD)
+        .max_by_key(|(duration, group)| (group.len(), *duration))?;
+
+    let mut ordered: Vec<(u16, f64)> = decoys
+        .iter()
+        .filter(|title| !title.segments_map.is_empty())
+        .map(|title| {
+            (
+                title.title_id,
+                segment_order(&parse_segment_map(&title.segments_map)),
+            )
+        })
+        .collect();
+    ordered.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let suggested = ordered.first().map(|(title_id, _)| *title_id);
+    let confident = match (ordered.first(), ordered.get(1)) {
+        (Some((_, best)), Some((_, second))) => {
+            *best >= MIN_ORDER_CONFIDENCE && best - second >= MIN_ORDER_LEAD
+        }
+        (Some((_, best)), None) => *best >= MIN_ORDER_CONFIDENCE,
+        _ => false,
+    };
+
+    Some(Obfuscation {
+        decoys: decoys.iter().map(|title| title.title_id).collect(),
+        suggested,
+        confident,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title(title_id: u16, length: &str, chapters: u16, size: &str) -> Title {
+        Title {
+            title_id,
+            chapters,
+            length: length.to_string(),
+            size: size.to_string(),
+            ..Title::default()
+        }
+    }
+
+    fn playlist(title_id: u16, segments_map: &str) -> Title {
+        Title {
+            segments_map: segments_map.to_string(),
+            ..title(title_id, "1:56:32", 24, "30.1 GB")
+        }
+    }
+
+    #[test]
+    fn parses_segment_maps() {
+        assert_eq!(parse_segment_map("1,3,5-7"), [1, 3, 5, 6, 7]);
+        assert_eq!(parse_segment_map("12"), [12]);
+        assert_eq!(parse_segment_map("2,x,4"), [2, 4]);
+        assert!(parse_segment_map("").is_empty());
+    }
+
+    #[test]
+    fn suggests_the_playlist_in_order() {
+        let titles = [
+            playlist(1, "5,1,9,3,7"),
+            playlist(2, "2,8,4,6,1"),
+            playlist(3, "1-9"),
+            playlist(4, "9,2,3,7,1"),
+            playlist(5, "3,6,1,8,2"),
+            title(6, "0:03:10", 1, "0.4 GB"),
+        ];
+
+        let obfuscation = detect_obfuscation(&titles).unwrap();
+        assert_eq!(obfuscation.decoys, [1, 2, 3, 4, 5]);
+        assert_eq!(obfuscation.suggested, Some(3));
+        assert!(obfuscation.confident);
+    }
+
+    #[test]
+    fn unclear_playlists_are_not_confident() {
+        let titles = [
+            playlist(1, "1,2,3,5,4"),
+            playlist(2, "1,2,4,3,5"),
+            playlist(3, "2,1,3,4,5"),
+            playlist(4, "1,3,2,4,5"),
+            playlist(5, "1,2,3,5,4"),
+        ];
+
+        let obfuscation = detect_obfuscation(&titles).unwrap();
+        assert_eq!(obfuscation.suggested, Some(1));
+        assert!(!obfuscation.confident);
+    }
+
+    #[test]
+    fn normal_discs_are_not_obfuscated() {
+        let titles = [
+            title(1, "1:52:10", 28, "31.5 GB"),
+            title(2, "0:25:01", 6, "3.1 GB"),
+        ];
+
+        assert_eq!(detect_obfuscation(&titles), None);
+    }
+
+    #[test]
+    fn picks_episodes_over_play_all_and_extras() {
+        let titles = [
+            title(0, "1:28:04", 16, "6.2 GB"),
+            title(1, "0:22:01", 4, "1.5 GB"),
+            title(2, "0:21:47", 4, "1.5 GB"),
+            title(3, "0:22:30", 4, "1.6 GB"),
+            title(4, "0:21:46", 4, "1.5 GB"),
+            title(5, "0:10:12", 1, "0.7 GB"),
+        ];
+
+        assert_eq!(likely_episodes(&titles), [1, 2, 3, 4]);
+        assert!(likely_episodes(&[]).is_empty());
+    }
+
+    #[test]
+    fn ranks_the_longest_title_first() {
+        let titles = [
+            title(1, "0:12:30", 4, "1.2 GB"),
+            title(2, "1:52:10", 28, "31.5 GB"),
+            title(3, "0:25:01", 6, "3.1 GB"),
+        ];
+
+        let ranked = rank_titles(&titles, None);
+        let order: Vec<u16> = ranked.iter().map(|scored| scored.title.title_id).collect();
+
+        assert_eq!(order, [2, 3, 1]);
+        assert_eq!(likely_main_feature(&ranked), Some(2));
+    }
+
+    #[test]
+    fn runtime_picks_between_cuts() {
+        // An extended cut is longer, but the theatrical runtime was asked for
+        let titles = [
+            title(1, "2:14:40", 32, "36.0 GB"),
+            title(2, "1:58:05", 28, "32.0 GB"),
+        ];
+
+        let ranked = rank_titles(&titles, Some(118));
+        assert_eq!(likely_main_feature(&ranked), Some(2));
+    }
+
+    #[test]
+    fn identical_titles_are_too_close_to_call() {
+        let titles = [
+            title(1, "1:40:00", 20, "25.0 GB"),
+            title(2, "1:40:00", 20, "25.0 GB"),
+        ];
+
+        let ranked = rank_titles(&titles, None);
+        assert_eq!(ranked[0].title.title_id, 1);
+        assert_eq!(likely_main_feature(&ranked), None);
+    }
+}
@@ -0,0 +1,204 @@
+//! # Disc Labels
+//!
+//! TV box sets are usually labelled with the show, season and disc, such as
+//! `FRIENDS_S3_D2` or `LOST_SEASON_1_DISC_4`. Reading them saves typing the show and season
+//! into the show modal for every disc of a season.
+//!
+//! ## Notes
+//!
+//! - Labels are split into words on anything that isn't a letter or digit, so underscores,
+//!   dashes and spaces all work. The show is every word before the season or disc.
+//! - Labels without a season, such as `LOTR_FOTR_DISC_1`, aren't treated as TV discs,
+//!   since plenty of movies come on more than one disc too.
+//! - Labels are written in capitals, so the show is only a guess at how it is written,
+//!   and can be fixed in the modal.
+
+/// Words that are left lowercase in a show's name, unless they start it
+const SMALL_WORDS: [&str; 10] = ["a", "an", "and", "at", "for", "in", "of", "on", "the", "to"];
+
+/// How labels mark the disc number, longest first so `DISC2` isn't read as `D`
+const DISC_MARKERS: [&str; 3] = ["DISC", "DISK", "D"];
+
+/// Numbers written out in words, as some labels do, e.g. `MAD_MEN_SEASON_ONE`
+const NUMBER_WORDS: [&str; 12] = [
+    "ONE", "TWO", "THREE", "FOUR", "FIVE", "SIX", "SEVEN", "EIGHT", "NINE", "TEN", "ELEVEN",
+    "TWELVE",
+];
+
+/// The show, season and disc read from a disc's label.
+///
+/// # Fields
+///
+/// * `show` - The name of the show, e.g. `Friends` for `FRIENDS_S3_D2`.
+/// * `season` - The season the disc is of.
+/// * `disc` - Which disc of the season it is, if the label says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelInfo {
+    pub show: String,
+    pub season: u8,
+    pub disc: Option<u8>,
+}
+
+/// Reads the show, season and disc from a disc's label. Returns `None` unless the label has
+/// both a show and a season.
+#[must_use]
+pub fn parse_disc_label(label: &str) -> Option<LabelInfo> {
+    let words: Vec<String> = label
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_uppercase)
+        .collect();
+
+    let mut show = Vec::new();
+    let mut season = None;
+    let mut disc = None;
+    let mut i = 0;
+
+    while i < words.len() {
+        let word = words[i].as_str();
+        let next = words.get(i + 1).and_then(|next| number(next));
+
+        // e.g. `S3`, `SEASON3` or `S02D1`
+        if let Some((found_season, found_disc)) = season_and_disc(word) {
+            season = season.or(Some(found_season));
+            disc = disc.or(found_disc);
+        // e.g. `SEASON_3`
+        } else if let (Some(found_season), "SEASON") = (next, word) {
+            season = season.or(Some(found_season));
+            i += 1;
+        // e.g. `D2` or `DISC2`
+        } else if let Some(found_disc) = disc_number(word) {
+            disc = disc.or(Some(found_disc));
+        // e.g. `DISC_2`
+        } else if let (Some(found_disc), "DISC" | "DISK") = (next, word) {
+            disc = disc.or(Some(found_disc));
+            i += 1;
+        } else if season.is_none() && disc.is_none() {
+            show.push(word);
+        }
+
+        i += 1;
+    }
+
+    if show.is_empty() {
+        return None;
+    }
+
+    Some(LabelInfo {
+        show: title_case(&show),
+        season: season?,
+        disc,
+    })
+}
+
+/// Reads a season, and the disc if it follows, from a word such as `S3`, `SEASON3` or
+/// `S02D1`
+fn season_and_disc(word: &str) -> Option<(u8, Option<u8>)> {
+    let rest = word
+        .strip_prefix("SEASON")
+        .or_else(|| word.strip_prefix('S'))?;
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let season = rest[..digits].parse().ok()?;
+
+    match &rest[digits..] {
+        "" => Some((season, None)),
+        disc => Some((season, Some(disc_number(disc)?))),
+    }
+}
+
+/// Reads the number from a word made of a disc marker followed by digits, e.g. `D2`
+fn disc_number(word: &str) -> Option<u8> {
+    DISC_MARKERS
+        .iter()
+        .find_map(|marker| word.strip_prefix(marker))
+        .filter(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))?
+        .parse()
+        .ok()
+}
+
+/// Reads a number written in digits or words, e.g. `3` or `THREE`
+fn number(word: &str) -> Option<u8> {
+    if word.bytes().all(|b| b.is_ascii_digit()) {
+        return word.parse().ok();
+    }
+    NUMBER_WORDS
+        .iter()
+        .position(|number| *number == word)
+        .and_then(|position| u8::try_from(position + 1).ok())
+}
+
+/// Joins the words of a show's name, capitalizing all but the small words after the first
+fn title_case(words: &[&str]) -> String {
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let lowercase = word.to_lowercase();
+            if i > 0 && SMALL_WORDS.contains(&lowercase.as_str()) {
+                return lowercase;
+            }
+            let mut chars = lowercase.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(show: &str, season: u8, disc: Option<u8>) -> LabelInfo {
+        LabelInfo {
+            show: show.to_string(),
+            season,
+            disc,
+        }
+    }
+
+    #[test]
+    fn reads_real_world_labels() {
+        let labels = [
+            ("FRIENDS_S3_D2", info("Friends", 3, Some(2))),
+            ("FRIENDS_SEASON3_DISC2", info("Friends", 3, Some(2))),
+            ("THE_OFFICE_S02D1", info("The Office", 2, Some(1))),
+            ("BREAKING_BAD_S1_DISC_1", info("Breaking Bad", 1, Some(1))),
+            ("LOST_SEASON_1_DISC_4", info("Lost", 1, Some(4))),
+            ("SOPRANOS_SEASON2_D3", info("Sopranos", 2, Some(3))),
+            (
+                "GAME_OF_THRONES_S1_DISC1",
+                info("Game of Thrones", 1, Some(1)),
+            ),
+            ("24_SEASON_5_DISC_1", info("24", 5, Some(1))),
+            ("SIMPSONS_S10D2", info("Simpsons", 10, Some(2))),
+            ("THE_WIRE_SEASON_1", info("The Wire", 1, None)),
+            ("MAD_MEN_SEASON_ONE_DISC_TWO", info("Mad Men", 1, Some(2))),
+            ("Seinfeld Season 4 Disc 1", info("Seinfeld", 4, Some(1))),
+            ("X-FILES_S1_D1", info("X Files", 1, Some(1))),
+            ("SPECIALS_S0_D1", info("Specials", 0, Some(1))),
+            ("DEXTER_D2_S1", info("Dexter", 1, Some(2))),
+        ];
+
+        for (label, expected) in labels {
+            assert_eq!(parse_disc_label(label), Some(expected), "{label}");
+        }
+    }
+
+    #[test]
+    fn leaves_movie_and_generic_labels() {
+        for label in [
+            "HEAT",
+            "LOGICAL_VOLUME_ID",
+            "DVD_VIDEO",
+            "LOTR_FOTR_DISC_1",
+            "SIMPSONS_MOVIE",
+            "S1_D1",
+            "",
+        ] {
+            assert_eq!(parse_disc_label(label), None, "{label}");
+        }
+    }
+}
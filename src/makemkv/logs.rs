@@ -0,0 +1,481 @@
+//! # Rip Logs
+//!
+//! Keeps the tail of what `makemkvcon` printed during the last rip of each drive, so a
+//! failed rip can show it on Discord instead of only in the bot's own logs. Everything it
+//! printed is also written to a log file for each job, kept in the `logs` directory of the
+//! data directory for as long as `--rip-log-retention-days`.
+//!
+//! ## Overview
+//!
+//! - **`excerpt`**: Trims the output of `makemkvcon` down to the lines worth reading.
+//!
+//! - **`record`**: Keeps the excerpt of a rip's output for its drive, every rip records
+//!   its own once `makemkvcon` is done.
+//!
+//! - **`take`**: Hands out the kept excerpt of a drive, once.
+//!
+//! - **`JobLogs`**: The log files of the jobs, named after when the job was submitted and
+//!   its id, such as `1760706000-12.log`.
+//!
+//! - **`JOB_LOGS`**: A globally accessible, thread-safe instance of `JobLogs`.
+//!
+//! - **`write_job_log`** and **`job_log_name`**: Write a run of `makemkvcon` to the log of
+//!   the job ripping from a drive, and name that log for the `HISTORY`.
+//!
+//! ## Notes
+//!
+//! - Progress, drive and title info lines are left out, `MSG:` lines are shown as the text
+//!   `MakeMKV` formatted them into. Anything else, such as errors printed to stderr, is
+//!   kept as it is.
+//! - A title that is retried keeps the output of both attempts, up to `EXCERPT_LINES`.
+//! - The excerpt is forgotten as the next rip on the drive starts, so a rip that fails
+//!   before `MakeMKV` ran doesn't show the log of an earlier one.
+//! - Job ids start over when the bot restarts, the time in the name keeps their logs
+//!   apart. Looking a log up by job id finds the newest one.
+//! - Job logs are only written for rips that went through the queue, and failing to write
+//!   one is only logged.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    process::Output,
+    sync::{LazyLock, Mutex, OnceLock},
+};
+
+use super::{
+    errors::Result,
+    robot::{self, RobotLine},
+};
+use crate::history::history_core::now;
+use crate::queue::QUEUE;
+use crate::{debug, info, trace, warn};
+
+/// How many lines of a rip's output are kept, the end of it is what explains a failure
+pub const EXCERPT_LINES: usize = 100;
+
+/// The name of the job log directory within the data directory
+const JOB_LOGS_DIR: &str = "logs";
+/// The number of seconds in a day, used for the log retention period
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// A globally accessible instance of `JobLogs` for writing and finding the logs of jobs.
+pub static JOB_LOGS: LazyLock<Mutex<JobLogs>> = LazyLock::new(Mutex::default);
+
+/// The excerpts of the last rip on each drive, by drive number
+static RIP_LOGS: OnceLock<Mutex<HashMap<u8, Vec<String>>>> = OnceLock::new();
+
+fn logs() -> std::sync::MutexGuard<'static, HashMap<u8, Vec<String>>> {
+    RIP_LOGS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// The lines of `output` worth reading when a rip failed, at most the last `EXCERPT_LINES`
+#[must_use]
+pub fn excerpt(output: &str) -> Vec<String> {
+    let lines: Vec<String> = output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match robot::parse_line(line) {
+            Some(RobotLine::Message(message)) => Some(message.text),
+            Some(_) => None,
+            None => Some(line.trim_end().to_string()),
+        })
+        .collect();
+
+    let skipped = lines.len().saturating_sub(EXCERPT_LINES);
+    lines.into_iter().skip(skipped).collect()
+}
+
+/// Keeps the excerpt of what `makemkvcon` printed while ripping from a drive, after that
+/// of any earlier attempt at the same rip
+pub fn record(drive_number: u8, stdout: &[u8], stderr: &[u8]) {
+    let mut lines = excerpt(&String::from_utf8_lossy(stdout));
+    lines.extend(excerpt(&String::from_utf8_lossy(stderr)));
+
+    let mut logs = logs();
+    let log = logs.entry(drive_number).or_default();
+    log.extend(lines);
+    let skipped = log.len().saturating_sub(EXCERPT_LINES);
+    log.drain(..skipped);
+    trace!(
+        "Kept {} line(s) of drive {}'s rip log",
+        log.len(),
+        drive_number
+    );
+}
+
+/// Forgets the excerpt of a drive's last rip, as the next one starts
+pub fn forget(drive_number: u8) {
+    logs().remove(&drive_number);
+}
+
+/// Takes the excerpt of a drive's last rip, `None` if `makemkvcon` didn't get to print
+/// anything worth reading
+#[must_use]
+pub fn take(drive_number: u8) -> Option<String> {
+    logs()
+        .remove(&drive_number)
+        .filter(|log| !log.is_empty())
+        .map(|log| log.join("\n"))
+}
+
+/// A job log, as listed by `JobLogs::recent`.
+///
+/// # Fields
+///
+/// * `name` - The name of the log, its file name without `.log`.
+/// * `job_id` - The id of the job, while the bot was running it.
+/// * `label` - What the job was, as shown in the queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobLog {
+    pub name: String,
+    pub job_id: u64,
+    pub label: String,
+}
+
+/// The log files of the jobs.
+///
+/// # Fields
+///
+/// * `dir` - The directory the logs are written to, empty until `init` has been called.
+/// * `retention_days` - How many days logs are kept for, none are written at 0.
+#[derive(Debug, Default)]
+pub struct JobLogs {
+    dir: PathBuf,
+    retention_days: u64,
+}
+
+impl JobLogs {
+    /// Sets up the log directory within the data directory, and removes the logs that are
+    /// older than `retention_days`. Logs can't be written if the directory can't be
+    /// created, which is only logged.
+    pub fn init(&mut self, data_dir: &Path, retention_days: u64) {
+        let dir = data_dir.join(JOB_LOGS_DIR);
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            warn!("Failed to create the job log directory: {}", e);
+            return;
+        }
+
+        self.dir = dir;
+        self.retention_days = retention_days;
+
+        match self.purge() {
+            Ok(0) => (),
+            Ok(removed) => debug!("Removed {} old job log(s)", removed),
+            Err(e) => warn!("Failed to remove old job logs: {}", e),
+        }
+
+        info!("Job logs initialized successfully!");
+    }
+
+    /// Whether logs are written at all
+    fn enabled(&self) -> bool {
+        !self.dir.as_os_str().is_empty() && self.retention_days > 0
+    }
+
+    /// Removes the logs older than the retention period, returning how many were removed.
+    /// Every log is removed with a retention of 0 days.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `MakeMkvError` if the log directory can't be read or a log can't be removed.
+    pub fn purge(&self) -> Result<usize> {
+        if self.dir.as_os_str().is_empty() {
+            return Ok(0);
+        }
+
+        let cutoff = now().saturating_sub(self.retention_days * SECONDS_PER_DAY);
+        let mut removed = 0;
+
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+
+            // Anything that isn't named like a log is left alone
+            let Some((submitted, _)) = log_name(&path).as_deref().and_then(parse_name) else {
+                continue;
+            };
+
+            if self.retention_days > 0 && submitted > cutoff {
+                continue;
+            }
+
+            std::fs::remove_file(&path)?;
+            trace!("Removed job log {}", path.display());
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+
+    /// Appends a run of `makemkvcon` with `args` to the log of a job, starting the log with
+    /// the job's `label` if it is new. Nothing is written if logs are off.
+    pub fn write(
+        &self,
+        job_id: u64,
+        submitted: u64,
+        label: &str,
+        args: &[String],
+        output: &Result<Output>,
+    ) {
+        if !self.enabled() {
+            return;
+        }
+
+        let path = self.dir.join(format!("{submitted}-{job_id}.log"));
+        let is_new = !path.exists();
+
+        let mut contents = String::new();
+        if is_new {
+            contents.push_str(label);
+            contents.push('\n');
+        }
+        let _ = write!(contents, "\n== makemkvcon {} ==\n", args.join(" "));
+        match output {
+            Ok(output) => {
+                contents.push_str(&String::from_utf8_lossy(&output.stdout));
+                if !output.stderr.is_empty() {
+                    contents.push_str("\n== stderr ==\n");
+                    contents.push_str(&String::from_utf8_lossy(&output.stderr));
+                }
+                let _ = write!(contents, "\n== exited with {} ==\n", output.status);
+            }
+            Err(e) => {
+                let _ = writeln!(contents, "Failed to run: {e}");
+            }
+        }
+
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()));
+
+        match written {
+            Ok(()) => trace!("Wrote the output of job {} to {}", job_id, path.display()),
+            Err(e) => warn!("Failed to write the log of job {}: {}", job_id, e),
+        }
+    }
+
+    /// The name of the log of a job, if it has one
+    #[must_use]
+    pub fn name_of(&self, job_id: u64, submitted: u64) -> Option<String> {
+        let name = format!("{submitted}-{job_id}");
+        self.path(&name).map(|_| name)
+    }
+
+    /// The path of a log by its name, or the newest log of a job id. `None` if there is no
+    /// such log.
+    #[must_use]
+    pub fn find(&self, query: &str) -> Option<PathBuf> {
+        let query = query.trim();
+        if let Some(path) = self.path(query) {
+            return Some(path);
+        }
+
+        let job_id = query.trim_start_matches('#').parse::<u64>().ok()?;
+        self.names()
+            .into_iter()
+            .filter(|&(_, id, _)| id == job_id)
+            .max_by_key(|&(submitted, _, _)| submitted)
+            .map(|(_, _, name)| self.dir.join(format!("{name}.log")))
+    }
+
+    /// The newest `count` logs, newest first
+    #[must_use]
+    pub fn recent(&self, count: usize) -> Vec<JobLog> {
+        let mut names = self.names();
+        names.sort_unstable_by(|a, b| b.cmp(a));
+
+        names
+            .into_iter()
+            .take(count)
+            .map(|(_, job_id, name)| {
+                // The first line of a log is the label of its job
+                let label = std::fs::File::open(self.dir.join(format!("{name}.log")))
+                    .ok()
+                    .and_then(|file| std::io::BufReader::new(file).lines().next()?.ok())
+                    .unwrap_or_default();
+                JobLog {
+                    name,
+                    job_id,
+                    label,
+                }
+            })
+            .collect()
+    }
+
+    /// The path of the log with a name, if it exists. Only names of logs are accepted, so
+    /// nothing outside of the log directory can be read.
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        parse_name(name)?;
+        let path = self.dir.join(format!("{name}.log"));
+        (!self.dir.as_os_str().is_empty() && path.is_file()).then_some(path)
+    }
+
+    /// The submission time, job id and name of every log
+    fn names(&self) -> Vec<(u64, u64, String)> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| log_name(&entry.ok()?.path()))
+            .filter_map(|name| {
+                let (submitted, job_id) = parse_name(&name)?;
+                Some((submitted, job_id, name))
+            })
+            .collect()
+    }
+}
+
+/// The name of a log file, without `.log`
+fn log_name(path: &Path) -> Option<String> {
+    if path.extension()? != "log" {
+        return None;
+    }
+    Some(path.file_stem()?.to_str()?.to_string())
+}
+
+/// Reads the submission time and job id out of the name of a log, e.g. `1760706000-12`
+fn parse_name(name: &str) -> Option<(u64, u64)> {
+    let (submitted, job_id) = name.split_once('-')?;
+    Some((submitted.parse().ok()?, job_id.parse().ok()?))
+}
+
+/// Appends a run of `makemkvcon` to the log of the job ripping from a drive
+pub async fn write_job_log(drive_number: u8, args: &[String], output: &Result<Output>) {
+    let Some(job) = QUEUE.lock().await.running_rip(drive_number).cloned() else {
+        trace!(
+            "No job is ripping from drive {}, not logging it",
+            drive_number
+        );
+        return;
+    };
+
+    JOB_LOGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .write(job.id, job.submitted, &job.label, args, output);
+}
+
+/// The name of the log of the job ripping from a drive, for the `HISTORY` to refer to
+pub async fn job_log_name(drive_number: u8) -> Option<String> {
+    let job = QUEUE.lock().await.running_rip(drive_number).cloned()?;
+
+    JOB_LOGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .name_of(job.id, job.submitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excerpts_keep_the_messages() {
+        let output = "MSG:1005,0,1,\"MakeMKV v1.17.7 linux(x64-release) started\",\"%1 started\",\"MakeMKV v1.17.7 linux(x64-release)\"\n\
+            PRGT:5018,0,\"Saving to MKV file\"\n\
+            PRGV:0,10,65536\n\
+            \n\
+            MSG:5037,516,2,\"Copy complete. 0 titles saved, 1 failed.\",\"Copy complete. %1 titles saved, %2 failed.\",\"0\",\"1\"\n\
+            Segmentation fault";
+
+        assert_eq!(
+            excerpt(output),
+            vec![
+                "MakeMKV v1.17.7 linux(x64-release) started",
+                "Copy complete. 0 titles saved, 1 failed.",
+                "Segmentation fault",
+            ]
+        );
+    }
+
+    #[test]
+    fn excerpts_keep_the_end() {
+        let output = (0..EXCERPT_LINES + 20)
+            .map(|line| format!("line {line}"))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let lines = excerpt(&output);
+        assert_eq!(lines.len(), EXCERPT_LINES);
+        assert_eq!(lines[0], "line 20");
+    }
+
+    #[test]
+    fn retries_add_to_the_log_until_taken() {
+        record(7, b"first attempt", b"");
+        record(7, b"second attempt", b"drive error");
+
+        assert_eq!(
+            take(7).as_deref(),
+            Some("first attempt\nsecond attempt\ndrive error")
+        );
+        assert_eq!(take(7), None);
+
+        record(7, b"PRGV:0,10,65536", b"");
+        assert_eq!(take(7), None);
+    }
+
+    fn output(stdout: &str) -> Output {
+        Output {
+            status: std::process::ExitStatus::default(),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn job_logs_are_found_by_name_or_newest_job() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut logs = JobLogs::default();
+        logs.init(dir.path(), 30);
+
+        let args = ["mkv".to_string()];
+        let now = now();
+        logs.write(3, now - 60, "Heat (Disc 0)", &args, &Ok(output("first")));
+        logs.write(3, now, "Ronin (Disc 0)", &args, &Ok(output("second")));
+        logs.write(3, now, "Ronin (Disc 0)", &args, &Ok(output("retry")));
+
+        let old = logs.name_of(3, now - 60).unwrap();
+        assert_eq!(
+            logs.find(&old),
+            Some(dir.path().join(format!("logs/{old}.log")))
+        );
+        let newest = std::fs::read_to_string(logs.find("3").unwrap()).unwrap();
+        assert!(newest.starts_with("Ronin (Disc 0)\n"));
+        assert!(newest.contains("second") && newest.contains("retry"));
+        assert_eq!(logs.recent(1)[0].name, logs.name_of(3, now).unwrap());
+
+        // Only names of logs are looked up
+        assert_eq!(logs.find("../history"), None);
+        assert_eq!(logs.find("4"), None);
+    }
+
+    #[test]
+    fn old_job_logs_are_purged() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut logs = JobLogs::default();
+        logs.init(dir.path(), 1);
+
+        let args = ["mkv".to_string()];
+        logs.write(
+            1,
+            now() - 2 * SECONDS_PER_DAY,
+            "Old",
+            &args,
+            &Ok(output("")),
+        );
+        logs.write(2, now(), "New", &args, &Ok(output("")));
+        std::fs::write(dir.path().join("logs/notes.txt"), "kept").unwrap();
+
+        assert_eq!(logs.purge().unwrap(), 1);
+        assert_eq!(logs.recent(10).len(), 1);
+        assert!(dir.path().join("logs/notes.txt").exists());
+    }
+}
@@ -16,15 +16,23 @@
 //!   including metadata such as season and episode numbers for TV shows.
 //!
 //! - **`MakeMkv`**: A struct that manages the interaction with MakeMKV, including drive locking,
-//!   output directory management, and the execution of ripping commands.
+//!   output directory management, and the execution of ripping commands. It is created once
+//!   at startup and shared as an `Arc<MakeMkv>`, rips on different drives run side by side.
 //!
-//! - **`MAKE_MKV`**: A globally accessible, thread-safe instance of `MakeMkv` for managing
-//!   ripping operations.
+//! - **Rip Events**: Rips send a `RipEvent` as they start, progress, complete or fail, see
+//!   the `events` module to subscribe to them.
+//!
+//! - **Rip ETAs**: Running rips keep an estimate of how long they have left, see the `eta`
+//!   module.
+//!
+//! - **Rip Logs**: The tail of what `MakeMKV` printed during the last rip of each drive is
+//!   kept, so a failed rip can show it, see the `logs` module.
 //!
 //! ## Features
 //!
 //! - **Thread-Safe Drive Management**: Ensures that optical drives are locked during ripping
-//!   operations to prevent concurrent access.
+//!   operations to prevent concurrent access. Each drive has a lock of its own, which is
+//!   released when the rip finishes or is dropped.
 //!
 //! - **Temporary Directory Handling**: Uses temporary directories for intermediate ripping
 //!   output, ensuring clean-up after the process completes.
@@ -32,25 +40,29 @@
 //! - **Media Organization**: Automatically organizes ripped media into appropriate directories
 //!   based on the type of rip (movie or TV show).
 //!
+//! - **Named Libraries**: Rips can be saved to another library than the output directory,
+//!   such as a separate mount for kids' movies, picked for each rip.
+//!
 //! - **Error Handling**: Provides detailed error types to handle various failure scenarios,
 //!   such as missing MakeMKV installation, drive in use, or failed ripping operations.
 //!
 //! ## Usage
 //!
-//! To use this module, initialize the `MakeMkv` instance, configure the output directory,
+//! To use this module, create and initialize a `MakeMkv` instance with the output directory,
 //! and execute ripping operations using the `Rip` struct. The module is designed to work
 //! asynchronously and integrates with the `tokio` runtime for concurrency.
 //!
 //! ## Example
 //!
-//! ```rust
-//! use cord_ripper_v1::makemkv::makemkv_core::{Rip, RipType, MAKE_MKV};
+//! ```no_run
+//! use cord_ripper::makemkv::makemkv_core::{MakeMkv, Rip, RipType};
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // Initialize MakeMKV
-//!     let mut makemkv = MAKE_MKV.lock().await;
+//!     let mut makemkv = MakeMkv::new("/path/to/output/directory");
 //!     makemkv.init("/path/to/output/directory").await?;
+//!     let makemkv = std::sync::Arc::new(makemkv);
 //!
 //!     // Create a Rip instance for a movie
 //!     let rip = Rip {
@@ -58,10 +70,17 @@
 //!         drive_number: 1,
 //!         rip_type: RipType::Movie,
 //!         title_id: 1,
+//!         year: Some(2019),
+//!         chapters_per_episode: None,
+//!         library: None,
+//!         episode_title: None,
+//!         note: None,
+//!         extra: None,
+//!         base_view: false,
 //!     };
 //!
 //!     // Execute the ripping process
-//!     rip.execute().await?;
+//!     rip.execute(&makemkv).await?;
 //!
 //!     Ok(())
 //! }
@@ -72,30 +91,140 @@
 //! - Ensure that MakeMKV is installed and accessible on the system before using this module.
 //! - The output directory must exist and be writable.
 //! - This module is designed for asynchronous execution and requires a `tokio` runtime.
-use core::panic;
-use std::{collections::HashSet, path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::Output,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 // use tempdir::TempDir;
+use serde::{Deserialize, Serialize};
 use tempfile::TempDir;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex, OwnedMutexGuard};
 
-use crate::{debug, error, info, trace, warn};
+use crate::history::{RipSource, HISTORY};
+use crate::library::LIBRARY;
+use crate::probe::{errors::ProbeError, validate_file};
+use crate::queue::worker::current_job;
+use crate::queue::{JobKind, JobTicket, Priority, QUEUE};
+use crate::{debug, error, info, tr, trace, warn};
 
 use super::{
+    drive_map::{drive_location, makemkv_source},
+    episodes::EpisodePattern,
     errors::{MakeMkvError, Result},
-    makemkv_helpers::{check_makemkv_output, makemkv_exists, Command as MakeMkvCommands},
+    eta,
+    events::{emit, RipEvent, RipStage},
+    logs,
+    makemkv_helpers::{
+        check_makemkv_output, drive_connected, drive_disconnected, keep_base_view, makemkv_version,
+        makemkvcon_command, parse_length, parse_size, remove_stale_temp_dirs, set_read_speed,
+        split_by_chapters, tag_title, RETRY_READ_SPEED, SCANNED_TITLES, TEMP_DIR_PREFIX,
+    },
+    messages::{hash_check_failures, mentions_device_gone},
+    naming::{part_path, stays_inside, NamingTemplate},
+    rippers::{ripper, rippers},
+    subtitles::{handle_forced_subtitles, ForcedSubtitles, SubtitleStream},
+    transfer::{move_verified_with_progress, same_mount},
 };
 
-lazy_static::lazy_static! {
-    /// A globally accessible instance of `MakeMkv` for managing ripping operations.
-    pub static ref MAKE_MKV: Arc<Mutex<MakeMkv>> = Arc::new(Mutex::new(MakeMkv::default()));
+/// How long a temporary rip directory has to be left untouched before it is considered
+/// abandoned and removed by the cleanup
+const STALE_TEMP_DIR_AGE: Duration = Duration::from_hours(1);
+
+/// The directory in a rip's temporary directory a retry is ripped into
+const RETRY_DIR: &str = "retry";
+
+/// The name the output directory goes by when picking the library a rip is saved to
+pub const DEFAULT_LIBRARY: &str = "main";
+
+/// A rip that saved less than the size the disc scan reported divided by this is
+/// suspicious, `MakeMKV`'s sizes are close enough that only a truncated rip falls this short
+const SUSPICIOUS_SIZE_DIVISOR: u64 = 2;
+
+/// What a rip saved.
+///
+/// # Fields
+///
+/// * `files` - The paths the ripped files were saved to.
+/// * `recovery` - How a retry after hash check failures went, `None` if no segment failed.
+/// * `size_warning` - How far short of the scanned size the files fell, `None` unless far
+///   enough for the rip to be suspicious.
+#[derive(Debug, Clone)]
+pub struct RipOutcome {
+    pub files: Vec<PathBuf>,
+    pub recovery: Option<HashRecovery>,
+    pub size_warning: Option<SizeWarning>,
 }
 
-#[derive(Debug)]
+/// A rip that saved far less than the disc scan said its title holds, which happens when
+/// `MakeMKV` stops early without reporting an error.
+///
+/// # Fields
+///
+/// * `expected_bytes` - The size of the title found by the disc scan.
+/// * `saved_bytes` - The size of the files that were saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeWarning {
+    pub expected_bytes: u64,
+    pub saved_bytes: u64,
+}
+
+impl SizeWarning {
+    /// Compares what a rip saved with the size the disc scan found, if there was one,
+    /// returning a warning when it fell too far short
+    #[must_use]
+    pub fn check(expected_bytes: Option<u64>, saved_bytes: u64) -> Option<SizeWarning> {
+        let expected_bytes = expected_bytes.filter(|bytes| *bytes > 0)?;
+        (saved_bytes < expected_bytes / SUSPICIOUS_SIZE_DIVISOR).then_some(SizeWarning {
+            expected_bytes,
+            saved_bytes,
+        })
+    }
+}
+
+/// How many of the segments that failed hash checks a slower retry read cleanly.
+///
+/// # Fields
+///
+/// * `failed_segments` - The segment files that failed hash checks the first time.
+/// * `recovered_segments` - The ones that passed them in the retry, 0 if the first attempt
+///   was kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HashRecovery {
+    pub failed_segments: usize,
+    pub recovered_segments: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rip {
     pub title: String,
     pub drive_number: u8,
     pub rip_type: RipType,
     pub title_id: u16,
+    /// The release year of a movie, used by the naming template
+    pub year: Option<u16>,
+    /// Splits a show title into an episode every this many chapters, for discs that store
+    /// a whole season as one long title
+    pub chapters_per_episode: Option<u16>,
+    /// The named library to save to, `None` for the output directory
+    pub library: Option<String>,
+    /// The name of a show episode, e.g. `The One with the Racecar Bed`, used by the naming
+    /// template when the episode was picked from Sonarr's list
+    pub episode_title: Option<String>,
+    /// A note about the rip, such as `Extended cut`, kept in the history and shown on its
+    /// summary
+    #[serde(default)]
+    pub note: Option<String>,
+    /// The name of an extra of a movie, e.g. `Making Of`, saved in the movie's extras
+    /// folder rather than as the movie. `None` for the movie itself
+    #[serde(default)]
+    pub extra: Option<String>,
+    /// Keeps only the 2D base view of a 3D title, dropping the 3D video track many players
+    /// can't play
+    #[serde(default)]
+    pub base_view: bool,
 }
 
 /// Represents a ripping operation, which can either be for a movie or a specific episode of a show.
@@ -105,22 +234,41 @@ pub struct Rip {
 ///
 /// # Methods
 ///
-/// - `execute`: Executes the ripping process using the `MAKE_MKV` instance. This method
+/// - `execute`: Executes the ripping process using the given `MakeMkv`. This method
 ///   is asynchronous and returns a `Result` indicating the success or failure of the operation.
 ///
 /// - `episode`: Returns the episode number if the rip is for a specific episode of a show.
 ///   If the rip is for a movie, this method returns `None`.
 ///
+/// - `with_episode_offset`: Returns a copy of a show rip numbered `offset` episodes later,
+///   or earlier, used for the episodes a title is split into and the titles after one that
+///   was skipped.
+///
+/// - `queued`: Sends a `RipEvent::Queued` for the rip, once it has been added to the queue.
+///
 /// # Example
 ///
-/// ```rust
+/// ```no_run
+/// use cord_ripper::makemkv::{MakeMkv, Rip, RipType};
+///
+/// # async fn example(makemkv: &MakeMkv) -> Result<(), cord_ripper::makemkv::errors::MakeMkvError> {
 /// let rip = Rip {
-///     rip_type: RipType::Show { season: 1, episode: 5 },
+///     rip_type: RipType::Show { season: 1, episode: 5, absolute_episode: None },
 ///     // other fields...
+/// #   title: "My Show".to_string(),
+/// #   drive_number: 1,
+/// #   title_id: 1,
+/// #   year: None,
+/// #   chapters_per_episode: None,
+/// #   library: None,
+/// #   episode_title: None,
+/// #   note: None,
+/// #   extra: None,
+/// #   base_view: false,
 /// };
 ///
 /// // Execute the rip
-/// rip.execute().await?;
+/// rip.execute(makemkv).await?;
 ///
 /// // Get the episode number
 /// if let Some(episode) = rip.episode() {
@@ -128,37 +276,238 @@ pub struct Rip {
 /// } else {
 ///     println!("Ripping a movie");
 /// }
+/// # Ok(())
+/// # }
 /// ```
 ///
-/// This struct is designed to work with the `MAKE_MKV` instance, which handles the
-/// underlying ripping logic.
+/// This struct is designed to work with a `MakeMkv` instance, which handles the
+/// underlying ripping logic. A rip is cancelled by dropping the future of `execute`, which
+/// stops `MakeMKV` and unlocks the drive.
 impl Rip {
-    /// Ececutes the ripping process using the `MAKE_MKV` instance.
-    pub async fn execute(&self) -> Result<()> {
-        MAKE_MKV.lock().await.run_rip(self).await?;
-        Ok(())
+    /// Ececutes the ripping process using `makemkv`, recording the saved files and the
+    /// drive's statistics in the `HISTORY` once the rip completes.
+    /// Returns the files that were saved, and the segments a retry recovered.
+    pub async fn execute(&self, makemkv: &MakeMkv) -> Result<RipOutcome> {
+        emit(RipEvent::Started {
+            rip: self.clone(),
+            host: drive_location(self.drive_number).0,
+        });
+
+        // Looked up before ripping, the disc could be swapped and scanned again by the end
+        let (source, expected_bytes, subtitles) = SCANNED_TITLES
+            .lock()
+            .await
+            .get(&self.drive_number)
+            .and_then(|disc_info| {
+                disc_info
+                    .titles
+                    .iter()
+                    .find(|title| title.title_id == self.title_id)
+                    .map(|title| {
+                        let source = RipSource {
+                            disc_name: disc_info.disc_name.clone(),
+                            source_file: title.source_file.clone(),
+                        };
+                        (
+                            Some(source),
+                            parse_size(&title.size),
+                            title.subtitles.clone(),
+                        )
+                    })
+            })
+            .unwrap_or_default();
+
+        let start_time = Instant::now();
+        let result = makemkv.run_rip(self).await;
+        let seconds = start_time.elapsed().as_secs_f64();
+        let log = logs::job_log_name(self.drive_number).await;
+
+        // Finished before the files are added to the library, so it sees them as they stay
+        if let Ok(outcome) = &result {
+            if self.needs_finishing(makemkv) {
+                let ticket = self.transcode_ticket().await;
+                if let Err(e) = ticket.wait_turn().await {
+                    warn!("Finishing {} without a transcode slot: {}", self.title, e);
+                }
+                self.finish_files(makemkv, &outcome.files, &subtitles).await;
+            }
+        }
+
+        let mut history = HISTORY.lock().await;
+
+        let mut outcome = match result {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                emit(RipEvent::Failed {
+                    rip: self.clone(),
+                    error: e.user_message(),
+                });
+                // Tracked per drive so a drive that is starting to fail stands out, only
+                // errors reading the disc count against it
+                if e.is_drive_failure() {
+                    if let Err(e) = history.record_drive_failure(self.drive_number) {
+                        warn!("Failed to record drive failure in history: {}", e);
+                    }
+                }
+                return Err(e);
+            }
+        };
+        let files = &outcome.files;
+
+        let bytes = files
+            .iter()
+            .filter_map(|file| std::fs::metadata(file).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        // MakeMKV can stop short of the end of a title and still report success
+        outcome.size_warning = SizeWarning::check(expected_bytes, bytes);
+        if let Some(warning) = outcome.size_warning {
+            warn!(
+                "{} saved {} bytes, but the disc scan found {} bytes, the rip may be truncated",
+                self.title, warning.saved_bytes, warning.expected_bytes
+            );
+        }
+
+        let mut library = LIBRARY.lock().await;
+        for file in files {
+            library.add(file);
+        }
+        drop(library);
+
+        // A rip that made it to disk shouldn't be reported as failed just because
+        // the history couldn't be written
+        if let Err(e) = history.record_drive_success(self.drive_number, bytes, seconds) {
+            warn!("Failed to record drive stats in history: {}", e);
+        }
+        if outcome.recovery.is_some() {
+            if let Err(e) = history.record_drive_retries(self.drive_number, 1) {
+                warn!("Failed to record drive retries in history: {}", e);
+            }
+        }
+
+        let suspicious = outcome.size_warning.is_some();
+        if let Err(e) = history.record(self, files.clone(), source, log, suspicious) {
+            warn!("Failed to record rip in history: {}", e);
+        }
+        drop(history);
+
+        emit(RipEvent::Completed {
+            rip: self.clone(),
+            files: files.clone(),
+            seconds,
+        });
+
+        // Written before importing, so Sonarr and Radarr can import them as extra files
+        crate::metadata::write_sidecars(self, files, makemkv).await;
+
+        // Sonarr or Radarr take it from here, if they are set up to
+        crate::arr::import_rip(self, files).await;
+
+        Ok(outcome)
+    }
+
+    /// Whether `finish_files` has anything to do to the saved files
+    fn needs_finishing(&self, makemkv: &MakeMkv) -> bool {
+        (self.note.is_some() && makemkv.tag_notes)
+            || makemkv.forced_subtitles != ForcedSubtitles::Off
+    }
+
+    /// Submits the finishing of the saved files to the queue as a transcode, so only so
+    /// many remux at once, see `--max-concurrent-transcodes`. It takes the priority of the
+    /// rip's own job.
+    async fn transcode_ticket(&self) -> JobTicket {
+        let priority = match current_job() {
+            Some(id) => QUEUE
+                .lock()
+                .await
+                .jobs()
+                .iter()
+                .find(|job| job.id == id)
+                .map_or(Priority::default(), |job| job.priority),
+            None => Priority::default(),
+        };
+
+        JobTicket::submit(
+            &tr!("Finishing {title}", title = self.title),
+            JobKind::Transcode,
+            priority,
+        )
+        .await
+    }
+
+    /// Writes the note into the saved files and handles their forced subtitles, as set up.
+    /// Failing is only logged, the note is still kept in the history and the subtitles are
+    /// all still in the files.
+    async fn finish_files(
+        &self,
+        makemkv: &MakeMkv,
+        files: &[PathBuf],
+        subtitles: &[SubtitleStream],
+    ) {
+        for file in files {
+            if let (Some(note), true) = (&self.note, makemkv.tag_notes) {
+                if let Err(e) = tag_title(file, note).await {
+                    warn!("Failed to tag {} with its note: {}", file.display(), e);
+                }
+            }
+            if let Err(e) = handle_forced_subtitles(file, subtitles, makemkv.forced_subtitles).await
+            {
+                warn!(
+                    "Failed to handle the forced subtitles of {}: {}",
+                    file.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Announces that the rip was added to the queue, to whoever subscribed to rip events
+    pub fn queued(&self) {
+        emit(RipEvent::Queued { rip: self.clone() });
     }
 
     /// Returns the episode number if the rip is for a specific episode of a show.
     pub fn episode(&self) -> Option<u8> {
         match self.rip_type {
-            RipType::Show { season: _, episode } => Some(episode),
+            RipType::Show { episode, .. } => Some(episode),
             RipType::Movie => None,
         }
     }
 
-    /// Cancels the ripping process and unlocks the drive.
-    pub async fn cancel(&self) -> Result<()> {
-        MAKE_MKV
-            .lock()
-            .await
-            .unlock_drive(self.drive_number)
-            .await?;
-        Ok(())
+    /// Returns a copy of the rip numbered `offset` episodes later, or earlier when it is
+    /// negative, or `None` if it is a movie or the episode numbers would be out of range.
+    #[must_use]
+    pub fn with_episode_offset(&self, offset: isize) -> Option<Rip> {
+        let RipType::Show {
+            season,
+            episode,
+            absolute_episode,
+        } = self.rip_type
+        else {
+            return None;
+        };
+
+        let episode = episode.checked_add_signed(i8::try_from(offset).ok()?)?;
+        let absolute_episode = match absolute_episode {
+            Some(absolute) => Some(absolute.checked_add_signed(i16::try_from(offset).ok()?)?),
+            None => None,
+        };
+
+        // The name was of the episode the title starts with
+        Some(Rip {
+            rip_type: RipType::Show {
+                season,
+                episode,
+                absolute_episode,
+            },
+            episode_title: None,
+            ..self.clone()
+        })
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RipType {
     Movie,
     /// Represents a TV show with associated season and episode information.
@@ -172,15 +521,22 @@ pub enum RipType {
     ///
     /// - `season`: The season number of the show (as an unsigned 8-bit integer).
     /// - `episode`: The episode number within the season (as an unsigned 8-bit integer).
+    /// - `absolute_episode`: The episode number across every season, used by anime style
+    ///   libraries. Season 0 holds the specials of a show.
     ///
     /// # Example
     ///
     /// ```rust
-    /// let show = Show {
+    /// use cord_ripper::makemkv::RipType;
+    ///
+    /// let show = RipType::Show {
     ///     season: 1,
     ///     episode: 5,
+    ///     absolute_episode: None,
     /// };
-    /// println!("Season: {}, Episode: {}", show.season, show.episode);
+    /// if let RipType::Show { season, episode, .. } = show {
+    ///     println!("Season: {}, Episode: {}", season, episode);
+    /// }
     /// ```
     ///
     /// This will output:
@@ -190,21 +546,25 @@ pub enum RipType {
     Show {
         season: u8,
         episode: u8,
+        absolute_episode: Option<u16>,
     },
 }
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct MakeMkv {
     pub output_dir: PathBuf,
-    pub drives: Arc<Mutex<HashSet<u8>>>,
-}
-
-impl Default for MakeMkv {
-    fn default() -> Self {
-        MakeMkv {
-            output_dir: PathBuf::new(),
-            drives: Arc::new(Mutex::new(HashSet::new())),
-        }
-    }
+    pub libraries: Vec<(String, PathBuf)>,
+    drives: Mutex<HashMap<u8, Arc<Mutex<()>>>>,
+    pub naming: NamingTemplate,
+    pub episode_patterns: Vec<EpisodePattern>,
+    temp_dirs: Mutex<HashSet<PathBuf>>,
+    pub eject_after_rip: bool,
+    pub nfo: bool,
+    pub artwork: bool,
+    pub tag_notes: bool,
+    pub forced_subtitles: ForcedSubtitles,
+    pub library_3d: Option<String>,
+    pub version: Option<String>,
 }
 
 /// The `MakeMkv` struct provides functionality for interacting with the MakeMKV software
@@ -213,7 +573,25 @@ impl Default for MakeMkv {
 ///
 /// # Fields
 /// - `output_dir`: A `PathBuf` representing the directory where ripped files will be saved.
-/// - `drives`: A thread-safe `HashSet` wrapped in an `Arc<Mutex<>>` to track locked drives.
+/// - `libraries`: Other named directories rips can be saved to instead, as `(name, root)`.
+///   The output directory is always available as `DEFAULT_LIBRARY`.
+/// - `drives`: A lock for each drive, held for as long as the drive is being ripped from.
+/// - `naming`: The `NamingTemplate` used to build the destination paths of rips.
+/// - `episode_patterns`: The filename patterns used to find the episodes already ripped.
+/// - `temp_dirs`: The temporary directories of rips that are currently running, which the
+///   cleanup must not remove.
+/// - `eject_after_rip`: Whether drives are ejected once a rip job finishes, unless changed
+///   for a single rip.
+/// - `nfo`: Whether Kodi `.nfo` files are written next to finished rips.
+/// - `artwork`: Whether the poster and background art of finished rips are downloaded into
+///   their folders.
+/// - `tag_notes`: Whether the note given to a rip is written into the title tag of its
+///   files.
+/// - `forced_subtitles`: What is done with the forced subtitles of a title once it is
+///   ripped.
+/// - `library_3d`: The library 3D titles kept in 3D are saved to by default, one of
+///   `libraries`.
+/// - `version`: The version of `MakeMKV` on this machine, `None` when only agents rip.
 ///
 /// # Methods
 ///
@@ -243,17 +621,34 @@ impl Default for MakeMkv {
 /// - `drive_number`: A `u8` representing the drive number to lock.
 ///
 /// ### Returns
-/// - `Ok(())` if the drive is successfully locked.
+/// - `Ok(guard)` if the drive is successfully locked, it stays locked until the guard is
+///   dropped.
 /// - `Err(MakeMkvError)` if the drive is already in use.
 ///
-/// ## `unlock_drive`
-/// Unlocks a specific drive after the ripping process is complete.
+/// ## `library_root`
+/// Returns the directory of a named library, the output directory for `None` or a name that
+/// isn't configured.
+///
+/// ## `library_of`
+/// Returns the name of the library a file was saved to, `None` for the output directory.
+///
+/// ## `destination_path`
+/// Builds the full path a rip will be saved to, from its library and the naming template.
 ///
 /// ### Parameters
-/// - `drive_number`: A `u8` representing the drive number to unlock.
+/// - `rip`: A reference to the `Rip` to build the path for.
 ///
 /// ### Returns
-/// - `Ok(())` if the drive is successfully unlocked.
+/// - The path of the `.mkv` file the rip will be saved as.
+///
+/// ## `cleanup_temp_dirs`
+/// Removes temporary directories left behind by failed or crashed rips.
+///
+/// ### Returns
+/// - The number of bytes reclaimed.
+///
+/// ## `active_temp_dirs`
+/// Returns the temporary directories of the rips that are currently running.
 ///
 /// ## `run_rip`
 /// Executes the ripping process for a specific drive and title, saving the output to the appropriate directory.
@@ -262,16 +657,20 @@ impl Default for MakeMkv {
 /// - `rip_details`: A reference to a `Rip` struct containing details about the drive, title, and rip type.
 ///
 /// ### Returns
-/// - `Ok(())` if the ripping process is successful.
+/// - `Ok(RipOutcome)` with the paths of the saved files if the ripping process is successful,
+///   and how many segments a retry recovered if any failed hash checks.
 /// - `Err(MakeMkvError)` if any error occurs during the ripping process.
 ///
 /// ### Process
 /// 1. Locks the specified drive.
 /// 2. Creates a temporary output directory.
-/// 3. Executes the MakeMKV command to rip the media.
+/// 3. Executes the `MakeMKV` command to rip the media, retrying once at a slower speed if
+///    segments fail hash checks.
 /// 4. Validates the output and calculates ripping statistics.
 /// 5. Moves the ripped file to the appropriate destination directory based on the rip type (movie or show).
-/// 6. Unlocks the drive and cleans up temporary resources.
+///    A copy to another mount hands the job's drive back to the queue while it runs.
+/// 6. Unlocks the drive, by dropping its lock, and cleans up temporary resources.
+/// 7. If the rip failed, removes any stale temporary directories left by earlier rips.
 ///
 /// ### Errors
 /// - Fails if MakeMKV command execution fails.
@@ -284,17 +683,34 @@ impl Default for MakeMkv {
 impl MakeMkv {
     pub fn new(output_dir: &str) -> Self {
         let output_dir = PathBuf::from(output_dir);
-        let drives = Arc::new(Mutex::new(HashSet::new()));
-        MakeMkv { output_dir, drives }
+        MakeMkv {
+            output_dir,
+            libraries: Vec::new(),
+            drives: Mutex::new(HashMap::new()),
+            naming: NamingTemplate::default(),
+            episode_patterns: EpisodePattern::defaults(),
+            temp_dirs: Mutex::new(HashSet::new()),
+            eject_after_rip: false,
+            nfo: false,
+            artwork: false,
+            tag_notes: false,
+            forced_subtitles: ForcedSubtitles::Off,
+            library_3d: None,
+            version: None,
+        }
     }
 
     /// Initializes the `MakeMkv` instance by verifying the existence of MakeMKV and the output directory.
     pub async fn init(&mut self, output_dir: &str) -> Result<()> {
-        let makemkv_exists = makemkv_exists().await;
-
-        if !makemkv_exists {
-            error!("MakeMKV is not installed");
-            panic!("MakeMKV is not installed");
+        // MakeMKV only has to be on this machine when drives on it are ripped from
+        if rippers().iter().any(|ripper| ripper.host().is_none()) {
+            let version = makemkv_version().await.inspect_err(|e| error!("{}", e))?;
+            info!(
+                "Found MakeMKV {} at `{}`",
+                version,
+                makemkvcon_command().join(" ")
+            );
+            self.version = Some(version);
         }
 
         let output_dir = PathBuf::from(output_dir);
@@ -315,44 +731,116 @@ impl MakeMkv {
             "Output directory set to: {}",
             self.output_dir.to_string_lossy()
         );
+
+        // Clear out anything left behind if the last run crashed mid rip
+        self.cleanup_temp_dirs().await;
+
         info!("MakeMKV initialized successfully!");
         Ok(())
     }
 
     /// Locks a specific drive to prevent concurrent access during the ripping process.
-    async fn lock_drive(&mut self, drive_number: u8) -> Result<()> {
-        // Lock the drives mutex to ensure thread safety
-        let mut drives = self.drives.lock().await;
+    /// The drive is unlocked once the returned guard is dropped, even if the rip is.
+    async fn lock_drive(&self, drive_number: u8) -> Result<OwnedMutexGuard<()>> {
+        // Only the map is locked here, so other drives can be locked while this one rips
+        let lock = self
+            .drives
+            .lock()
+            .await
+            .entry(drive_number)
+            .or_default()
+            .clone();
+
         // Check if the drive is already in use
-        if drives.contains(&drive_number) {
+        let guard = lock.try_lock_owned().map_err(|_| {
             error!("Drive {} is already in use", drive_number);
-            return Err(MakeMkvError::DriveInUseError(drive_number));
-        }
-        drives.insert(drive_number);
+            MakeMkvError::DriveInUseError(drive_number)
+        })?;
         debug!("Locked drive {}", drive_number);
-        Ok(())
+        Ok(guard)
     }
 
-    /// Unlocks a specific drive after the ripping process is complete.
-    async fn unlock_drive(&mut self, drive_number: u8) -> Result<()> {
-        // Lock the drives mutex to ensure thread safety
-        let mut drives = self.drives.lock().await;
-        drives.remove(&drive_number);
-        debug!("Unlocked drive {}", drive_number);
-        Ok(())
+    /// The directory of a named library, the output directory for `None` or a library that
+    /// is no longer configured
+    #[must_use]
+    pub fn library_root(&self, library: Option<&str>) -> &Path {
+        library
+            .and_then(|library| self.libraries.iter().find(|(name, _)| name == library))
+            .map_or(&self.output_dir, |(_, root)| root)
+    }
+
+    /// The output directory followed by the root of every named library
+    #[must_use]
+    pub fn library_roots(&self) -> Vec<PathBuf> {
+        std::iter::once(self.output_dir.clone())
+            .chain(self.libraries.iter().map(|(_, root)| root.clone()))
+            .collect()
+    }
+
+    /// The named library a file was saved to, `None` if it is in the output directory
+    #[must_use]
+    pub fn library_of(&self, path: &Path) -> Option<&str> {
+        self.libraries
+            .iter()
+            .find(|(_, root)| path.starts_with(root))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Builds the full path a rip will be saved to from its library and the naming template.
+    #[must_use]
+    pub fn destination_path(&self, rip: &Rip) -> PathBuf {
+        // The extension is appended rather than set so titles containing dots are
+        // kept intact
+        let mut destination_path = self
+            .library_root(rip.library.as_deref())
+            .join(self.naming.rip_path(rip))
+            .into_os_string();
+        destination_path.push(".mkv");
+        PathBuf::from(destination_path)
+    }
+
+    /// Whether a rip is already saved at its destination, either as one file or split into
+    /// parts. Files are only moved into place once complete, so one that is there is whole.
+    #[must_use]
+    pub fn is_ripped(&self, rip: &Rip) -> bool {
+        let destination_path = self.destination_path(rip);
+        destination_path.exists() || part_path(&destination_path, 1).exists()
+    }
+
+    /// Removes temporary directories left behind by failed or crashed rips, skipping those
+    /// of rips that are still running. Returns the number of bytes reclaimed.
+    pub async fn cleanup_temp_dirs(&self) -> u64 {
+        let active = self.temp_dirs.lock().await;
+        let reclaimed = remove_stale_temp_dirs(&self.output_dir, STALE_TEMP_DIR_AGE, &active);
+
+        if reclaimed > 0 {
+            info!(
+                "Cleaned up stale temporary directories, reclaimed {:.2} MB",
+                reclaimed as f64 / (1024.0 * 1024.0)
+            );
+        }
+
+        reclaimed
+    }
+
+    /// The temporary directories of the rips that are currently running.
+    pub async fn active_temp_dirs(&self) -> HashSet<PathBuf> {
+        self.temp_dirs.lock().await.clone()
     }
 
     /// Executes the ripping process for a specific drive and title, saving the output to the appropriate directory.
-    pub async fn run_rip(&mut self, rip_details: &Rip) -> Result<()> {
+    /// Returns the paths of the saved files, and the segments a retry recovered.
+    pub async fn run_rip(&self, rip_details: &Rip) -> Result<RipOutcome> {
         info!(
             "Starting rip for drive {}: {}",
             rip_details.drive_number, rip_details.title
         );
 
-        self.lock_drive(rip_details.drive_number).await?;
+        // The log of the drive's last rip has nothing to do with this one
+        logs::forget(rip_details.drive_number);
 
         // Create a temporary output directory for the raw makemkv files to be saved to
-        let temp_output_dir = TempDir::with_prefix_in("makemkv_output", &self.output_dir)
+        let temp_output_dir = TempDir::with_prefix_in(TEMP_DIR_PREFIX, &self.output_dir)
             .map_err(|_| MakeMkvError::TempDirError)?;
 
         debug!(
@@ -360,54 +848,82 @@ impl MakeMkv {
             temp_output_dir.path().display()
         );
 
+        // The directory is marked as active so the cleanup leaves it alone while ripping
+        let temp_path = temp_output_dir.path().to_path_buf();
+        self.temp_dirs.lock().await.insert(temp_path.clone());
+
+        let result = self
+            .rip_to_temp_dir(rip_details, temp_output_dir.path())
+            .await;
+
+        self.temp_dirs.lock().await.remove(&temp_path);
+
+        // Clean up the temporary output directory
+        temp_output_dir.close()?;
+        debug!("Closed temporary output directory");
+
+        // Failures are the most likely time for something to have been left behind
+        if result.is_err() {
+            self.cleanup_temp_dirs().await;
+        }
+
+        result
+    }
+
+    /// Rips a title into the temporary directory, then moves the ripped files to their
+    /// destination. Titles with segments that fail hash checks are retried once, slower.
+    /// Returns the paths of the saved files, and the segments the retry recovered.
+    async fn rip_to_temp_dir(
+        &self,
+        rip_details: &Rip,
+        temp_output_dir: &Path,
+    ) -> Result<RipOutcome> {
+        let drive_lock = self.lock_drive(rip_details.drive_number).await?;
+
         // Construct the MakeMKV command
-        // The drive number is 0-indexed in the command, so we subtract 1
-        let dev_path = format!("dev:/dev/sr{}", rip_details.drive_number - 1);
+        // The drive may have moved to another device since it was picked
+        let dev_path = makemkv_source(rip_details.drive_number);
 
         // The title_id is 0-indexed in the command, so we subtract 1
         let title_id = rip_details.title_id - 1;
 
-        // Construct the command to execute
-        let command = MakeMkvCommands::new(
-            "makemkvcon",
-            vec![
-                "mkv".to_string(),
-                dev_path,
-                title_id.to_string(),
-                "--minlength=600".to_string(),
-                temp_output_dir.path().to_string_lossy().to_string(),
-            ],
-        );
+        // Construct the command to execute, the directory to rip into is added by the
+        // ripper since an agent rips into a directory of its own
+        // Progress is printed with everything else, for the ETA
+        let args = vec![
+            "-r".to_string(),
+            "--progress=-same".to_string(),
+            "mkv".to_string(),
+            dev_path,
+            title_id.to_string(),
+            "--minlength=600".to_string(),
+        ];
+        let (host, _) = drive_location(rip_details.drive_number);
 
         info!("Starting MakeMKV Command");
-        debug!("Executing command: {} {:?}", command.command, command.args);
+        debug!("Executing command: makemkvcon {:?} on {:?}", args, host);
         let start_rip_time = Instant::now();
 
-        // Create the destination directory based on the rip type
-        // This is garbage, please fix you lazy shit
-        // Future me: nah push it
-        let (destination_dir, destination_path) = match rip_details.rip_type {
-            RipType::Movie => (
-                self.output_dir
-                    .join(format!("movies/{}", rip_details.title)),
-                self.output_dir
-                    .join(format!(
-                        "movies/{}/{}",
-                        rip_details.title, rip_details.title
-                    ))
-                    .with_extension("mkv"),
-            ),
-            RipType::Show { season, episode } => (
-                self.output_dir
-                    .join(format!("shows/{}/Season {}", rip_details.title, season)),
-                self.output_dir
-                    .join(format!(
-                        "shows/{}/Season {}/Episode {}",
-                        rip_details.title, season, episode
-                    ))
-                    .with_extension("mkv"),
-            ),
-        };
+        // Titles are made path safe, but a template with `..` in it could still climb out
+        let relative_path = self.naming.rip_path(rip_details);
+        if !stays_inside(&relative_path) {
+            error!(
+                "Refusing to save outside the output directory: {}",
+                relative_path.display()
+            );
+            return Err(MakeMkvError::UnsafePath(
+                relative_path.to_string_lossy().to_string(),
+            ));
+        }
+        let destination_path = self.destination_path(rip_details);
+
+        let destination_dir = destination_path.parent().map_or_else(
+            || {
+                self.library_root(rip_details.library.as_deref())
+                    .to_path_buf()
+            },
+            Path::to_path_buf,
+        );
 
         debug!(
             "Destination directory: {}",
@@ -415,39 +931,80 @@ impl MakeMkv {
         );
         debug!("Destination path: {}", destination_path.to_string_lossy());
 
-        if destination_path.exists()
-            && destination_path.is_file()
-            && rip_details.rip_type == RipType::Movie
-        {
+        // Episodes are numbered on from the library, but one numbered wrong mustn't
+        // replace an episode that is already there
+        if destination_path.is_file() {
             error!("File already exists: {}", destination_path.display());
-            // Unlock the drive before returning
-            self.unlock_drive(rip_details.drive_number).await?;
             return Err(MakeMkvError::FileAlreadyExists(
                 destination_path.to_string_lossy().to_string(),
             ));
         }
 
         // Execute the command and capture the output
-        let output = command.execute().await.map_err(|e| {
-            error!("Failed to execute MakeMKV command: {}", e);
-            MakeMkvError::CommandExecutionError(e.to_string())
-        })?;
+        emit(RipEvent::Progress {
+            rip: rip_details.clone(),
+            stage: RipStage::Ripping,
+        });
+        let output = rip_title(rip_details, host.as_deref(), args.clone(), temp_output_dir).await;
+
+        // A scratched disc often reads cleanly the second time, given more time to
+        let mut recovery = None;
+        let output = match output {
+            Ok(output) => {
+                let failed = hash_check_failures(&String::from_utf8_lossy(&output.stdout));
+                if failed.is_empty() {
+                    Ok(output)
+                } else {
+                    let (retried, recovered) = retry_hash_failures(
+                        rip_details,
+                        host.as_deref(),
+                        args,
+                        temp_output_dir,
+                        &output,
+                        failed.len(),
+                    )
+                    .await;
+                    recovery = Some(recovered);
+                    Ok(retried.unwrap_or(output))
+                }
+            }
+            Err(e) => Err(e),
+        };
 
-        // Unlock the drive after ripping regardless of success
-        self.unlock_drive(rip_details.drive_number).await?;
+        // Unlock the drive after ripping regardless of success, a drive that was unplugged
+        // would otherwise stay locked until the bot restarts
+        drop(drive_lock);
+        debug!("Unlocked drive {}", rip_details.drive_number);
 
+        let output = output?;
         trace!("MakeMKV output: {:?}", output);
 
-        match check_makemkv_output(&output) {
-            Ok(_) => (),
-            Err(_) => {
+        // A partial copy still leaves the saved titles in the temporary directory,
+        // so those are kept and the failure is reported once they have been moved
+        let partial_failure = match check_makemkv_output(&output) {
+            Ok(()) => None,
+            Err(e @ MakeMkvError::CopyIncomplete { saved, .. }) if saved > 0 => {
+                warn!(
+                    "MakeMKV only partially ripped {}, keeping saved titles",
+                    rip_details.title
+                );
+                Some(e)
+            }
+            Err(e) => {
                 warn!("MakeMKV failed to rip {}!", rip_details.title);
-                return Err(MakeMkvError::FailedToSaveDisc);
+
+                // An unplugged drive shows up as a read error, or as no error at all
+                if !drive_connected(rip_details.drive_number)
+                    || mentions_device_gone(&String::from_utf8_lossy(&output.stdout))
+                {
+                    return Err(drive_disconnected(rip_details.drive_number).await);
+                }
+                return Err(e);
             }
         };
 
         //Calculate the size of the ripped files and rate of ripping
-        let rip_size: f64 = fs_extra::dir::get_size(temp_output_dir.path())
+        let rip_size: f64 = fs_extra::dir::get_size(temp_output_dir)
             .map_err(|_| MakeMkvError::FailedToSaveDisc)? as f64
             / (1024.0 * 1024.0);
         let rip_time = start_rip_time.elapsed().as_secs_f64() / 60.00;
@@ -459,7 +1016,7 @@ impl MakeMkv {
         );
 
         // Get the list of mkv files in the temporary output directory
-        let ripped_files: Vec<PathBuf> = std::fs::read_dir(temp_output_dir.path())
+        let ripped_files: Vec<PathBuf> = std::fs::read_dir(temp_output_dir)
             .map_err(|_| MakeMkvError::TempDirError)?
             .filter_map(|entry| entry.ok().map(|e| e.path()))
             .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "mkv"))
@@ -471,28 +1028,431 @@ impl MakeMkv {
             return Err(MakeMkvError::FailedToSaveDisc);
         }
 
-        let ripped_file = ripped_files.first().unwrap();
-        debug!("Ripped file: {}", ripped_file.display());
-
         std::fs::create_dir_all(&destination_dir).map_err(|_| MakeMkvError::OutputDirError)?;
 
         debug!("Created output directory: {}", destination_dir.display());
 
-        // Move the ripped file to the destination directory
-        std::fs::rename(ripped_file, &destination_path)
-            .map_err(|_| MakeMkvError::FailedToSaveDisc)?;
-        debug!(
-            "Moved ripped file from {} to {}",
-            ripped_file.display(),
-            destination_path.display()
-        );
+        // Some titles are saved as multiple segment files, these are sorted by the
+        // name MakeMKV gave them and saved as predictably named parts
+        let mut ripped_files = ripped_files;
+        ripped_files.sort();
 
-        // Clean up the temporary output directory
-        temp_output_dir.close()?;
-        debug!("Closed temporary output directory");
+        // Only the 2D base view of a 3D title is kept when asked to, a file that can't be
+        // remuxed is kept as ripped rather than failing the rip
+        if rip_details.base_view {
+            let base_view_dir = temp_output_dir.join("2d");
+            std::fs::create_dir_all(&base_view_dir).map_err(|_| MakeMkvError::TempDirError)?;
+            for ripped_file in &mut ripped_files {
+                match keep_base_view(ripped_file, &base_view_dir).await {
+                    Ok(base_view) => *ripped_file = base_view,
+                    Err(e) => warn!(
+                        "Failed to drop the 3D track of {}, keeping it: {}",
+                        ripped_file.display(),
+                        e
+                    ),
+                }
+            }
+        }
+
+        // A season stored as one long title is split into consecutive episodes
+        let split = match (rip_details.chapters_per_episode, ripped_files.as_slice()) {
+            (Some(chapters_per_episode), [ripped_file]) if rip_details.episode().is_some() => {
+                emit(RipEvent::Progress {
+                    rip: rip_details.clone(),
+                    stage: RipStage::Splitting,
+                });
+                let split_dir = temp_output_dir.join("split");
+                std::fs::create_dir_all(&split_dir).map_err(|_| MakeMkvError::TempDirError)?;
+                Some(split_by_chapters(ripped_file, chapters_per_episode, &split_dir).await?)
+            }
+            _ => None,
+        };
+
+        let destination_paths: Vec<PathBuf> = if let Some(episodes) = split {
+            let destination_paths = (0..episodes.len())
+                .map(|offset| {
+                    rip_details
+                        .with_episode_offset(isize::try_from(offset).ok()?)
+                        .map(|rip| self.destination_path(&rip))
+                })
+                .collect::<Option<Vec<PathBuf>>>()
+                .ok_or_else(|| {
+                    MakeMkvError::SplitError("episode numbers are out of range".to_string())
+                })?;
+            ripped_files = episodes;
+            destination_paths
+        } else if ripped_files.len() == 1 {
+            vec![destination_path]
+        } else {
+            warn!(
+                "MakeMKV produced {} files for {}, saving them as parts",
+                ripped_files.len(),
+                rip_details.title
+            );
+            (1..=ripped_files.len())
+                .map(|part| part_path(&destination_path, part))
+                .collect()
+        };
+
+        // MakeMKV can exit cleanly with a file that doesn't play, so the files are checked
+        // before they are saved. Only a title saved whole is held to its length
+        let expected_length = match ripped_files.as_slice() {
+            [_] => scanned_length(rip_details).await,
+            _ => None,
+        };
+        for ripped_file in &ripped_files {
+            match validate_file(ripped_file, expected_length).await {
+                Ok(()) => {}
+                Err(ProbeError::Invalid(reason)) => {
+                    error!("{} failed validation: {}", ripped_file.display(), reason);
+                    return Err(MakeMkvError::ValidationFailed(reason));
+                }
+                // Only informative, a file that couldn't be checked is still saved
+                Err(e) => warn!("Couldn't validate {}: {}", ripped_file.display(), e),
+            }
+        }
+
+        // Move the ripped files to the destination directory
+        emit(RipEvent::Progress {
+            rip: rip_details.clone(),
+            stage: RipStage::Saving,
+        });
+
+        // A copy to another mount can take longer than the rip did, so the job hands its
+        // drive back to the queue while it copies, once there is a transfer slot for it
+        let sizes: Vec<u64> = ripped_files
+            .iter()
+            .map(|file| std::fs::metadata(file).map_or(0, |metadata| metadata.len()))
+            .collect();
+        let transfer = if same_mount(temp_output_dir, &destination_dir) {
+            None
+        } else {
+            crate::queue::transfers::transfer(sizes.iter().sum()).await
+        };
+
+        let mut copied = 0;
+        for ((ripped_file, destination_path), size) in
+            ripped_files.iter().zip(&destination_paths).zip(sizes)
+        {
+            debug!("Ripped file: {}", ripped_file.display());
+
+            // A library folder mounted from elsewhere is copied to, and checked
+            move_verified_with_progress(ripped_file, destination_path, &|bytes| {
+                if let Some(transfer) = &transfer {
+                    transfer.copied(copied + bytes);
+                }
+            })
+            .await?;
+            copied += size;
+            debug!(
+                "Moved ripped file from {} to {}",
+                ripped_file.display(),
+                destination_path.display()
+            );
+        }
+
+        if let Some(e) = partial_failure {
+            return Err(e);
+        }
 
         info!("Successfully ripped {}!", rip_details.title);
 
-        Ok(())
+        Ok(RipOutcome {
+            files: destination_paths,
+            recovery,
+            size_warning: None,
+        })
+    }
+}
+
+/// The length of a rip's title in seconds, as the disc scan found it
+async fn scanned_length(rip: &Rip) -> Option<u64> {
+    SCANNED_TITLES
+        .lock()
+        .await
+        .get(&rip.drive_number)?
+        .titles
+        .iter()
+        .find(|title| title.title_id == rip.title_id)
+        .and_then(|title| parse_length(&title.length))
+        .map(u64::from)
+}
+
+/// Runs `makemkvcon` to rip a title into a directory, following its progress for the ETA
+async fn rip_title(
+    rip_details: &Rip,
+    host: Option<&str>,
+    args: Vec<String>,
+    output_dir: &Path,
+) -> Result<Output> {
+    let (lines, lines_rx) = mpsc::unbounded_channel();
+    let ripper = ripper(host);
+    let rip = ripper.rip(args.clone(), output_dir, lines);
+    // The estimate is gone once the rip is, so it can't outlive a cancelled one
+    let tracker = eta::track(rip_details).await;
+    let output = tokio::join!(rip, tracker.follow(lines_rx)).0;
+    logs::write_job_log(rip_details.drive_number, &args, &output).await;
+
+    match output {
+        Ok(output) => {
+            logs::record(rip_details.drive_number, &output.stdout, &output.stderr);
+            Ok(output)
+        }
+        Err(_) if !drive_connected(rip_details.drive_number) => {
+            Err(drive_disconnected(rip_details.drive_number).await)
+        }
+        Err(e) => {
+            error!("Failed to execute MakeMKV command: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Rips a title again after `MakeMKV` reported `failed` segments failing hash checks in
+/// `first`, with the drive slowed down and direct disc access turned off. The retry is
+/// ripped next to the first attempt, and only replaces its files if it did better.
+///
+/// Returns the output of the retry if it was kept, and how many segments it recovered.
+async fn retry_hash_failures(
+    rip_details: &Rip,
+    host: Option<&str>,
+    mut args: Vec<String>,
+    temp_output_dir: &Path,
+    first: &Output,
+    failed: usize,
+) -> (Option<Output>, HashRecovery) {
+    let mut recovery = HashRecovery {
+        failed_segments: failed,
+        recovered_segments: 0,
+    };
+    warn!(
+        "{} segment(s) of {} failed hash checks, retrying at a slower speed",
+        failed, rip_details.title
+    );
+
+    let retry_dir = temp_output_dir.join(RETRY_DIR);
+    if let Err(e) = std::fs::create_dir_all(&retry_dir) {
+        warn!("Failed to create the retry directory: {}", e);
+        return (None, recovery);
+    }
+
+    // Some drives read more reliably when MakeMKV doesn't bypass the OS
+    args.insert(1, "--directio=false".to_string());
+    let slowed = match host {
+        Some(_) => None,
+        None => Some(SlowedDrive::slow(rip_details.drive_number).await),
+    };
+    let retry = rip_title(rip_details, host, args, &retry_dir).await;
+    drop(slowed);
+
+    let kept = match retry {
+        Ok(retry) => {
+            let still_failed = hash_check_failures(&String::from_utf8_lossy(&retry.stdout)).len();
+            // A retry that saved nothing is worse than a first attempt with a few bad blocks
+            let better = check_makemkv_output(&retry).is_ok()
+                && (still_failed < failed || check_makemkv_output(first).is_err());
+
+            if better && replace_ripped_files(&retry_dir, temp_output_dir).is_ok() {
+                recovery.recovered_segments = failed.saturating_sub(still_failed);
+                info!(
+                    "Retry of {} recovered {} of {} segment(s)",
+                    rip_details.title, recovery.recovered_segments, failed
+                );
+                Some(retry)
+            } else {
+                warn!(
+                    "Retry of {} didn't do better, keeping the first attempt",
+                    rip_details.title
+                );
+                None
+            }
+        }
+        Err(e) => {
+            warn!("Retry of {} failed: {}", rip_details.title, e);
+            None
+        }
+    };
+
+    if let Err(e) = std::fs::remove_dir_all(&retry_dir) {
+        debug!("Failed to remove the retry directory: {}", e);
+    }
+
+    (kept, recovery)
+}
+
+/// Replaces the files ripped into `dir` with the ones ripped into `retry_dir`
+fn replace_ripped_files(retry_dir: &Path, dir: &Path) -> std::io::Result<()> {
+    let files = |dir: &Path| -> std::io::Result<Vec<PathBuf>> {
+        Ok(std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect())
+    };
+
+    let retried = files(retry_dir)?;
+    for file in files(dir)? {
+        std::fs::remove_file(file)?;
+    }
+    for file in retried {
+        if let Some(name) = file.file_name() {
+            std::fs::rename(&file, dir.join(name))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A drive on this machine slowed down for a retry, back at its fastest once dropped,
+/// even if the retry was cancelled
+struct SlowedDrive {
+    device: String,
+}
+
+impl SlowedDrive {
+    async fn slow(drive_number: u8) -> Self {
+        let (_, device) = drive_location(drive_number);
+        set_read_speed(&device, Some(RETRY_READ_SPEED)).await;
+        SlowedDrive { device }
+    }
+}
+
+impl Drop for SlowedDrive {
+    fn drop(&mut self) {
+        let device = std::mem::take(&mut self.device);
+        tokio::spawn(async move { set_read_speed(&device, None).await });
+    }
+}
+
+/// Parses a library given as `name=path`, such as `kids=/mnt/kids`. `None` if either is
+/// missing, or the name is taken by the output directory.
+#[must_use]
+pub fn parse_library(library: &str) -> Option<(String, PathBuf)> {
+    let (name, root) = library.split_once('=')?;
+    let (name, root) = (name.trim(), root.trim());
+    (!name.is_empty() && !root.is_empty() && name != DEFAULT_LIBRARY)
+        .then(|| (name.to_string(), PathBuf::from(root)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn drives_are_locked_one_at_a_time() {
+        let makemkv = MakeMkv::new("/tmp");
+
+        let first = makemkv.lock_drive(1).await.expect("drive 1 is free");
+        assert!(matches!(
+            makemkv.lock_drive(1).await,
+            Err(MakeMkvError::DriveInUseError(1))
+        ));
+        // Other drives aren't held up by it
+        let second = makemkv.lock_drive(2).await.expect("drive 2 is free");
+
+        drop(first);
+        assert!(makemkv.lock_drive(1).await.is_ok());
+        drop(second);
+    }
+
+    #[test]
+    fn retried_files_replace_the_first_attempt() {
+        let dir = TempDir::new().expect("temp dir");
+        let retry_dir = dir.path().join(RETRY_DIR);
+        std::fs::create_dir(&retry_dir).unwrap();
+        std::fs::write(dir.path().join("title_t00.mkv"), "corrupt").unwrap();
+        std::fs::write(retry_dir.join("title_t00.mkv"), "clean").unwrap();
+
+        replace_ripped_files(&retry_dir, dir.path()).expect("files are replaced");
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("title_t00.mkv")).unwrap(),
+            "clean"
+        );
+        assert!(std::fs::read_dir(&retry_dir).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn rips_are_saved_to_their_library() {
+        let mut makemkv = MakeMkv::new("/srv/media");
+        makemkv.libraries = vec![parse_library("kids = /mnt/kids").expect("valid library")];
+        assert_eq!(parse_library("main=/mnt/media"), None);
+        assert_eq!(parse_library("kids"), None);
+
+        let mut rip = Rip {
+            title: "Heat".to_string(),
+            drive_number: 0,
+            rip_type: RipType::Movie,
+            title_id: 1,
+            year: None,
+            chapters_per_episode: None,
+            library: None,
+            episode_title: None,
+            note: None,
+            extra: None,
+            base_view: false,
+        };
+        assert!(makemkv.destination_path(&rip).starts_with("/srv/media"));
+
+        rip.library = Some("kids".to_string());
+        let destination = makemkv.destination_path(&rip);
+        assert!(destination.starts_with("/mnt/kids"));
+        assert_eq!(makemkv.library_of(&destination), Some("kids"));
+    }
+
+    #[test]
+    fn episodes_are_renumbered_both_ways() {
+        let rip = Rip {
+            title: "Friends".to_string(),
+            drive_number: 0,
+            rip_type: RipType::Show {
+                season: 2,
+                episode: 3,
+                absolute_episode: Some(27),
+            },
+            title_id: 4,
+            year: None,
+            chapters_per_episode: None,
+            library: None,
+            episode_title: None,
+            note: None,
+            extra: None,
+            base_view: false,
+        };
+
+        let later = rip.with_episode_offset(2).expect("in range");
+        assert_eq!(later.episode(), Some(5));
+        let earlier = rip.with_episode_offset(-1).expect("in range");
+        assert_eq!(
+            earlier.rip_type,
+            RipType::Show {
+                season: 2,
+                episode: 2,
+                absolute_episode: Some(26),
+            }
+        );
+        assert!(rip.with_episode_offset(-4).is_none());
+        assert!(rip.with_episode_offset(1000).is_none());
+        assert!(Rip {
+            rip_type: RipType::Movie,
+            ..rip
+        }
+        .with_episode_offset(1)
+        .is_none());
+    }
+
+    #[test]
+    fn only_rips_far_smaller_than_scanned_are_suspicious() {
+        let gigabyte = 1024 * 1024 * 1024;
+        assert_eq!(SizeWarning::check(Some(20 * gigabyte), 19 * gigabyte), None);
+        assert_eq!(
+            SizeWarning::check(Some(20 * gigabyte), 3 * gigabyte),
+            Some(SizeWarning {
+                expected_bytes: 20 * gigabyte,
+                saved_bytes: 3 * gigabyte,
+            })
+        );
+        // Without a scan there is nothing to compare with
+        assert_eq!(SizeWarning::check(None, 0), None);
+        assert_eq!(SizeWarning::check(Some(0), 0), None);
     }
 }
@@ -1,23 +1,61 @@
-use std::process::Output;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasher,
+    path::{Path, PathBuf},
+    process::Output,
+    sync::{Arc, LazyLock, OnceLock},
+    time::Duration,
+};
+
+use tokio::sync::{mpsc, Mutex};
 
 use super::{
+    drive_map::{device_identity, drive_location, makemkv_source, ListedDevice, DRIVE_MAP},
+    episodes::last_episode,
     errors::{MakeMkvError, Result},
-    makemkv_core::MAKE_MKV,
+    makemkv_core::MakeMkv,
+    messages::{makemkv_version as makemkv_version_from, parse_messages, MessageKind},
+    rippers::{ripper, rippers, DiscRipper},
+    robot::{self, RobotLine},
+    subtitles::SubtitleStream,
 };
-use crate::{debug, error, info, trace};
+use crate::library::LIBRARY;
+use crate::{debug, error, info, trace, warn};
+
+/// The titles last found on the disc in each drive, kept so later steps of a rip, like
+/// previews, don't have to wait on `MakeMKV` to scan the disc again.
+pub static SCANNED_TITLES: LazyLock<Arc<Mutex<HashMap<u8, DiscInfo>>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-pub struct Command<'a> {
-    pub command: &'a str,
+/// The label of the disc in each drive when the drives were last listed, kept so a rip
+/// can be filled in from it without listing the drives again.
+pub static DISC_LABELS: LazyLock<Arc<Mutex<HashMap<u8, String>>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+#[derive(Debug, Clone, Default)]
+pub struct Command {
+    pub command: String,
     pub args: Vec<String>,
+    pub envs: Vec<(String, String)>,
+    pub current_dir: Option<PathBuf>,
+    pub stdin: Option<Vec<u8>>,
+    pub timeout: Option<Duration>,
+    pub stdout_lines: Option<mpsc::UnboundedSender<String>>,
 }
 
 /// A struct representing a command to be executed, along with its arguments.
 /// This struct is designed to facilitate the execution of external commands
 /// asynchronously using Tokio's process handling utilities.
 ///
-/// # Lifetime Parameters
-/// - `'a`: The lifetime of the command string, which is stored as a leaked
-///   boxed string to ensure it lives for the duration of the program.
+/// # Fields
+/// - `command`: The program to run.
+/// - `args`: The arguments passed to it.
+/// - `envs`: Environment variables set for it, on top of the bot's own.
+/// - `current_dir`: The directory it runs in, the bot's own when `None`.
+/// - `stdin`: What is written to its standard input, nothing when `None`.
+/// - `timeout`: How long it can run before it is killed, forever when `None`.
+/// - `stdout_lines`: Where each line of its standard output is sent as it is printed, for
+///   following a long running command. The output is still returned in full.
 ///
 /// # Methods
 ///
@@ -33,56 +71,211 @@ pub struct Command<'a> {
 /// ### Returns
 /// A `Command` instance containing the provided command and arguments.
 ///
-/// ### Example
-/// ```rust
-/// let command = Command::new("ls", vec!["-la".to_string()]);
-/// ```
+/// ## `env`, `current_dir`, `stdin`, `timeout` and `stdout_lines`
+/// Set the fields of the same name, returning the command so they can be chained.
 ///
 /// ## `execute`
 /// Executes the command asynchronously and returns the output.
 ///
 /// ### Returns
-/// - `Ok(Output)`: The output of the command if it executes successfully.
-/// - `Err`: An error if the command fails to execute.
+/// - `Ok(Output)`: The output of the command if it executes successfully, with stdout and
+///   stderr captured separately.
+/// - `Err`: An error if the command fails to execute, or `TimedOut` if it ran for longer
+///   than its timeout.
 ///
 /// ### Behavior
 /// - Logs the command and its arguments at the `trace` level before execution.
 /// - Logs the command's output at the `trace` level after execution.
-/// - Ensures the command process is killed if dropped before completion.
+/// - Ensures the command process is killed if dropped before completion, or once it times
+///   out.
 ///
 /// ### Example
-/// ```rust
-/// let command = Command::new("ls", vec!["-la".to_string()]);
-/// let output = command.execute().await?;
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use cord_ripper::makemkv::makemkv_helpers::Command;
+///
+/// # async fn example(file: String) -> std::io::Result<()> {
+/// let output = Command::new("mkvmerge", vec!["-J".to_string(), file])
+///     .env("LC_ALL", "C")
+///     .timeout(Duration::from_secs(30))
+///     .execute()
+///     .await?;
 /// println!("Command output: {:?}", output);
+/// # Ok(())
+/// # }
 /// ```
 ///
 /// # Notes
-/// - The `command` field is stored as a leaked boxed string to ensure its
-///   lifetime matches the `'a` lifetime parameter.
 /// - This struct is designed to work with Tokio's asynchronous runtime.
-impl<'a> Command<'a> {
-    pub fn new<S: Into<String>>(command: S, args: Vec<String>) -> Command<'a> {
+impl Command {
+    pub fn new<S: Into<String>>(command: S, args: Vec<String>) -> Command {
         Command {
-            command: Box::leak(command.into().into_boxed_str()),
-            args: args.into_iter().map(Into::into).collect(),
+            command: command.into(),
+            args,
+            ..Command::default()
         }
     }
 
-    pub async fn execute(&self) -> Result<Output> {
+    #[must_use]
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Command {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Command {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    #[must_use]
+    pub fn stdin(mut self, input: impl Into<Vec<u8>>) -> Command {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Command {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    #[must_use]
+    pub fn stdout_lines(mut self, sender: mpsc::UnboundedSender<String>) -> Command {
+        self.stdout_lines = Some(sender);
+        self
+    }
+
+    pub async fn execute(&self) -> std::io::Result<Output> {
+        use std::process::Stdio;
+        use tokio::io::AsyncWriteExt;
+
         trace!("Executing command: {} {:?}", self.command, self.args);
-        let output = tokio::process::Command::new(&self.command)
+        let mut command = tokio::process::Command::new(&self.command);
+        command
             .args(&self.args)
-            .kill_on_drop(true)
-            .output()
-            .await?;
+            .envs(self.envs.iter().map(|(key, value)| (key, value)))
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command.spawn()?;
+        if let (Some(input), Some(mut stdin)) = (&self.stdin, child.stdin.take()) {
+            stdin.write_all(input).await?;
+            // Dropping stdin closes it, so the command sees the end of the input
+        }
+
+        let run = async {
+            match (&self.stdout_lines, child.stdout.take()) {
+                (Some(sender), Some(stdout)) => {
+                    let (stdout, mut output) =
+                        tokio::try_join!(forward_lines(stdout, sender), child.wait_with_output())?;
+                    output.stdout = stdout;
+                    Ok(output)
+                }
+                (_, stdout) => {
+                    child.stdout = stdout;
+                    child.wait_with_output().await
+                }
+            }
+        };
+
+        // The child is killed when it is dropped, which is what happens when it times out
+        let output = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run).await.map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("{} took longer than {:?}", self.command, timeout),
+                )
+            })??,
+            None => run.await?,
+        };
 
         trace!("Command output: {:?}", output);
         Ok(output)
     }
 }
 
-#[derive(Default, Debug)]
+/// Sends each line of a command's output on as it is printed, returning all of it once
+/// the command closes it
+async fn forward_lines(
+    stdout: tokio::process::ChildStdout,
+    sender: &mpsc::UnboundedSender<String>,
+) -> std::io::Result<Vec<u8>> {
+    use tokio::io::AsyncBufReadExt;
+
+    let mut reader = tokio::io::BufReader::new(stdout);
+    let mut output = Vec::new();
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).await? > 0 {
+        // Nobody listening anymore doesn't stop the command
+        let _ = sender.send(String::from_utf8_lossy(&line).trim_end().to_string());
+        output.append(&mut line);
+    }
+
+    Ok(output)
+}
+
+/// How long `makemkvcon` and `mkvmerge` get to answer when only asked about themselves or a
+/// file, a hung one would otherwise hold up startup or a rip for good
+pub(super) const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The command `makemkvcon` is run through, such as `docker exec makemkv` to run it in
+/// another container. Empty to run it directly.
+static MAKEMKV_PREFIX: OnceLock<Vec<String>> = OnceLock::new();
+
+/// How `makemkvcon` is run, its path or a wrapper such as
+/// `flatpak run --command=makemkvcon com.makemkv.MakeMKV`. `makemkvcon` when not set.
+static MAKEMKV_COMMAND: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Sets the command `makemkvcon` is run through, split on whitespace. Only the first call
+/// has any effect.
+pub fn set_makemkv_prefix(prefix: &str) {
+    let _ = MAKEMKV_PREFIX.set(prefix.split_whitespace().map(String::from).collect());
+}
+
+/// Sets how `makemkvcon` is run, split on whitespace. Only the first call has any effect.
+pub fn set_makemkv_command(command: &str) {
+    let _ = MAKEMKV_COMMAND.set(command.split_whitespace().map(String::from).collect());
+}
+
+/// The program and arguments `makemkvcon` is run with, through the prefix if one was set
+#[must_use]
+pub fn makemkvcon_command() -> Vec<String> {
+    let command = MAKEMKV_COMMAND
+        .get()
+        .filter(|command| !command.is_empty())
+        .cloned()
+        .unwrap_or_else(|| vec!["makemkvcon".to_string()]);
+
+    MAKEMKV_PREFIX
+        .get()
+        .into_iter()
+        .flatten()
+        .cloned()
+        .chain(command)
+        .collect()
+}
+
+/// Builds a `makemkvcon` command, run the way it was configured
+#[must_use]
+pub fn makemkvcon(args: Vec<String>) -> Command {
+    let mut command = makemkvcon_command();
+    let program = command.remove(0);
+    command.extend(args);
+    Command::new(program, command)
+}
+
+#[derive(Default, Clone, Debug)]
 /// Represents information about a disc, including its name and the titles it contains.
 ///
 /// # Fields
@@ -112,6 +305,12 @@ pub struct DiscInfo {
 /// - `resolution` - The resolution of the title, typically represented as a string (e.g., "1920x1080").
 /// - `aspect_ratio` - The aspect ratio of the title, typically represented as a string (e.g., "16:9").
 /// - `frame_rate` - The frame rate of the title, typically represented as a string (e.g., "24 fps").
+/// - `source_file` - The file the title is played from on the disc, the playlist on Blu-rays (e.g., "00800.mpls").
+/// - `original_title_id` - The disc's own number for the title, which `MakeMKV` renumbers.
+/// - `segments_map` - The stream files the title plays, in order, as ranges (e.g., "1,3,5-7").
+/// - `audio_languages` - The languages of the audio tracks, without repeats (e.g., "English").
+/// - `subtitles` - The subtitle streams, in the order `MakeMKV` lists them.
+/// - `three_d` - Whether the title has a 3D (MVC) video stream, which many players can't play.
 ///
 /// This struct is useful for organizing and accessing detailed information about
 /// media titles during processing or analysis.
@@ -124,6 +323,12 @@ pub struct Title {
     pub resolution: String,
     pub aspect_ratio: String,
     pub frame_rate: String,
+    pub source_file: String,
+    pub original_title_id: Option<u16>,
+    pub segments_map: String,
+    pub audio_languages: Vec<String>,
+    pub subtitles: Vec<SubtitleStream>,
+    pub three_d: bool,
 }
 
 #[derive(Debug)]
@@ -136,18 +341,25 @@ pub struct Title {
 /// # Fields
 ///
 /// * `drive_number` - A unique identifier for the drive, represented as an unsigned 8-bit integer.
+///   It stays the same for a drive across reboots and re-plugs, see `drive_map`.
+/// * `alias` - A friendly name for the drive, if one was set in the drive map.
+/// * `host` - The agent the drive is on, `None` for drives on this machine.
 /// * `drive_model` - A string representing the model name or identifier of the drive.
 /// * `drive_media_title` - A string representing the title of the media currently loaded in the drive.
+/// * `media` - The kind of disc loaded in the drive.
 ///
 /// # Example
 ///
 /// ```rust
-/// use cord_ripper_v1::makemkv::makemkv_helpers::Drive;
+/// use cord_ripper::makemkv::makemkv_helpers::{DiscMedia, Drive};
 ///
 /// let drive = Drive {
 ///     drive_number: 1,
+///     alias: None,
+///     host: None,
 ///     drive_model: String::from("ASUS BW-16D1HT"),
 ///     drive_media_title: String::from("My Movie Disc"),
+///     media: DiscMedia::Video,
 /// };
 ///
 /// println!("Drive {}: {} with media '{}'",
@@ -158,15 +370,57 @@ pub struct Title {
 /// ```
 pub struct Drive {
     pub drive_number: u8,
+    pub alias: Option<String>,
+    pub host: Option<String>,
     pub drive_model: String,
     pub drive_media_title: String,
+    pub media: DiscMedia,
+}
+
+/// The kind of disc loaded in a drive.
+///
+/// * `Empty` - There is no disc in the drive.
+/// * `Video` - A DVD or Blu-ray `MakeMKV` can rip.
+/// * `Audio` - An audio CD, ripped with `cdparanoia` rather than `MakeMKV`.
+/// * `Data` - Any other disc, with nothing `MakeMKV` or `cdparanoia` can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscMedia {
+    Empty,
+    Video,
+    Audio,
+    Data,
+}
+
+/// Runs `makemkvcon` the way it was configured, returning the version of `MakeMKV` it
+/// reports.
+///
+/// # Errors
+///
+/// Returns a `MakeMkvError` naming the command if it can't be run, or doesn't answer like
+/// `makemkvcon`.
+pub async fn makemkv_version() -> Result<String> {
+    let command = makemkvcon_command().join(" ");
+    let output = makemkvcon(vec![
+        "-r".to_string(),
+        "--cache=1".to_string(),
+        "info".to_string(),
+        "disc:9999".to_string(),
+    ])
+    .timeout(PROBE_TIMEOUT)
+    .execute()
+    .await
+    .map_err(|e| MakeMkvError::MakeMkvNotFound(command.clone(), e.to_string()))?;
+
+    makemkv_version_from(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        MakeMkvError::MakeMkvNotFound(
+            command,
+            "it ran, but didn't report a MakeMKV version".to_string(),
+        )
+    })
 }
 
 pub async fn makemkv_exists() -> bool {
-    let command = Command {
-        command: "makemkvcon",
-        args: vec![],
-    };
+    let command = makemkvcon(vec![]).timeout(PROBE_TIMEOUT);
 
     // Execite the command and check the output to see if MakeMKV responded
     let output = command.execute().await;
@@ -189,15 +443,47 @@ pub fn check_makemkv_output(output: &Output) -> Result<()> {
     // Convert the output from a vec of bytes to a string
     let stdout_string = String::from_utf8(output.stdout.clone())?;
 
+    // Walk through every message MakeMKV printed, keeping the first error and
+    // the final copy counts
+    let mut first_error = None;
+    let mut copy_counts = None;
+    for message in parse_messages(&stdout_string) {
+        match message.kind() {
+            MessageKind::CopyComplete { saved, failed } => copy_counts = Some((saved, failed)),
+            MessageKind::Error(e) => {
+                warn!("MakeMKV reported error {}: {}", message.code, message.text);
+                first_error.get_or_insert(e);
+            }
+            MessageKind::Info => trace!("MakeMKV message {}: {}", message.code, message.text),
+        }
+    }
+
+    match copy_counts {
+        Some((saved, 0)) if saved > 0 => return Ok(()),
+        Some((saved, failed)) if failed > 0 => {
+            error!("MakeMKV saved {} titles, {} failed", saved, failed);
+            // Prefer the specific error when nothing was saved at all
+            return Err(match first_error {
+                Some(e) if saved == 0 => e,
+                _ => MakeMkvError::CopyIncomplete { saved, failed },
+            });
+        }
+        _ => (),
+    }
+
+    if let Some(e) = first_error {
+        error!("MakeMKV failed: {}", e);
+        return Err(e);
+    }
+
     if output.status.success() {
-        // Even if the command is successful, we need to check the output
-        // for any errors that may have occurred, noted by 'Failed to save'
+        // Older MakeMKV versions, or commands run without the robot flag, won't
+        // report copy counts, so fall back to looking for 'Failed to save'
         if stdout_string.contains("Failed to save") {
             error!("Failed to read disc! Likely a scratched or currupt disc.");
             return Err(MakeMkvError::FailedToSaveDisc);
-        } else {
-            return Ok(());
         }
+        return Ok(());
     } else if let Some(exit_code) = output.status.code() {
         debug!("MakeMKV exited with code: {}", exit_code);
         // Specific codes reverse engineered from the MakeMKV source code
@@ -220,47 +506,19 @@ pub fn check_makemkv_output(output: &Output) -> Result<()> {
 
 pub async fn get_drives() -> Result<Vec<Drive>> {
     info!("Getting data from drives...");
-    let command = Command::new(
-        "makemkvcon",
-        vec![
-            "-r".to_string(),
-            "--cache=1".to_string(),
-            "info".to_string(),
-            "disc:9999".to_string(),
-        ],
-    );
-
-    // Execute the command and check the output to see if MakeMKV responded
-    let output = command.execute().await.map_err(|e| {
-        error!("Failed to execute MakeMKV command: {}", e);
-        MakeMkvError::CommandExecutionError(e.to_string())
-    })?;
 
     let mut discs = Vec::new();
+    let mut first_error = None;
 
-    // Yikes this is a clutterfuck
-    // Parse the output to find the drives
-    for line in String::from_utf8(output.stdout)?.lines() {
-        // In combination with the 'robotic output' of makemkvcon,
-        // drive information is always prefixed with 'DRV:'
-        // and drive information is *always* stored the same
-        if line.starts_with("DRV:") && line.contains("/dev/sr") {
-            // Info is displayed in csv format
-            let info: Vec<&str> = line.split(",").collect();
-            let disc_no: u8 = info[6][8..9].parse()?;
-            let inserted_disc = if clean_str(info[5]) == "" {
-                "No disc inserted".to_string()
-            } else {
-                clean_str(info[5]).replace("_", " ")
-            };
-            let drive_info = clean_str(info[4]);
-
-            // Wow we actually got a drive instance from this??
-            discs.push(Drive {
-                drive_number: disc_no + 1,
-                drive_model: drive_info,
-                drive_media_title: inserted_disc,
-            });
+    // Drives are listed from this machine and every agent, an agent that can't be reached
+    // only leaves its own drives out
+    for ripper in rippers() {
+        match list_drives(ripper.as_ref()).await {
+            Ok(drives) => discs.extend(drives),
+            Err(e) => {
+                error!("Failed to list drives: {}", e);
+                first_error.get_or_insert(e);
+            }
         }
     }
 
@@ -268,10 +526,95 @@ pub async fn get_drives() -> Result<Vec<Drive>> {
 
     debug!("Found following drives: {:?}", discs);
 
+    let mut labels = DISC_LABELS.lock().await;
+    for drive in &discs {
+        labels.insert(drive.drive_number, drive.drive_media_title.clone());
+    }
+    drop(labels);
+
     // Check if we found any drives, unlikely with the rats nest of references, but possible
     if discs.is_empty() {
         error!("No drives found");
-        return Err(MakeMkvError::NoDrivesFound);
+        return Err(first_error.unwrap_or(MakeMkvError::NoDrivesFound));
+    }
+
+    Ok(discs)
+}
+
+/// Lists the drives of a single ripper
+async fn list_drives(ripper: &dyn DiscRipper) -> Result<Vec<Drive>> {
+    let output = ripper
+        .makemkvcon(vec![
+            "-r".to_string(),
+            "--cache=1".to_string(),
+            "info".to_string(),
+            "disc:9999".to_string(),
+        ])
+        .await?;
+
+    let mut discs = Vec::new();
+
+    // MakeMKV lists a slot for every drive it could have, the ones without a device are
+    // empty
+    let stdout = String::from_utf8(output.stdout)?;
+    let records = robot::parse(&stdout).filter_map(|line| match line {
+        RobotLine::Drive(drive) if drive.is_present() => Some(drive),
+        _ => None,
+    });
+    for record in records {
+        let device = ListedDevice {
+            index: record.index,
+            path: record.device.clone(),
+            host: ripper.host().map(String::from),
+        };
+        let drive_info = record.drive_name.clone();
+
+        // Drives keep their number wherever they turn up, the name MakeMKV lists a
+        // drive with includes its serial number when udev and sysfs can't be read.
+        // Drives on agents are told apart by the agent too, in case two have the same
+        let identity = match ripper.host() {
+            Some(host) => format!("{host}: {drive_info}"),
+            None => device
+                .path
+                .strip_prefix("/dev/sr")
+                .and_then(|index| index.parse().ok())
+                .and_then(device_identity)
+                .unwrap_or_else(|| drive_info.clone()),
+        };
+        let (drive_number, alias) = {
+            let mut drive_map = DRIVE_MAP
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            let drive_number = drive_map.assign(&identity, device);
+            (drive_number, drive_map.alias(drive_number))
+        };
+
+        // The flags are the video filesystems MakeMKV found on the disc
+        let media = if !record.is_loaded() {
+            DiscMedia::Empty
+        } else if record.flags != 0 {
+            DiscMedia::Video
+        } else if ripper.host().is_none() && crate::audio::is_audio_disc(drive_number).await {
+            DiscMedia::Audio
+        } else {
+            DiscMedia::Data
+        };
+
+        let inserted_disc = match media {
+            DiscMedia::Audio => "Audio CD".to_string(),
+            _ if record.disc_name.is_empty() => "No disc inserted".to_string(),
+            _ => record.disc_name.replace('_', " "),
+        };
+
+        // Wow we actually got a drive instance from this??
+        discs.push(Drive {
+            drive_number,
+            alias,
+            host: ripper.host().map(String::from),
+            drive_model: drive_info,
+            drive_media_title: inserted_disc,
+            media,
+        });
     }
 
     Ok(discs)
@@ -284,21 +627,16 @@ pub async fn get_title_info(drive_number: u8) -> Result<DiscInfo> {
     // a minimum length of 10 minutes
     // This started as a feature to filter advertisement titles, but it is now a limitation
     // but it's not going to change until there is a demand for it
-    let command = Command::new(
-        "makemkvcon",
-        vec![
+    let (host, _) = drive_location(drive_number);
+    let output = ripper(host.as_deref())
+        .makemkvcon(vec![
             "-r".to_string(),
             "info".to_string(),
-            format!("dev:/dev/sr{}", drive_number - 1),
+            makemkv_source(drive_number),
             "--minlength=600".to_string(),
-        ],
-    );
-
-    // Execute the command and check the output to see if MakeMKV responded
-    let output = command.execute().await.map_err(|e| {
-        error!("Failed to execute MakeMKV command: {}", e);
-        MakeMkvError::CommandExecutionError(e.to_string())
-    })?;
+        ])
+        .await
+        .inspect_err(|e| error!("Failed to execute MakeMKV command: {}", e))?;
 
     // Check to see if the disc info was successfully retrieved
     let disc_info = parse_disc_info(&output).map_err(|e| {
@@ -306,6 +644,11 @@ pub async fn get_title_info(drive_number: u8) -> Result<DiscInfo> {
         MakeMkvError::ParseError(e.to_string())
     })?;
 
+    SCANNED_TITLES
+        .lock()
+        .await
+        .insert(drive_number, disc_info.clone());
+
     // Wow... we made it, take this king: 👑
     Ok(disc_info)
 }
@@ -314,53 +657,108 @@ pub fn parse_disc_info(output: &Output) -> Result<DiscInfo> {
     // Create some empty structs to store the disc and title info
     let mut disc_info = DiscInfo::default();
     let mut title_info = Title::default();
+    // The audio or subtitle stream being read, as its title and stream number
+    let mut audio_stream = None;
+    let mut subtitle_stream = None;
 
-    // Iter through lines of converted utf8 bytes
-    for line in String::from_utf8(output.stdout.clone())?.lines() {
-        // Trim the line
-        let line = line.trim();
-        trace!("{}", line);
-        // Create a vec of strings from the line
-        let info: Vec<&str> = line.split(",").collect();
-
-        // Check if the line starts with 'CINFO', 'TINFO', or 'SINFO'
-        // and parse the info accordingly
-        // A reference to the info_codes are available online in the 'apdefs.h' file
-        if line.starts_with("CINFO") {
-            let info_code: u8 = info[0].split(":").last().unwrap().parse()?;
-            if info_code == 2 {
-                disc_info.disc_name = clean_info(info);
-            }
-        } else if line.starts_with("TINFO") {
-            let mut title_code: u16 = info[0].split(":").last().unwrap().parse()?;
-            title_code = title_code + 1;
+    let stdout = String::from_utf8(output.stdout.clone())?;
+    for line in robot::parse(&stdout) {
+        trace!("{:?}", line);
 
-            title_info.title_id = title_code as u16;
-
-            let info_code: u8 = info[1].parse()?;
+        // The attributes are listed title by title, with the streams of a title after it
+        match line {
+            RobotLine::DiscInfo {
+                attribute: robot::ATTR_NAME,
+                value,
+                ..
+            } => disc_info.disc_name = value,
+            RobotLine::TitleInfo {
+                title,
+                attribute,
+                value,
+                ..
+            } => {
+                // Titles are numbered from 0 by MakeMKV, and from 1 everywhere else. A new
+                // title starts out empty, so it doesn't keep what the last one had
+                if title_info.title_id != title + 1 {
+                    title_info = Title {
+                        title_id: title + 1,
+                        ..Title::default()
+                    };
+                }
 
-            // Get title info
-            match info_code {
-                8 => title_info.chapters = clean_info(info).parse()?,
-                9 => title_info.length = clean_info(info),
-                10 => title_info.size = clean_info(info),
-                _ => continue,
+                match attribute {
+                    robot::ATTR_CHAPTER_COUNT => title_info.chapters = value.parse()?,
+                    robot::ATTR_DURATION => title_info.length = value,
+                    robot::ATTR_DISK_SIZE => title_info.size = value,
+                    robot::ATTR_SOURCE_FILE_NAME => title_info.source_file = value,
+                    robot::ATTR_ORIGINAL_TITLE_ID => {
+                        title_info.original_title_id = value.parse().ok();
+                    }
+                    robot::ATTR_SEGMENTS_MAP => title_info.segments_map = value,
+                    _ => {}
+                }
             }
-        } else if line.starts_with("SINFO") {
-            // I forgor how this works or if it can be used elseware
-            let info_code: u8 = info[2].parse()?;
-
-            // Get additional title info
-            match info_code {
-                13 => title_info.bitrate = clean_info(info),
-                19 => title_info.resolution = clean_info(info),
-                20 => title_info.aspect_ratio = clean_info(info),
-                21 => {
-                    title_info.frame_rate = clean_info(info);
+            RobotLine::StreamInfo {
+                title,
+                stream,
+                attribute,
+                code,
+                value,
+            } => match attribute {
+                // The type comes first for every stream
+                robot::ATTR_TYPE => {
+                    audio_stream = (code == robot::STREAM_TYPE_AUDIO).then_some((title, stream));
+                    subtitle_stream =
+                        (code == robot::STREAM_TYPE_SUBTITLES).then_some((title, stream));
+                    if let (Some(_), Some(title_info)) =
+                        (subtitle_stream, current_title(&mut disc_info, title))
+                    {
+                        title_info.subtitles.push(SubtitleStream::default());
+                    }
+                }
+                // Subtitle streams come after the video stream too
+                robot::ATTR_LANG_CODE | robot::ATTR_STREAM_FLAGS
+                    if subtitle_stream == Some((title, stream)) =>
+                {
+                    if let Some(subtitle) = current_title(&mut disc_info, title)
+                        .and_then(|title_info| title_info.subtitles.last_mut())
+                    {
+                        if attribute == robot::ATTR_LANG_CODE {
+                            subtitle.language = value;
+                        } else {
+                            let flags: u32 = value.parse().unwrap_or_default();
+                            subtitle.forced = flags & robot::STREAM_FLAG_FORCED != 0;
+                        }
+                    }
+                }
+                // The 3D stream of a 3D title comes after its 2D video stream
+                robot::ATTR_CODEC_ID if value == robot::CODEC_ID_MVC => {
+                    if let Some(title_info) = current_title(&mut disc_info, title) {
+                        title_info.three_d = true;
+                    }
+                }
+                // Audio streams come after the video stream, so their title has already
+                // been added
+                robot::ATTR_LANG_NAME if audio_stream == Some((title, stream)) => {
+                    if let Some(title_info) = current_title(&mut disc_info, title) {
+                        if !title_info.audio_languages.contains(&value) {
+                            title_info.audio_languages.push(value);
+                        }
+                    }
+                }
+                robot::ATTR_BITRATE => title_info.bitrate = value,
+                robot::ATTR_VIDEO_SIZE => title_info.resolution = value,
+                robot::ATTR_VIDEO_ASPECT_RATIO => title_info.aspect_ratio = value,
+                // The frame rate is the last attribute of the video stream, so the title is
+                // complete once it has been read. The 3D stream of a 3D title has one too
+                robot::ATTR_VIDEO_FRAME_RATE if current_title(&mut disc_info, title).is_none() => {
+                    title_info.frame_rate = value;
                     disc_info.titles.push(title_info.clone());
                 }
-                _ => continue,
-            }
+                _ => {}
+            },
+            _ => {}
         }
     }
 
@@ -369,52 +767,47 @@ pub fn parse_disc_info(output: &Output) -> Result<DiscInfo> {
     Ok(disc_info)
 }
 
-pub async fn get_last_episode_in_dir(title: &str, season: u8) -> Result<u8> {
-    let mut last_episode = 0;
+/// The title `MakeMKV` numbers `title` from 0, if it is the one being read
+fn current_title(disc_info: &mut DiscInfo, title: u16) -> Option<&mut Title> {
+    disc_info
+        .titles
+        .last_mut()
+        .filter(|title_info| title_info.title_id == title + 1)
+}
 
-    let makemkv = MAKE_MKV.lock().await;
+/// Finds the last episode already ripped for a season of a show in `library`, or 0 if
+/// there are none. `None` is the output directory.
+///
+/// The files are looked up in the `LIBRARY` index rather than read from disk, in the
+/// season directory `makemkv` names episodes into.
+pub async fn get_last_episode_in_dir(
+    makemkv: &MakeMkv,
+    library: Option<&str>,
+    title: &str,
+    season: u8,
+) -> u8 {
+    // The season directory is wherever the naming template puts the episodes
+    let season_dir = makemkv.library_root(library).join(
+        makemkv
+            .naming
+            .show_path(title, season, 1, None, None)
+            .parent()
+            .unwrap_or(Path::new("")),
+    );
+    let patterns = &makemkv.episode_patterns;
 
-    // Logic repeated before? maybe i should extract this to a function
-    let season_dir = makemkv
-        .output_dir
-        .join(format!("shows/{}/Season {}", title, season));
-    if !season_dir.exists() {
-        debug!(
-            "Season directory does not exist: {}, setting to 0",
-            season_dir.to_string_lossy()
-        );
-        return Ok(0);
-    }
-    let entries = std::fs::read_dir(&season_dir)
-        .map_err(|_| MakeMkvError::FileNotFoundError(season_dir.to_string_lossy().to_string()))?;
-
-    trace!("Entries in {} Season {}: {:?}", title, season, entries);
-
-    for entry in entries {
-        trace!("Entry: {:?}", entry);
-        let entry = entry.map_err(|_| {
-            MakeMkvError::FileNotFoundError(season_dir.to_string_lossy().to_string())
-        })?;
-        let path = entry.path();
-
-        // THIS IS SO BAD LMAOOOO
-        // Check if the path is a file and if it starts with "Episode " and ends with ".mkv"
-        if path.is_file() {
-            // IF the file_name returns something, and it can be converted to a string
-            if let Some(file_name) = path.file_name() {
-                if let Some(file_name_str) = file_name.to_str() {
-                    // Check if the file name starts with "Episode " and ends with ".mkv"
-                    if file_name_str.starts_with("Episode ") {
-                        if let Some(episode_str) = file_name_str.split_whitespace().nth(1) {
-                            if let Ok(episode) = episode_str.replace(".mkv", "").parse::<u8>() {
-                                last_episode = last_episode.max(episode);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let library = LIBRARY.lock().await;
+
+    trace!(
+        "Files in {} Season {}: {:?}",
+        title,
+        season,
+        library.files_in(&season_dir).collect::<Vec<_>>()
+    );
+
+    // Files in any of the recognized naming styles count, so a library named by
+    // something else isn't overwritten
+    let last_episode = last_episode(library.files_in(&season_dir), season, patterns);
 
     trace!(
         "Last episode in {} Season {}: {}",
@@ -423,14 +816,823 @@ pub async fn get_last_episode_in_dir(title: &str, season: u8) -> Result<u8> {
         last_episode
     );
 
-    Ok(last_episode)
+    last_episode
+}
+
+/// Parses a duration as reported by `MakeMKV`, e.g. "1:45:12", into seconds.
+///
+/// Returns `None` if the duration isn't in `hours:minutes:seconds`.
+#[must_use]
+pub fn parse_length(length: &str) -> Option<u32> {
+    let mut parts = length.trim().splitn(3, ':').map(str::parse::<u32>);
+    let (Some(Ok(hours)), Some(Ok(minutes)), Some(Ok(seconds))) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Parses a size as reported by `MakeMKV`, e.g. "4.7 GB", into bytes.
+///
+/// Returns `None` if the size isn't a number followed by a known unit.
+#[must_use]
+pub fn parse_size(size: &str) -> Option<u64> {
+    let (value, unit) = size.trim().split_once(' ')?;
+    let value: f64 = value.replace(',', ".").parse().ok()?;
+
+    // MakeMKV reports sizes in binary units, despite the names
+    let multiplier: u64 = match unit.trim() {
+        "B" => 1,
+        "KB" => 1024,
+        "MB" => 1024 * 1024,
+        "GB" => 1024 * 1024 * 1024,
+        "TB" => 1024 * 1024 * 1024 * 1024,
+        _ => return None,
+    };
+
+    Some((value * multiplier as f64) as u64)
+}
+
+/// The chapters a title is split before to get an episode every `chapters_per_episode`
+/// chapters, numbered from 1 as `mkvmerge` expects. Empty if the title is a single episode.
+#[must_use]
+pub fn chapter_split_points(total_chapters: u16, chapters_per_episode: u16) -> Vec<u16> {
+    if chapters_per_episode == 0 {
+        return Vec::new();
+    }
+
+    (1..)
+        .map_while(|episode: u16| {
+            episode
+                .checked_mul(chapters_per_episode)
+                .and_then(|chapter| chapter.checked_add(1))
+        })
+        .take_while(|&chapter| chapter <= total_chapters)
+        .collect()
+}
+
+/// Splits a ripped title into a file every `chapters_per_episode` chapters with
+/// `mkvmerge`, for discs that store a whole season as one long title. The episodes are
+/// saved to `output_dir` and returned in order.
+///
+/// # Errors
+///
+/// Returns a `MakeMkvError` if `mkvmerge` can't be run, or fails to read or split the title.
+pub async fn split_by_chapters(
+    file: &Path,
+    chapters_per_episode: u16,
+    output_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let split_error = |reason: String| {
+        error!("Failed to split {}: {}", file.display(), reason);
+        MakeMkvError::SplitError(reason)
+    };
+
+    // The chapter count comes from mkvmerge's own identification, so it matches the
+    // chapters it will split on
+    let output = Command::new(
+        "mkvmerge",
+        vec!["-J".to_string(), file.to_string_lossy().to_string()],
+    )
+    .timeout(PROBE_TIMEOUT)
+    .execute()
+    .await
+    .map_err(|e| split_error(e.to_string()))?;
+
+    let identification: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| split_error(format!("unreadable mkvmerge output: {e}")))?;
+    let total_chapters = identification["chapters"]
+        .as_array()
+        .and_then(|chapters| chapters.first())
+        .and_then(|chapters| chapters["num_entries"].as_u64())
+        .and_then(|chapters| u16::try_from(chapters).ok())
+        .unwrap_or(0);
+
+    let split_points = chapter_split_points(total_chapters, chapters_per_episode);
+    if split_points.is_empty() {
+        warn!(
+            "{} has {} chapters, too few to split every {}",
+            file.display(),
+            total_chapters,
+            chapters_per_episode
+        );
+        return Ok(vec![file.to_path_buf()]);
+    }
+
+    debug!(
+        "Splitting {} with {} chapters before chapters {:?}",
+        file.display(),
+        total_chapters,
+        split_points
+    );
+
+    let split_points = split_points
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<String>>()
+        .join(",");
+
+    // mkvmerge numbers the files it splits into, e.g. episode-001.mkv
+    let output = Command::new(
+        "mkvmerge",
+        vec![
+            "-q".to_string(),
+            "-o".to_string(),
+            output_dir.join("episode.mkv").to_string_lossy().to_string(),
+            "--split".to_string(),
+            format!("chapters:{split_points}"),
+            file.to_string_lossy().to_string(),
+        ],
+    )
+    .execute()
+    .await
+    .map_err(|e| split_error(e.to_string()))?;
+
+    // Exit code 1 is only warnings, the files are still written
+    if output.status.code().is_none_or(|code| code > 1) {
+        return Err(split_error(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ));
+    }
+
+    let mut episodes: Vec<PathBuf> = std::fs::read_dir(output_dir)
+        .map_err(|e| split_error(e.to_string()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "mkv"))
+        .collect();
+    episodes.sort();
+
+    info!("Split {} into {} episodes", file.display(), episodes.len());
+    Ok(episodes)
+}
+
+/// A track of a ripped file, as `mkvmerge -J` lists it.
+///
+/// # Fields
+///
+/// * `id` - The id of the track, counting every track from 0.
+/// * `kind` - What the track is, `video`, `audio` or `subtitles`.
+/// * `language` - The ISO 639-2 code of its language, `und` when it has none.
+/// * `forced` - Whether the file already flags it as forced.
+/// * `codec_id` - The Matroska codec of the track, e.g. `S_HDMV/PGS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    pub id: u64,
+    pub kind: String,
+    pub language: String,
+    pub forced: bool,
+    pub codec_id: String,
+}
+
+/// Reads the tracks out of the JSON `mkvmerge -J` prints
+fn parse_tracks(identification: &serde_json::Value) -> Vec<Track> {
+    identification["tracks"]
+        .as_array()
+        .map(|tracks| {
+            tracks
+                .iter()
+                .filter_map(|track| {
+                    let properties = &track["properties"];
+                    Some(Track {
+                        id: track["id"].as_u64()?,
+                        kind: track["type"].as_str()?.to_string(),
+                        language: properties["language"].as_str().unwrap_or("und").to_string(),
+                        forced: properties["forced_track"].as_bool().unwrap_or_default(),
+                        codec_id: properties["codec_id"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Runs an `MKVToolNix` command, failing on errors but not on warnings. Returns what it
+/// printed.
+pub(super) async fn run_mkvtoolnix(command: Command) -> Result<Vec<u8>> {
+    let output = command.execute().await?;
+
+    // Exit code 1 is only warnings, the work is still done
+    if output.status.code().is_none_or(|code| code > 1) {
+        return Err(MakeMkvError::CommandExecutionError(format!(
+            "{} failed: {}",
+            command.command,
+            String::from_utf8_lossy(&output.stdout).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// The tracks of a ripped file, as `mkvmerge -J` lists them
+pub(super) async fn identify_tracks(file: &Path) -> Result<Vec<Track>> {
+    let stdout = run_mkvtoolnix(
+        Command::new(
+            "mkvmerge",
+            vec!["-J".to_string(), file.to_string_lossy().to_string()],
+        )
+        .timeout(PROBE_TIMEOUT),
+    )
+    .await?;
+    let identification: serde_json::Value = serde_json::from_slice(&stdout)
+        .map_err(|e| MakeMkvError::ParseError(format!("unreadable mkvmerge output: {e}")))?;
+    Ok(parse_tracks(&identification))
+}
+
+/// Remuxes a ripped 3D title into `output_dir` without its 3D video track, leaving the 2D
+/// base view that every player can play. Returns the remuxed file, or `file` itself if it
+/// has no 3D track.
+///
+/// # Errors
+///
+/// Returns a `MakeMkvError` if `mkvmerge` can't be run, or fails to read or remux the file.
+pub async fn keep_base_view(file: &Path, output_dir: &Path) -> Result<PathBuf> {
+    let mvc_tracks: Vec<String> = identify_tracks(file)
+        .await?
+        .iter()
+        .filter(|track| track.kind == "video" && track.codec_id == robot::CODEC_ID_MVC)
+        .map(|track| track.id.to_string())
+        .collect();
+    if mvc_tracks.is_empty() {
+        debug!("{} has no 3D track to drop", file.display());
+        return Ok(file.to_path_buf());
+    }
+
+    let Some(file_name) = file.file_name() else {
+        return Err(MakeMkvError::FileNotFoundError(file.display().to_string()));
+    };
+    let base_view = output_dir.join(file_name);
+
+    // Remuxing reads the whole title, so it gets as long as it needs like a split
+    run_mkvtoolnix(Command::new(
+        "mkvmerge",
+        vec![
+            "-q".to_string(),
+            "-o".to_string(),
+            base_view.to_string_lossy().to_string(),
+            "--video-tracks".to_string(),
+            format!("!{}", mvc_tracks.join(",")),
+            file.to_string_lossy().to_string(),
+        ],
+    ))
+    .await?;
+
+    info!("Dropped the 3D track of {}", file.display());
+    Ok(base_view)
+}
+
+/// Writes `title` into the title tag of a saved file with `mkvpropedit`, so players show
+/// the note attached to the rip, such as the edition of the disc.
+///
+/// # Errors
+///
+/// Returns a `MakeMkvError` if `mkvpropedit` can't be run or fails to edit the file.
+pub async fn tag_title(file: &Path, title: &str) -> Result<()> {
+    let output = Command::new(
+        "mkvpropedit",
+        vec![
+            file.to_string_lossy().to_string(),
+            "--edit".to_string(),
+            "info".to_string(),
+            "--set".to_string(),
+            format!("title={title}"),
+        ],
+    )
+    .timeout(PROBE_TIMEOUT)
+    .execute()
+    .await?;
+
+    // Exit code 1 is only warnings, the tag is still written
+    if output.status.code().is_none_or(|code| code > 1) {
+        return Err(MakeMkvError::CommandExecutionError(format!(
+            "mkvpropedit failed on {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stdout).trim()
+        )));
+    }
+
+    debug!("Tagged {} with title {:?}", file.display(), title);
+    Ok(())
+}
+
+/// Opens the tray of a drive with `eject`, signalling that the disc can be swapped.
+///
+/// # Errors
+///
+/// Returns a `MakeMkvError` if `eject` can't be run or fails to open the tray.
+pub async fn eject_drive(drive_number: u8) -> Result<()> {
+    let (host, device) = drive_location(drive_number);
+
+    let output = ripper(host.as_deref()).eject(&device).await.map_err(|e| {
+        error!("Failed to execute eject command: {}", e);
+        MakeMkvError::EjectError(drive_number, e.to_string())
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        error!("Failed to eject drive {}: {}", drive_number, stderr);
+        return Err(MakeMkvError::EjectError(drive_number, stderr));
+    }
+
+    info!("Ejected drive {}", drive_number);
+    Ok(())
+}
+
+/// The read speed a drive is slowed to for a retry, as a multiple of the base speed of the
+/// disc. Slower reads give a scratched disc a better chance.
+pub(super) const RETRY_READ_SPEED: u32 = 4;
+
+/// Sets the read speed of the drive at a device on this machine, `None` for its fastest.
+/// Only possible on Linux, elsewhere the drive keeps its speed. Failing to is only logged,
+/// a drive that ignores it still rips.
+pub(super) async fn set_read_speed(device: &str, speed: Option<u32>) {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+
+    // A speed of 0 is the drive's fastest
+    let speed = speed.unwrap_or(0).to_string();
+    match Command::new("eject", vec!["-x".to_string(), speed, device.to_string()])
+        .timeout(PROBE_TIMEOUT)
+        .execute()
+        .await
+    {
+        Ok(output) if output.status.success() => debug!("Set the read speed of {}", device),
+        Ok(output) => warn!(
+            "Failed to set the read speed of {}: {}",
+            device,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => warn!("Failed to set the read speed of {}: {}", device, e),
+    }
+}
+
+/// The command that opens the tray of the drive at a device on this platform
+pub(super) fn eject_command(device: &str) -> (&'static str, Vec<String>) {
+    if cfg!(target_os = "windows") {
+        // Windows has no eject command, the shell's eject verb is what Explorer uses. The
+        // drive is looked up as 'D:\' in This PC, special folder 17
+        let script = format!(
+            "(New-Object -ComObject Shell.Application).Namespace(17).ParseName('{}\\').InvokeVerb('Eject')",
+            device.trim_end_matches('\\')
+        );
+        (
+            "powershell",
+            vec!["-NoProfile".to_string(), "-Command".to_string(), script],
+        )
+    } else if cfg!(target_os = "macos") {
+        // MakeMKV lists the raw device, diskutil wants the block device
+        (
+            "diskutil",
+            vec![
+                "eject".to_string(),
+                device.replace("/dev/rdisk", "/dev/disk"),
+            ],
+        )
+    } else {
+        ("eject", vec![device.to_string()])
+    }
+}
+
+/// Checks if the device of a drive is still there, USB drives that are unplugged take
+/// their `/dev/sr` node with them.
+///
+/// Only Linux keeps a device for an empty drive, elsewhere a drive is always assumed to be
+/// connected and disconnects are spotted from the output of `MakeMKV` alone.
+#[must_use]
+pub fn drive_connected(drive_number: u8) -> bool {
+    // Drives on agents can't be checked from here, the agent reports a disconnect as a
+    // read error
+    let (host, device) = drive_location(drive_number);
+    host.is_some() || !cfg!(target_os = "linux") || Path::new(&device).exists()
+}
+
+/// Handles a drive that went away mid command, refreshing the drive list so it is logged
+/// which drives are left. Returns the error the disconnect is reported with.
+pub async fn drive_disconnected(drive_number: u8) -> MakeMkvError {
+    error!("Drive {} disconnected", drive_number);
+
+    match get_drives().await {
+        Ok(drives) => info!(
+            "Drives still connected: {:?}",
+            drives
+                .iter()
+                .map(|drive| drive.drive_number)
+                .collect::<Vec<u8>>()
+        ),
+        Err(e) => warn!(
+            "Failed to refresh drives after drive {} disconnected: {}",
+            drive_number, e
+        ),
+    }
+
+    MakeMkvError::DriveDisconnected(drive_number)
+}
+
+/// The capabilities a drive needs to read a disc, `uhd` for discs with 4K titles
+#[must_use]
+pub fn required_capabilities(disc_info: &DiscInfo) -> Vec<&'static str> {
+    let uhd = disc_info
+        .titles
+        .iter()
+        .any(|title| title.resolution.ends_with("x2160"));
+
+    if uhd {
+        vec!["uhd"]
+    } else {
+        Vec::new()
+    }
 }
 
-// Simple functioin to clean up the string
-fn clean_info(info: Vec<&str>) -> String {
-    clean_str(info.last().unwrap())
+/// Checks the drive a disc was scanned in can read it, going by the capabilities listed
+/// for it in the drive map. Drives with none listed are let through, as are discs that
+/// haven't been scanned.
+///
+/// # Errors
+///
+/// Returns a `MakeMkvError` naming the drives that can read the disc, if the drive can't.
+pub async fn check_capabilities(drive_number: u8) -> Result<()> {
+    let required = SCANNED_TITLES
+        .lock()
+        .await
+        .get(&drive_number)
+        .map(required_capabilities)
+        .unwrap_or_default();
+
+    let drive_map = DRIVE_MAP
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let capabilities = drive_map.capabilities(drive_number);
+    if capabilities.is_empty() {
+        return Ok(());
+    }
+
+    match required
+        .into_iter()
+        .find(|required| !capabilities.iter().any(|c| c == required))
+    {
+        Some(missing) => {
+            warn!("Drive {} can't read {} discs", drive_number, missing);
+            Err(MakeMkvError::MissingCapability(
+                drive_number,
+                missing.to_uppercase(),
+                drive_map.drives_with(missing),
+            ))
+        }
+        None => Ok(()),
+    }
 }
 
-fn clean_str(s: &str) -> String {
-    s.trim().replace("\"", "").to_string()
+/// The prefix given to the temporary directories `MakeMKV` rips into
+pub const TEMP_DIR_PREFIX: &str = "makemkv_output";
+
+/// Removes temporary rip directories in the output directory that haven't been modified
+/// for at least `max_age`, returning the number of bytes reclaimed.
+///
+/// Failed or crashed rips can leave these behind, anything in `active` belongs to a rip
+/// that is still running and is always skipped. Errors are logged rather than returned,
+/// a directory that can't be removed now will be tried again on the next cleanup.
+#[must_use]
+pub fn remove_stale_temp_dirs<S: BuildHasher>(
+    output_dir: &Path,
+    max_age: Duration,
+    active: &HashSet<PathBuf, S>,
+) -> u64 {
+    let entries = match std::fs::read_dir(output_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to read output directory for cleanup: {}", e);
+            return 0;
+        }
+    };
+
+    let mut reclaimed = 0;
+
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        let is_temp_dir = path.is_dir()
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(TEMP_DIR_PREFIX));
+
+        if !is_temp_dir || active.contains(&path) {
+            continue;
+        }
+
+        // A directory whose age can't be read is left alone in case it is still in use
+        let age = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+
+        if age.is_none_or(|age| age < max_age) {
+            trace!("Skipping recent temporary directory {}", path.display());
+            continue;
+        }
+
+        let size = fs_extra::dir::get_size(&path).unwrap_or_default();
+
+        match std::fs::remove_dir_all(&path) {
+            Ok(()) => {
+                debug!("Removed stale temporary directory {}", path.display());
+                reclaimed += size;
+            }
+            Err(e) => warn!("Failed to remove {}: {}", path.display(), e),
+        }
+    }
+
+    reclaimed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn commands_get_env_dir_and_stdin() {
+        let output = Command::new(
+            "sh",
+            vec![
+                "-c".to_string(),
+                "echo \"$GREETING $(pwd) $(cat)\"; echo oops >&2".to_string(),
+            ],
+        )
+        .env("GREETING", "hello")
+        .current_dir("/")
+        .stdin("world")
+        .execute()
+        .await
+        .expect("sh runs");
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello / world\n");
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "oops\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn commands_send_lines_as_they_are_printed() {
+        let (sender, mut lines) = mpsc::unbounded_channel();
+        let output = Command::new(
+            "sh",
+            vec!["-c".to_string(), "echo PRGV:1,2,4; echo done".to_string()],
+        )
+        .stdout_lines(sender)
+        .execute()
+        .await
+        .expect("sh runs");
+
+        assert_eq!(lines.recv().await.as_deref(), Some("PRGV:1,2,4"));
+        assert_eq!(lines.recv().await.as_deref(), Some("done"));
+        assert_eq!(lines.recv().await, None);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "PRGV:1,2,4\ndone\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn commands_are_killed_after_their_timeout() {
+        let result = Command::new("sleep", vec!["5".to_string()])
+            .timeout(Duration::from_millis(50))
+            .execute()
+            .await;
+
+        assert_eq!(
+            result.map(|_| ()).map_err(|e| e.kind()),
+            Err(std::io::ErrorKind::TimedOut)
+        );
+    }
+
+    #[test]
+    fn parses_names_with_commas() {
+        let stdout = [
+            r#"CINFO:2,0,"MY, MOVIE (2019)""#,
+            r#"TINFO:0,2,0,"MY, MOVIE (2019)""#,
+            r#"TINFO:0,8,0,"24""#,
+            r#"TINFO:0,9,0,"1:45:12""#,
+            r#"TINFO:0,10,0,"23.5 GB""#,
+            r#"TINFO:0,16,0,"00800.mpls""#,
+            r#"TINFO:0,26,0,"1,2,3-5""#,
+            r#"SINFO:0,0,19,0,"1920x1080""#,
+            r#"SINFO:0,0,20,0,"16:9""#,
+            r#"SINFO:0,0,21,0,"23.976 (24000/1001)""#,
+        ]
+        .join("\n");
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        };
+
+        let disc = parse_disc_info(&output).expect("disc info parses");
+        assert_eq!(disc.disc_name, "MY, MOVIE (2019)");
+        assert_eq!(disc.titles.len(), 1);
+
+        let title = &disc.titles[0];
+        assert_eq!(title.title_id, 1);
+        assert_eq!(title.chapters, 24);
+        assert_eq!(title.length, "1:45:12");
+        assert_eq!(title.size, "23.5 GB");
+        assert_eq!(title.source_file, "00800.mpls");
+        assert_eq!(title.segments_map, "1,2,3-5");
+        assert_eq!(title.resolution, "1920x1080");
+        assert_eq!(title.frame_rate, "23.976 (24000/1001)");
+    }
+
+    #[tokio::test]
+    async fn counts_episodes_in_the_chosen_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut makemkv = MakeMkv::new(&dir.path().join("main").to_string_lossy());
+        makemkv.libraries = vec![("kids".to_string(), dir.path().join("kids"))];
+
+        let mut episode = makemkv
+            .library_root(Some("kids"))
+            .join(makemkv.naming.show_path("Bluey", 1, 4, None, None))
+            .into_os_string();
+        episode.push(".mkv");
+        LIBRARY.lock().await.add(Path::new(&episode));
+
+        assert_eq!(
+            get_last_episode_in_dir(&makemkv, Some("kids"), "Bluey", 1).await,
+            4
+        );
+        assert_eq!(get_last_episode_in_dir(&makemkv, None, "Bluey", 1).await, 0);
+    }
+
+    #[test]
+    fn starts_each_title_empty() {
+        let stdout = [
+            r#"TINFO:0,9,0,"1:45:12""#,
+            r#"TINFO:0,16,0,"00800.mpls""#,
+            r#"TINFO:0,24,0,"3""#,
+            r#"TINFO:0,26,0,"1,2,3-5""#,
+            r#"SINFO:0,0,21,0,"23.976 (24000/1001)""#,
+            r#"TINFO:1,9,0,"0:42:10""#,
+            r#"SINFO:1,0,21,0,"25""#,
+        ]
+        .join("\n");
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        };
+
+        let disc = parse_disc_info(&output).expect("disc info parses");
+        assert_eq!(disc.titles.len(), 2);
+        assert_eq!(disc.titles[0].original_title_id, Some(3));
+
+        let title = &disc.titles[1];
+        assert_eq!(title.title_id, 2);
+        assert_eq!(title.length, "0:42:10");
+        assert_eq!(title.source_file, "");
+        assert_eq!(title.original_title_id, None);
+        assert_eq!(title.segments_map, "");
+    }
+
+    #[test]
+    fn collects_audio_languages() {
+        let stdout = [
+            r#"TINFO:0,9,0,"0:42:10""#,
+            r#"SINFO:0,0,1,6201,"Video""#,
+            r#"SINFO:0,0,21,0,"25""#,
+            r#"SINFO:0,1,1,6202,"Audio""#,
+            r#"SINFO:0,1,4,0,"English""#,
+            r#"SINFO:0,2,1,6202,"Audio""#,
+            r#"SINFO:0,2,4,0,"French""#,
+            r#"SINFO:0,3,1,6202,"Audio""#,
+            r#"SINFO:0,3,4,0,"English""#,
+            r#"SINFO:0,4,1,6203,"Subtitles""#,
+            r#"SINFO:0,4,4,0,"German""#,
+            r#"TINFO:1,9,0,"0:41:55""#,
+            r#"SINFO:1,0,1,6201,"Video""#,
+            r#"SINFO:1,0,21,0,"25""#,
+        ]
+        .join("\n");
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        };
+
+        let disc = parse_disc_info(&output).expect("disc info parses");
+        assert_eq!(disc.titles[0].audio_languages, vec!["English", "French"]);
+        assert!(disc.titles[1].audio_languages.is_empty());
+    }
+
+    #[test]
+    fn reads_mkvmerge_tracks() {
+        let identification = serde_json::json!({
+            "tracks": [
+                {"id": 0, "type": "video", "properties": {"codec_id": "V_MPEG4/ISO/AVC"}},
+                {"id": 1, "type": "video", "properties": {"codec_id": "V_MPEG4/ISO/MVC"}},
+                {"id": 2, "type": "subtitles", "properties": {
+                    "language": "eng", "forced_track": true, "codec_id": "S_HDMV/PGS"
+                }}
+            ]
+        });
+        let tracks = parse_tracks(&identification);
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(tracks[0].language, "und");
+        assert_eq!(tracks[1].codec_id, robot::CODEC_ID_MVC);
+        assert!(tracks[2].forced);
+    }
+
+    #[test]
+    fn collects_forced_subtitles() {
+        let stdout = [
+            r#"TINFO:0,9,0,"2:42:10""#,
+            r#"SINFO:0,0,1,6201,"Video""#,
+            r#"SINFO:0,0,21,0,"23.976 (24000/1001)""#,
+            r#"SINFO:0,1,1,6202,"Audio""#,
+            r#"SINFO:0,1,3,0,"eng""#,
+            r#"SINFO:0,2,1,6203,"Subtitles""#,
+            r#"SINFO:0,2,3,0,"eng""#,
+            r#"SINFO:0,3,1,6203,"Subtitles""#,
+            r#"SINFO:0,3,3,0,"eng""#,
+            r#"SINFO:0,3,22,0,"6144""#,
+        ]
+        .join("\n");
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        };
+
+        let disc = parse_disc_info(&output).expect("disc info parses");
+        let subtitles = &disc.titles[0].subtitles;
+        assert_eq!(subtitles.len(), 2);
+        assert_eq!(subtitles[1].language, "eng");
+        assert!(!subtitles[0].forced);
+        assert!(subtitles[1].forced);
+        assert!(!disc.titles[0].three_d);
+    }
+
+    #[test]
+    fn detects_3d_titles() {
+        let stdout = [
+            r#"TINFO:0,9,0,"2:42:10""#,
+            r#"SINFO:0,0,1,6201,"Video""#,
+            r#"SINFO:0,0,5,0,"V_MPEG4/ISO/AVC""#,
+            r#"SINFO:0,0,21,0,"23.976 (24000/1001)""#,
+            r#"SINFO:0,1,1,6201,"Video""#,
+            r#"SINFO:0,1,5,0,"V_MPEG4/ISO/MVC""#,
+            r#"SINFO:0,1,21,0,"23.976 (24000/1001)""#,
+            r#"TINFO:1,9,0,"0:12:00""#,
+            r#"SINFO:1,0,1,6201,"Video""#,
+            r#"SINFO:1,0,21,0,"23.976 (24000/1001)""#,
+        ]
+        .join("\n");
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        };
+
+        let disc = parse_disc_info(&output).expect("disc info parses");
+        assert_eq!(disc.titles.len(), 2);
+        assert!(disc.titles[0].three_d);
+        assert!(!disc.titles[1].three_d);
+    }
+
+    #[test]
+    fn parses_lengths() {
+        assert_eq!(parse_length("1:45:12"), Some(6312));
+        assert_eq!(parse_length("0:22:05"), Some(1325));
+        assert_eq!(parse_length("22:05"), None);
+        assert_eq!(parse_length(""), None);
+    }
+
+    #[test]
+    fn splits_every_n_chapters() {
+        assert_eq!(chapter_split_points(12, 3), vec![4, 7, 10]);
+    }
+
+    #[test]
+    fn leftover_chapters_are_their_own_episode() {
+        assert_eq!(chapter_split_points(13, 3), vec![4, 7, 10, 13]);
+    }
+
+    #[test]
+    fn uhd_discs_need_uhd_drives() {
+        let disc = |resolution: &str| DiscInfo {
+            disc_name: String::new(),
+            titles: vec![Title {
+                resolution: resolution.to_string(),
+                ..Title::default()
+            }],
+        };
+        assert_eq!(required_capabilities(&disc("3840x2160")), vec!["uhd"]);
+        assert!(required_capabilities(&disc("1920x1080")).is_empty());
+    }
+
+    #[test]
+    fn short_titles_are_not_split() {
+        assert!(chapter_split_points(3, 3).is_empty());
+        assert!(chapter_split_points(0, 3).is_empty());
+        assert!(chapter_split_points(12, 0).is_empty());
+    }
 }
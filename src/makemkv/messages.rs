@@ -0,0 +1,385 @@
+//! # `MakeMKV` Message Codes
+//!
+//! When `makemkvcon` is run with the `-r` (robot) flag, every user facing message is
+//! printed as a `MSG:` line containing a numeric message code, the formatted message,
+//! the format string, and the parameters that were substituted into it:
+//!
+//! ```text
+//! MSG:5037,516,2,"Copy complete. 0 titles saved, 1 failed.","Copy complete. %1 titles saved, %2 failed.","0","1"
+//! ```
+//!
+//! This module parses those lines and maps the known codes into [`MessageKind`] values,
+//! which in turn are converted into typed [`MakeMkvError`] variants by
+//! `check_makemkv_output`.
+//!
+//! The codes were collected from `MakeMKV` logs and other makemkv related projects, `MakeMKV`
+//! does not publish them. Unknown codes are kept as [`MessageKind::Info`] so they can still
+//! be logged.
+
+use super::errors::MakeMkvError;
+use super::robot::{parse_line, RobotLine};
+
+/// "`MakeMKV` v1.x.x linux(x64-release) started"
+pub const MSG_APP_STARTED: u32 = 1005;
+/// "Error '%1' occurred while reading '%2' at offset '%3'"
+pub const MSG_READ_ERROR: u32 = 2003;
+/// "Hash check failed for file %1 at offset %2, file is corrupt"
+pub const MSG_HASH_CHECK_FAILED: u32 = 2024;
+/// "Failed to save title %1 to file %2"
+pub const MSG_TITLE_SAVE_FAILED: u32 = 5003;
+/// "Failed to open disc"
+pub const MSG_DISC_OPEN_FAILED: u32 = 5010;
+/// "Operation successfully completed"
+pub const MSG_OPERATION_COMPLETE: u32 = 5011;
+/// "Saving %1 titles into directory %2"
+pub const MSG_SAVING_TITLES: u32 = 5014;
+/// "This application version is too old. Please download the latest version ..."
+pub const MSG_VERSION_TOO_OLD: u32 = 5021;
+/// "Copy complete. %1 titles saved."
+pub const MSG_COPY_COMPLETE: u32 = 5036;
+/// "Copy complete. %1 titles saved, %2 failed."
+pub const MSG_COPY_COMPLETE_WITH_FAILURES: u32 = 5037;
+/// "The program can't find any usable optical drives."
+pub const MSG_NO_DRIVES: u32 = 5050;
+/// "Evaluation period has expired. Shareware functionality is no longer available."
+pub const MSG_EVALUATION_EXPIRED: u32 = 5055;
+/// "Your temporary key has expired and was removed"
+pub const MSG_KEY_EXPIRED: u32 = 5095;
+
+/// A single `MSG:` line from the robot output of `makemkvcon`.
+///
+/// # Fields
+///
+/// * `code` - The `MakeMKV` message code.
+/// * `text` - The fully formatted message, as it would be shown to a user.
+/// * `params` - The parameters substituted into the message format string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub code: u32,
+    pub text: String,
+    pub params: Vec<String>,
+}
+
+/// What a [`Message`] means for the rip it was printed during.
+#[derive(Debug, PartialEq)]
+pub enum MessageKind {
+    /// Purely informational, nothing to act upon.
+    Info,
+    /// The copy finished, with the number of titles saved and failed.
+    CopyComplete { saved: u32, failed: u32 },
+    /// A message that means the rip has failed, or is failing.
+    Error(MakeMkvError),
+}
+
+impl Message {
+    /// Parses a single robot output line, returning `None` if it is not a `MSG:` line.
+    #[must_use]
+    pub fn parse(line: &str) -> Option<Message> {
+        match parse_line(line)? {
+            RobotLine::Message(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Maps the message code (falling back to the message text for codes that have moved
+    /// between `MakeMKV` releases) to what it means for the rip.
+    #[must_use]
+    pub fn kind(&self) -> MessageKind {
+        match self.code {
+            MSG_COPY_COMPLETE => MessageKind::CopyComplete {
+                saved: self.param_u32(0),
+                failed: 0,
+            },
+            MSG_COPY_COMPLETE_WITH_FAILURES => MessageKind::CopyComplete {
+                saved: self.param_u32(0),
+                failed: self.param_u32(1),
+            },
+            MSG_READ_ERROR => MessageKind::Error(MakeMkvError::ReadError(self.text.clone())),
+            MSG_HASH_CHECK_FAILED => {
+                MessageKind::Error(MakeMkvError::HashCheckFailed(self.param(0)))
+            }
+            MSG_TITLE_SAVE_FAILED => MessageKind::Error(MakeMkvError::FailedToSaveDisc),
+            MSG_DISC_OPEN_FAILED => MessageKind::Error(MakeMkvError::DiscOpenFailed),
+            MSG_VERSION_TOO_OLD => MessageKind::Error(MakeMkvError::VersionTooOld),
+            MSG_NO_DRIVES => MessageKind::Error(MakeMkvError::NoDrivesFound),
+            MSG_EVALUATION_EXPIRED | MSG_KEY_EXPIRED => {
+                MessageKind::Error(MakeMkvError::RegistrationExpired)
+            }
+            _ if self.text.starts_with("Hash check failed") => {
+                MessageKind::Error(MakeMkvError::HashCheckFailed(self.param(0)))
+            }
+            _ => MessageKind::Info,
+        }
+    }
+
+    fn param(&self, index: usize) -> String {
+        self.params.get(index).cloned().unwrap_or_default()
+    }
+
+    fn param_u32(&self, index: usize) -> u32 {
+        self.params
+            .get(index)
+            .and_then(|param| param.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+/// What the OS and `MakeMKV` print when a drive goes away mid command. `MakeMKV` has no
+/// message code of its own for it, the OS error ends up inside of a read error instead.
+/// "No such file or directory" is left out, it is also printed for missing output files,
+/// a missing `/dev/sr` node is checked for separately.
+const DEVICE_GONE_ERRORS: [&str; 3] = [
+    "No such device",
+    "Device not configured",
+    "device was disconnected",
+];
+
+/// Checks if the output of `makemkvcon` says the drive it was reading from is gone.
+#[must_use]
+pub fn mentions_device_gone(stdout: &str) -> bool {
+    DEVICE_GONE_ERRORS
+        .iter()
+        .any(|error| stdout.to_lowercase().contains(&error.to_lowercase()))
+}
+
+/// Finds the version of `MakeMKV` in the robot output of `makemkvcon`, from the message it
+/// prints when it starts, e.g. `1.17.7` from `MakeMKV v1.17.7 linux(x64-release) started`
+#[must_use]
+pub fn makemkv_version(stdout: &str) -> Option<String> {
+    parse_messages(stdout)
+        .into_iter()
+        .find(|message| message.code == MSG_APP_STARTED)?
+        .text
+        .split_whitespace()
+        .find_map(|word| {
+            word.strip_prefix('v')
+                .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .map(String::from)
+}
+
+/// Parses every `MSG:` line out of the robot output of `makemkvcon`.
+#[must_use]
+pub fn parse_messages(stdout: &str) -> Vec<Message> {
+    stdout.lines().filter_map(Message::parse).collect()
+}
+
+/// The segment files `MakeMKV` reported failed hash checks for, each listed once in the
+/// order they failed. A scratched segment usually fails at many offsets.
+#[must_use]
+pub fn hash_check_failures(stdout: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    for message in parse_messages(stdout) {
+        if let MessageKind::Error(MakeMkvError::HashCheckFailed(file)) = message.kind() {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_each_segment_that_failed_a_hash_check_once() {
+        let stdout = [
+            r#"MSG:2024,0,2,"Hash check failed for file 00055.m2ts at offset 1024, file is corrupt","Hash check failed for file %1 at offset %2, file is corrupt","00055.m2ts","1024""#,
+            r#"MSG:2024,0,2,"Hash check failed for file 00055.m2ts at offset 2048, file is corrupt","Hash check failed for file %1 at offset %2, file is corrupt","00055.m2ts","2048""#,
+            r#"MSG:2024,0,2,"Hash check failed for file 00012.m2ts at offset 0, file is corrupt","Hash check failed for file %1 at offset %2, file is corrupt","00012.m2ts","0""#,
+            r#"MSG:5036,0,1,"Copy complete. 1 titles saved.","Copy complete. %1 titles saved.","1""#,
+        ]
+        .join("\n");
+
+        assert_eq!(
+            hash_check_failures(&stdout),
+            vec!["00055.m2ts".to_string(), "00012.m2ts".to_string()]
+        );
+        assert!(hash_check_failures("").is_empty());
+    }
+
+    #[test]
+    fn finds_the_makemkv_version() {
+        let stdout = format!(
+            "{}\nDRV:0,2,999,1,\"BD-RE\",\"\",\"/dev/sr0\"",
+            msg(
+                MSG_APP_STARTED,
+                "MakeMKV v1.17.7 linux(x64-release) started",
+                &["v1.17.7", "linux(x64-release)"]
+            )
+        );
+        assert_eq!(makemkv_version(&stdout), Some("1.17.7".to_string()));
+        assert_eq!(makemkv_version("DRV:0,256,999,0,\"\",\"\",\"\""), None);
+    }
+
+    fn msg(code: u32, text: &str, params: &[&str]) -> String {
+        let params: Vec<String> = params.iter().map(|p| format!("\"{p}\"")).collect();
+        format!(
+            "MSG:{code},0,{},\"{text}\",\"{text}\",{}",
+            params.len(),
+            params.join(",")
+        )
+    }
+
+    /// Sample lines for every known code, and what they should map to
+    #[allow(clippy::too_many_lines)]
+    fn known_codes() -> Vec<(String, MessageKind)> {
+        vec![
+            (
+                msg(MSG_APP_STARTED, "MakeMKV v1.17.7 started", &["v1.17.7"]),
+                MessageKind::Info,
+            ),
+            (
+                msg(
+                    MSG_READ_ERROR,
+                    "Error 'Scsi error' occurred while reading",
+                    &["Scsi error"],
+                ),
+                MessageKind::Error(MakeMkvError::ReadError(
+                    "Error 'Scsi error' occurred while reading".to_string(),
+                )),
+            ),
+            (
+                msg(
+                    MSG_HASH_CHECK_FAILED,
+                    "Hash check failed for file 00055.m2ts",
+                    &["00055.m2ts", "1024"],
+                ),
+                MessageKind::Error(MakeMkvError::HashCheckFailed("00055.m2ts".to_string())),
+            ),
+            (
+                msg(
+                    MSG_TITLE_SAVE_FAILED,
+                    "Failed to save title 0 to file a.mkv",
+                    &["0", "a.mkv"],
+                ),
+                MessageKind::Error(MakeMkvError::FailedToSaveDisc),
+            ),
+            (
+                msg(MSG_DISC_OPEN_FAILED, "Failed to open disc", &[]),
+                MessageKind::Error(MakeMkvError::DiscOpenFailed),
+            ),
+            (
+                msg(
+                    MSG_OPERATION_COMPLETE,
+                    "Operation successfully completed",
+                    &[],
+                ),
+                MessageKind::Info,
+            ),
+            (
+                msg(
+                    MSG_SAVING_TITLES,
+                    "Saving 1 titles into directory /tmp",
+                    &["1", "/tmp"],
+                ),
+                MessageKind::Info,
+            ),
+            (
+                msg(
+                    MSG_VERSION_TOO_OLD,
+                    "This application version is too old.",
+                    &[],
+                ),
+                MessageKind::Error(MakeMkvError::VersionTooOld),
+            ),
+            (
+                msg(MSG_COPY_COMPLETE, "Copy complete. 1 titles saved.", &["1"]),
+                MessageKind::CopyComplete {
+                    saved: 1,
+                    failed: 0,
+                },
+            ),
+            (
+                msg(
+                    MSG_COPY_COMPLETE_WITH_FAILURES,
+                    "Copy complete. 2 titles saved, 1 failed.",
+                    &["2", "1"],
+                ),
+                MessageKind::CopyComplete {
+                    saved: 2,
+                    failed: 1,
+                },
+            ),
+            (
+                msg(
+                    MSG_NO_DRIVES,
+                    "The program can't find any usable optical drives.",
+                    &[],
+                ),
+                MessageKind::Error(MakeMkvError::NoDrivesFound),
+            ),
+            (
+                msg(
+                    MSG_EVALUATION_EXPIRED,
+                    "Evaluation period has expired.",
+                    &[],
+                ),
+                MessageKind::Error(MakeMkvError::RegistrationExpired),
+            ),
+            (
+                msg(
+                    MSG_KEY_EXPIRED,
+                    "Your temporary key has expired and was removed",
+                    &[],
+                ),
+                MessageKind::Error(MakeMkvError::RegistrationExpired),
+            ),
+            (
+                msg(
+                    4004,
+                    "Hash check failed for file 00012.m2ts at offset 0",
+                    &["00012.m2ts", "0"],
+                ),
+                MessageKind::Error(MakeMkvError::HashCheckFailed("00012.m2ts".to_string())),
+            ),
+            (msg(9999, "Something new", &[]), MessageKind::Info),
+        ]
+    }
+
+    #[test]
+    fn maps_every_known_code() {
+        for (line, expected) in known_codes() {
+            let message = Message::parse(&line).expect("line should parse");
+            assert_eq!(message.kind(), expected, "unexpected kind for {line}");
+        }
+    }
+
+    #[test]
+    fn keeps_commas_inside_quoted_text() {
+        let line = "MSG:5037,516,2,\"Copy complete. 0 titles saved, 1 failed.\",\"Copy complete. %1 titles saved, %2 failed.\",\"0\",\"1\"";
+        let message = Message::parse(line).unwrap();
+
+        assert_eq!(message.code, MSG_COPY_COMPLETE_WITH_FAILURES);
+        assert_eq!(message.text, "Copy complete. 0 titles saved, 1 failed.");
+        assert_eq!(message.params, vec!["0", "1"]);
+    }
+
+    #[test]
+    fn ignores_non_message_lines() {
+        let stdout = "PRGV:0,0,65536\nMSG:5010,0,0,\"Failed to open disc\",\"Failed to open disc\"\nDRV:0,2,999,1,\"BD\",\"DISC\",\"/dev/sr0\"";
+        let messages = parse_messages(stdout);
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].code, MSG_DISC_OPEN_FAILED);
+    }
+
+    #[test]
+    fn spots_unplugged_drives() {
+        let gone = msg(
+            MSG_READ_ERROR,
+            "Error 'No such device' occurred while reading '/dev/sr2'",
+            &["No such device"],
+        );
+        let scratched = msg(
+            MSG_READ_ERROR,
+            "Error 'Scsi error - MEDIUM ERROR' occurred while reading",
+            &["Scsi error"],
+        );
+
+        assert!(mentions_device_gone(&gone));
+        assert!(!mentions_device_gone(&scratched));
+    }
+}
@@ -1,7 +1,23 @@
+pub mod drive_map;
+pub mod episodes;
 pub mod errors;
+pub mod eta;
+pub mod events;
+pub mod heuristics;
+pub mod labels;
+pub mod logs;
 pub mod makemkv_core;
 pub mod makemkv_helpers;
+pub mod messages;
+pub mod naming;
 pub mod processes;
+pub mod rippers;
+pub mod robot;
+pub mod subtitles;
+pub mod transfer;
 
-pub use makemkv_core::{MakeMkv, Rip, RipType};
-pub use makemkv_helpers::{get_drives, get_last_episode_in_dir, get_title_info, DiscInfo, Title};
+pub use makemkv_core::{HashRecovery, MakeMkv, Rip, RipOutcome, RipType, SizeWarning};
+pub use makemkv_helpers::{
+    check_capabilities, eject_drive, get_drives, get_last_episode_in_dir, get_title_info,
+    parse_size, DiscInfo, DiscMedia, Title,
+};
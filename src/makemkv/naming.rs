@@ -0,0 +1,504 @@
+//! # Naming Templates
+//!
+//! Ripped titles are saved to a path built from a naming template, relative to the
+//! output directory. Templates are plain strings containing tokens that are replaced
+//! with details of the rip:
+//!
+//! - `{title}`: The title of the movie or show
+//! - `{year}`: The release year of a movie, if one was given
+//! - `{season}`: The season number of a show
+//! - `{episode}`: The episode number of a show
+//! - `{absolute}`: The absolute episode number of a show, for anime style libraries
+//! - `{season_folder}`: `Specials` for season 0, otherwise `Season {season}`
+//! - `{episode_title}`: The name of a show episode, when it was picked from Sonarr's list
+//!
+//! Numeric tokens can be zero padded with a width, e.g. `S{season:02}E{episode:02}` produces
+//! `S01E05`.
+//!
+//! Any text wrapped in square brackets is optional, and is dropped entirely when a token
+//! inside of it has no value. This allows the default movie template to produce
+//! `movies/Title (2019)/Title (2019)` when a year is given, and `movies/Title/Title` when
+//! it is not.
+//!
+//! The `.mkv` extension is added when the file is saved, so templates should not include it.
+//!
+//! Extras of a movie, such as a making of, aren't named by a template. They are saved by
+//! the name they were given in a `Featurettes` folder next to the movie, which Plex,
+//! Jellyfin and Emby all list as extras of the movie in that folder.
+//!
+//! Titles are typed in by users, so they are made path safe before going into a template:
+//! a title of `AC/DC` is saved as `AC-DC` rather than in a directory called `AC`, and one
+//! of `..` can't climb out of the output directory. What counts as safe depends on the
+//! `FilenameProfile`, so libraries on SMB shares read by Windows can be kept to names
+//! Windows accepts, e.g. without colons or trailing dots.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
+
+use super::makemkv_core::{Rip, RipType};
+use crate::{debug, warn};
+
+/// The default template for movies, `movies/{title}[ ({year})]/{title}[ ({year})]`
+pub const DEFAULT_MOVIE_TEMPLATE: &str = "movies/{title}[ ({year})]/{title}[ ({year})]";
+/// The default template for shows,
+/// `shows/{title}/Season {season}/Episode {episode}[ - {episode_title}]`
+pub const DEFAULT_SHOW_TEMPLATE: &str =
+    "shows/{title}/Season {season}/Episode {episode}[ - {episode_title}]";
+/// The template for movies imported by Radarr, each in its own directory so only that rip
+/// is imported
+pub const ARR_MOVIE_TEMPLATE: &str = "imports/{title}[ ({year})]/{title}[ ({year})]";
+/// The template for shows imported by Sonarr, named so Sonarr can parse the episode
+pub const ARR_SHOW_TEMPLATE: &str =
+    "imports/{title} - S{season:02}E{episode:02}/{title} - S{season:02}E{episode:02}";
+
+/// The template for movies in the layout Plex and Jellyfin expect
+pub const MEDIA_SERVER_MOVIE_TEMPLATE: &str = "Movies/{title}[ ({year})]/{title}[ ({year})]";
+/// The template for shows in the layout Plex and Jellyfin expect
+pub const MEDIA_SERVER_SHOW_TEMPLATE: &str =
+    "TV Shows/{title}/{season_folder}/{title} - S{season:02}E{episode:02}[ - {episode_title}]";
+/// The template for movies kept in a single directory
+pub const FLAT_MOVIE_TEMPLATE: &str = "movies/{title}[ ({year})]";
+/// The template for shows with every episode of a show in one directory
+pub const FLAT_SHOW_TEMPLATE: &str =
+    "shows/{title}/{title} - S{season:02}E{episode:02}[ - {episode_title}]";
+
+/// The folder extras are saved in, next to the movie they belong to
+pub const EXTRAS_FOLDER: &str = "Featurettes";
+
+/// The most characters a name is cut down to by `path_safe`. File systems allow 255 bytes,
+/// but names are joined with a year, episode or part number, and SMB shares count UTF-16.
+pub const MAX_NAME_LENGTH: usize = 100;
+
+/// Characters that can't be used in file names on Windows or SMB shares
+pub const RESERVED_CHARACTERS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Names Windows keeps for devices, with or without an extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Punctuation kept by the strict profile, along with ASCII letters, digits and spaces
+const STRICT_PUNCTUATION: [char; 9] = ['-', '_', '.', '(', ')', ',', '\'', '&', '!'];
+
+/// Global filename profile, the default until `set_filename_profile` is called
+static FILENAME_PROFILE: OnceLock<FilenameProfile> = OnceLock::new();
+
+/// The systems the names of ripped files have to work on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilenameProfile {
+    /// Linux and macOS, only `/` can't be used
+    Posix,
+    /// Windows and SMB shares, which also reject `\\ : * ? " < > |`, trailing dots and
+    /// spaces, and device names such as `CON`
+    #[default]
+    Windows,
+    /// Windows, limited to ASCII letters, digits, spaces and simple punctuation, for sync
+    /// tools and older devices. Accented letters are replaced with their plain ones.
+    Strict,
+}
+
+impl FilenameProfile {
+    /// Convert a profile name, such as `windows`, to its `FilenameProfile`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<FilenameProfile> {
+        match name.to_lowercase().as_str() {
+            "posix" => Some(FilenameProfile::Posix),
+            "windows" => Some(FilenameProfile::Windows),
+            "strict" => Some(FilenameProfile::Strict),
+            _ => None,
+        }
+    }
+
+    /// The characters the profile replaces with `-`, other than those `Strict` replaces
+    #[must_use]
+    pub fn reserved_characters(self) -> &'static [char] {
+        match self {
+            FilenameProfile::Posix => &RESERVED_CHARACTERS[..1],
+            FilenameProfile::Windows | FilenameProfile::Strict => &RESERVED_CHARACTERS,
+        }
+    }
+
+    /// Makes a name safe to use as a single file or directory name. Reserved characters
+    /// are replaced with `-`, e.g. the slash in `AC/DC`, control characters are dropped,
+    /// runs of dots are collapsed into one so `..` can't be made, leading dots and spaces
+    /// are trimmed, and the name is cut down to `MAX_NAME_LENGTH` characters. Windows
+    /// profiles also trim trailing dots and add `_` to device names.
+    #[must_use]
+    pub fn sanitize(self, name: &str) -> String {
+        let mut safe = String::with_capacity(name.len());
+        for c in name.chars().filter(|c| !c.is_control()) {
+            match c {
+                _ if self.reserved_characters().contains(&c) => safe.push('-'),
+                '.' if safe.ends_with('.') => {}
+                c if self == FilenameProfile::Strict
+                    && !(c.is_ascii_alphanumeric()
+                        || c == ' '
+                        || STRICT_PUNCTUATION.contains(&c)) =>
+                {
+                    match ascii_fold(c) {
+                        Some(folded) => safe.push_str(folded),
+                        None if safe.ends_with('-') => {}
+                        None => safe.push('-'),
+                    }
+                }
+                c => safe.push(c),
+            }
+        }
+
+        let trimmed = safe.trim_start_matches(['.', ' ']);
+        let shortened: String = trimmed.chars().take(MAX_NAME_LENGTH).collect();
+        if self == FilenameProfile::Posix {
+            return shortened.trim_end().to_string();
+        }
+
+        let mut safe = shortened.trim_end_matches(['.', ' ']).to_string();
+        let stem = safe.split('.').next().unwrap_or_default();
+        if RESERVED_NAMES.contains(&stem.to_uppercase().as_str()) {
+            safe.insert(stem.len(), '_');
+        }
+        safe
+    }
+}
+
+/// Set the systems the names of ripped files have to work on, this can only be done once
+/// at startup
+pub fn set_filename_profile(profile: FilenameProfile) {
+    debug!("Filename profile set to: {:?}", profile);
+    if FILENAME_PROFILE.set(profile).is_err() {
+        warn!("Filename profile was already set, ignoring");
+    }
+}
+
+/// The configured filename profile
+pub fn filename_profile() -> FilenameProfile {
+    *FILENAME_PROFILE.get_or_init(FilenameProfile::default)
+}
+
+/// The plain ASCII letters of an accented Latin letter, for the strict profile
+fn ascii_fold(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' => "A",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' => "E",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' => "O",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' => "Y",
+        'ç' => "c",
+        'Ç' => "C",
+        'ñ' => "n",
+        'Ñ' => "N",
+        'ß' => "ss",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        _ => return None,
+    })
+}
+
+/// The naming templates used to build destination paths for rips.
+///
+/// # Fields
+///
+/// * `movie` - The template used for movie rips.
+/// * `show` - The template used for show rips.
+#[derive(Debug, Clone)]
+pub struct NamingTemplate {
+    pub movie: String,
+    pub show: String,
+}
+
+impl Default for NamingTemplate {
+    fn default() -> Self {
+        NamingTemplate {
+            movie: DEFAULT_MOVIE_TEMPLATE.to_string(),
+            show: DEFAULT_SHOW_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl NamingTemplate {
+    /// Templates for the layout Plex and Jellyfin expect, with zero padded episode numbers
+    /// and season 0 as `Specials`.
+    #[must_use]
+    pub fn media_server() -> Self {
+        NamingTemplate {
+            movie: MEDIA_SERVER_MOVIE_TEMPLATE.to_string(),
+            show: MEDIA_SERVER_SHOW_TEMPLATE.to_string(),
+        }
+    }
+
+    /// Templates that keep every movie in one directory, and every episode of a show in
+    /// the show's directory.
+    #[must_use]
+    pub fn flat() -> Self {
+        NamingTemplate {
+            movie: FLAT_MOVIE_TEMPLATE.to_string(),
+            show: FLAT_SHOW_TEMPLATE.to_string(),
+        }
+    }
+
+    /// The templates used when rips are imported by Sonarr and Radarr, which then handle
+    /// the naming themselves.
+    #[must_use]
+    pub fn arr_import() -> Self {
+        NamingTemplate {
+            movie: ARR_MOVIE_TEMPLATE.to_string(),
+            show: ARR_SHOW_TEMPLATE.to_string(),
+        }
+    }
+
+    /// Builds the path of a movie, relative to the output directory and without an extension.
+    #[must_use]
+    pub fn movie_path(&self, title: &str, year: Option<u16>) -> PathBuf {
+        safe_path(&render(
+            &self.movie,
+            &[
+                ("title", Some(path_safe(title))),
+                ("year", year.map(|year| year.to_string())),
+            ],
+        ))
+    }
+
+    /// Builds the path of an extra of a movie, in the extras folder next to the movie,
+    /// relative to the output directory and without an extension.
+    #[must_use]
+    pub fn extra_path(&self, title: &str, year: Option<u16>, extra: &str) -> PathBuf {
+        let movie_path = self.movie_path(title, year);
+        movie_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(EXTRAS_FOLDER)
+            .join(path_safe(extra))
+    }
+
+    /// Builds the path of a show episode, relative to the output directory and without an
+    /// extension.
+    #[must_use]
+    pub fn show_path(
+        &self,
+        title: &str,
+        season: u8,
+        episode: u8,
+        absolute_episode: Option<u16>,
+        episode_title: Option<&str>,
+    ) -> PathBuf {
+        let season_folder = if season == 0 {
+            "Specials".to_string()
+        } else {
+            format!("Season {season}")
+        };
+
+        safe_path(&render(
+            &self.show,
+            &[
+                ("title", Some(path_safe(title))),
+                ("season", Some(season.to_string())),
+                ("season_folder", Some(season_folder)),
+                ("episode", Some(episode.to_string())),
+                (
+                    "absolute",
+                    absolute_episode.map(|absolute| absolute.to_string()),
+                ),
+                ("episode_title", episode_title.map(path_safe)),
+            ],
+        ))
+    }
+
+    /// Builds the path a rip will be saved to, relative to the output directory and
+    /// without an extension.
+    #[must_use]
+    pub fn rip_path(&self, rip: &Rip) -> PathBuf {
+        match rip.rip_type {
+            RipType::Movie => match &rip.extra {
+                Some(extra) => self.extra_path(&rip.title, rip.year, extra),
+                None => self.movie_path(&rip.title, rip.year),
+            },
+            RipType::Show {
+                season,
+                episode,
+                absolute_episode,
+            } => self.show_path(
+                &rip.title,
+                season,
+                episode,
+                absolute_episode,
+                rip.episode_title.as_deref(),
+            ),
+        }
+    }
+}
+
+/// Builds the path of a single part of a multi-file rip, e.g. `Movie - Part 2.mkv`
+#[must_use]
+pub fn part_path(destination_path: &Path, part: usize) -> PathBuf {
+    let stem = destination_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    destination_path.with_file_name(format!("{stem} - Part {part}.mkv"))
+}
+
+/// Makes a name safe to use as a single file or directory name on the systems of the
+/// configured `FilenameProfile`, see `FilenameProfile::sanitize`.
+#[must_use]
+pub fn path_safe(name: &str) -> String {
+    filename_profile().sanitize(name)
+}
+
+/// Makes each directory and file name of a rendered template safe, for anything the
+/// template itself adds around the tokens. `..` and the like are left for `stays_inside`
+/// to refuse, rather than quietly dropped.
+fn safe_path(rendered: &str) -> PathBuf {
+    Path::new(rendered)
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => PathBuf::from(path_safe(&name.to_string_lossy())),
+            component => PathBuf::from(component.as_os_str()),
+        })
+        .collect()
+}
+
+/// Whether a path relative to the output directory stays inside it, with no `..`, root
+/// or drive prefix. Titles are made path safe, but templates can still climb out.
+#[must_use]
+pub fn stays_inside(relative: &Path) -> bool {
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Replaces every `{token}` in the template with its value, dropping any `[optional]`
+/// sections that contain a token without a value.
+fn render(template: &str, tokens: &[(&str, Option<String>)]) -> String {
+    let mut rendered = String::new();
+    let mut section = String::new();
+    let mut section_complete = true;
+    let mut in_section = false;
+
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if !in_section => {
+                in_section = true;
+                section_complete = true;
+                section.clear();
+            }
+            ']' if in_section => {
+                in_section = false;
+                if section_complete {
+                    rendered.push_str(&section);
+                }
+            }
+            '{' => {
+                let token: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let (name, width) = match token.split_once(':') {
+                    Some((name, width)) => (name, width.parse().unwrap_or(0)),
+                    None => (token.as_str(), 0),
+                };
+                let value = tokens
+                    .iter()
+                    .find(|(token, _)| *token == name)
+                    .and_then(|(_, value)| value.as_ref())
+                    .map(|value| format!("{value:0>width$}"));
+
+                match (value, in_section) {
+                    (Some(value), true) => section.push_str(&value),
+                    (Some(value), false) => rendered.push_str(&value),
+                    (None, true) => section_complete = false,
+                    (None, false) => (),
+                }
+            }
+            _ if in_section => section.push(c),
+            _ => rendered.push(c),
+        }
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn makes_names_path_safe() {
+        assert_eq!(path_safe("AC/DC: Live"), "AC-DC- Live");
+        assert_eq!(path_safe("..//etc"), "--etc");
+        assert_eq!(path_safe("Movie...Title"), "Movie.Title");
+        assert_eq!(path_safe(".hidden"), "hidden");
+        assert_eq!(path_safe("Monsters, Inc. "), "Monsters, Inc");
+        assert_eq!(path_safe("Tab\tBreak\n"), "TabBreak");
+        assert_eq!(path_safe(&"a".repeat(150)).len(), MAX_NAME_LENGTH);
+        assert_eq!(path_safe("Heat (1995)"), "Heat (1995)");
+    }
+
+    #[test]
+    fn profiles_keep_what_their_systems_allow() {
+        let title = "Star Wars: Episode IV...";
+        assert_eq!(
+            FilenameProfile::Posix.sanitize(title),
+            "Star Wars: Episode IV."
+        );
+        assert_eq!(
+            FilenameProfile::Windows.sanitize(title),
+            "Star Wars- Episode IV"
+        );
+        assert_eq!(FilenameProfile::Posix.sanitize("AC/DC"), "AC-DC");
+
+        assert_eq!(FilenameProfile::Windows.sanitize("con"), "con_");
+        assert_eq!(FilenameProfile::Windows.sanitize("Nul.Live"), "Nul_.Live");
+        assert_eq!(FilenameProfile::Windows.sanitize("Console"), "Console");
+        assert_eq!(FilenameProfile::Posix.sanitize("CON"), "CON");
+
+        assert_eq!(
+            FilenameProfile::Strict.sanitize("Amélie #1 — Œuvre"),
+            "Amelie -1 - OEuvre"
+        );
+        assert_eq!(FilenameProfile::Strict.sanitize("Léon: Ça"), "Leon- Ca");
+    }
+
+    #[test]
+    fn titles_cant_leave_their_directory() {
+        let naming = NamingTemplate::default();
+        assert_eq!(
+            naming.movie_path("../../etc", None),
+            PathBuf::from("movies/-.-etc/-.-etc")
+        );
+        assert_eq!(
+            naming.show_path("AC/DC", 1, 2, None, None),
+            PathBuf::from("shows/AC-DC/Season 1/Episode 2")
+        );
+        assert_eq!(
+            NamingTemplate::media_server().show_path(
+                "Friends",
+                2,
+                7,
+                None,
+                Some("The One with the Racecar Bed")
+            ),
+            PathBuf::from(
+                "TV Shows/Friends/Season 2/Friends - S02E07 - The One with the Racecar Bed"
+            )
+        );
+
+        assert_eq!(
+            naming.extra_path("Heat", Some(1995), "Making Of: Part 1"),
+            PathBuf::from("movies/Heat (1995)/Featurettes/Making Of- Part 1")
+        );
+
+        assert!(stays_inside(&naming.movie_path("../../etc", None)));
+        assert!(!stays_inside(Path::new("../movies/Heat")));
+        assert!(!stays_inside(Path::new("/etc/passwd")));
+    }
+}
@@ -0,0 +1,347 @@
+//! # Disc Rippers
+//!
+//! Everything that reads from a drive through `MakeMKV` goes through a `DiscRipper`, so the
+//! drives don't have to be on the machine the bot runs on. A `LocalRipper` runs `makemkvcon`
+//! directly, and a `RemoteRipper` asks a `cord-ripper-agent` running next to the drives to
+//! run it, downloading whatever it ripped.
+//!
+//! ## Overview
+//!
+//! - **`DiscRipper`**: Runs `makemkvcon`, rips and ejects on the machine with the drives.
+//!
+//! - **`LocalRipper`**: Does so on this machine.
+//!
+//! - **`RemoteRipper`**: Does so through an agent, over HTTP.
+//!
+//! - **`init_rippers`**: Sets the rippers drives are listed from, this machine's and one
+//!   for each agent.
+//!
+//! ## Notes
+//!
+//! - Agents are told apart by name, which is kept with the drives they list so each drive
+//!   is ripped by the agent it is on.
+//! - A rip is cancelled by dropping its request, the agent stops `makemkvcon` once the
+//!   connection closes.
+//! - Audio CDs, archives and previews read the drive with other tools, and only work for
+//!   drives on this machine.
+
+use std::{
+    path::Path,
+    process::{ExitStatus, Output},
+    sync::{Arc, OnceLock},
+};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use super::{
+    errors::{MakeMkvError, Result},
+    makemkv_helpers::{eject_command, makemkvcon, Command},
+};
+use crate::{debug, error, info, trace};
+
+/// The rippers drives are listed from, set once at startup
+static RIPPERS: OnceLock<Vec<Arc<dyn DiscRipper>>> = OnceLock::new();
+
+/// Runs `MakeMKV` on the machine a set of drives is on.
+///
+/// # Methods
+///
+/// * `host` - The name of the agent the drives are on, `None` for this machine.
+/// * `makemkvcon` - Runs `makemkvcon` with the given arguments.
+/// * `rip` - Runs `makemkvcon` with the given arguments and a directory to rip into,
+///   leaving the ripped files in `output_dir`. Lines of output are sent to `lines` as they
+///   are printed, where the ripper can follow them.
+/// * `eject` - Opens the tray of the drive at a device.
+#[async_trait]
+pub trait DiscRipper: Send + Sync {
+    fn host(&self) -> Option<&str>;
+
+    async fn makemkvcon(&self, args: Vec<String>) -> Result<Output>;
+
+    async fn rip(
+        &self,
+        args: Vec<String>,
+        output_dir: &Path,
+        lines: mpsc::UnboundedSender<String>,
+    ) -> Result<Output>;
+
+    async fn eject(&self, device: &str) -> Result<Output>;
+}
+
+/// Runs `MakeMKV` on this machine
+pub struct LocalRipper;
+
+#[async_trait]
+impl DiscRipper for LocalRipper {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+
+    async fn makemkvcon(&self, args: Vec<String>) -> Result<Output> {
+        makemkvcon(args)
+            .execute()
+            .await
+            .map_err(|e| MakeMkvError::CommandExecutionError(e.to_string()))
+    }
+
+    async fn rip(
+        &self,
+        mut args: Vec<String>,
+        output_dir: &Path,
+        lines: mpsc::UnboundedSender<String>,
+    ) -> Result<Output> {
+        args.push(output_dir.to_string_lossy().to_string());
+        makemkvcon(args)
+            .stdout_lines(lines)
+            .execute()
+            .await
+            .map_err(|e| MakeMkvError::CommandExecutionError(e.to_string()))
+    }
+
+    async fn eject(&self, device: &str) -> Result<Output> {
+        let (program, args) = eject_command(device);
+        Command::new(program, args)
+            .execute()
+            .await
+            .map_err(|e| MakeMkvError::CommandExecutionError(e.to_string()))
+    }
+}
+
+/// What an agent answers with once a command has run.
+///
+/// # Fields
+///
+/// * `status` - The exit code of the command, `None` if it was killed.
+/// * `stdout` and `stderr` - What the command printed.
+/// * `job` - The job the files of a rip are kept under, until they are downloaded.
+/// * `files` - The names of the files a rip saved.
+#[derive(Debug, Deserialize)]
+struct AgentOutput {
+    status: Option<i32>,
+    stdout: String,
+    stderr: String,
+    #[serde(default)]
+    job: Option<String>,
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+/// Runs `MakeMKV` through a `cord-ripper-agent`.
+///
+/// # Fields
+///
+/// * `name` - The name the agent was given, shown with its drives.
+/// * `url` - Where the agent is reached, e.g. `http://home-server:9000`.
+/// * `token` - The token the agent was started with, if any.
+/// * `client` - The HTTP client requests are sent with.
+pub struct RemoteRipper {
+    name: String,
+    url: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl RemoteRipper {
+    #[must_use]
+    pub fn new(name: &str, url: &str, token: Option<String>) -> Self {
+        RemoteRipper {
+            name: name.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+            token,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn error(&self, error: impl std::fmt::Display) -> MakeMkvError {
+        error!("Agent {} failed: {}", self.name, error);
+        MakeMkvError::AgentError(self.name.clone(), error.to_string())
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Asks the agent to run a command, as `makemkvcon` or `rip`
+    async fn run(&self, path: &str, args: Vec<String>) -> Result<AgentOutput> {
+        trace!("Asking agent {} to run {} {:?}", self.name, path, args);
+        let response = self
+            .request(reqwest::Method::POST, path)
+            .json(&serde_json::json!({ "args": args }))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| self.error(e))?;
+
+        response.json().await.map_err(|e| self.error(e))
+    }
+
+    /// Downloads the files of a rip into `output_dir`, then has the agent remove them
+    async fn download(&self, job: &str, files: &[String], output_dir: &Path) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        for name in files {
+            debug!("Downloading {} from agent {}", name, self.name);
+            let mut response = self
+                .request(reqwest::Method::GET, &format!("/jobs/{job}/{name}"))
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| self.error(e))?;
+
+            // Only the name is kept, so an agent can't write outside of the directory
+            let file_name = Path::new(name)
+                .file_name()
+                .ok_or_else(|| self.error(format!("Invalid file name {name}")))?;
+            let mut file = tokio::fs::File::create(output_dir.join(file_name)).await?;
+            while let Some(chunk) = response.chunk().await.map_err(|e| self.error(e))? {
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+        }
+
+        if let Err(e) = self
+            .request(reqwest::Method::DELETE, &format!("/jobs/{job}"))
+            .send()
+            .await
+        {
+            debug!(
+                "Failed to remove job {} from agent {}: {}",
+                job, self.name, e
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DiscRipper for RemoteRipper {
+    fn host(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    async fn makemkvcon(&self, args: Vec<String>) -> Result<Output> {
+        self.run("/makemkvcon", args).await.map(into_output)
+    }
+
+    // The agent only answers once the rip is done, so there are no lines to follow
+    async fn rip(
+        &self,
+        args: Vec<String>,
+        output_dir: &Path,
+        _lines: mpsc::UnboundedSender<String>,
+    ) -> Result<Output> {
+        let output = self.run("/rip", args).await?;
+
+        if let Some(job) = &output.job {
+            self.download(job, &output.files, output_dir).await?;
+        }
+
+        Ok(into_output(output))
+    }
+
+    async fn eject(&self, device: &str) -> Result<Output> {
+        self.run("/eject", vec![device.to_string()])
+            .await
+            .map(into_output)
+    }
+}
+
+/// Turns the answer of an agent into the output of a command run here, so it is checked
+/// the same way
+fn into_output(output: AgentOutput) -> Output {
+    Output {
+        status: exit_status(output.status.unwrap_or(1)),
+        stdout: output.stdout.into_bytes(),
+        stderr: output.stderr.into_bytes(),
+    }
+}
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    // The raw status holds the exit code in its second byte
+    std::os::unix::process::ExitStatusExt::from_raw(code << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> ExitStatus {
+    std::os::windows::process::ExitStatusExt::from_raw(code.cast_unsigned())
+}
+
+/// Sets the rippers drives are listed from, this machine's if `local` and then one for each
+/// agent, as a name and URL. Only the first call has any effect.
+pub fn init_rippers(local: bool, agents: &[(String, String)], token: Option<&str>) {
+    let mut rippers: Vec<Arc<dyn DiscRipper>> = Vec::new();
+    if local {
+        rippers.push(Arc::new(LocalRipper));
+    }
+    for (name, url) in agents {
+        info!("Listing drives from agent {} at {}", name, url);
+        rippers.push(Arc::new(RemoteRipper::new(
+            name,
+            url,
+            token.map(String::from),
+        )));
+    }
+
+    let _ = RIPPERS.set(rippers);
+}
+
+/// The rippers drives are listed from, only this machine's until `init_rippers` is called
+#[must_use]
+pub fn rippers() -> Vec<Arc<dyn DiscRipper>> {
+    RIPPERS
+        .get()
+        .cloned()
+        .unwrap_or_else(|| vec![Arc::new(LocalRipper)])
+}
+
+/// The ripper for the drives on a host, `None` for this machine
+#[must_use]
+pub fn ripper(host: Option<&str>) -> Arc<dyn DiscRipper> {
+    rippers()
+        .into_iter()
+        .find(|ripper| ripper.host() == host)
+        .unwrap_or_else(|| Arc::new(LocalRipper))
+}
+
+/// Parses an agent given as `name=url`
+#[must_use]
+pub fn parse_agent(agent: &str) -> Option<(String, String)> {
+    let (name, url) = agent.split_once('=')?;
+    let (name, url) = (name.trim(), url.trim());
+    (!name.is_empty() && url.starts_with("http")).then(|| (name.to_string(), url.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_agents() {
+        assert_eq!(
+            parse_agent("home=http://home-server:9000"),
+            Some(("home".to_string(), "http://home-server:9000".to_string()))
+        );
+        assert_eq!(parse_agent("http://home-server:9000"), None);
+        assert_eq!(parse_agent("=http://home-server:9000"), None);
+    }
+
+    #[test]
+    fn keeps_agent_exit_codes() {
+        let output = into_output(AgentOutput {
+            status: Some(11),
+            stdout: String::new(),
+            stderr: String::new(),
+            job: None,
+            files: Vec::new(),
+        });
+        assert_eq!(output.status.code(), Some(11));
+    }
+}
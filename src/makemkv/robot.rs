@@ -0,0 +1,386 @@
+//! # `MakeMKV` Robot Output
+//!
+//! `makemkvcon -r` prints everything it has to say as lines of a prefix and comma separated
+//! fields, with text fields in double quotes:
+//!
+//! ```text
+//! DRV:0,2,999,1,"BD-RE ASUS BW-16D1HT","MY_MOVIE","/dev/sr0"
+//! TINFO:0,2,0,"My Movie"
+//! PRGV:1024,2048,65536
+//! ```
+//!
+//! This module turns those lines into [`RobotLine`] values, so nothing else has to know
+//! which field is which or split them itself.
+//!
+//! ## Overview
+//!
+//! - **`RobotLine`**: A single parsed line, one variant for each prefix.
+//!
+//! - **`DriveRecord`**: A drive slot, from a `DRV:` line.
+//!
+//! - **`parse_line`** and **`parse`**: Parse one line, or every line of the output.
+//!
+//! - **`split_fields`**: Splits the fields of a line, keeping commas and escaped quotes
+//!   inside of quoted fields.
+//!
+//! ## Notes
+//!
+//! - The attribute ids of `CINFO`, `TINFO` and `SINFO` lines come from `apdefs.h` in the
+//!   `MakeMKV` sources, the ones used here have constants below.
+//! - Lines with an unknown prefix, or fields that don't parse, are skipped rather than
+//!   failing the whole output, `MakeMKV` adds fields between releases.
+//! - Disc labels and title names can have commas and quotes of their own, such as
+//!   `"MY, MOVIE (2019)"`, so fields are never split on every comma.
+
+use super::messages::Message;
+
+/// The type of a stream, its code is one of the `STREAM_TYPE_` constants
+pub const ATTR_TYPE: u32 = 1;
+/// The name of a disc or title
+pub const ATTR_NAME: u32 = 2;
+/// The ISO 639-2 code of the language of a stream, e.g. "eng"
+pub const ATTR_LANG_CODE: u32 = 3;
+/// The language of a stream, e.g. "English"
+pub const ATTR_LANG_NAME: u32 = 4;
+/// The Matroska codec of a stream, e.g. `V_MPEG4/ISO/AVC`
+pub const ATTR_CODEC_ID: u32 = 5;
+/// The number of chapters in a title
+pub const ATTR_CHAPTER_COUNT: u32 = 8;
+/// The length of a title, e.g. "1:45:12"
+pub const ATTR_DURATION: u32 = 9;
+/// The size of a title, e.g. "23.5 GB"
+pub const ATTR_DISK_SIZE: u32 = 10;
+/// The bitrate of a stream
+pub const ATTR_BITRATE: u32 = 13;
+/// The file a title is played from on the disc, e.g. "00800.mpls"
+pub const ATTR_SOURCE_FILE_NAME: u32 = 16;
+/// The resolution of a video stream, e.g. "1920x1080"
+pub const ATTR_VIDEO_SIZE: u32 = 19;
+/// The aspect ratio of a video stream
+pub const ATTR_VIDEO_ASPECT_RATIO: u32 = 20;
+/// The frame rate of a video stream
+pub const ATTR_VIDEO_FRAME_RATE: u32 = 21;
+/// The number of the title on the disc itself, before `--minlength` filtered any out
+pub const ATTR_ORIGINAL_TITLE_ID: u32 = 24;
+/// The `STREAM_FLAG_` flags of a stream, added together
+pub const ATTR_STREAM_FLAGS: u32 = 22;
+/// The segments a title is played from, e.g. "1,2,3-5"
+pub const ATTR_SEGMENTS_MAP: u32 = 26;
+
+/// The code of the type attribute of audio streams
+pub const STREAM_TYPE_AUDIO: u32 = 6202;
+/// The code of the type attribute of subtitle streams
+pub const STREAM_TYPE_SUBTITLES: u32 = 6203;
+
+/// The flag of subtitle streams that only hold the forced subtitles, such as the lines
+/// shown during foreign language scenes
+pub const STREAM_FLAG_FORCED: u32 = 4096;
+
+/// The codec of the 3D video stream of 3D Blu-rays, which holds the second eye's view on
+/// top of the regular 2D stream
+pub const CODEC_ID_MVC: &str = "V_MPEG4/ISO/MVC";
+
+/// The state `MakeMKV` gives a drive slot with a disc loaded
+pub const DRIVE_STATE_LOADED: u32 = 2;
+
+/// A single line of robot output.
+///
+/// # Variants
+///
+/// - `Message`: `MSG:` - A message for the user, see the `messages` module.
+/// - `ProgressTitle`: `PRGT:` - The name of the operation that is running.
+/// - `ProgressCurrent`: `PRGC:` - The name of the step of the operation that is running.
+/// - `ProgressValue`: `PRGV:` - How far the step and the operation are, out of `max`.
+/// - `Drive`: `DRV:` - A drive slot, see `DriveRecord`.
+/// - `TitleCount`: `TCOUNT:` - The number of titles on the disc.
+/// - `DiscInfo`: `CINFO:` - An attribute of the disc.
+/// - `TitleInfo`: `TINFO:` - An attribute of a title, numbered from 0.
+/// - `StreamInfo`: `SINFO:` - An attribute of a stream of a title.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RobotLine {
+    Message(Message),
+    ProgressTitle {
+        code: u32,
+        id: u32,
+        name: String,
+    },
+    ProgressCurrent {
+        code: u32,
+        id: u32,
+        name: String,
+    },
+    ProgressValue {
+        current: u32,
+        total: u32,
+        max: u32,
+    },
+    Drive(DriveRecord),
+    TitleCount(u32),
+    DiscInfo {
+        attribute: u32,
+        code: u32,
+        value: String,
+    },
+    TitleInfo {
+        title: u16,
+        attribute: u32,
+        code: u32,
+        value: String,
+    },
+    StreamInfo {
+        title: u16,
+        stream: u16,
+        attribute: u32,
+        code: u32,
+        value: String,
+    },
+}
+
+/// A drive slot from a `DRV:` line. `MakeMKV` lists a slot for every drive it could have,
+/// the ones without a drive have an empty `device`.
+///
+/// # Fields
+///
+/// - `index`: The slot, as used in `disc:<index>`.
+/// - `state`: `DRIVE_STATE_LOADED` once a disc is loaded.
+/// - `flags`: The video filesystems found on the disc, 0 for none.
+/// - `drive_name`: The model of the drive, and its serial number on some systems.
+/// - `disc_name`: The label of the disc, empty when there is none.
+/// - `device`: The device of the drive, such as `/dev/sr0` or `D:`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriveRecord {
+    pub index: u8,
+    pub state: u32,
+    pub flags: u32,
+    pub drive_name: String,
+    pub disc_name: String,
+    pub device: String,
+}
+
+impl DriveRecord {
+    /// Whether the slot has a drive in it
+    #[must_use]
+    pub fn is_present(&self) -> bool {
+        !self.device.is_empty()
+    }
+
+    /// Whether a disc is loaded in the drive
+    #[must_use]
+    pub fn is_loaded(&self) -> bool {
+        self.state == DRIVE_STATE_LOADED
+    }
+}
+
+/// Parses a single line of robot output, returning `None` for lines that aren't robot
+/// output or don't have the fields their prefix should.
+#[must_use]
+pub fn parse_line(line: &str) -> Option<RobotLine> {
+    let (prefix, rest) = line.trim().split_once(':')?;
+    let fields = split_fields(rest);
+    let number = |index: usize| fields.get(index)?.trim().parse::<u32>().ok();
+    let text = |index: usize| fields.get(index).cloned().unwrap_or_default();
+
+    let line = match prefix {
+        // code, flags, count, message, format, params...
+        "MSG" => RobotLine::Message(Message {
+            code: number(0)?,
+            text: text(3),
+            params: fields.iter().skip(5).cloned().collect(),
+        }),
+        "PRGT" => RobotLine::ProgressTitle {
+            code: number(0)?,
+            id: number(1)?,
+            name: text(2),
+        },
+        "PRGC" => RobotLine::ProgressCurrent {
+            code: number(0)?,
+            id: number(1)?,
+            name: text(2),
+        },
+        "PRGV" => RobotLine::ProgressValue {
+            current: number(0)?,
+            total: number(1)?,
+            max: number(2)?,
+        },
+        // index, state, enabled, flags, drive name, disc name, device
+        "DRV" => RobotLine::Drive(DriveRecord {
+            index: u8::try_from(number(0)?).ok()?,
+            state: number(1)?,
+            flags: number(3)?,
+            drive_name: text(4),
+            disc_name: text(5),
+            device: text(6),
+        }),
+        "TCOUNT" => RobotLine::TitleCount(number(0)?),
+        "CINFO" => RobotLine::DiscInfo {
+            attribute: number(0)?,
+            code: number(1)?,
+            value: text(2),
+        },
+        "TINFO" => RobotLine::TitleInfo {
+            title: u16::try_from(number(0)?).ok()?,
+            attribute: number(1)?,
+            code: number(2)?,
+            value: text(3),
+        },
+        "SINFO" => RobotLine::StreamInfo {
+            title: u16::try_from(number(0)?).ok()?,
+            stream: u16::try_from(number(1)?).ok()?,
+            attribute: number(2)?,
+            code: number(3)?,
+            value: text(4),
+        },
+        _ => return None,
+    };
+
+    Some(line)
+}
+
+/// Parses every line of robot output, skipping anything else `makemkvcon` printed.
+pub fn parse(stdout: &str) -> impl Iterator<Item = RobotLine> + '_ {
+    stdout.lines().filter_map(parse_line)
+}
+
+/// Splits the comma separated fields of a robot output line, keeping commas that are
+/// inside of double quotes and removing the quotes themselves.
+///
+/// A quote only opens a quoted field at the start of the field, anywhere else it is part
+/// of the text. Inside of a quoted field, `""` and `\"` are a literal quote, unless the
+/// `\"` ends the field, so a value like `"C:\"` keeps its backslash.
+#[must_use]
+pub fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    // Whether anything has been read for the current field, quotes included
+    let mut started = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\\' if chars.peek() == Some(&'"') => {
+                    // Only an escape if the quote after it doesn't close the field
+                    let mut ahead = chars.clone();
+                    ahead.next();
+                    if matches!(ahead.peek(), None | Some(',')) {
+                        field.push(c);
+                    } else {
+                        chars.next();
+                        field.push('"');
+                    }
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if !started => {
+                in_quotes = true;
+                started = true;
+            }
+            ',' => {
+                fields.push(std::mem::take(&mut field));
+                started = false;
+            }
+            _ => {
+                field.push(c);
+                started = true;
+            }
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_drives() {
+        let line = r#"DRV:0,2,999,1,"BD-RE ASUS BW-16D1HT","MY_MOVIE","/dev/sr0""#;
+        let Some(RobotLine::Drive(drive)) = parse_line(line) else {
+            panic!("not a drive: {line}");
+        };
+        assert_eq!(drive.index, 0);
+        assert!(drive.is_present() && drive.is_loaded());
+        assert_eq!(drive.flags, 1);
+        assert_eq!(drive.drive_name, "BD-RE ASUS BW-16D1HT");
+        assert_eq!(drive.disc_name, "MY_MOVIE");
+        assert_eq!(drive.device, "/dev/sr0");
+
+        let Some(RobotLine::Drive(empty)) = parse_line(r#"DRV:5,256,999,0,"","","""#) else {
+            panic!("not a drive");
+        };
+        assert!(!empty.is_present());
+    }
+
+    #[test]
+    fn parses_info_and_progress() {
+        assert_eq!(
+            parse_line(r#"TINFO:3,26,0,"1,2,3-5""#),
+            Some(RobotLine::TitleInfo {
+                title: 3,
+                attribute: ATTR_SEGMENTS_MAP,
+                code: 0,
+                value: "1,2,3-5".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_line(r#"SINFO:0,0,19,0,"1920x1080""#),
+            Some(RobotLine::StreamInfo {
+                title: 0,
+                stream: 0,
+                attribute: ATTR_VIDEO_SIZE,
+                code: 0,
+                value: "1920x1080".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_line("PRGV:1024,2048,65536"),
+            Some(RobotLine::ProgressValue {
+                current: 1024,
+                total: 2048,
+                max: 65536,
+            })
+        );
+        assert_eq!(parse_line("TCOUNT:12"), Some(RobotLine::TitleCount(12)));
+    }
+
+    #[test]
+    fn keeps_commas_and_quotes_inside_of_names() {
+        assert_eq!(
+            split_fields(r#"0,2,0,"MY, MOVIE (2019)""#),
+            vec!["0", "2", "0", "MY, MOVIE (2019)"]
+        );
+        assert_eq!(
+            split_fields(r#""The ""Best"", Cut","Say \"Hi\" Again""#),
+            vec![r#"The "Best", Cut"#, r#"Say "Hi" Again"#]
+        );
+        assert_eq!(split_fields(r#""C:\","""#), vec![r"C:\", ""]);
+        assert_eq!(split_fields(r#"12" Single,2"#), vec![r#"12" Single"#, "2"]);
+
+        let line = r#"DRV:1,2,999,1,"BD-RE, External","MY, MOVIE (2019)","/dev/sr1""#;
+        let Some(RobotLine::Drive(drive)) = parse_line(line) else {
+            panic!("not a drive: {line}");
+        };
+        assert_eq!(drive.drive_name, "BD-RE, External");
+        assert_eq!(drive.disc_name, "MY, MOVIE (2019)");
+        assert_eq!(drive.device, "/dev/sr1");
+    }
+
+    #[test]
+    fn skips_lines_that_are_not_robot_output() {
+        assert_eq!(parse_line("Using direct disc access mode"), None);
+        assert_eq!(parse_line("TINFO:not,a,title"), None);
+        assert_eq!(parse_line(""), None);
+    }
+}
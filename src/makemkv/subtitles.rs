@@ -0,0 +1,250 @@
+//! # Forced Subtitles
+//!
+//! Films with scenes in another language, such as the Na'vi lines in Avatar, subtitle just
+//! those lines. Blu-rays keep them as a separate subtitle stream flagged as forced, but
+//! players only show a track on their own when the file marks it as forced and default,
+//! which `MakeMKV` doesn't do. Without it those scenes play untranslated unless the file is
+//! remuxed by hand. This module finds the forced subtitles of a ripped file and, as set
+//! up, flags them so players show them, or extracts them next to the file.
+//!
+//! ## Overview
+//!
+//! - **`SubtitleStream`**: A subtitle stream of a title, as the disc scan found it.
+//!
+//! - **`ForcedSubtitles`**: What is done with forced subtitles once a title is ripped.
+//!
+//! - **`forced_track`**: Picks the forced subtitle track of a file, in the language of its
+//!   first audio track.
+//!
+//! - **`handle_forced_subtitles`**: Flags or extracts the forced subtitles of a ripped file.
+//!
+//! ## Notes
+//!
+//! - The tracks of a file are matched to the streams of the scan by language and order,
+//!   since `MakeMKV` leaves out the streams in languages it wasn't set to keep.
+//! - A track `mkvmerge` already reports as forced counts too, in case `MakeMKV` starts
+//!   marking them itself.
+//! - Only forced subtitles in the language of the first audio track are used, forced
+//!   English subtitles are no help with a French dub.
+//! - Both need `MKVToolNix`, `mkvpropedit` to flag tracks and `mkvextract` to extract them.
+//!   Failing is only logged by the rip, the file is kept as it was.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    errors::Result,
+    makemkv_helpers::{identify_tracks, run_mkvtoolnix, Command, Track, PROBE_TIMEOUT},
+};
+use crate::{debug, info};
+
+/// A subtitle stream of a title, as the disc scan found it.
+///
+/// # Fields
+///
+/// * `language` - The ISO 639-2 code of its language, e.g. `eng`.
+/// * `forced` - Whether it only holds the forced subtitles.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubtitleStream {
+    pub language: String,
+    pub forced: bool,
+}
+
+/// What is done with the forced subtitles of a title once it is ripped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ForcedSubtitles {
+    /// The file is left as `MakeMKV` saved it
+    #[default]
+    Off,
+    /// The forced track is flagged as forced and default, so players show it on their own
+    Flag,
+    /// The forced track is copied next to the file, e.g. `Movie.eng.forced.sup`
+    Extract,
+}
+
+impl ForcedSubtitles {
+    /// Convert a name, such as `flag`, to its `ForcedSubtitles`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<ForcedSubtitles> {
+        match name.to_lowercase().as_str() {
+            "off" => Some(ForcedSubtitles::Off),
+            "flag" => Some(ForcedSubtitles::Flag),
+            "extract" => Some(ForcedSubtitles::Extract),
+            _ => None,
+        }
+    }
+}
+
+/// The forced subtitle track of a file in the language of its first audio track, if it
+/// has one. `scanned` are the subtitle streams the disc scan found for the title.
+#[must_use]
+pub fn forced_track<'a>(tracks: &'a [Track], scanned: &[SubtitleStream]) -> Option<&'a Track> {
+    let audio_language = tracks.iter().find(|track| track.kind == "audio")?;
+
+    // The nth track of a language is the nth stream of it in the scan
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    tracks
+        .iter()
+        .filter(|track| track.kind == "subtitles")
+        .find(|track| {
+            let index = seen.entry(track.language.as_str()).or_default();
+            let forced_in_scan = scanned
+                .iter()
+                .filter(|stream| stream.language == track.language)
+                .nth(*index)
+                .is_some_and(|stream| stream.forced);
+            *index += 1;
+
+            track.language == audio_language.language && (track.forced || forced_in_scan)
+        })
+}
+
+/// Where the forced subtitles of `file` are extracted to, named so media servers pick
+/// them up as forced, e.g. `Movie.eng.forced.sup`
+fn sidecar_path(file: &Path, track: &Track) -> PathBuf {
+    let extension = match track.codec_id.as_str() {
+        "S_HDMV/PGS" => "sup",
+        "S_VOBSUB" => "sub",
+        "S_TEXT/ASS" => "ass",
+        "S_TEXT/SSA" => "ssa",
+        _ => "srt",
+    };
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    file.with_file_name(format!("{stem}.{}.forced.{extension}", track.language))
+}
+
+/// Flags or extracts the forced subtitles of a ripped file, as `mode` says. `scanned` are
+/// the subtitle streams the disc scan found for the title, empty if it wasn't scanned.
+///
+/// # Errors
+///
+/// Returns a `MakeMkvError` if `mkvmerge`, `mkvpropedit` or `mkvextract` can't be run, or
+/// fail on the file.
+pub async fn handle_forced_subtitles(
+    file: &Path,
+    scanned: &[SubtitleStream],
+    mode: ForcedSubtitles,
+) -> Result<()> {
+    if mode == ForcedSubtitles::Off {
+        return Ok(());
+    }
+
+    let tracks = identify_tracks(file).await?;
+
+    let Some(forced) = forced_track(&tracks, scanned) else {
+        debug!("{} has no forced subtitles to handle", file.display());
+        return Ok(());
+    };
+
+    match mode {
+        ForcedSubtitles::Off => {}
+        ForcedSubtitles::Flag => {
+            // mkvpropedit counts tracks from 1, in the same order as mkvmerge's ids. Only
+            // the forced track is left as default, or players could pick another
+            let mut args = vec![file.to_string_lossy().to_string()];
+            for track in tracks.iter().filter(|track| track.kind == "subtitles") {
+                let is_forced = track.id == forced.id;
+                args.extend([
+                    "--edit".to_string(),
+                    format!("track:{}", track.id + 1),
+                    "--set".to_string(),
+                    format!("flag-default={}", u8::from(is_forced)),
+                ]);
+                if is_forced {
+                    args.extend(["--set".to_string(), "flag-forced=1".to_string()]);
+                }
+            }
+            run_mkvtoolnix(Command::new("mkvpropedit", args).timeout(PROBE_TIMEOUT)).await?;
+            info!(
+                "Flagged track {} of {} as forced subtitles",
+                forced.id,
+                file.display()
+            );
+        }
+        ForcedSubtitles::Extract => {
+            let sidecar = sidecar_path(file, forced);
+            // Extracting reads the whole title, so it gets as long as it needs
+            run_mkvtoolnix(Command::new(
+                "mkvextract",
+                vec![
+                    file.to_string_lossy().to_string(),
+                    "tracks".to_string(),
+                    format!("{}:{}", forced.id, sidecar.display()),
+                ],
+            ))
+            .await?;
+            info!("Extracted forced subtitles to {}", sidecar.display());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(id: u64, kind: &str, language: &str) -> Track {
+        Track {
+            id,
+            kind: kind.to_string(),
+            language: language.to_string(),
+            forced: false,
+            codec_id: "S_HDMV/PGS".to_string(),
+        }
+    }
+
+    fn stream(language: &str, forced: bool) -> SubtitleStream {
+        SubtitleStream {
+            language: language.to_string(),
+            forced,
+        }
+    }
+
+    #[test]
+    fn picks_the_forced_track_in_the_audio_language() {
+        let tracks = [
+            track(0, "video", "und"),
+            track(1, "audio", "eng"),
+            track(2, "audio", "fra"),
+            track(3, "subtitles", "fra"),
+            track(4, "subtitles", "eng"),
+            track(5, "subtitles", "eng"),
+        ];
+        // The German streams were left out of the file
+        let scanned = [
+            stream("deu", true),
+            stream("fra", true),
+            stream("eng", false),
+            stream("eng", true),
+        ];
+
+        assert_eq!(
+            forced_track(&tracks, &scanned).map(|track| track.id),
+            Some(5)
+        );
+        assert_eq!(forced_track(&tracks, &scanned[..3]), None);
+
+        let mut flagged = tracks.clone();
+        flagged[4].forced = true;
+        assert_eq!(forced_track(&flagged, &[]).map(|track| track.id), Some(4));
+    }
+
+    #[test]
+    fn names_sidecars_after_the_file() {
+        assert_eq!(
+            sidecar_path(
+                Path::new("/movies/Avatar (2009)/Avatar (2009).mkv"),
+                &track(3, "subtitles", "eng")
+            ),
+            PathBuf::from("/movies/Avatar (2009)/Avatar (2009).eng.forced.sup")
+        );
+    }
+}
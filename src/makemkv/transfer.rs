@@ -0,0 +1,347 @@
+//! # Verified Moves
+//!
+//! Ripped files are moved from their temporary directory into the library once `MakeMKV` is
+//! done with them. Within one mount that is a rename, which can't lose anything, but a
+//! library on another mount, such as NFS or SMB, has to be copied to.
+//! Writes to network mounts occasionally come up short without reporting an error, so
+//! copies are checked before the ripped file is removed.
+//!
+//! Copies are written next to the destination with a `.part` extension, and only renamed
+//! to their real name once they have been checked. Plex and Jellyfin watch the library
+//! for new video files, and would otherwise pick up a copy that is half written.
+//!
+//! ## Overview
+//!
+//! - **`move_verified`**: Moves a file, copying and checking it when it crosses mounts.
+//!
+//! - **`move_verified_with_progress`**: Moves a file the same way, reporting how much of
+//!   it has been copied as it goes.
+//!
+//! - **`same_mount`**: Whether a move between two directories is a rename.
+//!
+//! - **`set_verify_contents`**: Also compares what was copied byte for byte, not just
+//!   its size.
+//!
+//! ## Notes
+//!
+//! - A copy that doesn't match is copied over again, up to `COPY_ATTEMPTS` times.
+//!   The ripped file is only removed once a copy matches, so a rip is never lost to a
+//!   bad copy.
+//! - A file already at the destination is never replaced, both a rename and the rename
+//!   of a checked copy would silently overwrite it.
+//! - Comparing contents reads both files back in full, so it is off by default. The size
+//!   catches the truncated writes network mounts are known for.
+
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::errors::{MakeMkvError, Result};
+use crate::{debug, trace, warn};
+
+/// How many times a copy is made before giving up on it
+const COPY_ATTEMPTS: u32 = 3;
+/// How much of each file is read at a time when comparing contents
+const COMPARE_CHUNK_SIZE: usize = 1024 * 1024;
+/// How much of each file is copied at a time, progress is reported after each
+const COPY_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+/// The extension added to a copy until it has been checked
+pub const PARTIAL_EXTENSION: &str = "part";
+
+static VERIFY_CONTENTS: OnceLock<bool> = OnceLock::new();
+
+/// Set whether copies are compared byte for byte as well as by size, this can only be
+/// done once at startup
+pub fn set_verify_contents(verify: bool) {
+    debug!("Verify copied contents set to: {}", verify);
+    if VERIFY_CONTENTS.set(verify).is_err() {
+        warn!("Verify copied contents was already set, ignoring");
+    }
+}
+
+/// Whether copies are compared byte for byte as well as by size
+pub fn verify_contents() -> bool {
+    *VERIFY_CONTENTS.get_or_init(|| false)
+}
+
+/// Moves a file to its destination. A rename is tried first, if the destination is on
+/// another mount the file is copied instead, checked, and only then removed.
+///
+/// # Errors
+///
+/// Returns `MakeMkvError::FileAlreadyExists` if there is already a file at `destination`,
+/// `MakeMkvError::WriteVerificationFailed` if no copy matched the original after
+/// `COPY_ATTEMPTS` tries, or the error of the rename or copy if either failed outright.
+pub async fn move_verified(source: &Path, destination: &Path) -> Result<()> {
+    move_verified_with_progress(source, destination, &|_| ()).await
+}
+
+/// Moves a file like `move_verified`, calling `progress` with the number of bytes copied
+/// so far as a copy goes. A copy that is made again starts back at 0.
+///
+/// # Errors
+///
+/// Returns the same errors as `move_verified`.
+pub async fn move_verified_with_progress(
+    source: &Path,
+    destination: &Path,
+    progress: &(dyn Fn(u64) + Sync),
+) -> Result<()> {
+    if tokio::fs::try_exists(destination).await? {
+        return Err(already_exists(destination));
+    }
+
+    match tokio::fs::rename(source, destination).await {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            debug!(
+                "{} is on another mount, copying {} instead",
+                destination.display(),
+                source.display()
+            );
+        }
+        Err(e) => {
+            warn!(
+                "Failed to move {} to {}: {}",
+                source.display(),
+                destination.display(),
+                e
+            );
+            // Not the disc's fault, so it isn't mistaken for a failed read
+            return Err(e.into());
+        }
+    }
+
+    // A copy that never matched isn't left behind, it would only be mistaken for a rip
+    if let Err(e) = copy_verified(source, destination, verify_contents(), progress).await {
+        let _ = tokio::fs::remove_file(partial_path(destination)).await;
+        return Err(e);
+    }
+    tokio::fs::remove_file(source).await?;
+    Ok(())
+}
+
+/// Copies a file until a copy matches the original, leaving the original in place. The
+/// copy only takes the destination's name once it matches.
+async fn copy_verified(
+    source: &Path,
+    destination: &Path,
+    compare_contents: bool,
+    progress: &(dyn Fn(u64) + Sync),
+) -> Result<()> {
+    let partial = partial_path(destination);
+
+    for attempt in 1..=COPY_ATTEMPTS {
+        copy_synced(source, &partial, progress).await?;
+
+        let source_path = source.to_path_buf();
+        let destination_path = partial.clone();
+        let problem = tokio::task::spawn_blocking(move || {
+            mismatch(&source_path, &destination_path, compare_contents)
+        })
+        .await
+        .map_err(|e| MakeMkvError::WriteVerificationFailed(e.to_string()))??;
+
+        let Some(problem) = problem else {
+            trace!(
+                "Copy of {} matched on attempt {}",
+                source.display(),
+                attempt
+            );
+            // Checked again, something else may have been saved there while copying
+            if tokio::fs::try_exists(destination).await? {
+                return Err(already_exists(destination));
+            }
+            // Within one directory, so the whole file appears at once
+            tokio::fs::rename(&partial, destination).await?;
+            return Ok(());
+        };
+
+        warn!(
+            "Copy of {} to {} {} on attempt {} of {}",
+            source.display(),
+            destination.display(),
+            problem,
+            attempt,
+            COPY_ATTEMPTS
+        );
+    }
+
+    Err(MakeMkvError::WriteVerificationFailed(
+        destination.to_string_lossy().to_string(),
+    ))
+}
+
+fn already_exists(destination: &Path) -> MakeMkvError {
+    warn!("{} already exists, not replacing it", destination.display());
+    MakeMkvError::FileAlreadyExists(destination.to_string_lossy().to_string())
+}
+
+/// The path a copy is written to until it has been checked, e.g. `Heat (1995).mkv.part`
+#[must_use]
+pub fn partial_path(destination: &Path) -> PathBuf {
+    let mut name = destination.as_os_str().to_os_string();
+    name.push(".");
+    name.push(PARTIAL_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Copies a file and waits for it to be written out, network mounts can otherwise still be
+/// holding some of it when it is checked. Copied a chunk at a time, so `progress` can be
+/// told how far along it is.
+async fn copy_synced(
+    source: &Path,
+    destination: &Path,
+    progress: &(dyn Fn(u64) + Sync),
+) -> io::Result<()> {
+    let mut reader = tokio::fs::File::open(source).await?;
+    let mut writer = tokio::fs::File::create(destination).await?;
+    let mut chunk = vec![0; COPY_CHUNK_SIZE];
+    let mut copied = 0;
+
+    progress(0);
+    loop {
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&chunk[..read]).await?;
+        copied += read as u64;
+        progress(copied);
+    }
+
+    writer.sync_all().await
+}
+
+/// Whether two directories are on the same mount, where moving a file between them is a
+/// rename rather than a copy
+#[must_use]
+pub fn same_mount(first: &Path, second: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        match (std::fs::metadata(first), std::fs::metadata(second)) {
+            (Ok(first), Ok(second)) => first.dev() == second.dev(),
+            _ => false,
+        }
+    }
+    // Assumed to be a copy, which only costs a transfer slot
+    #[cfg(not(unix))]
+    {
+        let _ = (first, second);
+        false
+    }
+}
+
+/// How a copy differs from the original, `None` if it matches. Reads both files when
+/// comparing contents, so should be run with `spawn_blocking`.
+fn mismatch(
+    source: &Path,
+    destination: &Path,
+    compare_contents: bool,
+) -> io::Result<Option<String>> {
+    let expected = std::fs::metadata(source)?.len();
+    let copied = std::fs::metadata(destination)?.len();
+    if copied != expected {
+        return Ok(Some(format!("was {copied} bytes rather than {expected}")));
+    }
+
+    if compare_contents && !same_contents(source, destination)? {
+        return Ok(Some("had different contents".to_string()));
+    }
+
+    Ok(None)
+}
+
+/// Whether two files of the same size have the same contents
+fn same_contents(first: &Path, second: &Path) -> io::Result<bool> {
+    let mut first = io::BufReader::with_capacity(COMPARE_CHUNK_SIZE, std::fs::File::open(first)?);
+    let mut second = io::BufReader::with_capacity(COMPARE_CHUNK_SIZE, std::fs::File::open(second)?);
+    let mut first_chunk = vec![0; COMPARE_CHUNK_SIZE];
+    let mut second_chunk = vec![0; COMPARE_CHUNK_SIZE];
+
+    loop {
+        let read = first.read(&mut first_chunk)?;
+        if read == 0 {
+            // Anything left in the second file means it is longer
+            return Ok(second.read(&mut second_chunk)? == 0);
+        }
+        // Reads can come back short, so the second file is read to the same length
+        second.read_exact(&mut second_chunk[..read]).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                io::Error::other("copy is shorter than the original")
+            } else {
+                e
+            }
+        })?;
+        if first_chunk[..read] != second_chunk[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_short_and_changed_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("title_t00.mkv");
+        let copy = dir.path().join("Heat (1995).mkv");
+        std::fs::write(&source, "abcdef").unwrap();
+
+        std::fs::write(&copy, "abc").unwrap();
+        assert!(mismatch(&source, &copy, false).unwrap().is_some());
+
+        std::fs::write(&copy, "abcxyz").unwrap();
+        assert!(mismatch(&source, &copy, false).unwrap().is_none());
+        assert!(mismatch(&source, &copy, true).unwrap().is_some());
+
+        std::fs::write(&copy, "abcdef").unwrap();
+        assert!(mismatch(&source, &copy, true).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn copies_keep_the_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("title_t00.mkv");
+        let copy = dir.path().join("Heat (1995).mkv");
+        std::fs::write(&source, "abcdef").unwrap();
+
+        let copied = std::sync::atomic::AtomicU64::new(0);
+        copy_verified(&source, &copy, true, &|bytes| {
+            copied.store(bytes, std::sync::atomic::Ordering::Relaxed);
+        })
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read(&copy).unwrap(), b"abcdef");
+        assert_eq!(copied.into_inner(), 6);
+        assert!(same_mount(dir.path(), dir.path()));
+        assert!(source.exists());
+        assert!(!partial_path(&copy).exists());
+    }
+
+    #[tokio::test]
+    async fn never_replaces_an_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("title_t00.mkv");
+        let episode = dir.path().join("Bluey - S01E01.mkv");
+        std::fs::write(&source, "new").unwrap();
+        std::fs::write(&episode, "old").unwrap();
+
+        let result = move_verified(&source, &episode).await;
+        assert!(matches!(result, Err(MakeMkvError::FileAlreadyExists(_))));
+        assert_eq!(std::fs::read(&episode).unwrap(), b"old");
+        assert!(source.exists());
+
+        let result = copy_verified(&source, &episode, false, &|_| ()).await;
+        assert!(matches!(result, Err(MakeMkvError::FileAlreadyExists(_))));
+        assert_eq!(std::fs::read(&episode).unwrap(), b"old");
+    }
+}
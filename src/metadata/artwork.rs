@@ -0,0 +1,134 @@
+//! # Artwork
+//!
+//! Players that read artwork from the library, such as Kodi without a scraper or a TV
+//! browsing a share, show covers from a `poster.jpg` and `fanart.jpg` next to the files.
+//! This module downloads them from the artwork Sonarr and Radarr link to.
+//!
+//! ## Notes
+//!
+//! - Artwork that is already there is never replaced, whether it was downloaded for an
+//!   earlier disc of a show or put there by hand.
+//! - Movies that share a folder with other movies get `{file}-poster.jpg` instead, so
+//!   their covers don't end up on each other.
+//! - Downloads are limited to `MAX_ARTWORK_BYTES`, and written to a `.part` file first so
+//!   a failed download doesn't leave a broken image behind.
+
+use std::path::{Path, PathBuf};
+
+use crate::{debug, trace};
+
+use super::errors::{MetadataError, Result};
+
+/// The largest image downloaded, posters are usually well under a megabyte
+pub const MAX_ARTWORK_BYTES: u64 = 10 * 1024 * 1024;
+
+/// The file extensions of videos, which tell whether a movie has a folder to itself
+const VIDEO_EXTENSIONS: [&str; 4] = ["mkv", "mp4", "m4v", "avi"];
+
+/// Where the artwork of a movie or show goes, named `{kind}.jpg` when it has `dir` to
+/// itself and `{file}-{kind}.jpg` when it shares it with other videos
+#[must_use]
+pub fn artwork_path(dir: &Path, file: Option<&Path>, kind: &str) -> PathBuf {
+    let Some(stem) = file.and_then(|file| file.file_stem()) else {
+        return dir.join(format!("{kind}.jpg"));
+    };
+
+    let shared = std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries.filter_map(std::result::Result::ok).any(|entry| {
+            let path = entry.path();
+            path.file_stem() != Some(stem)
+                && path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| VIDEO_EXTENSIONS.contains(&extension))
+        })
+    });
+
+    if shared {
+        dir.join(format!("{}-{kind}.jpg", stem.to_string_lossy()))
+    } else {
+        dir.join(format!("{kind}.jpg"))
+    }
+}
+
+/// Downloads an image to `path`, unless something is already there. Returns whether it
+/// was downloaded.
+///
+/// # Errors
+///
+/// Returns a `MetadataError` if the image can't be downloaded, isn't an image, is larger
+/// than `MAX_ARTWORK_BYTES`, or can't be written.
+pub async fn download_artwork(url: &str, path: &Path) -> Result<bool> {
+    if path.exists() {
+        trace!("{} already exists, not replacing it", path.display());
+        return Ok(false);
+    }
+
+    trace!("Downloading {} to {}", url, path.display());
+    let mut response = reqwest::get(url).await?;
+    if !response.status().is_success() {
+        return Err(MetadataError::DownloadError(format!(
+            "{url} responded with status {}",
+            response.status().as_u16()
+        )));
+    }
+
+    let is_image = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("image/"));
+    if !is_image {
+        return Err(MetadataError::DownloadError(format!(
+            "{url} isn't an image"
+        )));
+    }
+
+    // The length isn't always sent, so it is checked again as the image comes in
+    if let Some(length) = response.content_length() {
+        if length > MAX_ARTWORK_BYTES {
+            return Err(MetadataError::TooLarge(length));
+        }
+    }
+    let mut image = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        image.extend_from_slice(&chunk);
+        if image.len() as u64 > MAX_ARTWORK_BYTES {
+            return Err(MetadataError::TooLarge(image.len() as u64));
+        }
+    }
+
+    let part_path = path.with_extension("jpg.part");
+    tokio::fs::write(&part_path, &image).await?;
+    tokio::fs::rename(&part_path, path).await?;
+
+    debug!("Downloaded {}", path.display());
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_artwork_by_file_in_shared_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let heat = dir.path().join("Heat (1995).mkv");
+        std::fs::write(&heat, "").unwrap();
+
+        assert_eq!(
+            artwork_path(dir.path(), Some(&heat), "poster"),
+            dir.path().join("poster.jpg")
+        );
+
+        std::fs::write(dir.path().join("Ronin (1998).mkv"), "").unwrap();
+        assert_eq!(
+            artwork_path(dir.path(), Some(&heat), "poster"),
+            dir.path().join("Heat (1995)-poster.jpg")
+        );
+        assert_eq!(
+            artwork_path(dir.path(), None, "fanart"),
+            dir.path().join("fanart.jpg")
+        );
+    }
+}
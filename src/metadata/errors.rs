@@ -0,0 +1,28 @@
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, MetadataError>;
+
+#[derive(Debug, Error)]
+pub enum MetadataError {
+    #[error("Failed to write metadata file: {0}")]
+    WriteError(String),
+
+    #[error("Failed to download artwork: {0}")]
+    DownloadError(String),
+
+    #[error("Artwork is too large to download at {0} bytes")]
+    TooLarge(u64),
+}
+
+impl From<io::Error> for MetadataError {
+    fn from(error: io::Error) -> Self {
+        MetadataError::WriteError(error.to_string())
+    }
+}
+
+impl From<reqwest::Error> for MetadataError {
+    fn from(error: reqwest::Error) -> Self {
+        MetadataError::DownloadError(error.to_string())
+    }
+}
@@ -0,0 +1,132 @@
+//! # Metadata Core Module
+//!
+//! This module writes files next to finished rips describing what they are, for players
+//! that read them from the library rather than a media server scraping them.
+//!
+//! ## Overview
+//!
+//! - **`write_sidecars`**: Writes the `.nfo` files of a finished rip and downloads its
+//!   artwork, see the `nfo` and `artwork` modules.
+//!
+//! ## Notes
+//!
+//! - The details come from Sonarr and Radarr when they are configured, otherwise only the
+//!   title, year, season and episode of the rip are written, and there is no artwork.
+//! - Nothing here fails a rip, anything going wrong is logged and the rip is kept.
+//! - A show's `tvshow.nfo` and artwork are only written once, so the show isn't looked up
+//!   again for every episode and any changes made to them are kept.
+
+use std::path::{Path, PathBuf};
+
+use crate::arr::{lookup_metadata, ArrMetadata};
+use crate::makemkv::{MakeMkv, Rip, RipType};
+use crate::{debug, trace, warn};
+
+use super::artwork::{artwork_path, download_artwork};
+use super::errors::Result;
+use super::nfo::{episode_nfo, movie_nfo, show_dir, tvshow_nfo, TVSHOW_NFO};
+
+/// Writes the `.nfo` files of a finished rip next to its files, and downloads its artwork
+/// into its folder, as far as `makemkv` is set up to. Titles split by chapters save an
+/// episode per file, numbered on from the rip's episode.
+pub async fn write_sidecars(rip: &Rip, files: &[PathBuf], makemkv: &MakeMkv) {
+    // The movie's own files cover its extras, an .nfo of their own would list them as
+    // another movie
+    if (!makemkv.nfo && !makemkv.artwork) || rip.extra.is_some() {
+        return;
+    }
+    let Some(first) = files.first() else {
+        return;
+    };
+
+    // Where the files about the whole movie or show go
+    let (dir, file) = match rip.rip_type {
+        RipType::Movie => (first.parent(), Some(first.as_path())),
+        RipType::Show { .. } => (show_dir(first), None),
+    };
+    let Some(dir) = dir else {
+        warn!("{} has no folder to write metadata to", first.display());
+        return;
+    };
+
+    let poster = artwork_path(dir, file, "poster");
+    let fanart = artwork_path(dir, file, "fanart");
+    let show_nfo = dir.join(TVSHOW_NFO);
+
+    // Shows are only looked up while something about the show is still missing
+    let wanted = match rip.rip_type {
+        RipType::Movie => true,
+        RipType::Show { .. } => {
+            (makemkv.nfo && !show_nfo.exists())
+                || (makemkv.artwork && !(poster.exists() && fanart.exists()))
+        }
+    };
+    let metadata = if wanted {
+        lookup_metadata(rip.rip_type, &rip.title, rip.year).await
+    } else {
+        None
+    };
+
+    if makemkv.nfo {
+        if let Err(e) = write_nfos(rip, files, &show_nfo, metadata.as_ref()).await {
+            warn!("Failed to write the .nfo files of {}: {}", rip.title, e);
+        }
+    }
+
+    let Some(metadata) = metadata.filter(|_| makemkv.artwork) else {
+        return;
+    };
+    for (url, path) in [
+        (&metadata.poster_url, &poster),
+        (&metadata.fanart_url, &fanart),
+    ] {
+        let Some(url) = url else {
+            continue;
+        };
+        if let Err(e) = download_artwork(url, path).await {
+            warn!("Failed to download artwork for {}: {}", rip.title, e);
+        }
+    }
+}
+
+async fn write_nfos(
+    rip: &Rip,
+    files: &[PathBuf],
+    show_nfo: &Path,
+    metadata: Option<&ArrMetadata>,
+) -> Result<()> {
+    match rip.rip_type {
+        RipType::Movie => {
+            let nfo = movie_nfo(&rip.title, rip.year, metadata);
+            for file in files {
+                write(&file.with_extension("nfo"), &nfo).await?;
+            }
+        }
+        RipType::Show {
+            season, episode, ..
+        } => {
+            if !show_nfo.exists() {
+                write(show_nfo, &tvshow_nfo(&rip.title, metadata)).await?;
+            }
+
+            for (offset, file) in (0u8..).zip(files) {
+                let Some(number) = episode.checked_add(offset) else {
+                    break;
+                };
+                // Only the title's first episode is named, the rest came from splitting it
+                let name = rip.episode_title.as_deref().filter(|_| offset == 0);
+                let nfo = episode_nfo(&rip.title, season, number, name);
+                write(&file.with_extension("nfo"), &nfo).await?;
+            }
+        }
+    }
+
+    debug!("Wrote the .nfo files of {}", rip.title);
+    Ok(())
+}
+
+async fn write(path: &Path, contents: &str) -> Result<()> {
+    tokio::fs::write(path, contents).await?;
+    trace!("Wrote {}", path.display());
+    Ok(())
+}
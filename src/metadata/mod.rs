@@ -0,0 +1,6 @@
+pub mod artwork;
+pub mod errors;
+pub mod metadata_core;
+pub mod nfo;
+
+pub use metadata_core::write_sidecars;
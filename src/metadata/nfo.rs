@@ -0,0 +1,194 @@
+//! # NFO Files
+//!
+//! Kodi reads the details of a movie or episode from an `.nfo` file next to it, which
+//! saves running a media server that scrapes them. This module writes them in Kodi's
+//! format.
+//!
+//! ## Overview
+//!
+//! - **`movie_nfo`**: The `.nfo` of a movie, named after the movie's file.
+//!
+//! - **`tvshow_nfo`**: The `tvshow.nfo` in a show's folder.
+//!
+//! - **`episode_nfo`**: The `.nfo` of an episode, named after the episode's file.
+//!
+//! ## Notes
+//!
+//! - Movies get an `.nfo` named after their file rather than `movie.nfo`, since Kodi only
+//!   reads `movie.nfo` when every movie has a folder of its own, which the flat layout
+//!   doesn't.
+//! - The ids Sonarr and Radarr have are written as `uniqueid`s, which lets Kodi match the
+//!   rip exactly rather than by its name.
+
+use std::path::Path;
+
+use crate::arr::ArrMetadata;
+
+/// The file a show's details are kept in, in the show's folder
+pub const TVSHOW_NFO: &str = "tvshow.nfo";
+
+/// The `.nfo` of a movie, from what Radarr knows about it if it does
+#[must_use]
+pub fn movie_nfo(title: &str, year: Option<u16>, metadata: Option<&ArrMetadata>) -> String {
+    let mut elements = vec![element("title", title)];
+    elements.extend(year.map(|year| element("year", &year.to_string())));
+    if let Some(metadata) = metadata {
+        elements.extend(
+            metadata
+                .runtime
+                .map(|runtime| element("runtime", &runtime.to_string())),
+        );
+        elements.extend(details(metadata));
+    }
+    document("movie", &elements)
+}
+
+/// The `tvshow.nfo` of a show, from what Sonarr knows about it if it does
+#[must_use]
+pub fn tvshow_nfo(title: &str, metadata: Option<&ArrMetadata>) -> String {
+    let mut elements = vec![element("title", title)];
+    if let Some(metadata) = metadata {
+        elements.extend(metadata.year.map(|year| element("year", &year.to_string())));
+        elements.extend(details(metadata));
+    }
+    document("tvshow", &elements)
+}
+
+/// The `.nfo` of an episode, named `Episode {episode}` when it has no name
+#[must_use]
+pub fn episode_nfo(show: &str, season: u8, episode: u8, name: Option<&str>) -> String {
+    let title = name.map_or_else(|| format!("Episode {episode}"), str::to_string);
+    document(
+        "episodedetails",
+        &[
+            element("title", &title),
+            element("showtitle", show),
+            element("season", &season.to_string()),
+            element("episode", &episode.to_string()),
+        ],
+    )
+}
+
+/// The folder a show's `tvshow.nfo` goes in, the one above the season folder when the
+/// episode is in one
+#[must_use]
+pub fn show_dir(episode_file: &Path) -> Option<&Path> {
+    let parent = episode_file.parent()?;
+    let name = parent.file_name()?.to_string_lossy();
+    if name == "Specials" || name.starts_with("Season ") {
+        parent.parent()
+    } else {
+        Some(parent)
+    }
+}
+
+/// The plot and ids of a movie or show, the first id being the one Kodi goes by
+fn details(metadata: &ArrMetadata) -> Vec<String> {
+    let mut elements: Vec<String> = metadata
+        .overview
+        .iter()
+        .map(|overview| element("plot", overview))
+        .collect();
+
+    // Kodi's default scraper matches shows on TheTVDB, and movies on TheMovieDB
+    let ids = [
+        ("tvdb", metadata.tvdb_id.map(|id| id.to_string())),
+        ("tmdb", metadata.tmdb_id.map(|id| id.to_string())),
+        ("imdb", metadata.imdb_id.clone()),
+    ];
+    let ids = ids
+        .into_iter()
+        .filter_map(|(kind, id)| Some((kind, id?)))
+        .enumerate()
+        .map(|(i, (kind, id))| {
+            let default = if i == 0 { " default=\"true\"" } else { "" };
+            format!(
+                "<uniqueid type=\"{kind}\"{default}>{}</uniqueid>",
+                escape(&id)
+            )
+        });
+
+    elements.extend(ids);
+    elements
+}
+
+fn element(name: &str, value: &str) -> String {
+    format!("<{name}>{}</{name}>", escape(value))
+}
+
+fn document(root: &str, elements: &[String]) -> String {
+    let elements = elements
+        .iter()
+        .map(|element| format!("    {element}"))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<{root}>\n{elements}\n</{root}>\n"
+    )
+}
+
+/// Escapes the characters XML gives a meaning to
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_movies_with_their_ids() {
+        let metadata = ArrMetadata {
+            title: "Heat".to_string(),
+            year: Some(1995),
+            runtime: Some(170),
+            overview: Some("Cops & robbers.".to_string()),
+            tmdb_id: Some(949),
+            imdb_id: Some("tt0113277".to_string()),
+            tvdb_id: None,
+            poster_url: None,
+            fanart_url: None,
+        };
+
+        assert_eq!(
+            movie_nfo("Heat", Some(1995), Some(&metadata)),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <movie>\n    \
+             <title>Heat</title>\n    \
+             <year>1995</year>\n    \
+             <runtime>170</runtime>\n    \
+             <plot>Cops &amp; robbers.</plot>\n    \
+             <uniqueid type=\"tmdb\" default=\"true\">949</uniqueid>\n    \
+             <uniqueid type=\"imdb\">tt0113277</uniqueid>\n\
+             </movie>\n"
+        );
+        assert!(
+            movie_nfo("Heat", None, None).contains("<movie>\n    <title>Heat</title>\n</movie>")
+        );
+    }
+
+    #[test]
+    fn writes_episodes_and_finds_the_show_folder() {
+        let nfo = episode_nfo("Friends", 2, 7, Some("The One with the Racecar Bed"));
+        assert!(nfo.contains("<title>The One with the Racecar Bed</title>"));
+        assert!(nfo.contains("<season>2</season>"));
+        assert!(episode_nfo("Friends", 2, 7, None).contains("<title>Episode 7</title>"));
+
+        assert_eq!(
+            show_dir(Path::new("/tv/Friends/Season 2/Friends - S02E07.mkv")),
+            Some(Path::new("/tv/Friends"))
+        );
+        assert_eq!(
+            show_dir(Path::new("/tv/Friends/Specials/Friends - S00E01.mkv")),
+            Some(Path::new("/tv/Friends"))
+        );
+        assert_eq!(
+            show_dir(Path::new("/tv/Friends/Friends - S02E07.mkv")),
+            Some(Path::new("/tv/Friends"))
+        );
+    }
+}
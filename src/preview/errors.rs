@@ -0,0 +1,40 @@
+use std::io;
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, PreviewError>;
+
+#[derive(Debug, Error)]
+pub enum PreviewError {
+    #[error("Failed to execute command: {0}")]
+    CommandExecutionError(String),
+
+    #[error("No titles found for disc {0}, the disc needs to be scanned first")]
+    NotScanned(u8),
+
+    #[error("No previews could be generated for disc {0}")]
+    NoPreviews(u8),
+}
+
+impl PreviewError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            PreviewError::CommandExecutionError(_) => {
+                tr!("Failed to run ffmpeg, make sure it is installed.")
+            }
+            PreviewError::NotScanned(drive_number) => {
+                tr!("The titles on disc {drive_number} have changed, please start the rip again.", drive_number = drive_number)
+            }
+            PreviewError::NoPreviews(drive_number) => tr!("No previews could be made for disc {drive_number}, ffmpeg may not be able to read it.", drive_number = drive_number),
+        }
+    }
+}
+
+impl From<io::Error> for PreviewError {
+    fn from(error: io::Error) -> Self {
+        PreviewError::CommandExecutionError(error.to_string())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod preview_core;
+
+pub use preview_core::{generate_previews, Previews, MAX_PREVIEWS};
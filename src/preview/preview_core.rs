@@ -0,0 +1,218 @@
+//! # Preview Core Module
+//!
+//! This module grabs a single frame from titles on a disc, so a user picking a title can
+//! see which one is the main feature and which are extras, trailers or a different cut.
+//!
+//! ## Overview
+//!
+//! - **`generate_previews`**: Grabs a frame from each title with `ffmpeg`, reading the disc
+//!   directly rather than ripping anything first.
+//!
+//! - **`Previews`**: The frames that were grabbed, kept in a temporary directory until
+//!   they are uploaded.
+//!
+//! ## Notes
+//!
+//! - Blu-ray titles are read through their playlist with `ffmpeg`'s `bluray:` protocol, and
+//!   DVD titles with its `dvdvideo` demuxer, so `ffmpeg` needs to be built with `libbluray`
+//!   and `libdvdnav` for each.
+//! - Frames are taken a tenth of the way into a title, past any studio logos.
+//! - Titles that can't be read are skipped, a preview is only a hint.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tempfile::TempDir;
+use tokio::process::Command;
+
+use crate::makemkv::makemkv_helpers::{parse_length, Title, SCANNED_TITLES};
+use crate::{debug, trace, warn};
+
+use super::errors::{PreviewError, Result};
+
+/// The most titles that get a preview. A Discord message holds up to 10 embeds, one of
+/// which is the rip's own.
+pub const MAX_PREVIEWS: usize = 9;
+
+/// How long to wait on a single frame before giving up on the title
+const FRAME_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Frames are scaled down to this width, which is plenty for an embed
+const FRAME_WIDTH: u32 = 480;
+
+/// Frames grabbed from the titles on a disc.
+///
+/// # Fields
+///
+/// * `dir` - The temporary directory holding the frames, removed when dropped.
+/// * `frames` - The title id and path of each frame, in the order the titles were given.
+#[derive(Debug)]
+pub struct Previews {
+    pub dir: TempDir,
+    pub frames: Vec<(u16, PathBuf)>,
+}
+
+/// Grabs a frame from each of the given titles on a disc, up to `MAX_PREVIEWS`.
+///
+/// The titles are looked up from the last scan of the disc, so this doesn't wait on
+/// `MakeMKV` again.
+///
+/// # Errors
+///
+/// Returns a `PreviewError` if the disc hasn't been scanned, `ffmpeg` can't be run, or
+/// none of the titles could be read.
+pub async fn generate_previews(drive_number: u8, title_ids: &[u16]) -> Result<Previews> {
+    let titles: Vec<Title> = {
+        let scanned = SCANNED_TITLES.lock().await;
+        let disc_info = scanned
+            .get(&drive_number)
+            .ok_or(PreviewError::NotScanned(drive_number))?;
+
+        title_ids
+            .iter()
+            .filter_map(|id| disc_info.titles.iter().find(|title| title.title_id == *id))
+            .take(MAX_PREVIEWS)
+            .cloned()
+            .collect()
+    };
+
+    if titles.is_empty() {
+        return Err(PreviewError::NotScanned(drive_number));
+    }
+
+    let dir = tempfile::Builder::new()
+        .prefix("cord-ripper-previews")
+        .tempdir()?;
+    let mut frames = Vec::new();
+
+    for title in &titles {
+        let path = dir.path().join(format!("title_{}.jpg", title.title_id));
+
+        let Some(input) = ffmpeg_input(drive_number, title) else {
+            debug!(
+                "Don't know how to read title {} ({}), skipping preview",
+                title.title_id, title.source_file
+            );
+            continue;
+        };
+
+        match grab_frame(input, frame_offset(title), &path).await {
+            Ok(true) => frames.push((title.title_id, path)),
+            Ok(false) => warn!("Failed to grab a frame from title {}", title.title_id),
+            Err(e) => return Err(e),
+        }
+    }
+
+    if frames.is_empty() {
+        return Err(PreviewError::NoPreviews(drive_number));
+    }
+
+    debug!(
+        "Generated {} previews for disc {}",
+        frames.len(),
+        drive_number
+    );
+    Ok(Previews { dir, frames })
+}
+
+/// The `ffmpeg` arguments that read a title, or `None` if its source isn't recognized
+fn ffmpeg_input(drive_number: u8, title: &Title) -> Option<Vec<String>> {
+    let device = crate::makemkv::drive_map::device_path(drive_number);
+
+    // Blu-ray titles are playlists, e.g. "00800.mpls"
+    if let Some(playlist) = title.source_file.strip_suffix(".mpls") {
+        let playlist: u32 = playlist.parse().ok()?;
+        return Some(vec![
+            "-playlist".to_string(),
+            playlist.to_string(),
+            "-i".to_string(),
+            format!("bluray:{device}"),
+        ]);
+    }
+
+    let dvd_title = title.original_title_id?;
+    Some(vec![
+        "-f".to_string(),
+        "dvdvideo".to_string(),
+        "-title".to_string(),
+        dvd_title.to_string(),
+        "-i".to_string(),
+        device,
+    ])
+}
+
+/// How far into a title to grab its frame, in seconds
+fn frame_offset(title: &Title) -> u32 {
+    parse_length(&title.length).map_or(60, |length| length / 10)
+}
+
+/// Grabs a single frame with `ffmpeg`. Returns whether a frame was saved, an error is only
+/// returned if `ffmpeg` can't be run at all.
+async fn grab_frame(input: Vec<String>, offset: u32, path: &std::path::Path) -> Result<bool> {
+    trace!("Grabbing frame at {}s to {}", offset, path.display());
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args([
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-ss",
+            &offset.to_string(),
+        ])
+        .args(input)
+        .args([
+            "-frames:v",
+            "1",
+            "-vf",
+            &format!("scale={FRAME_WIDTH}:-2"),
+            "-y",
+        ])
+        .arg(path)
+        .kill_on_drop(true);
+
+    match tokio::time::timeout(FRAME_TIMEOUT, command.output()).await {
+        Ok(Ok(output)) => {
+            if !output.status.success() {
+                trace!(
+                    "ffmpeg failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            Ok(output.status.success() && path.exists())
+        }
+        Ok(Err(e)) => Err(e.into()),
+        // Dropping the future kills ffmpeg
+        Err(_) => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Output;
+
+    use super::*;
+    use crate::makemkv::makemkv_helpers::parse_disc_info;
+
+    #[test]
+    fn grabs_dvd_titles_only_by_their_own_id() {
+        let stdout = [
+            r#"TINFO:0,9,0,"1:45:12""#,
+            r#"TINFO:0,24,0,"3""#,
+            r#"SINFO:0,0,21,0,"25""#,
+            r#"TINFO:1,9,0,"0:42:10""#,
+            r#"SINFO:1,0,21,0,"25""#,
+        ]
+        .join("\n");
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        };
+        let disc = parse_disc_info(&output).expect("disc info parses");
+
+        let input = ffmpeg_input(0, &disc.titles[0]).expect("the first title has an id");
+        assert_eq!(input[..4], ["-f", "dvdvideo", "-title", "3"]);
+        assert_eq!(ffmpeg_input(0, &disc.titles[1]), None);
+    }
+}
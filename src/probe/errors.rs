@@ -0,0 +1,25 @@
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, ProbeError>;
+
+#[derive(Debug, Error)]
+pub enum ProbeError {
+    #[error("Failed to execute command: {0}")]
+    CommandExecutionError(String),
+
+    #[error("ffprobe timed out reading {0}")]
+    TimedOut(String),
+
+    #[error("Failed to read ffprobe output: {0}")]
+    ParseError(String),
+
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl From<io::Error> for ProbeError {
+    fn from(error: io::Error) -> Self {
+        ProbeError::CommandExecutionError(error.to_string())
+    }
+}
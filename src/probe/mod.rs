@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod probe_core;
+
+pub use probe_core::{probe_files, validate_file, AudioTrack, MediaStats};
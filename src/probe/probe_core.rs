@@ -0,0 +1,381 @@
+//! # Probe Core Module
+//!
+//! This module reads what a rip actually saved with `ffprobe`, so the summary of a rip can
+//! show its size, duration, video bitrate and audio tracks, enough to sanity check its
+//! quality without opening the files.
+//!
+//! ## Overview
+//!
+//! - **`MediaStats`**: What the files of a rip hold, summed up over all of them.
+//!
+//! - **`AudioTrack`**: An audio track of a file, such as `eng TrueHD 7.1`.
+//!
+//! - **`probe_files`**: Probes the files a rip saved with `ffprobe`, and sums them up.
+//!
+//! - **`validate_file`**: Checks a ripped file can be read, has video and audio, and plays
+//!   for about as long as the disc said its title does.
+//!
+//! ## Notes
+//!
+//! - MKV files rarely carry a bitrate on the video stream itself, `MakeMKV` writes it into
+//!   the `BPS` tag instead, which is read when there is no other.
+//! - The files of a rip saved as several episodes or parts are summed up, the video
+//!   bitrate averaged over their durations and the audio tracks taken from the first.
+//! - A file `ffprobe` can't read is left out, the stats are only informative.
+//! - `MakeMKV` can exit cleanly having saved a file that doesn't play, such as one cut
+//!   short by a bad read, which is what validation is for. A file only fails it for what
+//!   `ffprobe` found in it, not for `ffprobe` being missing or slow.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::process::Command;
+
+use crate::{debug, tr, trace, warn};
+
+use super::errors::{ProbeError, Result};
+
+/// How long `ffprobe` gets to read a file, it only reads the headers
+const PROBE_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// How far the length of a ripped file can be off from the length the disc scan found
+/// for its title, as a percentage of it
+const LENGTH_TOLERANCE_PERCENT: u64 = 5;
+
+/// The least the length of a ripped file can be off by, in seconds, short titles are
+/// rounded to the second
+const MIN_LENGTH_TOLERANCE: u64 = 30;
+
+/// An audio track of a ripped file.
+///
+/// # Fields
+///
+/// * `language` - The ISO 639-2 code of its language, `und` when it has none.
+/// * `codec` - The name of its codec, e.g. `TrueHD` or `DTS-HD MA`.
+/// * `channels` - Its channel layout, e.g. `5.1`, or the number of channels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioTrack {
+    pub language: String,
+    pub codec: String,
+    pub channels: String,
+}
+
+impl fmt::Display for AudioTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.language, self.codec, self.channels)
+    }
+}
+
+/// What the files of a rip hold.
+///
+/// # Fields
+///
+/// * `size` - The size of the files, in bytes.
+/// * `duration` - How long they play for, in seconds.
+/// * `video_bitrate` - The average bitrate of their video, in bits per second, if known.
+/// * `audio_tracks` - The audio tracks of the first file, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaStats {
+    pub size: u64,
+    pub duration: u64,
+    pub video_bitrate: Option<u64>,
+    pub audio_tracks: Vec<AudioTrack>,
+}
+
+impl MediaStats {
+    /// Sums up the stats of several files, `None` if there are none
+    #[must_use]
+    pub fn combine(stats: &[MediaStats]) -> Option<MediaStats> {
+        let first = stats.first()?;
+
+        // Weighted by duration, so a short extra doesn't count as much as the movie
+        let with_bitrate: Vec<(u64, u64)> = stats
+            .iter()
+            .filter_map(|stats| Some((stats.video_bitrate?, stats.duration)))
+            .collect();
+        let timed: u64 = with_bitrate.iter().map(|(_, duration)| duration).sum();
+        let video_bitrate = match (with_bitrate.as_slice(), timed) {
+            ([], _) => None,
+            // Nothing to weigh them by, so they count the same
+            (_, 0) => Some(
+                with_bitrate.iter().map(|(bitrate, _)| bitrate).sum::<u64>()
+                    / with_bitrate.len() as u64,
+            ),
+            (_, timed) => Some(
+                with_bitrate
+                    .iter()
+                    .map(|(bitrate, duration)| bitrate * duration)
+                    .sum::<u64>()
+                    / timed,
+            ),
+        };
+
+        Some(MediaStats {
+            size: stats.iter().map(|stats| stats.size).sum(),
+            duration: stats.iter().map(|stats| stats.duration).sum(),
+            video_bitrate,
+            audio_tracks: first.audio_tracks.clone(),
+        })
+    }
+}
+
+/// Reads a whole number out of a JSON string such as `"8165.123000"`, dropping any
+/// fraction
+fn whole_number(value: &Value) -> Option<u64> {
+    value.as_str()?.split('.').next()?.parse().ok()
+}
+
+/// The bitrate of a video stream, from the stream itself or the `BPS` tag `MakeMKV` and
+/// `mkvmerge` write
+fn stream_bitrate(stream: &Value) -> Option<u64> {
+    whole_number(&stream["bit_rate"])
+        .or_else(|| whole_number(&stream["tags"]["BPS"]))
+        .or_else(|| whole_number(&stream["tags"]["BPS-eng"]))
+}
+
+/// The audio track of an audio stream, named the way they are on disc cases
+fn audio_track(stream: &Value) -> AudioTrack {
+    let codec_name = stream["codec_name"].as_str().unwrap_or_default();
+    let codec = match codec_name {
+        "truehd" => "TrueHD".to_string(),
+        "ac3" => "AC-3".to_string(),
+        "eac3" => "E-AC-3".to_string(),
+        // The profile tells DTS-HD Master Audio apart from the plain DTS core
+        "dts" => stream["profile"].as_str().unwrap_or("DTS").to_string(),
+        codec if codec.starts_with("pcm") => "PCM".to_string(),
+        codec => codec.to_uppercase(),
+    };
+
+    // Layouts such as "5.1(side)" are the same to anyone reading the summary
+    let channels = stream["channel_layout"]
+        .as_str()
+        .map(|layout| layout.split('(').next().unwrap_or(layout).to_string())
+        .or_else(|| {
+            stream["channels"]
+                .as_u64()
+                .map(|channels| format!("{channels}ch"))
+        })
+        .unwrap_or_default();
+
+    AudioTrack {
+        language: stream["tags"]["language"]
+            .as_str()
+            .unwrap_or("und")
+            .to_string(),
+        codec,
+        channels,
+    }
+}
+
+/// Reads the stats of a file out of the JSON `ffprobe` prints for it
+fn parse_probe(probe: &Value) -> Option<MediaStats> {
+    let streams = probe["streams"].as_array()?;
+    let of_type = |codec_type: &'static str| {
+        streams
+            .iter()
+            .filter(move |stream| stream["codec_type"] == codec_type)
+    };
+
+    Some(MediaStats {
+        size: whole_number(&probe["format"]["size"])?,
+        duration: whole_number(&probe["format"]["duration"]).unwrap_or_default(),
+        video_bitrate: of_type("video").find_map(stream_bitrate),
+        audio_tracks: of_type("audio").map(audio_track).collect(),
+    })
+}
+
+/// What is wrong with a ripped file, from what `ffprobe` printed for it, `None` if
+/// nothing is. `expected_length` is how long its title is in seconds, if known.
+fn validation_problem(probe: &Value, expected_length: Option<u64>) -> Option<String> {
+    let streams = probe["streams"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let has = |codec_type: &str| {
+        streams
+            .iter()
+            .any(|stream| stream["codec_type"] == codec_type)
+    };
+    if !has("video") {
+        return Some(tr!("it has no video stream"));
+    }
+    if !has("audio") {
+        return Some(tr!("it has no audio stream"));
+    }
+
+    let expected_length = expected_length.filter(|length| *length > 0)?;
+    let Some(duration) = whole_number(&probe["format"]["duration"]) else {
+        return Some(tr!("its length can't be read"));
+    };
+    let tolerance = (expected_length * LENGTH_TOLERANCE_PERCENT / 100).max(MIN_LENGTH_TOLERANCE);
+    (duration.abs_diff(expected_length) > tolerance).then(|| {
+        tr!(
+            "it plays for {duration}s, but the title is {expected}s long",
+            duration = duration,
+            expected = expected_length
+        )
+    })
+}
+
+/// Runs `ffprobe` on a file, failing as invalid if it can't read the file
+async fn ffprobe(file: &Path) -> Result<Value> {
+    let mut command = Command::new("ffprobe");
+    command
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        .arg(file)
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(PROBE_TIMEOUT, command.output())
+        .await
+        .map_err(|_| ProbeError::TimedOut(file.display().to_string()))??;
+    if !output.status.success() {
+        return Err(ProbeError::Invalid(tr!(
+            "ffprobe can't read it: {reason}",
+            reason = String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let probe: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| ProbeError::ParseError(e.to_string()))?;
+    trace!("Probed {}: {}", file.display(), probe);
+    Ok(probe)
+}
+
+/// Reads the stats of a single file with `ffprobe`
+async fn probe_file(file: &Path) -> Result<MediaStats> {
+    let probe = ffprobe(file).await?;
+    parse_probe(&probe)
+        .ok_or_else(|| ProbeError::ParseError(format!("no size or streams for {}", file.display())))
+}
+
+/// Probes the files a rip saved with `ffprobe` and sums them up, `None` if none of them
+/// could be read. Failures are only logged, the stats are only informative.
+pub async fn probe_files(files: &[PathBuf]) -> Option<MediaStats> {
+    let mut stats = Vec::new();
+    for file in files {
+        match probe_file(file).await {
+            Ok(file_stats) => stats.push(file_stats),
+            Err(e) => warn!("Failed to probe {}: {}", file.display(), e),
+        }
+    }
+
+    let combined = MediaStats::combine(&stats);
+    debug!("Probed {} of {} files", stats.len(), files.len());
+    combined
+}
+
+/// Checks a ripped file can be read by `ffprobe`, has at least one video and one audio
+/// stream, and plays for about `expected_length` seconds when the length of its title is
+/// known. Meant for whole titles, an episode split out of one is only a piece of it.
+///
+/// # Errors
+///
+/// Returns `ProbeError::Invalid` if the file fails any of the checks, or another
+/// `ProbeError` if it couldn't be checked at all, such as when `ffprobe` isn't installed.
+pub async fn validate_file(file: &Path, expected_length: Option<u64>) -> Result<()> {
+    let probe = ffprobe(file).await?;
+    if let Some(problem) = validation_problem(&probe, expected_length) {
+        return Err(ProbeError::Invalid(problem));
+    }
+
+    debug!("Validated {}", file.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_ffprobe_output() {
+        let probe = serde_json::json!({
+            "streams": [
+                {"codec_type": "video", "codec_name": "h264", "tags": {"BPS-eng": "28456123"}},
+                {"codec_type": "audio", "codec_name": "truehd", "channel_layout": "7.1",
+                    "tags": {"language": "eng"}},
+                {"codec_type": "audio", "codec_name": "dts", "profile": "DTS-HD MA",
+                    "channel_layout": "5.1(side)", "tags": {"language": "fra"}},
+                {"codec_type": "audio", "codec_name": "pcm_bluray", "channels": 2},
+                {"codec_type": "subtitle", "codec_name": "hdmv_pgs_subtitle"}
+            ],
+            "format": {"size": "33554432000", "duration": "8165.123000"}
+        });
+
+        let stats = parse_probe(&probe).expect("probe parses");
+        assert_eq!(stats.size, 33_554_432_000);
+        assert_eq!(stats.duration, 8165);
+        assert_eq!(stats.video_bitrate, Some(28_456_123));
+        let audio: Vec<String> = stats.audio_tracks.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            audio,
+            vec!["eng TrueHD 7.1", "fra DTS-HD MA 5.1", "und PCM 2ch"]
+        );
+    }
+
+    #[test]
+    fn flags_files_that_wont_play_right() {
+        let probe = |streams: &[&str], duration: &str| {
+            serde_json::json!({
+                "streams": streams
+                    .iter()
+                    .map(|codec_type| serde_json::json!({"codec_type": codec_type}))
+                    .collect::<Vec<Value>>(),
+                "format": {"duration": duration}
+            })
+        };
+        let full = probe(&["video", "audio", "subtitle"], "7200.5");
+
+        assert_eq!(validation_problem(&full, Some(7210)), None);
+        assert_eq!(validation_problem(&full, None), None);
+        assert!(validation_problem(&full, Some(8000)).is_some());
+        assert!(validation_problem(&probe(&["video"], "7200"), None).is_some());
+        assert!(validation_problem(&probe(&["audio"], "7200"), None).is_some());
+
+        // A short title gets at least half a minute either way
+        let short = probe(&["video", "audio"], "95");
+        assert_eq!(validation_problem(&short, Some(120)), None);
+        assert!(validation_problem(&short, Some(130)).is_some());
+    }
+
+    #[test]
+    fn combines_files_weighted_by_duration() {
+        let movie = MediaStats {
+            size: 30,
+            duration: 3000,
+            video_bitrate: Some(30_000_000),
+            audio_tracks: vec![AudioTrack {
+                language: "eng".to_string(),
+                codec: "TrueHD".to_string(),
+                channels: "7.1".to_string(),
+            }],
+        };
+        let extra = MediaStats {
+            size: 1,
+            duration: 1000,
+            video_bitrate: Some(10_000_000),
+            audio_tracks: Vec::new(),
+        };
+        let unknown = MediaStats {
+            size: 2,
+            duration: 500,
+            video_bitrate: None,
+            audio_tracks: Vec::new(),
+        };
+
+        let combined = MediaStats::combine(&[movie.clone(), extra, unknown]).unwrap();
+        assert_eq!(combined.size, 33);
+        assert_eq!(combined.duration, 4500);
+        assert_eq!(combined.video_bitrate, Some(25_000_000));
+        assert_eq!(combined.audio_tracks, movie.audio_tracks);
+        assert_eq!(MediaStats::combine(&[]), None);
+    }
+}
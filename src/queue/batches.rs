@@ -0,0 +1,233 @@
+//! # Batches
+//!
+//! A show rip of several titles runs as a single job, and when it stops partway, because
+//! the remaining episodes were cancelled or the bot stopped, the titles it had left would
+//! otherwise have to be picked out again. This module remembers the titles each batch has
+//! left, so it can be resumed from where it stopped.
+//!
+//! ## Overview
+//!
+//! - **`Batch`**: The titles a batch has left to rip, and the message it is shown on.
+//!
+//! - **`Batches`**: The batches that have titles left, stored as JSON in the data
+//!   directory, and which of them are running.
+//!
+//! - **`BATCHES`**: A globally accessible, thread-safe instance of `Batches`.
+//!
+//! - **`run`**: Marks the batch of a message as running, for as long as the returned
+//!   `Running` is kept.
+//!
+//! ## Notes
+//!
+//! - Batches are known by the id of their message, job ids start over when the bot
+//!   restarts and a resumed batch carries on in the same message.
+//! - A batch is forgotten once it has been through all of its titles, titles that failed
+//!   are retried from its summary instead.
+//! - Unlike job messages, batches are kept across restarts, a batch the bot was running
+//!   when it stopped is simply no longer running.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex, MutexGuard, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::history_core::write_json_atomic;
+use crate::makemkv::Rip;
+use crate::{debug, info, trace, warn};
+
+/// The name of the batch file within the data directory
+const BATCHES_FILE: &str = "batches.json";
+
+/// A globally accessible instance of `Batches` for resuming batches that stopped partway.
+pub static BATCHES: LazyLock<Mutex<Batches>> = LazyLock::new(Mutex::default);
+
+/// Locks `BATCHES`, a panic while it was held leaves nothing half done
+pub fn batches() -> MutexGuard<'static, Batches> {
+    BATCHES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The titles a batch has left to rip.
+///
+/// # Fields
+///
+/// * `channel_id` - The id of the channel the batch's message is in.
+/// * `message_id` - The id of the message the batch is shown on.
+/// * `label` - What the batch is ripping, such as the show and season.
+/// * `owner` - The id of the user who started the batch.
+/// * `rips` - The titles left, starting with the one being ripped, numbered as they will
+///   be saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Batch {
+    pub channel_id: u64,
+    pub message_id: u64,
+    pub label: String,
+    pub owner: u64,
+    pub rips: Vec<Rip>,
+}
+
+/// The batches that have titles left.
+///
+/// # Fields
+///
+/// * `remaining` - The batches, by the id of their message.
+/// * `running` - The message ids of the batches being ripped right now.
+/// * `path` - Where the batches are saved, empty until `init` has been called.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Batches {
+    remaining: BTreeMap<u64, Batch>,
+    #[serde(skip)]
+    running: HashSet<u64>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Batches {
+    /// Loads the batches left over from before the bot last stopped. A file that can't be
+    /// read is only logged, at worst those batches can't be resumed.
+    pub fn init(&mut self, data_dir: &Path) {
+        let path = data_dir.join(BATCHES_FILE);
+
+        if path.exists() {
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    serde_json::from_str::<Batches>(&contents).map_err(|e| e.to_string())
+                });
+            match loaded {
+                Ok(loaded) => {
+                    self.remaining = loaded.remaining;
+                    debug!("Found {} batch(es) to resume", self.remaining.len());
+                }
+                Err(e) => warn!("Failed to load the batches: {}", e),
+            }
+        }
+
+        self.path = path;
+
+        info!("Batches initialized successfully!");
+    }
+
+    /// Writes the batches to disk, failing to is only logged since they are only needed
+    /// if a batch stops partway
+    fn save(&self) {
+        // Batches are only kept in memory until init has been called
+        if self.path.as_os_str().is_empty() {
+            return;
+        }
+
+        match write_json_atomic(&self.path, self) {
+            Ok(()) => trace!("Saved batches to {}", self.path.display()),
+            Err(e) => warn!("Failed to save the batches: {}", e),
+        }
+    }
+
+    /// Remembers the titles a batch has left, replacing what it had left before
+    pub fn remember(&mut self, batch: Batch) {
+        self.remaining.insert(batch.message_id, batch);
+        self.save();
+    }
+
+    /// Forgets the batch of a message, once it has been through all of its titles
+    pub fn forget(&mut self, message_id: u64) {
+        if self.remaining.remove(&message_id).is_some() {
+            self.save();
+        }
+    }
+
+    /// The batch of a message, if it has titles left
+    #[must_use]
+    pub fn get(&self, message_id: u64) -> Option<&Batch> {
+        self.remaining.get(&message_id)
+    }
+
+    /// Whether the batch of a message is being ripped right now
+    #[must_use]
+    pub fn is_running(&self, message_id: u64) -> bool {
+        self.running.contains(&message_id)
+    }
+
+    /// The batches that stopped partway and can be resumed
+    #[must_use]
+    pub fn stopped(&self) -> Vec<&Batch> {
+        self.remaining
+            .values()
+            .filter(|batch| !self.running.contains(&batch.message_id))
+            .collect()
+    }
+}
+
+/// Marks the batch of a message as running until it is dropped, however the batch ended.
+#[derive(Debug)]
+pub struct Running {
+    message_id: u64,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        batches().running.remove(&self.message_id);
+    }
+}
+
+/// Marks the batch of a message as running, until the returned `Running` is dropped
+#[must_use]
+pub fn run(message_id: u64) -> Running {
+    batches().running.insert(message_id);
+    Running { message_id }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::makemkv::RipType;
+
+    fn batch(message_id: u64, episodes: &[u8]) -> Batch {
+        Batch {
+            channel_id: 1,
+            message_id,
+            label: "Frasier Season 2".to_string(),
+            owner: 3,
+            rips: episodes
+                .iter()
+                .map(|&episode| Rip {
+                    title: "Frasier".to_string(),
+                    drive_number: 0,
+                    rip_type: RipType::Show {
+                        season: 2,
+                        episode,
+                        absolute_episode: None,
+                    },
+                    title_id: u16::from(episode),
+                    year: None,
+                    chapters_per_episode: None,
+                    library: None,
+                    episode_title: None,
+                    note: None,
+                    extra: None,
+                    base_view: false,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn batches_left_behind_can_be_resumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut batches = Batches::default();
+        batches.init(dir.path());
+        batches.remember(batch(10, &[5, 6, 7, 8]));
+        batches.remember(batch(20, &[1]));
+        batches.forget(20);
+        batches.running.insert(10);
+        assert!(batches.stopped().is_empty());
+
+        let mut restarted = Batches::default();
+        restarted.init(dir.path());
+        let stopped = restarted.stopped();
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0].rips.len(), 4);
+        assert_eq!(stopped[0].rips[0].episode(), Some(5));
+    }
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, QueueError>;
+
+#[derive(Debug, Error)]
+pub enum QueueError {
+    #[error("No job found in the queue with id {0}")]
+    JobNotFound(u64),
+
+    #[error("Job {0} has already started")]
+    JobAlreadyStarted(u64),
+
+    #[error("Invalid priority: {0}")]
+    InvalidPriority(String),
+}
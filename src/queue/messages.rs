@@ -0,0 +1,246 @@
+//! # Job Messages
+//!
+//! Running jobs show their progress on a Discord message, which is edited by whatever
+//! follows the job, normally the interaction that started it. This module remembers the
+//! message of every running job, so its progress can be picked up again once nothing
+//! follows it anymore, such as after the bot reconnects to Discord.
+//!
+//! ## Overview
+//!
+//! - **`JobMessage`**: The message a job shows its progress on.
+//!
+//! - **`JobMessages`**: The messages of the running jobs, stored as JSON in the data
+//!   directory, and which of them are being followed.
+//!
+//! - **`JOB_MESSAGES`**: A globally accessible, thread-safe instance of `JobMessages`.
+//!
+//! - **`follow`**: Marks the message of a job as followed, for as long as the returned
+//!   `Following` is kept.
+//!
+//! ## Notes
+//!
+//! - A message is forgotten once its job leaves the queue, see `JobTicket`.
+//! - Jobs don't outlive the bot, so the messages left in the file at startup are of jobs
+//!   that were interrupted by it stopping, see `take_interrupted`.
+//! - Only ids are stored, this module knows nothing of Discord itself.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::history_core::write_json_atomic;
+use crate::{debug, info, trace, warn};
+
+/// The name of the job message file within the data directory
+const JOB_MESSAGES_FILE: &str = "job_messages.json";
+
+/// A globally accessible instance of `JobMessages` for finding the messages of jobs.
+pub static JOB_MESSAGES: LazyLock<Mutex<JobMessages>> = LazyLock::new(Mutex::default);
+
+/// The message a job shows its progress on.
+///
+/// # Fields
+///
+/// * `channel_id` - The id of the channel the message is in.
+/// * `message_id` - The id of the message.
+/// * `label` - What the job is doing, such as the title being ripped.
+/// * `drive_number` - The drive the job rips from.
+/// * `started` - When the job started, in seconds since the unix epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobMessage {
+    pub channel_id: u64,
+    pub message_id: u64,
+    pub label: String,
+    pub drive_number: u8,
+    pub started: u64,
+}
+
+/// The messages of the running jobs.
+///
+/// # Fields
+///
+/// * `messages` - The message of each running job, by job id.
+/// * `followed` - The ids of the jobs whose message is being edited.
+/// * `interrupted` - The messages of the jobs that were running when the bot last stopped.
+/// * `path` - Where the messages are saved, empty until `init` has been called.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JobMessages {
+    messages: BTreeMap<u64, JobMessage>,
+    #[serde(skip)]
+    followed: HashSet<u64>,
+    #[serde(skip)]
+    interrupted: Vec<JobMessage>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl JobMessages {
+    /// Loads the messages of the jobs that were running when the bot last stopped, which
+    /// are kept aside as interrupted. A file that can't be read is only logged, at worst
+    /// those messages aren't marked as interrupted.
+    pub fn init(&mut self, data_dir: &Path) {
+        let path = data_dir.join(JOB_MESSAGES_FILE);
+
+        if path.exists() {
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    serde_json::from_str::<JobMessages>(&contents).map_err(|e| e.to_string())
+                });
+            match loaded {
+                Ok(loaded) => {
+                    self.interrupted = loaded.messages.into_values().collect();
+                    debug!(
+                        "Found the messages of {} interrupted job(s)",
+                        self.interrupted.len()
+                    );
+                }
+                Err(e) => warn!("Failed to load the job messages: {}", e),
+            }
+        }
+
+        self.path = path;
+        self.messages.clear();
+        self.save();
+
+        info!("Job messages initialized successfully!");
+    }
+
+    /// Writes the messages to disk, failing to is only logged since they are only needed
+    /// if something else goes wrong
+    fn save(&self) {
+        // Messages are only kept in memory until init has been called
+        if self.path.as_os_str().is_empty() {
+            return;
+        }
+
+        match write_json_atomic(&self.path, self) {
+            Ok(()) => trace!("Saved job messages to {}", self.path.display()),
+            Err(e) => warn!("Failed to save the job messages: {}", e),
+        }
+    }
+
+    /// Remembers the message of a job, as followed
+    fn track(&mut self, job_id: u64, message: JobMessage) {
+        self.followed.insert(job_id);
+        if self.messages.get(&job_id) != Some(&message) {
+            self.messages.insert(job_id, message);
+            self.save();
+        }
+    }
+
+    /// Forgets the message of a job, once the job has left the queue
+    pub fn finish(&mut self, job_id: u64) {
+        self.followed.remove(&job_id);
+        if self.messages.remove(&job_id).is_some() {
+            self.save();
+        }
+    }
+
+    /// Takes the messages of the jobs that were running when the bot last stopped, they are
+    /// only handed out once
+    pub fn take_interrupted(&mut self) -> Vec<JobMessage> {
+        std::mem::take(&mut self.interrupted)
+    }
+
+    /// Takes over the messages of the running jobs that nothing follows, each is followed
+    /// for as long as its `Following` is kept
+    pub fn take_unfollowed(&mut self) -> Vec<(u64, JobMessage, Following)> {
+        let unfollowed: Vec<(u64, JobMessage)> = self
+            .messages
+            .iter()
+            .filter(|(job_id, _)| !self.followed.contains(job_id))
+            .map(|(job_id, message)| (*job_id, message.clone()))
+            .collect();
+
+        unfollowed
+            .into_iter()
+            .map(|(job_id, message)| {
+                self.followed.insert(job_id);
+                (job_id, message, Following { job_id })
+            })
+            .collect()
+    }
+}
+
+/// Marks the message of a job as followed until it is dropped, however the follower ended.
+#[derive(Debug)]
+pub struct Following {
+    job_id: u64,
+}
+
+impl Drop for Following {
+    fn drop(&mut self) {
+        JOB_MESSAGES
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .followed
+            .remove(&self.job_id);
+    }
+}
+
+/// Remembers the message of a job, which is followed until the returned `Following` is
+/// dropped
+#[must_use]
+pub fn follow(job_id: u64, message: JobMessage) -> Following {
+    JOB_MESSAGES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .track(job_id, message);
+    Following { job_id }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(message_id: u64) -> JobMessage {
+        JobMessage {
+            channel_id: 1,
+            message_id,
+            label: "Heat".to_string(),
+            drive_number: 0,
+            started: 0,
+        }
+    }
+
+    #[test]
+    fn only_unfollowed_messages_are_taken_over() {
+        let mut messages = JobMessages::default();
+        messages.track(1, message(10));
+        messages.track(2, message(20));
+        messages.followed.remove(&2);
+
+        let taken = messages.take_unfollowed();
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].0, 2);
+        assert!(messages.take_unfollowed().is_empty());
+
+        messages.finish(1);
+        assert!(!messages.messages.contains_key(&1));
+    }
+
+    #[test]
+    fn messages_left_behind_are_interrupted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut messages = JobMessages {
+            path: dir.path().join(JOB_MESSAGES_FILE),
+            ..JobMessages::default()
+        };
+        messages.track(1, message(10));
+
+        let mut restarted = JobMessages::default();
+        restarted.init(dir.path());
+        assert_eq!(restarted.take_interrupted(), vec![message(10)]);
+        assert!(restarted.take_interrupted().is_empty());
+
+        // Nothing is left over for the restart after
+        let mut again = JobMessages::default();
+        again.init(dir.path());
+        assert!(again.take_interrupted().is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+pub mod batches;
+pub mod errors;
+pub mod messages;
+pub mod queue_core;
+pub mod transfers;
+pub mod worker;
+
+pub use queue_core::{JobKind, JobTicket, Priority, QUEUE};
@@ -0,0 +1,719 @@
+//! # Queue Core Module
+//!
+//! This module schedules rip and transcode jobs so only a limited number of each run at
+//! once, with the rest waiting their turn in the queue.
+//!
+//! ## Overview
+//!
+//! - **`Priority`**: How urgently a job should be run, high priority jobs are always
+//!   started before normal ones, and normal ones before low ones.
+//!
+//! - **`Job`**: A single submission, such as a movie or a batch of show episodes from one
+//!   disc.
+//!
+//! - **`JobKind`**: Whether a job rips from a drive or transcodes, each kind has its own
+//!   number of slots.
+//!
+//! - **`RipQueue`**: The jobs that are running or waiting, ordered by priority and then by
+//!   when they were submitted.
+//!
+//! - **`JobTicket`**: Handed out when a job is submitted, and used to wait for the job's
+//!   turn. The job is removed from the queue when its ticket is dropped.
+//!
+//! - **`QUEUE`**: A globally accessible, thread-safe instance of `RipQueue`.
+//!
+//! ## Notes
+//!
+//! - Waiting jobs are woken whenever the queue changes, and check again whether it is
+//!   their turn.
+//! - A drive can only rip one disc at a time, so a rip waiting on a busy drive is skipped
+//!   over and the next job for a free drive is started instead.
+//! - Each machine drives are on, this one and every agent, has its own rip slots. A rip on
+//!   an idle agent starts straight away instead of waiting behind rips on a busy one.
+//! - Rips run on workers of their job, see the `worker` module, which hold on to the job's
+//!   ticket until the rip is over.
+//! - Writing the notes and forced subtitles into a finished rip is a transcode job of its
+//!   own, submitted by the rip with the rip's priority, see `Rip::execute`.
+//! - A rip copying its files to a library on another mount hands its drive and rip slot
+//!   back while it does, and takes a transfer slot instead, see the `transfers` module.
+//! - The message each job shows its progress on is remembered while it runs, see the
+//!   `messages` module.
+
+use std::{
+    cmp::Reverse,
+    fmt,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
+
+use tokio::sync::{futures::Notified, Mutex, Notify};
+
+use crate::history::history_core::now;
+use crate::makemkv::drive_map::drive_location;
+use crate::{debug, info, trace};
+
+use super::errors::{QueueError, Result};
+use super::messages::JOB_MESSAGES;
+
+/// A globally accessible instance of `RipQueue` for scheduling rips.
+pub static QUEUE: LazyLock<Arc<Mutex<RipQueue>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(RipQueue::default())));
+
+/// Notified whenever a job is added, started, finished, or bumped.
+static QUEUE_CHANGED: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+/// How urgently a job should be run.
+///
+/// * `Low` - Run once no normal or high priority job is waiting.
+/// * `Normal` - Run in the order it was submitted.
+/// * `High` - Run before every normal priority job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Normal => write!(f, "Normal"),
+            Priority::High => write!(f, "High"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = QueueError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            _ => Err(QueueError::InvalidPriority(s.to_string())),
+        }
+    }
+}
+
+/// What a job does, each kind has its own number of slots.
+///
+/// * `Rip` - Rips a disc from the given drive.
+/// * `Transcode` - Transcodes a ripped file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Rip { drive_number: u8 },
+    Transcode,
+}
+
+/// Where a job is at.
+///
+/// * `Queued` - Waiting for its turn, or to get its drive back after copying.
+/// * `Running` - Running, holding its drive and a slot of its kind.
+/// * `Copying` - Copying what it ripped to the library, holding a transfer slot only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Copying,
+}
+
+/// Why a waiting job hasn't started yet, shown in the queue.
+///
+/// * `Queued` - Other jobs are ahead of it in line.
+/// * `WaitingForSlot` - It is next in line, but every slot for its kind is in use.
+/// * `WaitingForDrive` - Its drive is busy with another job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitReason {
+    Queued,
+    WaitingForSlot,
+    WaitingForDrive,
+}
+
+/// A single submission to the queue.
+///
+/// # Fields
+///
+/// * `id` - A unique, incrementing identifier for the job.
+/// * `label` - A short description of the job, shown in the queue.
+/// * `kind` - Whether the job rips or transcodes.
+/// * `host` - The agent a rip reads from, `None` for this machine and for transcodes.
+/// * `priority` - How urgently the job should be run.
+/// * `state` - Whether the job is waiting or running.
+/// * `submitted` - When the job was submitted, in seconds since the unix epoch.
+/// * `order` - The position of the job within its priority, lower runs first.
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: u64,
+    pub label: String,
+    pub kind: JobKind,
+    pub host: Option<String>,
+    pub priority: Priority,
+    pub state: JobState,
+    pub submitted: u64,
+    order: i64,
+}
+
+#[derive(Debug)]
+pub struct RipQueue {
+    next_id: u64,
+    next_order: i64,
+    jobs: Vec<Job>,
+    max_rips: usize,
+    max_transcodes: usize,
+    max_transfers: usize,
+}
+
+impl Default for RipQueue {
+    fn default() -> Self {
+        RipQueue {
+            next_id: 0,
+            next_order: 0,
+            jobs: Vec::new(),
+            max_rips: 1,
+            max_transcodes: 1,
+            max_transfers: 1,
+        }
+    }
+}
+
+/// The `RipQueue` struct holds every job that is running or waiting to run.
+///
+/// # Methods
+///
+/// ## `init`
+/// Sets the number of rips on each machine, transcodes and transfers that can run at the
+/// same time.
+///
+/// ## `submit`
+/// Adds a job to the end of its priority and returns its id.
+///
+/// ## `try_start`
+/// Starts a job if it is next in line and there is a free slot.
+///
+/// ## `finish`
+/// Removes a job from the queue.
+///
+/// ## `start_copy`
+/// Hands a running job's drive and slot back while it copies, if there is a transfer slot.
+///
+/// ## `finish_copy`
+/// Puts a job that is done copying back in line for its drive.
+///
+/// ## `bump`
+/// Moves a waiting job to the front of the high priority jobs.
+///
+/// ## `jobs`
+/// Returns the running jobs followed by the waiting jobs, in the order they will run.
+///
+/// ## `jobs_ahead`
+/// Returns the number of jobs that will run before a waiting job.
+///
+/// ## `running_rip`
+/// Returns the rip running on a drive.
+///
+/// ## `wait_reason`
+/// Returns why a waiting job hasn't started yet.
+impl RipQueue {
+    /// Sets the number of rips on each machine, transcodes and transfers that can run at
+    /// the same time, at least one of each is always allowed.
+    pub fn init(&mut self, max_rips: usize, max_transcodes: usize, max_transfers: usize) {
+        self.max_rips = max_rips.max(1);
+        self.max_transcodes = max_transcodes.max(1);
+        self.max_transfers = max_transfers.max(1);
+
+        info!(
+            "Queue initialized, running up to {} rip(s) per machine, {} transcode(s) and {} transfer(s) at once",
+            self.max_rips, self.max_transcodes, self.max_transfers
+        );
+    }
+
+    /// Adds a job to the end of its priority and returns its id. `host` is the agent a rip
+    /// reads from, if any.
+    pub fn submit(
+        &mut self,
+        label: &str,
+        kind: JobKind,
+        host: Option<String>,
+        priority: Priority,
+    ) -> u64 {
+        self.next_id += 1;
+        self.next_order += 1;
+
+        self.jobs.push(Job {
+            id: self.next_id,
+            label: label.to_string(),
+            kind,
+            host,
+            priority,
+            state: JobState::Queued,
+            submitted: now(),
+            order: self.next_order,
+        });
+
+        info!(
+            "Queued job {}: {} ({} priority)",
+            self.next_id, label, priority
+        );
+        self.next_id
+    }
+
+    /// Starts a job if it is next in line and there is a free slot, returning whether the
+    /// job is running.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QueueError` if the job is no longer in the queue.
+    pub fn try_start(&mut self, id: u64) -> Result<bool> {
+        match self.wait_reason(id)? {
+            None => (),
+            Some(reason) => {
+                trace!("Job {} is still waiting: {:?}", id, reason);
+                return Ok(false);
+            }
+        }
+
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            job.state = JobState::Running;
+            debug!("Started job {}: {}", job.id, job.label);
+        }
+
+        Ok(true)
+    }
+
+    /// Removes a job from the queue, whether it was running or still waiting.
+    pub fn finish(&mut self, id: u64) {
+        self.jobs.retain(|job| job.id != id);
+        debug!("Removed job {} from the queue", id);
+    }
+
+    /// Hands the drive and slot of a running job back while it copies its files to the
+    /// library, returning whether there was a transfer slot for it. A job that has to wait
+    /// for one keeps its drive, so rips can't pile up faster than they can be copied.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QueueError` if the job is no longer in the queue.
+    pub fn start_copy(&mut self, id: u64) -> Result<bool> {
+        let copying = self
+            .jobs
+            .iter()
+            .filter(|job| job.state == JobState::Copying)
+            .count();
+
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or(QueueError::JobNotFound(id))?;
+
+        if job.state == JobState::Copying {
+            return Ok(true);
+        }
+        if copying >= self.max_transfers {
+            trace!("Job {} is waiting for a transfer slot", id);
+            return Ok(false);
+        }
+
+        job.state = JobState::Copying;
+        debug!("Job {} is copying to the library: {}", job.id, job.label);
+        Ok(true)
+    }
+
+    /// Puts a job that is done copying back in line, ahead of the jobs submitted after it,
+    /// for when it has more to rip.
+    pub fn finish_copy(&mut self, id: u64) {
+        if let Some(job) = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id && job.state == JobState::Copying)
+        {
+            job.state = JobState::Queued;
+            debug!("Job {} is done copying: {}", job.id, job.label);
+        }
+    }
+
+    /// Moves a waiting job to the front of the high priority jobs.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QueueError` if the job doesn't exist or has already started.
+    pub fn bump(&mut self, id: u64) -> Result<()> {
+        let first_order = self.jobs.iter().map(|job| job.order).min().unwrap_or(0);
+
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or(QueueError::JobNotFound(id))?;
+
+        if job.state != JobState::Queued {
+            return Err(QueueError::JobAlreadyStarted(id));
+        }
+
+        job.priority = Priority::High;
+        job.order = first_order - 1;
+
+        info!("Bumped job {}: {}", job.id, job.label);
+        QUEUE_CHANGED.notify_waiters();
+        Ok(())
+    }
+
+    /// Returns the running and copying jobs followed by the waiting jobs, in the order they
+    /// will run.
+    #[must_use]
+    pub fn jobs(&self) -> Vec<&Job> {
+        self.jobs
+            .iter()
+            .filter(|job| job.state != JobState::Queued)
+            .chain(self.waiting())
+            .collect()
+    }
+
+    /// Returns the rip job running on a drive, if there is one.
+    #[must_use]
+    pub fn running_rip(&self, drive_number: u8) -> Option<&Job> {
+        self.jobs
+            .iter()
+            .find(|job| job.state == JobState::Running && job.kind == JobKind::Rip { drive_number })
+    }
+
+    /// Returns the number of jobs that will run before a waiting job.
+    #[must_use]
+    pub fn jobs_ahead(&self, id: u64) -> usize {
+        self.jobs()
+            .iter()
+            .position(|job| job.id == id)
+            .unwrap_or_default()
+    }
+
+    /// Returns why a waiting job hasn't started yet, or `None` if it is running or free
+    /// to start.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QueueError` if the job is no longer in the queue.
+    pub fn wait_reason(&self, id: u64) -> Result<Option<WaitReason>> {
+        let job = self
+            .jobs
+            .iter()
+            .find(|job| job.id == id)
+            .ok_or(QueueError::JobNotFound(id))?;
+
+        match job.state {
+            JobState::Running => return Ok(None),
+            // Only back in line once it is done copying
+            JobState::Copying => return Ok(Some(WaitReason::Queued)),
+            JobState::Queued => (),
+        }
+
+        let is_transcode = job.kind == JobKind::Transcode;
+        let max_running = if is_transcode {
+            self.max_transcodes
+        } else {
+            self.max_rips
+        };
+
+        // Rips only compete for the slots of the machine their drive is on
+        let same_kind = |other: &Job| {
+            (other.kind == JobKind::Transcode) == is_transcode
+                && (is_transcode || other.host == job.host)
+        };
+        let drive_busy = |kind: JobKind| {
+            self.jobs.iter().any(|other| {
+                other.state == JobState::Running
+                    && matches!(kind, JobKind::Rip { .. })
+                    && other.kind == kind
+            })
+        };
+
+        if drive_busy(job.kind) {
+            return Ok(Some(WaitReason::WaitingForDrive));
+        }
+
+        // An earlier job for the same drive has to go first, the disc can't be in two
+        // places at once
+        let same_drive_ahead = matches!(job.kind, JobKind::Rip { .. })
+            && self
+                .waiting()
+                .into_iter()
+                .take_while(|other| other.id != id)
+                .any(|other| other.kind == job.kind);
+
+        if same_drive_ahead {
+            return Ok(Some(WaitReason::Queued));
+        }
+
+        let running = self
+            .jobs
+            .iter()
+            .filter(|other| other.state == JobState::Running && same_kind(other))
+            .count();
+
+        // Jobs of the same kind ahead of this one that could start right now, those
+        // stuck behind a busy drive don't hold up the rest of the queue
+        let ahead = self
+            .waiting()
+            .into_iter()
+            .filter(|other| same_kind(other) && !drive_busy(other.kind))
+            .take_while(|other| other.id != id)
+            .count();
+
+        if running + ahead < max_running {
+            Ok(None)
+        } else if ahead == 0 {
+            Ok(Some(WaitReason::WaitingForSlot))
+        } else {
+            Ok(Some(WaitReason::Queued))
+        }
+    }
+
+    /// The waiting jobs, highest priority first and then in the order they were submitted
+    fn waiting(&self) -> Vec<&Job> {
+        let mut waiting: Vec<&Job> = self
+            .jobs
+            .iter()
+            .filter(|job| job.state == JobState::Queued)
+            .collect();
+        waiting.sort_by_key(|job| (Reverse(job.priority), job.order));
+        waiting
+    }
+}
+
+/// A handle to a submitted job, removing the job from the queue when dropped so a
+/// cancelled or failed rip never holds up the jobs behind it.
+#[derive(Debug)]
+pub struct JobTicket {
+    pub id: u64,
+}
+
+impl JobTicket {
+    /// Submits a job to the queue and returns its ticket.
+    pub async fn submit(label: &str, kind: JobKind, priority: Priority) -> Self {
+        let host = match kind {
+            JobKind::Rip { drive_number } => drive_location(drive_number).0,
+            JobKind::Transcode => None,
+        };
+        let id = QUEUE.lock().await.submit(label, kind, host, priority);
+        QUEUE_CHANGED.notify_waiters();
+        JobTicket { id }
+    }
+
+    /// Starts the job if it is its turn, otherwise returns the number of jobs ahead of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QueueError` if the job is no longer in the queue.
+    pub async fn try_start(&self) -> Result<Option<usize>> {
+        let mut queue = QUEUE.lock().await;
+
+        if queue.try_start(self.id)? {
+            QUEUE_CHANGED.notify_waiters();
+            Ok(None)
+        } else {
+            Ok(Some(queue.jobs_ahead(self.id)))
+        }
+    }
+
+    /// Waits for the job's turn, returning straight away if it is already running.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QueueError` if the job is no longer in the queue.
+    pub async fn wait_turn(&self) -> Result<()> {
+        loop {
+            let changed = Self::changed();
+            if self.try_start().await?.is_none() {
+                return Ok(());
+            }
+            changed.await;
+        }
+    }
+
+    /// Resolves the next time the queue changes. Must be created before calling
+    /// `try_start` so a change in between isn't missed.
+    pub fn changed() -> Notified<'static> {
+        QUEUE_CHANGED.notified()
+    }
+}
+
+/// Wakes the jobs waiting on the queue, after it was changed outside of a `JobTicket`
+pub(super) fn queue_changed() {
+    QUEUE_CHANGED.notify_waiters();
+}
+
+impl Drop for JobTicket {
+    fn drop(&mut self) {
+        let id = self.id;
+        JOB_MESSAGES
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .finish(id);
+
+        // Drop can't be async, so the queue is updated in the background
+        tokio::spawn(async move {
+            QUEUE.lock().await.finish(id);
+            QUEUE_CHANGED.notify_waiters();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rip(drive_number: u8) -> JobKind {
+        JobKind::Rip { drive_number }
+    }
+
+    /// The labels of the jobs of `queue`, running first and then in the order they will run
+    fn labels(queue: &RipQueue) -> Vec<&str> {
+        queue.jobs().iter().map(|job| job.label.as_str()).collect()
+    }
+
+    #[test]
+    fn runs_by_priority_then_in_order() {
+        let mut queue = RipQueue::default();
+        queue.submit("Low", rip(1), None, Priority::Low);
+        queue.submit("Normal", rip(2), None, Priority::Normal);
+        queue.submit("High", rip(3), None, Priority::High);
+        queue.submit("Normal 2", rip(4), None, Priority::Normal);
+        queue.submit("High 2", rip(5), None, Priority::High);
+
+        assert_eq!(
+            labels(&queue),
+            ["High", "High 2", "Normal", "Normal 2", "Low"]
+        );
+    }
+
+    #[test]
+    fn bumped_jobs_go_first() {
+        let mut queue = RipQueue::default();
+        let running = queue.submit("Running", rip(1), None, Priority::Normal);
+        assert!(queue.try_start(running).unwrap());
+        queue.submit("High", rip(2), None, Priority::High);
+        queue.submit("Normal", rip(3), None, Priority::Normal);
+        let bumped = queue.submit("Low", rip(4), None, Priority::Low);
+
+        queue.bump(bumped).unwrap();
+        assert_eq!(labels(&queue), ["Running", "Low", "High", "Normal"]);
+        assert_eq!(queue.jobs_ahead(bumped), 1);
+        assert_eq!(
+            queue.wait_reason(bumped).unwrap(),
+            Some(WaitReason::WaitingForSlot)
+        );
+
+        // Only waiting jobs can be bumped
+        assert!(matches!(
+            queue.bump(running),
+            Err(QueueError::JobAlreadyStarted(_))
+        ));
+        assert!(matches!(queue.bump(99), Err(QueueError::JobNotFound(99))));
+    }
+
+    #[test]
+    fn parses_priorities() {
+        assert_eq!(" Low ".parse::<Priority>().unwrap(), Priority::Low);
+        assert_eq!("HIGH".parse::<Priority>().unwrap(), Priority::High);
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn idle_hosts_start_without_waiting() {
+        let mut queue = RipQueue::default();
+        let local = queue.submit("Local", rip(1), None, Priority::Normal);
+        let waiting = queue.submit("Local 2", rip(2), None, Priority::Normal);
+        let garage = queue.submit("Garage", rip(3), Some("garage".into()), Priority::Normal);
+
+        assert!(queue.try_start(local).unwrap());
+        assert!(!queue.try_start(waiting).unwrap());
+
+        // The agent has a slot of its own, so it doesn't wait behind this machine's rips
+        assert!(queue.try_start(garage).unwrap());
+    }
+
+    #[test]
+    fn hosts_keep_their_own_slots() {
+        let mut queue = RipQueue::default();
+        let first = queue.submit("Garage", rip(1), Some("garage".into()), Priority::Normal);
+        let second = queue.submit("Garage 2", rip(2), Some("garage".into()), Priority::Normal);
+
+        assert!(queue.try_start(first).unwrap());
+        assert_eq!(
+            queue.wait_reason(second).unwrap(),
+            Some(WaitReason::WaitingForSlot)
+        );
+    }
+
+    #[test]
+    fn rips_wait_for_a_slot() {
+        let mut queue = RipQueue::default();
+        queue.init(2, 1, 1);
+        let first = queue.submit("Heat", rip(1), None, Priority::Normal);
+        let second = queue.submit("Ran", rip(2), None, Priority::Normal);
+        let third = queue.submit("Brazil", rip(3), None, Priority::Normal);
+
+        assert!(queue.try_start(first).unwrap());
+        assert!(queue.try_start(second).unwrap());
+        assert_eq!(
+            queue.wait_reason(third).unwrap(),
+            Some(WaitReason::WaitingForSlot)
+        );
+        assert!(!queue.try_start(third).unwrap());
+
+        queue.finish(first);
+        assert!(queue.try_start(third).unwrap());
+    }
+
+    #[test]
+    fn transcodes_have_their_own_slots() {
+        let mut queue = RipQueue::default();
+        let transcode = queue.submit("Finishing Heat", JobKind::Transcode, None, Priority::Normal);
+        let waiting = queue.submit("Finishing Ran", JobKind::Transcode, None, Priority::Normal);
+        let ripping = queue.submit("Brazil", rip(1), None, Priority::Normal);
+
+        assert!(queue.try_start(transcode).unwrap());
+        assert_eq!(
+            queue.wait_reason(waiting).unwrap(),
+            Some(WaitReason::WaitingForSlot)
+        );
+
+        // Transcodes don't take the slots of rips
+        assert!(queue.try_start(ripping).unwrap());
+        assert!(!queue.try_start(waiting).unwrap());
+
+        queue.finish(transcode);
+        assert!(queue.try_start(waiting).unwrap());
+    }
+
+    #[test]
+    fn copying_jobs_free_their_drive() {
+        let mut queue = RipQueue::default();
+        let copying = queue.submit("Heat", rip(1), None, Priority::Normal);
+        let next = queue.submit("Ran", rip(1), None, Priority::Normal);
+        let other = queue.submit("Brazil", rip(2), None, Priority::Normal);
+
+        assert!(queue.try_start(copying).unwrap());
+        assert!(queue.start_copy(copying).unwrap());
+        assert!(queue.try_start(next).unwrap());
+
+        // One transfer at a time, the second rip keeps its drive until the first is copied
+        assert!(!queue.start_copy(next).unwrap());
+        queue.finish_copy(copying);
+        assert!(queue.start_copy(next).unwrap());
+
+        // Done copying, the first job is next in line for its drive again
+        assert_eq!(
+            queue.wait_reason(copying).unwrap(),
+            None,
+            "its drive is free while the second job copies"
+        );
+        assert!(queue.try_start(copying).unwrap());
+        assert_eq!(
+            queue.wait_reason(other).unwrap(),
+            Some(WaitReason::WaitingForSlot)
+        );
+    }
+}
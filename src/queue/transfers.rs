@@ -0,0 +1,135 @@
+//! # Transfers
+//!
+//! Moving a rip into a library on another mount, such as a NAS, is a copy, and copying a
+//! 40 GB UHD rip over a slow network can take longer than ripping it did. This module
+//! takes the copy out of the rip's drive and rip slot, so the next disc can be ripped
+//! while the last one is still being copied.
+//!
+//! ## Overview
+//!
+//! - **`Transfer`**: A job copying its files to the library, for as long as it is kept.
+//!
+//! - **`transfer`**: Waits for a transfer slot for the job running on the current task,
+//!   and hands its drive and rip slot back to the queue until the `Transfer` is dropped.
+//!
+//! - **`progress`**: How far along the copy of a job is, shown in `/queue`.
+//!
+//! ## Notes
+//!
+//! - Transfer slots are shared by every job, see `--max-concurrent-transfers`. A rip
+//!   waiting for one keeps its drive, so rips can't pile up in the output directory faster
+//!   than they are copied out of it.
+//! - Other jobs for the drive can rip while a job copies, they are for the disc in it. A
+//!   batch waits for its drive again before its next title.
+//! - Work that isn't running on a worker of a job, like the rips of the library API,
+//!   copies without a transfer slot.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{debug, trace};
+
+use super::queue_core::{queue_changed, JobTicket, QUEUE};
+use super::worker::current_job;
+
+/// How far along the copy of each job is, in percent, by the id of the job
+static PROGRESS: LazyLock<Mutex<HashMap<u64, u8>>> = LazyLock::new(Mutex::default);
+
+fn progress_map() -> MutexGuard<'static, HashMap<u64, u8>> {
+    PROGRESS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A job copying its files to the library, it gets back in line for its drive once
+/// dropped.
+///
+/// # Fields
+///
+/// * `job_id` - The id of the job that is copying.
+/// * `total_bytes` - The size of every file it is copying.
+#[derive(Debug)]
+pub struct Transfer {
+    job_id: u64,
+    total_bytes: u64,
+}
+
+impl Transfer {
+    /// Records that `bytes` of the files have been copied so far
+    pub fn copied(&self, bytes: u64) {
+        let percent = bytes.saturating_mul(100) / self.total_bytes.max(1);
+        progress_map().insert(self.job_id, u8::try_from(percent.min(100)).unwrap_or(100));
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        let job_id = self.job_id;
+        progress_map().remove(&job_id);
+
+        // Drop can't be async, so the queue is updated in the background
+        tokio::spawn(async move {
+            QUEUE.lock().await.finish_copy(job_id);
+            queue_changed();
+        });
+        trace!("Job {} is done copying", job_id);
+    }
+}
+
+/// Waits for a transfer slot for the job running on the current task, then hands its
+/// drive and rip slot back to the queue until the returned `Transfer` is dropped.
+/// `total_bytes` is the size of the files to copy, for the progress.
+///
+/// Returns `None` when not running on a worker of a job, or the job has left the queue,
+/// the files are then copied without a slot.
+pub async fn transfer(total_bytes: u64) -> Option<Transfer> {
+    let job_id = current_job()?;
+
+    loop {
+        // Created before checking, so a slot freed in between isn't missed
+        let changed = JobTicket::changed();
+        if QUEUE.lock().await.start_copy(job_id).ok()? {
+            break;
+        }
+        changed.await;
+    }
+    queue_changed();
+
+    debug!(
+        "Job {} handed its drive back to copy to the library",
+        job_id
+    );
+    progress_map().insert(job_id, 0);
+    Some(Transfer {
+        job_id,
+        total_bytes,
+    })
+}
+
+/// How far along the copy of a job is, in percent, `None` if it isn't copying
+#[must_use]
+pub fn progress(job_id: u64) -> Option<u8> {
+    progress_map().get(&job_id).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn progress_is_kept_in_percent() {
+        let transfer = Transfer {
+            job_id: 900,
+            total_bytes: 40 * 1024,
+        };
+        transfer.copied(0);
+        assert_eq!(progress(900), Some(0));
+        transfer.copied(25 * 1024);
+        assert_eq!(progress(900), Some(62));
+        transfer.copied(50 * 1024);
+        assert_eq!(progress(900), Some(100));
+
+        drop(transfer);
+        assert_eq!(progress(900), None);
+    }
+}
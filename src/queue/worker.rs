@@ -0,0 +1,154 @@
+//! # Rip Workers
+//!
+//! Runs the work of a job, such as ripping a title, on a task of its own rather than
+//! inside the Discord interaction that started it. A handler that panics, or an
+//! interaction that is dropped, then no longer takes a running rip down with it.
+//!
+//! ## Overview
+//!
+//! - **`spawn`**: Starts the work of a job on its own task, which keeps the job's place in
+//!   the queue until the work is done.
+//!
+//! - **`Worker`**: A handle to the work, to wait for what it finishes with.
+//!
+//! - **`cancel`**: Stops the work of a job, e.g. from a Cancel button.
+//!
+//! - **`current_job`**: The id of the job whose work is running on the current task.
+//!
+//! ## Notes
+//!
+//! - Dropping a `Worker` doesn't stop its work, only `cancel` does. A rip nobody is
+//!   waiting on anymore is still recorded in the `HISTORY` and sent out as rip events,
+//!   `Rip::execute` does both.
+//! - A panic in the work is caught by its task and logged, the `Worker` then has no result.
+//! - Work only starts on its job's turn. A job that handed its drive back to copy its last
+//!   rip waits to get it back before ripping again.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use tokio::task::{AbortHandle, JoinHandle};
+
+use crate::{debug, error, trace, warn};
+
+use super::queue_core::JobTicket;
+
+/// How often a cancelled job is checked on, until its work has let go of its drive
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The work that is running, by the id of its job
+static WORKERS: OnceLock<Mutex<HashMap<u64, AbortHandle>>> = OnceLock::new();
+
+tokio::task_local! {
+    /// The id of the job whose work runs on the task
+    static JOB: u64;
+}
+
+fn workers() -> std::sync::MutexGuard<'static, HashMap<u64, AbortHandle>> {
+    WORKERS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// A handle to the work of a job, see `spawn`
+#[derive(Debug)]
+pub struct Worker<T> {
+    job_id: u64,
+    handle: JoinHandle<T>,
+}
+
+impl<T> Worker<T> {
+    /// Waits for the work to be done, `None` if it was cancelled or panicked
+    pub async fn result(self) -> Option<T> {
+        match self.handle.await {
+            Ok(output) => Some(output),
+            Err(e) if e.is_panic() => {
+                error!("The work of job {} panicked: {}", self.job_id, e);
+                None
+            }
+            Err(_) => {
+                debug!("The work of job {} was cancelled", self.job_id);
+                None
+            }
+        }
+    }
+}
+
+/// Starts `work` on a task of its own for the job of `ticket`. The task holds on to the
+/// ticket, so the job keeps its place in the queue until the work is done, even if
+/// nothing is waiting for it anymore.
+pub fn spawn<F>(ticket: Arc<JobTicket>, work: F) -> Worker<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let job_id = ticket.id;
+
+    // Held until the handle is stored, so work that ends straight away can't miss it
+    let mut running = workers();
+    let handle = tokio::spawn(JOB.scope(job_id, async move {
+        if let Err(e) = ticket.wait_turn().await {
+            warn!("Job {} lost its place in the queue: {}", job_id, e);
+        }
+        let output = work.await;
+        let task = tokio::task::id();
+        workers().retain(|_, handle| handle.id() != task);
+        drop(ticket);
+        output
+    }));
+    running.insert(job_id, handle.abort_handle());
+    trace!("Started the work of job {}", job_id);
+
+    Worker { job_id, handle }
+}
+
+/// The id of the job whose work is running on the current task, `None` outside of a worker
+#[must_use]
+pub fn current_job() -> Option<u64> {
+    JOB.try_with(|job_id| *job_id).ok()
+}
+
+/// Stops the work of a job, returning once it has stopped so its drive is free again.
+/// Returns whether there was any work to stop.
+pub async fn cancel(job_id: u64) -> bool {
+    let Some(handle) = workers().remove(&job_id) else {
+        return false;
+    };
+
+    // Dropping the work stops MakeMKV, removes its temporary files and unlocks the drive
+    handle.abort();
+    while !handle.is_finished() {
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+    }
+    debug!("Cancelled the work of job {}", job_id);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::{JobKind, Priority};
+
+    #[tokio::test]
+    async fn work_outlives_its_worker_until_cancelled() {
+        let ticket =
+            Arc::new(JobTicket::submit("Test", JobKind::Transcode, Priority::Normal).await);
+        let id = ticket.id;
+
+        let (done, finished) = tokio::sync::oneshot::channel();
+        drop(spawn(Arc::clone(&ticket), async move {
+            let _ = done.send(());
+        }));
+        finished.await.expect("the work ran without its worker");
+
+        let worker = spawn(ticket, std::future::pending::<()>());
+        assert!(cancel(id).await);
+        assert!(worker.result().await.is_none());
+        assert!(!cancel(id).await);
+    }
+}
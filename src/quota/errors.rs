@@ -0,0 +1,77 @@
+use std::io;
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, QuotaError>;
+
+#[derive(Debug, Error)]
+pub enum QuotaError {
+    #[error("Daily quota of {limit} rips reached, the next rip is allowed in {retry_in}s")]
+    DailyQuotaReached { limit: u32, retry_in: u64 },
+
+    #[error("Weekly quota of {limit} rips reached, the next rip is allowed in {retry_in}s")]
+    WeeklyQuotaReached { limit: u32, retry_in: u64 },
+
+    #[error("Rip cooldown active, the next rip is allowed in {0}s")]
+    CoolingDown(u64),
+
+    #[error("Failed to read quota file: {0}")]
+    ReadError(String),
+
+    #[error("Failed to write quota file: {0}")]
+    WriteError(String),
+
+    #[error("Failed to parse quota file: {0}")]
+    ParseError(String),
+}
+
+impl QuotaError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            QuotaError::DailyQuotaReached { limit, retry_in } => tr!(
+                "You have started {limit} rips today, the most allowed in a day. You can start another in {wait}.",
+                limit = limit,
+                wait = wait_text(*retry_in)
+            ),
+            QuotaError::WeeklyQuotaReached { limit, retry_in } => tr!(
+                "You have started {limit} rips this week, the most allowed in a week. You can start another in {wait}.",
+                limit = limit,
+                wait = wait_text(*retry_in)
+            ),
+            QuotaError::CoolingDown(retry_in) => tr!(
+                "Please wait {wait} before starting another rip.",
+                wait = wait_text(*retry_in)
+            ),
+            _ => tr!("The rip quotas couldn't be checked."),
+        }
+    }
+}
+
+/// How long a user has to wait, rounded up to the minute so it is never too short
+fn wait_text(seconds: u64) -> String {
+    let minutes = seconds.div_ceil(60).max(1);
+    if minutes < 60 {
+        tr!("{minutes} min", minutes = minutes)
+    } else {
+        tr!(
+            "{hours} h {minutes} min",
+            hours = minutes / 60,
+            minutes = minutes % 60
+        )
+    }
+}
+
+impl From<io::Error> for QuotaError {
+    fn from(error: io::Error) -> Self {
+        QuotaError::WriteError(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for QuotaError {
+    fn from(error: serde_json::Error) -> Self {
+        QuotaError::ParseError(error.to_string())
+    }
+}
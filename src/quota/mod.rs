@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod quota_core;
+
+pub use quota_core::{QuotaLimits, QUOTAS};
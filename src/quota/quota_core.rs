@@ -0,0 +1,290 @@
+//! # Quota Core Module
+//!
+//! This module limits how many rips each user can start, so a single enthusiastic member
+//! can't keep every drive busy all weekend while everyone else waits.
+//!
+//! ## Overview
+//!
+//! - **`QuotaLimits`**: How many rips a user can start in a day and in a week, how long
+//!   they have to wait between rips, and which roles aren't limited at all.
+//!
+//! - **`Quotas`**: When each user started their recent rips, stored as JSON in the data
+//!   directory so restarting the bot doesn't reset everyone's quota.
+//!
+//! - **`QUOTAS`**: A globally accessible, thread-safe instance of `Quotas`.
+//!
+//! ## Notes
+//!
+//! - Days and weeks are rolling, counted back from now rather than from midnight, so
+//!   there is no time zone to configure and no rush for the drives at midnight.
+//! - A rip counts once it is confirmed, one movie, season batch, audio CD or archive per
+//!   confirmation, whether it later finishes or not. Retrying the failed titles of a
+//!   batch doesn't count again.
+//! - Server administrators are never limited, the Discord side checks that before asking
+//!   here.
+//! - Rips older than a week are dropped whenever a user starts a new one, so the file
+//!   only ever holds a week of rips.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::history::history_core::write_json_atomic;
+use crate::{debug, info, trace, warn};
+
+use super::errors::{QuotaError, Result};
+
+/// The name of the quota file within the data directory
+const QUOTA_FILE: &str = "quotas.json";
+/// The number of seconds in a day, the window of the daily quota
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+/// The number of seconds in a week, the window of the weekly quota
+const SECONDS_PER_WEEK: u64 = SECONDS_PER_DAY * 7;
+
+/// A globally accessible instance of `Quotas` for limiting the rips each user starts.
+pub static QUOTAS: LazyLock<Mutex<Quotas>> = LazyLock::new(Mutex::default);
+
+/// The limits rips are held to.
+///
+/// # Fields
+///
+/// * `daily` - The most rips a user can start in a day, `None` for no limit.
+/// * `weekly` - The most rips a user can start in a week, `None` for no limit.
+/// * `cooldown_seconds` - How long a user has to wait after starting a rip before they can
+///   start another, 0 for no wait.
+/// * `exempt_roles` - The ids of the Discord roles whose members aren't limited.
+#[derive(Debug, Clone, Default)]
+pub struct QuotaLimits {
+    pub daily: Option<u32>,
+    pub weekly: Option<u32>,
+    pub cooldown_seconds: u64,
+    pub exempt_roles: Vec<u64>,
+}
+
+impl QuotaLimits {
+    /// Whether any limit is set, without one there is nothing to check or record
+    #[must_use]
+    pub fn is_limited(&self) -> bool {
+        self.daily.is_some() || self.weekly.is_some() || self.cooldown_seconds > 0
+    }
+}
+
+/// When each user started their recent rips.
+///
+/// # Fields
+///
+/// * `rips` - The times, in seconds since the unix epoch, each user started their rips in
+///   the last week, oldest first, by Discord user id.
+/// * `limits` - The limits rips are held to.
+/// * `path` - Where the quotas are saved, empty until `init` has been called.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Quotas {
+    rips: BTreeMap<u64, Vec<u64>>,
+    #[serde(skip)]
+    limits: QuotaLimits,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Quotas {
+    /// Loads the quotas from the data directory, which has already been created by the
+    /// history.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QuotaError` if the quota file exists but can't be read or parsed.
+    pub fn init(&mut self, data_dir: &Path, limits: QuotaLimits) -> Result<()> {
+        let path = data_dir.join(QUOTA_FILE);
+
+        if path.exists() {
+            let contents =
+                std::fs::read_to_string(&path).map_err(|e| QuotaError::ReadError(e.to_string()))?;
+            *self = serde_json::from_str(&contents)?;
+            debug!("Loaded the recent rips of {} users", self.rips.len());
+        } else {
+            debug!("No quota file found, starting with no recent rips");
+        }
+
+        self.path = path;
+        self.limits = limits;
+
+        info!("Quotas initialized successfully!");
+        Ok(())
+    }
+
+    /// Writes the quotas to disk
+    fn save(&self) -> Result<()> {
+        // Quotas are disabled until init has been called
+        if self.path.as_os_str().is_empty() {
+            return Ok(());
+        }
+
+        write_json_atomic(&self.path, self)?;
+
+        trace!("Saved quotas to {}", self.path.display());
+        Ok(())
+    }
+
+    /// Whether a member with the given roles is exempt from the limits
+    #[must_use]
+    pub fn is_exempt(&self, roles: &[u64]) -> bool {
+        roles
+            .iter()
+            .any(|role| self.limits.exempt_roles.contains(role))
+    }
+
+    /// Checks whether a user can start a rip at `now`, without counting it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QuotaError` with how long the user has to wait if they are cooling down
+    /// or have reached their daily or weekly quota.
+    pub fn check(&self, user: u64, now: u64) -> Result<()> {
+        let Some(rips) = self.rips.get(&user) else {
+            return Ok(());
+        };
+
+        // Rips are kept oldest first, so the last one is the one the cooldown runs from
+        if let Some(last) = rips.last() {
+            let ready_at = last + self.limits.cooldown_seconds;
+            if ready_at > now {
+                return Err(QuotaError::CoolingDown(ready_at - now));
+            }
+        }
+
+        if let Some(limit) = self.limits.daily {
+            if let Some(retry_in) = window_wait(rips, limit, SECONDS_PER_DAY, now) {
+                return Err(QuotaError::DailyQuotaReached { limit, retry_in });
+            }
+        }
+
+        if let Some(limit) = self.limits.weekly {
+            if let Some(retry_in) = window_wait(rips, limit, SECONDS_PER_WEEK, now) {
+                return Err(QuotaError::WeeklyQuotaReached { limit, retry_in });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Counts a rip started by a user at `now`, if they haven't reached any of their
+    /// limits. Nothing is counted when there are no limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QuotaError` if the user can't start a rip yet, see `check`.
+    pub fn take(&mut self, user: u64, now: u64) -> Result<()> {
+        if !self.limits.is_limited() {
+            return Ok(());
+        }
+
+        self.check(user, now)?;
+
+        let rips = self.rips.entry(user).or_default();
+        rips.retain(|started| started + SECONDS_PER_WEEK > now);
+        rips.push(now);
+        debug!("User {} has started {} rips this week", user, rips.len());
+
+        // The rip was already allowed, a failed save only means the next restart forgets it
+        if let Err(e) = self.save() {
+            warn!("Failed to save quotas: {}", e);
+        }
+
+        Ok(())
+    }
+}
+
+/// How long until one of the rips in a window of `window` seconds expires, if `limit` of
+/// them were started within it. `None` if there's room for another rip.
+fn window_wait(rips: &[u64], limit: u32, window: u64, now: u64) -> Option<u64> {
+    let in_window: Vec<u64> = rips
+        .iter()
+        .copied()
+        .filter(|started| started + window > now)
+        .collect();
+
+    if in_window.len() < limit as usize {
+        return None;
+    }
+
+    // The limit could have been lowered since, so the wait is until enough have expired
+    // to be under it again
+    let expiring = in_window[in_window.len() - limit as usize];
+    Some(expiring + window - now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 60 * 60;
+
+    fn quotas(limits: QuotaLimits, rips: &[u64]) -> Quotas {
+        Quotas {
+            rips: BTreeMap::from([(1, rips.to_vec())]),
+            limits,
+            path: PathBuf::new(),
+        }
+    }
+
+    #[test]
+    fn cooldown_runs_from_the_last_rip() {
+        let limits = QuotaLimits {
+            cooldown_seconds: HOUR,
+            ..QuotaLimits::default()
+        };
+        let quotas = quotas(limits, &[0, 10 * HOUR]);
+
+        assert!(matches!(
+            quotas.check(1, 10 * HOUR + 60),
+            Err(QuotaError::CoolingDown(wait)) if wait == HOUR - 60
+        ));
+        assert!(quotas.check(1, 11 * HOUR).is_ok());
+        assert!(quotas.check(2, 10 * HOUR).is_ok());
+    }
+
+    #[test]
+    fn daily_quota_is_a_rolling_day() {
+        let limits = QuotaLimits {
+            daily: Some(2),
+            ..QuotaLimits::default()
+        };
+        let quotas = quotas(limits, &[HOUR, 2 * HOUR]);
+
+        assert!(matches!(
+            quotas.check(1, 3 * HOUR),
+            Err(QuotaError::DailyQuotaReached { limit: 2, retry_in }) if retry_in == 22 * HOUR
+        ));
+        assert!(quotas.check(1, 25 * HOUR).is_ok());
+    }
+
+    #[test]
+    fn weekly_quota_counts_rips_from_every_day() {
+        let limits = QuotaLimits {
+            daily: Some(2),
+            weekly: Some(3),
+            ..QuotaLimits::default()
+        };
+        let mut quotas = quotas(limits, &[0, SECONDS_PER_DAY]);
+
+        assert!(quotas.take(1, 2 * SECONDS_PER_DAY).is_ok());
+        assert!(matches!(
+            quotas.take(1, 3 * SECONDS_PER_DAY),
+            Err(QuotaError::WeeklyQuotaReached { limit: 3, retry_in }) if retry_in == 4 * SECONDS_PER_DAY
+        ));
+        assert_eq!(quotas.rips[&1].len(), 3);
+    }
+
+    #[test]
+    fn nothing_is_recorded_without_limits() {
+        let mut quotas = quotas(QuotaLimits::default(), &[]);
+        assert!(quotas.take(1, 0).is_ok());
+        assert!(quotas.take(1, 0).is_ok());
+        assert!(quotas.rips[&1].is_empty());
+    }
+}
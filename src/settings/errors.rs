@@ -0,0 +1,50 @@
+use std::io;
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, SettingsError>;
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Failed to read settings file: {0}")]
+    ReadError(String),
+
+    #[error("Failed to write settings file: {0}")]
+    WriteError(String),
+
+    #[error("Failed to parse settings file: {0}")]
+    ParseError(String),
+
+    #[error("Invalid naming template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Invalid rip preset: {0}")]
+    InvalidPreset(String),
+}
+
+impl SettingsError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            SettingsError::InvalidTemplate(template) => tr!(
+                "`{template}` can't be used as a naming template, it is missing the title or episode.",
+                template = template
+            ),
+            _ => tr!("The settings couldn't be saved."),
+        }
+    }
+}
+
+impl From<io::Error> for SettingsError {
+    fn from(error: io::Error) -> Self {
+        SettingsError::WriteError(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for SettingsError {
+    fn from(error: serde_json::Error) -> Self {
+        SettingsError::ParseError(error.to_string())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod settings_core;
+
+pub use settings_core::{RipPreset, Settings, SETTINGS};
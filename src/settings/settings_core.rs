@@ -0,0 +1,329 @@
+//! # Settings Core Module
+//!
+//! This module keeps the settings chosen with `/setup`, so a new server can be set up from
+//! Discord rather than by hand crafting command-line arguments before the first rip.
+//!
+//! ## Overview
+//!
+//! - **`Settings`**: The naming templates, alert channel and quota exempt roles, stored as
+//!   JSON in the data directory.
+//!
+//! - **`RipPreset`**: A named set of rip options, picked at the start of `/rip`.
+//!
+//! - **`SETTINGS`**: A globally accessible, thread-safe instance of `Settings`.
+//!
+//! ## Notes
+//!
+//! - Settings are read once at startup, so changes saved by `/setup` take effect after a
+//!   restart.
+//! - Command-line arguments and environment variables win over the settings file:
+//!   `--arr-import` replaces the naming templates, `ALERT_CHANNEL_ID` replaces the alert
+//!   channel, and `--quota-exempt-role` roles are exempt along with the saved ones.
+//! - The file can also be edited by hand while the bot is stopped, anything left out of it
+//!   keeps its default.
+//! - Rip presets are only set up by editing the file, `/setup` keeps them as they are.
+//! - So is `token_path`, a file to read the Discord token from when neither
+//!   `DISCORD_TOKEN` nor `DISCORD_TOKEN_FILE` is set, and `skip_update_check`, which stops
+//!   the bot checking GitHub for newer releases.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::history::history_core::write_json_atomic;
+use crate::makemkv::naming::NamingTemplate;
+use crate::{debug, info, trace};
+
+use super::errors::{Result, SettingsError};
+
+/// The name of the settings file within the data directory
+const SETTINGS_FILE: &str = "settings.json";
+
+/// A globally accessible instance of `Settings` for the settings chosen with `/setup`.
+pub static SETTINGS: LazyLock<Mutex<Settings>> = LazyLock::new(Mutex::default);
+
+/// The most presets that fit in the select menu they are picked from
+const MAX_PRESETS: usize = 25;
+
+/// A named set of rip options for routine discs, such as a `Kids DVD` preset that saves
+/// to the kids library, so they don't have to be picked one by one.
+///
+/// # Fields
+///
+/// * `name` - What the preset is picked by.
+/// * `min_minutes` - Titles shorter than this are left out of the title select step.
+/// * `library` - The library rips are saved to, `None` for the output directory.
+/// * `high_priority` - Whether rips jump ahead of normal priority ones in the queue.
+/// * `eject_when_done` - Whether the disc is ejected once ripped, `None` for the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RipPreset {
+    pub name: String,
+    #[serde(default)]
+    pub min_minutes: Option<u32>,
+    #[serde(default)]
+    pub library: Option<String>,
+    #[serde(default)]
+    pub high_priority: bool,
+    #[serde(default)]
+    pub eject_when_done: Option<bool>,
+}
+
+/// The settings chosen with `/setup`.
+///
+/// # Fields
+///
+/// * `movie_template` - The naming template for movies, `None` for the default.
+/// * `show_template` - The naming template for shows, `None` for the default.
+/// * `alert_channel_id` - The channel low disk space alerts are posted to.
+/// * `quota_exempt_roles` - The ids of the roles whose members aren't held to the rip
+///   quotas.
+/// * `presets` - The rip presets, in the order they are listed.
+/// * `token_path` - A file to read the Discord token from, if it isn't in the environment.
+/// * `skip_update_check` - Whether the bot doesn't check GitHub for newer releases.
+/// * `path` - Where the settings are saved, empty until `init` has been called.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub movie_template: Option<String>,
+    #[serde(default)]
+    pub show_template: Option<String>,
+    #[serde(default)]
+    pub alert_channel_id: Option<u64>,
+    #[serde(default)]
+    pub quota_exempt_roles: Vec<u64>,
+    #[serde(default)]
+    pub presets: Vec<RipPreset>,
+    #[serde(default)]
+    pub token_path: Option<PathBuf>,
+    #[serde(default)]
+    pub skip_update_check: bool,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Settings {
+    /// Loads the settings from the data directory, if they were ever saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SettingsError` if the settings file exists but can't be read or parsed,
+    /// or has a template or preset that can't be used.
+    pub fn init(&mut self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join(SETTINGS_FILE);
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| SettingsError::ReadError(e.to_string()))?;
+            *self = serde_json::from_str(&contents)?;
+            self.naming()?;
+            self.check_presets()?;
+            debug!("Loaded settings: {:?}", self);
+        } else {
+            debug!("No settings file found, using the defaults");
+        }
+
+        self.path = path;
+
+        info!("Settings initialized successfully!");
+        Ok(())
+    }
+
+    /// Replaces the settings with new ones and saves them, keeping where they are saved,
+    /// the presets, the token path and the update check, which `/setup` doesn't change.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SettingsError` if a template can't be used, or the settings can't be
+    /// written. The settings are left as they were then.
+    pub fn replace(&mut self, settings: Settings) -> Result<()> {
+        settings.naming()?;
+
+        let settings = Settings {
+            presets: self.presets.clone(),
+            token_path: self.token_path.clone(),
+            skip_update_check: self.skip_update_check,
+            path: self.path.clone(),
+            ..settings
+        };
+        settings.save()?;
+
+        *self = settings;
+        Ok(())
+    }
+
+    /// Writes the settings to disk
+    fn save(&self) -> Result<()> {
+        // Settings can't be saved until init has been called
+        if self.path.as_os_str().is_empty() {
+            return Err(SettingsError::WriteError(
+                "Settings have not been initialized".to_string(),
+            ));
+        }
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        write_json_atomic(&self.path, self)?;
+
+        trace!("Saved settings to {}", self.path.display());
+        Ok(())
+    }
+
+    /// The preset with the given name, if there is one
+    #[must_use]
+    pub fn preset(&self, name: &str) -> Option<&RipPreset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Checks every preset has a name of its own, and they fit in a select menu
+    fn check_presets(&self) -> Result<()> {
+        if self.presets.len() > MAX_PRESETS {
+            return Err(SettingsError::InvalidPreset(format!(
+                "only {MAX_PRESETS} presets can be set up"
+            )));
+        }
+        for (i, preset) in self.presets.iter().enumerate() {
+            if preset.name.trim().is_empty() {
+                return Err(SettingsError::InvalidPreset(
+                    "a preset has no name".to_string(),
+                ));
+            }
+            if self.presets[..i]
+                .iter()
+                .any(|other| other.name == preset.name)
+            {
+                return Err(SettingsError::InvalidPreset(format!(
+                    "{} is the name of more than one preset",
+                    preset.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The naming templates to rip with, `None` if neither was changed from the default.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SettingsError` if a template is missing the title, or a show template
+    /// the episode, which would save every rip over the last.
+    pub fn naming(&self) -> Result<Option<NamingTemplate>> {
+        if self.movie_template.is_none() && self.show_template.is_none() {
+            return Ok(None);
+        }
+
+        let default = NamingTemplate::default();
+        let naming = NamingTemplate {
+            movie: self.movie_template.clone().unwrap_or(default.movie),
+            show: self.show_template.clone().unwrap_or(default.show),
+        };
+
+        if !naming.movie.contains("{title") {
+            return Err(SettingsError::InvalidTemplate(naming.movie));
+        }
+        if !naming.show.contains("{title")
+            || !(naming.show.contains("{episode") || naming.show.contains("{absolute"))
+        {
+            return Err(SettingsError::InvalidTemplate(naming.show));
+        }
+
+        Ok(Some(naming))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_templates_that_would_overwrite_rips() {
+        let mut settings = Settings::default();
+        assert!(settings.naming().unwrap().is_none());
+
+        settings.show_template = Some("shows/{title}/{title} - S{season:02}E{episode:02}".into());
+        let naming = settings.naming().unwrap().unwrap();
+        assert_eq!(naming.movie, NamingTemplate::default().movie);
+
+        settings.movie_template = Some("movies/movie".into());
+        assert!(matches!(
+            settings.naming(),
+            Err(SettingsError::InvalidTemplate(_))
+        ));
+
+        settings.movie_template = None;
+        settings.show_template = Some("shows/{title}/Season {season}".into());
+        assert!(settings.naming().is_err());
+    }
+
+    #[test]
+    fn presets_are_checked_and_kept_by_setup() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(SETTINGS_FILE),
+            r#"{"presets": [{"name": "Kids DVD", "min_minutes": 5, "library": "kids"}], "token_path": "/etc/cord-ripper/token", "skip_update_check": true}"#,
+        )
+        .unwrap();
+
+        let mut settings = Settings::default();
+        settings.init(dir.path()).unwrap();
+        let kids = RipPreset {
+            name: "Kids DVD".into(),
+            min_minutes: Some(5),
+            library: Some("kids".into()),
+            ..RipPreset::default()
+        };
+        assert_eq!(settings.preset("Kids DVD"), Some(&kids));
+
+        settings
+            .replace(Settings {
+                alert_channel_id: Some(42),
+                ..Settings::default()
+            })
+            .unwrap();
+        assert_eq!(settings.presets, vec![kids.clone()]);
+        assert_eq!(
+            settings.token_path,
+            Some(PathBuf::from("/etc/cord-ripper/token"))
+        );
+        assert!(settings.skip_update_check);
+
+        settings.presets = vec![kids.clone(), kids];
+        assert!(matches!(
+            settings.check_presets(),
+            Err(SettingsError::InvalidPreset(_))
+        ));
+    }
+
+    #[test]
+    fn saved_settings_are_loaded_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = Settings::default();
+        settings.init(dir.path()).unwrap();
+
+        settings
+            .replace(Settings {
+                alert_channel_id: Some(42),
+                quota_exempt_roles: vec![7],
+                ..Settings::default()
+            })
+            .unwrap();
+
+        let mut loaded = Settings::default();
+        loaded.init(dir.path()).unwrap();
+        assert_eq!(loaded, settings);
+
+        // A bad template is refused without touching what was saved
+        assert!(settings
+            .replace(Settings {
+                movie_template: Some("movie".into()),
+                ..Settings::default()
+            })
+            .is_err());
+        assert_eq!(settings.alert_channel_id, Some(42));
+    }
+}
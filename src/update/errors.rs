@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, UpdateError>;
+
+#[derive(Debug, Error)]
+pub enum UpdateError {
+    #[error("Failed to reach GitHub: {0}")]
+    RequestError(String),
+
+    #[error("GitHub responded with status {0}")]
+    BadStatus(u16),
+}
+
+impl From<reqwest::Error> for UpdateError {
+    fn from(error: reqwest::Error) -> Self {
+        UpdateError::RequestError(error.to_string())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod update_core;
+
+pub use update_core::{newer_release, Release};
@@ -0,0 +1,197 @@
+//! # Update Core Module
+//!
+//! The bot tends to run headless for months, so a new release is easy to miss. This module
+//! asks GitHub for the latest release, for the bot to announce when it is newer than the
+//! version running.
+//!
+//! ## Overview
+//!
+//! - **`Release`**: A release of Cord Ripper, with its changelog.
+//!
+//! - **`newer_release`**: The latest release, if it is newer than the version running.
+//!
+//! ## Notes
+//!
+//! - Only full releases are considered, GitHub leaves drafts and pre-releases out of the
+//!   latest release.
+//! - Versions are compared by their numbers, a tag that isn't a version, such as
+//!   `nightly`, is never newer.
+//! - The check is opted out of with `skip_update_check` in `settings.json`.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::trace;
+
+use super::errors::{Result, UpdateError};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/qmusillo/cord-ripper/releases/latest";
+
+/// GitHub refuses API requests without a user agent
+const USER_AGENT: &str = concat!(
+    "cord-ripper/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/qmusillo/cord-ripper )"
+);
+
+/// How long the check can take, so a hung request doesn't hold up the daily checks
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The most lines of a changelog shown in its summary
+const MAX_CHANGELOG_LINES: usize = 10;
+
+/// The most characters of a changelog shown in its summary, which fits an embed field
+const MAX_CHANGELOG_CHARS: usize = 1000;
+
+/// A release of Cord Ripper.
+///
+/// # Fields
+///
+/// * `version` - The tag of the release, such as `v0.1.0`.
+/// * `name` - The name of the release, `None` if it only has a tag.
+/// * `url` - The release's page on GitHub.
+/// * `changelog` - The release notes, in markdown.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Release {
+    #[serde(rename = "tag_name")]
+    pub version: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(rename = "html_url")]
+    pub url: String,
+    #[serde(rename = "body", default)]
+    pub changelog: Option<String>,
+}
+
+impl Release {
+    /// The first lines of the changelog, `None` if it has none
+    #[must_use]
+    pub fn summary(&self) -> Option<String> {
+        let lines: Vec<&str> = self
+            .changelog
+            .as_deref()?
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+
+        let mut summary = lines
+            .iter()
+            .take(MAX_CHANGELOG_LINES)
+            .copied()
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let mut cut = lines.len() > MAX_CHANGELOG_LINES;
+        if summary.chars().count() > MAX_CHANGELOG_CHARS {
+            summary = summary.chars().take(MAX_CHANGELOG_CHARS).collect();
+            cut = true;
+        }
+        if cut {
+            summary.push_str("\n...");
+        }
+        Some(summary)
+    }
+}
+
+/// Asks GitHub for the latest release. Returns `None` if it isn't newer than the version
+/// running, or there are no releases yet.
+///
+/// # Errors
+///
+/// Returns an `UpdateError` if GitHub can't be reached or responds with an error.
+pub async fn newer_release() -> Result<Option<Release>> {
+    let http = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+
+    trace!("Checking GitHub for a newer release");
+    let response = http
+        .get(RELEASES_URL)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await?;
+
+    // Nothing has been released yet
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(UpdateError::BadStatus(response.status().as_u16()));
+    }
+
+    let release: Release = response.json().await?;
+    trace!("The latest release is {}", release.version);
+
+    Ok(is_newer(&release.version, env!("CARGO_PKG_VERSION")).then_some(release))
+}
+
+/// Whether the version `latest` is newer than `current`
+fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// The major, minor and patch numbers of a version such as `v1.2.3`, missing numbers are 0
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    // Build metadata and pre-release tags don't count
+    let version = version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split(['-', '+'])
+        .next()?;
+
+    let mut numbers = version.split('.').map(str::parse::<u64>);
+    let major = numbers.next()?.ok()?;
+    let minor = numbers.next().transpose().ok()?.unwrap_or(0);
+    let patch = numbers.next().transpose().ok()?.unwrap_or(0);
+    if numbers.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions_by_their_numbers() {
+        assert!(is_newer("v0.0.10", "0.0.9"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(!is_newer("v0.0.9", "0.0.9"));
+        assert!(!is_newer("0.0.8", "0.0.9"));
+        assert!(!is_newer("nightly", "0.0.9"));
+        assert_eq!(parse_version("v1.2.3-rc.1"), Some((1, 2, 3)));
+        assert_eq!(parse_version("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn summarizes_the_first_lines_of_the_changelog() {
+        let mut release = Release {
+            version: "v0.1.0".to_string(),
+            name: None,
+            url: "https://github.com/qmusillo/cord-ripper/releases/tag/v0.1.0".to_string(),
+            changelog: Some("## Changes\r\n\r\n- Weekly digest\r\n- Update check\r\n".to_string()),
+        };
+        assert_eq!(
+            release.summary().as_deref(),
+            Some("## Changes\n- Weekly digest\n- Update check")
+        );
+
+        let fixes: Vec<String> = (1..=12).map(|i| format!("- Fix {i}")).collect();
+        release.changelog = Some(fixes.join("\n"));
+        let summary = release.summary().unwrap();
+        assert!(summary.starts_with("- Fix 1\n"));
+        assert!(summary.ends_with("- Fix 10\n..."));
+
+        release.changelog = Some("  \n".to_string());
+        assert_eq!(release.summary(), None);
+    }
+}
@@ -0,0 +1,19 @@
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, UploadError>;
+
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("Failed to execute rclone: {0}")]
+    CommandExecutionError(String),
+
+    #[error("rclone failed to upload {0}: {1}")]
+    UploadFailed(String, String),
+}
+
+impl From<io::Error> for UploadError {
+    fn from(error: io::Error) -> Self {
+        UploadError::CommandExecutionError(error.to_string())
+    }
+}
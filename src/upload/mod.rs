@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod upload_core;
+
+pub use upload_core::{UploadProgress, UPLOADER};
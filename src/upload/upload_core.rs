@@ -0,0 +1,177 @@
+//! # Upload Core Module
+//!
+//! This module copies finished rips to remote storage with `rclone`, so they land on a
+//! remote media box without a separate sync job.
+//!
+//! ## Overview
+//!
+//! - **`Uploader`**: The remote each library is uploaded to.
+//!
+//! - **`UploadProgress`**: A progress update from a running upload.
+//!
+//! - **`UPLOADER`**: A globally accessible, thread-safe instance of `Uploader`.
+//!
+//! ## Notes
+//!
+//! - Remotes are anything `rclone` can copy to, e.g. `media-box:/srv/media` for an SFTP
+//!   remote or `s3:my-bucket/media` for S3, set up beforehand with `rclone config`.
+//! - Files keep their path relative to the output directory on the remote.
+//! - The local copy is kept, so the history, `/rename` and `/delete_rip` still work.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, LazyLock},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    sync::{mpsc::UnboundedSender, Mutex},
+};
+
+use crate::makemkv::RipType;
+use crate::{debug, error, info, trace};
+
+use super::errors::{Result, UploadError};
+
+/// A globally accessible instance of `Uploader` for uploading finished rips.
+pub static UPLOADER: LazyLock<Arc<Mutex<Uploader>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(Uploader::default())));
+
+/// A progress update from a running upload.
+///
+/// * `file` - The number of the file being uploaded, starting from 1.
+/// * `files` - The number of files being uploaded.
+/// * `stats` - The latest stats reported by `rclone`, e.g.
+///   `1.2 GiB / 4.5 GiB, 27%, 50 MiB/s, ETA 1m5s`.
+#[derive(Debug, Clone)]
+pub struct UploadProgress {
+    pub file: usize,
+    pub files: usize,
+    pub stats: String,
+}
+
+/// The remotes finished rips are uploaded to, by library.
+///
+/// # Fields
+///
+/// * `movies` - The `rclone` remote movies are uploaded to, if any.
+/// * `shows` - The `rclone` remote shows are uploaded to, if any.
+///
+/// # Methods
+///
+/// ## `init`
+/// Sets the remotes of each library.
+///
+/// ## `remote_for`
+/// Returns the remote a type of rip is uploaded to, if uploads are enabled for it.
+///
+/// ## `upload`
+/// Copies files to a remote, sending progress updates as it goes.
+///
+/// ### Parameters
+/// - `root`: The output directory, files keep their path relative to it.
+/// - `files`: The files to upload.
+/// - `remote`: The `rclone` remote to upload to.
+/// - `progress`: Where progress updates are sent.
+///
+/// ### Errors
+/// - Fails if `rclone` can't be run, or fails to upload a file.
+#[derive(Debug, Clone, Default)]
+pub struct Uploader {
+    pub movies: Option<String>,
+    pub shows: Option<String>,
+}
+
+impl Uploader {
+    pub fn init(&mut self, movies: Option<String>, shows: Option<String>) {
+        for (library, remote) in [("Movies", &movies), ("Shows", &shows)] {
+            if let Some(remote) = remote {
+                info!("{} will be uploaded to {}", library, remote);
+            }
+        }
+
+        self.movies = movies;
+        self.shows = shows;
+    }
+
+    #[must_use]
+    pub fn remote_for(&self, rip_type: RipType) -> Option<String> {
+        match rip_type {
+            RipType::Movie => self.movies.clone(),
+            RipType::Show { .. } => self.shows.clone(),
+        }
+    }
+
+    /// Copies files to a remote with `rclone copyto`, one at a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `UploadError` if `rclone` can't be run or fails to upload a file.
+    pub async fn upload(
+        root: &Path,
+        files: &[PathBuf],
+        remote: &str,
+        progress: &UnboundedSender<UploadProgress>,
+    ) -> Result<()> {
+        for (index, file) in files.iter().enumerate() {
+            let relative = file.strip_prefix(root).unwrap_or(file);
+            let destination = format!(
+                "{}/{}",
+                remote.trim_end_matches('/'),
+                relative.to_string_lossy()
+            );
+
+            debug!("Uploading {} to {}", file.display(), destination);
+
+            // Stats are logged as one line every few seconds, which is easy to relay
+            let mut child = Command::new("rclone")
+                .arg("copyto")
+                .arg(file)
+                .arg(&destination)
+                .args([
+                    "--stats",
+                    "5s",
+                    "--stats-one-line",
+                    "--stats-log-level",
+                    "NOTICE",
+                ])
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()?;
+
+            let mut last_error = String::new();
+            if let Some(stderr) = child.stderr.take() {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Some(line) = lines.next_line().await? {
+                    trace!("rclone: {}", line);
+
+                    if let Some((_, stats)) = line.split_once("NOTICE: ") {
+                        // The receiver going away only means nobody is watching
+                        let _ = progress.send(UploadProgress {
+                            file: index + 1,
+                            files: files.len(),
+                            stats: stats.trim().to_string(),
+                        });
+                    } else if let Some((_, message)) = line.split_once("ERROR : ") {
+                        last_error = message.trim().to_string();
+                    }
+                }
+            }
+
+            if !child.wait().await?.success() {
+                error!("Failed to upload {}: {}", file.display(), last_error);
+                return Err(UploadError::UploadFailed(
+                    relative.to_string_lossy().to_string(),
+                    last_error,
+                ));
+            }
+
+            info!("Uploaded {} to {}", file.display(), destination);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::io;
+use thiserror::Error;
+
+use crate::tr;
+
+pub type Result<T> = std::result::Result<T, WatchError>;
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("Failed to read the watch folder: {0}")]
+    ReadError(String),
+
+    #[error("Couldn't tell what {0} is from its name")]
+    Unmatched(String),
+
+    #[error("Refusing to save outside the output directory: {0}")]
+    UnsafePath(String),
+
+    #[error("File already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("File failed validation, {0}")]
+    Invalid(String),
+
+    #[error("Failed to move the file into the library: {0}")]
+    SaveError(String),
+}
+
+impl WatchError {
+    /// A short explanation of the error that can be shown to Discord users
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            WatchError::ReadError(_) => tr!("Failed to read the watch folder."),
+            WatchError::Unmatched(_) => tr!(
+                "Couldn't tell the title from the file's name, rename it to `Title (Year).mkv` or `Show S01E05.mkv`."
+            ),
+            WatchError::UnsafePath(_) => {
+                tr!("The file's name would save it outside of the library.")
+            }
+            WatchError::AlreadyExists(path) => {
+                tr!("{path} is already in the library.", path = path)
+            }
+            WatchError::Invalid(reason) => {
+                tr!("The file doesn't look right, {reason}.", reason = reason)
+            }
+            WatchError::SaveError(_) => tr!("Failed to move the file into the library."),
+        }
+    }
+}
+
+impl From<io::Error> for WatchError {
+    fn from(error: io::Error) -> Self {
+        WatchError::ReadError(error.to_string())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod watch_core;
+
+pub use watch_core::{WatchEvent, WATCH_FOLDER};
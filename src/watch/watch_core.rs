@@ -0,0 +1,405 @@
+//! # Watch Folder
+//!
+//! Not every disc goes through the bot, some are ripped by hand with the `MakeMKV` GUI,
+//! which saves its titles wherever it is pointed and names them after the disc. Those
+//! files would otherwise have to be named and moved into the library by hand. This module
+//! picks up the MKVs dropped into an incoming folder, names them with the same metadata
+//! lookups and naming template as rips, and moves them into the output directory.
+//!
+//! ## Overview
+//!
+//! - **`WatchFolder`**: The incoming folder, and the files in it that couldn't be
+//!   imported.
+//!
+//! - **`WATCH_FOLDER`**: A globally accessible, thread-safe instance of `WatchFolder`.
+//!
+//! - **`WatchEvent`**: A file that was imported into the library, or couldn't be.
+//!
+//! - **`incoming_rip`**: Reads what a file is from its path within the incoming folder.
+//!
+//! ## Notes
+//!
+//! - Files are read with the same rules as `/import_library`, `Title (Year).mkv` is a
+//!   movie and `Show S01E05.mkv` an episode. The `_t00` `MakeMKV` adds to its file names
+//!   is ignored, and a file only named `title_t00.mkv` is named after its folder, which
+//!   the GUI names after the disc.
+//! - A disc label such as `FRIENDS_S3_D2` doesn't say which episode a title is, so those
+//!   files are left for a person to rename.
+//! - A file is only picked up once it hasn't changed for `SETTLE_TIME`, the GUI writes
+//!   straight into the final file while ripping.
+//! - A file that can't be imported is reported once and left where it is. It is tried
+//!   again once renamed, or after a restart.
+//! - Imported files are validated, recorded in the history and get the same `.nfo` files,
+//!   artwork and Sonarr or Radarr import as rips, but aren't tied to a drive.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+
+use tokio::sync::Mutex;
+
+use crate::arr::{canonical_movie, canonical_series, episode_titles, import_rip};
+use crate::history::{import::parse_library_path, import::ImportedRip, HISTORY};
+use crate::library::LIBRARY;
+use crate::makemkv::{
+    labels::parse_disc_label, naming::stays_inside, transfer::move_verified, MakeMkv, Rip, RipType,
+};
+use crate::probe::{errors::ProbeError, validate_file};
+use crate::{debug, info, warn};
+
+use super::errors::{Result, WatchError};
+
+/// How long a file has to be left unchanged before it is picked up
+pub const SETTLE_TIME: Duration = Duration::from_mins(2);
+
+/// A globally accessible instance of `WatchFolder` for importing hand ripped files.
+pub static WATCH_FOLDER: LazyLock<Mutex<WatchFolder>> = LazyLock::new(Mutex::default);
+
+/// What happened to a file picked up from the incoming folder.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// The file was named and moved into the library
+    Imported {
+        file: PathBuf,
+        rip: Rip,
+        destination: PathBuf,
+    },
+    /// The file couldn't be imported, it was left where it is
+    Failed { file: PathBuf, error: WatchError },
+}
+
+/// The incoming folder hand ripped files are imported from.
+///
+/// # Fields
+///
+/// * `dir` - The incoming folder, `None` until `init` has been called.
+/// * `failed` - The files that couldn't be imported, so they are only reported once.
+#[derive(Debug, Default)]
+pub struct WatchFolder {
+    dir: Option<PathBuf>,
+    failed: HashSet<PathBuf>,
+}
+
+impl WatchFolder {
+    /// Sets the incoming folder to import files from
+    pub fn init(&mut self, dir: PathBuf) {
+        info!("Watching {} for MKVs to import", dir.display());
+        self.dir = Some(dir);
+    }
+
+    /// The incoming folder, `None` when there is none
+    #[must_use]
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Imports the files in the incoming folder that have settled, returning what happened
+    /// to each of them. Files that failed before are skipped.
+    pub async fn poll(&mut self, makemkv: &MakeMkv) -> Vec<WatchEvent> {
+        let Some(dir) = self.dir.clone() else {
+            return Vec::new();
+        };
+
+        let files = match settled_files(&dir, SystemTime::now()) {
+            Ok(files) => files,
+            Err(e) => {
+                warn!("Failed to read the watch folder {}: {}", dir.display(), e);
+                return Vec::new();
+            }
+        };
+
+        // Files that were renamed or removed since failing are forgotten
+        self.failed.retain(|file| files.contains(file));
+
+        let mut events = Vec::new();
+        for file in files {
+            if self.failed.contains(&file) {
+                continue;
+            }
+
+            match import_file(&dir, &file, makemkv).await {
+                Ok((rip, destination)) => {
+                    info!(
+                        "Imported {} from the watch folder to {}",
+                        file.display(),
+                        destination.display()
+                    );
+                    events.push(WatchEvent::Imported {
+                        file,
+                        rip,
+                        destination,
+                    });
+                }
+                Err(error) => {
+                    warn!("Failed to import {}: {}", file.display(), error);
+                    self.failed.insert(file.clone());
+                    events.push(WatchEvent::Failed { file, error });
+                }
+            }
+        }
+
+        events
+    }
+}
+
+/// The MKVs under `dir` that haven't been changed for `SETTLE_TIME`, sorted
+fn settled_files(dir: &Path, now: SystemTime) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+
+            // Hidden files are usually something still being written, such as a sync
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if metadata.is_dir() {
+                dirs.push(path);
+            } else if is_mkv(&path)
+                && metadata
+                    .modified()
+                    .is_ok_and(|modified| is_settled(modified, now))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn is_mkv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("mkv"))
+}
+
+/// Whether a file last changed at `modified` has been left alone long enough to pick up
+fn is_settled(modified: SystemTime, now: SystemTime) -> bool {
+    now.duration_since(modified)
+        .is_ok_and(|age| age >= SETTLE_TIME)
+}
+
+/// Strips the title number `MakeMKV` adds to the names of its files, e.g. `Inception_t00`
+fn strip_title_number(stem: &str) -> &str {
+    match stem.rsplit_once("_t") {
+        Some((name, number))
+            if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            name
+        }
+        _ => stem,
+    }
+}
+
+/// Reads what a file is from its path within the incoming folder. `None` if it isn't an
+/// MKV, or its name doesn't say what it is.
+#[must_use]
+pub fn incoming_rip(relative: &Path) -> Option<ImportedRip> {
+    if !is_mkv(relative) {
+        return None;
+    }
+
+    let stem = relative.file_stem()?.to_string_lossy();
+    let name = strip_title_number(&stem);
+
+    // Discs without a name are saved as title_t00.mkv, in a folder named after the disc
+    let (name, parent) = if name.is_empty() || name.eq_ignore_ascii_case("title") {
+        let folder = relative.parent()?.file_name()?.to_string_lossy();
+        (folder.to_string(), relative.parent()?.parent())
+    } else {
+        (name.to_string(), relative.parent())
+    };
+
+    let path = parent.unwrap_or(Path::new("")).join(format!("{name}.mkv"));
+    let mut found = parse_library_path(&path)?;
+
+    if found.rip_type == RipType::Movie && parse_disc_label(&found.title).is_some() {
+        debug!("{} is named after a season's disc", relative.display());
+        return None;
+    }
+
+    // Disc labels use underscores for spaces
+    found.title = found.title.replace('_', " ").trim().to_string();
+    (!found.title.is_empty()).then_some(found)
+}
+
+/// Resolves the title of a file with Sonarr or Radarr, as rips are, into the `Rip` it is
+/// saved as
+async fn resolve(found: ImportedRip) -> Rip {
+    let (title, year, episode_title) = match found.rip_type {
+        RipType::Movie => {
+            let (title, year) = canonical_movie(found.title, found.year).await;
+            (title, year, None)
+        }
+        RipType::Show {
+            season, episode, ..
+        } => {
+            let title = canonical_series(found.title).await;
+            let episode_title = episode_titles(&title, season)
+                .await
+                .into_iter()
+                .find(|(number, _)| *number == episode)
+                .map(|(_, name)| name);
+            (title, None, episode_title)
+        }
+    };
+
+    Rip {
+        title,
+        drive_number: 0,
+        rip_type: found.rip_type,
+        title_id: 0,
+        year,
+        chapters_per_episode: None,
+        library: None,
+        episode_title,
+        note: None,
+        extra: None,
+        base_view: false,
+    }
+}
+
+/// Names a file in the incoming folder and moves it into the library, returning the rip
+/// it was saved as and where
+async fn import_file(dir: &Path, file: &Path, makemkv: &MakeMkv) -> Result<(Rip, PathBuf)> {
+    let relative = file.strip_prefix(dir).unwrap_or(file);
+    let found = incoming_rip(relative)
+        .ok_or_else(|| WatchError::Unmatched(relative.display().to_string()))?;
+
+    match validate_file(file, None).await {
+        Ok(()) => {}
+        Err(ProbeError::Invalid(reason)) => return Err(WatchError::Invalid(reason)),
+        // Only informative, a file that couldn't be checked is still imported
+        Err(e) => warn!("Couldn't validate {}: {}", file.display(), e),
+    }
+
+    let rip = resolve(found).await;
+
+    let relative_path = makemkv.naming.rip_path(&rip);
+    if !stays_inside(&relative_path) {
+        return Err(WatchError::UnsafePath(
+            relative_path.to_string_lossy().to_string(),
+        ));
+    }
+    let destination = makemkv.destination_path(&rip);
+    if destination.exists() {
+        return Err(WatchError::AlreadyExists(
+            destination.to_string_lossy().to_string(),
+        ));
+    }
+
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| WatchError::SaveError(e.to_string()))?;
+    }
+    move_verified(file, &destination)
+        .await
+        .map_err(|e| WatchError::SaveError(e.to_string()))?;
+    remove_empty_folders(dir, file);
+
+    LIBRARY.lock().await.add(&destination);
+    let files = vec![destination.clone()];
+    if let Err(e) = HISTORY
+        .lock()
+        .await
+        .record(&rip, files.clone(), None, None, false)
+    {
+        warn!("Failed to record {} in history: {}", rip.title, e);
+    }
+
+    // Written before importing, so Sonarr and Radarr can import them as extra files
+    crate::metadata::write_sidecars(&rip, &files, makemkv).await;
+    import_rip(&rip, &files).await;
+
+    Ok((rip, destination))
+}
+
+/// Removes the folders a file was in once they are empty, such as the one the GUI made
+/// for its disc, up to the incoming folder itself
+fn remove_empty_folders(dir: &Path, file: &Path) {
+    for folder in file.ancestors().skip(1) {
+        if folder == dir || !folder.starts_with(dir) {
+            break;
+        }
+        // Fails on folders that still have something in them, which is where it stops
+        if std::fs::remove_dir(folder).is_err() {
+            break;
+        }
+        debug!("Removed empty folder {}", folder.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &str) -> Option<(String, Option<u16>, RipType)> {
+        incoming_rip(Path::new(path)).map(|found| (found.title, found.year, found.rip_type))
+    }
+
+    #[test]
+    fn reads_hand_ripped_names() {
+        assert_eq!(
+            read("Inception (2010).mkv"),
+            Some(("Inception".to_string(), Some(2010), RipType::Movie))
+        );
+        assert_eq!(
+            read("INCEPTION/title_t00.mkv"),
+            Some(("INCEPTION".to_string(), None, RipType::Movie))
+        );
+        assert_eq!(
+            read("THE_MATRIX/THE_MATRIX_t01.mkv"),
+            Some(("THE MATRIX".to_string(), None, RipType::Movie))
+        );
+        assert_eq!(
+            read("Frasier/Frasier S02E05.mkv"),
+            Some((
+                "Frasier".to_string(),
+                None,
+                RipType::Show {
+                    season: 2,
+                    episode: 5,
+                    absolute_episode: None
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn leaves_files_it_cant_name() {
+        assert_eq!(read("FRIENDS_S3_D2/title_t03.mkv"), None);
+        assert_eq!(read("title_t00.mkv"), None);
+        assert_eq!(read("Inception (2010).mp4"), None);
+    }
+
+    #[test]
+    fn waits_for_files_to_settle() {
+        let now = SystemTime::now();
+        assert!(is_settled(now - SETTLE_TIME, now));
+        assert!(!is_settled(now - Duration::from_secs(30), now));
+        assert!(!is_settled(now + Duration::from_secs(30), now));
+    }
+
+    #[test]
+    fn removes_only_empty_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let disc = dir.path().join("INCEPTION");
+        std::fs::create_dir_all(disc.join("extras")).unwrap();
+        let file = disc.join("title_t00.mkv");
+
+        remove_empty_folders(dir.path(), &file);
+        assert!(disc.exists());
+
+        std::fs::remove_dir(disc.join("extras")).unwrap();
+        remove_empty_folders(dir.path(), &file);
+        assert!(!disc.exists());
+        assert!(dir.path().exists());
+    }
+}